@@ -11,6 +11,13 @@ use {
 pub type BankingPacketBatch = Arc<Vec<PacketBatch>>;
 pub type BankingPacketReceiver = Receiver<BankingPacketBatch>;
 
+/// Bundles of packets. The transactions of each `PacketBatch` are locked,
+/// executed and committed together, in order, or not at all.
+/// Bundles do not pass through sigverify; banking stage verifies their
+/// signatures when buffering them.
+pub type BankingBundleBatch = Arc<Vec<PacketBatch>>;
+pub type BankingBundleReceiver = Receiver<BankingBundleBatch>;
+
 /// Priority floor shared from the banking-stage scheduler to sigverify.
 ///
 /// When saturated, the scheduler publishes the queue-min transaction's
@@ -9,10 +9,11 @@ use {
     solana_pubkey::Pubkey,
     solana_runtime::{bank_forks::BankForks, snapshot_controller::SnapshotController},
     solana_tls_utils::NotifyKeyUpdate,
+    solana_unified_scheduler_pool::DefaultSchedulerPool,
     std::{
         collections::{HashMap, HashSet},
         net::UdpSocket,
-        sync::{Arc, RwLock},
+        sync::{Arc, RwLock, Weak},
     },
     tokio::sync::mpsc,
 };
@@ -90,4 +91,6 @@ pub struct AdminRpcRequestMetadataPostInit {
     pub snapshot_controller: Arc<SnapshotController>,
     pub blockstore: Arc<Blockstore>,
     pub votor_event_sender: VotorEventSender,
+    /// Weak, because the pool must be uniquely owned by `bank_forks` when it's dropped.
+    pub scheduler_pool: Weak<DefaultSchedulerPool>,
 }
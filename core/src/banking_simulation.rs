@@ -16,10 +16,11 @@ use {
     agave_votor_messages::migration::MigrationStatus,
     assert_matches::assert_matches,
     bincode::deserialize_from,
-    crossbeam_channel::{Sender, bounded, unbounded},
+    crossbeam_channel::{Sender, bounded, never, unbounded},
     itertools::Itertools,
     log::*,
     solana_clock::{DEFAULT_MS_PER_SLOT, HOLD_TRANSACTIONS_SLOT_OFFSET, Slot},
+    solana_cost_model::cost_tracker_post_analysis::CostTrackerPostAnalysis,
     solana_genesis_config::GenesisConfig,
     solana_gossip::{cluster_info::ClusterInfo, contact_info::ContactInfoQuery, node::Node},
     solana_keypair::Keypair,
@@ -48,15 +49,15 @@ use {
     solana_signer::Signer,
     solana_turbine::broadcast_stage::{BroadcastStage, BroadcastStageType},
     std::{
-        collections::BTreeMap,
-        fmt::Display,
+        collections::{BTreeMap, HashMap},
+        fmt::{self, Display},
         fs::File,
         io::{self, BufRead, BufReader},
         net::{IpAddr, Ipv4Addr},
         path::PathBuf,
         sync::{
             Arc, RwLock,
-            atomic::{AtomicBool, Ordering},
+            atomic::{AtomicBool, AtomicUsize, Ordering},
         },
         thread::{self, JoinHandle, sleep},
         time::{Duration, Instant, SystemTime},
@@ -149,7 +150,169 @@ type TimedBatchesToSend = Vec<(
 
 type EventSenderThread = JoinHandle<(TracedSender, TracedSender, TracedSender)>;
 
-#[derive(Default)]
+/// Number of the costliest writable accounts kept per block and per simulation.
+const MAX_REPORTED_ACCOUNTS: usize = 10;
+
+/// Outcome of a block produced by the simulated leader.
+#[derive(Clone, Debug)]
+pub struct SimulatedBlockReport {
+    pub slot: Slot,
+    /// Base and priority fees collected by the block.
+    pub total_fees: u64,
+    pub priority_fees: u64,
+    pub block_cost: u64,
+    pub block_cost_limit: u64,
+    pub account_cost_limit: u64,
+    pub included_transactions: u64,
+    pub included_non_vote_transactions: u64,
+    /// The costliest writable accounts of the block, in descending order of cost.
+    pub costliest_accounts: Vec<(Pubkey, u64)>,
+}
+
+impl SimulatedBlockReport {
+    fn new(bank: &Bank) -> Self {
+        let cost_tracker = bank.read_cost_tracker().unwrap();
+        let fee_details = bank.get_collector_fee_details();
+        let parent_non_vote_transaction_count = bank.parent().map_or(0, |parent| {
+            parent.non_vote_transaction_count_since_restart()
+        });
+        Self {
+            slot: bank.slot(),
+            total_fees: fee_details.total_transaction_fee(),
+            priority_fees: fee_details.total_priority_fee(),
+            block_cost: cost_tracker.block_cost(),
+            block_cost_limit: cost_tracker.get_block_limit(),
+            account_cost_limit: cost_tracker.get_account_limit(),
+            included_transactions: bank.executed_transaction_count(),
+            included_non_vote_transactions: bank
+                .non_vote_transaction_count_since_restart()
+                .saturating_sub(parent_non_vote_transaction_count),
+            costliest_accounts: costliest_accounts(
+                cost_tracker
+                    .get_cost_by_writable_accounts()
+                    .iter()
+                    .map(|(pubkey, cost)| (*pubkey, *cost)),
+            ),
+        }
+    }
+
+    /// Fraction of the block cost limit used by the block.
+    pub fn cost_fill(&self) -> f64 {
+        self.block_cost as f64 / self.block_cost_limit as f64
+    }
+}
+
+/// Outcome of simulating the leader's blocks under one block production policy.
+#[derive(Clone, Debug, Default)]
+pub struct SimulationReport {
+    /// Non-vote transactions sent to banking stage during the simulation.
+    pub offered_non_vote_transactions: u64,
+    pub blocks: Vec<SimulatedBlockReport>,
+}
+
+impl SimulationReport {
+    pub fn total_fees(&self) -> u64 {
+        self.blocks.iter().map(|block| block.total_fees).sum()
+    }
+
+    pub fn priority_fees(&self) -> u64 {
+        self.blocks.iter().map(|block| block.priority_fees).sum()
+    }
+
+    /// Fraction of the block cost limits used over all the simulated blocks.
+    pub fn cost_fill(&self) -> f64 {
+        let block_cost: u64 = self.blocks.iter().map(|block| block.block_cost).sum();
+        let block_cost_limit: u64 = self.blocks.iter().map(|block| block.block_cost_limit).sum();
+        if block_cost_limit == 0 {
+            return 0.0;
+        }
+        block_cost as f64 / block_cost_limit as f64
+    }
+
+    pub fn included_non_vote_transactions(&self) -> u64 {
+        self.blocks
+            .iter()
+            .map(|block| block.included_non_vote_transactions)
+            .sum()
+    }
+
+    /// Offered non-vote transactions which were not included in any simulated block.
+    pub fn dropped_non_vote_transactions(&self) -> u64 {
+        self.offered_non_vote_transactions
+            .saturating_sub(self.included_non_vote_transactions())
+    }
+
+    /// The costliest writable accounts over all the simulated blocks, in
+    /// descending order of cost.
+    pub fn costliest_accounts(&self) -> Vec<(Pubkey, u64)> {
+        let mut cost_by_account = HashMap::<Pubkey, u64>::new();
+        for (pubkey, cost) in self
+            .blocks
+            .iter()
+            .flat_map(|block| &block.costliest_accounts)
+        {
+            *cost_by_account.entry(*pubkey).or_default() += cost;
+        }
+        costliest_accounts(cost_by_account)
+    }
+}
+
+impl Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "blocks: {} fees: {} (priority: {}) cu fill: {:.2}% non-vote txs: {} included, {} \
+             dropped (of {} offered)",
+            self.blocks.len(),
+            self.total_fees(),
+            self.priority_fees(),
+            self.cost_fill() * 100.0,
+            self.included_non_vote_transactions(),
+            self.dropped_non_vote_transactions(),
+            self.offered_non_vote_transactions,
+        )?;
+        for block in &self.blocks {
+            writeln!(
+                f,
+                "  slot {}: fees: {} (priority: {}) cu: {} ({:.2}%) txs: {} (non-vote: {})",
+                block.slot,
+                block.total_fees,
+                block.priority_fees,
+                block.block_cost,
+                block.cost_fill() * 100.0,
+                block.included_transactions,
+                block.included_non_vote_transactions,
+            )?;
+        }
+        writeln!(f, "  costliest writable accounts:")?;
+        let account_cost_limit = self
+            .blocks
+            .first()
+            .map_or(0, |block| block.account_cost_limit);
+        for (pubkey, cost) in self.costliest_accounts() {
+            writeln!(
+                f,
+                "    {pubkey}: cu: {cost} ({:.2}% of the account limit per block)",
+                cost as f64 / self.blocks.len() as f64 / account_cost_limit as f64 * 100.0,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn costliest_accounts(
+    cost_by_account: impl IntoIterator<Item = (Pubkey, u64)>,
+) -> Vec<(Pubkey, u64)> {
+    cost_by_account
+        .into_iter()
+        .sorted_unstable_by(|(pubkey_a, cost_a), (pubkey_b, cost_b)| {
+            cost_b.cmp(cost_a).then_with(|| pubkey_a.cmp(pubkey_b))
+        })
+        .take(MAX_REPORTED_ACCOUNTS)
+        .collect()
+}
+
+#[derive(Clone, Default)]
 pub struct BankingTraceEvents {
     packet_batches_by_time: PacketBatchesByTime,
     freeze_time_by_slot: FreezeTimeBySlot,
@@ -340,6 +503,7 @@ struct SenderLoop {
     raw_base_event_time: SystemTime,
     total_batch_count: usize,
     timed_batches_to_send: TimedBatchesToSend,
+    offered_non_vote_transactions: Arc<AtomicUsize>,
 }
 
 impl SenderLoop {
@@ -391,6 +555,10 @@ impl SenderLoop {
                 ChannelLabel::Dummy => unreachable!(),
             };
             sender.send(batches_with_stats).unwrap();
+            if matches!(label, ChannelLabel::NonVote) {
+                self.offered_non_vote_transactions
+                    .fetch_add(tx_count, Ordering::Relaxed);
+            }
 
             logger.on_sending_batches(&simulation_duration, label, batch_count, tx_count);
             if self.exit.load(Ordering::Relaxed) {
@@ -429,7 +597,7 @@ impl SimulatorLoop {
         self,
         base_simulation_time: SystemTime,
         sender_thread: EventSenderThread,
-    ) -> (EventSenderThread, Sender<Slot>) {
+    ) -> (EventSenderThread, Sender<Slot>, Vec<SimulatedBlockReport>) {
         sleep(WARMUP_DURATION);
         info!("warmup done!");
         self.start(base_simulation_time, sender_thread)
@@ -439,7 +607,7 @@ impl SimulatorLoop {
         mut self,
         base_simulation_time: SystemTime,
         sender_thread: EventSenderThread,
-    ) -> (EventSenderThread, Sender<Slot>) {
+    ) -> (EventSenderThread, Sender<Slot>, Vec<SimulatedBlockReport>) {
         let logger = SimulatorLoopLogger {
             simulated_leader: self.simulated_leader,
             base_event_time: self.base_event_time,
@@ -447,6 +615,7 @@ impl SimulatorLoop {
             freeze_time_by_slot: self.freeze_time_by_slot,
         };
         let (mut bank, mut bank_created) = (self.bank, Instant::now());
+        let mut simulated_blocks = vec![];
         loop {
             if self.poh_recorder.read().unwrap().bank().is_none() {
                 let next_leader_slot = self.leader_schedule_cache.next_leader_slot(
@@ -480,6 +649,9 @@ impl SimulatorLoop {
                     .unwrap();
                 if new_leader.id != self.simulated_leader {
                     logger.on_new_leader(&bank, bank_created.elapsed(), new_slot, new_leader.id);
+                    if *bank.leader_id() == self.simulated_leader {
+                        simulated_blocks.push(SimulatedBlockReport::new(&bank));
+                    }
                     break;
                 } else if sender_thread.is_finished() {
                     warn!("sender thread existed maybe due to completion of sending traced events");
@@ -495,6 +667,7 @@ impl SimulatorLoop {
                     .hash_event(bank.slot(), &bank.last_blockhash(), &bank.hash());
                 if *bank.leader_id() == self.simulated_leader {
                     logger.log_frozen_bank_cost(&bank, bank_created.elapsed());
+                    simulated_blocks.push(SimulatedBlockReport::new(&bank));
                 }
                 self.retransmit_slots_sender.send(bank.slot()).unwrap();
                 update_bank_forks_and_poh_recorder_for_new_tpu_bank(
@@ -523,7 +696,11 @@ impl SimulatorLoop {
             sleep(Duration::from_millis(10));
         }
 
-        (sender_thread, self.retransmit_slots_sender)
+        (
+            sender_thread,
+            self.retransmit_slots_sender,
+            simulated_blocks,
+        )
     }
 }
 
@@ -698,6 +875,8 @@ impl BankingSimulator {
         bank_forks: Arc<RwLock<BankForks>>,
         blockstore: Arc<Blockstore>,
         block_production_method: BlockProductionMethod,
+        scheduler_config: SchedulerConfig,
+        offered_non_vote_transactions: Arc<AtomicUsize>,
     ) -> (SenderLoop, SimulatorLoop, SimulatorThreads) {
         let parent_slot = self.parent_slot().unwrap();
         let mut packet_batches_by_time = self.banking_trace_events.packet_batches_by_time;
@@ -844,9 +1023,10 @@ impl BankingSimulator {
             non_vote_receiver,
             tpu_vote_receiver,
             gossip_vote_receiver,
+            never(),
             mpsc::channel(1).1,
             num_workers,
-            SchedulerConfig::default(),
+            scheduler_config,
             None,
             replay_vote_sender,
             None,
@@ -893,6 +1073,7 @@ impl BankingSimulator {
             raw_base_event_time,
             total_batch_count,
             timed_batches_to_send,
+            offered_non_vote_transactions,
         };
 
         let simulator_loop = SimulatorLoop {
@@ -922,18 +1103,24 @@ impl BankingSimulator {
         (sender_loop, simulator_loop, simulator_threads)
     }
 
+    /// Simulates the leader's blocks with the given block production policy,
+    /// reporting on the produced blocks.
     pub fn start(
         self,
         genesis_config: GenesisConfig,
         bank_forks: Arc<RwLock<BankForks>>,
         blockstore: Arc<Blockstore>,
         block_production_method: BlockProductionMethod,
-    ) -> Result<(), SimulateError> {
+        scheduler_config: SchedulerConfig,
+    ) -> Result<SimulationReport, SimulateError> {
+        let offered_non_vote_transactions = Arc::<AtomicUsize>::default();
         let (sender_loop, simulator_loop, simulator_threads) = self.prepare_simulation(
             genesis_config,
             bank_forks,
             blockstore,
             block_production_method,
+            scheduler_config,
+            offered_non_vote_transactions.clone(),
         );
 
         sender_loop.log_starting();
@@ -941,12 +1128,16 @@ impl BankingSimulator {
         // Spawning and entering these two loops must be done at the same time as they're timed.
         // So, all the mundane setup must be done in advance.
         let sender_thread = sender_loop.spawn(base_simulation_time)?;
-        let (sender_thread, retransmit_slots_sender) =
+        let (sender_thread, retransmit_slots_sender, blocks) =
             simulator_loop.enter(base_simulation_time, sender_thread);
 
         simulator_threads.finish(sender_thread, retransmit_slots_sender);
 
-        Ok(())
+        Ok(SimulationReport {
+            offered_non_vote_transactions: offered_non_vote_transactions.load(Ordering::Relaxed)
+                as u64,
+            blocks,
+        })
     }
 
     pub fn event_file_name(index: usize) -> String {
@@ -957,3 +1148,55 @@ impl BankingSimulator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_report(
+        included_non_vote_transactions: u64,
+        costliest_accounts: Vec<(Pubkey, u64)>,
+    ) -> SimulatedBlockReport {
+        SimulatedBlockReport {
+            slot: 0,
+            total_fees: 10,
+            priority_fees: 5,
+            block_cost: 25,
+            block_cost_limit: 100,
+            account_cost_limit: 10,
+            included_transactions: included_non_vote_transactions,
+            included_non_vote_transactions,
+            costliest_accounts,
+        }
+    }
+
+    #[test]
+    fn test_simulation_report() {
+        let (hot, warm) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let report = SimulationReport {
+            offered_non_vote_transactions: 10,
+            blocks: vec![
+                block_report(3, vec![(hot, 6), (warm, 5)]),
+                block_report(4, vec![(warm, 2), (hot, 4)]),
+            ],
+        };
+
+        assert_eq!(report.total_fees(), 20);
+        assert_eq!(report.priority_fees(), 10);
+        assert_eq!(report.cost_fill(), 0.25);
+        assert_eq!(report.included_non_vote_transactions(), 7);
+        assert_eq!(report.dropped_non_vote_transactions(), 3);
+        assert_eq!(report.costliest_accounts(), vec![(hot, 10), (warm, 7)]);
+    }
+
+    #[test]
+    fn test_costliest_accounts_truncated() {
+        let accounts = (0..MAX_REPORTED_ACCOUNTS as u64 + 1)
+            .map(|cost| (Pubkey::new_unique(), cost))
+            .collect::<Vec<_>>();
+        let costliest = costliest_accounts(accounts.iter().copied());
+        assert_eq!(costliest.len(), MAX_REPORTED_ACCOUNTS);
+        assert_eq!(costliest[0], accounts[MAX_REPORTED_ACCOUNTS]);
+        assert!(!costliest.contains(&accounts[0]));
+    }
+}
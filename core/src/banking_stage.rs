@@ -8,8 +8,9 @@ use {
     },
     crate::{
         banking_stage::{
-            consume_worker::ConsumeWorker,
+            consume_worker::{ConsumeWorker, ConsumeWorkerMetrics},
             transaction_scheduler::{
+                scheduler::Scheduler,
                 scheduler_controller::{
                     DEFAULT_SCHEDULER_PACING_FILL_TIME_MILLIS, SchedulerConfig, SchedulerController,
                 },
@@ -18,7 +19,9 @@ use {
         },
         validator::BlockProductionMethod,
     },
-    agave_banking_stage_ingress_types::{BankingPacketReceiver, SchedulerPriorityFloor},
+    agave_banking_stage_ingress_types::{
+        BankingBundleReceiver, BankingPacketReceiver, SchedulerPriorityFloor,
+    },
     crossbeam_channel::{Receiver, Sender, bounded},
     futures::{StreamExt, stream::FuturesUnordered},
     histogram::Histogram,
@@ -31,7 +34,9 @@ use {
     },
     solana_pubkey::Pubkey,
     solana_runtime::{
-        bank::Bank, bank_forks::BankForks, prioritization_fee_cache::PrioritizationFeeCache,
+        bank::Bank,
+        bank_forks::{BankForks, SharableBanks},
+        prioritization_fee_cache::PrioritizationFeeCache,
         vote_sender_types::ReplayVoteSender,
     },
     solana_time_utils::AtomicInterval,
@@ -51,7 +56,8 @@ use {
     tokio_util::sync::CancellationToken,
     transaction_scheduler::{
         greedy_scheduler::{GreedyScheduler, GreedySchedulerConfig},
-        receive_and_buffer::TransactionViewReceiveAndBuffer,
+        prio_graph_scheduler::{PrioGraphScheduler, PrioGraphSchedulerConfig},
+        receive_and_buffer::{ReceiveAndBuffer, TransactionViewReceiveAndBuffer},
     },
     vote_worker::VoteWorker,
 };
@@ -330,6 +336,7 @@ pub struct BankingStage {
     tpu_vote_receiver: BankingPacketReceiver,
     gossip_vote_receiver: BankingPacketReceiver,
     non_vote_receiver: BankingPacketReceiver,
+    bundle_receiver: BankingBundleReceiver,
     transaction_recorder: TransactionRecorder,
     poh_recorder: Arc<RwLock<PohRecorder>>,
     bank_forks: Arc<RwLock<BankForks>>,
@@ -349,6 +356,7 @@ impl BankingStage {
         non_vote_receiver: BankingPacketReceiver,
         tpu_vote_receiver: BankingPacketReceiver,
         gossip_vote_receiver: BankingPacketReceiver,
+        bundle_receiver: BankingBundleReceiver,
         banking_control_receiver: mpsc::Receiver<BankingControlMsg>,
         num_workers: NonZeroUsize,
         scheduler_config: SchedulerConfig,
@@ -375,6 +383,7 @@ impl BankingStage {
             tpu_vote_receiver,
             gossip_vote_receiver,
             non_vote_receiver,
+            bundle_receiver,
             transaction_recorder,
             poh_recorder,
             bank_forks,
@@ -475,8 +484,9 @@ impl BankingStage {
                 config,
             } => match block_production_method {
                 BlockProductionMethod::CentralScheduler
-                | BlockProductionMethod::CentralSchedulerGreedy => {
-                    self.spawn_internal_central(num_workers, config)
+                | BlockProductionMethod::CentralSchedulerGreedy
+                | BlockProductionMethod::CentralSchedulerPrioGraph => {
+                    self.spawn_internal_central(block_production_method, num_workers, config)
                 }
             },
             #[cfg(unix)]
@@ -495,6 +505,7 @@ impl BankingStage {
 
     fn spawn_internal_central(
         &self,
+        block_production_method: BlockProductionMethod,
         num_workers: NonZeroUsize,
         scheduler_config: SchedulerConfig,
     ) -> Result<Vec<JoinHandle<()>>, ()> {
@@ -509,8 +520,16 @@ impl BankingStage {
         let sharable_banks = self.bank_forks.read().unwrap().sharable_banks();
         let receive_and_buffer = TransactionViewReceiveAndBuffer {
             receiver: self.non_vote_receiver.clone(),
+            bundle_receiver: self.bundle_receiver.clone(),
             sharable_banks: sharable_banks.clone(),
             filter_keys: self.filter_keys.clone(),
+            reserved_peers: Arc::new(
+                scheduler_config
+                    .block_space_reservations
+                    .iter()
+                    .map(|reservation| reservation.peer)
+                    .collect(),
+            ),
         };
 
         // Spawn vote worker.
@@ -556,51 +575,90 @@ impl BankingStage {
             )
         }
 
-        // Both block production methods currently route to the greedy scheduler.
-        let scheduler = GreedyScheduler::new(
-            work_senders,
-            finished_work_receiver,
-            GreedySchedulerConfig::default(),
-        );
+        let scheduler_thread = match block_production_method {
+            BlockProductionMethod::CentralScheduler
+            | BlockProductionMethod::CentralSchedulerGreedy => self.spawn_scheduler_controller(
+                exit,
+                scheduler_config,
+                decision_maker,
+                receive_and_buffer,
+                sharable_banks,
+                GreedyScheduler::new(
+                    work_senders,
+                    finished_work_receiver,
+                    GreedySchedulerConfig::default(),
+                ),
+                worker_metrics,
+            ),
+            BlockProductionMethod::CentralSchedulerPrioGraph => self.spawn_scheduler_controller(
+                exit,
+                scheduler_config,
+                decision_maker,
+                receive_and_buffer,
+                sharable_banks,
+                PrioGraphScheduler::new(
+                    work_senders,
+                    finished_work_receiver,
+                    PrioGraphSchedulerConfig::default(),
+                ),
+                worker_metrics,
+            ),
+        };
+        threads.push(scheduler_thread);
+
+        Ok(threads)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_scheduler_controller<S>(
+        &self,
+        exit: Arc<AtomicBool>,
+        scheduler_config: SchedulerConfig,
+        decision_maker: DecisionMaker,
+        receive_and_buffer: TransactionViewReceiveAndBuffer,
+        sharable_banks: SharableBanks,
+        scheduler: S,
+        worker_metrics: Vec<Arc<ConsumeWorkerMetrics>>,
+    ) -> JoinHandle<()>
+    where
+        S: Scheduler<<TransactionViewReceiveAndBuffer as ReceiveAndBuffer>::Transaction>
+            + Send
+            + 'static,
+    {
         let priority_floor = self.priority_floor.clone();
-        let exit = exit.clone();
         let shutdown_signal = self.banking_shutdown_signal.clone();
-        threads.push(
-            Builder::new()
-                .name("solBnkTxSched".to_string())
-                .spawn(move || {
-                    let mut scheduler_controller = SchedulerController::new(
-                        exit,
-                        scheduler_config,
-                        decision_maker,
-                        receive_and_buffer,
-                        sharable_banks,
-                        scheduler,
-                        worker_metrics,
-                        priority_floor,
-                    );
-
-                    match scheduler_controller.run() {
-                        Ok(_) => info!("Scheduler exiting without error"),
-                        Err(SchedulerError::DisconnectedRecvChannel(_)) => {
-                            info!("Upstream disconnected, shutting down banking");
-
-                            // NB: We must signal shutdown before dropping the scheduler
-                            //     controller, else, the workers may exit with an error and
-                            //     trigger a new spawn before we have a chance to issue the
-                            //     cancel.
-                            shutdown_signal.cancel();
-                            drop(scheduler_controller);
-                        }
-                        Err(SchedulerError::DisconnectedSendChannel(_)) => {
-                            warn!("Unexpected worker disconnect from scheduler")
-                        }
-                    }
-                })
-                .unwrap(),
-        );
+        Builder::new()
+            .name("solBnkTxSched".to_string())
+            .spawn(move || {
+                let mut scheduler_controller = SchedulerController::new(
+                    exit,
+                    scheduler_config,
+                    decision_maker,
+                    receive_and_buffer,
+                    sharable_banks,
+                    scheduler,
+                    worker_metrics,
+                    priority_floor,
+                );
 
-        Ok(threads)
+                match scheduler_controller.run() {
+                    Ok(_) => info!("Scheduler exiting without error"),
+                    Err(SchedulerError::DisconnectedRecvChannel(_)) => {
+                        info!("Upstream disconnected, shutting down banking");
+
+                        // NB: We must signal shutdown before dropping the scheduler
+                        //     controller, else, the workers may exit with an error and
+                        //     trigger a new spawn before we have a chance to issue the
+                        //     cancel.
+                        shutdown_signal.cancel();
+                        drop(scheduler_controller);
+                    }
+                    Err(SchedulerError::DisconnectedSendChannel(_)) => {
+                        warn!("Unexpected worker disconnect from scheduler")
+                    }
+                }
+            })
+            .unwrap()
     }
 
     fn spawn_vote_worker(&self) -> JoinHandle<()> {
@@ -832,9 +890,10 @@ mod tests {
             banking_trace::{BankingTracer, Channels},
             validator::SchedulerPacing,
         },
-        agave_banking_stage_ingress_types::BankingPacketBatch,
-        crossbeam_channel::bounded,
+        agave_banking_stage_ingress_types::{BankingBundleBatch, BankingPacketBatch},
+        crossbeam_channel::{bounded, never},
         itertools::Itertools,
+        solana_compute_budget_interface::ComputeBudgetInstruction,
         solana_entry::{
             entry::{self, EntrySlice},
             entry_or_marker::EntryOrMarker,
@@ -858,11 +917,13 @@ mod tests {
         solana_runtime::{bank::Bank, genesis_utils::bootstrap_validator_stake_lamports},
         solana_runtime_transaction::runtime_transaction::RuntimeTransaction,
         solana_signer::Signer,
+        solana_system_interface::instruction as system_instruction,
         solana_system_transaction as system_transaction,
         solana_transaction::{Transaction, sanitized::SanitizedTransaction},
         solana_vote::vote_transaction::new_tower_sync_transaction,
         solana_vote_program::vote_state::TowerSync,
         std::{sync::atomic::Ordering, thread::sleep, time::Instant},
+        test_case::test_case,
     };
 
     pub(crate) fn sanitize_transactions(
@@ -908,10 +969,12 @@ mod tests {
             non_vote_receiver,
             tpu_vote_receiver,
             gossip_vote_receiver,
+            never(),
             mpsc::channel(1).1,
             DEFAULT_NUM_WORKERS,
             SchedulerConfig {
                 scheduler_pacing: SchedulerPacing::Disabled,
+                ..SchedulerConfig::default()
             },
             None,
             replay_vote_sender,
@@ -929,8 +992,11 @@ mod tests {
         poh_service.join().unwrap();
     }
 
-    #[test]
-    fn test_banking_stage_entries_only_central_scheduler() {
+    #[test_case(BlockProductionMethod::CentralSchedulerGreedy; "greedy")]
+    #[test_case(BlockProductionMethod::CentralSchedulerPrioGraph; "prio_graph")]
+    fn test_banking_stage_entries_only_central_scheduler(
+        block_production_method: BlockProductionMethod,
+    ) {
         agave_logger::setup();
         let GenesisConfigInfo {
             genesis_config,
@@ -964,16 +1030,18 @@ mod tests {
         let (replay_vote_sender, _replay_vote_receiver) = bounded(1024);
 
         let banking_stage = BankingStage::new_num_threads(
-            BlockProductionMethod::CentralSchedulerGreedy,
+            block_production_method,
             poh_recorder.clone(),
             transaction_recorder,
             non_vote_receiver,
             tpu_vote_receiver,
             gossip_vote_receiver,
+            never(),
             mpsc::channel(1).1,
             DEFAULT_NUM_WORKERS,
             SchedulerConfig {
                 scheduler_pacing: SchedulerPacing::Disabled,
+                ..SchedulerConfig::default()
             },
             None,
             replay_vote_sender,
@@ -1126,10 +1194,12 @@ mod tests {
                 non_vote_receiver,
                 tpu_vote_receiver,
                 gossip_vote_receiver,
+                never(),
                 mpsc::channel(1).1,
                 DEFAULT_NUM_WORKERS,
                 SchedulerConfig {
                     scheduler_pacing: SchedulerPacing::Disabled,
+                    ..SchedulerConfig::default()
                 },
                 None,
                 replay_vote_sender,
@@ -1178,6 +1248,136 @@ mod tests {
         assert!(bank.get_balance(&alice.pubkey()) != 3);
     }
 
+    #[test]
+    fn test_banking_stage_bundle_rolls_back_on_failure() {
+        agave_logger::setup();
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_slow_genesis_config(1_000_000);
+        let (bank, bank_forks) = Bank::new_with_bank_forks_for_tests(&genesis_config);
+        let banking_tracer = BankingTracer::new_disabled();
+        let Channels {
+            non_vote_sender,
+            non_vote_receiver,
+            tpu_vote_sender,
+            tpu_vote_receiver,
+            gossip_vote_sender,
+            gossip_vote_receiver,
+        } = banking_tracer.create_channels();
+        let (bundle_sender, bundle_receiver) = bounded(1);
+
+        // The first transfer of the failing bundle succeeds on its own, the
+        // second one exceeds the mint's balance. The failing bundle pays a
+        // higher priority so it is scheduled before the succeeding one.
+        let alice = Keypair::new();
+        let bob = Keypair::new();
+        let carol = Keypair::new();
+        let transfer_with_price = |to: &Pubkey, lamports: u64, compute_unit_price: u64| {
+            Transaction::new_signed_with_payer(
+                &[
+                    ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+                    system_instruction::transfer(&mint_keypair.pubkey(), to, lamports),
+                ],
+                Some(&mint_keypair.pubkey()),
+                &[&mint_keypair],
+                genesis_config.hash(),
+            )
+        };
+        let failing_bundle = [
+            transfer_with_price(&alice.pubkey(), 1, 1_000),
+            transfer_with_price(&bob.pubkey(), 1_000_000, 1_000),
+        ];
+        let succeeding_bundle = [transfer_with_price(&carol.pubkey(), 1, 0)];
+        bundle_sender
+            .send(BankingBundleBatch::new(vec![
+                to_packet_batches(&failing_bundle, 2).remove(0),
+                to_packet_batches(&succeeding_bundle, 1).remove(0),
+            ]))
+            .unwrap();
+
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Arc::new(
+            Blockstore::open(ledger_path.path())
+                .expect("Expected to be able to open database ledger"),
+        );
+        let (
+            exit,
+            poh_recorder,
+            _poh_controller,
+            transaction_recorder,
+            poh_service,
+            entry_receiver,
+        ) = create_test_recorder(bank.clone(), blockstore, None, None);
+        let (replay_vote_sender, _replay_vote_receiver) = bounded(1024);
+        let banking_stage = BankingStage::new_num_threads(
+            BlockProductionMethod::CentralSchedulerGreedy,
+            poh_recorder,
+            transaction_recorder,
+            non_vote_receiver,
+            tpu_vote_receiver,
+            gossip_vote_receiver,
+            bundle_receiver,
+            mpsc::channel(1).1,
+            DEFAULT_NUM_WORKERS,
+            SchedulerConfig {
+                scheduler_pacing: SchedulerPacing::Disabled,
+                ..SchedulerConfig::default()
+            },
+            None,
+            replay_vote_sender,
+            None,
+            bank_forks,
+            None,
+            Arc::default(),
+            Arc::new(SchedulerPriorityFloor::new()),
+        );
+
+        // wait for banking_stage to process the succeeding bundle
+        const TIMEOUT: Duration = Duration::from_secs(10);
+        let start = Instant::now();
+        while bank.get_balance(&carol.pubkey()) < 1 {
+            if start.elapsed() > TIMEOUT {
+                panic!("banking stage took too long to process bundles");
+            }
+            sleep(Duration::from_millis(10));
+        }
+        exit.store(true, Ordering::Relaxed);
+        drop(non_vote_sender);
+        drop(tpu_vote_sender);
+        drop(gossip_vote_sender);
+        drop(bundle_sender);
+        banking_stage.join().unwrap();
+        poh_service.join().unwrap();
+
+        // No transaction of the failing bundle was committed, not even the
+        // transfer that would have succeeded alone.
+        assert_eq!(bank.get_balance(&alice.pubkey()), 0);
+        assert_eq!(bank.get_balance(&bob.pubkey()), 0);
+        for transaction in &failing_bundle {
+            assert!(
+                bank.get_signature_status(&transaction.signatures[0])
+                    .is_none()
+            );
+        }
+        let recorded_transactions: Vec<_> = entry_receiver
+            .try_iter()
+            .filter_map(
+                |(_bank, (entry_or_marker, _tick_height))| match entry_or_marker {
+                    EntryOrMarker::Entry(entry) => Some(entry.transactions),
+                    EntryOrMarker::Marker(_) => None,
+                },
+            )
+            .flatten()
+            .collect();
+        assert_eq!(recorded_transactions.len(), 1);
+        assert_eq!(
+            recorded_transactions[0].signatures,
+            succeeding_bundle[0].signatures
+        );
+    }
+
     #[test]
     fn test_bank_record_transactions() {
         agave_logger::setup();
@@ -1281,10 +1481,12 @@ mod tests {
             non_vote_receiver,
             tpu_vote_receiver,
             gossip_vote_receiver,
+            never(),
             mpsc::channel(1).1,
             DEFAULT_NUM_WORKERS,
             SchedulerConfig {
                 scheduler_pacing: SchedulerPacing::Disabled,
+                ..SchedulerConfig::default()
             },
             None,
             replay_vote_sender,
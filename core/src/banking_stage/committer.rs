@@ -3,7 +3,7 @@ use {
     itertools::Itertools,
     solana_cost_model::cost_model::CostModel,
     solana_ledger::{
-        blockstore_processor::TransactionStatusSender,
+        blockstore_processor::{TransactionPostAccounts, TransactionStatusSender},
         transaction_balances::compile_collected_balances,
     },
     solana_measure::measure_us,
@@ -69,6 +69,13 @@ impl Committer {
         execute_and_commit_timings: &mut LeaderExecuteAndCommitTimings,
         processed_counts: &ProcessedTransactionCounts,
     ) -> (u64, Vec<CommitTransactionDetails>) {
+        let post_accounts =
+            self.transaction_status_sender
+                .as_ref()
+                .and_then(|transaction_status_sender| {
+                    transaction_status_sender
+                        .collect_post_accounts(batch.sanitized_transactions(), &processing_results)
+                });
         let (commit_results, commit_time_us) = measure_us!(bank.commit_transactions(
             batch.sanitized_transactions(),
             processing_results,
@@ -117,6 +124,7 @@ impl Committer {
                 batch,
                 balance_collector,
                 starting_transaction_index,
+                post_accounts,
             );
         });
         execute_and_commit_timings.find_and_send_votes_us = find_and_send_votes_us;
@@ -130,6 +138,7 @@ impl Committer {
         batch: &TransactionBatch<impl TransactionWithMeta>,
         balance_collector: Option<BalanceCollector>,
         starting_transaction_index: Option<usize>,
+        post_accounts: Option<TransactionPostAccounts>,
     ) {
         if let Some(transaction_status_sender) = &self.transaction_status_sender {
             let sanitized_transactions = batch.sanitized_transactions();
@@ -186,6 +195,7 @@ impl Committer {
                 token_balances,
                 tx_costs,
                 batch_transaction_indexes,
+                post_accounts,
             );
         }
     }
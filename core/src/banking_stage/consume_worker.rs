@@ -122,8 +122,8 @@ impl<Tx: TransactionWithMeta> ConsumeWorker<Tx> {
             &work.transactions,
             &work.max_ages,
             ExecutionFlags {
-                drop_on_failure: false,
-                all_or_nothing: false,
+                drop_on_failure: work.is_bundle,
+                all_or_nothing: work.is_bundle,
             },
         );
         self.metrics.update_for_consume(&output);
@@ -2955,6 +2955,7 @@ mod tests {
             ids: vec![id],
             transactions,
             max_ages: vec![max_age],
+            is_bundle: false,
         };
         consume_sender.send(work).unwrap();
         let consumed = consumed_receiver.recv().unwrap();
@@ -3000,6 +3001,7 @@ mod tests {
                         sanitized_epoch: bank.epoch(),
                         alt_invalidation_slot: bank.slot(),
                     }],
+                    is_bundle: false,
                 })
                 .unwrap();
         }
@@ -3063,6 +3065,7 @@ mod tests {
             ids: vec![id],
             transactions,
             max_ages: vec![max_age],
+            is_bundle: false,
         };
         consume_sender.send(work).unwrap();
         let consumed = consumed_receiver.recv().unwrap();
@@ -3118,6 +3121,7 @@ mod tests {
                 ids: vec![id1, id2],
                 transactions: txs,
                 max_ages: vec![max_age, max_age],
+                is_bundle: false,
             })
             .unwrap();
 
@@ -3184,6 +3188,7 @@ mod tests {
                 ids: vec![id1],
                 transactions: txs1,
                 max_ages: vec![max_age],
+                is_bundle: false,
             })
             .unwrap();
 
@@ -3193,6 +3198,7 @@ mod tests {
                 ids: vec![id2],
                 transactions: txs2,
                 max_ages: vec![max_age],
+                is_bundle: false,
             })
             .unwrap();
         let consumed = consumed_receiver.recv().unwrap();
@@ -3336,6 +3342,7 @@ mod tests {
                         alt_invalidation_slot: bank.slot() + 1,
                     },
                 ],
+                is_bundle: false,
             })
             .unwrap();
 
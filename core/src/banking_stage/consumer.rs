@@ -197,11 +197,11 @@ impl Consumer {
         let (
             (transaction_qos_cost_results, cost_model_throttled_transactions_count),
             cost_model_us,
-        ) = measure_us!(QosService::select_and_accumulate_transaction_costs(
-            bank,
-            txs,
-            pre_results
-        ));
+        ) = measure_us!(if flags.all_or_nothing {
+            QosService::select_and_accumulate_bundle_costs(bank, txs, pre_results)
+        } else {
+            QosService::select_and_accumulate_transaction_costs(bank, txs, pre_results)
+        });
 
         // Only lock accounts for those transactions are selected for the block;
         // Once accounts are locked, other threads cannot encode transactions that will modify the
@@ -1260,6 +1260,7 @@ mod tests {
         let tss = Some(TransactionStatusSender {
             sender: transaction_status_sender,
             dependency_tracker: None,
+            record_post_accounts: false,
         });
         let TestFrame {
             mint_keypair,
@@ -1331,6 +1332,7 @@ mod tests {
         let tss = Some(TransactionStatusSender {
             sender: transaction_status_sender,
             dependency_tracker: None,
+            record_post_accounts: false,
         });
         let TestFrame {
             mint_keypair,
@@ -51,6 +51,66 @@ impl QosService {
         )
     }
 
+    /// Like `select_and_accumulate_transaction_costs`, but the transactions
+    /// are selected as a unit: if any of them is filtered out or does not fit
+    /// in the block, none are selected and no cost is accumulated.
+    pub fn select_and_accumulate_bundle_costs<'a, Tx: TransactionWithMeta>(
+        bank: &Bank,
+        transactions: &'a [Tx],
+        pre_results: impl Iterator<Item = transaction::Result<()>>,
+    ) -> (Vec<transaction::Result<TransactionCost<'a, Tx>>>, u64) {
+        let transaction_costs =
+            Self::compute_transaction_costs(&bank.feature_set, transactions.iter(), pre_results);
+        if let Some(err) = transaction_costs
+            .iter()
+            .find_map(|cost| cost.as_ref().err())
+        {
+            // Transactions that would have been selected are cancelled with
+            // the bundle.
+            let err = err.clone();
+            let select_results = transaction_costs
+                .into_iter()
+                .map(|cost| match cost {
+                    Ok(_) => Err(TransactionError::CommitCancelled),
+                    Err(err) => Err(err),
+                })
+                .collect();
+            debug!(
+                "slot {:?}, bundle of {} transactions filtered out, '{:?}'",
+                bank.slot(),
+                transactions.len(),
+                err
+            );
+            return (select_results, transactions.len() as u64);
+        }
+
+        let mut cost_tracker = bank.write_cost_tracker().unwrap();
+        let mut select_results = Vec::with_capacity(transactions.len());
+        for cost in transaction_costs {
+            let cost = cost.expect("errors handled above");
+            if let Err(e) = cost_tracker.try_add(&cost) {
+                // Give back the costs of the transactions already added, and
+                // retry the bundle as a whole.
+                for added_cost in select_results.iter().flatten() {
+                    cost_tracker.remove(added_cost);
+                }
+                debug!(
+                    "slot {:?}, bundle of {} transactions, not fit into current block, '{:?}'",
+                    bank.slot(),
+                    transactions.len(),
+                    e
+                );
+                let err = TransactionError::from(e);
+                let select_results = transactions.iter().map(|_| Err(err.clone())).collect();
+                return (select_results, transactions.len() as u64);
+            }
+            select_results.push(Ok(cost));
+        }
+        cost_tracker.add_transactions_in_flight(select_results.len());
+
+        (select_results, 0)
+    }
+
     // invoke cost_model to calculate cost for the given list of transactions that have not
     // been filtered out already.
     fn compute_transaction_costs<'a, Tx: TransactionWithMeta>(
@@ -314,6 +374,65 @@ mod tests {
         assert!(results[3].is_err());
     }
 
+    #[test]
+    fn test_select_and_accumulate_bundle_costs() {
+        agave_logger::setup();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10);
+        let bank = Arc::new(Bank::new_for_tests(&genesis_config));
+
+        let txs = (0..3)
+            .map(|_| {
+                let keypair = Keypair::new();
+                RuntimeTransaction::from_transaction_for_tests(system_transaction::transfer(
+                    &keypair,
+                    &keypair.pubkey(),
+                    1,
+                    Hash::default(),
+                ))
+            })
+            .collect_vec();
+        let transfer_tx_cost = CostModel::calculate_cost(&txs[0], &FeatureSet::all_enabled()).sum();
+
+        // set cost tracker limit to fit 2 transfer txs
+        let cost_limit = transfer_tx_cost * 2;
+        bank.write_cost_tracker()
+            .unwrap()
+            .set_limits(CostTrackerLimits::new(cost_limit, cost_limit, 0));
+
+        // a bundle that does not fit is not selected at all
+        let (results, num_throttled) =
+            QosService::select_and_accumulate_bundle_costs(&bank, &txs, std::iter::repeat(Ok(())));
+        assert_eq!(num_throttled, 3);
+        assert!(
+            results.iter().all(|result| matches!(
+                result,
+                Err(TransactionError::WouldExceedMaxBlockCostLimit)
+            ))
+        );
+        assert_eq!(bank.read_cost_tracker().unwrap().block_cost(), 0);
+
+        // a bundle with a filtered out transaction is cancelled
+        let (results, num_throttled) = QosService::select_and_accumulate_bundle_costs(
+            &bank,
+            &txs[..2],
+            [Ok(()), Err(TransactionError::AccountNotFound)].into_iter(),
+        );
+        assert_eq!(num_throttled, 2);
+        assert_matches!(results[0], Err(TransactionError::CommitCancelled));
+        assert_matches!(results[1], Err(TransactionError::AccountNotFound));
+        assert_eq!(bank.read_cost_tracker().unwrap().block_cost(), 0);
+
+        // a bundle that fits is selected as a whole
+        let (results, num_throttled) = QosService::select_and_accumulate_bundle_costs(
+            &bank,
+            &txs[..2],
+            std::iter::repeat(Ok(())),
+        );
+        assert_eq!(num_throttled, 0);
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(bank.read_cost_tracker().unwrap().block_cost(), cost_limit);
+    }
+
     #[test]
     fn test_update_and_remove_transaction_costs_committed() {
         agave_logger::setup();
@@ -43,6 +43,9 @@ pub struct ConsumeWork<Tx> {
     pub ids: Vec<TransactionId>,
     pub transactions: Vec<Tx>,
     pub max_ages: Vec<MaxAge>,
+    /// The transactions form a bundle, and must be committed together, in
+    /// order, or not at all.
+    pub is_bundle: bool,
 }
 
 /// Message: [Worker -> Scheduler]
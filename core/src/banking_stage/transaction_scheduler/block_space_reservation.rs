@@ -0,0 +1,266 @@
+//! Block space reserved for named peers.
+//!
+//! Stake-weighted QoS only shares out connection capacity, so a peer cannot
+//! count on getting its transactions into a block. A block space reservation
+//! grants a peer, identified by the pubkey of its QUIC connection, a share of
+//! the block CU limit of every leader slot. Transactions received from the
+//! peer are queued in the container's reserved lane and scheduled ahead of the
+//! regular queue while the peer's reservation for the slot lasts; the rest
+//! overflow into the regular queue and compete at their priority. Reserved CUs
+//! the peer has not used yet are held back from other transactions.
+
+use {
+    ahash::AHashMap, solana_clock::Slot, solana_cost_model::cost_tracker::SharedBlockCost,
+    solana_pubkey::Pubkey, solana_runtime::bank::Bank, std::str::FromStr,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockSpaceReservation {
+    /// Identity of the peer, as authenticated by its QUIC connection.
+    pub peer: Pubkey,
+    /// Share of the block CU limit reserved for the peer, in percent.
+    pub share_percent: u8,
+}
+
+/// Parses reservations given as `PUBKEY:PERCENT`.
+impl FromStr for BlockSpaceReservation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (peer, share_percent) = s
+            .split_once(':')
+            .ok_or_else(|| format!("expected PUBKEY:PERCENT, got {s}"))?;
+        let peer = peer
+            .parse()
+            .map_err(|err| format!("invalid peer pubkey {peer}: {err}"))?;
+        let share_percent = share_percent
+            .parse()
+            .ok()
+            .filter(|share_percent| (1..=100).contains(share_percent))
+            .ok_or_else(|| format!("invalid share {share_percent}, expected 1 to 100 percent"))?;
+        Ok(Self {
+            peer,
+            share_percent,
+        })
+    }
+}
+
+#[derive(Default)]
+struct PeerUsage {
+    share_percent: u8,
+    reserved_cus: u64,
+    scheduled_cus: u64,
+    num_scheduled: usize,
+    num_overflowed: usize,
+}
+
+impl PeerUsage {
+    fn remaining_cus(&self) -> u64 {
+        self.reserved_cus.saturating_sub(self.scheduled_cus)
+    }
+}
+
+pub(crate) struct BlockSpaceReservations {
+    slot: Option<Slot>,
+    block_limit: u64,
+    block_cost: SharedBlockCost,
+    usage_by_peer: AHashMap<Pubkey, PeerUsage>,
+}
+
+impl BlockSpaceReservations {
+    pub(crate) fn new(reservations: &[BlockSpaceReservation]) -> Self {
+        let total_share_percent: u32 = reservations
+            .iter()
+            .map(|reservation| u32::from(reservation.share_percent))
+            .sum();
+        assert!(
+            total_share_percent <= 100,
+            "block space reservations must not exceed 100% of the block"
+        );
+        Self {
+            slot: None,
+            block_limit: 0,
+            block_cost: SharedBlockCost::new(0),
+            usage_by_peer: reservations
+                .iter()
+                .map(|reservation| {
+                    (
+                        reservation.peer,
+                        PeerUsage {
+                            share_percent: reservation.share_percent,
+                            ..PeerUsage::default()
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Start the reservations of a new leader slot when `bank` is not the
+    /// bank of the current one. The usage of the previous slot is reported.
+    pub(crate) fn update(&mut self, bank: &Bank) {
+        if self.slot == Some(bank.slot()) {
+            return;
+        }
+        self.report();
+        self.slot = Some(bank.slot());
+
+        let cost_tracker = bank.read_cost_tracker().unwrap();
+        self.block_limit = cost_tracker.get_block_limit();
+        self.block_cost = cost_tracker.shared_block_cost();
+        drop(cost_tracker);
+
+        for usage in self.usage_by_peer.values_mut() {
+            *usage = PeerUsage {
+                share_percent: usage.share_percent,
+                reserved_cus: self.block_limit * u64::from(usage.share_percent) / 100,
+                ..PeerUsage::default()
+            };
+        }
+    }
+
+    /// Whether `cost` CUs fit in what is left of the reservation of `peer`.
+    pub(crate) fn fits(&self, peer: &Pubkey, cost: u64) -> bool {
+        self.usage_by_peer
+            .get(peer)
+            .is_some_and(|usage| cost <= usage.remaining_cus())
+    }
+
+    /// Charge a transaction of `peer` scheduled from the reserved lane.
+    pub(crate) fn record_scheduled(&mut self, peer: &Pubkey, cost: u64) {
+        if let Some(usage) = self.usage_by_peer.get_mut(peer) {
+            usage.scheduled_cus += cost;
+            usage.num_scheduled += 1;
+        }
+    }
+
+    /// Record a transaction of `peer` moved to the regular queue because it
+    /// did not fit in the peer's reservation.
+    pub(crate) fn record_overflowed(&mut self, peer: &Pubkey) {
+        if let Some(usage) = self.usage_by_peer.get_mut(peer) {
+            usage.num_overflowed += 1;
+        }
+    }
+
+    /// The part of `budget` transactions outside the reserved lane may use,
+    /// leaving room in the block for the unused reservations.
+    pub(crate) fn unreserved_budget(&self, budget: u64, in_flight_cus: u64) -> u64 {
+        let unused_reserved_cus: u64 = self
+            .usage_by_peer
+            .values()
+            .map(PeerUsage::remaining_cus)
+            .sum();
+        let unreserved_cus = self
+            .block_limit
+            .saturating_sub(self.block_cost.load())
+            .saturating_sub(in_flight_cus)
+            .saturating_sub(unused_reserved_cus);
+        budget.min(unreserved_cus)
+    }
+
+    fn report(&self) {
+        let Some(slot) = self.slot else {
+            return;
+        };
+        for (peer, usage) in &self.usage_by_peer {
+            datapoint_info!(
+                "banking_stage_block_space_reservations",
+                "peer" => peer.to_string(),
+                ("slot", slot, i64),
+                ("share_percent", usage.share_percent, i64),
+                ("reserved_cus", usage.reserved_cus, i64),
+                ("scheduled_cus", usage.scheduled_cus, i64),
+                ("num_scheduled", usage.num_scheduled, i64),
+                ("num_overflowed", usage.num_overflowed, i64),
+            );
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_block_limit(&mut self, block_limit: u64) {
+        self.slot = Some(0);
+        self.block_limit = block_limit;
+        for usage in self.usage_by_peer.values_mut() {
+            usage.reserved_cus = block_limit * u64::from(usage.share_percent) / 100;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reservation_budget() {
+        let peer = Pubkey::new_unique();
+        let other_peer = Pubkey::new_unique();
+        let mut reservations = BlockSpaceReservations::new(&[
+            BlockSpaceReservation {
+                peer,
+                share_percent: 10,
+            },
+            BlockSpaceReservation {
+                peer: other_peer,
+                share_percent: 20,
+            },
+        ]);
+        reservations.set_block_limit(1_000);
+
+        assert!(reservations.fits(&peer, 100));
+        assert!(!reservations.fits(&peer, 101));
+        assert!(!reservations.fits(&Pubkey::new_unique(), 1));
+        // Unused reservations are held back from the regular queue.
+        assert_eq!(reservations.unreserved_budget(u64::MAX, 0), 700);
+        assert_eq!(reservations.unreserved_budget(u64::MAX, 50), 650);
+        assert_eq!(reservations.unreserved_budget(300, 0), 300);
+
+        reservations.record_scheduled(&peer, 60);
+        assert!(reservations.fits(&peer, 40));
+        assert!(!reservations.fits(&peer, 41));
+        assert_eq!(reservations.unreserved_budget(u64::MAX, 60), 700);
+
+        // Overflowing transactions do not use the reservation.
+        reservations.record_overflowed(&peer);
+        assert_eq!(reservations.usage_by_peer[&peer].num_overflowed, 1);
+        assert_eq!(reservations.usage_by_peer[&peer].scheduled_cus, 60);
+    }
+
+    #[test]
+    fn test_parse_reservation() {
+        let peer = Pubkey::new_unique();
+        assert_eq!(
+            format!("{peer}:25").parse(),
+            Ok(BlockSpaceReservation {
+                peer,
+                share_percent: 25,
+            })
+        );
+        assert!(format!("{peer}").parse::<BlockSpaceReservation>().is_err());
+        assert!(
+            format!("{peer}:0")
+                .parse::<BlockSpaceReservation>()
+                .is_err()
+        );
+        assert!(
+            format!("{peer}:101")
+                .parse::<BlockSpaceReservation>()
+                .is_err()
+        );
+        assert!("peer:10".parse::<BlockSpaceReservation>().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "must not exceed 100%")]
+    fn test_reservations_over_block() {
+        BlockSpaceReservations::new(&[
+            BlockSpaceReservation {
+                peer: Pubkey::new_unique(),
+                share_percent: 60,
+            },
+            BlockSpaceReservation {
+                peer: Pubkey::new_unique(),
+                share_percent: 41,
+            },
+        ]);
+    }
+}
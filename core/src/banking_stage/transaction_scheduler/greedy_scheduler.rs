@@ -3,19 +3,17 @@ use {
         scheduler::{Scheduler, SchedulingSummary},
         scheduler_common::{
             SchedulingCommon, TransactionSchedulingError, TransactionSchedulingInfo, select_thread,
+            try_schedule_transaction,
         },
         scheduler_error::SchedulerError,
         transaction_priority_id::TransactionPriorityId,
-        transaction_state::TransactionState,
         transaction_state_container::StateContainer,
     },
     crate::banking_stage::{
         consumer::{ENTRY_OVERHEAD_BYTES, TARGET_NUM_TRANSACTIONS_PER_BATCH},
         scheduler_messages::{ConsumeWork, FinishedConsumeWork},
     },
-    agave_scheduling_utils::thread_aware_account_locks::{
-        ThreadAwareAccountLocks, ThreadId, ThreadSet, TryLockError,
-    },
+    agave_scheduling_utils::thread_aware_account_locks::ThreadSet,
     crossbeam_channel::{Receiver, Sender},
     solana_cost_model::block_cost_limits::MAX_BLOCK_UNITS,
     solana_ledger::shred::get_data_shred_bytes_per_batch_typical,
@@ -129,13 +127,28 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
         let mut num_unschedulable_conflicts: usize = 0;
         let mut num_unschedulable_threads: usize = 0;
 
+        // Bundles are scheduled ahead of individual transactions.
+        let num_bundled =
+            self.common
+                .schedule_bundles(container, schedulable_threads, &mut budget)?;
+        num_scheduled += num_bundled;
+        num_sent += num_bundled;
+
+        // Followed by the transactions of peers with reserved block space,
+        // which is then held back from the rest.
+        let num_reserved =
+            self.common
+                .schedule_reserved(container, schedulable_threads, &mut budget)?;
+        num_scheduled += num_reserved;
+        num_sent += num_reserved;
+        budget = self.common.unreserved_budget(budget);
+
         while budget > 0
             && num_scanned < self.config.max_scanned_transactions_per_scheduling_pass
             && !schedulable_threads.is_empty()
-            && !container.is_empty()
         {
-            let Some(id) = container.pop() else {
-                unreachable!("container is not empty")
+            let Some(id) = self.common.pop_next(container) else {
+                break;
             };
 
             num_scanned += 1;
@@ -225,8 +238,10 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
             "number of scheduled and sent transactions must match"
         );
 
-        // Push unschedulables back into the queue
+        // Push unschedulables and transactions deferred by write lock pricing
+        // back into the queue
         container.push_ids_into_queue(self.unschedulables.drain(..));
+        let num_repriced = self.common.finish_pricing(container);
 
         Ok(SchedulingSummary {
             starting_queue_size,
@@ -234,6 +249,7 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
             num_scheduled,
             num_unschedulable_conflicts,
             num_unschedulable_threads,
+            num_repriced,
         })
     }
 
@@ -242,57 +258,17 @@ impl<Tx: TransactionWithMeta> Scheduler<Tx> for GreedyScheduler<Tx> {
     }
 }
 
-fn try_schedule_transaction<Tx: TransactionWithMeta>(
-    transaction_state: &mut TransactionState<Tx>,
-    account_locks: &mut ThreadAwareAccountLocks,
-    schedulable_threads: ThreadSet,
-    thread_selector: impl Fn(ThreadSet) -> ThreadId,
-) -> Result<TransactionSchedulingInfo<Tx>, TransactionSchedulingError> {
-    // Schedule the transaction if it can be.
-    let transaction = transaction_state.transaction();
-    let account_keys = transaction.account_keys();
-    let write_account_locks = account_keys
-        .iter()
-        .enumerate()
-        .filter_map(|(index, key)| transaction.is_writable(index).then_some(key));
-    let read_account_locks = account_keys
-        .iter()
-        .enumerate()
-        .filter_map(|(index, key)| (!transaction.is_writable(index)).then_some(key));
-
-    let thread_id = match account_locks.try_lock_accounts(
-        write_account_locks,
-        read_account_locks,
-        schedulable_threads,
-        thread_selector,
-    ) {
-        Ok(thread_id) => thread_id,
-        Err(TryLockError::MultipleConflicts) => {
-            return Err(TransactionSchedulingError::UnschedulableConflicts);
-        }
-        Err(TryLockError::ThreadNotAllowed) => {
-            return Err(TransactionSchedulingError::UnschedulableThread);
-        }
-    };
-
-    let (transaction, max_age) = transaction_state.take_transaction_for_scheduling();
-    let cost = transaction_state.cost();
-
-    Ok(TransactionSchedulingInfo {
-        thread_id,
-        transaction,
-        max_age,
-        cost,
-    })
-}
-
 #[cfg(test)]
 mod test {
     use {
         super::*,
         crate::banking_stage::{
             scheduler_messages::{MaxAge, TransactionId},
-            transaction_scheduler::transaction_state_container::TransactionStateContainer,
+            transaction_scheduler::{
+                block_space_reservation::{BlockSpaceReservation, BlockSpaceReservations},
+                transaction_state_container::TransactionStateContainer,
+                write_lock_pricing::{WriteLockPricing, WriteLockPricingConfig},
+            },
         },
         crossbeam_channel::bounded,
         itertools::Itertools,
@@ -676,4 +652,80 @@ mod test {
         assert_eq!(collect_work(&work_receivers[0]).1, [vec![5, 4]]);
         assert_eq!(collect_work(&work_receivers[1]).1, [vec![0]]);
     }
+
+    #[test]
+    fn test_schedule_write_lock_pricing() {
+        let (mut scheduler, work_receivers, _finished_work_sender) =
+            create_test_frame(1, GreedySchedulerConfig::default());
+        let hot_pubkey = Pubkey::new_unique();
+        let mut write_lock_pricing = WriteLockPricing::new(WriteLockPricingConfig {
+            utilization_threshold_percent: 50,
+        });
+        // The hot account is 75% full: priority is halved.
+        write_lock_pricing.set_utilization_bps(hot_pubkey, 7_500);
+        scheduler.common.write_lock_pricing = Some(write_lock_pricing);
+
+        let mut container = create_container([
+            (&Keypair::new(), &[hot_pubkey], 1, 10),
+            (&Keypair::new(), &[Pubkey::new_unique()], 1, 6),
+            (&Keypair::new(), &[Pubkey::new_unique()], 1, 4),
+        ]);
+
+        let scheduling_summary = scheduler
+            .schedule(
+                &mut container,
+                u64::MAX, // no budget
+            )
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 3);
+        assert_eq!(scheduling_summary.num_repriced, 1);
+        assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![1, 0, 2]]);
+    }
+
+    #[test]
+    fn test_schedule_block_space_reservations() {
+        let (mut scheduler, work_receivers, _finished_work_sender) =
+            create_test_frame(1, GreedySchedulerConfig::default());
+        let peer = Pubkey::new_unique();
+        let mut reservations = BlockSpaceReservations::new(&[BlockSpaceReservation {
+            peer,
+            share_percent: 50,
+        }]);
+        reservations.set_block_limit(10_000);
+        scheduler.common.block_space_reservations = Some(reservations);
+
+        let mut container = create_container([
+            (&Keypair::new(), &[Pubkey::new_unique()], 1, 3),
+            (&Keypair::new(), &[Pubkey::new_unique()], 1, 2),
+            (&Keypair::new(), &[Pubkey::new_unique()], 1, 1),
+        ]);
+
+        // Half of the block is held back for the peer.
+        let scheduling_summary = scheduler
+            .schedule(
+                &mut container,
+                u64::MAX, // no budget
+            )
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 1);
+        assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![0]]);
+
+        // The peer's transaction is scheduled ahead of higher priorities.
+        container.insert_new_reserved_transaction(
+            prioritized_tranfers(&Keypair::new(), [Pubkey::new_unique()], 1, 0),
+            MaxAge::MAX,
+            0,
+            5_000,
+            peer,
+        );
+        let scheduling_summary = scheduler
+            .schedule(
+                &mut container,
+                u64::MAX, // no budget
+            )
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 1);
+        assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![3]]);
+        assert_eq!(container.queue_size(), 2);
+    }
 }
@@ -1,6 +1,8 @@
 mod batch_id_generator;
+pub mod block_space_reservation;
 pub(crate) mod greedy_scheduler;
 mod in_flight_tracker;
+pub(crate) mod prio_graph_scheduler;
 pub(crate) mod receive_and_buffer;
 pub(crate) mod scheduler;
 pub(crate) mod scheduler_common;
@@ -10,3 +12,4 @@ mod scheduler_metrics;
 mod transaction_priority_id;
 pub(crate) mod transaction_state;
 pub(crate) mod transaction_state_container;
+pub mod write_lock_pricing;
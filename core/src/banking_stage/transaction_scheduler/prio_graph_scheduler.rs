@@ -0,0 +1,701 @@
+use {
+    super::{
+        greedy_scheduler::GreedySchedulerConfig,
+        scheduler::{Scheduler, SchedulingSummary},
+        scheduler_common::{
+            SchedulingCommon, TransactionSchedulingError, TransactionSchedulingInfo, select_thread,
+            try_schedule_transaction,
+        },
+        scheduler_error::SchedulerError,
+        transaction_priority_id::TransactionPriorityId,
+        transaction_state_container::StateContainer,
+    },
+    crate::banking_stage::{
+        consumer::ENTRY_OVERHEAD_BYTES,
+        scheduler_messages::{ConsumeWork, FinishedConsumeWork},
+    },
+    agave_scheduling_utils::thread_aware_account_locks::ThreadSet,
+    ahash::AHashMap,
+    crossbeam_channel::{Receiver, Sender},
+    solana_pubkey::Pubkey,
+    solana_runtime_transaction::transaction_with_meta::TransactionWithMeta,
+    solana_svm_transaction::svm_message::SVMMessage,
+    std::{collections::BinaryHeap, num::Saturating},
+};
+
+pub(crate) struct PrioGraphSchedulerConfig {
+    pub max_scheduled_cus: u64,
+    pub look_ahead_window_size: usize,
+    pub target_transactions_per_batch: usize,
+    pub target_entry_bytes_per_batch: u64,
+}
+
+impl Default for PrioGraphSchedulerConfig {
+    fn default() -> Self {
+        let GreedySchedulerConfig {
+            target_scheduled_cus,
+            target_transactions_per_batch,
+            target_entry_bytes_per_batch,
+            ..
+        } = GreedySchedulerConfig::default();
+        Self {
+            max_scheduled_cus: target_scheduled_cus,
+            look_ahead_window_size: 2048,
+            target_transactions_per_batch,
+            target_entry_bytes_per_batch,
+        }
+    }
+}
+
+/// Scheduler that looks ahead at the highest priority transactions of the
+/// container and builds a graph of their account conflicts. Transactions
+/// are only scheduled once every higher priority transaction they conflict
+/// with has been scheduled, so chains of conflicting transactions land on a
+/// single thread while independent chains are spread across threads.
+pub struct PrioGraphScheduler<Tx: TransactionWithMeta> {
+    common: SchedulingCommon<Tx>,
+    graph: PrioGraph,
+    config: PrioGraphSchedulerConfig,
+}
+
+impl<Tx: TransactionWithMeta> PrioGraphScheduler<Tx> {
+    pub(crate) fn new(
+        consume_work_senders: Vec<Sender<ConsumeWork<Tx>>>,
+        finished_consume_work_receiver: Receiver<FinishedConsumeWork<Tx>>,
+        config: PrioGraphSchedulerConfig,
+    ) -> Self {
+        assert!(
+            config.target_entry_bytes_per_batch > ENTRY_OVERHEAD_BYTES,
+            "target entry bytes per batch must exceed entry overhead"
+        );
+        Self {
+            graph: PrioGraph::with_capacity(config.look_ahead_window_size),
+            common: SchedulingCommon::new(
+                consume_work_senders,
+                finished_consume_work_receiver,
+                config.target_transactions_per_batch,
+            ),
+            config,
+        }
+    }
+}
+
+impl<Tx: TransactionWithMeta> Scheduler<Tx> for PrioGraphScheduler<Tx> {
+    fn schedule<S: StateContainer<Tx>>(
+        &mut self,
+        container: &mut S,
+        budget: u64,
+    ) -> Result<SchedulingSummary, SchedulerError> {
+        // Subtract any in-flight compute units from the budget.
+        let mut budget = budget.saturating_sub(
+            self.common
+                .in_flight_tracker
+                .cus_in_flight_per_thread()
+                .iter()
+                .sum(),
+        );
+
+        let starting_queue_size = container.queue_size();
+        let starting_buffer_size = container.buffer_size();
+
+        let num_threads = self.common.consume_work_senders.len();
+        let max_cu_per_thread = self.config.max_scheduled_cus / num_threads as u64;
+
+        let mut schedulable_threads = ThreadSet::any(num_threads);
+        for thread_id in 0..num_threads {
+            if self.common.consume_work_senders[thread_id].is_full()
+                || self.common.in_flight_tracker.cus_in_flight_per_thread()[thread_id]
+                    >= max_cu_per_thread
+            {
+                schedulable_threads.remove(thread_id);
+            }
+        }
+        if schedulable_threads.is_empty() {
+            return Ok(SchedulingSummary {
+                starting_queue_size,
+                starting_buffer_size,
+                ..SchedulingSummary::default()
+            });
+        }
+
+        #[cfg(debug_assertions)]
+        debug_assert!(
+            self.common.batches.is_empty(),
+            "batches must start empty for scheduling"
+        );
+
+        // Fill the look-ahead window, in (effective) priority order.
+        while self.graph.len() < self.config.look_ahead_window_size {
+            let Some(id) = self.common.pop_next(container) else {
+                break;
+            };
+            let Some(transaction_state) = container.get_mut_transaction_state(id.id) else {
+                panic!("transaction state must exist")
+            };
+            self.graph.insert(id, transaction_state.transaction());
+        }
+
+        // Track metrics on filter.
+        let mut num_scheduled = Saturating::<usize>(0);
+        let mut num_sent: usize = 0;
+        let mut num_unschedulable_conflicts: usize = 0;
+        let mut num_unschedulable_threads: usize = 0;
+
+        // Bundles are scheduled ahead of individual transactions.
+        let num_bundled =
+            self.common
+                .schedule_bundles(container, schedulable_threads, &mut budget)?;
+        num_scheduled += num_bundled;
+        num_sent += num_bundled;
+
+        // Followed by the transactions of peers with reserved block space,
+        // which is then held back from the rest.
+        let num_reserved =
+            self.common
+                .schedule_reserved(container, schedulable_threads, &mut budget)?;
+        num_scheduled += num_reserved;
+        num_sent += num_reserved;
+        budget = self.common.unreserved_budget(budget);
+
+        while budget > 0 && !schedulable_threads.is_empty() {
+            let Some((id, node)) = self.graph.pop() else {
+                break;
+            };
+
+            let Some(transaction_state) = container.get_mut_transaction_state(id.id) else {
+                panic!("transaction state must exist")
+            };
+
+            match try_schedule_transaction(
+                transaction_state,
+                &mut self.common.account_locks,
+                schedulable_threads,
+                |thread_set| {
+                    select_thread(
+                        thread_set,
+                        self.common.batches.total_cus(),
+                        self.common.in_flight_tracker.cus_in_flight_per_thread(),
+                        self.common.batches.transactions(),
+                        self.common.in_flight_tracker.num_in_flight_per_thread(),
+                    )
+                },
+            ) {
+                // Transactions depending on an unschedulable transaction stay
+                // blocked until the next pass.
+                Err(TransactionSchedulingError::UnschedulableConflicts) => {
+                    num_unschedulable_conflicts += 1;
+                }
+                Err(TransactionSchedulingError::UnschedulableThread) => {
+                    num_unschedulable_threads += 1;
+                }
+                Ok(TransactionSchedulingInfo {
+                    thread_id,
+                    transaction,
+                    max_age,
+                    cost,
+                }) => {
+                    let transaction_bytes = transaction.serialized_size() as u64;
+                    if self.common.batches.entry_bytes()[thread_id] + transaction_bytes
+                        > self.config.target_entry_bytes_per_batch
+                    {
+                        num_sent += self.common.send_batches()?;
+                    }
+
+                    num_scheduled += 1;
+                    self.common.batches.add_transaction_to_batch(
+                        thread_id,
+                        id.id,
+                        transaction,
+                        max_age,
+                        cost,
+                        transaction_bytes,
+                    );
+                    budget = budget.saturating_sub(cost);
+
+                    // The account locks are held by `thread_id` until the
+                    // batch completes, so the unblocked transactions can only
+                    // be scheduled behind this one, on the same thread.
+                    self.graph.unblock(node);
+
+                    // If a hard batch target is reached, send all the batches.
+                    if self.common.batches.transactions()[thread_id].len()
+                        >= self.config.target_transactions_per_batch
+                        || self.common.batches.entry_bytes()[thread_id]
+                            >= self.config.target_entry_bytes_per_batch
+                    {
+                        num_sent += self.common.send_batches()?;
+                    }
+
+                    // If the thread is at max_cu_per_thread, remove it from the schedulable threads.
+                    if self.common.consume_work_senders[thread_id].is_full()
+                        || self.common.in_flight_tracker.cus_in_flight_per_thread()[thread_id]
+                            + self.common.batches.total_cus()[thread_id]
+                            >= max_cu_per_thread
+                    {
+                        schedulable_threads.remove(thread_id);
+                    }
+                }
+            }
+        }
+
+        num_sent += self.common.send_batches()?;
+        let Saturating(num_scheduled) = num_scheduled;
+        assert_eq!(
+            num_scheduled, num_sent,
+            "number of scheduled and sent transactions must match"
+        );
+
+        // Push everything left in the window, and anything deferred by write
+        // lock pricing, back into the queue.
+        container.push_ids_into_queue(self.graph.drain_unscheduled());
+        let num_repriced = self.common.finish_pricing(container);
+
+        Ok(SchedulingSummary {
+            starting_queue_size,
+            starting_buffer_size,
+            num_scheduled,
+            num_unschedulable_conflicts,
+            num_unschedulable_threads,
+            num_repriced,
+        })
+    }
+
+    fn scheduling_common_mut(&mut self) -> &mut SchedulingCommon<Tx> {
+        &mut self.common
+    }
+}
+
+struct GraphNode {
+    id: TransactionPriorityId,
+    /// Number of unscheduled transactions this transaction conflicts with
+    /// and must be scheduled after.
+    blocked_by: usize,
+    /// Transactions that must be scheduled after this transaction.
+    dependents: Vec<usize>,
+    scheduled: bool,
+}
+
+#[derive(Default)]
+struct AccountAccess {
+    last_write: Option<usize>,
+    reads_since_write: Vec<usize>,
+}
+
+/// Graph of the account conflicts between the transactions of the
+/// look-ahead window. Transactions must be inserted in priority order, so
+/// every edge points from a higher to a lower priority transaction.
+struct PrioGraph {
+    nodes: Vec<GraphNode>,
+    accesses: AHashMap<Pubkey, AccountAccess>,
+    /// Transactions that are not blocked by any other transaction.
+    unblocked: BinaryHeap<(TransactionPriorityId, usize)>,
+}
+
+impl PrioGraph {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            nodes: Vec::with_capacity(capacity),
+            accesses: AHashMap::with_capacity(capacity),
+            unblocked: BinaryHeap::with_capacity(capacity),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn insert(&mut self, id: TransactionPriorityId, transaction: &impl SVMMessage) {
+        let node = self.nodes.len();
+        self.nodes.push(GraphNode {
+            id,
+            blocked_by: 0,
+            dependents: Vec::new(),
+            scheduled: false,
+        });
+
+        for (index, key) in transaction.account_keys().iter().enumerate() {
+            let access = self.accesses.entry(*key).or_default();
+            if transaction.is_writable(index) {
+                // Writes conflict with every read since the last write, or
+                // with the last write if there were none.
+                if access.reads_since_write.is_empty() {
+                    if let Some(last_write) = access.last_write {
+                        Self::add_edge(&mut self.nodes, last_write, node);
+                    }
+                } else {
+                    for read in access.reads_since_write.drain(..) {
+                        Self::add_edge(&mut self.nodes, read, node);
+                    }
+                }
+                access.last_write = Some(node);
+            } else {
+                if let Some(last_write) = access.last_write {
+                    Self::add_edge(&mut self.nodes, last_write, node);
+                }
+                access.reads_since_write.push(node);
+            }
+        }
+
+        if self.nodes[node].blocked_by == 0 {
+            self.unblocked.push((id, node));
+        }
+    }
+
+    fn add_edge(nodes: &mut [GraphNode], from: usize, to: usize) {
+        // Edges to a node are all added while inserting it, so a duplicate
+        // edge is always the last one.
+        if nodes[from].dependents.last() != Some(&to) {
+            nodes[from].dependents.push(to);
+            nodes[to].blocked_by += 1;
+        }
+    }
+
+    /// Pops the highest priority unblocked transaction.
+    fn pop(&mut self) -> Option<(TransactionPriorityId, usize)> {
+        self.unblocked.pop()
+    }
+
+    /// Marks `node` as scheduled, unblocking the transactions that were
+    /// only waiting on it.
+    fn unblock(&mut self, node: usize) {
+        self.nodes[node].scheduled = true;
+        let dependents = std::mem::take(&mut self.nodes[node].dependents);
+        for dependent in dependents {
+            let dependent_node = &mut self.nodes[dependent];
+            dependent_node.blocked_by -= 1;
+            if dependent_node.blocked_by == 0 {
+                self.unblocked.push((dependent_node.id, dependent));
+            }
+        }
+    }
+
+    /// Clears the graph, returning the ids of the unscheduled transactions.
+    fn drain_unscheduled(&mut self) -> impl Iterator<Item = TransactionPriorityId> + '_ {
+        self.accesses.clear();
+        self.unblocked.clear();
+        self.nodes
+            .drain(..)
+            .filter_map(|node| (!node.scheduled).then_some(node.id))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::banking_stage::{
+            scheduler_messages::{MaxAge, TransactionId},
+            transaction_scheduler::transaction_state_container::TransactionStateContainer,
+        },
+        crossbeam_channel::bounded,
+        itertools::Itertools,
+        solana_compute_budget_interface::ComputeBudgetInstruction,
+        solana_hash::Hash,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_message::Message,
+        solana_runtime_transaction::runtime_transaction::RuntimeTransaction,
+        solana_signer::Signer,
+        solana_system_interface::instruction as system_instruction,
+        solana_transaction::{Transaction, sanitized::SanitizedTransaction},
+        std::borrow::Borrow,
+    };
+
+    #[allow(clippy::type_complexity)]
+    fn create_test_frame(
+        num_threads: usize,
+        config: PrioGraphSchedulerConfig,
+    ) -> (
+        PrioGraphScheduler<RuntimeTransaction<SanitizedTransaction>>,
+        Vec<Receiver<ConsumeWork<RuntimeTransaction<SanitizedTransaction>>>>,
+        Sender<FinishedConsumeWork<RuntimeTransaction<SanitizedTransaction>>>,
+    ) {
+        let (consume_work_senders, consume_work_receivers) =
+            (0..num_threads).map(|_| bounded(1024)).unzip();
+        let (finished_consume_work_sender, finished_consume_work_receiver) = bounded(1024);
+        let scheduler =
+            PrioGraphScheduler::new(consume_work_senders, finished_consume_work_receiver, config);
+        (
+            scheduler,
+            consume_work_receivers,
+            finished_consume_work_sender,
+        )
+    }
+
+    fn prioritized_tranfers(
+        from_keypair: &Keypair,
+        to_pubkeys: impl IntoIterator<Item = impl Borrow<Pubkey>>,
+        lamports: u64,
+        priority: u64,
+    ) -> RuntimeTransaction<SanitizedTransaction> {
+        let to_pubkeys_lamports = to_pubkeys
+            .into_iter()
+            .map(|pubkey| *pubkey.borrow())
+            .zip(std::iter::repeat(lamports))
+            .collect_vec();
+        let mut ixs =
+            system_instruction::transfer_many(&from_keypair.pubkey(), &to_pubkeys_lamports);
+        let prioritization = ComputeBudgetInstruction::set_compute_unit_price(priority);
+        ixs.push(prioritization);
+        let message = Message::new(&ixs, Some(&from_keypair.pubkey()));
+        let tx = Transaction::new(&[from_keypair], message, Hash::default());
+        RuntimeTransaction::from_transaction_for_tests(tx)
+    }
+
+    fn create_container(
+        tx_infos: impl IntoIterator<
+            Item = (
+                impl Borrow<Keypair>,
+                impl IntoIterator<Item = impl Borrow<Pubkey>>,
+                u64,
+                u64,
+            ),
+        >,
+    ) -> TransactionStateContainer<RuntimeTransaction<SanitizedTransaction>> {
+        let mut container = TransactionStateContainer::with_capacity(10 * 1024);
+        for (from_keypair, to_pubkeys, lamports, compute_unit_price) in tx_infos.into_iter() {
+            let transaction = prioritized_tranfers(
+                from_keypair.borrow(),
+                to_pubkeys,
+                lamports,
+                compute_unit_price,
+            );
+            const TEST_TRANSACTION_COST: u64 = 5000;
+            container.insert_new_transaction(
+                transaction,
+                MaxAge::MAX,
+                compute_unit_price,
+                TEST_TRANSACTION_COST,
+            );
+        }
+
+        container
+    }
+
+    fn collect_work(
+        receiver: &Receiver<ConsumeWork<RuntimeTransaction<SanitizedTransaction>>>,
+    ) -> (
+        Vec<ConsumeWork<RuntimeTransaction<SanitizedTransaction>>>,
+        Vec<Vec<TransactionId>>,
+    ) {
+        receiver
+            .try_iter()
+            .map(|work| {
+                let ids = work.ids.clone();
+                (work, ids)
+            })
+            .unzip()
+    }
+
+    #[test]
+    fn test_schedule_disconnected_channel() {
+        let (mut scheduler, work_receivers, _finished_work_sender) =
+            create_test_frame(1, PrioGraphSchedulerConfig::default());
+        let mut container = create_container([(&Keypair::new(), &[Pubkey::new_unique()], 1, 1)]);
+
+        drop(work_receivers); // explicitly drop receivers
+        assert_matches!(
+            scheduler.schedule(
+                &mut container,
+                u64::MAX, // no budget
+            ),
+            Err(SchedulerError::DisconnectedSendChannel(_))
+        );
+    }
+
+    #[test]
+    fn test_schedule_single_threaded_no_conflicts() {
+        let (mut scheduler, work_receivers, _finished_work_sender) =
+            create_test_frame(1, PrioGraphSchedulerConfig::default());
+        let mut container = create_container([
+            (&Keypair::new(), &[Pubkey::new_unique()], 1, 1),
+            (&Keypair::new(), &[Pubkey::new_unique()], 2, 2),
+        ]);
+
+        let scheduling_summary = scheduler
+            .schedule(
+                &mut container,
+                u64::MAX, // no budget
+            )
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 2);
+        assert_eq!(scheduling_summary.num_unschedulable_conflicts, 0);
+        assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![1, 0]]);
+    }
+
+    #[test]
+    fn test_schedule_budget() {
+        let (mut scheduler, _work_receivers, _finished_work_sender) =
+            create_test_frame(1, PrioGraphSchedulerConfig::default());
+        let mut container = create_container([
+            (&Keypair::new(), &[Pubkey::new_unique()], 1, 1),
+            (&Keypair::new(), &[Pubkey::new_unique()], 2, 2),
+        ]);
+
+        let scheduling_summary = scheduler
+            .schedule(
+                &mut container,
+                0, // zero budget
+            )
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 0);
+        assert_eq!(scheduling_summary.num_unschedulable_conflicts, 0);
+        assert_eq!(container.queue_size(), 2);
+    }
+
+    #[test]
+    fn test_schedule_look_ahead_window() {
+        let (mut scheduler, work_receivers, _finished_work_sender) = create_test_frame(
+            1,
+            PrioGraphSchedulerConfig {
+                look_ahead_window_size: 1,
+                ..PrioGraphSchedulerConfig::default()
+            },
+        );
+        let mut container = create_container([
+            (&Keypair::new(), &[Pubkey::new_unique()], 1, 1),
+            (&Keypair::new(), &[Pubkey::new_unique()], 2, 2),
+        ]);
+
+        let scheduling_summary = scheduler
+            .schedule(
+                &mut container,
+                u64::MAX, // no budget
+            )
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 1);
+        assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![1]]);
+        assert_eq!(container.queue_size(), 1);
+    }
+
+    #[test]
+    fn test_schedule_single_threaded_conflict() {
+        let (mut scheduler, work_receivers, _finished_work_sender) =
+            create_test_frame(1, PrioGraphSchedulerConfig::default());
+        let pubkey = Pubkey::new_unique();
+        let mut container = create_container([
+            (&Keypair::new(), &[pubkey], 1, 1),
+            (&Keypair::new(), &[pubkey], 1, 2),
+        ]);
+
+        let scheduling_summary = scheduler
+            .schedule(
+                &mut container,
+                u64::MAX, // no budget
+            )
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 2);
+        assert_eq!(scheduling_summary.num_unschedulable_conflicts, 0);
+        assert_eq!(collect_work(&work_receivers[0]).1, vec![vec![1, 0]]);
+    }
+
+    #[test]
+    fn test_schedule_independent_chains() {
+        let (mut scheduler, work_receivers, _finished_work_sender) =
+            create_test_frame(2, PrioGraphSchedulerConfig::default());
+        let pubkey1 = Pubkey::new_unique();
+        let pubkey2 = Pubkey::new_unique();
+
+        // Two chains of conflicting transactions, interleaved in priority:
+        // 3 -> 1 on pubkey1, and 2 -> 0 on pubkey2.
+        let mut container = create_container([
+            (Keypair::new(), [pubkey2], 0, 0),
+            (Keypair::new(), [pubkey1], 1, 1),
+            (Keypair::new(), [pubkey2], 2, 2),
+            (Keypair::new(), [pubkey1], 3, 3),
+        ]);
+
+        let scheduling_summary = scheduler
+            .schedule(
+                &mut container,
+                u64::MAX, // no budget
+            )
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 4);
+        assert_eq!(scheduling_summary.num_unschedulable_conflicts, 0);
+        assert_eq!(collect_work(&work_receivers[0]).1, [vec![3, 1]]);
+        assert_eq!(collect_work(&work_receivers[1]).1, [vec![2, 0]]);
+    }
+
+    #[test]
+    fn test_schedule_priority_guard() {
+        let (mut scheduler, work_receivers, _finished_work_sender) =
+            create_test_frame(2, PrioGraphSchedulerConfig::default());
+        let pubkey1 = Pubkey::new_unique();
+        let pubkey2 = Pubkey::new_unique();
+        let pubkey3 = Pubkey::new_unique();
+
+        // Dependency graph:
+        // 3 --
+        //     \
+        //       -> 1 -> 0
+        //     /
+        // 2 --
+        //
+        // Notes:
+        // - 3 and 2 are immediately schedulable at the top of the graph.
+        // - 3 and 2 will be scheduled to different threads.
+        // - 1 conflicts with both 3 and 2 (different threads) so is unschedulable.
+        // - 0 is blocked by 1, so is not scheduled ahead of it.
+        let mut container = create_container([
+            (Keypair::new(), &[pubkey3][..], 0, 0),
+            (Keypair::new(), &[pubkey1, pubkey2, pubkey3][..], 1, 1),
+            (Keypair::new(), &[pubkey2][..], 2, 2),
+            (Keypair::new(), &[pubkey1][..], 3, 3),
+        ]);
+
+        let scheduling_summary = scheduler
+            .schedule(
+                &mut container,
+                u64::MAX, // no budget
+            )
+            .unwrap();
+        assert_eq!(scheduling_summary.num_scheduled, 2);
+        assert_eq!(scheduling_summary.num_unschedulable_conflicts, 1);
+        assert_eq!(collect_work(&work_receivers[0]).1, [vec![3]]);
+        assert_eq!(collect_work(&work_receivers[1]).1, [vec![2]]);
+        assert_eq!(container.queue_size(), 2);
+    }
+
+    #[test]
+    fn test_prio_graph_read_write_edges() {
+        let mut graph = PrioGraph::with_capacity(4);
+        let read_key = Pubkey::new_unique();
+        let fee_payers = (0..4).map(|_| Keypair::new()).collect_vec();
+
+        // 3 and 2 read `read_key` while 1 writes to it, so 1 waits on both
+        // reads.
+        let read = |payer: &Keypair| {
+            let ix = Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[],
+                vec![AccountMeta::new_readonly(read_key, false)],
+            );
+            let message = Message::new(&[ix], Some(&payer.pubkey()));
+            RuntimeTransaction::from_transaction_for_tests(Transaction::new(
+                &[payer],
+                message,
+                Hash::default(),
+            ))
+        };
+        graph.insert(TransactionPriorityId::new(3, 3), &read(&fee_payers[3]));
+        graph.insert(TransactionPriorityId::new(2, 2), &read(&fee_payers[2]));
+        graph.insert(
+            TransactionPriorityId::new(1, 1),
+            &prioritized_tranfers(&fee_payers[1], [read_key], 1, 1),
+        );
+
+        let (first, first_node) = graph.pop().unwrap();
+        let (second, second_node) = graph.pop().unwrap();
+        assert_eq!((first.id, second.id), (3, 2));
+        assert!(graph.pop().is_none());
+
+        graph.unblock(first_node);
+        assert!(graph.pop().is_none());
+        graph.unblock(second_node);
+        assert_eq!(graph.pop().unwrap().0.id, 1);
+
+        assert_eq!(graph.drain_unscheduled().map(|id| id.id).collect_vec(), [1]);
+        assert_eq!(graph.len(), 0);
+    }
+}
@@ -3,8 +3,8 @@ use {
         transaction_priority_id::TransactionPriorityId,
         transaction_state::TransactionState,
         transaction_state_container::{
-            EXTRA_CAPACITY, SharedBytes, StateContainer, TransactionViewState,
-            TransactionViewStateContainer,
+            EXTRA_CAPACITY, MAX_BUNDLE_LENGTH, RuntimeTransactionView, SharedBytes, StateContainer,
+            TransactionViewState, TransactionViewStateContainer,
         },
     },
     crate::{
@@ -13,7 +13,9 @@ use {
         },
         transaction_priority::calculate_priority_and_cost,
     },
-    agave_banking_stage_ingress_types::{BankingPacketBatch, BankingPacketReceiver},
+    agave_banking_stage_ingress_types::{
+        BankingBundleBatch, BankingBundleReceiver, BankingPacketBatch, BankingPacketReceiver,
+    },
     agave_transaction_view::{
         resolved_transaction_view::ResolvedTransactionView, sanitize::SanitizeConfig,
         transaction_data::TransactionData, transaction_version::TransactionVersion,
@@ -26,6 +28,7 @@ use {
     solana_address_lookup_table_interface::state::estimate_last_valid_slot,
     solana_clock::{Epoch, Slot},
     solana_message::v0::LoadedAddresses,
+    solana_perf::packet::PacketBatch,
     solana_pubkey::Pubkey,
     solana_runtime::{
         bank::Bank,
@@ -46,6 +49,7 @@ use {
 pub(crate) struct DisconnectedError;
 
 /// Stats/metrics returned by `receive_and_buffer_packets`.
+#[derive(Default)]
 pub(crate) struct ReceivingStats {
     pub num_received: usize,
     /// Count of packets that passed sigverify but were dropped
@@ -61,6 +65,8 @@ pub(crate) struct ReceivingStats {
     pub num_dropped_on_fee_payer: usize,
     pub num_dropped_on_filter_key: usize,
     pub num_dropped_on_capacity: usize,
+    /// Count of bundles dropped as a whole, on checks or capacity.
+    pub num_dropped_bundles: usize,
 
     pub num_buffered: usize,
 
@@ -81,6 +87,7 @@ impl ReceivingStats {
         self.num_dropped_on_fee_payer += other.num_dropped_on_fee_payer;
         self.num_dropped_on_filter_key += other.num_dropped_on_filter_key;
         self.num_dropped_on_capacity += other.num_dropped_on_capacity;
+        self.num_dropped_bundles += other.num_dropped_bundles;
         self.num_buffered += other.num_buffered;
 
         self.receive_time_us += other.receive_time_us;
@@ -103,8 +110,12 @@ pub(crate) trait ReceiveAndBuffer {
 
 pub(crate) struct TransactionViewReceiveAndBuffer {
     pub receiver: BankingPacketReceiver,
+    pub bundle_receiver: BankingBundleReceiver,
     pub sharable_banks: SharableBanks,
     pub filter_keys: Arc<HashSet<Pubkey>>,
+    /// Peers with reserved block space. Their transactions are buffered in
+    /// the container's reserved lane.
+    pub reserved_peers: Arc<HashSet<Pubkey>>,
 }
 
 impl ReceiveAndBuffer for TransactionViewReceiveAndBuffer {
@@ -138,6 +149,7 @@ impl ReceiveAndBuffer for TransactionViewReceiveAndBuffer {
             num_dropped_on_fee_payer: 0,
             num_dropped_on_filter_key: 0,
             num_dropped_on_capacity: 0,
+            num_dropped_bundles: 0,
             num_buffered: 0,
             receive_time_us: 0,
             buffer_time_us: 0,
@@ -205,6 +217,18 @@ impl ReceiveAndBuffer for TransactionViewReceiveAndBuffer {
             }
         }
 
+        // Bundles are received without blocking. The bundle channel being
+        // disconnected does not stop the packet flow.
+        while let Ok(bundle_batch) = self.bundle_receiver.try_recv() {
+            stats.accumulate(self.handle_bundle_batch(
+                container,
+                decision,
+                &root_bank,
+                &working_bank,
+                bundle_batch,
+            ));
+        }
+
         Ok(ReceivingStats {
             num_received: stats.num_received,
             num_dropped_without_parsing: stats.num_dropped_without_parsing,
@@ -216,6 +240,7 @@ impl ReceiveAndBuffer for TransactionViewReceiveAndBuffer {
             num_dropped_on_fee_payer: stats.num_dropped_on_fee_payer,
             num_dropped_on_filter_key: stats.num_dropped_on_filter_key,
             num_dropped_on_capacity: stats.num_dropped_on_capacity,
+            num_dropped_bundles: stats.num_dropped_bundles,
             num_buffered: stats.num_buffered,
             receive_time_us: stats.receive_time_us,
             buffer_time_us: stats.buffer_time_us,
@@ -377,10 +402,15 @@ impl TransactionViewReceiveAndBuffer {
                         }
                     })
                 {
-                    let priority = container
+                    let transaction_state = container
                         .get_mut_transaction_state(transaction_id)
-                        .expect("transaction must exist")
-                        .priority();
+                        .expect("transaction must exist");
+                    if let Some(peer) = packet.meta().remote_pubkey() {
+                        if self.reserved_peers.contains(&peer) {
+                            transaction_state.set_reserved_for(Some(peer));
+                        }
+                    }
+                    let priority = transaction_state.priority();
                     transaction_priority_ids
                         .push(TransactionPriorityId::new(priority, transaction_id));
 
@@ -406,12 +436,156 @@ impl TransactionViewReceiveAndBuffer {
             num_dropped_on_fee_payer,
             num_dropped_on_filter_key,
             num_dropped_on_capacity,
+            num_dropped_bundles: 0,
             num_buffered,
             receive_time_us: 0, // receive is outside this function
             buffer_time_us: start.elapsed().as_micros() as u64,
         }
     }
 
+    /// Buffer each bundle of the batch into the container's bundle queue.
+    /// Bundles are never forwarded, and a bundle failing any check is
+    /// dropped as a whole.
+    fn handle_bundle_batch(
+        &mut self,
+        container: &mut TransactionViewStateContainer,
+        decision: &BufferedPacketsDecision,
+        root_bank: &Bank,
+        working_bank: &Bank,
+        bundle_batch: BankingBundleBatch,
+    ) -> ReceivingStats {
+        let start = Instant::now();
+        let mut stats = ReceivingStats::default();
+        let should_parse = !matches!(decision, BufferedPacketsDecision::Forward);
+
+        let sanitize_config =
+            sanitize_config(root_bank.feature_set.snapshot().limit_instruction_accounts);
+        let transaction_account_lock_limit = working_bank.get_transaction_account_lock_limit();
+
+        for bundle in bundle_batch.iter() {
+            stats.num_received += bundle.len();
+            if !should_parse {
+                stats.num_dropped_without_parsing += bundle.len();
+                continue;
+            }
+
+            match self.try_buffer_bundle(
+                container,
+                bundle,
+                root_bank,
+                working_bank,
+                transaction_account_lock_limit,
+                &sanitize_config,
+            ) {
+                Some(num_dropped_on_capacity) => {
+                    stats.num_buffered += bundle.len();
+                    stats.num_dropped_bundles += num_dropped_on_capacity;
+                }
+                None => stats.num_dropped_bundles += 1,
+            }
+        }
+
+        stats.buffer_time_us = start.elapsed().as_micros() as u64;
+        stats
+    }
+
+    /// Insert and check every transaction of the `bundle`, then push the
+    /// bundle into the bundle queue.
+    /// Returns the number of bundles dropped on capacity, or `None` if the
+    /// bundle was rejected.
+    fn try_buffer_bundle(
+        &self,
+        container: &mut TransactionViewStateContainer,
+        bundle: &PacketBatch,
+        root_bank: &Bank,
+        working_bank: &Bank,
+        transaction_account_lock_limit: usize,
+        sanitize_config: &SanitizeConfig,
+    ) -> Option<usize> {
+        if bundle.is_empty() || bundle.len() > MAX_BUNDLE_LENGTH {
+            return None;
+        }
+
+        let mut ids = Vec::with_capacity(bundle.len());
+        for packet in bundle.iter() {
+            let transaction_id = packet.data(..).and_then(|packet_data| {
+                container.try_insert_map_only_with_data(packet_data, |bytes| {
+                    Self::try_handle_packet(
+                        bytes,
+                        root_bank,
+                        working_bank,
+                        transaction_account_lock_limit,
+                        sanitize_config,
+                        &self.filter_keys,
+                    )
+                    .map_err(|_| ())
+                })
+            });
+            let Some(transaction_id) = transaction_id else {
+                for id in ids {
+                    container.remove_by_id(id);
+                }
+                return None;
+            };
+            ids.push(transaction_id);
+        }
+
+        // Bundles do not pass through sigverify, so their signatures are
+        // verified here before any other check.
+        let signatures_verified = ids.iter().all(|id| {
+            verify_signatures(
+                container
+                    .get_transaction(*id)
+                    .expect("transaction must exist"),
+            )
+        });
+        if !signatures_verified {
+            for id in ids {
+                container.remove_by_id(id);
+            }
+            return None;
+        }
+
+        let mut error_counters = TransactionErrorMetrics::default();
+        let check_results = {
+            let transactions: Vec<_> = ids
+                .iter()
+                .map(|id| {
+                    container
+                        .get_transaction(*id)
+                        .expect("transaction must exist")
+                })
+                .collect();
+            let lock_results = vec![Ok(()); transactions.len()];
+            working_bank.check_transactions::<RuntimeTransaction<_>>(
+                &transactions,
+                &lock_results,
+                working_bank.max_processing_age(),
+                true,
+                &mut error_counters,
+            )
+        };
+        let passed_checks = check_results.iter().zip(&ids).all(|(result, id)| {
+            result.is_ok()
+                && Consumer::check_fee_payer_unlocked(
+                    working_bank,
+                    container
+                        .get_transaction(*id)
+                        .expect("transaction must exist"),
+                    &mut error_counters,
+                )
+                .is_ok()
+        });
+        if !passed_checks {
+            for id in ids {
+                container.remove_by_id(id);
+            }
+            return None;
+        }
+
+        Some(container.push_bundle(ids))
+    }
+
     fn try_handle_packet(
         bytes: SharedBytes,
         root_bank: &Bank,
@@ -543,12 +717,23 @@ fn calculate_max_age(
     }
 }
 
+/// Verify every signature of the `transaction` against its signer keys.
+fn verify_signatures(transaction: &RuntimeTransactionView) -> bool {
+    let signatures = transaction.signatures();
+    let message = transaction.message_data();
+    !signatures.is_empty()
+        && signatures
+            .iter()
+            .zip(transaction.static_account_keys())
+            .all(|(signature, pubkey)| signature.verify(pubkey.as_ref(), message))
+}
+
 #[cfg(test)]
 mod tests {
     use {
         super::*,
         crate::banking_stage::tests::create_slow_genesis_config,
-        crossbeam_channel::{Receiver, bounded},
+        crossbeam_channel::{Receiver, bounded, never},
         solana_hash::Hash,
         solana_keypair::Keypair,
         solana_ledger::genesis_utils::GenesisConfigInfo,
@@ -559,6 +744,7 @@ mod tests {
         solana_perf::packet::{Packet, PacketBatch, RecycledPacketBatch, to_packet_batches},
         solana_pubkey::Pubkey,
         solana_runtime::bank_forks::BankForks,
+        solana_signature::Signature,
         solana_signer::Signer,
         solana_system_interface::instruction as system_instruction,
         solana_system_transaction::transfer,
@@ -603,8 +789,10 @@ mod tests {
     ) {
         let receive_and_buffer = TransactionViewReceiveAndBuffer {
             receiver,
+            bundle_receiver: never(),
             sharable_banks: bank_forks.read().unwrap().sharable_banks(),
             filter_keys,
+            reserved_peers: Arc::default(),
         };
         let container = TransactionViewStateContainer::with_capacity(TEST_CONTAINER_CAPACITY);
         (receive_and_buffer, container)
@@ -695,6 +883,7 @@ mod tests {
             num_dropped_on_fee_payer,
             num_dropped_on_filter_key: _,
             num_dropped_on_capacity,
+            num_dropped_bundles: _,
             num_buffered,
             receive_time_us: _,
             buffer_time_us: _,
@@ -750,6 +939,7 @@ mod tests {
             num_dropped_on_fee_payer,
             num_dropped_on_filter_key: _,
             num_dropped_on_capacity,
+            num_dropped_bundles: _,
             num_buffered,
             receive_time_us: _,
             buffer_time_us: _,
@@ -794,6 +984,7 @@ mod tests {
             num_dropped_on_fee_payer,
             num_dropped_on_filter_key: _,
             num_dropped_on_capacity,
+            num_dropped_bundles: _,
             num_buffered,
             receive_time_us: _,
             buffer_time_us: _,
@@ -837,6 +1028,7 @@ mod tests {
             num_dropped_on_fee_payer,
             num_dropped_on_filter_key: _,
             num_dropped_on_capacity,
+            num_dropped_bundles: _,
             num_buffered,
             receive_time_us: _,
             buffer_time_us: _,
@@ -885,6 +1077,7 @@ mod tests {
             num_dropped_on_fee_payer,
             num_dropped_on_filter_key: _,
             num_dropped_on_capacity,
+            num_dropped_bundles: _,
             num_buffered,
             receive_time_us: _,
             buffer_time_us: _,
@@ -948,6 +1141,7 @@ mod tests {
             num_dropped_on_fee_payer,
             num_dropped_on_filter_key: _,
             num_dropped_on_capacity,
+            num_dropped_bundles: _,
             num_buffered,
             receive_time_us: _,
             buffer_time_us: _,
@@ -996,6 +1190,7 @@ mod tests {
             num_dropped_on_fee_payer,
             num_dropped_on_filter_key: _,
             num_dropped_on_capacity,
+            num_dropped_bundles: _,
             num_buffered,
             receive_time_us: _,
             buffer_time_us: _,
@@ -1017,6 +1212,46 @@ mod tests {
         verify_container(&mut container, 1);
     }
 
+    #[test]
+    fn test_receive_and_buffer_reserved_peer() {
+        let (sender, receiver) = bounded(1024);
+        let (bank_forks, mint_keypair) = test_bank_forks();
+        let (mut receive_and_buffer, mut container) =
+            setup_transaction_view_receive_and_buffer(receiver, bank_forks.clone());
+        let reserved_peer = Pubkey::new_unique();
+        receive_and_buffer.reserved_peers = Arc::new(HashSet::from([reserved_peer]));
+
+        let blockhash = bank_forks.read().unwrap().root_bank().last_blockhash();
+        let transactions: Vec<_> = (0..3)
+            .map(|_| transfer(&mint_keypair, &Pubkey::new_unique(), 1, blockhash))
+            .collect();
+        let mut packet_batches = to_packet_batches(&transactions, 3);
+        let remote_pubkeys = [Some(reserved_peer), Some(Pubkey::new_unique()), None];
+        for (mut packet, remote_pubkey) in packet_batches[0].iter_mut().zip(remote_pubkeys) {
+            if let Some(remote_pubkey) = remote_pubkey {
+                packet.meta_mut().set_remote_pubkey(remote_pubkey);
+            }
+        }
+        sender.send(Arc::new(packet_batches)).unwrap();
+
+        let stats = receive_and_buffer
+            .receive_and_buffer_packets(&mut container, &BufferedPacketsDecision::Hold)
+            .unwrap();
+
+        // Only the transaction of the reserved peer is in the reserved lane.
+        assert_eq!(stats.num_buffered, 3);
+        assert_eq!(container.reserved_queue_size(), 1);
+        let id = container.pop_reserved().unwrap();
+        assert_eq!(
+            container
+                .get_mut_transaction_state(id.id)
+                .unwrap()
+                .reserved_for(),
+            Some(&reserved_peer)
+        );
+        verify_container(&mut container, 2);
+    }
+
     #[test]
     fn test_receive_and_buffer_filters_fee_payer() {
         let (sender, receiver) = bounded(1024);
@@ -1139,6 +1374,7 @@ mod tests {
             num_dropped_on_fee_payer,
             num_dropped_on_filter_key: _,
             num_dropped_on_capacity,
+            num_dropped_bundles: _,
             num_buffered,
             receive_time_us: _,
             buffer_time_us: _,
@@ -1219,6 +1455,7 @@ mod tests {
             num_dropped_on_fee_payer,
             num_dropped_on_filter_key: _,
             num_dropped_on_capacity,
+            num_dropped_bundles: _,
             num_buffered,
             receive_time_us: _,
             buffer_time_us: _,
@@ -1239,4 +1476,47 @@ mod tests {
 
         verify_container(&mut container, 0);
     }
+
+    #[test]
+    fn test_receive_bundle_invalid_signature() {
+        let (_sender, receiver) = bounded(1024);
+        let (bank_forks, mint_keypair) = test_bank_forks();
+        let (mut receive_and_buffer, mut container) =
+            setup_transaction_view_receive_and_buffer(receiver, bank_forks.clone());
+
+        let bank = bank_forks.read().unwrap().root_bank();
+        let transactions: Vec<_> = (0..4)
+            .map(|_| {
+                transfer(
+                    &mint_keypair,
+                    &Pubkey::new_unique(),
+                    1,
+                    bank.last_blockhash(),
+                )
+            })
+            .collect();
+        let valid_bundle = to_packet_batches(&transactions[..2], 2).remove(0);
+        let mut invalid_transactions = transactions[2..].to_vec();
+        invalid_transactions[1].signatures[0] = Signature::default();
+        let invalid_bundle = to_packet_batches(&invalid_transactions, 2).remove(0);
+
+        let ReceivingStats {
+            num_received,
+            num_dropped_bundles,
+            num_buffered,
+            ..
+        } = receive_and_buffer.handle_bundle_batch(
+            &mut container,
+            &BufferedPacketsDecision::Hold,
+            &bank,
+            &bank,
+            Arc::new(vec![valid_bundle, invalid_bundle]),
+        );
+
+        assert_eq!(num_received, 4);
+        assert_eq!(num_dropped_bundles, 1);
+        assert_eq!(num_buffered, 2);
+        assert_eq!(container.pop_bundle().map(|ids| ids.len()), Some(2));
+        assert!(container.pop_bundle().is_none());
+    }
 }
@@ -58,4 +58,7 @@ pub(crate) struct SchedulingSummary {
     pub num_unschedulable_conflicts: usize,
     /// Number of transactions that were skipped due to thread capacity.
     pub num_unschedulable_threads: usize,
+    /// Number of transactions deferred because write lock pricing put their
+    /// effective priority below other transactions.
+    pub num_repriced: usize,
 }
@@ -1,7 +1,9 @@
 use {
     super::{
-        in_flight_tracker::InFlightTracker, scheduler_error::SchedulerError,
-        transaction_state_container::StateContainer,
+        block_space_reservation::BlockSpaceReservations, in_flight_tracker::InFlightTracker,
+        scheduler_error::SchedulerError, transaction_priority_id::TransactionPriorityId,
+        transaction_state::TransactionState, transaction_state_container::StateContainer,
+        write_lock_pricing::WriteLockPricing,
     },
     crate::banking_stage::{
         consumer::ENTRY_OVERHEAD_BYTES,
@@ -10,7 +12,7 @@ use {
         },
     },
     agave_scheduling_utils::thread_aware_account_locks::{
-        MAX_THREADS, ThreadAwareAccountLocks, ThreadId, ThreadSet,
+        MAX_THREADS, ThreadAwareAccountLocks, ThreadId, ThreadSet, TryLockError,
     },
     crossbeam_channel::{Receiver, Sender, TryRecvError},
     itertools::izip,
@@ -157,6 +159,52 @@ pub fn select_thread<Tx>(
         .unwrap()
 }
 
+/// Attempts to lock the accounts of the transaction on one of the
+/// `schedulable_threads`, taking the transaction out of its state on success.
+pub fn try_schedule_transaction<Tx: TransactionWithMeta>(
+    transaction_state: &mut TransactionState<Tx>,
+    account_locks: &mut ThreadAwareAccountLocks,
+    schedulable_threads: ThreadSet,
+    thread_selector: impl Fn(ThreadSet) -> ThreadId,
+) -> Result<TransactionSchedulingInfo<Tx>, TransactionSchedulingError> {
+    // Schedule the transaction if it can be.
+    let transaction = transaction_state.transaction();
+    let account_keys = transaction.account_keys();
+    let write_account_locks = account_keys
+        .iter()
+        .enumerate()
+        .filter_map(|(index, key)| transaction.is_writable(index).then_some(key));
+    let read_account_locks = account_keys
+        .iter()
+        .enumerate()
+        .filter_map(|(index, key)| (!transaction.is_writable(index)).then_some(key));
+
+    let thread_id = match account_locks.try_lock_accounts(
+        write_account_locks,
+        read_account_locks,
+        schedulable_threads,
+        thread_selector,
+    ) {
+        Ok(thread_id) => thread_id,
+        Err(TryLockError::MultipleConflicts) => {
+            return Err(TransactionSchedulingError::UnschedulableConflicts);
+        }
+        Err(TryLockError::ThreadNotAllowed) => {
+            return Err(TransactionSchedulingError::UnschedulableThread);
+        }
+    };
+
+    let (transaction, max_age) = transaction_state.take_transaction_for_scheduling();
+    let cost = transaction_state.cost();
+
+    Ok(TransactionSchedulingInfo {
+        thread_id,
+        transaction,
+        max_age,
+        cost,
+    })
+}
+
 /// Common scheduler communication structure.
 pub(crate) struct SchedulingCommon<Tx> {
     pub(crate) consume_work_senders: Vec<Sender<ConsumeWork<Tx>>>,
@@ -164,6 +212,10 @@ pub(crate) struct SchedulingCommon<Tx> {
     pub(crate) in_flight_tracker: InFlightTracker,
     pub(crate) account_locks: ThreadAwareAccountLocks,
     pub(crate) batches: Batches<Tx>,
+    /// Set when block production prices write locks on contended accounts.
+    pub(crate) write_lock_pricing: Option<WriteLockPricing>,
+    /// Set when block space is reserved for named peers.
+    pub(crate) block_space_reservations: Option<BlockSpaceReservations>,
 }
 
 impl<Tx> SchedulingCommon<Tx> {
@@ -187,12 +239,22 @@ impl<Tx> SchedulingCommon<Tx> {
             finished_consume_work_receiver,
             in_flight_tracker: InFlightTracker::new(num_threads),
             account_locks: ThreadAwareAccountLocks::new(num_threads),
+            write_lock_pricing: None,
+            block_space_reservations: None,
         }
     }
 
     /// Send a batch of transactions to the given thread's `ConsumeWork` channel.
     /// Returns the number of transactions sent.
     pub fn send_batch(&mut self, thread_index: usize) -> Result<usize, SchedulerError> {
+        self.send_batch_internal(thread_index, false)
+    }
+
+    fn send_batch_internal(
+        &mut self,
+        thread_index: usize,
+        is_bundle: bool,
+    ) -> Result<usize, SchedulerError> {
         if self.batches.ids[thread_index].is_empty() {
             return Ok(0);
         }
@@ -209,6 +271,7 @@ impl<Tx> SchedulingCommon<Tx> {
             ids,
             transactions,
             max_ages,
+            is_bundle,
         };
         self.consume_work_senders[thread_index]
             .send(work)
@@ -227,6 +290,227 @@ impl<Tx> SchedulingCommon<Tx> {
 }
 
 impl<Tx: TransactionWithMeta> SchedulingCommon<Tx> {
+    /// Pop the next transaction to schedule from the container, in effective
+    /// priority order if write locks are priced.
+    pub(crate) fn pop_next<S: StateContainer<Tx>>(
+        &mut self,
+        container: &mut S,
+    ) -> Option<TransactionPriorityId> {
+        match self.write_lock_pricing.as_mut() {
+            Some(write_lock_pricing) => write_lock_pricing.pop(container),
+            None => container.pop(),
+        }
+    }
+
+    /// Return the transactions deferred by write lock pricing to the
+    /// container. Returns the number of transactions deferred in this pass.
+    pub(crate) fn finish_pricing<S: StateContainer<Tx>>(&mut self, container: &mut S) -> usize {
+        self.write_lock_pricing
+            .as_mut()
+            .map(|write_lock_pricing| write_lock_pricing.finish(container))
+            .unwrap_or_default()
+    }
+
+    /// Schedule bundles from the `container`'s bundle queue, in priority order.
+    /// All accounts of a bundle are locked on a single thread, and the bundle
+    /// is sent to that thread on its own.
+    /// Bundles that cannot be scheduled are pushed back into the queue.
+    /// Returns the number of transactions sent.
+    ///
+    /// Batches must be empty when this is called.
+    pub fn schedule_bundles<S: StateContainer<Tx>>(
+        &mut self,
+        container: &mut S,
+        schedulable_threads: ThreadSet,
+        budget: &mut u64,
+    ) -> Result<usize, SchedulerError> {
+        let mut num_sent = 0;
+        let mut unschedulable_bundles = vec![];
+
+        while *budget > 0 {
+            let Some(ids) = container.pop_bundle() else {
+                break;
+            };
+
+            let mut write_account_locks = vec![];
+            let mut read_account_locks = vec![];
+            for id in &ids {
+                let transaction = container
+                    .get_transaction(*id)
+                    .expect("bundled transaction must exist");
+                let account_keys = transaction.account_keys();
+                for (index, key) in account_keys.iter().enumerate() {
+                    if transaction.is_writable(index) {
+                        write_account_locks.push(*key);
+                    } else {
+                        read_account_locks.push(*key);
+                    }
+                }
+            }
+
+            let Ok(thread_id) = self.account_locks.try_lock_accounts(
+                write_account_locks.iter(),
+                read_account_locks.iter(),
+                schedulable_threads,
+                |thread_set| {
+                    select_thread(
+                        thread_set,
+                        self.batches.total_cus(),
+                        self.in_flight_tracker.cus_in_flight_per_thread(),
+                        self.batches.transactions(),
+                        self.in_flight_tracker.num_in_flight_per_thread(),
+                    )
+                },
+            ) else {
+                unschedulable_bundles.push(ids);
+                continue;
+            };
+
+            for id in ids {
+                let transaction_state = container
+                    .get_mut_transaction_state(id)
+                    .expect("bundled transaction must exist");
+                let (transaction, max_age) = transaction_state.take_transaction_for_scheduling();
+                let cost = transaction_state.cost();
+                let transaction_bytes = transaction.serialized_size() as u64;
+                self.batches.add_transaction_to_batch(
+                    thread_id,
+                    id,
+                    transaction,
+                    max_age,
+                    cost,
+                    transaction_bytes,
+                );
+                *budget = budget.saturating_sub(cost);
+            }
+            num_sent += self.send_batch_internal(thread_id, true)?;
+        }
+
+        for ids in unschedulable_bundles {
+            container.push_bundle(ids);
+        }
+
+        Ok(num_sent)
+    }
+
+    /// Schedule transactions from the `container`'s reserved lane, in priority
+    /// order, while they fit in the block space reservation of their peer.
+    /// Transactions that do not fit move to the regular queue, and those that
+    /// cannot be scheduled are pushed back into the reserved lane.
+    /// Returns the number of transactions sent.
+    ///
+    /// Batches must be empty when this is called.
+    pub fn schedule_reserved<S: StateContainer<Tx>>(
+        &mut self,
+        container: &mut S,
+        schedulable_threads: ThreadSet,
+        budget: &mut u64,
+    ) -> Result<usize, SchedulerError> {
+        let Some(mut reservations) = self.block_space_reservations.take() else {
+            return Ok(0);
+        };
+        let result = self.schedule_reserved_within(
+            &mut reservations,
+            container,
+            schedulable_threads,
+            budget,
+        );
+        self.block_space_reservations = Some(reservations);
+        result
+    }
+
+    fn schedule_reserved_within<S: StateContainer<Tx>>(
+        &mut self,
+        reservations: &mut BlockSpaceReservations,
+        container: &mut S,
+        schedulable_threads: ThreadSet,
+        budget: &mut u64,
+    ) -> Result<usize, SchedulerError> {
+        let mut num_sent = 0;
+        let mut unschedulables = vec![];
+
+        while *budget > 0 {
+            let Some(id) = container.pop_reserved() else {
+                break;
+            };
+            let Some(transaction_state) = container.get_mut_transaction_state(id.id) else {
+                panic!("transaction state must exist")
+            };
+            let peer = *transaction_state
+                .reserved_for()
+                .expect("reserved transaction must have a peer");
+
+            if !reservations.fits(&peer, transaction_state.cost()) {
+                reservations.record_overflowed(&peer);
+                transaction_state.set_reserved_for(None);
+                container.push_ids_into_queue(std::iter::once(id));
+                continue;
+            }
+
+            let Ok(TransactionSchedulingInfo {
+                thread_id,
+                transaction,
+                max_age,
+                cost,
+            }) = try_schedule_transaction(
+                transaction_state,
+                &mut self.account_locks,
+                schedulable_threads,
+                |thread_set| {
+                    select_thread(
+                        thread_set,
+                        self.batches.total_cus(),
+                        self.in_flight_tracker.cus_in_flight_per_thread(),
+                        self.batches.transactions(),
+                        self.in_flight_tracker.num_in_flight_per_thread(),
+                    )
+                },
+            )
+            else {
+                unschedulables.push(id);
+                continue;
+            };
+
+            reservations.record_scheduled(&peer, cost);
+            let transaction_bytes = transaction.serialized_size() as u64;
+            self.batches.add_transaction_to_batch(
+                thread_id,
+                id.id,
+                transaction,
+                max_age,
+                cost,
+                transaction_bytes,
+            );
+            *budget = budget.saturating_sub(cost);
+
+            if self.batches.transactions()[thread_id].len()
+                >= self.batches.target_num_transactions_per_batch
+            {
+                num_sent += self.send_batch(thread_id)?;
+            }
+        }
+        num_sent += self.send_batches()?;
+
+        container.push_ids_into_queue(unschedulables.into_iter());
+
+        Ok(num_sent)
+    }
+
+    /// The part of `budget` left to transactions outside the reserved lane,
+    /// once the unused block space reservations are set aside.
+    pub(crate) fn unreserved_budget(&self, budget: u64) -> u64 {
+        match self.block_space_reservations.as_ref() {
+            Some(reservations) => reservations.unreserved_budget(
+                budget,
+                self.in_flight_tracker
+                    .cus_in_flight_per_thread()
+                    .iter()
+                    .sum(),
+            ),
+            None => budget,
+        }
+    }
+
     /// Receive completed batches of transactions.
     /// Returns `Ok((num_transactions, num_retryable))` if a batch was received, `Ok((0, 0))` if no batch was received.
     pub fn try_receive_completed(
@@ -241,6 +525,7 @@ impl<Tx: TransactionWithMeta> SchedulingCommon<Tx> {
                         ids,
                         transactions,
                         max_ages: _,
+                        is_bundle,
                     },
                 retryable_indexes,
             }) => {
@@ -250,6 +535,22 @@ impl<Tx: TransactionWithMeta> SchedulingCommon<Tx> {
                 // Free the locks
                 self.complete_batch(batch_id, &transactions);
 
+                // Bundles are retried as a whole, only if no transaction was
+                // committed. Otherwise the bundle is dropped.
+                if is_bundle {
+                    if num_retryable == num_transactions {
+                        let immediately_retryable = retryable_indexes
+                            .iter()
+                            .all(|index| index.immediately_retryable);
+                        container.retry_bundle(ids, transactions, immediately_retryable);
+                    } else {
+                        for id in ids {
+                            container.remove_by_id(id);
+                        }
+                    }
+                    return Ok((num_transactions, num_retryable));
+                }
+
                 // Assumption - retryable indexes are in order (sorted by workers).
                 let mut retryable_iter = retryable_indexes.iter().peekable();
                 for (index, (id, transaction)) in izip!(ids, transactions).enumerate() {
@@ -311,7 +612,10 @@ mod tests {
         super::*,
         crate::banking_stage::{
             consumer::RetryableIndex,
-            transaction_scheduler::transaction_state_container::TransactionStateContainer,
+            transaction_scheduler::{
+                block_space_reservation::BlockSpaceReservation,
+                transaction_state_container::TransactionStateContainer,
+            },
         },
         crossbeam_channel::bounded,
         solana_hash::Hash,
@@ -599,4 +903,167 @@ mod tests {
         // This should panic because the retryable indexes are not in order.
         let _ = common.try_receive_completed(&mut container);
     }
+
+    fn add_bundle_to_container(
+        container: &mut TransactionStateContainer<RuntimeTransaction<SanitizedTransaction>>,
+        payer: &Keypair,
+        count: usize,
+    ) {
+        container.insert_new_bundle((0..count).map(|_| {
+            let transaction = RuntimeTransaction::from_transaction_for_tests(
+                system_transaction::transfer(payer, &Pubkey::new_unique(), 1, Hash::default()),
+            );
+            (transaction, MaxAge::MAX, 1, DUMMY_COST)
+        }));
+    }
+
+    #[test]
+    fn test_schedule_bundles() {
+        let mut container = TransactionStateContainer::with_capacity(1024);
+        let payer = Keypair::new();
+        add_bundle_to_container(&mut container, &payer, 2);
+
+        let (work_senders, work_receivers): (Vec<Sender<_>>, Vec<Receiver<_>>) =
+            (0..NUM_WORKERS).map(|_| bounded(1024)).unzip();
+        let (_finished_work_sender, finished_work_receiver) = bounded(1024);
+        let mut common = SchedulingCommon::new(work_senders, finished_work_receiver, 10);
+
+        // Conflicting transactions of a bundle are sent together, to one thread.
+        let mut budget = u64::MAX;
+        let num_sent = common
+            .schedule_bundles(&mut container, ThreadSet::any(NUM_WORKERS), &mut budget)
+            .unwrap();
+        assert_eq!(num_sent, 2);
+        assert_eq!(budget, u64::MAX - 2 * DUMMY_COST);
+        let (thread_id, work) = work_receivers
+            .iter()
+            .enumerate()
+            .find_map(|(thread_id, receiver)| Some((thread_id, receiver.try_recv().ok()?)))
+            .unwrap();
+        assert!(work.is_bundle);
+        assert_eq!(work.ids.len(), 2);
+        assert!(work_receivers.iter().all(|receiver| receiver.is_empty()));
+
+        // A conflicting bundle is pushed back if its thread is not schedulable.
+        add_bundle_to_container(&mut container, &payer, 1);
+        let num_sent = common
+            .schedule_bundles(
+                &mut container,
+                ThreadSet::any(NUM_WORKERS) - ThreadSet::only(thread_id),
+                &mut budget,
+            )
+            .unwrap();
+        assert_eq!(num_sent, 0);
+        assert_eq!(container.bundle_queue_size(), 1);
+    }
+
+    #[test]
+    fn test_schedule_reserved() {
+        let mut container = TransactionStateContainer::with_capacity(1024);
+        let peer = Pubkey::new_unique();
+        for priority in [3, 2, 1] {
+            container.insert_new_reserved_transaction(
+                simple_transaction(),
+                MaxAge::MAX,
+                priority,
+                10,
+                peer,
+            );
+        }
+        add_transactions_to_container(&mut container, 1);
+
+        let (work_senders, work_receivers): (Vec<Sender<_>>, Vec<Receiver<_>>) =
+            (0..NUM_WORKERS).map(|_| bounded(1024)).unzip();
+        let (_finished_work_sender, finished_work_receiver) = bounded(1024);
+        let mut common = SchedulingCommon::new(work_senders, finished_work_receiver, 10);
+
+        // Without reservations, the reserved lane is not scheduled.
+        let mut budget = u64::MAX;
+        let num_sent = common
+            .schedule_reserved(&mut container, ThreadSet::any(NUM_WORKERS), &mut budget)
+            .unwrap();
+        assert_eq!(num_sent, 0);
+        assert_eq!(common.unreserved_budget(100), 100);
+
+        let mut reservations = BlockSpaceReservations::new(&[BlockSpaceReservation {
+            peer,
+            share_percent: 20,
+        }]);
+        reservations.set_block_limit(100);
+        common.block_space_reservations = Some(reservations);
+
+        // Two transactions fit in the reservation, the third overflows into
+        // the regular queue.
+        let num_sent = common
+            .schedule_reserved(&mut container, ThreadSet::any(NUM_WORKERS), &mut budget)
+            .unwrap();
+        assert_eq!(num_sent, 2);
+        assert_eq!(budget, u64::MAX - 20);
+        assert_eq!(
+            work_receivers
+                .iter()
+                .map(|receiver| receiver
+                    .try_iter()
+                    .map(|work| work.ids.len())
+                    .sum::<usize>())
+                .sum::<usize>(),
+            2
+        );
+        assert_eq!(container.reserved_queue_size(), 0);
+        assert_eq!(container.queue_size(), 2);
+        assert_eq!(container.pop().unwrap().priority, 1);
+
+        // The reservation is used up, so nothing is held back anymore.
+        assert_eq!(common.unreserved_budget(u64::MAX), 80);
+    }
+
+    #[test]
+    fn test_receive_completed_bundle() {
+        let mut container = TransactionStateContainer::with_capacity(1024);
+        add_bundle_to_container(&mut container, &Keypair::new(), 2);
+
+        let (work_senders, work_receivers): (Vec<Sender<_>>, Vec<Receiver<_>>) =
+            (0..1).map(|_| bounded(1024)).unzip();
+        let (finished_work_sender, finished_work_receiver) = bounded(1024);
+        let mut common = SchedulingCommon::new(work_senders, finished_work_receiver, 10);
+
+        // Bundles with every transaction retryable are retried as a whole.
+        let mut budget = u64::MAX;
+        common
+            .schedule_bundles(&mut container, ThreadSet::any(1), &mut budget)
+            .unwrap();
+        let work = work_receivers[0].try_recv().unwrap();
+        let ids = work.ids.clone();
+        finished_work_sender
+            .send(FinishedConsumeWork {
+                work,
+                retryable_indexes: vec![RetryableIndex::new(0, true), RetryableIndex::new(1, true)],
+            })
+            .unwrap();
+        assert_eq!(
+            common.try_receive_completed(&mut container).unwrap(),
+            (2, 2)
+        );
+        assert_eq!(container.buffer_size(), 2);
+        assert_eq!(container.bundle_queue_size(), 1);
+
+        // Otherwise the bundle is dropped.
+        common
+            .schedule_bundles(&mut container, ThreadSet::any(1), &mut budget)
+            .unwrap();
+        let work = work_receivers[0].try_recv().unwrap();
+        assert_eq!(work.ids, ids);
+        finished_work_sender
+            .send(FinishedConsumeWork {
+                work,
+                retryable_indexes: vec![RetryableIndex::new(1, true)],
+            })
+            .unwrap();
+        assert_eq!(
+            common.try_receive_completed(&mut container).unwrap(),
+            (2, 1)
+        );
+        assert_eq!(container.buffer_size(), 0);
+        assert_eq!(container.bundle_queue_size(), 0);
+    }
 }
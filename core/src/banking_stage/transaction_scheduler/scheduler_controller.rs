@@ -14,8 +14,11 @@ use {
             consume_worker::ConsumeWorkerMetrics,
             decision_maker::{BufferedPacketsDecision, DecisionMaker},
             transaction_scheduler::{
-                receive_and_buffer::ReceivingStats, transaction_priority_id::TransactionPriorityId,
+                block_space_reservation::{BlockSpaceReservation, BlockSpaceReservations},
+                receive_and_buffer::ReceivingStats,
+                transaction_priority_id::TransactionPriorityId,
                 transaction_state_container::StateContainer,
+                write_lock_pricing::{WriteLockPricing, WriteLockPricingConfig},
             },
         },
         validator::SchedulerPacing,
@@ -48,6 +51,10 @@ const DESATURATION_BUFFER_PCT: u8 = 95;
 #[derive(Clone)]
 pub struct SchedulerConfig {
     pub scheduler_pacing: SchedulerPacing,
+    /// Price write locks on contended accounts, if set.
+    pub write_lock_pricing: Option<WriteLockPricingConfig>,
+    /// Block space reserved for named peers in every leader slot.
+    pub block_space_reservations: Vec<BlockSpaceReservation>,
 }
 
 impl Default for SchedulerConfig {
@@ -56,6 +63,8 @@ impl Default for SchedulerConfig {
             scheduler_pacing: SchedulerPacing::FillTimeMillis(
                 DEFAULT_SCHEDULER_PACING_FILL_TIME_MILLIS,
             ),
+            write_lock_pricing: None,
+            block_space_reservations: Vec::new(),
         }
     }
 }
@@ -161,13 +170,22 @@ where
         decision_maker: DecisionMaker,
         receive_and_buffer: R,
         sharable_banks: SharableBanks,
-        scheduler: S,
+        mut scheduler: S,
         worker_metrics: Vec<Arc<ConsumeWorkerMetrics>>,
         priority_floor: Arc<SchedulerPriorityFloor>,
     ) -> Self {
         priority_floor.clear();
         let container_capacity = TOTAL_BUFFERED_PACKETS;
         let saturation_state = SaturationState::new(priority_floor, container_capacity);
+        if let Some(write_lock_pricing) = config.write_lock_pricing {
+            scheduler.scheduling_common_mut().write_lock_pricing =
+                Some(WriteLockPricing::new(write_lock_pricing));
+        }
+        if !config.block_space_reservations.is_empty() {
+            scheduler.scheduling_common_mut().block_space_reservations = Some(
+                BlockSpaceReservations::new(&config.block_space_reservations),
+            );
+        }
         Self {
             exit,
             config,
@@ -292,7 +310,23 @@ where
         now: &Instant,
     ) -> Result<usize, SchedulerError> {
         let scheduled = match decision {
-            BufferedPacketsDecision::Consume(_bank) => {
+            BufferedPacketsDecision::Consume(bank) => {
+                if let Some(write_lock_pricing) = self
+                    .scheduler
+                    .scheduling_common_mut()
+                    .write_lock_pricing
+                    .as_mut()
+                {
+                    write_lock_pricing.update(bank, *now);
+                }
+                if let Some(block_space_reservations) = self
+                    .scheduler
+                    .scheduling_common_mut()
+                    .block_space_reservations
+                    .as_mut()
+                {
+                    block_space_reservations.update(bank);
+                }
                 let scheduling_budget = cost_pacer
                     .expect("cost pacer must be set for Consume")
                     .scheduling_budget(now);
@@ -307,6 +341,7 @@ where
                         scheduling_summary.num_unschedulable_conflicts;
                     count_metrics.num_unschedulable_threads +=
                         scheduling_summary.num_unschedulable_threads;
+                    count_metrics.num_repriced += scheduling_summary.num_repriced;
                 });
 
                 self.timing_metrics.update(|timing_metrics| {
@@ -356,10 +391,20 @@ where
     /// This only clears pending transactions, and does **not** clear in-flight transactions.
     fn clear_container(&mut self) {
         let mut num_dropped_on_clear = Saturating::<usize>(0);
-        while let Some(id) = self.container.pop() {
+        while let Some(id) = self
+            .container
+            .pop()
+            .or_else(|| self.container.pop_reserved())
+        {
             self.container.remove_by_id(id.id);
             num_dropped_on_clear += 1;
         }
+        while let Some(ids) = self.container.pop_bundle() {
+            num_dropped_on_clear += ids.len();
+            for id in ids {
+                self.container.remove_by_id(id);
+            }
+        }
 
         self.count_metrics.update(|count_metrics| {
             count_metrics.num_dropped_on_clear += num_dropped_on_clear;
@@ -469,6 +514,7 @@ where
                 num_dropped_on_fee_payer,
                 num_dropped_on_filter_key,
                 num_dropped_on_capacity,
+                num_dropped_bundles,
                 num_buffered,
                 receive_time_us: _,
                 buffer_time_us: _,
@@ -486,6 +532,7 @@ where
             count_metrics.num_dropped_on_receive_fee_payer += *num_dropped_on_fee_payer;
             count_metrics.num_dropped_on_filter_key += *num_dropped_on_filter_key;
             count_metrics.num_dropped_on_capacity += *num_dropped_on_capacity;
+            count_metrics.num_dropped_bundles += *num_dropped_bundles;
             count_metrics.num_buffered += *num_buffered;
         });
 
@@ -537,7 +584,7 @@ mod tests {
             transaction_scheduler::greedy_scheduler::{GreedyScheduler, GreedySchedulerConfig},
         },
         agave_banking_stage_ingress_types::{BankingPacketBatch, BankingPacketReceiver},
-        crossbeam_channel::{Receiver, Sender, bounded},
+        crossbeam_channel::{Receiver, Sender, bounded, never},
         itertools::Itertools,
         solana_compute_budget_interface::ComputeBudgetInstruction,
         solana_fee_calculator::FeeRateGovernor,
@@ -579,8 +626,10 @@ mod tests {
     ) -> TransactionViewReceiveAndBuffer {
         TransactionViewReceiveAndBuffer {
             receiver,
+            bundle_receiver: never(),
             sharable_banks: bank_forks.read().unwrap().sharable_banks(),
             filter_keys: Arc::default(),
+            reserved_peers: Arc::default(),
         }
     }
 
@@ -736,6 +785,7 @@ mod tests {
                     ids: vec![],
                     transactions: vec![],
                     max_ages: vec![],
+                    is_bundle: false,
                 },
                 retryable_indexes: vec![],
             })
@@ -57,6 +57,8 @@ pub struct SchedulerCountMetricsInner {
     pub num_unschedulable_conflicts: Saturating<usize>,
     /// Number of transactions that were unschedulable due to thread capacity.
     pub num_unschedulable_threads: Saturating<usize>,
+    /// Number of transactions deferred by write lock pricing.
+    pub num_repriced: Saturating<usize>,
     /// Number of transactions dropped due to account key filtering.
     pub num_dropped_on_filter_key: Saturating<usize>,
     /// Number of completed transactions received from workers.
@@ -85,6 +87,8 @@ pub struct SchedulerCountMetricsInner {
     pub num_dropped_on_clean: Saturating<usize>,
     /// Number of transactions that were dropped due to exceeded capacity.
     pub num_dropped_on_capacity: Saturating<usize>,
+    /// Number of bundles that were dropped on receive checks or capacity.
+    pub num_dropped_bundles: Saturating<usize>,
     /// Min prioritization fees in the transaction container
     pub min_prioritization_fees: u64,
     /// Max prioritization fees in the transaction container
@@ -125,6 +129,7 @@ impl SchedulerCountMetricsInner {
             num_scheduled: Saturating(num_scheduled),
             num_unschedulable_conflicts: Saturating(num_unschedulable_conflicts),
             num_unschedulable_threads: Saturating(num_unschedulable_threads),
+            num_repriced: Saturating(num_repriced),
             num_dropped_on_filter_key: Saturating(num_dropped_on_filter_key),
             num_finished: Saturating(num_finished),
             num_retryable: Saturating(num_retryable),
@@ -140,6 +145,7 @@ impl SchedulerCountMetricsInner {
             num_dropped_on_clear: Saturating(num_dropped_on_clear),
             num_dropped_on_clean: Saturating(num_dropped_on_clean),
             num_dropped_on_capacity: Saturating(num_dropped_on_capacity),
+            num_dropped_bundles: Saturating(num_dropped_bundles),
             min_prioritization_fees: _min_prioritization_fees,
             max_prioritization_fees: _max_prioritization_fees,
         } = self;
@@ -150,6 +156,7 @@ impl SchedulerCountMetricsInner {
             ("num_scheduled", num_scheduled, i64),
             ("num_unschedulable_conflicts", num_unschedulable_conflicts, i64),
             ("num_unschedulable_threads", num_unschedulable_threads, i64),
+            ("num_repriced", num_repriced, i64),
             ("num_dropped_on_filter_key", num_dropped_on_filter_key, i64),
             ("num_finished", num_finished, i64),
             ("num_retryable", num_retryable, i64),
@@ -187,6 +194,7 @@ impl SchedulerCountMetricsInner {
                 i64
             ),
             ("num_dropped_on_capacity", num_dropped_on_capacity, i64),
+            ("num_dropped_bundles", num_dropped_bundles, i64),
             ("min_priority", self.get_min_priority(), i64),
             ("max_priority", self.get_max_priority(), i64),
         );
@@ -202,6 +210,7 @@ impl SchedulerCountMetricsInner {
             || self.num_scheduled != Saturating(0)
             || self.num_unschedulable_conflicts != Saturating(0)
             || self.num_unschedulable_threads != Saturating(0)
+            || self.num_repriced != Saturating(0)
             || self.num_dropped_on_filter_key != Saturating(0)
             || self.num_finished != Saturating(0)
             || self.num_retryable != Saturating(0)
@@ -213,6 +222,7 @@ impl SchedulerCountMetricsInner {
         self.num_scheduled = Saturating(0);
         self.num_unschedulable_conflicts = Saturating(0);
         self.num_unschedulable_threads = Saturating(0);
+        self.num_repriced = Saturating(0);
         self.num_dropped_on_filter_key = Saturating(0);
         self.num_finished = Saturating(0);
         self.num_retryable = Saturating(0);
@@ -226,6 +236,7 @@ impl SchedulerCountMetricsInner {
         self.num_dropped_on_clear = Saturating(0);
         self.num_dropped_on_clean = Saturating(0);
         self.num_dropped_on_capacity = Saturating(0);
+        self.num_dropped_bundles = Saturating(0);
         self.min_prioritization_fees = u64::MAX;
         self.max_prioritization_fees = 0;
     }
@@ -1,4 +1,4 @@
-use crate::banking_stage::scheduler_messages::MaxAge;
+use {crate::banking_stage::scheduler_messages::MaxAge, solana_pubkey::Pubkey};
 
 /// TransactionState is used to track the state of a transaction in the transaction scheduler
 /// and banking stage as a whole.
@@ -20,6 +20,9 @@ pub(crate) struct TransactionState<Tx> {
     priority: u64,
     /// Estimated cost of the transaction.
     cost: u64,
+    /// The peer whose block space reservation the transaction is queued
+    /// under, if any.
+    reserved_for: Option<Pubkey>,
 }
 
 impl<Tx> TransactionState<Tx> {
@@ -30,6 +33,7 @@ impl<Tx> TransactionState<Tx> {
             max_age,
             priority,
             cost,
+            reserved_for: None,
         }
     }
 
@@ -45,6 +49,18 @@ impl<Tx> TransactionState<Tx> {
         self.cost
     }
 
+    /// Return the peer whose reserved lane the transaction is queued in.
+    pub(crate) fn reserved_for(&self) -> Option<&Pubkey> {
+        self.reserved_for.as_ref()
+    }
+
+    /// Move the transaction into the reserved lane of `peer`, or into the
+    /// regular queue if `None`. Must only be called while the transaction is
+    /// not queued.
+    pub(crate) fn set_reserved_for(&mut self, peer: Option<Pubkey>) {
+        self.reserved_for = peer;
+    }
+
     /// Intended to be called when a transaction is scheduled. This method
     /// takes ownership of the transaction from the state.
     ///
@@ -7,7 +7,12 @@ use {
     solana_runtime_transaction::{
         runtime_transaction::RuntimeTransaction, transaction_with_meta::TransactionWithMeta,
     },
-    std::{collections::BTreeSet, iter::Rev, ops::Bound, sync::Arc},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        iter::Rev,
+        ops::Bound,
+        sync::Arc,
+    },
 };
 
 /// This structure will hold `TransactionState` for the entirety of a
@@ -35,11 +40,23 @@ use {
 ///
 /// The container maintains a fixed capacity. If the queue is full when pushing
 /// a new transaction, the lowest priority transaction will be dropped.
+///
+/// Bundles are kept in a separate queue, keyed by the priority of the bundle
+/// and the id of its first transaction. The transactions of a bundle are in
+/// the map, but never in the transaction priority queue.
+///
+/// Transactions queued under a peer's block space reservation are kept in a
+/// separate reserved lane instead of the priority queue, so the scheduler can
+/// serve them first. Which of the two queues a transaction is in follows its
+/// `TransactionState::reserved_for`.
 pub(crate) struct TransactionStateContainer<Tx: TransactionWithMeta> {
     capacity: usize,
     priority_queue: BTreeSet<TransactionPriorityId>,
+    reserved_queue: BTreeSet<TransactionPriorityId>,
     id_to_transaction_state: Slab<TransactionState<Tx>>,
     held_transactions: Vec<TransactionPriorityId>,
+    bundle_queue: BTreeMap<TransactionPriorityId, Vec<TransactionId>>,
+    held_bundles: Vec<Vec<TransactionId>>,
 }
 
 pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
@@ -50,12 +67,17 @@ pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
 
     fn buffer_size(&self) -> usize;
 
-    /// Returns true if the queue is empty.
+    /// Returns true if both the queue and the reserved lane are empty.
     fn is_empty(&self) -> bool;
 
     /// Get the top transaction id in the priority queue.
     fn pop(&mut self) -> Option<TransactionPriorityId>;
 
+    fn reserved_queue_size(&self) -> usize;
+
+    /// Get the top transaction id in the reserved lane.
+    fn pop_reserved(&mut self) -> Option<TransactionPriorityId>;
+
     /// Get mutable transaction state by id.
     fn get_mut_transaction_state(&mut self, id: TransactionId)
     -> Option<&mut TransactionState<Tx>>;
@@ -85,9 +107,11 @@ pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
         }
     }
 
-    /// Pushes transaction ids into the priority queue. If the queue if full,
+    /// Pushes transaction ids into the priority queue, or the reserved lane
+    /// for transactions queued under a reservation. If the queue if full,
     /// the lowest priority transactions will be dropped (removed from the
-    /// queue and map) **after** all ids have been pushed.
+    /// queue and map) **after** all ids have been pushed. Transactions in
+    /// the reserved lane are only dropped once the priority queue is empty.
     /// To avoid allocating, the caller should not push more than
     /// [`EXTRA_CAPACITY`] ids in a call.
     /// Returns the number of dropped transactions.
@@ -99,6 +123,41 @@ pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
     /// Hold the tarnsaction until the next flush (next slot).
     fn hold_transaction(&mut self, priority_id: TransactionPriorityId);
 
+    fn bundle_queue_size(&self) -> usize;
+
+    /// Pushes a bundle of transactions, already inserted into the map, into
+    /// the bundle queue. If the bundle queue is full, the lowest priority
+    /// bundle will be dropped (removed from the queue and map).
+    /// Returns the number of dropped bundles.
+    fn push_bundle(&mut self, ids: Vec<TransactionId>) -> usize;
+
+    /// Get the transaction ids of the top bundle in the bundle queue.
+    fn pop_bundle(&mut self) -> Option<Vec<TransactionId>>;
+
+    /// Retries a bundle - inserts its transactions back into map.
+    /// This transitions the transactions to `Unprocessed` state.
+    fn retry_bundle(
+        &mut self,
+        ids: Vec<TransactionId>,
+        transactions: Vec<Tx>,
+        immediately_retryable: bool,
+    ) {
+        for (id, transaction) in ids.iter().zip(transactions) {
+            self.get_mut_transaction_state(*id)
+                .expect("transaction must exist")
+                .retry_transaction(transaction);
+        }
+
+        if immediately_retryable {
+            self.push_bundle(ids);
+        } else {
+            self.hold_bundle(ids);
+        }
+    }
+
+    /// Hold the bundle until the next flush (next slot).
+    fn hold_bundle(&mut self, ids: Vec<TransactionId>);
+
     /// Remove transaction by id.
     fn remove_by_id(&mut self, id: TransactionId);
 
@@ -118,13 +177,21 @@ pub(crate) trait StateContainer<Tx: TransactionWithMeta> {
 // pushing a new transaction into the container to avoid reallocation.
 pub(crate) const EXTRA_CAPACITY: usize = 64;
 
+/// Maximum number of transactions in a bundle.
+pub(crate) const MAX_BUNDLE_LENGTH: usize = 5;
+/// Maximum number of bundles held by the container.
+const MAX_BUFFERED_BUNDLES: usize = 1024;
+
 impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<Tx> {
     fn with_capacity(capacity: usize) -> Self {
         Self {
             capacity,
             priority_queue: BTreeSet::new(),
+            reserved_queue: BTreeSet::new(),
             id_to_transaction_state: Slab::with_capacity(capacity + EXTRA_CAPACITY),
             held_transactions: Vec::with_capacity(capacity),
+            bundle_queue: BTreeMap::new(),
+            held_bundles: Vec::new(),
         }
     }
 
@@ -137,13 +204,21 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
     }
 
     fn is_empty(&self) -> bool {
-        self.priority_queue.is_empty()
+        self.priority_queue.is_empty() && self.reserved_queue.is_empty()
     }
 
     fn pop(&mut self) -> Option<TransactionPriorityId> {
         self.priority_queue.pop_last()
     }
 
+    fn reserved_queue_size(&self) -> usize {
+        self.reserved_queue.len()
+    }
+
+    fn pop_reserved(&mut self) -> Option<TransactionPriorityId> {
+        self.reserved_queue.pop_last()
+    }
+
     fn get_mut_transaction_state(
         &mut self,
         id: TransactionId,
@@ -162,7 +237,11 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
         priority_ids: impl Iterator<Item = TransactionPriorityId>,
     ) -> usize {
         for id in priority_ids {
-            self.priority_queue.insert(id);
+            if self.id_to_transaction_state[id.id].reserved_for().is_some() {
+                self.reserved_queue.insert(id);
+            } else {
+                self.priority_queue.insert(id);
+            }
         }
 
         // The number of items in the `id_to_transaction_state` map is
@@ -175,7 +254,14 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
             .saturating_sub(self.capacity);
 
         for _ in 0..num_dropped {
-            let priority_id = self.priority_queue.pop_first().expect("queue is not empty");
+            // Bundled and pending transactions are not in the queues.
+            let Some(priority_id) = self
+                .priority_queue
+                .pop_first()
+                .or_else(|| self.reserved_queue.pop_first())
+            else {
+                return num_dropped;
+            };
             self.id_to_transaction_state.remove(priority_id.id);
         }
 
@@ -186,18 +272,53 @@ impl<Tx: TransactionWithMeta> StateContainer<Tx> for TransactionStateContainer<T
         self.held_transactions.push(priority_id);
     }
 
+    fn bundle_queue_size(&self) -> usize {
+        self.bundle_queue.len()
+    }
+
+    fn push_bundle(&mut self, ids: Vec<TransactionId>) -> usize {
+        let priority_id = TransactionPriorityId::new(self.bundle_priority(&ids), ids[0]);
+        self.bundle_queue.insert(priority_id, ids);
+
+        let num_dropped = self.bundle_queue.len().saturating_sub(MAX_BUFFERED_BUNDLES);
+        for _ in 0..num_dropped {
+            let (_, ids) = self.bundle_queue.pop_first().expect("queue is not empty");
+            for id in ids {
+                self.id_to_transaction_state.remove(id);
+            }
+        }
+
+        num_dropped
+    }
+
+    fn pop_bundle(&mut self) -> Option<Vec<TransactionId>> {
+        self.bundle_queue.pop_last().map(|(_, ids)| ids)
+    }
+
+    fn hold_bundle(&mut self, ids: Vec<TransactionId>) {
+        self.held_bundles.push(ids);
+    }
+
     fn remove_by_id(&mut self, id: TransactionId) {
         let state = self.id_to_transaction_state.remove(id);
         // Remove from queue if present. May not be present if the transaction was already popped
         // (in-flight/scheduling).
-        self.priority_queue
-            .remove(&TransactionPriorityId::new(state.priority(), id));
+        let priority_id = TransactionPriorityId::new(state.priority(), id);
+        if state.reserved_for().is_some() {
+            self.reserved_queue.remove(&priority_id);
+        } else {
+            self.priority_queue.remove(&priority_id);
+        }
     }
 
     fn flush_held_transactions(&mut self) {
         let mut held_transactions = core::mem::take(&mut self.held_transactions);
         self.push_ids_into_queue(held_transactions.drain(..));
         core::mem::swap(&mut self.held_transactions, &mut held_transactions);
+
+        for ids in core::mem::take(&mut self.held_bundles) {
+            self.push_bundle(ids);
+        }
     }
 
     fn get_min_max_priority(&self) -> Option<(u64, u64)> {
@@ -242,6 +363,73 @@ impl<Tx: TransactionWithMeta> TransactionStateContainer<Tx> {
         self.push_ids_into_queue(std::iter::once(priority_id)) > 0
     }
 
+    /// Insert a new transaction into the reserved lane of `peer`.
+    /// Returns `true` if a packet was dropped due to capacity limits.
+    #[cfg(test)]
+    pub(crate) fn insert_new_reserved_transaction(
+        &mut self,
+        transaction: Tx,
+        max_age: crate::banking_stage::scheduler_messages::MaxAge,
+        priority: u64,
+        cost: u64,
+        peer: solana_pubkey::Pubkey,
+    ) -> bool {
+        let priority_id = {
+            let entry = self.get_vacant_map_entry();
+            let transaction_id = entry.key();
+            let mut state = TransactionState::new(transaction, max_age, priority, cost);
+            state.set_reserved_for(Some(peer));
+            entry.insert(state);
+            TransactionPriorityId::new(priority, transaction_id)
+        };
+
+        self.push_ids_into_queue(std::iter::once(priority_id)) > 0
+    }
+
+    /// Insert a new bundle into the container's map and bundle queue.
+    /// Returns the number of bundles dropped due to capacity limits.
+    #[cfg(test)]
+    pub(crate) fn insert_new_bundle(
+        &mut self,
+        transactions: impl IntoIterator<
+            Item = (
+                Tx,
+                crate::banking_stage::scheduler_messages::MaxAge,
+                u64,
+                u64,
+            ),
+        >,
+    ) -> usize {
+        let ids = transactions
+            .into_iter()
+            .map(|(transaction, max_age, priority, cost)| {
+                let entry = self.get_vacant_map_entry();
+                let transaction_id = entry.key();
+                entry.insert(TransactionState::new(transaction, max_age, priority, cost));
+                transaction_id
+            })
+            .collect();
+
+        self.push_bundle(ids)
+    }
+
+    /// The priority of a bundle is the priority of its transactions, weighted
+    /// by their cost.
+    fn bundle_priority(&self, ids: &[TransactionId]) -> u64 {
+        let (weighted_priority, total_cost) =
+            ids.iter()
+                .fold((0u128, 0u128), |(weighted_priority, total_cost), id| {
+                    let state = &self.id_to_transaction_state[*id];
+                    (
+                        weighted_priority + u128::from(state.priority()) * u128::from(state.cost()),
+                        total_cost + u128::from(state.cost()),
+                    )
+                });
+        weighted_priority
+            .checked_div(total_cost)
+            .map_or(0, |priority| priority as u64)
+    }
+
     fn get_vacant_map_entry(&mut self) -> VacantEntry<'_, TransactionState<Tx>> {
         assert!(self.id_to_transaction_state.len() < self.id_to_transaction_state.capacity());
         self.id_to_transaction_state.vacant_entry()
@@ -335,6 +523,16 @@ impl StateContainer<RuntimeTransactionView> for TransactionViewStateContainer {
         self.inner.pop()
     }
 
+    #[inline]
+    fn reserved_queue_size(&self) -> usize {
+        self.inner.reserved_queue_size()
+    }
+
+    #[inline]
+    fn pop_reserved(&mut self) -> Option<TransactionPriorityId> {
+        self.inner.pop_reserved()
+    }
+
     #[inline]
     fn get_mut_transaction_state(
         &mut self,
@@ -361,6 +559,26 @@ impl StateContainer<RuntimeTransactionView> for TransactionViewStateContainer {
         self.inner.hold_transaction(priority_id);
     }
 
+    #[inline]
+    fn bundle_queue_size(&self) -> usize {
+        self.inner.bundle_queue_size()
+    }
+
+    #[inline]
+    fn push_bundle(&mut self, ids: Vec<TransactionId>) -> usize {
+        self.inner.push_bundle(ids)
+    }
+
+    #[inline]
+    fn pop_bundle(&mut self) -> Option<Vec<TransactionId>> {
+        self.inner.pop_bundle()
+    }
+
+    #[inline]
+    fn hold_bundle(&mut self, ids: Vec<TransactionId>) {
+        self.inner.hold_bundle(ids);
+    }
+
     #[inline]
     fn remove_by_id(&mut self, id: TransactionId) {
         self.inner.remove_by_id(id);
@@ -629,4 +847,140 @@ mod tests {
         assert!(container.get_transaction(popped.id).is_none());
         assert!(container.is_empty());
     }
+
+    #[test]
+    fn test_reserved_lane() {
+        let peer = solana_pubkey::new_rand();
+        let mut container = TransactionStateContainer::with_capacity(3);
+        for priority in [5, 1] {
+            let (transaction, max_age, priority, cost) = test_transaction(priority);
+            container.insert_new_reserved_transaction(transaction, max_age, priority, cost, peer);
+        }
+        assert_eq!(container.queue_size(), 0);
+        assert_eq!(container.reserved_queue_size(), 2);
+        assert!(!container.is_empty());
+        assert_eq!(container.get_min_max_priority(), None);
+
+        // Regular transactions are dropped on capacity before reserved ones.
+        push_to_container(&mut container, 2);
+        assert_eq!(container.queue_size(), 1);
+        assert_eq!(container.reserved_queue_size(), 2);
+
+        // Retried transactions go back into their lane.
+        let id = container.pop_reserved().unwrap();
+        assert_eq!(id.priority, 5);
+        let (transaction, _) = container
+            .get_mut_transaction_state(id.id)
+            .unwrap()
+            .take_transaction_for_scheduling();
+        container.retry_transaction(id.id, transaction, true);
+        assert_eq!(container.reserved_queue_size(), 2);
+
+        // Transactions leaving their reservation move to the priority queue.
+        let id = container.pop_reserved().unwrap();
+        container
+            .get_mut_transaction_state(id.id)
+            .unwrap()
+            .set_reserved_for(None);
+        container.push_ids_into_queue(std::iter::once(id));
+        assert_eq!(container.queue_size(), 2);
+        assert_eq!(container.reserved_queue_size(), 1);
+
+        let id = container.pop_reserved().unwrap();
+        container.push_ids_into_queue(std::iter::once(id));
+        container.remove_by_id(id.id);
+        assert_eq!(container.reserved_queue_size(), 0);
+        assert_eq!(container.buffer_size(), 2);
+    }
+
+    #[test]
+    fn test_bundle_priority() {
+        let mut container = TransactionStateContainer::with_capacity(8);
+        container.insert_new_bundle([
+            (test_transaction(10).0, MaxAge::MAX, 10, 1_000),
+            (test_transaction(40).0, MaxAge::MAX, 40, 3_000),
+        ]);
+        let (transaction, max_age, priority, cost) = test_transaction(30);
+        container.insert_new_bundle([(transaction, max_age, priority, cost)]);
+
+        // Bundles are not in the transaction queue.
+        assert_eq!(container.queue_size(), 0);
+        assert_eq!(container.buffer_size(), 3);
+        assert_eq!(container.bundle_queue_size(), 2);
+
+        // Cost-weighted priority of the first bundle is 32.
+        let ids = container.pop_bundle().unwrap();
+        assert_eq!(ids.len(), 2);
+        let ids = container.pop_bundle().unwrap();
+        assert_eq!(ids.len(), 1);
+        assert!(container.pop_bundle().is_none());
+    }
+
+    #[test]
+    fn test_bundle_queue_capacity() {
+        let mut container = TransactionStateContainer::with_capacity(MAX_BUFFERED_BUNDLES + 1);
+        for priority in 1..=MAX_BUFFERED_BUNDLES as u64 {
+            let (transaction, max_age, priority, cost) = test_transaction(priority);
+            assert_eq!(
+                container.insert_new_bundle([(transaction, max_age, priority, cost)]),
+                0
+            );
+        }
+
+        // The lowest priority bundle is dropped, along with its transactions.
+        let (transaction, max_age, _, cost) = test_transaction(0);
+        assert_eq!(
+            container.insert_new_bundle([(transaction, max_age, u64::MAX, cost)]),
+            1
+        );
+        assert_eq!(container.bundle_queue_size(), MAX_BUFFERED_BUNDLES);
+        assert_eq!(container.buffer_size(), MAX_BUFFERED_BUNDLES);
+
+        let ids = container.pop_bundle().unwrap();
+        assert_eq!(
+            container
+                .get_mut_transaction_state(ids[0])
+                .unwrap()
+                .priority(),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_retry_bundle() {
+        let mut container = TransactionStateContainer::with_capacity(4);
+        let bundle: Vec<_> = [5, 6].into_iter().map(test_transaction).collect();
+        container.insert_new_bundle(bundle);
+
+        // Immediately retryable bundles are pushed back into the queue.
+        let ids = container.pop_bundle().unwrap();
+        let transactions = ids
+            .iter()
+            .map(|id| {
+                container
+                    .get_mut_transaction_state(*id)
+                    .unwrap()
+                    .take_transaction_for_scheduling()
+                    .0
+            })
+            .collect();
+        container.retry_bundle(ids.clone(), transactions, true);
+        assert_eq!(container.pop_bundle(), Some(ids.clone()));
+
+        // Other bundles are held until the next flush.
+        let transactions = ids
+            .iter()
+            .map(|id| {
+                container
+                    .get_mut_transaction_state(*id)
+                    .unwrap()
+                    .take_transaction_for_scheduling()
+                    .0
+            })
+            .collect();
+        container.retry_bundle(ids.clone(), transactions, false);
+        assert!(container.pop_bundle().is_none());
+        container.flush_held_transactions();
+        assert_eq!(container.pop_bundle(), Some(ids));
+    }
 }
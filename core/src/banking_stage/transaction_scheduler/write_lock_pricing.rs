@@ -0,0 +1,260 @@
+//! Dynamic pricing of write locks on contended accounts.
+//!
+//! The cost model charges every write lock the same static cost, so the
+//! accounts in highest demand fill up at the start of the block. With write
+//! lock pricing enabled, the scheduler discounts the priority of transactions
+//! write-locking accounts that are close to their per-account limit, so they
+//! are scheduled only once their effective priority beats the rest of the
+//! queue.
+
+use {
+    super::{
+        transaction_priority_id::TransactionPriorityId, transaction_state_container::StateContainer,
+    },
+    ahash::AHashMap,
+    solana_clock::Slot,
+    solana_cost_model::cost_tracker::MAX_UTILIZATION_BPS,
+    solana_pubkey::Pubkey,
+    solana_runtime::bank::Bank,
+    solana_runtime_transaction::transaction_with_meta::TransactionWithMeta,
+    solana_svm_transaction::svm_message::SVMMessage,
+    std::{
+        collections::BinaryHeap,
+        time::{Duration, Instant},
+    },
+};
+
+/// Minimum time between two snapshots of the account utilization within a slot.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteLockPricingConfig {
+    /// Share of the per-account limit above which write locks on an account
+    /// get more expensive. Must be below 100.
+    pub utilization_threshold_percent: u8,
+}
+
+impl Default for WriteLockPricingConfig {
+    fn default() -> Self {
+        Self {
+            utilization_threshold_percent: 50,
+        }
+    }
+}
+
+pub(crate) struct WriteLockPricing {
+    threshold_bps: u64,
+    slot: Option<Slot>,
+    last_refresh: Instant,
+    /// Utilization of the accounts above the threshold, in basis points.
+    utilization_bps_by_account: AHashMap<Pubkey, u64>,
+    /// Transactions popped from the container that were outbid at their
+    /// effective priority, keyed by effective priority.
+    deferred: BinaryHeap<(u64, TransactionPriorityId)>,
+    num_deferred: usize,
+}
+
+impl WriteLockPricing {
+    pub(crate) fn new(config: WriteLockPricingConfig) -> Self {
+        assert!(
+            config.utilization_threshold_percent < 100,
+            "write lock pricing threshold must be below 100%"
+        );
+        Self {
+            threshold_bps: u64::from(config.utilization_threshold_percent) * 100,
+            slot: None,
+            last_refresh: Instant::now(),
+            utilization_bps_by_account: AHashMap::new(),
+            deferred: BinaryHeap::new(),
+            num_deferred: 0,
+        }
+    }
+
+    /// Snapshot the utilization of the accounts above the threshold from the
+    /// `bank`'s cost tracker. Refreshed at most every `REFRESH_INTERVAL`
+    /// within a slot, since the cost tracker is shared with the workers.
+    pub(crate) fn update(&mut self, bank: &Bank, now: Instant) {
+        if self.slot == Some(bank.slot())
+            && now.saturating_duration_since(self.last_refresh) < REFRESH_INTERVAL
+        {
+            return;
+        }
+        self.slot = Some(bank.slot());
+        self.last_refresh = now;
+
+        let cost_tracker = bank.read_cost_tracker().unwrap();
+        self.utilization_bps_by_account.clear();
+        self.utilization_bps_by_account.extend(
+            cost_tracker
+                .utilized_accounts(self.threshold_bps)
+                .map(|(account_key, utilization_bps)| (*account_key, utilization_bps)),
+        );
+    }
+
+    /// The priority of `transaction` once the write locks it takes are priced
+    /// in. The hottest write-locked account above the threshold scales the
+    /// priority down linearly, reaching zero at full utilization.
+    pub(crate) fn effective_priority(&self, transaction: &impl SVMMessage, priority: u64) -> u64 {
+        if self.utilization_bps_by_account.is_empty() {
+            return priority;
+        }
+
+        let Some(utilization_bps) = transaction
+            .account_keys()
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| transaction.is_writable(*index))
+            .filter_map(|(_, account_key)| self.utilization_bps_by_account.get(account_key))
+            .max()
+        else {
+            return priority;
+        };
+
+        let headroom_bps = MAX_UTILIZATION_BPS.saturating_sub(*utilization_bps);
+        let threshold_headroom_bps = MAX_UTILIZATION_BPS - self.threshold_bps;
+        (u128::from(priority) * u128::from(headroom_bps) / u128::from(threshold_headroom_bps))
+            as u64
+    }
+
+    /// Pop the next transaction in effective priority order. Transactions
+    /// whose effective priority is below their priority are deferred until
+    /// nothing in the container can outbid them.
+    pub(crate) fn pop<Tx: TransactionWithMeta>(
+        &mut self,
+        container: &mut impl StateContainer<Tx>,
+    ) -> Option<TransactionPriorityId> {
+        loop {
+            // Effective priority never exceeds priority, so the top of the
+            // container bounds every effective priority still in it.
+            let next_priority = container.get_min_max_priority().map(|(_, max)| max);
+            if let Some((effective_priority, _)) = self.deferred.peek() {
+                if next_priority.is_none_or(|priority| *effective_priority >= priority) {
+                    return self.deferred.pop().map(|(_, id)| id);
+                }
+            }
+
+            let id = container.pop()?;
+            let Some(transaction) = container.get_transaction(id.id) else {
+                panic!("transaction state must exist")
+            };
+            let effective_priority = self.effective_priority(transaction, id.priority);
+            if effective_priority >= id.priority {
+                return Some(id);
+            }
+            self.deferred.push((effective_priority, id));
+            self.num_deferred += 1;
+        }
+    }
+
+    /// Push the deferred transactions back into the container. Returns the
+    /// number of transactions deferred since the last call.
+    pub(crate) fn finish<Tx: TransactionWithMeta>(
+        &mut self,
+        container: &mut impl StateContainer<Tx>,
+    ) -> usize {
+        container.push_ids_into_queue(self.deferred.drain().map(|(_, id)| id));
+        core::mem::take(&mut self.num_deferred)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_utilization_bps(&mut self, account_key: Pubkey, utilization_bps: u64) {
+        self.utilization_bps_by_account
+            .insert(account_key, utilization_bps);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::banking_stage::{
+            scheduler_messages::MaxAge,
+            transaction_scheduler::transaction_state_container::TransactionStateContainer,
+        },
+        solana_hash::Hash,
+        solana_keypair::Keypair,
+        solana_runtime_transaction::runtime_transaction::RuntimeTransaction,
+        solana_sdk_ids::system_program,
+        solana_system_transaction as system_transaction,
+        solana_transaction::sanitized::SanitizedTransaction,
+    };
+
+    fn transfer_to(to: &Pubkey) -> RuntimeTransaction<SanitizedTransaction> {
+        RuntimeTransaction::from_transaction_for_tests(system_transaction::transfer(
+            &Keypair::new(),
+            to,
+            1,
+            Hash::default(),
+        ))
+    }
+
+    #[test]
+    fn test_effective_priority() {
+        let mut pricing = WriteLockPricing::new(WriteLockPricingConfig {
+            utilization_threshold_percent: 50,
+        });
+        let hot_account = Pubkey::new_unique();
+        let full_account = Pubkey::new_unique();
+        let transaction = transfer_to(&hot_account);
+        assert_eq!(pricing.effective_priority(&transaction, 1_000), 1_000);
+
+        // Linear discount between the threshold and the account limit.
+        pricing.set_utilization_bps(hot_account, 5_000);
+        assert_eq!(pricing.effective_priority(&transaction, 1_000), 1_000);
+        pricing.set_utilization_bps(hot_account, 7_500);
+        assert_eq!(pricing.effective_priority(&transaction, 1_000), 500);
+        pricing.set_utilization_bps(full_account, MAX_UTILIZATION_BPS);
+        assert_eq!(
+            pricing.effective_priority(&transfer_to(&full_account), 1_000),
+            0
+        );
+
+        // Read-only accounts are not priced.
+        pricing.set_utilization_bps(system_program::id(), MAX_UTILIZATION_BPS);
+        assert_eq!(
+            pricing.effective_priority(&transfer_to(&Pubkey::new_unique()), 1_000),
+            1_000
+        );
+    }
+
+    #[test]
+    fn test_pop_in_effective_priority_order() {
+        let mut pricing = WriteLockPricing::new(WriteLockPricingConfig::default());
+        let hot_account = Pubkey::new_unique();
+        pricing.set_utilization_bps(hot_account, 7_500);
+
+        let mut container = TransactionStateContainer::with_capacity(16);
+        container.insert_new_transaction(transfer_to(&hot_account), MaxAge::MAX, 100, 5_000);
+        container.insert_new_transaction(
+            transfer_to(&Pubkey::new_unique()),
+            MaxAge::MAX,
+            60,
+            5_000,
+        );
+        container.insert_new_transaction(
+            transfer_to(&Pubkey::new_unique()),
+            MaxAge::MAX,
+            40,
+            5_000,
+        );
+
+        // The hot transaction is only worth 50 and lands between the others.
+        assert_eq!(pricing.pop(&mut container).unwrap().id, 1);
+        assert_eq!(pricing.pop(&mut container).unwrap().id, 0);
+        assert_eq!(pricing.pop(&mut container).unwrap().id, 2);
+        assert_eq!(pricing.pop(&mut container), None);
+        assert_eq!(pricing.finish(&mut container), 1);
+
+        // Deferred transactions go back into the container at their priority.
+        container.insert_new_transaction(transfer_to(&hot_account), MaxAge::MAX, 100, 5_000);
+        container.insert_new_transaction(
+            transfer_to(&Pubkey::new_unique()),
+            MaxAge::MAX,
+            60,
+            5_000,
+        );
+        assert_eq!(pricing.pop(&mut container).unwrap().id, 4);
+        assert_eq!(pricing.finish(&mut container), 1);
+        assert_eq!(container.pop(), Some(TransactionPriorityId::new(100, 3)));
+    }
+}
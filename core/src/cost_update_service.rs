@@ -27,6 +27,9 @@ pub struct CostUpdateService {
 const MAX_LOOP_COUNT: usize = 25;
 // Throttle checking the count to avoid excessive polling
 const LOOP_LIMITER: Duration = Duration::from_millis(10);
+// Accounts at or above this share of the per-account limit, in basis points,
+// have their utilization reported for every frozen bank
+const REPORTED_ACCOUNT_UTILIZATION_BPS: u64 = 5_000;
 
 impl CostUpdateService {
     pub fn new(cost_update_receiver: CostUpdateReceiver) -> Self {
@@ -80,6 +83,11 @@ impl CostUpdateService {
                                     total_transaction_fee,
                                     total_priority_fee,
                                 );
+                                cost_tracker.report_account_utilization(
+                                    slot,
+                                    is_leader_block,
+                                    REPORTED_ACCOUNT_UTILIZATION_BPS,
+                                );
                                 break;
                             }
                         }
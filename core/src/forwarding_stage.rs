@@ -2,12 +2,16 @@
 //! packets to a node that is or will be leader soon.
 
 use {
-    crate::next_leader::next_leaders,
+    crate::next_leader::{leader_targets, next_leader_pubkeys, next_leaders},
     agave_banking_stage_ingress_types::BankingPacketBatch,
     agave_transaction_view::transaction_view::SanitizedTransactionView,
     async_trait::async_trait,
     crossbeam_channel::{Receiver, RecvTimeoutError},
+    forwarding_policy::{
+        ForwardingPolicyConfig, ForwardingTargets, MAX_LEADER_FANOUT, SenderBudget,
+    },
     packet_container::PacketContainer,
+    solana_clock::Slot,
     solana_cost_model::cost_model::CostModel,
     solana_fee_structure::FeeDetails,
     solana_gossip::{cluster_info::ClusterInfo, contact_info::Protocol, node::NodeMultihoming},
@@ -26,12 +30,13 @@ use {
     solana_streamer::sendmmsg::{SendPktsError, batch_send},
     solana_tls_utils::NotifyKeyUpdate,
     solana_tpu_client_next::{
-        ConnectionWorkersScheduler,
+        ConnectionWorkersScheduler, ConnectionWorkersSchedulerError,
         connection_workers_scheduler::{
-            BindTarget, ConnectionWorkersSchedulerConfig, Fanout, StakeIdentity,
+            BindTarget, ConnectionWorkersSchedulerConfig, Fanout, StakeIdentity, WorkersBroadcaster,
         },
         leader_updater::LeaderUpdater,
         transaction_batch::TransactionBatch,
+        workers_cache::{WorkersCache, WorkersCacheError, shutdown_worker},
     },
     solana_transaction::sanitized::MessageHash,
     solana_transaction_error::TransportError,
@@ -49,6 +54,7 @@ use {
     tokio_util::sync::CancellationToken,
 };
 
+pub mod forwarding_policy;
 mod packet_container;
 
 /// [`ForwardingClientConfig`] is the config for `tpu-client-next` instance.
@@ -58,6 +64,7 @@ pub struct ForwardingClientConfig<'a> {
     pub runtime_handle: RuntimeHandle,
     pub cancel: CancellationToken,
     pub node_multihoming: Arc<NodeMultihoming>,
+    pub policy: ForwardingPolicyConfig,
 }
 
 /// Maximum forwarding rate in bytes per second.
@@ -85,6 +92,7 @@ const NUM_LOOKAHEAD_LEADERS: u64 = 3;
 pub(crate) struct ForwardAddressGetter {
     cluster_info: Arc<ClusterInfo>,
     poh_recorder: Arc<RwLock<PohRecorder>>,
+    targets: Arc<ForwardingTargets>,
 }
 
 impl ForwardAddressGetter {
@@ -92,18 +100,38 @@ impl ForwardAddressGetter {
         Self {
             cluster_info,
             poh_recorder,
+            targets: Arc::default(),
         }
     }
 
+    fn with_targets(self, targets: Arc<ForwardingTargets>) -> Self {
+        Self { targets, ..self }
+    }
+
     /// Returns a list of forwarding addresses for non-vote transactions.
     fn get_non_vote_forwarding_addresses(
         &self,
         max_count: u64,
         protocol: Protocol,
     ) -> Vec<SocketAddr> {
-        next_leaders(&self.cluster_info, &self.poh_recorder, max_count, |node| {
+        // Look further ahead to make up for the leaders that are skipped.
+        let lookahead = if self.targets.skip_empty_block_leaders() {
+            max_count + NUM_LOOKAHEAD_LEADERS
+        } else {
+            max_count
+        };
+        let leaders = self.targets.select(
+            next_leader_pubkeys(&self.poh_recorder, lookahead),
+            max_count as usize,
+        );
+        let targets = leader_targets(&self.cluster_info, leaders, |node| {
             node.tpu_forwards(protocol)
-        })
+        });
+        self.targets.record_addresses(&targets);
+        targets
+            .into_iter()
+            .map(|(_leader, address)| address)
+            .collect()
     }
 
     /// Returns the TPU vote forwarding address of the next leader, if
@@ -138,8 +166,12 @@ pub(crate) fn spawn_forwarding_stage(
         runtime_handle,
         cancel,
         node_multihoming,
+        policy,
     } = tpu_forwarding_client_config;
 
+    let forwarding_targets = Arc::new(ForwardingTargets::new(policy.skip_empty_block_leaders));
+    let forward_address_getter = forward_address_getter.with_targets(forwarding_targets.clone());
+
     // Create TPU clients for each socket provided.
     // Number of clients is same as number of bind IP addresses.
     let non_vote_clients: Box<[TpuClientNextClient]> = tpu_client_sockets
@@ -151,6 +183,7 @@ pub(crate) fn spawn_forwarding_stage(
                 forward_address_getter.clone(),
                 Some(stake_identity),
                 socket,
+                policy.leader_fanout.get().min(MAX_LEADER_FANOUT),
                 cancel.clone(),
             )
         })
@@ -161,6 +194,8 @@ pub(crate) fn spawn_forwarding_stage(
         non_vote_clients.clone(),
         sharable_banks,
         Some(node_multihoming.bind_ip_addrs.clone()),
+        &policy,
+        forwarding_targets,
     );
     SpawnForwardingStageResult {
         join_handle: Builder::new()
@@ -189,6 +224,11 @@ struct ForwardingStage<VoteClient: ForwardingClient, NonVoteClient: ForwardingCl
     data_budget: TokenBucket,
     metrics: ForwardingStageMetrics,
     bind_ip_addrs: Option<Arc<BindIpAddrs>>,
+    leader_fanout: usize,
+    forwarding_targets: Arc<ForwardingTargets>,
+    /// Slot of the working bank when blocks were last observed.
+    last_observed_working_slot: Slot,
+    sender_budget: Option<SenderBudget>,
 }
 
 impl<VoteClient: ForwardingClient, NonVoteClient: ForwardingClient>
@@ -200,6 +240,8 @@ impl<VoteClient: ForwardingClient, NonVoteClient: ForwardingClient>
         non_vote_clients: Box<[NonVoteClient]>,
         sharable_banks: SharableBanks,
         bind_ip_addrs: Option<Arc<BindIpAddrs>>,
+        policy: &ForwardingPolicyConfig,
+        forwarding_targets: Arc<ForwardingTargets>,
     ) -> Self {
         let data_budget = TokenBucket::new(
             MAX_BYTES_PER_SECOND,
@@ -215,6 +257,10 @@ impl<VoteClient: ForwardingClient, NonVoteClient: ForwardingClient>
             data_budget,
             metrics: ForwardingStageMetrics::default(),
             bind_ip_addrs,
+            leader_fanout: policy.leader_fanout.get().min(MAX_LEADER_FANOUT),
+            forwarding_targets,
+            last_observed_working_slot: 0,
+            sender_budget: policy.max_bytes_per_sender.map(SenderBudget::new),
         }
     }
 
@@ -225,8 +271,34 @@ impl<VoteClient: ForwardingClient, NonVoteClient: ForwardingClient>
             if !self.receive_and_buffer(&root_bank) {
                 break;
             }
+            if self.forwarding_targets.skip_empty_block_leaders() {
+                self.observe_blocks();
+            }
             self.forward_buffered_packets();
-            self.metrics.maybe_report();
+            self.metrics
+                .maybe_report(&self.forwarding_targets, self.leader_fanout);
+        }
+    }
+
+    /// Record the blocks frozen since the working bank last changed, so that
+    /// leaders producing empty blocks can be skipped.
+    fn observe_blocks(&mut self) {
+        let working_bank = self.sharable_banks.working();
+        let previous_working_slot = self.last_observed_working_slot;
+        if working_bank.slot() == previous_working_slot {
+            return;
+        }
+        self.last_observed_working_slot = working_bank.slot();
+
+        // The previous working bank was not necessarily frozen when it was
+        // seen, so observe it again.
+        for bank in working_bank
+            .parents()
+            .iter()
+            .take_while(|bank| bank.slot() >= previous_working_slot)
+            .filter(|bank| bank.is_frozen())
+        {
+            self.forwarding_targets.observe_block(bank);
         }
     }
 
@@ -350,8 +422,19 @@ impl<VoteClient: ForwardingClient, NonVoteClient: ForwardingClient>
                 .unwrap_or(0);
             &self.non_vote_clients[active_index]
         };
+        let now = Instant::now();
         // Loop through packets creating batches of packets to forward.
         while let Some(packet) = self.packet_container.pop_max() {
+            // If its sender exceeds their share of the data-budget, drop.
+            if let Some(sender_budget) = &mut self.sender_budget {
+                if !packet.meta().is_simple_vote_tx()
+                    && !sender_budget.try_consume(packet.data(..).expect("packet has data"), now)
+                {
+                    self.metrics.non_votes_dropped_on_sender_budget += 1;
+                    continue;
+                }
+            }
+
             // If it exceeds our data-budget, drop.
             if self
                 .data_budget
@@ -505,13 +588,17 @@ impl TpuClientNextClient {
         forward_address_getter: ForwardAddressGetter,
         stake_identity: Option<&Keypair>,
         bind_socket: UdpSocket,
+        leader_fanout: usize,
         cancel: CancellationToken,
     ) -> Self {
         // For now use large channel, the more suitable size to be found later.
         let (sender, receiver) = mpsc::channel(128);
+        let broadcaster = TargetMetricsBroadcaster {
+            targets: forward_address_getter.targets.clone(),
+        };
         let leader_updater = forward_address_getter;
 
-        let config = Self::create_config(bind_socket, stake_identity);
+        let config = Self::create_config(bind_socket, stake_identity, leader_fanout);
         let (update_certificate_sender, update_certificate_receiver) = watch::channel(None);
         let scheduler: ConnectionWorkersScheduler = ConnectionWorkersScheduler::new(
             Box::new(leader_updater),
@@ -525,7 +612,8 @@ impl TpuClientNextClient {
             METRICS_REPORTING_INTERVAL,
             cancel,
         ));
-        let _handle = runtime_handle.spawn(scheduler.run(config));
+        let _handle =
+            runtime_handle.spawn(scheduler.run_with_broadcaster(config, Box::new(broadcaster)));
         Self {
             sender,
             update_certificate_sender,
@@ -535,6 +623,7 @@ impl TpuClientNextClient {
     fn create_config(
         bind_socket: UdpSocket,
         stake_identity: Option<&Keypair>,
+        leader_fanout: usize,
     ) -> ConnectionWorkersSchedulerConfig {
         ConnectionWorkersSchedulerConfig {
             bind: BindTarget::Socket(bind_socket),
@@ -545,11 +634,12 @@ impl TpuClientNextClient {
             skip_check_transaction_age: true,
             worker_channel_size: 2,
             max_reconnect_attempts: 4,
-            // Send to the next leader only, but verify that connections exist
-            // for the leaders of the next `4 * NUM_CONSECUTIVE_SLOTS`.
+            // Send to the next `leader_fanout` leaders, but verify that
+            // connections exist for the leaders of the next
+            // `MAX_LEADER_FANOUT * NUM_CONSECUTIVE_SLOTS`.
             leaders_fanout: Fanout {
-                send: 1,
-                connect: 4,
+                send: leader_fanout,
+                connect: MAX_LEADER_FANOUT,
             },
             override_initial_congestion_window: None,
         }
@@ -567,6 +657,41 @@ impl ForwardingClient for TpuClientNextClient {
     }
 }
 
+/// Sends each transaction batch to the workers of the leaders without waiting,
+/// like `NonblockingBroadcaster`, and records the result of every send for the
+/// target leader.
+struct TargetMetricsBroadcaster {
+    targets: Arc<ForwardingTargets>,
+}
+
+#[async_trait]
+impl WorkersBroadcaster for TargetMetricsBroadcaster {
+    async fn send_to_workers(
+        &self,
+        workers: &mut WorkersCache,
+        leaders: &[SocketAddr],
+        transaction_batch: TransactionBatch,
+    ) -> Result<(), ConnectionWorkersSchedulerError> {
+        let num_transactions = transaction_batch.len();
+        for leader in leaders {
+            let send_res =
+                workers.try_send_transactions_to_address(leader, transaction_batch.clone());
+            self.targets
+                .record_send(leader, num_transactions, send_res.is_ok());
+            if let Err(err) = send_res {
+                debug!("Failed to send transactions to {leader:?}, worker send error: {err}.");
+                if err == WorkersCacheError::ReceiverDropped {
+                    // Remove the worker from the cache if the peer has disconnected.
+                    if let Some(pop_worker) = workers.pop(*leader) {
+                        shutdown_worker(pop_worker)
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 impl NotifyKeyUpdate for TpuClientNextClient {
     fn update_key(&self, identity: &Keypair) -> Result<(), Box<dyn std::error::Error>> {
         let stake_identity = StakeIdentity::new(identity);
@@ -674,15 +799,19 @@ struct ForwardingStageMetrics {
     non_votes_dropped_on_data_budget: usize,
     non_votes_forwarded: usize,
     non_votes_dropped_on_send: usize,
+    /// Number of non-votes dropped because their fee payer exceeded the
+    /// per-sender forwarding limit.
+    non_votes_dropped_on_sender_budget: usize,
 }
 
 impl ForwardingStageMetrics {
-    fn maybe_report(&mut self) {
+    fn maybe_report(&mut self, forwarding_targets: &ForwardingTargets, leader_fanout: usize) {
         const REPORTING_INTERVAL: Duration = Duration::from_secs(1);
 
         if self.last_reported.elapsed() > REPORTING_INTERVAL {
             // Reset time and all counts.
             let metrics = core::mem::take(self);
+            forwarding_targets.report_metrics(leader_fanout);
 
             // Only report if something happened.
             if !metrics.did_something {
@@ -731,6 +860,11 @@ impl ForwardingStageMetrics {
                     metrics.non_votes_dropped_on_send,
                     i64
                 ),
+                (
+                    "non_votes_dropped_on_sender_budget",
+                    metrics.non_votes_dropped_on_sender_budget,
+                    i64
+                ),
             );
         }
     }
@@ -753,6 +887,7 @@ impl Default for ForwardingStageMetrics {
             non_votes_dropped_on_data_budget: 0,
             non_votes_forwarded: 0,
             non_votes_dropped_on_send: 0,
+            non_votes_dropped_on_sender_budget: 0,
         }
     }
 }
@@ -855,6 +990,8 @@ mod tests {
             Box::new([non_vote_mock_client.clone()]),
             sharable_banks,
             None,
+            &ForwardingPolicyConfig::default(),
+            Arc::default(),
         );
 
         // Send packet batches.
@@ -0,0 +1,382 @@
+//! Policies deciding which leaders non-vote transactions are forwarded to and
+//! how much of the forwarding budget a single sender may use.
+//!
+//! Leaders are selected in leader schedule order. Weighting the fanout by the
+//! stake of the leaders is not implemented.
+
+use {
+    agave_transaction_view::transaction_view::UnsanitizedTransactionView,
+    ahash::AHashMap,
+    solana_clock::Slot,
+    solana_pubkey::Pubkey,
+    solana_runtime::bank::Bank,
+    std::{
+        net::SocketAddr,
+        num::{NonZeroU64, NonZeroUsize},
+        sync::{
+            Mutex, RwLock,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::{Duration, Instant},
+    },
+};
+
+/// Maximum number of upcoming leaders a transaction can be forwarded to.
+pub const MAX_LEADER_FANOUT: usize = 4;
+
+/// Empty blocks older than this many slots no longer get a leader skipped.
+const EMPTY_BLOCK_EXPIRY_SLOTS: Slot = 9_000;
+
+/// Interval over which `ForwardingPolicyConfig::max_bytes_per_sender` applies.
+const SENDER_BUDGET_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForwardingPolicyConfig {
+    /// Number of upcoming leaders each non-vote transaction is sent to, up to
+    /// `MAX_LEADER_FANOUT`.
+    pub leader_fanout: NonZeroUsize,
+    /// Skip leaders whose most recent block had no non-vote transactions.
+    pub skip_empty_block_leaders: bool,
+    /// Maximum number of bytes forwarded per second for a single fee payer.
+    pub max_bytes_per_sender: Option<NonZeroU64>,
+}
+
+impl Default for ForwardingPolicyConfig {
+    fn default() -> Self {
+        Self {
+            leader_fanout: NonZeroUsize::MIN,
+            skip_empty_block_leaders: false,
+            max_bytes_per_sender: None,
+        }
+    }
+}
+
+/// Selects the leaders non-vote transactions are forwarded to. Shared between
+/// the forwarding stage, which observes the blocks leaders produce, and the
+/// `tpu-client-next` scheduler, which looks up the forwarding addresses.
+#[derive(Default)]
+pub(crate) struct ForwardingTargets {
+    skip_empty_block_leaders: bool,
+    /// Leader -> slot of the most recent block it produced without any
+    /// non-vote transaction.
+    empty_block_leaders: RwLock<AHashMap<Pubkey, Slot>>,
+    /// Forwarding address -> leader, for the most recently selected leaders.
+    leaders_by_address: RwLock<AHashMap<SocketAddr, Pubkey>>,
+    metrics: ForwardingTargetMetrics,
+}
+
+impl ForwardingTargets {
+    pub(crate) fn new(skip_empty_block_leaders: bool) -> Self {
+        Self {
+            skip_empty_block_leaders,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn skip_empty_block_leaders(&self) -> bool {
+        self.skip_empty_block_leaders
+    }
+
+    /// Record the block of a frozen `bank`.
+    pub(crate) fn observe_block(&self, bank: &Bank) {
+        let Some(parent) = bank.parent() else {
+            return;
+        };
+        let num_non_vote_transactions = bank
+            .non_vote_transaction_count_since_restart()
+            .saturating_sub(parent.non_vote_transaction_count_since_restart());
+        self.record_block(
+            *bank.collector_id(),
+            bank.slot(),
+            num_non_vote_transactions == 0,
+        );
+    }
+
+    fn record_block(&self, leader: Pubkey, slot: Slot, is_empty: bool) {
+        let mut empty_block_leaders = self.empty_block_leaders.write().unwrap();
+        if is_empty {
+            empty_block_leaders.insert(leader, slot);
+        } else if empty_block_leaders
+            .get(&leader)
+            .is_some_and(|empty_slot| *empty_slot < slot)
+        {
+            empty_block_leaders.remove(&leader);
+        }
+        empty_block_leaders
+            .retain(|_, empty_slot| slot.saturating_sub(*empty_slot) < EMPTY_BLOCK_EXPIRY_SLOTS);
+    }
+
+    /// The first `max_count` of the upcoming `leaders` transactions should be
+    /// forwarded to. Falls back to the next leader if every upcoming leader
+    /// is skipped.
+    pub(crate) fn select(
+        &self,
+        leaders: impl IntoIterator<Item = Pubkey>,
+        max_count: usize,
+    ) -> Vec<Pubkey> {
+        self.metrics.lookups.fetch_add(1, Ordering::Relaxed);
+        let empty_block_leaders = self.empty_block_leaders.read().unwrap();
+        let mut leaders = leaders.into_iter().peekable();
+        let next_leader = leaders.peek().copied();
+        let mut num_skipped = 0;
+        let mut selected: Vec<_> = leaders
+            .filter(|leader| {
+                let skip =
+                    self.skip_empty_block_leaders && empty_block_leaders.contains_key(leader);
+                num_skipped += usize::from(skip);
+                !skip
+            })
+            .take(max_count)
+            .collect();
+        if selected.is_empty()
+            && max_count > 0
+            && let Some(next_leader) = next_leader
+        {
+            selected.push(next_leader);
+            num_skipped -= 1;
+        }
+        self.metrics
+            .leaders_skipped_empty_block
+            .fetch_add(num_skipped, Ordering::Relaxed);
+        self.metrics
+            .leaders_selected
+            .fetch_add(selected.len(), Ordering::Relaxed);
+        selected
+    }
+
+    /// Remember the forwarding address of each selected leader, so the
+    /// results of sending to an address are reported for its leader.
+    pub(crate) fn record_addresses(&self, targets: &[(Pubkey, SocketAddr)]) {
+        *self.leaders_by_address.write().unwrap() = targets
+            .iter()
+            .map(|(leader, address)| (*address, *leader))
+            .collect();
+    }
+
+    /// Record the result of the forwarding client sending `num_transactions`
+    /// to the leader at `address`.
+    pub(crate) fn record_send(&self, address: &SocketAddr, num_transactions: usize, is_ok: bool) {
+        let Some(leader) = self
+            .leaders_by_address
+            .read()
+            .unwrap()
+            .get(address)
+            .copied()
+        else {
+            return;
+        };
+        let mut sends_by_leader = self.metrics.sends_by_leader.lock().unwrap();
+        let sends = sends_by_leader.entry(leader).or_default();
+        if is_ok {
+            sends.forwarded += num_transactions;
+        } else {
+            sends.failed += num_transactions;
+        }
+    }
+
+    pub(crate) fn report_metrics(&self, leader_fanout: usize) {
+        let sends_by_leader = std::mem::take(&mut *self.metrics.sends_by_leader.lock().unwrap());
+        for (leader, sends) in sends_by_leader {
+            datapoint_info!(
+                "forwarding_stage_target",
+                "leader" => leader.to_string(),
+                ("forwarded", sends.forwarded, i64),
+                ("failed", sends.failed, i64),
+            );
+        }
+
+        let lookups = self.metrics.lookups.swap(0, Ordering::Relaxed);
+        if lookups == 0 {
+            return;
+        }
+        datapoint_info!(
+            "forwarding_stage_targets",
+            ("leader_fanout", leader_fanout, i64),
+            ("lookups", lookups, i64),
+            (
+                "leaders_selected",
+                self.metrics.leaders_selected.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "leaders_skipped_empty_block",
+                self.metrics
+                    .leaders_skipped_empty_block
+                    .swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "empty_block_leaders",
+                self.empty_block_leaders.read().unwrap().len(),
+                i64
+            ),
+        );
+    }
+}
+
+#[derive(Default)]
+struct ForwardingTargetMetrics {
+    /// Number of times the forwarding targets were looked up.
+    lookups: AtomicUsize,
+    /// Number of leaders selected as forwarding targets.
+    leaders_selected: AtomicUsize,
+    /// Number of upcoming leaders skipped for producing an empty block.
+    leaders_skipped_empty_block: AtomicUsize,
+    /// Send results of the forwarding client, per target leader.
+    sends_by_leader: Mutex<AHashMap<Pubkey, TargetSends>>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct TargetSends {
+    /// Number of transactions handed to the connection to the leader.
+    forwarded: usize,
+    /// Number of transactions the forwarding client failed to send.
+    failed: usize,
+}
+
+/// Caps the number of bytes forwarded for each fee payer per
+/// `SENDER_BUDGET_INTERVAL`, so a single sender cannot use up the forwarding
+/// data budget.
+pub(crate) struct SenderBudget {
+    max_bytes: u64,
+    interval_start: Instant,
+    bytes_by_sender: AHashMap<Pubkey, u64>,
+}
+
+impl SenderBudget {
+    pub(crate) fn new(max_bytes: NonZeroU64) -> Self {
+        Self {
+            max_bytes: max_bytes.get(),
+            interval_start: Instant::now(),
+            bytes_by_sender: AHashMap::new(),
+        }
+    }
+
+    /// Charge the forwarding of serialized `transaction` to its fee payer.
+    /// Returns false if the fee payer is out of budget.
+    pub(crate) fn try_consume(&mut self, transaction: &[u8], now: Instant) -> bool {
+        if now.saturating_duration_since(self.interval_start) >= SENDER_BUDGET_INTERVAL {
+            self.interval_start = now;
+            self.bytes_by_sender.clear();
+        }
+
+        let Some(fee_payer) = UnsanitizedTransactionView::try_new_unsanitized(transaction)
+            .ok()
+            .and_then(|view| view.static_account_keys().first().copied())
+        else {
+            return true;
+        };
+        let bytes = self.bytes_by_sender.entry(fee_payer).or_default();
+        let new_bytes = bytes.saturating_add(transaction.len() as u64);
+        if new_bytes > self.max_bytes {
+            return false;
+        }
+        *bytes = new_bytes;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, solana_hash::Hash, solana_keypair::Keypair, solana_signer::Signer,
+        solana_system_transaction as system_transaction,
+    };
+
+    #[test]
+    fn test_select_skips_empty_block_leaders() {
+        let leaders: Vec<_> = (0..4).map(|_| Pubkey::new_unique()).collect();
+
+        let targets = ForwardingTargets::new(true);
+        assert_eq!(targets.select(leaders.clone(), 2), leaders[..2]);
+
+        targets.record_block(leaders[0], 10, true);
+        targets.record_block(leaders[1], 14, false);
+        assert_eq!(targets.select(leaders.clone(), 2), leaders[1..3]);
+
+        // A non-empty block clears the leader.
+        targets.record_block(leaders[0], 18, false);
+        assert_eq!(targets.select(leaders.clone(), 2), leaders[..2]);
+
+        // Empty blocks expire.
+        targets.record_block(leaders[0], 20, true);
+        targets.record_block(leaders[1], 20 + EMPTY_BLOCK_EXPIRY_SLOTS, false);
+        assert_eq!(targets.select(leaders.clone(), 2), leaders[..2]);
+
+        // The next leader is selected if all upcoming leaders are skipped.
+        for leader in &leaders {
+            targets.record_block(*leader, 20 + EMPTY_BLOCK_EXPIRY_SLOTS, true);
+        }
+        assert_eq!(targets.select(leaders.clone(), 2), leaders[..1]);
+
+        // Leaders are not skipped when the policy is disabled.
+        let targets = ForwardingTargets::new(false);
+        targets.record_block(leaders[0], 10, true);
+        assert_eq!(targets.select(leaders.clone(), 2), leaders[..2]);
+    }
+
+    #[test]
+    fn test_record_send() {
+        let leaders: Vec<_> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let addresses: Vec<_> = (0..3)
+            .map(|port| SocketAddr::from(([127, 0, 0, 1], 8000 + port)))
+            .collect();
+
+        let targets = ForwardingTargets::new(false);
+        targets.record_addresses(&[(leaders[0], addresses[0]), (leaders[1], addresses[1])]);
+        targets.record_send(&addresses[0], 128, true);
+        targets.record_send(&addresses[0], 64, false);
+        targets.record_send(&addresses[1], 32, true);
+        // Sends to addresses of leaders that were not selected are not
+        // reported.
+        targets.record_send(&addresses[2], 16, true);
+
+        let sends_by_leader = targets.metrics.sends_by_leader.lock().unwrap();
+        assert_eq!(sends_by_leader.len(), 2);
+        assert_eq!(
+            sends_by_leader[&leaders[0]],
+            TargetSends {
+                forwarded: 128,
+                failed: 64
+            }
+        );
+        assert_eq!(
+            sends_by_leader[&leaders[1]],
+            TargetSends {
+                forwarded: 32,
+                failed: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_sender_budget() {
+        let sender = Keypair::new();
+        let transfer = |from: &Keypair| {
+            bincode::serialize(&system_transaction::transfer(
+                from,
+                &Pubkey::new_unique(),
+                1,
+                Hash::default(),
+            ))
+            .unwrap()
+        };
+        let transaction = transfer(&sender);
+        let len = transaction.len() as u64;
+
+        let now = Instant::now();
+        let mut budget = SenderBudget::new(NonZeroU64::new(2 * len).unwrap());
+        assert!(budget.try_consume(&transaction, now));
+        assert!(budget.try_consume(&transaction, now));
+        assert!(!budget.try_consume(&transaction, now));
+        // Other senders have their own budget.
+        assert!(budget.try_consume(&transfer(&Keypair::new()), now));
+        assert_eq!(
+            budget.bytes_by_sender.get(&sender.pubkey()),
+            Some(&(2 * len))
+        );
+
+        // The budget is refilled every interval.
+        assert!(budget.try_consume(&transaction, now + SENDER_BUDGET_INTERVAL));
+    }
+}
@@ -8,6 +8,7 @@ use {
     },
     solana_leader_schedule::NUM_CONSECUTIVE_LEADER_SLOTS,
     solana_poh::poh_recorder::PohRecorder,
+    solana_pubkey::Pubkey,
     std::{net::SocketAddr, sync::RwLock},
 };
 
@@ -43,21 +44,55 @@ pub(crate) fn next_leaders(
     max_count: u64,
     port_selector: impl ContactInfoQuery<Option<SocketAddr>>,
 ) -> Vec<SocketAddr> {
+    leader_addresses(
+        cluster_info,
+        next_leader_pubkeys(poh_recorder, max_count),
+        port_selector,
+    )
+}
+
+/// Returns the leaders of the next `max_count` leader windows, starting at
+/// the slot transactions are forwarded to.
+pub(crate) fn next_leader_pubkeys(
+    poh_recorder: &RwLock<PohRecorder>,
+    max_count: u64,
+) -> Vec<Pubkey> {
     let recorder = poh_recorder.read().unwrap();
-    let leader_pubkeys: Vec<_> = (0..max_count)
+    (0..max_count)
         .filter_map(|i| {
             recorder.leader_after_n_slots(
                 FORWARD_TRANSACTIONS_TO_LEADER_AT_SLOT_OFFSET
                     + i * NUM_CONSECUTIVE_LEADER_SLOTS.get() as u64,
             )
         })
-        .collect();
-    drop(recorder);
+        .collect()
+}
+
+/// Returns the sockets selected by `port_selector` of the `leader_pubkeys`
+/// with known contact information.
+pub(crate) fn leader_addresses(
+    cluster_info: &impl LikeClusterInfo,
+    leader_pubkeys: impl IntoIterator<Item = Pubkey>,
+    port_selector: impl ContactInfoQuery<Option<SocketAddr>>,
+) -> Vec<SocketAddr> {
+    leader_targets(cluster_info, leader_pubkeys, port_selector)
+        .into_iter()
+        .map(|(_leader_pubkey, address)| address)
+        .collect()
+}
 
+/// Returns the `leader_pubkeys` with known contact information, paired with
+/// their socket selected by `port_selector`.
+pub(crate) fn leader_targets(
+    cluster_info: &impl LikeClusterInfo,
+    leader_pubkeys: impl IntoIterator<Item = Pubkey>,
+    port_selector: impl ContactInfoQuery<Option<SocketAddr>>,
+) -> Vec<(Pubkey, SocketAddr)> {
     leader_pubkeys
-        .iter()
+        .into_iter()
         .filter_map(|leader_pubkey| {
-            cluster_info.lookup_contact_info(leader_pubkey, &port_selector)?
+            let address = cluster_info.lookup_contact_info(&leader_pubkey, &port_selector)??;
+            Some((leader_pubkey, address))
         })
         .collect()
 }
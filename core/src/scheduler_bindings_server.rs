@@ -29,3 +29,186 @@ pub(crate) fn spawn(path: &Path, session_sender: mpsc::Sender<BankingControlMsg>
         })
         .unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            banking_stage::{
+                BankingStage, transaction_scheduler::scheduler_controller::SchedulerConfig,
+            },
+            banking_trace::{BankingTracer, Channels},
+            validator::{BlockProductionMethod, SchedulerPacing},
+        },
+        agave_banking_stage_ingress_types::{BankingPacketBatch, SchedulerPriorityFloor},
+        agave_external_pack::pack::{FeePerCuPack, PackConfig},
+        agave_scheduler_bindings::LEADER_READY,
+        agave_scheduling_utils::handshake::{ClientHandshakeError, ClientLogon, client},
+        crossbeam_channel::{bounded, never},
+        solana_entry::entry_or_marker::EntryOrMarker,
+        solana_ledger::{
+            blockstore::Blockstore, genesis_utils::create_genesis_config_with_leader,
+            get_tmp_ledger_path_auto_delete,
+        },
+        solana_perf::packet::to_packet_batches,
+        solana_poh::poh_recorder::create_test_recorder,
+        solana_runtime::{bank::Bank, genesis_utils::bootstrap_validator_stake_lamports},
+        solana_system_transaction as system_transaction,
+        std::{
+            num::NonZeroUsize,
+            sync::{Arc, atomic::Ordering},
+            thread::sleep,
+            time::{Duration, Instant},
+        },
+    };
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn pack_config() -> PackConfig {
+        PackConfig {
+            max_buffered: 1024,
+            ..PackConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_handshake_delivers_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scheduler_bindings.ipc");
+        let (session_sender, mut session_receiver) = mpsc::channel(1);
+        spawn(&path, session_sender);
+
+        // An invalid logon is rejected and does not stop the server.
+        let logon = ClientLogon {
+            worker_count: 0,
+            ..pack_config().logon(2)
+        };
+        assert!(matches!(
+            client::connect(&path, logon, TIMEOUT),
+            Err(ClientHandshakeError::Rejected(_))
+        ));
+
+        let session = client::connect(&path, pack_config().logon(2), TIMEOUT).unwrap();
+        assert_eq!(session.workers.len(), 2);
+        let Some(BankingControlMsg::External { session }) = session_receiver.blocking_recv() else {
+            panic!("expected an external session");
+        };
+        assert_eq!(session.workers.len(), 2);
+    }
+
+    #[test]
+    fn test_external_pack_end_to_end() {
+        agave_logger::setup();
+        let mut genesis_config_info = create_genesis_config_with_leader(
+            10_000_000_000,
+            &solana_pubkey::new_rand(),
+            bootstrap_validator_stake_lamports(),
+        );
+        // Stay in the first slot for the duration of the test.
+        genesis_config_info.genesis_config.ticks_per_slot *= 1024;
+        let genesis_config = genesis_config_info.genesis_config;
+        let mint_keypair = genesis_config_info.mint_keypair;
+        let (bank, bank_forks) = Bank::new_with_bank_forks_for_tests(&genesis_config);
+        let start_hash = bank.last_blockhash();
+        let Channels {
+            non_vote_sender,
+            non_vote_receiver,
+            tpu_vote_sender,
+            tpu_vote_receiver,
+            gossip_vote_sender,
+            gossip_vote_receiver,
+        } = BankingTracer::new_disabled().create_channels();
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Arc::new(Blockstore::open(ledger_path.path()).unwrap());
+        let (
+            exit,
+            poh_recorder,
+            _poh_controller,
+            transaction_recorder,
+            poh_service,
+            entry_receiver,
+        ) = create_test_recorder(bank.clone(), blockstore, None, None);
+        let (replay_vote_sender, _replay_vote_receiver) = bounded(1024);
+        let (banking_control_sender, banking_control_receiver) = mpsc::channel(1);
+
+        let banking_stage = BankingStage::new_num_threads(
+            BlockProductionMethod::CentralSchedulerGreedy,
+            poh_recorder,
+            transaction_recorder,
+            non_vote_receiver,
+            tpu_vote_receiver,
+            gossip_vote_receiver,
+            never(),
+            banking_control_receiver,
+            NonZeroUsize::new(2).unwrap(),
+            SchedulerConfig {
+                scheduler_pacing: SchedulerPacing::Disabled,
+                ..SchedulerConfig::default()
+            },
+            None,
+            replay_vote_sender,
+            None,
+            bank_forks,
+            None,
+            Arc::default(),
+            Arc::new(SchedulerPriorityFloor::new()),
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scheduler_bindings.ipc");
+        spawn(&path, banking_control_sender);
+
+        // Run the reference pack until it sees the transaction included.
+        let (leader_sender, leader_receiver) = bounded(1);
+        let pack_thread = std::thread::spawn(move || {
+            let session = client::connect(&path, pack_config().logon(2), TIMEOUT).unwrap();
+            let mut pack = FeePerCuPack::new(session, pack_config());
+            let start = Instant::now();
+            let mut leader = false;
+            while pack.stats().included == 0 {
+                assert!(start.elapsed() < TIMEOUT, "transaction was not included");
+                pack.poll();
+                // Progress is only published once the external scheduler
+                // replaced the internal one.
+                if !leader && pack.progress().leader_state == LEADER_READY {
+                    leader = true;
+                    leader_sender.send(()).unwrap();
+                }
+            }
+            *pack.stats()
+        });
+        leader_receiver.recv_timeout(TIMEOUT).unwrap();
+
+        let to = solana_pubkey::new_rand();
+        let transaction = system_transaction::transfer(&mint_keypair, &to, 1, start_hash);
+        non_vote_sender
+            .send(BankingPacketBatch::new(to_packet_batches(
+                &[transaction],
+                1,
+            )))
+            .unwrap();
+
+        let start = Instant::now();
+        let mut included = false;
+        while !included {
+            assert!(start.elapsed() < TIMEOUT, "transaction was not included");
+            match entry_receiver.try_recv() {
+                Ok((_bank, (EntryOrMarker::Entry(entry), _))) => {
+                    included = !entry.transactions.is_empty();
+                }
+                _ => sleep(Duration::from_millis(10)),
+            }
+        }
+        let stats = pack_thread.join().unwrap();
+        assert_eq!(stats.received, 1);
+        assert_eq!(stats.included, 1);
+
+        drop(non_vote_sender);
+        drop(tpu_vote_sender);
+        drop(gossip_vote_sender);
+        banking_stage.join().unwrap();
+        exit.store(true, Ordering::Relaxed);
+        poh_service.join().unwrap();
+    }
+}
@@ -23,11 +23,11 @@ use {
         tpu_entry_notifier::TpuEntryNotifier,
         validator::{BlockProductionMethod, GeneratorConfig},
     },
-    agave_banking_stage_ingress_types::SchedulerPriorityFloor,
+    agave_banking_stage_ingress_types::{BankingBundleBatch, SchedulerPriorityFloor},
     agave_votor::event::VotorEventSender,
     agave_votor_messages::VerifiedVoterSlotsSender,
     agave_xdp::transmitter::XdpSender,
-    crossbeam_channel::{Receiver, bounded, unbounded},
+    crossbeam_channel::{Receiver, Sender, bounded, unbounded},
     solana_clock::Slot,
     solana_gossip::cluster_info::ClusterInfo,
     solana_keypair::Keypair,
@@ -100,11 +100,15 @@ pub(crate) const TPU_VOTE_CHANNEL_SIZE: usize = 4_000;
 /// channel drops packets (tracked via streamer metrics) rather than blocking.
 const TPU_FORWARD_CHANNEL_SIZE: usize = 50_000;
 
+/// Capacity of the channel carrying bundles into banking stage.
+const TPU_BUNDLE_CHANNEL_SIZE: usize = 1_000;
+
 pub struct Tpu {
     fetch_stage: FetchStage,
     cluster_info_vote_listener: ClusterInfoVoteListener,
     sigverify_stage: SigVerifyStage,
     banking_stage: BankingStageHandle,
+    bundle_sender: Sender<BankingBundleBatch>,
     forwarding_stage: JoinHandle<()>,
     broadcast_stage: BroadcastStage,
     tpu_quic_t: thread::JoinHandle<()>,
@@ -206,6 +210,7 @@ impl Tpu {
             gossip_vote_sender,
             gossip_vote_receiver,
         } = banking_tracer_channels;
+        let (bundle_sender, bundle_receiver) = bounded(TPU_BUNDLE_CHANNEL_SIZE);
 
         // Streamer for Votes:
         let quic_vote_sockets: Vec<QuicSocket> =
@@ -316,6 +321,7 @@ impl Tpu {
             non_vote_receiver,
             tpu_vote_receiver,
             gossip_vote_receiver,
+            bundle_receiver,
             banking_control_receiver,
             block_production_num_workers,
             block_production_scheduler_config,
@@ -385,6 +391,7 @@ impl Tpu {
             cluster_info_vote_listener,
             sigverify_stage,
             banking_stage,
+            bundle_sender,
             forwarding_stage,
             broadcast_stage,
             tpu_quic_t,
@@ -396,6 +403,15 @@ impl Tpu {
         }
     }
 
+    /// Sender for bundles. Each `PacketBatch` is a bundle.
+    ///
+    /// This is an internal API for in-process components; nothing in the
+    /// validator sends on it yet. Bundles skip sigverify, so banking stage
+    /// verifies their signatures and drops any bundle that fails.
+    pub fn bundle_sender(&self) -> &Sender<BankingBundleBatch> {
+        &self.bundle_sender
+    }
+
     pub fn join(self) -> thread::Result<()> {
         let results = vec![
             self.fetch_stage.join(),
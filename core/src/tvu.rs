@@ -31,6 +31,7 @@ use {
         generated_cert_types::GeneratedCertTypes,
     },
     agave_votor::{
+        consensus_notifier::ConsensusEventSender,
         event::{LatestSwitchRequest, LeaderWindowInfo, VotorEventReceiver, VotorEventSender},
         vote_history::VoteHistory,
         vote_history_storage::VoteHistoryStorage,
@@ -187,6 +188,9 @@ pub struct AlpenglowInitializationState {
     pub votor_event_sender: VotorEventSender,
     pub votor_event_receiver: VotorEventReceiver,
 
+    // For Geyser consensus notifications
+    pub consensus_event_sender: Option<ConsensusEventSender>,
+
     // For BLS streamer setup
     pub cancel: CancellationToken,
     pub staked_nodes: Arc<RwLock<StakedNodes>>,
@@ -278,6 +282,7 @@ impl Tvu {
             bls_connection_cache,
             voting_service_test_override,
             highest_finalized,
+            consensus_event_sender,
         } = votor_init;
 
         // streamer and sigverify for A2A BLS messages
@@ -519,6 +524,7 @@ impl Tvu {
             highest_parent_ready,
             event_sender: votor_event_sender.clone(),
             latest_switch_request: latest_switch_request.clone(),
+            consensus_event_sender,
             own_vote_sender: own_message_sender.clone(),
             repair_event_sender,
             event_receiver: votor_event_receiver,
@@ -912,6 +918,7 @@ pub mod tests {
                 highest_finalized: Arc::new(RwLock::new(None)),
                 bank_forks_controller,
                 bank_forks_controller_receiver,
+                consensus_event_sender: None,
             },
             reward_votes_sender,
         )
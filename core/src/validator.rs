@@ -15,7 +15,7 @@ use {
             ExternalRootSource, Tower, reconcile_blockstore_roots_with_external_source,
             tower_storage::{NullTowerStorage, TowerStorage},
         },
-        forwarding_stage::ForwardingClientConfig,
+        forwarding_stage::{ForwardingClientConfig, forwarding_policy::ForwardingPolicyConfig},
         repair::{
             self, repair_handler::RepairHandlerType, serve_repair_service::ServeRepairService,
         },
@@ -59,6 +59,9 @@ use {
     },
     solana_geyser_plugin_manager::{
         GeyserPluginManagerRequest,
+        consensus_notifier::{
+            self as geyser_consensus_notifier, ConsensusNotifier as GeyserConsensusNotifier,
+        },
         contact_info_notifier::{
             self as geyser_contact_info_notifier, ContactInfoNotifier as GeyserContactInfoNotifier,
         },
@@ -112,8 +115,11 @@ use {
         },
         rpc::JsonRpcConfig,
         rpc_completed_slots_service::RpcCompletedSlotsService,
+        rpc_grpc_service::GrpcStreamService,
         rpc_pubsub_service::{PubSubConfig, PubSubService},
+        rpc_rate_limiter::RpcRateLimiter,
         rpc_service::{JsonRpcService, JsonRpcServiceConfig, RpcTpuClientArgs},
+        rpc_slow_query_log::RpcSlowQueryLog,
         rpc_subscriptions::RpcSubscriptions,
         transaction_notifier_interface::TransactionNotifierArc,
         transaction_status_service::TransactionStatusService,
@@ -173,9 +179,26 @@ use {
 const MAX_COMPLETED_DATA_SETS_IN_CHANNEL: usize = 100_000;
 const WAIT_FOR_SUPERMAJORITY_THRESHOLD_PERCENT: u64 = 80;
 
-#[derive(Clone, EnumCount, EnumIter, EnumString, VariantNames, Default, IntoStaticStr, Display)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    EnumCount,
+    EnumIter,
+    EnumString,
+    VariantNames,
+    Default,
+    IntoStaticStr,
+    Display,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+)]
 #[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
 pub enum BlockVerificationMethod {
+    BlockstoreProcessor,
     #[default]
     UnifiedScheduler,
 }
@@ -211,6 +234,7 @@ pub enum BlockProductionMethod {
     CentralScheduler,
     #[default]
     CentralSchedulerGreedy,
+    CentralSchedulerPrioGraph,
 }
 
 impl BlockProductionMethod {
@@ -372,6 +396,8 @@ pub struct ValidatorConfig {
     pub accounts_db_skip_shrink: bool,
     pub accounts_db_force_initial_clean: bool,
     pub staked_nodes_overrides: Arc<RwLock<HashMap<Pubkey, u64>>>,
+    pub rpc_rate_limiter: Arc<RpcRateLimiter>,
+    pub rpc_slow_query_log: Option<Arc<RpcSlowQueryLog>>,
     pub validator_exit: Arc<RwLock<Exit>>,
     pub validator_exit_backpressure: HashMap<String, Arc<AtomicBool>>,
     pub no_wait_for_vote_to_start_leader: bool,
@@ -383,6 +409,7 @@ pub struct ValidatorConfig {
     pub block_production_num_workers: NonZeroUsize,
     pub block_production_scheduler_config: SchedulerConfig,
     pub enable_block_production_forwarding: bool,
+    pub forwarding_policy: ForwardingPolicyConfig,
     pub enable_scheduler_bindings: bool,
     pub generator_config: Option<GeneratorConfig>,
     pub use_snapshot_archives_at_startup: UseSnapshotArchivesAtStartup,
@@ -454,6 +481,8 @@ impl ValidatorConfig {
             accounts_db_skip_shrink: false,
             accounts_db_force_initial_clean: false,
             staked_nodes_overrides: Arc::new(RwLock::new(HashMap::new())),
+            rpc_rate_limiter: Arc::new(RpcRateLimiter::default()),
+            rpc_slow_query_log: None,
             validator_exit: Arc::new(RwLock::new(Exit::default())),
             validator_exit_backpressure: HashMap::default(),
             no_wait_for_vote_to_start_leader: true,
@@ -467,6 +496,7 @@ impl ValidatorConfig {
             block_production_scheduler_config: SchedulerConfig::default(),
             // enable forwarding by default for tests
             enable_block_production_forwarding: true,
+            forwarding_policy: ForwardingPolicyConfig::default(),
             enable_scheduler_bindings: false,
             generator_config: None,
             use_snapshot_archives_at_startup: UseSnapshotArchivesAtStartup::default(),
@@ -649,6 +679,7 @@ pub struct Validator {
     log_config: Option<ValidatorLogConfig>,
     json_rpc_service: Option<JsonRpcService>,
     pubsub_service: Option<PubSubService>,
+    grpc_stream_service: Option<GrpcStreamService>,
     rpc_completed_slots_service: Option<JoinHandle<()>>,
     optimistically_confirmed_bank_tracker: Option<OptimisticallyConfirmedBankTracker>,
     transaction_status_service: Option<TransactionStatusService>,
@@ -674,6 +705,10 @@ pub struct Validator {
     /// running. `None` when no loaded plugin opted into contact info
     /// notifications.
     _contact_info_notifier: Option<GeyserContactInfoNotifier>,
+    /// Held for the lifetime of the validator so the dispatch thread keeps
+    /// running. `None` when no loaded plugin opted into consensus
+    /// notifications.
+    _consensus_notifier: Option<GeyserConsensusNotifier>,
     blockstore_metric_report_service: BlockstoreMetricReportService,
     accounts_background_service: AccountsBackgroundService,
     xdp_transmitter: Option<Transmitter>,
@@ -936,6 +971,14 @@ impl Validator {
         )
         .map_err(ValidatorError::Other)?;
 
+        if let Some(geyser_plugin_service) = &geyser_plugin_service {
+            geyser_plugin_service.set_catch_up_blockstore(
+                blockstore.clone(),
+                config.rpc_addrs.is_some() && config.rpc_config.enable_rpc_transaction_history,
+            );
+            geyser_plugin_service.set_account_restream_bank_forks(bank_forks.clone());
+        }
+
         let migration_status = bank_forks.read().unwrap().migration_status();
 
         if !config.no_poh_speed_test && !migration_status.is_alpenglow_enabled() {
@@ -1009,6 +1052,18 @@ impl Validator {
             )
         });
 
+        // Opt-in Geyser notifications for votor votes, certificates and
+        // finalized blocks, with the same no-op default as above.
+        let (consensus_notifier, consensus_event_sender) = geyser_plugin_service
+            .as_ref()
+            .and_then(|service| {
+                geyser_consensus_notifier::attach(
+                    service.plugin_manager_handle(),
+                    geyser_consensus_notifier::DEFAULT_CHANNEL_CAPACITY,
+                )
+            })
+            .unzip();
+
         assert!(is_snapshot_config_valid(&config.snapshot_config));
 
         let (snapshot_request_sender, snapshot_request_receiver) = unbounded();
@@ -1111,10 +1166,17 @@ impl Validator {
             Some(replay_vote_sender.clone()),
             prioritization_fee_cache.clone(),
         );
-        bank_forks
-            .write()
-            .unwrap()
-            .install_scheduler_pool(scheduler_pool);
+        // The pool is installed regardless of the block verification method, so that replay can
+        // be switched to the unified scheduler at runtime via the admin rpc.
+        let weak_scheduler_pool = Arc::downgrade(&scheduler_pool);
+        {
+            let mut bank_forks = bank_forks.write().unwrap();
+            bank_forks.install_scheduler_pool(scheduler_pool);
+            bank_forks.set_block_verification_scheduler_enabled(matches!(
+                config.block_verification_method,
+                BlockVerificationMethod::UnifiedScheduler
+            ));
+        }
 
         let entry_notification_sender = entry_notifier_service
             .as_ref()
@@ -1244,6 +1306,7 @@ impl Validator {
             json_rpc_service,
             rpc_subscriptions,
             pubsub_service,
+            grpc_stream_service,
             completed_data_sets_sender,
             completed_data_sets_service,
             rpc_completed_slots_service,
@@ -1297,6 +1360,8 @@ impl Validator {
                 leader_schedule_cache: leader_schedule_cache.clone(),
                 max_complete_transaction_status_slot: max_complete_transaction_status_slot.clone(),
                 prioritization_fee_cache: prioritization_fee_cache.clone(),
+                rpc_rate_limiter: config.rpc_rate_limiter.clone(),
+                rpc_slow_query_log: config.rpc_slow_query_log.clone(),
                 rpc_tpu_client_args,
             };
             let json_rpc_service =
@@ -1327,6 +1392,23 @@ impl Validator {
 
                 Some(pubsub_service)
             };
+            let grpc_stream_service = config
+                .pubsub_config
+                .grpc_addr
+                .filter(|_| config.rpc_config.full_api)
+                .map(|grpc_addr| {
+                    let (trigger, grpc_stream_service) = GrpcStreamService::new(
+                        config.pubsub_config.clone(),
+                        &rpc_subscriptions,
+                        grpc_addr,
+                    );
+                    config
+                        .validator_exit
+                        .write()
+                        .unwrap()
+                        .register_exit(Box::new(move || trigger.cancel()));
+                    grpc_stream_service
+                });
 
             let (completed_data_sets_sender, completed_data_sets_service) =
                 if !config.rpc_config.full_api {
@@ -1398,6 +1480,7 @@ impl Validator {
                 Some(json_rpc_service),
                 Some(rpc_subscriptions),
                 pubsub_service,
+                grpc_stream_service,
                 completed_data_sets_sender,
                 completed_data_sets_service,
                 rpc_completed_slots_service,
@@ -1406,7 +1489,7 @@ impl Validator {
                 bank_notification_sender_config,
             )
         } else {
-            (None, None, None, None, None, None, None, None, None)
+            (None, None, None, None, None, None, None, None, None, None)
         };
 
         let ip_echo_server = match node.sockets.ip_echo {
@@ -1678,6 +1761,7 @@ impl Validator {
                 bls_connection_cache,
                 voting_service_test_override: config.voting_service_test_override.clone(),
                 highest_finalized,
+                consensus_event_sender,
             },
             reward_votes_sender,
         )
@@ -1694,6 +1778,7 @@ impl Validator {
                 runtime_handle: runtime_handle.clone(),
                 cancel: cancel.clone(),
                 node_multihoming: node_multihoming.clone(),
+                policy: config.forwarding_policy.clone(),
             }
         };
         let (banking_control_sender, banking_control_receiver) = mpsc::channel(1);
@@ -1791,6 +1876,7 @@ impl Validator {
             snapshot_controller,
             blockstore: blockstore.clone(),
             votor_event_sender,
+            scheduler_pool: weak_scheduler_pool,
         });
 
         Ok(Self {
@@ -1801,6 +1887,7 @@ impl Validator {
             serve_repair_service,
             json_rpc_service,
             pubsub_service,
+            grpc_stream_service,
             rpc_completed_slots_service,
             optimistically_confirmed_bank_tracker,
             transaction_status_service,
@@ -1821,6 +1908,7 @@ impl Validator {
             blockstore,
             geyser_plugin_service,
             _contact_info_notifier: contact_info_notifier,
+            _consensus_notifier: consensus_notifier,
             blockstore_metric_report_service,
             accounts_background_service,
             xdp_transmitter,
@@ -1933,6 +2021,10 @@ impl Validator {
             pubsub_service.join().expect("pubsub_service");
         }
 
+        if let Some(grpc_stream_service) = self.grpc_stream_service {
+            grpc_stream_service.join().expect("grpc_stream_service");
+        }
+
         if let Some(rpc_completed_slots_service) = self.rpc_completed_slots_service {
             rpc_completed_slots_service
                 .join()
@@ -2396,6 +2488,7 @@ fn load_blockstore(
                 exit.clone(),
                 enable_rpc_transaction_history,
                 config.rpc_config.enable_extended_tx_metadata_storage,
+                config.rpc_config.accounts_diff_retention_slots,
                 transaction_notifier,
                 dependency_tracker,
             )
@@ -2874,14 +2967,19 @@ fn initialize_rpc_transaction_history_services(
     exit: Arc<AtomicBool>,
     enable_rpc_transaction_history: bool,
     enable_extended_tx_metadata_storage: bool,
+    accounts_diff_retention_slots: Option<u64>,
     transaction_notifier: Option<TransactionNotifierArc>,
     dependency_tracker: Option<Arc<DependencyTracker>>,
 ) -> TransactionHistoryServices {
     let max_complete_transaction_status_slot = Arc::new(AtomicU64::new(blockstore.max_root()));
     let (transaction_status_sender, transaction_status_receiver) = unbounded();
+    // Accounts diffs are stored alongside the rest of the transaction history
+    let accounts_diff_retention_slots =
+        accounts_diff_retention_slots.filter(|_| enable_rpc_transaction_history);
     let transaction_status_sender = Some(TransactionStatusSender {
         sender: transaction_status_sender,
         dependency_tracker: dependency_tracker.clone(),
+        record_post_accounts: accounts_diff_retention_slots.is_some(),
     });
     let transaction_status_service = Some(TransactionStatusService::new(
         transaction_status_receiver,
@@ -2890,6 +2988,7 @@ fn initialize_rpc_transaction_history_services(
         transaction_notifier,
         blockstore,
         enable_extended_tx_metadata_storage,
+        accounts_diff_retention_slots,
         dependency_tracker,
         exit,
     ));
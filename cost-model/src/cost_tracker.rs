@@ -24,6 +24,9 @@ use {
 
 const WRITABLE_ACCOUNTS_PER_BLOCK: usize = 4096;
 
+/// Utilization of the per-account limit is expressed in basis points.
+pub const MAX_UTILIZATION_BPS: u64 = 10_000;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CostTrackerError {
     /// would exceed block max limit
@@ -237,6 +240,54 @@ impl CostTracker {
         self.transaction_count.0
     }
 
+    /// Get the cost accumulated so far against the write lock of `account_key`.
+    pub fn account_cost(&self, account_key: &Pubkey) -> u64 {
+        self.cost_by_writable_accounts
+            .get(account_key)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Get the utilization of the per-account limit by `account_key`, in
+    /// basis points. Saturates at `MAX_UTILIZATION_BPS`.
+    pub fn account_utilization_bps(&self, account_key: &Pubkey) -> u64 {
+        self.utilization_bps(self.account_cost(account_key))
+    }
+
+    /// Iterate over the write-locked accounts whose utilization of the
+    /// per-account limit is at least `min_utilization_bps`.
+    pub fn utilized_accounts(
+        &self,
+        min_utilization_bps: u64,
+    ) -> impl Iterator<Item = (&Pubkey, u64)> + '_ {
+        self.cost_by_writable_accounts
+            .iter()
+            .map(|(account_key, &cost)| (account_key, self.utilization_bps(cost)))
+            .filter(move |(_, utilization_bps)| *utilization_bps >= min_utilization_bps)
+    }
+
+    /// Report the utilization of every account at or above
+    /// `min_utilization_bps`, one datapoint per account. Every reported
+    /// account holds that share of the account limit, so the block limit
+    /// bounds the number of datapoints.
+    pub fn report_account_utilization(
+        &self,
+        bank_slot: solana_clock::Slot,
+        is_leader: bool,
+        min_utilization_bps: u64,
+    ) {
+        for (account_key, utilization_bps) in self.utilized_accounts(min_utilization_bps) {
+            datapoint_info!(
+                "cost_tracker_account_utilization",
+                "is_leader" => is_leader.to_string(),
+                ("bank_slot", bank_slot, i64),
+                ("account", account_key.to_string(), String),
+                ("account_cost", self.account_cost(account_key), i64),
+                ("utilization_bps", utilization_bps, i64),
+            );
+        }
+    }
+
     pub fn report_stats(
         &self,
         bank_slot: solana_clock::Slot,
@@ -305,6 +356,14 @@ impl CostTracker {
             .unwrap_or_default()
     }
 
+    fn utilization_bps(&self, cost: u64) -> u64 {
+        if self.limits.account_cost == 0 {
+            return MAX_UTILIZATION_BPS;
+        }
+        (u128::from(cost) * u128::from(MAX_UTILIZATION_BPS) / u128::from(self.limits.account_cost))
+            .min(u128::from(MAX_UTILIZATION_BPS)) as u64
+    }
+
     fn find_number_of_contended_accounts(&self) -> usize {
         // accounts has more than 95% of account_cu_limit is considered as highly contended
         let contended_cost_mark: u64 = self
@@ -1029,4 +1088,34 @@ mod tests {
             cost_tracker.cost_by_writable_accounts
         );
     }
+
+    #[test]
+    fn test_account_utilization() {
+        let mut cost_tracker = CostTracker::new(1_000, 10_000);
+        let hot_account = Pubkey::new_unique();
+        let cold_account = Pubkey::new_unique();
+        let untouched_account = Pubkey::new_unique();
+
+        let transaction = WritableKeysTransaction::new(vec![hot_account]);
+        cost_tracker.add_transaction_cost(&simple_transaction_cost(&transaction, 750));
+        let transaction = WritableKeysTransaction::new(vec![cold_account]);
+        cost_tracker.add_transaction_cost(&simple_transaction_cost(&transaction, 100));
+
+        assert_eq!(cost_tracker.account_cost(&hot_account), 750);
+        assert_eq!(cost_tracker.account_cost(&untouched_account), 0);
+        assert_eq!(cost_tracker.account_utilization_bps(&hot_account), 7_500);
+        assert_eq!(cost_tracker.account_utilization_bps(&cold_account), 1_000);
+        assert_eq!(cost_tracker.account_utilization_bps(&untouched_account), 0);
+
+        let utilized_accounts = cost_tracker.utilized_accounts(5_000).collect::<Vec<_>>();
+        assert_eq!(utilized_accounts, vec![(&hot_account, 7_500)]);
+        assert_eq!(cost_tracker.utilized_accounts(0).count(), 2);
+
+        // utilization saturates once the account limit is exceeded
+        cost_tracker.set_limits(CostTrackerLimits::new(500, 10_000, u64::MAX));
+        assert_eq!(
+            cost_tracker.account_utilization_bps(&hot_account),
+            MAX_UTILIZATION_BPS
+        );
+    }
 }
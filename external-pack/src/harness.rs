@@ -0,0 +1,266 @@
+//! Creates the shared memory allocator and queues of a scheduler bindings
+//! session in process, and plays the validator side of the protocol.
+//!
+//! The harness hands out the [`ClientSession`] a pack would receive from the
+//! validator's handshake, queues transactions and progress messages to it,
+//! and answers the batches the pack sends to the workers with responses
+//! chosen by the caller.
+
+use {
+    agave_scheduler_bindings::{
+        LEADER_READY, PackToWorkerMessage, ProgressMessage, SharablePubkeys,
+        SharableTransactionRegion, TpuToPackMessage, TransactionResponseRegion,
+        WorkerToPackMessage, pack_message_flags, processed_codes,
+        worker_message_types::{
+            CheckResponse, ExecutionResponse, fee_payer_balance_flags, not_included_reasons,
+            resolve_flags, status_check_flags,
+        },
+    },
+    agave_scheduling_utils::{
+        handshake::{
+            AgaveHandshakeError, AgaveSession, ClientHandshakeError, ClientLogon, ClientSession,
+            client, server::Server,
+        },
+        responses_region::{execution_responses_from_iter, resolve_responses_from_iter},
+        transaction_ptr::TransactionPtrBatch,
+    },
+    agave_transaction_view::transaction_data::TransactionData,
+    thiserror::Error,
+};
+
+#[derive(Debug, Error)]
+pub enum HarnessError {
+    #[error("Agave; err={0}")]
+    Agave(#[from] AgaveHandshakeError),
+    #[error("Client; err={0}")]
+    Client(#[from] ClientHandshakeError),
+}
+
+/// A batch the pack sent to a worker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerRequest {
+    pub worker: usize,
+    pub flags: u16,
+    pub max_working_slot: u64,
+    /// The serialized transactions of the batch
+    pub transactions: Vec<Vec<u8>>,
+}
+
+impl WorkerRequest {
+    pub fn is_execute(&self) -> bool {
+        self.flags & pack_message_flags::EXECUTE != 0
+    }
+}
+
+pub struct LocalHarness {
+    agave: AgaveSession,
+    current_slot: u64,
+}
+
+impl LocalHarness {
+    /// Creates the session requested by `logon`, returning the client side
+    /// to be given to the pack. The logon is not validated, see
+    /// [`Server::setup_session`].
+    pub fn new(logon: ClientLogon) -> Result<(Self, ClientSession), HarnessError> {
+        let (agave, files) = Server::setup_session(logon)?;
+        let client_session = client::setup_session(&logon, files)?;
+
+        Ok((
+            Self {
+                agave,
+                current_slot: 0,
+            },
+            client_session,
+        ))
+    }
+
+    /// Copies `transaction` to shared memory and queues it to the pack.
+    /// Returns `false` if the queue is full.
+    ///
+    /// # Panics
+    ///
+    /// - If the shared memory allocation fails.
+    pub fn queue_tpu(&mut self, transaction: &[u8], flags: u8) -> bool {
+        let tpu_to_pack = &mut self.agave.tpu_to_pack;
+        tpu_to_pack.producer.sync();
+        if tpu_to_pack.producer.len() == tpu_to_pack.producer.capacity() {
+            return false;
+        }
+
+        let ptr = tpu_to_pack
+            .allocator
+            .allocate(u32::try_from(transaction.len()).unwrap())
+            .expect("shared memory allocation");
+        // SAFETY:
+        // - We own the allocation exclusively and it holds `transaction.len()` bytes.
+        let offset = unsafe {
+            std::ptr::copy_nonoverlapping(transaction.as_ptr(), ptr.as_ptr(), transaction.len());
+            tpu_to_pack.allocator.offset(ptr)
+        };
+
+        // Ownership of the allocation is transferred to the pack.
+        tpu_to_pack
+            .producer
+            .try_write(TpuToPackMessage {
+                transaction: SharableTransactionRegion {
+                    offset,
+                    length: transaction.len() as u32,
+                },
+                flags,
+                src_addr: [0; 16],
+            })
+            .expect("space checked above");
+        tpu_to_pack.producer.commit();
+        true
+    }
+
+    /// Queues `progress` to the pack. Batches sent for execution with a
+    /// `max_working_slot` before `progress.current_slot` are then answered as
+    /// not processed. Returns `false` if the queue is full.
+    pub fn queue_progress(&mut self, progress: ProgressMessage) -> bool {
+        self.current_slot = progress.current_slot;
+        let progress_tracker = &mut self.agave.progress_tracker;
+        progress_tracker.sync();
+        if progress_tracker.try_write(progress).is_err() {
+            return false;
+        }
+        progress_tracker.commit();
+        true
+    }
+
+    /// Answers the batches the pack sent to the workers, with `check` for
+    /// the transactions sent for checks and `execute` for the transactions
+    /// sent for execution. Returns the answered batches.
+    pub fn respond(
+        &mut self,
+        mut check: impl FnMut(&[u8]) -> CheckResponse,
+        mut execute: impl FnMut(&[u8]) -> ExecutionResponse,
+    ) -> Vec<WorkerRequest> {
+        let mut requests = vec![];
+        for (index, worker) in self.agave.workers.iter_mut().enumerate() {
+            worker.pack_to_worker.sync();
+            while let Some(message) = worker.pack_to_worker.try_read().copied() {
+                let request = Self::read_request(index, &message, &worker.allocator);
+                let (processed_code, responses) =
+                    if request.is_execute() && request.max_working_slot < self.current_slot {
+                        (
+                            processed_codes::MAX_WORKING_SLOT_EXCEEDED,
+                            TransactionResponseRegion {
+                                tag: 0,
+                                num_transaction_responses: 0,
+                                transaction_responses_offset: 0,
+                            },
+                        )
+                    } else if request.is_execute() {
+                        let responses = request.transactions.iter().map(|tx| execute(tx));
+                        (
+                            processed_codes::PROCESSED,
+                            execution_responses_from_iter(&worker.allocator, responses)
+                                .expect("shared memory allocation"),
+                        )
+                    } else {
+                        let responses = request.transactions.iter().map(|tx| check(tx));
+                        (
+                            processed_codes::PROCESSED,
+                            resolve_responses_from_iter(&worker.allocator, responses)
+                                .expect("shared memory allocation"),
+                        )
+                    };
+
+                // The batch is returned to the pack, which frees it.
+                worker.worker_to_pack.sync();
+                worker
+                    .worker_to_pack
+                    .try_write(WorkerToPackMessage {
+                        batch: message.batch,
+                        processed_code,
+                        responses,
+                    })
+                    .expect("worker_to_pack queue is full");
+                worker.worker_to_pack.commit();
+                requests.push(request);
+            }
+            worker.pack_to_worker.finalize();
+        }
+
+        requests
+    }
+
+    fn read_request(
+        worker: usize,
+        message: &PackToWorkerMessage,
+        allocator: &rts_alloc::Allocator,
+    ) -> WorkerRequest {
+        // SAFETY:
+        // - The pack allocated the batch and its transactions in the shared allocator.
+        // - The metadata of the batch is not read.
+        let batch = unsafe {
+            TransactionPtrBatch::<()>::from_sharable_transaction_batch_region(
+                &message.batch,
+                allocator,
+            )
+        };
+        let transactions = batch.iter().map(|(tx, ())| tx.data().to_vec()).collect();
+
+        WorkerRequest {
+            worker,
+            flags: message.flags,
+            max_working_slot: message.max_working_slot,
+            transactions,
+        }
+    }
+}
+
+/// A progress message for a leader slot with a working bank
+pub fn leader_ready(slot: u64, remaining_cost_units: u64) -> ProgressMessage {
+    ProgressMessage {
+        leader_state: LEADER_READY,
+        current_slot_progress: 0,
+        epoch: 0,
+        current_slot: slot,
+        next_leader_slot: u64::MAX,
+        leader_range_end: u64::MAX,
+        remaining_cost_units,
+        latest_blockhash: [0; 32],
+    }
+}
+
+/// A check response for a transaction passing every check
+pub fn check_ok(slot: u64, fee_payer_balance: u64) -> CheckResponse {
+    CheckResponse {
+        parsing_and_sanitization_flags: 0,
+        status_check_flags: status_check_flags::REQUESTED | status_check_flags::PERFORMED,
+        fee_payer_balance_flags: fee_payer_balance_flags::REQUESTED
+            | fee_payer_balance_flags::PERFORMED,
+        resolve_flags: resolve_flags::REQUESTED | resolve_flags::PERFORMED,
+        included_slot: 0,
+        balance_slot: slot,
+        fee_payer_balance,
+        resolution_slot: slot,
+        min_alt_deactivation_slot: u64::MAX,
+        resolved_pubkeys: SharablePubkeys {
+            offset: 0,
+            num_pubkeys: 0,
+        },
+    }
+}
+
+/// An execution response for a transaction included in the block
+pub fn executed(slot: u64, cost_units: u64) -> ExecutionResponse {
+    ExecutionResponse {
+        execution_slot: slot,
+        not_included_reason: not_included_reasons::NONE,
+        cost_units,
+        fee_payer_balance: 0,
+    }
+}
+
+/// An execution response for a transaction not included in the block
+pub fn not_included(slot: u64, reason: u8) -> ExecutionResponse {
+    ExecutionResponse {
+        execution_slot: slot,
+        not_included_reason: reason,
+        cost_units: 0,
+        fee_payer_balance: 0,
+    }
+}
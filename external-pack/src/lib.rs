@@ -0,0 +1,18 @@
+//! Reference external pack process for the Agave scheduler bindings.
+//!
+//! The pack receives transactions from the validator over the `tpu_to_pack`
+//! queue, has the workers check them (status, fee-payer balance and address
+//! lookup tables), and then sends the checked transactions for execution in
+//! order of the fee they pay per requested compute unit while the validator
+//! is leader. See `agave-scheduler-bindings` for the protocol.
+//!
+//! [`harness::LocalHarness`] creates the shared memory allocator and queues of
+//! a session in process and plays the validator side of the protocol, so a
+//! pack can be tested without a validator.
+
+#[cfg(unix)]
+pub mod harness;
+#[cfg(unix)]
+pub mod pack;
+#[cfg(unix)]
+pub mod priority;
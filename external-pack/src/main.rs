@@ -0,0 +1,82 @@
+#[cfg(unix)]
+fn main() {
+    use {
+        agave_external_pack::pack::{FeePerCuPack, PackConfig},
+        agave_scheduling_utils::handshake::client,
+        clap::{App, Arg, crate_description, crate_name, value_t_or_exit},
+        log::info,
+        std::{path::PathBuf, sync::atomic::AtomicBool, time::Duration},
+    };
+
+    agave_logger::setup();
+
+    let default_config = PackConfig::default();
+    let default_batch_size = default_config.batch_size.to_string();
+    let default_max_buffered = default_config.max_buffered.to_string();
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(solana_version::version!())
+        .arg(
+            Arg::with_name("bindings_path")
+                .long("bindings-path")
+                .value_name("PATH")
+                .takes_value(true)
+                .required(true)
+                .help("Path of the scheduler bindings socket of the validator"),
+        )
+        .arg(
+            Arg::with_name("workers")
+                .long("workers")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .default_value("4")
+                .help("Number of validator worker threads to request"),
+        )
+        .arg(
+            Arg::with_name("batch_size")
+                .long("batch-size")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .default_value(&default_batch_size)
+                .help("Maximum number of transactions in a batch sent to a worker"),
+        )
+        .arg(
+            Arg::with_name("max_buffered")
+                .long("max-buffered")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .default_value(&default_max_buffered)
+                .help("Maximum number of transactions held by the pack"),
+        )
+        .get_matches();
+
+    let bindings_path = value_t_or_exit!(matches, "bindings_path", PathBuf);
+    let workers = value_t_or_exit!(matches, "workers", usize);
+    let config = PackConfig {
+        batch_size: value_t_or_exit!(matches, "batch_size", usize),
+        max_buffered: value_t_or_exit!(matches, "max_buffered", usize),
+        ..default_config
+    };
+
+    let session = client::connect(
+        &bindings_path,
+        config.logon(workers),
+        Duration::from_secs(5),
+    )
+    .unwrap_or_else(|err| {
+        eprintln!("Failed to connect to {}: {err}", bindings_path.display());
+        std::process::exit(1);
+    });
+    info!(
+        "Connected to {}; workers={workers}",
+        bindings_path.display()
+    );
+
+    FeePerCuPack::new(session, config).run(&AtomicBool::new(false));
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("The scheduler bindings are only supported on unix");
+    std::process::exit(1);
+}
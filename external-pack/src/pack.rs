@@ -0,0 +1,755 @@
+//! A pack sending transactions for execution in order of the fee they pay
+//! per requested compute unit.
+//!
+//! Received transactions are first sent to the workers for checks (status,
+//! fee-payer balance and address lookup tables). Transactions passing their
+//! checks are sent for execution while the validator is leader, highest
+//! priority first, as long as the compute units they request fit in the
+//! remaining block budget and their accounts can be locked on a worker.
+
+use {
+    crate::priority::FeePerCu,
+    agave_scheduler_bindings::{
+        LEADER_READY, MAX_TRANSACTIONS_PER_MESSAGE, ProgressMessage,
+        pack_message_flags::{self, check_flags},
+        worker_message_types::{
+            CheckResponse, fee_payer_balance_flags, not_included_reasons,
+            parsing_and_sanitization_flags, resolve_flags, status_check_flags,
+        },
+    },
+    agave_scheduling_utils::{
+        bridge::{
+            KeyedTransactionMeta, ScheduleBatch, SchedulerBindingsBridge, TransactionKey,
+            TxDecision, WorkerAction, WorkerResponse,
+        },
+        handshake::{ClientLogon, ClientSession},
+        thread_aware_account_locks::{ThreadAwareAccountLocks, ThreadSet},
+    },
+    log::{info, warn},
+    std::{
+        collections::{BTreeSet, HashMap},
+        sync::atomic::{AtomicBool, Ordering},
+        time::{Duration, Instant},
+    },
+};
+
+const TPU_TO_PACK_CAPACITY: usize = 8192;
+const PROGRESS_TRACKER_CAPACITY: usize = 256;
+const PACK_TO_WORKER_CAPACITY: usize = 64;
+const WORKER_TO_PACK_CAPACITY: usize = 64;
+
+/// Shared memory requested per transaction, the largest allocation size
+const ALLOCATION_PER_TRANSACTION: usize = 4096;
+/// Shared memory requested for the batches and responses in flight
+const ALLOCATION_HEADROOM: usize = 64 * 1024 * 1024;
+
+const STATS_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Flags of the batches sent to the workers for checks
+const CHECK_FLAGS: u16 = pack_message_flags::CHECK
+    | check_flags::STATUS_CHECKS
+    | check_flags::LOAD_FEE_PAYER_BALANCE
+    | check_flags::LOAD_ADDRESS_LOOKUP_TABLES;
+
+/// Status check flags failing a transaction
+const STATUS_CHECK_FAILURES: u8 = status_check_flags::TOO_OLD
+    | status_check_flags::ALREADY_PROCESSED
+    | status_check_flags::INVALID_NONCE
+    | status_check_flags::UNSUPPORTED_VERSION;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackConfig {
+    /// Maximum number of transactions in a batch sent to a worker
+    pub batch_size: usize,
+    /// Maximum number of transactions held by the pack, the lowest priority
+    /// transactions are evicted beyond it
+    pub max_buffered: usize,
+    /// Maximum number of checked transactions considered for execution per
+    /// poll
+    pub lookahead: usize,
+}
+
+impl Default for PackConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 16,
+            max_buffered: 50_000,
+            lookahead: 2048,
+        }
+    }
+}
+
+impl PackConfig {
+    /// The logon requesting a session sized for this configuration
+    pub fn logon(&self, worker_count: usize) -> ClientLogon {
+        ClientLogon {
+            worker_count,
+            allocator_size: self
+                .max_buffered
+                .saturating_add(TPU_TO_PACK_CAPACITY)
+                .saturating_mul(ALLOCATION_PER_TRANSACTION)
+                .saturating_add(ALLOCATION_HEADROOM),
+            allocator_handles: 1,
+            tpu_to_pack_capacity: TPU_TO_PACK_CAPACITY,
+            progress_tracker_capacity: PROGRESS_TRACKER_CAPACITY,
+            pack_to_worker_capacity: PACK_TO_WORKER_CAPACITY,
+            worker_to_pack_capacity: WORKER_TO_PACK_CAPACITY,
+            flags: 0,
+        }
+    }
+}
+
+/// What a transaction was sent to a worker for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Check,
+    Execute,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PackStats {
+    /// Transactions received from the validator
+    pub received: u64,
+    /// Transactions failing sanitization or with an invalid compute budget
+    pub invalid: u64,
+    /// Transactions evicted, or not admitted, because the buffer was full
+    pub evicted: u64,
+    /// Transactions failing their checks
+    pub check_failed: u64,
+    /// Transactions included in a block
+    pub included: u64,
+    /// Transactions executed but not included in a block
+    pub not_included: u64,
+    /// Transactions sent again for execution after a retryable failure
+    pub retried: u64,
+}
+
+pub struct FeePerCuPack {
+    bridge: SchedulerBindingsBridge<Stage>,
+    config: PackConfig,
+    locks: ThreadAwareAccountLocks,
+    /// Priority of every transaction held by the pack
+    priorities: HashMap<TransactionKey, FeePerCu>,
+    /// Transactions waiting to be sent for checks
+    unchecked: BTreeSet<(u64, TransactionKey)>,
+    /// Transactions that passed their checks, waiting to be sent for
+    /// execution
+    checked: BTreeSet<(u64, TransactionKey)>,
+    /// Compute units requested by the transactions sent for execution and
+    /// not answered yet
+    in_flight_compute_units: u64,
+    stats: PackStats,
+}
+
+impl FeePerCuPack {
+    /// # Panics
+    ///
+    /// - If `config.batch_size` is zero or above [`MAX_TRANSACTIONS_PER_MESSAGE`].
+    pub fn new(session: ClientSession, config: PackConfig) -> Self {
+        assert!((1..=MAX_TRANSACTIONS_PER_MESSAGE).contains(&config.batch_size));
+        let bridge = SchedulerBindingsBridge::new(session);
+        let locks = ThreadAwareAccountLocks::new(bridge.worker_count());
+
+        Self {
+            bridge,
+            config,
+            locks,
+            priorities: HashMap::new(),
+            unchecked: BTreeSet::new(),
+            checked: BTreeSet::new(),
+            in_flight_compute_units: 0,
+            stats: PackStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> &PackStats {
+        &self.stats
+    }
+
+    /// The last progress message received from the validator
+    pub fn progress(&self) -> &ProgressMessage {
+        self.bridge.progress()
+    }
+
+    /// Number of transactions held by the pack, including the ones sent to
+    /// the workers
+    pub fn buffered(&self) -> usize {
+        self.priorities.len()
+    }
+
+    /// Polls the pack until `exit` is set
+    pub fn run(mut self, exit: &AtomicBool) {
+        let mut last_report = Instant::now();
+        while !exit.load(Ordering::Relaxed) {
+            self.poll();
+
+            if last_report.elapsed() >= STATS_REPORT_INTERVAL {
+                info!(
+                    "external pack; buffered={} in_flight_compute_units={} stats={:?}",
+                    self.buffered(),
+                    self.in_flight_compute_units,
+                    self.stats,
+                );
+                last_report = Instant::now();
+            }
+        }
+    }
+
+    /// Handles the messages received from the validator and sends the
+    /// transactions that can be sent to the workers
+    pub fn poll(&mut self) {
+        self.bridge.drain_progress();
+        self.drain_workers();
+        self.drain_tpu();
+        self.schedule_checks();
+        self.schedule_execution();
+    }
+
+    fn drain_tpu(&mut self) {
+        let Self {
+            bridge,
+            config,
+            priorities,
+            unchecked,
+            checked,
+            stats,
+            ..
+        } = self;
+
+        let sanitize_failures = bridge.drain_tpu(
+            |bridge, key| {
+                stats.received = stats.received.saturating_add(1);
+                let Some(fee_per_cu) =
+                    FeePerCu::new(&bridge.transaction(key).data, bridge.runtime())
+                else {
+                    stats.invalid = stats.invalid.saturating_add(1);
+                    return TxDecision::Drop;
+                };
+
+                if priorities.len() >= config.max_buffered {
+                    // Only transactions not sent to a worker can be evicted.
+                    let lowest = [unchecked.first(), checked.first()]
+                        .into_iter()
+                        .flatten()
+                        .min()
+                        .copied();
+                    stats.evicted = stats.evicted.saturating_add(1);
+                    match lowest {
+                        Some(lowest) if lowest.0 < fee_per_cu.priority => {
+                            unchecked.remove(&lowest);
+                            checked.remove(&lowest);
+                            priorities.remove(&lowest.1);
+                            bridge.drop_transaction(lowest.1);
+                        }
+                        _ => return TxDecision::Drop,
+                    }
+                }
+
+                priorities.insert(key, fee_per_cu);
+                unchecked.insert((fee_per_cu.priority, key));
+                TxDecision::Keep
+            },
+            usize::MAX,
+        );
+        stats.received = stats.received.saturating_add(sanitize_failures as u64);
+        stats.invalid = stats.invalid.saturating_add(sanitize_failures as u64);
+    }
+
+    fn drain_workers(&mut self) {
+        let Self {
+            bridge,
+            locks,
+            priorities,
+            unchecked,
+            checked,
+            in_flight_compute_units,
+            stats,
+            ..
+        } = self;
+
+        for worker in 0..bridge.worker_count() {
+            bridge.drain_worker(
+                worker,
+                |bridge,
+                 WorkerResponse {
+                     key,
+                     meta,
+                     response,
+                 }| {
+                    let fee_per_cu = priorities[&key];
+                    if meta == Stage::Execute {
+                        let transaction = bridge.transaction(key);
+                        locks.unlock_accounts(
+                            transaction.write_locks(),
+                            transaction.read_locks(),
+                            worker,
+                        );
+                        *in_flight_compute_units =
+                            in_flight_compute_units.saturating_sub(fee_per_cu.compute_units);
+                    }
+
+                    let decision = match response {
+                        // The slot ended before the batch was processed, the
+                        // checks are redone against the next bank.
+                        WorkerAction::Unprocessed => {
+                            unchecked.insert((fee_per_cu.priority, key));
+                            TxDecision::Keep
+                        }
+                        WorkerAction::Check(response, _) => {
+                            if check_passed(&response, fee_per_cu.fee) {
+                                checked.insert((fee_per_cu.priority, key));
+                                TxDecision::Keep
+                            } else {
+                                stats.check_failed = stats.check_failed.saturating_add(1);
+                                TxDecision::Drop
+                            }
+                        }
+                        WorkerAction::Execute(response) => match response.not_included_reason {
+                            not_included_reasons::NONE => {
+                                stats.included = stats.included.saturating_add(1);
+                                TxDecision::Drop
+                            }
+                            reason if is_retryable(reason) => {
+                                stats.retried = stats.retried.saturating_add(1);
+                                checked.insert((fee_per_cu.priority, key));
+                                TxDecision::Keep
+                            }
+                            _ => {
+                                stats.not_included = stats.not_included.saturating_add(1);
+                                TxDecision::Drop
+                            }
+                        },
+                    };
+
+                    if decision == TxDecision::Drop {
+                        priorities.remove(&key);
+                    }
+                    decision
+                },
+                usize::MAX,
+            );
+        }
+    }
+
+    fn schedule_checks(&mut self) {
+        while !self.unchecked.is_empty() {
+            // Send to the worker with the most room in its queue.
+            let mut target = None;
+            for worker in 0..self.bridge.worker_count() {
+                let rem = self.bridge.worker(worker).rem();
+                if rem > 0 && target.is_none_or(|(_, most)| rem > most) {
+                    target = Some((worker, rem));
+                }
+            }
+            let Some((worker, _)) = target else {
+                break;
+            };
+
+            let batch: Vec<_> = std::iter::from_fn(|| self.unchecked.pop_last())
+                .take(self.config.batch_size)
+                .map(|(_, key)| KeyedTransactionMeta {
+                    key,
+                    meta: Stage::Check,
+                })
+                .collect();
+            if let Err(err) = self.bridge.schedule(ScheduleBatch {
+                worker,
+                transactions: &batch,
+                max_working_slot: u64::MAX,
+                flags: CHECK_FLAGS,
+            }) {
+                warn!("Failed to schedule checks; err={err}");
+                for KeyedTransactionMeta { key, .. } in batch {
+                    self.unchecked.insert((self.priorities[&key].priority, key));
+                }
+                break;
+            }
+        }
+    }
+
+    fn schedule_execution(&mut self) {
+        let progress = *self.bridge.progress();
+        if progress.leader_state != LEADER_READY {
+            return;
+        }
+
+        let worker_count = self.bridge.worker_count();
+        let mut budget = progress
+            .remaining_cost_units
+            .saturating_sub(self.in_flight_compute_units);
+        let mut rem: Vec<_> = (0..worker_count)
+            .map(|worker| self.bridge.worker(worker).rem())
+            .collect();
+        let mut batches = vec![vec![]; worker_count];
+
+        let candidates: Vec<_> = self
+            .checked
+            .iter()
+            .rev()
+            .take(self.config.lookahead)
+            .copied()
+            .collect();
+        for (priority, key) in candidates {
+            let compute_units = self.priorities[&key].compute_units;
+            if compute_units > budget {
+                continue;
+            }
+
+            let mut allowed = ThreadSet::none();
+            for (worker, rem) in rem.iter().enumerate() {
+                if *rem > 0 {
+                    allowed.insert(worker);
+                }
+            }
+            if allowed.is_empty() {
+                break;
+            }
+
+            // Spread the transactions over the workers they can be locked on.
+            let transaction = self.bridge.transaction(key);
+            let Ok(worker) = self.locks.try_lock_accounts(
+                transaction.write_locks(),
+                transaction.read_locks(),
+                allowed,
+                |threads| {
+                    threads
+                        .contained_threads_iter()
+                        .min_by_key(|worker| batches[*worker].len())
+                        .expect("thread set is not empty")
+                },
+            ) else {
+                continue;
+            };
+
+            self.checked.remove(&(priority, key));
+            budget = budget.saturating_sub(compute_units);
+            self.in_flight_compute_units =
+                self.in_flight_compute_units.saturating_add(compute_units);
+            batches[worker].push(KeyedTransactionMeta {
+                key,
+                meta: Stage::Execute,
+            });
+            if batches[worker].len() == self.config.batch_size {
+                self.send_execution(worker, &mut batches[worker], progress.current_slot);
+                rem[worker] = rem[worker].saturating_sub(1);
+            }
+        }
+
+        for (worker, batch) in batches.iter_mut().enumerate() {
+            if !batch.is_empty() {
+                self.send_execution(worker, batch, progress.current_slot);
+            }
+        }
+    }
+
+    fn send_execution(
+        &mut self,
+        worker: usize,
+        batch: &mut Vec<KeyedTransactionMeta<Stage>>,
+        slot: u64,
+    ) {
+        if let Err(err) = self.bridge.schedule(ScheduleBatch {
+            worker,
+            transactions: batch.as_slice(),
+            max_working_slot: slot,
+            flags: pack_message_flags::EXECUTE,
+        }) {
+            warn!("Failed to schedule execution; err={err}");
+            for KeyedTransactionMeta { key, .. } in batch.iter() {
+                let fee_per_cu = self.priorities[key];
+                let transaction = self.bridge.transaction(*key);
+                self.locks.unlock_accounts(
+                    transaction.write_locks(),
+                    transaction.read_locks(),
+                    worker,
+                );
+                self.in_flight_compute_units = self
+                    .in_flight_compute_units
+                    .saturating_sub(fee_per_cu.compute_units);
+                self.checked.insert((fee_per_cu.priority, *key));
+            }
+        }
+        batch.clear();
+    }
+}
+
+fn check_passed(response: &CheckResponse, fee: u64) -> bool {
+    response.parsing_and_sanitization_flags & parsing_and_sanitization_flags::FAILED == 0
+        && response.status_check_flags & STATUS_CHECK_FAILURES == 0
+        && response.resolve_flags & resolve_flags::FAILED == 0
+        && (response.fee_payer_balance_flags & fee_payer_balance_flags::PERFORMED == 0
+            || response.fee_payer_balance >= fee)
+}
+
+/// Whether a transaction not included for `reason` may be included later
+fn is_retryable(reason: u8) -> bool {
+    matches!(
+        reason,
+        not_included_reasons::BANK_NOT_AVAILABLE
+            | not_included_reasons::ACCOUNT_IN_USE
+            | not_included_reasons::WOULD_EXCEED_MAX_BLOCK_COST_LIMIT
+            | not_included_reasons::WOULD_EXCEED_MAX_ACCOUNT_COST_LIMIT
+            | not_included_reasons::WOULD_EXCEED_ACCOUNT_DATA_BLOCK_LIMIT
+            | not_included_reasons::WOULD_EXCEED_MAX_VOTE_COST_LIMIT
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::harness::{LocalHarness, check_ok, executed, leader_ready, not_included},
+        solana_compute_budget_interface::ComputeBudgetInstruction,
+        solana_hash::Hash,
+        solana_keypair::Keypair,
+        solana_pubkey::Pubkey,
+        solana_signer::Signer,
+        solana_system_interface::instruction as system_instruction,
+        solana_transaction::Transaction,
+    };
+
+    const COMPUTE_UNIT_LIMIT: u32 = 10_000;
+
+    fn setup(config: PackConfig, worker_count: usize) -> (LocalHarness, FeePerCuPack) {
+        let (harness, session) = LocalHarness::new(config.logon(worker_count)).unwrap();
+        (harness, FeePerCuPack::new(session, config))
+    }
+
+    fn transfer(compute_unit_price: u64) -> Vec<u8> {
+        let payer = Keypair::new();
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNIT_LIMIT),
+                ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+                system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1),
+            ],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        bincode::serialize(&transaction).unwrap()
+    }
+
+    #[test]
+    fn test_execution_by_priority() {
+        let (mut harness, mut pack) = setup(PackConfig::default(), 1);
+        let transactions = [transfer(1_000), transfer(3_000), transfer(2_000)];
+        for transaction in &transactions {
+            assert!(harness.queue_tpu(transaction, 0));
+        }
+        harness.queue_progress(leader_ready(1, u64::MAX));
+
+        pack.poll();
+        let requests = harness.respond(|_| check_ok(1, u64::MAX), |_| unreachable!());
+        assert_eq!(requests.len(), 1);
+        assert!(!requests[0].is_execute());
+        assert_eq!(
+            requests[0].transactions,
+            [
+                transactions[1].clone(),
+                transactions[2].clone(),
+                transactions[0].clone()
+            ]
+        );
+
+        pack.poll();
+        let requests = harness.respond(
+            |_| unreachable!(),
+            |_| executed(1, u64::from(COMPUTE_UNIT_LIMIT)),
+        );
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].is_execute());
+        assert_eq!(requests[0].max_working_slot, 1);
+        assert_eq!(
+            requests[0].transactions,
+            [
+                transactions[1].clone(),
+                transactions[2].clone(),
+                transactions[0].clone()
+            ]
+        );
+
+        pack.poll();
+        assert_eq!(pack.buffered(), 0);
+        assert_eq!(pack.stats().received, 3);
+        assert_eq!(pack.stats().included, 3);
+    }
+
+    #[test]
+    fn test_failed_check_dropped() {
+        let (mut harness, mut pack) = setup(PackConfig::default(), 1);
+        assert!(harness.queue_tpu(&transfer(1_000), 0));
+        harness.queue_progress(leader_ready(1, u64::MAX));
+
+        pack.poll();
+        // The fee payer cannot pay the fee.
+        assert_eq!(
+            harness
+                .respond(|_| check_ok(1, 0), |_| unreachable!())
+                .len(),
+            1
+        );
+
+        pack.poll();
+        assert!(
+            harness
+                .respond(|_| unreachable!(), |_| unreachable!())
+                .is_empty()
+        );
+        assert_eq!(pack.buffered(), 0);
+        assert_eq!(pack.stats().check_failed, 1);
+    }
+
+    #[test]
+    fn test_execution_waits_for_leader_slot() {
+        let (mut harness, mut pack) = setup(PackConfig::default(), 1);
+        assert!(harness.queue_tpu(&transfer(1_000), 0));
+
+        pack.poll();
+        assert_eq!(
+            harness
+                .respond(|_| check_ok(1, u64::MAX), |_| unreachable!())
+                .len(),
+            1
+        );
+        pack.poll();
+        assert!(
+            harness
+                .respond(|_| unreachable!(), |_| unreachable!())
+                .is_empty()
+        );
+
+        harness.queue_progress(leader_ready(2, u64::MAX));
+        pack.poll();
+        let requests = harness.respond(|_| unreachable!(), |_| executed(2, 0));
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].max_working_slot, 2);
+    }
+
+    #[test]
+    fn test_execution_within_block_budget() {
+        let (mut harness, mut pack) = setup(PackConfig::default(), 1);
+        let transactions = [transfer(1_000), transfer(2_000)];
+        for transaction in &transactions {
+            assert!(harness.queue_tpu(transaction, 0));
+        }
+        harness.queue_progress(leader_ready(1, u64::from(COMPUTE_UNIT_LIMIT)));
+
+        pack.poll();
+        harness.respond(|_| check_ok(1, u64::MAX), |_| unreachable!());
+        pack.poll();
+        let requests = harness.respond(|_| unreachable!(), |_| executed(1, 0));
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].transactions, [transactions[1].clone()]);
+
+        // The block is full, the remaining transaction is retried later.
+        harness.queue_progress(leader_ready(1, 0));
+        pack.poll();
+        assert!(
+            harness
+                .respond(|_| unreachable!(), |_| unreachable!())
+                .is_empty()
+        );
+        assert_eq!(pack.buffered(), 1);
+    }
+
+    #[test]
+    fn test_retryable_execution_failure() {
+        let (mut harness, mut pack) = setup(PackConfig::default(), 1);
+        let transaction = transfer(1_000);
+        assert!(harness.queue_tpu(&transaction, 0));
+        harness.queue_progress(leader_ready(1, u64::MAX));
+
+        pack.poll();
+        harness.respond(|_| check_ok(1, u64::MAX), |_| unreachable!());
+        pack.poll();
+        harness.respond(
+            |_| unreachable!(),
+            |_| not_included(1, not_included_reasons::ACCOUNT_IN_USE),
+        );
+
+        pack.poll();
+        let requests = harness.respond(|_| unreachable!(), |_| executed(1, 0));
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].transactions, [transaction]);
+        assert_eq!(pack.stats().retried, 1);
+    }
+
+    #[test]
+    fn test_unprocessed_execution_checked_again() {
+        let (mut harness, mut pack) = setup(PackConfig::default(), 1);
+        let transaction = transfer(1_000);
+        assert!(harness.queue_tpu(&transaction, 0));
+        harness.queue_progress(leader_ready(1, u64::MAX));
+
+        pack.poll();
+        harness.respond(|_| check_ok(1, u64::MAX), |_| unreachable!());
+        pack.poll();
+
+        // The slot ends before the execution batch is processed.
+        harness.queue_progress(leader_ready(2, u64::MAX));
+        let requests = harness.respond(|_| unreachable!(), |_| unreachable!());
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].is_execute());
+
+        pack.poll();
+        let requests = harness.respond(|_| check_ok(2, u64::MAX), |_| unreachable!());
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].transactions, [transaction]);
+    }
+
+    #[test]
+    fn test_lowest_priority_evicted() {
+        let config = PackConfig {
+            max_buffered: 2,
+            ..PackConfig::default()
+        };
+        let (mut harness, mut pack) = setup(config, 1);
+        let transactions = [transfer(2_000), transfer(1_000), transfer(3_000)];
+        for transaction in &transactions {
+            assert!(harness.queue_tpu(transaction, 0));
+        }
+
+        pack.poll();
+        let requests = harness.respond(|_| check_ok(1, u64::MAX), |_| unreachable!());
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].transactions,
+            [transactions[2].clone(), transactions[0].clone()]
+        );
+        assert_eq!(pack.buffered(), 2);
+        assert_eq!(pack.stats().evicted, 1);
+    }
+
+    #[test]
+    fn test_conflicting_transactions_on_same_worker() {
+        let (mut harness, mut pack) = setup(PackConfig::default(), 2);
+        let payer = Keypair::new();
+        let transactions: Vec<_> = (0..2)
+            .map(|_| {
+                bincode::serialize(&Transaction::new_signed_with_payer(
+                    &[system_instruction::transfer(
+                        &payer.pubkey(),
+                        &Pubkey::new_unique(),
+                        1,
+                    )],
+                    Some(&payer.pubkey()),
+                    &[&payer],
+                    Hash::new_unique(),
+                ))
+                .unwrap()
+            })
+            .collect();
+        for transaction in &transactions {
+            assert!(harness.queue_tpu(transaction, 0));
+        }
+        harness.queue_progress(leader_ready(1, u64::MAX));
+
+        pack.poll();
+        harness.respond(|_| check_ok(1, u64::MAX), |_| unreachable!());
+        pack.poll();
+        let requests = harness.respond(|_| unreachable!(), |_| executed(1, 0));
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].transactions.len(), 2);
+    }
+}
@@ -0,0 +1,131 @@
+//! Priority of a transaction by the fee it pays per requested compute unit.
+
+use {
+    agave_scheduling_utils::bridge::RuntimeState,
+    agave_transaction_view::{
+        transaction_data::TransactionData, transaction_view::SanitizedTransactionView,
+    },
+    solana_compute_budget_instruction::compute_budget_instruction_details::ComputeBudgetInstructionDetails,
+};
+
+/// Scales the reward per compute unit so that low fees still order
+/// transactions
+const PRIORITY_MULTIPLIER: u64 = 1_000_000;
+
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeePerCu {
+    /// The fee charged to the fee payer, in lamports
+    pub fee: u64,
+    /// The part of the fee the leader is rewarded with, in lamports
+    pub reward: u64,
+    /// The compute unit limit requested by the transaction
+    pub compute_units: u64,
+    /// `reward` per compute unit, scaled by [`PRIORITY_MULTIPLIER`]
+    pub priority: u64,
+}
+
+impl FeePerCu {
+    /// Returns `None` if the compute budget instructions of the transaction
+    /// are invalid
+    pub fn new<D: TransactionData>(
+        transaction: &SanitizedTransactionView<D>,
+        runtime: &RuntimeState,
+    ) -> Option<Self> {
+        let limits =
+            ComputeBudgetInstructionDetails::try_from(transaction.program_instructions_iter())
+                .ok()?
+                .sanitize_and_convert_to_compute_budget_limits(&runtime.feature_set)
+                .ok()?;
+        let compute_units = u64::from(limits.compute_unit_limit);
+        let prioritization_fee = u128::from(limits.compute_unit_price)
+            .saturating_mul(u128::from(limits.compute_unit_limit))
+            .div_ceil(MICRO_LAMPORTS_PER_LAMPORT);
+        let prioritization_fee = u64::try_from(prioritization_fee).unwrap_or(u64::MAX);
+        let signature_fee =
+            u64::from(transaction.num_signatures()).saturating_mul(runtime.lamports_per_signature);
+
+        // The prioritization fee goes to the leader, the signature fee is
+        // partially burnt
+        let signature_reward = signature_fee
+            .saturating_mul(100u64.saturating_sub(runtime.burn_percent))
+            .checked_div(100)
+            .unwrap_or_default();
+        let reward = prioritization_fee.saturating_add(signature_reward);
+        let priority = reward
+            .saturating_mul(PRIORITY_MULTIPLIER)
+            .checked_div(compute_units.max(1))
+            .unwrap_or_default();
+
+        Some(Self {
+            fee: prioritization_fee.saturating_add(signature_fee),
+            reward,
+            compute_units,
+            priority,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, agave_feature_set::FeatureSet,
+        solana_compute_budget_interface::ComputeBudgetInstruction, solana_fee::FeeFeatures,
+        solana_hash::Hash, solana_instruction::Instruction, solana_keypair::Keypair,
+        solana_pubkey::Pubkey, solana_runtime_transaction::sanitize_config::sanitize_config,
+        solana_signer::Signer, solana_system_interface::instruction as system_instruction,
+        solana_transaction::Transaction,
+    };
+
+    fn fee_per_cu(instructions: &[Instruction]) -> Option<FeePerCu> {
+        let payer = Keypair::new();
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        let data = bincode::serialize(&transaction).unwrap();
+        let view =
+            SanitizedTransactionView::try_new_sanitized(&data[..], &sanitize_config(true)).unwrap();
+        let runtime = RuntimeState {
+            feature_set: FeatureSet::all_enabled(),
+            fee_features: FeeFeatures {},
+            lamports_per_signature: 5000,
+            burn_percent: 50,
+        };
+        FeePerCu::new(&view, &runtime)
+    }
+
+    #[test]
+    fn test_fee_per_cu() {
+        let transfer =
+            system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1);
+        assert_eq!(
+            fee_per_cu(&[
+                ComputeBudgetInstruction::set_compute_unit_limit(10_000),
+                ComputeBudgetInstruction::set_compute_unit_price(3_000_000),
+                transfer,
+            ]),
+            Some(FeePerCu {
+                fee: 30_000 + 5000,
+                reward: 30_000 + 2500,
+                compute_units: 10_000,
+                // 32_500 lamports over 10_000 compute units
+                priority: 3_250_000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_fee_per_cu_invalid_compute_budget() {
+        assert_eq!(
+            fee_per_cu(&[
+                ComputeBudgetInstruction::set_compute_unit_limit(10_000),
+                ComputeBudgetInstruction::set_compute_unit_limit(20_000),
+            ]),
+            None
+        );
+    }
+}
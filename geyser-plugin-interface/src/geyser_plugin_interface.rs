@@ -9,7 +9,7 @@ use {
     solana_signature::Signature,
     solana_transaction::{sanitized::SanitizedTransaction, versioned::VersionedTransaction},
     solana_transaction_status::{Reward, RewardsAndNumPartitions, TransactionStatusMeta},
-    std::{any::Any, error, io, net::SocketAddr},
+    std::{any::Any, error, io, net::SocketAddr, ops::RangeInclusive},
     thiserror::Error,
 };
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -110,6 +110,45 @@ pub struct ReplicaAccountInfoV3<'a> {
     pub txn: Option<&'a SanitizedTransaction>,
 }
 
+#[derive(Clone, Debug)]
+#[repr(C)]
+/// Information about an account being updated
+/// (extended with the order of the update among the updates of the account)
+pub struct ReplicaAccountInfoV4<'a> {
+    /// The Pubkey for the account
+    pub pubkey: &'a [u8],
+
+    /// The lamports for the account
+    pub lamports: u64,
+
+    /// The Pubkey of the owner program account
+    pub owner: &'a [u8],
+
+    /// This account's data contains a loaded program (and is now read-only)
+    pub executable: bool,
+
+    /// The epoch at which this account will next owe rent
+    pub rent_epoch: u64,
+
+    /// The data held in this account.
+    pub data: &'a [u8],
+
+    /// See `ReplicaAccountInfoV3::write_version`.
+    pub write_version: u64,
+
+    /// Increases with every update of this account notified to the plugin,
+    /// across slots and forks, so that of two updates of the account notified
+    /// by the same validator process, the one with the higher write_sequence
+    /// was written last. The sequence is not persisted and starts over when
+    /// the validator restarts, so it must not be compared with the sequences
+    /// of a previous run. Zero for the updates replayed by catch-up, which are
+    /// delivered in order before any live update.
+    pub write_sequence: u64,
+
+    /// Reference to transaction causing this account modification
+    pub txn: Option<&'a SanitizedTransaction>,
+}
+
 /// A wrapper to future-proof ReplicaAccountInfo handling.
 /// If there were a change to the structure of ReplicaAccountInfo,
 /// there would be new enum entry for the newer version, forcing
@@ -119,6 +158,7 @@ pub enum ReplicaAccountInfoVersions<'a> {
     V0_0_1(&'a ReplicaAccountInfo<'a>),
     V0_0_2(&'a ReplicaAccountInfoV2<'a>),
     V0_0_3(&'a ReplicaAccountInfoV3<'a>),
+    V0_0_4(&'a ReplicaAccountInfoV4<'a>),
 }
 
 /// Information about a transaction
@@ -456,6 +496,184 @@ pub enum ReplicaContactInfoVersions<'a> {
     V0_0_1(&'a ReplicaContactInfoV0_0_1<'a>),
 }
 
+/// The kind of an Alpenglow consensus vote
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ConsensusVoteType {
+    Notarize,
+    NotarizeFallback,
+    Skip,
+    SkipFallback,
+    Finalize,
+    Genesis,
+}
+
+/// A verified Alpenglow consensus vote, as added to the validator's
+/// consensus pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct ReplicaConsensusVoteInfoV0_0_1<'a> {
+    /// The slot voted on.
+    pub slot: Slot,
+
+    pub vote_type: ConsensusVoteType,
+
+    /// The block voted for, set for notarize, notarize fallback and genesis
+    /// votes.
+    pub block_id: Option<&'a Hash>,
+
+    /// The 32-byte vote account of the voter.
+    pub vote_account: &'a [u8],
+
+    /// The rank of the voter in the epoch's BLS rank map.
+    pub rank: u16,
+
+    /// The stake of the voter in the slot's epoch.
+    pub stake: u64,
+
+    /// The total stake of the slot's epoch.
+    pub total_stake: u64,
+
+    /// The BLS signature of the vote, in affine form.
+    pub signature: &'a [u8],
+}
+
+/// A wrapper to future-proof ReplicaConsensusVoteInfo handling, see
+/// [`ReplicaContactInfoVersions`].
+#[repr(u32)]
+pub enum ReplicaConsensusVoteInfoVersions<'a> {
+    V0_0_1(&'a ReplicaConsensusVoteInfoV0_0_1<'a>),
+}
+
+/// The kind of an Alpenglow consensus certificate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ConsensusCertificateType {
+    Notarize,
+    NotarizeFallback,
+    Skip,
+    Finalize,
+    FinalizeFast,
+    Genesis,
+}
+
+/// An Alpenglow consensus certificate, as added to the validator's consensus
+/// pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct ReplicaConsensusCertificateInfoV0_0_1<'a> {
+    /// The slot certified.
+    pub slot: Slot,
+
+    pub certificate_type: ConsensusCertificateType,
+
+    /// The block certified, set for all but skip and finalize certificates.
+    pub block_id: Option<&'a Hash>,
+
+    /// The aggregate BLS signature, in affine form.
+    pub signature: &'a [u8],
+
+    /// The ranks of the voters included in the aggregate signature, in the
+    /// encoding of `solana-signer-store`.
+    pub bitmap: &'a [u8],
+
+    /// True if the validator built the certificate from the votes it
+    /// received, false if it received the certificate.
+    pub is_generated: bool,
+}
+
+/// A wrapper to future-proof ReplicaConsensusCertificateInfo handling, see
+/// [`ReplicaContactInfoVersions`].
+#[repr(u32)]
+pub enum ReplicaConsensusCertificateInfoVersions<'a> {
+    V0_0_1(&'a ReplicaConsensusCertificateInfoV0_0_1<'a>),
+}
+
+/// A re-stream of the accounts of a rooted slot, requested for the plugin
+/// through the admin RPC. See `GeyserPlugin::notify_account_restream_start`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct ReplicaAccountRestreamInfoV0_0_1<'a> {
+    /// Identifies the re-stream, unique while the validator is running.
+    pub id: u64,
+
+    /// The rooted slot whose accounts are re-streamed.
+    pub slot: Slot,
+
+    /// The 32-byte pubkeys of the programs owning the re-streamed accounts.
+    pub owners: &'a [[u8; 32]],
+}
+
+/// A wrapper to future-proof ReplicaAccountRestreamInfo handling, see
+/// [`ReplicaContactInfoVersions`].
+#[repr(u32)]
+pub enum ReplicaAccountRestreamInfoVersions<'a> {
+    V0_0_1(&'a ReplicaAccountRestreamInfoV0_0_1<'a>),
+}
+
+/// Selects the account updates delivered to a plugin.
+///
+/// An account matches when its pubkey is listed in `pubkeys` or its owner is
+/// listed in `owners`, with every account matching when both lists are empty,
+/// and its data length falls within one of `data_size_ranges`, with any length
+/// matching when the list is empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeyserAccountFilter {
+    /// Accounts owned by any of these programs match
+    pub owners: Vec<[u8; 32]>,
+
+    /// These accounts match regardless of their owner
+    pub pubkeys: Vec<[u8; 32]>,
+
+    /// Inclusive ranges of account data lengths, in bytes
+    pub data_size_ranges: Vec<RangeInclusive<usize>>,
+}
+
+/// Selects the transactions delivered to a plugin.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeyserTransactionFilter {
+    /// Only deliver vote transactions if `Some(true)`, or only non-vote
+    /// transactions if `Some(false)`. Both are delivered if `None`.
+    pub vote: Option<bool>,
+
+    /// If not empty, only deliver transactions that reference at least one
+    /// of these accounts, either directly or through an address lookup table
+    pub mentioned_accounts: Vec<[u8; 32]>,
+}
+
+/// Notifications a plugin wants to receive, returned by
+/// `GeyserPlugin::notification_filter`. The validator evaluates the filter
+/// before calling into the plugin, so updates the plugin is not interested in
+/// never cross the plugin boundary.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeyserPluginFilter {
+    /// Filter for `update_account`. Every account update is delivered if `None`.
+    pub accounts: Option<GeyserAccountFilter>,
+
+    /// Filter for `notify_transaction`. Every transaction is delivered if `None`.
+    pub transactions: Option<GeyserTransactionFilter>,
+}
+
+/// Notifications the validator discarded because the plugin's dispatch queue
+/// was full, or rooted slots catch-up could not replay, reported by
+/// `GeyserPlugin::notify_gap`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationGap {
+    /// The number of notifications discarded. Zero for slots catch-up could
+    /// not replay, as the notifications they would have produced are unknown.
+    pub dropped: u64,
+
+    /// The lowest and highest slot referenced by the discarded notifications,
+    /// or `None` if none of them referenced a slot
+    pub slots: Option<RangeInclusive<Slot>>,
+
+    /// Whether only account updates of the slots are missing. Catch-up
+    /// delivers the other notifications of the slots it replays, but not the
+    /// account writes made outside of transactions, such as rewards, fee and
+    /// rent collection and sysvar updates.
+    pub account_updates_only: bool,
+}
+
 /// Errors returned by plugin calls
 #[derive(Error, Debug)]
 #[repr(u32)]
@@ -599,6 +817,33 @@ pub trait GeyserPlugin: Any + Send + Sync + std::fmt::Debug {
         Ok(())
     }
 
+    /// Called when the validator starts re-streaming the accounts of a rooted
+    /// slot to the plugin, as requested through the `restreamPluginAccounts`
+    /// admin RPC method. The accounts owned by `restream.owners` at
+    /// `restream.slot` follow, delivered by `update_account` at that slot with
+    /// `is_startup` set, and then `notify_account_restream_end`. Live
+    /// notifications keep being delivered during the re-stream.
+    #[allow(unused_variables)]
+    fn notify_account_restream_start(
+        &self,
+        restream: ReplicaAccountRestreamInfoVersions,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after the last account of a re-stream. `accounts` is the number
+    /// of accounts delivered, and `is_complete` is false if the re-stream was
+    /// aborted before every account was delivered.
+    #[allow(unused_variables)]
+    fn notify_account_restream_end(
+        &self,
+        restream: ReplicaAccountRestreamInfoVersions,
+        accounts: u64,
+        is_complete: bool,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     /// Called when a slot status is updated
     #[allow(unused_variables)]
     fn update_slot_status(
@@ -610,6 +855,18 @@ pub trait GeyserPlugin: Any + Send + Sync + std::fmt::Debug {
         Ok(())
     }
 
+    /// Called when a slot that had account updates will never be rooted,
+    /// because it is dead or on a fork that was pruned when a new root was
+    /// set. `accounts` lists the accounts updated in the slot, whose updates
+    /// at this slot should be discarded. Each abandoned slot is notified
+    /// separately, after the account updates of the slot.
+    ///
+    /// Only called when `account_rollback_notifications_enabled()` returns true.
+    #[allow(unused_variables)]
+    fn notify_slot_abandoned(&self, slot: Slot, accounts: &[[u8; 32]]) -> Result<()> {
+        Ok(())
+    }
+
     /// Called when a transaction is processed in a slot.
     #[allow(unused_variables)]
     fn notify_transaction(
@@ -677,6 +934,86 @@ pub trait GeyserPlugin: Any + Send + Sync + std::fmt::Debug {
         Ok(())
     }
 
+    /// Called when a verified Alpenglow vote enters the validator's
+    /// consensus pool. Votes the pool already had or rejected, for example
+    /// conflicting votes, are not notified.
+    ///
+    /// Consensus notifications are delivered from a dedicated thread through
+    /// a bounded channel and are best-effort: the consensus pool never waits
+    /// for plugins, and drops notifications when the channel is full.
+    ///
+    /// Only called when `consensus_notifications_enabled()` returns true.
+    #[allow(unused_variables)]
+    fn notify_consensus_vote(&self, vote: ReplicaConsensusVoteInfoVersions) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a certificate enters the validator's consensus pool,
+    /// either received from another validator or built from the votes in the
+    /// pool. See `notify_consensus_vote` for delivery guarantees.
+    ///
+    /// Only called when `consensus_notifications_enabled()` returns true.
+    #[allow(unused_variables)]
+    fn notify_consensus_certificate(
+        &self,
+        certificate: ReplicaConsensusCertificateInfoVersions,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when the consensus pool has the certificates finalizing a
+    /// block: a fast finalize certificate (`is_fast`), or both a finalize and
+    /// a notarize certificate. A block finalized both ways is notified twice.
+    /// See `notify_consensus_vote` for delivery guarantees.
+    ///
+    /// Only called when `consensus_notifications_enabled()` returns true.
+    #[allow(unused_variables)]
+    fn notify_block_finalized(&self, slot: Slot, block_id: &Hash, is_fast: bool) -> Result<()> {
+        Ok(())
+    }
+
+    /// The notification filter applied by the validator to this plugin's
+    /// account and transaction notifications. Called once after each
+    /// successful `on_load`, so the filter may depend on the plugin config.
+    /// Default is None -- the plugin receives every notification it enabled.
+    fn notification_filter(&self) -> Option<GeyserPluginFilter> {
+        None
+    }
+
+    /// Called before the next notification when the validator discarded
+    /// notifications for this plugin. Only happens for plugins with a
+    /// `drop_oldest` dispatch queue that fell behind, or when catch-up
+    /// requested slots that are no longer in the ledger or replayed slots
+    /// without all their account updates; the plugin may need to
+    /// resynchronize the state covered by `gap.slots`.
+    #[allow(unused_variables)]
+    fn notify_gap(&self, gap: &NotificationGap) -> Result<()> {
+        Ok(())
+    }
+
+    /// The slot from which the validator replays rooted blocks to this plugin
+    /// before resuming live notifications, so a plugin restarting from a
+    /// checkpoint does not miss the updates made while it was down. Called
+    /// once after each successful `on_load`. For every replayed slot the
+    /// plugin receives, in order, the account updates and notification of
+    /// each transaction, the entries, the block metadata and a `Rooted` slot
+    /// status. Account writes made outside of transactions are not replayed,
+    /// and are reported by a `notify_gap` with `account_updates_only` over
+    /// the replayed slots. Live notifications produced meanwhile are queued
+    /// and delivered after `notify_catch_up_complete`.
+    /// Default is None -- the plugin only receives live notifications.
+    fn catch_up_from_slot(&self) -> Option<Slot> {
+        None
+    }
+
+    /// Called once catch-up is done, before the first queued live
+    /// notification. `last_slot` is the last slot replayed, or None if there
+    /// was no rooted slot to replay.
+    #[allow(unused_variables)]
+    fn notify_catch_up_complete(&self, last_slot: Option<Slot>) -> Result<()> {
+        Ok(())
+    }
+
     /// Check if the plugin is interested in account data
     /// Default is true -- if the plugin is not interested in
     /// account data, please return false.
@@ -692,6 +1029,15 @@ pub trait GeyserPlugin: Any + Send + Sync + std::fmt::Debug {
         true
     }
 
+    /// Check if the plugin wants to roll back the account updates of
+    /// abandoned forks. When true, account updates are delivered as
+    /// `ReplicaAccountInfoVersions::V0_0_4`, which carries a per-account
+    /// `write_sequence`, and `notify_slot_abandoned` is called.
+    /// Default is false.
+    fn account_rollback_notifications_enabled(&self) -> bool {
+        false
+    }
+
     /// Check if the plugin is interested in transaction data
     /// Default is false -- if the plugin is interested in
     /// transaction data, please return true.
@@ -715,6 +1061,14 @@ pub trait GeyserPlugin: Any + Send + Sync + std::fmt::Debug {
         false
     }
 
+    /// Check if the plugin is interested in Alpenglow consensus votes,
+    /// certificates and finalized blocks. Default is false -- if the plugin
+    /// wants consensus notifications, return true. When no loaded plugin
+    /// returns true, the consensus pool does no work for these notifications.
+    fn consensus_notifications_enabled(&self) -> bool {
+        false
+    }
+
     /// Called when a transaction is deshredded (entries formed from shreds).
     /// This is triggered before any execution occurs. Unlike notify_transaction,
     /// this does not include execution metadata (TransactionStatusMeta).
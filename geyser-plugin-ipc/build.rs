@@ -0,0 +1,32 @@
+fn main() -> Result<(), std::io::Error> {
+    const PROTOC_ENVAR: &str = "PROTOC";
+    // Safety: env is checked and updated before any threads might exist
+    if std::env::var(PROTOC_ENVAR).is_err() {
+        #[cfg(not(windows))]
+        unsafe {
+            std::env::set_var(PROTOC_ENVAR, protobuf_src::protoc())
+        }
+    }
+
+    let proto = std::path::PathBuf::from("proto/geyser_ipc.proto");
+    println!("cargo:rerun-if-changed={}", proto.display());
+    // The transactions, transaction status and rewards use the messages of
+    // the ledger storage protobufs
+    let storage_proto_path = std::path::PathBuf::from("../storage-proto/proto");
+    println!(
+        "cargo:rerun-if-changed={}",
+        storage_proto_path.join("confirmed_block.proto").display()
+    );
+
+    tonic_prost_build::configure()
+        .build_client(false)
+        .build_server(false)
+        .extern_path(
+            ".solana.storage.ConfirmedBlock",
+            "::solana_storage_proto::convert::generated",
+        )
+        .compile_protos(
+            &[proto],
+            &[std::path::PathBuf::from("proto"), storage_proto_path],
+        )
+}
@@ -0,0 +1,532 @@
+//! The validator side: a Geyser plugin forwarding notifications to a host.
+
+use {
+    crate::protocol::{
+        PROTOCOL_VERSION, encode_slot_status,
+        generated::{self, host_message, validator_message},
+        read_message, write_message,
+    },
+    agave_geyser_plugin_interface::geyser_plugin_interface::{
+        GeyserPlugin, GeyserPluginError, NotificationGap, ReplicaAccountInfoVersions,
+        ReplicaBlockInfoVersions, ReplicaEntryInfoVersions, ReplicaTransactionInfoVersions, Result,
+        SlotStatus,
+    },
+    log::*,
+    solana_clock::Slot,
+    std::{
+        fs,
+        io::{self, BufWriter, Write},
+        os::unix::net::UnixStream,
+        path::{Path, PathBuf},
+        sync::{
+            Mutex,
+            mpsc::{Receiver, SyncSender, sync_channel},
+        },
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+/// Minimum time between attempts to reconnect to an unreachable host
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+/// How long to wait for the host to answer `Hello`
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a write may wait for a host that stopped reading before the
+/// connection is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+const WRITE_BUFFER_SIZE: usize = 1024 * 1024;
+/// Notifications waiting for the writer thread, unless the plugin config sets
+/// `ipc.queue_capacity`
+pub const DEFAULT_QUEUE_CAPACITY: usize = 100_000;
+
+/// Forwards the notifications of the validator to an `agave-geyser-plugin-host`
+/// listening on the Unix socket given by the `ipc.socket_path` field of the
+/// plugin config.
+///
+/// Notifications are queued for a writer thread, so the validator threads
+/// producing them never wait for the host, and are not acknowledged. They are
+/// dropped when the queue of `ipc.queue_capacity` notifications is full or the
+/// host is unreachable, and the host is told about the dropped notifications
+/// with a `NotificationGap`. A host that stops reading is disconnected once a
+/// write has been stalled for [`WRITE_TIMEOUT`], and the connection is retried
+/// at most every second.
+#[derive(Debug, Default)]
+pub struct IpcGeyserPlugin {
+    socket_path: PathBuf,
+    host: generated::HostHello,
+    sender: Option<SyncSender<Outgoing>>,
+    // Notifications dropped because the queue was full. The lock is held
+    // while queueing, so the gap is queued right after the notifications
+    // queued before the dropped ones.
+    overflow: Mutex<Option<NotificationGap>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+enum Outgoing {
+    Notification {
+        slot: Option<Slot>,
+        message: validator_message::Message,
+        flush: bool,
+    },
+    Gap(NotificationGap),
+}
+
+/// The connection to the host, owned by the writer thread
+#[derive(Debug)]
+struct Connection {
+    socket_path: PathBuf,
+    host: generated::HostHello,
+    writer: Option<BufWriter<UnixStream>>,
+    last_connect_attempt: Option<Instant>,
+    dropped: Option<NotificationGap>,
+}
+
+fn record_dropped(dropped: &mut Option<NotificationGap>, gap: &NotificationGap) {
+    let pending = dropped.get_or_insert(NotificationGap {
+        dropped: 0,
+        slots: None,
+        account_updates_only: gap.account_updates_only,
+    });
+    pending.dropped = pending.dropped.saturating_add(gap.dropped);
+    pending.account_updates_only &= gap.account_updates_only;
+    if let Some(slots) = &gap.slots {
+        pending.slots = Some(match pending.slots.take() {
+            Some(pending_slots) => {
+                (*pending_slots.start()).min(*slots.start())
+                    ..=(*pending_slots.end()).max(*slots.end())
+            }
+            None => slots.clone(),
+        });
+    }
+}
+
+fn dropped_notification(slot: Option<Slot>) -> NotificationGap {
+    NotificationGap {
+        dropped: 1,
+        slots: slot.map(|slot| slot..=slot),
+        account_updates_only: false,
+    }
+}
+
+impl Connection {
+    fn run(mut self, receiver: Receiver<Outgoing>) {
+        while let Ok(outgoing) = receiver.recv() {
+            match outgoing {
+                Outgoing::Notification {
+                    slot,
+                    message,
+                    flush,
+                } => self.send(message, flush, dropped_notification(slot)),
+                Outgoing::Gap(gap) => {
+                    let message = validator_message::Message::NotificationGap((&gap).into());
+                    self.send(message, true, gap);
+                }
+            }
+        }
+        if let Some(mut writer) = self.writer.take() {
+            if let Err(err) = writer.flush() {
+                warn!(
+                    "Failed to flush notifications to Geyser plugin host at {:?}: {err}",
+                    self.socket_path
+                );
+            }
+        }
+    }
+
+    fn reconnect(&mut self) {
+        if self
+            .last_connect_attempt
+            .is_some_and(|attempt| attempt.elapsed() < RECONNECT_INTERVAL)
+        {
+            return;
+        }
+        self.last_connect_attempt = Some(Instant::now());
+        let (mut writer, hello) = match IpcGeyserPlugin::connect(&self.socket_path) {
+            Ok(connected) => connected,
+            Err(err) => {
+                debug!(
+                    "Failed to reconnect to Geyser plugin host at {:?}: {err}",
+                    self.socket_path
+                );
+                return;
+            }
+        };
+        if hello != self.host {
+            warn!(
+                "Geyser plugin host at {:?} now serves {hello:?}, the notifications enabled at \
+                 load time ({:?}) are kept until the plugin is reloaded",
+                self.socket_path, self.host
+            );
+        }
+        if let Some(gap) = self.dropped.take() {
+            let message = validator_message::Message::NotificationGap((&gap).into());
+            if let Err(err) = write_message(
+                &mut writer,
+                &generated::ValidatorMessage {
+                    message: Some(message),
+                },
+            )
+            .and_then(|()| writer.flush())
+            {
+                debug!(
+                    "Failed to reconnect to Geyser plugin host at {:?}: {err}",
+                    self.socket_path
+                );
+                self.dropped = Some(gap);
+                return;
+            }
+            warn!(
+                "Reconnected to Geyser plugin host at {:?}, {} notifications were dropped",
+                self.socket_path, gap.dropped
+            );
+        }
+        self.writer = Some(writer);
+    }
+
+    /// Sends `message`, recording `lost` as dropped if it cannot be sent.
+    /// Messages are buffered unless `flush` is set, which is done for the
+    /// notifications ending a batch.
+    fn send(&mut self, message: validator_message::Message, flush: bool, lost: NotificationGap) {
+        if self.writer.is_none() {
+            self.reconnect();
+        }
+        let Some(writer) = self.writer.as_mut() else {
+            record_dropped(&mut self.dropped, &lost);
+            return;
+        };
+        let result = write_message(
+            writer,
+            &generated::ValidatorMessage {
+                message: Some(message),
+            },
+        )
+        .and_then(|()| if flush { writer.flush() } else { Ok(()) });
+        if let Err(err) = result {
+            error!(
+                "Lost connection to Geyser plugin host at {:?}: {err}",
+                self.socket_path
+            );
+            self.writer = None;
+            record_dropped(&mut self.dropped, &lost);
+        }
+    }
+}
+
+impl IpcGeyserPlugin {
+    fn config_from_file(config_file: &str) -> Result<(PathBuf, usize)> {
+        let contents = fs::read_to_string(config_file)?;
+        let config: serde_json::Value =
+            json5::from_str(&contents).map_err(|err| GeyserPluginError::ConfigFileReadError {
+                msg: format!("Failed to parse {config_file}: {err}"),
+            })?;
+        let socket_path = config["ipc"]["socket_path"].as_str().ok_or_else(|| {
+            GeyserPluginError::ConfigFileReadError {
+                msg: format!("{config_file} does not set the string field ipc.socket_path"),
+            }
+        })?;
+        let mut socket_path = PathBuf::from(socket_path);
+        if socket_path.is_relative() {
+            if let Some(config_dir) = Path::new(config_file).parent() {
+                socket_path = config_dir.join(socket_path);
+            }
+        }
+        let queue_capacity = match &config["ipc"]["queue_capacity"] {
+            serde_json::Value::Null => DEFAULT_QUEUE_CAPACITY,
+            queue_capacity => queue_capacity
+                .as_u64()
+                .and_then(|queue_capacity| usize::try_from(queue_capacity).ok())
+                .filter(|queue_capacity| *queue_capacity > 0)
+                .ok_or_else(|| GeyserPluginError::ConfigFileReadError {
+                    msg: format!(
+                        "{config_file} sets ipc.queue_capacity to {queue_capacity}, expected a \
+                         positive integer"
+                    ),
+                })?,
+        };
+        Ok((socket_path, queue_capacity))
+    }
+
+    fn connect(socket_path: &Path) -> io::Result<(BufWriter<UnixStream>, generated::HostHello)> {
+        let mut stream = UnixStream::connect(socket_path)?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        write_message(
+            &mut stream,
+            &generated::ValidatorMessage {
+                message: Some(validator_message::Message::Hello(generated::Hello {
+                    protocol_version: PROTOCOL_VERSION,
+                })),
+            },
+        )?;
+        let hello = match read_message::<generated::HostMessage>(&mut stream)? {
+            Some(generated::HostMessage {
+                message: Some(host_message::Message::Hello(hello)),
+            }) => hello,
+            message => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("expected the host hello, got {message:?}"),
+                ));
+            }
+        };
+        if hello.protocol_version != PROTOCOL_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "host speaks protocol version {}, expected {PROTOCOL_VERSION}",
+                    hello.protocol_version
+                ),
+            ));
+        }
+        stream.set_read_timeout(None)?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        Ok((BufWriter::with_capacity(WRITE_BUFFER_SIZE, stream), hello))
+    }
+
+    /// Queues `message`, which is about `slot`, for the writer thread.
+    /// Messages are buffered unless `flush` is set.
+    fn send(&self, slot: Option<Slot>, message: validator_message::Message, flush: bool) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        let mut overflow = self.overflow.lock().unwrap();
+        if let Some(gap) = overflow.take() {
+            if sender.try_send(Outgoing::Gap(gap.clone())).is_err() {
+                *overflow = Some(gap);
+            }
+        }
+        let outgoing = Outgoing::Notification {
+            slot,
+            message,
+            flush,
+        };
+        // Once a notification is dropped, later ones are dropped too until the
+        // gap is queued, so that the host receives the gap in order
+        if overflow.is_some() || sender.try_send(outgoing).is_err() {
+            record_dropped(&mut overflow, &dropped_notification(slot));
+        }
+    }
+}
+
+impl GeyserPlugin for IpcGeyserPlugin {
+    fn name(&self) -> &'static str {
+        "GeyserPluginIpc"
+    }
+
+    fn on_load(&mut self, config_file: &str, _is_reload: bool) -> Result<()> {
+        let (socket_path, queue_capacity) = Self::config_from_file(config_file)?;
+        self.socket_path = socket_path;
+        let (writer, host) = Self::connect(&self.socket_path).map_err(|err| {
+            GeyserPluginError::Custom(
+                format!(
+                    "Failed to connect to Geyser plugin host at {:?}: {err}",
+                    self.socket_path
+                )
+                .into(),
+            )
+        })?;
+        info!(
+            "Connected to Geyser plugin host at {:?} serving {}",
+            self.socket_path, host.name
+        );
+        self.host = host;
+        let connection = Connection {
+            socket_path: self.socket_path.clone(),
+            host: self.host.clone(),
+            writer: Some(writer),
+            last_connect_attempt: None,
+            dropped: None,
+        };
+        let (sender, receiver) = sync_channel(queue_capacity);
+        self.sender = Some(sender);
+        self.writer = Some(
+            thread::Builder::new()
+                .name("solGeyserIpc".to_string())
+                .spawn(move || connection.run(receiver))
+                .unwrap(),
+        );
+        Ok(())
+    }
+
+    fn on_unload(&mut self) {
+        // The writer thread flushes the queued notifications and exits once
+        // the sender is dropped
+        drop(self.sender.take());
+        if let Some(writer) = self.writer.take() {
+            if writer.join().is_err() {
+                error!("Geyser plugin IPC writer thread panicked");
+            }
+        }
+    }
+
+    fn update_account(
+        &self,
+        account: ReplicaAccountInfoVersions,
+        slot: Slot,
+        is_startup: bool,
+    ) -> Result<()> {
+        let ReplicaAccountInfoVersions::V0_0_3(account) = account else {
+            return Ok(());
+        };
+        let message = validator_message::Message::UpdateAccount(generated::UpdateAccount {
+            slot,
+            is_startup,
+            account: Some(generated::update_account::Account::V003(account.into())),
+        });
+        self.send(Some(slot), message, false);
+        Ok(())
+    }
+
+    fn notify_end_of_startup(&self) -> Result<()> {
+        let message = validator_message::Message::EndOfStartup(generated::EndOfStartup {});
+        self.send(None, message, true);
+        Ok(())
+    }
+
+    fn update_slot_status(
+        &self,
+        slot: Slot,
+        parent: Option<u64>,
+        status: &SlotStatus,
+    ) -> Result<()> {
+        let (status, dead_error) = encode_slot_status(status);
+        let message = validator_message::Message::UpdateSlotStatus(generated::UpdateSlotStatus {
+            slot,
+            parent,
+            status: status.into(),
+            dead_error,
+        });
+        self.send(Some(slot), message, true);
+        Ok(())
+    }
+
+    fn notify_transaction(
+        &self,
+        transaction: ReplicaTransactionInfoVersions,
+        slot: Slot,
+    ) -> Result<()> {
+        let ReplicaTransactionInfoVersions::V0_0_3(transaction) = transaction else {
+            return Ok(());
+        };
+        let message = validator_message::Message::NotifyTransaction(generated::NotifyTransaction {
+            slot,
+            transaction: Some(generated::notify_transaction::Transaction::V003(
+                transaction.into(),
+            )),
+        });
+        self.send(Some(slot), message, false);
+        Ok(())
+    }
+
+    fn notify_entry(&self, entry: ReplicaEntryInfoVersions) -> Result<()> {
+        let ReplicaEntryInfoVersions::V0_0_2(entry) = entry else {
+            return Ok(());
+        };
+        let message = validator_message::Message::NotifyEntry(generated::NotifyEntry {
+            entry: Some(generated::notify_entry::Entry::V002(entry.into())),
+        });
+        self.send(Some(entry.slot), message, false);
+        Ok(())
+    }
+
+    fn notify_block_metadata(&self, blockinfo: ReplicaBlockInfoVersions) -> Result<()> {
+        let ReplicaBlockInfoVersions::V0_0_4(block) = blockinfo else {
+            return Ok(());
+        };
+        let message =
+            validator_message::Message::NotifyBlockMetadata(generated::NotifyBlockMetadata {
+                block: Some(generated::notify_block_metadata::Block::V004(block.into())),
+            });
+        self.send(Some(block.slot), message, true);
+        Ok(())
+    }
+
+    fn notify_gap(&self, gap: &NotificationGap) -> Result<()> {
+        let message = validator_message::Message::NotificationGap(gap.into());
+        self.send(None, message, true);
+        Ok(())
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        self.host.account_data_notifications_enabled
+    }
+
+    fn account_data_snapshot_notifications_enabled(&self) -> bool {
+        self.host.account_data_snapshot_notifications_enabled
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        self.host.transaction_notifications_enabled
+    }
+
+    fn entry_notifications_enabled(&self) -> bool {
+        self.host.entry_notifications_enabled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, agave_geyser_plugin_interface::geyser_plugin_interface::ReplicaAccountInfoV3,
+        solana_pubkey::Pubkey, std::os::unix::net::UnixListener,
+    };
+
+    #[test]
+    fn test_stalled_host_does_not_block_notifications() {
+        let dir = tempfile::tempdir().unwrap();
+        let listener = UnixListener::bind(dir.path().join("host.sock")).unwrap();
+        let config_file = dir.path().join("plugin.json");
+        fs::write(
+            &config_file,
+            r#"{ ipc: { socket_path: "host.sock", queue_capacity: 4 } }"#,
+        )
+        .unwrap();
+
+        // Answers the handshake, then never reads
+        let host_thread = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            read_message::<generated::ValidatorMessage>(&mut stream).unwrap();
+            write_message(
+                &mut stream,
+                &generated::HostMessage {
+                    message: Some(host_message::Message::Hello(generated::HostHello {
+                        protocol_version: PROTOCOL_VERSION,
+                        account_data_notifications_enabled: true,
+                        ..generated::HostHello::default()
+                    })),
+                },
+            )
+            .unwrap();
+            stream
+        });
+        let mut plugin = IpcGeyserPlugin::default();
+        plugin
+            .on_load(config_file.to_str().unwrap(), false)
+            .unwrap();
+        let host_stream = host_thread.join().unwrap();
+
+        // Far more data than the socket and write buffers hold
+        let pubkey = Pubkey::new_unique();
+        let data = vec![0; 64 * 1024];
+        let account = ReplicaAccountInfoV3 {
+            pubkey: pubkey.as_ref(),
+            lamports: 1,
+            owner: pubkey.as_ref(),
+            executable: false,
+            rent_epoch: 0,
+            data: &data,
+            write_version: 0,
+            txn: None,
+        };
+        let start = Instant::now();
+        for slot in 0..1000 {
+            plugin
+                .update_account(ReplicaAccountInfoVersions::V0_0_3(&account), slot, false)
+                .unwrap();
+        }
+        assert!(start.elapsed() < WRITE_TIMEOUT);
+        assert!(plugin.overflow.lock().unwrap().is_some());
+
+        drop(host_stream);
+        plugin.on_unload();
+    }
+}
@@ -0,0 +1,439 @@
+//! The host side: runs a Geyser plugin library outside of the validator and
+//! calls it with the notifications received over the socket.
+
+use {
+    crate::protocol::{
+        PROTOCOL_VERSION, decode_slot_status,
+        generated::{
+            self, notify_block_metadata, notify_entry, notify_transaction, update_account,
+            validator_message,
+        },
+        read_message, write_message,
+    },
+    agave_geyser_plugin_interface::geyser_plugin_interface::{
+        GeyserPlugin, GeyserPluginError, ReplicaAccountInfoV3, ReplicaAccountInfoVersions,
+        ReplicaBlockInfoV4, ReplicaBlockInfoVersions, ReplicaEntryInfoV2, ReplicaEntryInfoVersions,
+        ReplicaTransactionInfoV3, ReplicaTransactionInfoVersions,
+    },
+    libloading::Library,
+    log::*,
+    solana_hash::Hash,
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    solana_transaction_status::{RewardsAndNumPartitions, TransactionStatusMeta},
+    std::{
+        fs,
+        io::{self, BufReader},
+        os::unix::net::{UnixListener, UnixStream},
+        path::{Path, PathBuf},
+    },
+    thiserror::Error,
+};
+
+#[derive(Error, Debug)]
+pub enum PluginHostError {
+    #[error("Cannot read the config file {0:?}: {1}")]
+    CannotReadConfigFile(PathBuf, io::Error),
+
+    #[error("The config file {0:?} is not in a valid Json5 format: {1}")]
+    InvalidConfigFileFormat(PathBuf, String),
+
+    #[error("The config file {0:?} does not set the libpath field")]
+    LibPathNotSet(PathBuf),
+
+    #[error("Failed to load the plugin library: {0}")]
+    PluginLoadError(String),
+
+    #[error("The plugin failed to start: {0}")]
+    PluginStartError(#[from] GeyserPluginError),
+}
+
+/// A Geyser plugin served to validators connecting to a Unix socket
+#[derive(Debug)]
+pub struct PluginHost {
+    plugin: Box<dyn GeyserPlugin>,
+    // Declared after the plugin so the plugin is dropped first, see
+    // `LoadedGeyserPlugin` in the plugin manager
+    _library: Library,
+}
+
+impl PluginHost {
+    /// Loads the plugin library named by the `libpath` field of the config
+    /// file, the same config file the validator would be given for an
+    /// in-process plugin, and calls its `on_load`.
+    ///
+    /// # Safety
+    ///
+    /// The library must export a `_create_plugin` function returning a
+    /// `GeyserPlugin` built against this version of the plugin interface.
+    pub unsafe fn load(config_file: &Path) -> Result<Self, PluginHostError> {
+        type PluginConstructor = unsafe fn() -> *mut dyn GeyserPlugin;
+
+        let contents = fs::read_to_string(config_file)
+            .map_err(|err| PluginHostError::CannotReadConfigFile(config_file.to_owned(), err))?;
+        let config: serde_json::Value = json5::from_str(&contents).map_err(|err| {
+            PluginHostError::InvalidConfigFileFormat(config_file.to_owned(), err.to_string())
+        })?;
+        let libpath = config["libpath"]
+            .as_str()
+            .ok_or_else(|| PluginHostError::LibPathNotSet(config_file.to_owned()))?;
+        let mut libpath = PathBuf::from(libpath);
+        if libpath.is_relative() {
+            if let Some(config_dir) = config_file.parent() {
+                libpath = config_dir.join(libpath);
+            }
+        }
+        let config_file = config_file.to_str().ok_or_else(|| {
+            PluginHostError::InvalidConfigFileFormat(
+                config_file.to_owned(),
+                "the path is not valid UTF-8".to_string(),
+            )
+        })?;
+
+        let (plugin, library) = unsafe {
+            let library = Library::new(&libpath)
+                .map_err(|err| PluginHostError::PluginLoadError(err.to_string()))?;
+            let constructor: libloading::Symbol<PluginConstructor> = library
+                .get(b"_create_plugin")
+                .map_err(|err| PluginHostError::PluginLoadError(err.to_string()))?;
+            (Box::from_raw(constructor()), library)
+        };
+        let mut host = Self::new(plugin, library);
+        host.plugin.setup_logger(log::logger(), log::max_level())?;
+        host.plugin.on_load(config_file, false)?;
+        info!(
+            "Loaded Geyser plugin {} from {libpath:?}",
+            host.plugin.name()
+        );
+        Ok(host)
+    }
+
+    /// Serves an already loaded plugin
+    pub fn new(plugin: Box<dyn GeyserPlugin>, library: Library) -> Self {
+        Self {
+            plugin,
+            _library: library,
+        }
+    }
+
+    /// Serves the validators connecting to `listener`, one at a time
+    pub fn serve(&self, listener: &UnixListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            info!("Validator connected");
+            match self.serve_connection(stream) {
+                Ok(()) => info!("Validator disconnected"),
+                Err(err) => warn!("Validator connection failed: {err}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Serves a single validator connection until the validator closes it
+    pub fn serve_connection(&self, stream: UnixStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+
+        match read_message::<generated::ValidatorMessage>(&mut reader)? {
+            Some(generated::ValidatorMessage {
+                message: Some(validator_message::Message::Hello(hello)),
+            }) if hello.protocol_version == PROTOCOL_VERSION => {}
+            message => {
+                return Err(invalid_data(format!(
+                    "expected a protocol version {PROTOCOL_VERSION} hello, got {message:?}"
+                )));
+            }
+        }
+        write_message(
+            &mut writer,
+            &generated::HostMessage {
+                message: Some(generated::host_message::Message::Hello(
+                    generated::HostHello {
+                        protocol_version: PROTOCOL_VERSION,
+                        name: self.plugin.name().to_string(),
+                        account_data_notifications_enabled: self
+                            .plugin
+                            .account_data_notifications_enabled(),
+                        account_data_snapshot_notifications_enabled: self
+                            .plugin
+                            .account_data_snapshot_notifications_enabled(),
+                        transaction_notifications_enabled: self
+                            .plugin
+                            .transaction_notifications_enabled(),
+                        entry_notifications_enabled: self.plugin.entry_notifications_enabled(),
+                    },
+                )),
+            },
+        )?;
+
+        while let Some(message) = read_message::<generated::ValidatorMessage>(&mut reader)? {
+            let Some(message) = message.message else {
+                continue;
+            };
+            self.notify(message)?;
+        }
+        Ok(())
+    }
+
+    /// Calls the plugin with a notification. Errors returned by the plugin are
+    /// logged, like the validator does for in-process plugins, while malformed
+    /// messages end the connection.
+    fn notify(&self, message: validator_message::Message) -> io::Result<()> {
+        let result = match message {
+            validator_message::Message::Hello(_) => {
+                return Err(invalid_data("unexpected hello"));
+            }
+            validator_message::Message::UpdateAccount(generated::UpdateAccount {
+                slot,
+                is_startup,
+                account: Some(update_account::Account::V003(account)),
+            }) => {
+                let account = ReplicaAccountInfoV3 {
+                    pubkey: &account.pubkey,
+                    lamports: account.lamports,
+                    owner: &account.owner,
+                    executable: account.executable,
+                    rent_epoch: account.rent_epoch,
+                    data: &account.data,
+                    write_version: account.write_version,
+                    txn: None,
+                };
+                self.plugin.update_account(
+                    ReplicaAccountInfoVersions::V0_0_3(&account),
+                    slot,
+                    is_startup,
+                )
+            }
+            validator_message::Message::EndOfStartup(_) => self.plugin.notify_end_of_startup(),
+            validator_message::Message::UpdateSlotStatus(update) => {
+                let status = decode_slot_status(update.status(), update.dead_error);
+                self.plugin
+                    .update_slot_status(update.slot, update.parent, &status)
+            }
+            validator_message::Message::NotifyTransaction(generated::NotifyTransaction {
+                slot,
+                transaction: Some(notify_transaction::Transaction::V003(transaction)),
+            }) => {
+                let signature = Signature::try_from(transaction.signature.as_slice())
+                    .map_err(|_| invalid_data("invalid transaction signature"))?;
+                let message_hash = Hash::try_from(transaction.message_hash.as_slice())
+                    .map_err(|_| invalid_data("invalid transaction message hash"))?;
+                let versioned_transaction: VersionedTransaction = transaction
+                    .transaction
+                    .ok_or_else(|| invalid_data("missing transaction"))?
+                    .into();
+                let transaction_status_meta: TransactionStatusMeta = transaction
+                    .transaction_status_meta
+                    .ok_or_else(|| invalid_data("missing transaction status meta"))?
+                    .try_into()
+                    .map_err(|err| invalid_data(format!("invalid transaction status: {err}")))?;
+                let transaction = ReplicaTransactionInfoV3 {
+                    signature: &signature,
+                    message_hash: &message_hash,
+                    is_vote: transaction.is_vote,
+                    transaction: &versioned_transaction,
+                    transaction_status_meta: &transaction_status_meta,
+                    index: transaction.index as usize,
+                };
+                self.plugin
+                    .notify_transaction(ReplicaTransactionInfoVersions::V0_0_3(&transaction), slot)
+            }
+            validator_message::Message::NotifyEntry(generated::NotifyEntry {
+                entry: Some(notify_entry::Entry::V002(entry)),
+            }) => {
+                let entry = ReplicaEntryInfoV2 {
+                    slot: entry.slot,
+                    index: entry.index as usize,
+                    num_hashes: entry.num_hashes,
+                    hash: &entry.hash,
+                    executed_transaction_count: entry.executed_transaction_count,
+                    starting_transaction_index: entry.starting_transaction_index as usize,
+                };
+                self.plugin
+                    .notify_entry(ReplicaEntryInfoVersions::V0_0_2(&entry))
+            }
+            validator_message::Message::NotifyBlockMetadata(generated::NotifyBlockMetadata {
+                block: Some(notify_block_metadata::Block::V004(block)),
+            }) => {
+                let rewards: RewardsAndNumPartitions = block.rewards.unwrap_or_default().into();
+                let block = ReplicaBlockInfoV4 {
+                    parent_slot: block.parent_slot,
+                    parent_blockhash: &block.parent_blockhash,
+                    slot: block.slot,
+                    blockhash: &block.blockhash,
+                    rewards: &rewards,
+                    block_time: block.block_time,
+                    block_height: block.block_height,
+                    executed_transaction_count: block.executed_transaction_count,
+                    entry_count: block.entry_count,
+                };
+                self.plugin
+                    .notify_block_metadata(ReplicaBlockInfoVersions::V0_0_4(&block))
+            }
+            validator_message::Message::NotificationGap(gap) => self.plugin.notify_gap(&gap.into()),
+            message => {
+                // A version of the notification this host does not know about
+                warn!("Ignoring unsupported notification {message:?}");
+                Ok(())
+            }
+        };
+        if let Err(err) = result {
+            error!(
+                "Geyser plugin {} failed to handle a notification: {err}",
+                self.plugin.name()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PluginHost {
+    fn drop(&mut self) {
+        self.plugin.on_unload();
+    }
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::client::IpcGeyserPlugin,
+        agave_geyser_plugin_interface::geyser_plugin_interface::{NotificationGap, SlotStatus},
+        solana_geyser_plugin_manager::recording_plugin::{Notification, RecordingPlugin},
+        solana_message::{Message, VersionedMessage},
+        solana_pubkey::Pubkey,
+        std::{sync::Arc, thread},
+    };
+
+    fn this_library() -> Library {
+        #[cfg(unix)]
+        let library = libloading::os::unix::Library::this();
+        #[cfg(windows)]
+        let library = libloading::os::windows::Library::this().unwrap();
+        Library::from(library)
+    }
+
+    #[test]
+    fn test_forward_notifications_to_host() {
+        let plugin = RecordingPlugin {
+            transactions: true,
+            ..RecordingPlugin::default()
+        };
+        let events = Arc::clone(&plugin.notifications);
+        let host = PluginHost::new(Box::new(plugin), this_library());
+
+        let dir = tempfile::tempdir().unwrap();
+        let listener = UnixListener::bind(dir.path().join("host.sock")).unwrap();
+        let config_file = dir.path().join("plugin.json");
+        fs::write(&config_file, r#"{ ipc: { socket_path: "host.sock" } }"#).unwrap();
+
+        let host_thread = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            host.serve_connection(stream).unwrap();
+        });
+
+        let mut plugin = IpcGeyserPlugin::default();
+        plugin
+            .on_load(config_file.to_str().unwrap(), false)
+            .unwrap();
+        assert!(plugin.account_data_notifications_enabled());
+        assert!(plugin.transaction_notifications_enabled());
+        assert!(!plugin.entry_notifications_enabled());
+
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let account = ReplicaAccountInfoV3 {
+            pubkey: pubkey.as_ref(),
+            lamports: 7,
+            owner: owner.as_ref(),
+            executable: false,
+            rent_epoch: 0,
+            data: &[1, 2, 3],
+            write_version: 1,
+            txn: None,
+        };
+        plugin
+            .update_account(ReplicaAccountInfoVersions::V0_0_3(&account), 5, false)
+            .unwrap();
+
+        let signature = Signature::from([3; 64]);
+        let transaction = VersionedTransaction {
+            signatures: vec![signature],
+            message: VersionedMessage::Legacy(Message::new(&[], Some(&pubkey))),
+        };
+        let transaction_status_meta = TransactionStatusMeta {
+            fee: 5000,
+            ..TransactionStatusMeta::default()
+        };
+        let transaction_info = ReplicaTransactionInfoV3 {
+            signature: &signature,
+            message_hash: &Hash::default(),
+            is_vote: false,
+            transaction: &transaction,
+            transaction_status_meta: &transaction_status_meta,
+            index: 0,
+        };
+        plugin
+            .notify_transaction(ReplicaTransactionInfoVersions::V0_0_3(&transaction_info), 5)
+            .unwrap();
+        let gap = NotificationGap {
+            dropped: 2,
+            slots: Some(3..=4),
+            account_updates_only: true,
+        };
+        plugin.notify_gap(&gap).unwrap();
+        plugin
+            .update_slot_status(5, Some(4), &SlotStatus::Dead("bad".to_string()))
+            .unwrap();
+        plugin.on_unload();
+
+        host_thread.join().unwrap();
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                Notification::Account {
+                    slot: 5,
+                    pubkey,
+                    lamports: 7,
+                    is_startup: false,
+                    write_version: 1,
+                    write_sequence: None,
+                },
+                Notification::Transaction {
+                    slot: 5,
+                    signature,
+                    fee: 5000,
+                },
+                Notification::Gap(gap),
+                Notification::SlotStatus {
+                    slot: 5,
+                    parent: Some(4),
+                    status: SlotStatus::Dead("bad".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reject_protocol_version_mismatch() {
+        let host = PluginHost::new(Box::<RecordingPlugin>::default(), this_library());
+        let (mut validator, host_stream) = UnixStream::pair().unwrap();
+        write_message(
+            &mut validator,
+            &generated::ValidatorMessage {
+                message: Some(validator_message::Message::Hello(generated::Hello {
+                    protocol_version: PROTOCOL_VERSION + 1,
+                })),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            host.serve_connection(host_stream).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "agave-unstable-api")]
+//! Runs Geyser plugins outside of the validator process.
+//!
+//! [`client::IpcGeyserPlugin`] is loaded by the validator in place of the
+//! plugin library, when the plugin config has an `ipc` section, and forwards
+//! the notifications over a Unix socket to `agave-geyser-plugin-host`, which
+//! loads the plugin library and calls it. A crashing or slow plugin then
+//! cannot take the validator down with it, and plugins can be restarted or
+//! upgraded without restarting the validator.
+//!
+//! # Protocol
+//!
+//! Every message is a protobuf message of `proto/geyser_ipc.proto`, written
+//! to the socket preceded by its length as a protobuf varint. The validator
+//! opens the connection and sends `Hello` with [`protocol::PROTOCOL_VERSION`];
+//! the host replies with `HostHello`, describing the notifications the plugin
+//! enabled, and closes the connection if the versions differ. Afterwards only
+//! the validator sends messages, one per notification, without waiting for
+//! acknowledgements. Notifications carry a oneof of the versions of the
+//! `Replica*Info` structures so new versions can be added without breaking
+//! existing hosts.
+//!
+//! The account, slot status, transaction, entry, block metadata, end of
+//! startup and gap notifications are forwarded. Account updates are forwarded
+//! without the transaction that wrote them, so the host always passes a `txn`
+//! of `None` to the plugin. Deshredded transactions, contact info, consensus
+//! events, abandoned slots and the start and end of account re-streams are
+//! not forwarded, and the host plugin's notification filter, catch-up request
+//! and account rollback opt-in are not applied by the validator.
+
+pub mod client;
+pub mod host;
+pub mod protocol;
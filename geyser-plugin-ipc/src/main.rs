@@ -0,0 +1,68 @@
+//! Serves a Geyser plugin library to a validator over a Unix socket.
+
+use {
+    agave_geyser_plugin_ipc::host::PluginHost,
+    clap::{App, Arg, crate_description, crate_name},
+    log::*,
+    std::{fs, io, os::unix::net::UnixListener, path::Path, process::exit},
+};
+
+fn main() {
+    agave_logger::setup_with_default_filter();
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(solana_version::version!())
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .value_name("FILE")
+                .takes_value(true)
+                .required(true)
+                .help("Config file of the Geyser plugin to serve"),
+        )
+        .arg(
+            Arg::with_name("socket")
+                .long("socket")
+                .value_name("PATH")
+                .takes_value(true)
+                .required(true)
+                .help(
+                    "Unix socket to listen on for the validator, the ipc.socket_path of the \
+                     validator's plugin config",
+                ),
+        )
+        .get_matches();
+
+    let config_file = Path::new(matches.value_of("config").unwrap());
+    let socket_path = Path::new(matches.value_of("socket").unwrap());
+
+    // Safety: the operator vouches for the plugin library named by the config
+    let host = match unsafe { PluginHost::load(config_file) } {
+        Ok(host) => host,
+        Err(err) => {
+            error!("Failed to load the Geyser plugin: {err}");
+            exit(1);
+        }
+    };
+
+    // Remove the socket left behind by a previous run
+    match fs::remove_file(socket_path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            error!("Failed to remove {socket_path:?}: {err}");
+            exit(1);
+        }
+        _ => {}
+    }
+    let listener = match UnixListener::bind(socket_path) {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!("Failed to listen on {socket_path:?}: {err}");
+            exit(1);
+        }
+    };
+    info!("Serving Geyser plugin on {socket_path:?}");
+    if let Err(err) = host.serve(&listener) {
+        error!("Failed to accept validator connections: {err}");
+        exit(1);
+    }
+}
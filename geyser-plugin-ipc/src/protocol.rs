@@ -0,0 +1,241 @@
+//! Framing and encoding of the messages exchanged with a plugin host.
+
+use {
+    agave_geyser_plugin_interface::geyser_plugin_interface::{
+        NotificationGap, ReplicaAccountInfoV3, ReplicaBlockInfoV4, ReplicaEntryInfoV2,
+        ReplicaTransactionInfoV3, SlotStatus,
+    },
+    prost::Message,
+    std::io::{self, Read, Write},
+};
+
+pub mod generated {
+    include!(concat!(env!("OUT_DIR"), "/agave.geyser.ipc.rs"));
+}
+
+/// Version of the protocol, exchanged in the `Hello` messages. Hosts reject
+/// validators speaking another version.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Largest accepted message, which leaves room for the largest account
+pub const MAX_MESSAGE_LEN: usize = 64 * 1024 * 1024;
+
+/// Writes `message` preceded by its length as a protobuf varint
+pub fn write_message(writer: &mut impl Write, message: &impl Message) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(message.encoded_len().saturating_add(10));
+    message
+        .encode_length_delimited(&mut buf)
+        .map_err(io::Error::other)?;
+    writer.write_all(&buf)
+}
+
+/// Reads a message written by [`write_message`]. Returns `None` if the
+/// stream ended before the message started.
+pub fn read_message<M: Message + Default>(reader: &mut impl Read) -> io::Result<Option<M>> {
+    let mut len = 0usize;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        if let Err(err) = reader.read_exact(&mut byte) {
+            return match err.kind() {
+                io::ErrorKind::UnexpectedEof if shift == 0 => Ok(None),
+                _ => Err(err),
+            };
+        }
+        len |= usize::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    if len > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {len} bytes exceeds the {MAX_MESSAGE_LEN} bytes limit"),
+        ));
+    }
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf)?;
+    M::decode(buf.as_slice())
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+impl From<&ReplicaAccountInfoV3<'_>> for generated::ReplicaAccountInfoV3 {
+    fn from(account: &ReplicaAccountInfoV3<'_>) -> Self {
+        Self {
+            pubkey: account.pubkey.to_vec(),
+            lamports: account.lamports,
+            owner: account.owner.to_vec(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: account.data.to_vec(),
+            write_version: account.write_version,
+        }
+    }
+}
+
+impl From<&ReplicaTransactionInfoV3<'_>> for generated::ReplicaTransactionInfoV3 {
+    fn from(transaction: &ReplicaTransactionInfoV3<'_>) -> Self {
+        Self {
+            signature: transaction.signature.as_ref().to_vec(),
+            message_hash: transaction.message_hash.as_ref().to_vec(),
+            is_vote: transaction.is_vote,
+            transaction: Some(transaction.transaction.clone().into()),
+            transaction_status_meta: Some(transaction.transaction_status_meta.clone().into()),
+            index: transaction.index as u64,
+        }
+    }
+}
+
+impl From<&ReplicaEntryInfoV2<'_>> for generated::ReplicaEntryInfoV2 {
+    fn from(entry: &ReplicaEntryInfoV2<'_>) -> Self {
+        Self {
+            slot: entry.slot,
+            index: entry.index as u64,
+            num_hashes: entry.num_hashes,
+            hash: entry.hash.to_vec(),
+            executed_transaction_count: entry.executed_transaction_count,
+            starting_transaction_index: entry.starting_transaction_index as u64,
+        }
+    }
+}
+
+impl From<&ReplicaBlockInfoV4<'_>> for generated::ReplicaBlockInfoV4 {
+    fn from(block: &ReplicaBlockInfoV4<'_>) -> Self {
+        Self {
+            parent_slot: block.parent_slot,
+            parent_blockhash: block.parent_blockhash.to_string(),
+            slot: block.slot,
+            blockhash: block.blockhash.to_string(),
+            rewards: Some(block.rewards.clone().into()),
+            block_time: block.block_time,
+            block_height: block.block_height,
+            executed_transaction_count: block.executed_transaction_count,
+            entry_count: block.entry_count,
+        }
+    }
+}
+
+impl From<&NotificationGap> for generated::NotificationGap {
+    fn from(gap: &NotificationGap) -> Self {
+        Self {
+            dropped: gap.dropped,
+            first_slot: gap.slots.as_ref().map(|slots| *slots.start()),
+            last_slot: gap.slots.as_ref().map(|slots| *slots.end()),
+            account_updates_only: gap.account_updates_only,
+        }
+    }
+}
+
+impl From<generated::NotificationGap> for NotificationGap {
+    fn from(gap: generated::NotificationGap) -> Self {
+        Self {
+            dropped: gap.dropped,
+            slots: gap
+                .first_slot
+                .zip(gap.last_slot)
+                .map(|(first_slot, last_slot)| first_slot..=last_slot),
+            account_updates_only: gap.account_updates_only,
+        }
+    }
+}
+
+/// Returns the encoded status and, for dead slots, the error
+pub fn encode_slot_status(status: &SlotStatus) -> (generated::SlotStatus, String) {
+    let status = match status {
+        SlotStatus::Processed => generated::SlotStatus::Processed,
+        SlotStatus::Rooted => generated::SlotStatus::Rooted,
+        SlotStatus::Confirmed => generated::SlotStatus::Confirmed,
+        SlotStatus::FirstShredReceived => generated::SlotStatus::FirstShredReceived,
+        SlotStatus::Completed => generated::SlotStatus::Completed,
+        SlotStatus::CreatedBank => generated::SlotStatus::CreatedBank,
+        SlotStatus::Dead(error) => return (generated::SlotStatus::Dead, error.clone()),
+    };
+    (status, String::new())
+}
+
+pub fn decode_slot_status(status: generated::SlotStatus, dead_error: String) -> SlotStatus {
+    match status {
+        generated::SlotStatus::Processed => SlotStatus::Processed,
+        generated::SlotStatus::Rooted => SlotStatus::Rooted,
+        generated::SlotStatus::Confirmed => SlotStatus::Confirmed,
+        generated::SlotStatus::FirstShredReceived => SlotStatus::FirstShredReceived,
+        generated::SlotStatus::Completed => SlotStatus::Completed,
+        generated::SlotStatus::CreatedBank => SlotStatus::CreatedBank,
+        generated::SlotStatus::Dead => SlotStatus::Dead(dead_error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{generated::validator_message, *},
+        std::io::Cursor,
+    };
+
+    #[test]
+    fn test_read_write_message() {
+        let messages = [
+            generated::ValidatorMessage {
+                message: Some(validator_message::Message::Hello(generated::Hello {
+                    protocol_version: PROTOCOL_VERSION,
+                })),
+            },
+            generated::ValidatorMessage {
+                message: Some(validator_message::Message::UpdateAccount(
+                    generated::UpdateAccount {
+                        slot: 42,
+                        is_startup: false,
+                        account: Some(generated::update_account::Account::V003(
+                            generated::ReplicaAccountInfoV3 {
+                                data: vec![7; 300],
+                                ..generated::ReplicaAccountInfoV3::default()
+                            },
+                        )),
+                    },
+                )),
+            },
+        ];
+        let mut buf = vec![];
+        for message in &messages {
+            write_message(&mut buf, message).unwrap();
+        }
+
+        let mut reader = Cursor::new(buf.clone());
+        for message in &messages {
+            assert_eq!(
+                read_message::<generated::ValidatorMessage>(&mut reader).unwrap(),
+                Some(message.clone())
+            );
+        }
+        assert_eq!(
+            read_message::<generated::ValidatorMessage>(&mut reader).unwrap(),
+            None
+        );
+
+        // A message cut short is an error, not the end of the stream
+        let mut reader = Cursor::new(&buf[..buf.len() - 1]);
+        read_message::<generated::ValidatorMessage>(&mut reader).unwrap();
+        assert_eq!(
+            read_message::<generated::ValidatorMessage>(&mut reader)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_slot_status_round_trip() {
+        for status in [
+            SlotStatus::Processed,
+            SlotStatus::Rooted,
+            SlotStatus::Confirmed,
+            SlotStatus::FirstShredReceived,
+            SlotStatus::Completed,
+            SlotStatus::CreatedBank,
+            SlotStatus::Dead("bad block".to_string()),
+        ] {
+            let (encoded, dead_error) = encode_slot_status(&status);
+            assert_eq!(decode_slot_status(encoded, dead_error), status);
+        }
+    }
+}
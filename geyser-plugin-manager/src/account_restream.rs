@@ -0,0 +1,478 @@
+//! Re-streaming of the accounts of the root bank to a plugin, requested
+//! through the `restreamPluginAccounts` admin RPC method.
+//!
+//! A re-stream scans the root bank at the time of the request from a
+//! dedicated thread, and delivers the accounts owned by the requested programs
+//! framed like the accounts restored from the snapshot at startup:
+//! `notify_account_restream_start`, then `update_account` with `is_startup`
+//! set, then `notify_account_restream_end`. Plugins with a dispatch queue
+//! receive the re-stream through their queue, in order with their live
+//! notifications. Plugins called inline are given the accounts by a delivery
+//! thread fed by a bounded queue, so that the scan runs ahead of a slow plugin
+//! by at most [`RESTREAM_DELIVERY_QUEUE_CAPACITY`] accounts.
+//!
+//! The accounts are delivered with a `write_version` and `write_sequence` of
+//! zero and without their transaction, and are subject to the plugin's
+//! notification filter. Unloading or reloading the plugin aborts the
+//! re-stream.
+
+use {
+    crate::{
+        accounts_update_notifier::{AccountsUpdateNotifierImpl, OwnedAccountInfo},
+        geyser_plugin_manager::{GeyserPluginManager, LoadedGeyserPlugin},
+    },
+    agave_geyser_plugin_interface::geyser_plugin_interface::{
+        ReplicaAccountInfoV3, ReplicaAccountRestreamInfoV0_0_1, ReplicaAccountRestreamInfoVersions,
+    },
+    crossbeam_channel::bounded,
+    jsonrpc_core::{ErrorCode, Result as JsonRpcResult},
+    log::*,
+    serde::{Deserialize, Serialize},
+    solana_account::ReadableAccount,
+    solana_clock::Slot,
+    solana_pubkey::Pubkey,
+    solana_runtime::{bank::Bank, bank_forks::BankForks},
+    std::{
+        collections::HashSet,
+        sync::{
+            Arc, OnceLock, RwLock, Weak,
+            atomic::{AtomicU64, Ordering},
+        },
+        thread,
+    },
+};
+
+/// Number of accounts the scan of a re-stream to a plugin called inline can
+/// run ahead of the plugin
+const RESTREAM_DELIVERY_QUEUE_CAPACITY: usize = 1024;
+
+/// A re-stream started for a plugin, returned by the admin RPC method
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountRestream {
+    pub id: u64,
+    pub plugin: String,
+    /// The rooted slot whose accounts are re-streamed
+    pub slot: Slot,
+    /// The programs owning the re-streamed accounts
+    pub owners: Vec<String>,
+}
+
+/// The banks re-streams read from. Plugins given on the command line are
+/// loaded before the banks, so re-streams are refused until the banks are
+/// provided.
+#[derive(Debug)]
+pub(crate) struct AccountRestreamSource {
+    bank_forks: OnceLock<Arc<RwLock<BankForks>>>,
+    next_id: AtomicU64,
+}
+
+impl Default for AccountRestreamSource {
+    fn default() -> Self {
+        Self {
+            bank_forks: OnceLock::new(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+}
+
+impl AccountRestreamSource {
+    pub(crate) fn set_bank_forks(&self, bank_forks: Arc<RwLock<BankForks>>) {
+        if self.bank_forks.set(bank_forks).is_err() {
+            warn!("Geyser account re-stream bank forks are already set");
+        }
+    }
+
+    /// Admin RPC request handler. Starts re-streaming to the plugin named
+    /// `name` the accounts owned by `owners` in the current root bank.
+    pub(crate) fn start(
+        &self,
+        plugin_manager: &GeyserPluginManager,
+        name: &str,
+        owners: Vec<Pubkey>,
+    ) -> JsonRpcResult<AccountRestream> {
+        if owners.is_empty() {
+            return Err(jsonrpc_core::Error {
+                code: ErrorCode::InvalidParams,
+                message: "At least one owner is required to re-stream accounts".to_string(),
+                data: None,
+            });
+        }
+        let Some(plugin) = plugin_manager
+            .plugins
+            .iter()
+            .find(|plugin| plugin.name().eq(name))
+        else {
+            return Err(jsonrpc_core::Error {
+                code: ErrorCode::InvalidRequest,
+                message: String::from("The plugin you requested to re-stream to is not loaded"),
+                data: None,
+            });
+        };
+        if !plugin.account_data_notifications_enabled() {
+            return Err(jsonrpc_core::Error {
+                code: ErrorCode::InvalidRequest,
+                message: format!("Plugin {name} does not enable account notifications"),
+                data: None,
+            });
+        }
+        let Some(bank_forks) = self.bank_forks.get() else {
+            return Err(jsonrpc_core::Error {
+                code: ErrorCode::InvalidRequest,
+                message: String::from("The validator has not loaded its banks yet"),
+                data: None,
+            });
+        };
+        let bank = bank_forks.read().unwrap().root_bank();
+        let restream = Arc::new(Restream {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            slot: bank.slot(),
+            owners: owners.iter().map(Pubkey::to_bytes).collect(),
+        });
+        let response = AccountRestream {
+            id: restream.id,
+            plugin: name.to_string(),
+            slot: restream.slot,
+            owners: owners.iter().map(Pubkey::to_string).collect(),
+        };
+
+        let plugin = Arc::downgrade(plugin);
+        let owners = owners.into_iter().collect();
+        thread::Builder::new()
+            .name("solGeyserRstrm".to_string())
+            .spawn(move || restream.run(&bank, &owners, &plugin))
+            .map_err(|err| jsonrpc_core::Error {
+                code: ErrorCode::InternalError,
+                message: format!("Failed to start the account re-stream: {err}"),
+                data: None,
+            })?;
+        info!(
+            "Started account re-stream {} of slot {} to plugin {name}",
+            response.id, response.slot
+        );
+        Ok(response)
+    }
+}
+
+#[derive(Debug)]
+struct Restream {
+    id: u64,
+    slot: Slot,
+    owners: Vec<[u8; 32]>,
+}
+
+impl Restream {
+    fn info(&self) -> ReplicaAccountRestreamInfoV0_0_1<'_> {
+        ReplicaAccountRestreamInfoV0_0_1 {
+            id: self.id,
+            slot: self.slot,
+            owners: &self.owners,
+        }
+    }
+
+    /// Delivers the re-stream to the plugin. Returns early if the plugin is
+    /// unloaded.
+    fn run(
+        self: Arc<Self>,
+        bank: &Bank,
+        owners: &HashSet<Pubkey>,
+        plugin: &Weak<LoadedGeyserPlugin>,
+    ) {
+        let Some(loaded_plugin) = plugin.upgrade() else {
+            return;
+        };
+        let restream = Arc::clone(&self);
+        Self::deliver(&loaded_plugin, self.slot, move |plugin| {
+            restream.notify_start(plugin)
+        });
+        let is_inline = loaded_plugin.dispatcher().is_none();
+        drop(loaded_plugin);
+
+        let slot = self.slot;
+        let mut accounts = 0u64;
+        let mut unloaded = false;
+        let scan_result = thread::scope(|scope| {
+            // Plugins called inline are given the accounts by a delivery
+            // thread, which stops once the plugin is unloaded
+            let (sender, receiver) = bounded::<OwnedAccountInfo>(RESTREAM_DELIVERY_QUEUE_CAPACITY);
+            if is_inline {
+                thread::Builder::new()
+                    .name("solGeyRstrmDlvr".to_string())
+                    .spawn_scoped(scope, move || {
+                        for account in receiver {
+                            let Some(plugin) = plugin.upgrade() else {
+                                break;
+                            };
+                            AccountsUpdateNotifierImpl::update_account(
+                                &plugin,
+                                &account.as_replica(),
+                                0,
+                                slot,
+                                true,
+                            );
+                        }
+                    })
+                    .unwrap();
+            }
+            bank.scan_all_accounts(|account| {
+                let Some((pubkey, account, _slot)) = account else {
+                    return;
+                };
+                if unloaded || account.lamports() == 0 || !owners.contains(account.owner()) {
+                    return;
+                }
+                // Only held while delivering an account, so the plugin can be unloaded
+                let Some(plugin) = plugin.upgrade() else {
+                    unloaded = true;
+                    return;
+                };
+                let account = ReplicaAccountInfoV3 {
+                    pubkey: pubkey.as_ref(),
+                    lamports: account.lamports(),
+                    owner: account.owner().as_ref(),
+                    executable: account.executable(),
+                    rent_epoch: account.rent_epoch(),
+                    data: account.data(),
+                    write_version: 0,
+                    txn: None,
+                };
+                if !plugin.notification_filter().matches_account(&account) {
+                    return;
+                }
+                let account = OwnedAccountInfo::new(&account);
+                match plugin.dispatcher() {
+                    // Blocks while the delivery thread is behind
+                    None => {
+                        drop(plugin);
+                        if sender.send(account).is_err() {
+                            unloaded = true;
+                            return;
+                        }
+                    }
+                    Some(dispatcher) => {
+                        dispatcher.dispatch(Some(slot), move |plugin| {
+                            AccountsUpdateNotifierImpl::update_account(
+                                plugin,
+                                &account.as_replica(),
+                                0,
+                                slot,
+                                true,
+                            )
+                        });
+                    }
+                }
+                accounts = accounts.saturating_add(1);
+            })
+        });
+        if let Err(err) = &scan_result {
+            warn!(
+                "Account re-stream {} of slot {slot} failed to scan the accounts: {err}",
+                self.id
+            );
+        }
+
+        let Some(loaded_plugin) = plugin.upgrade() else {
+            info!(
+                "Account re-stream {} of slot {slot} aborted, the plugin was unloaded",
+                self.id
+            );
+            return;
+        };
+        let is_complete = scan_result.is_ok() && !unloaded;
+        let restream = Arc::clone(&self);
+        Self::deliver(&loaded_plugin, slot, move |plugin| {
+            restream.notify_end(plugin, accounts, is_complete)
+        });
+        info!(
+            "Re-streamed {accounts} accounts of slot {slot} to plugin {}",
+            loaded_plugin.name()
+        );
+    }
+
+    /// Calls `notify` with the plugin, through its dispatch queue if it has one
+    fn deliver(
+        plugin: &LoadedGeyserPlugin,
+        slot: Slot,
+        notify: impl FnOnce(&LoadedGeyserPlugin) + Send + 'static,
+    ) {
+        match plugin.dispatcher() {
+            None => notify(plugin),
+            Some(dispatcher) => dispatcher.dispatch(Some(slot), notify),
+        }
+    }
+
+    fn notify_start(&self, plugin: &LoadedGeyserPlugin) {
+        let info = self.info();
+        if let Err(err) =
+            plugin.notify_account_restream_start(ReplicaAccountRestreamInfoVersions::V0_0_1(&info))
+        {
+            error!(
+                "Failed to notify the start of account re-stream {}, error: {} to plugin {}",
+                self.id,
+                err,
+                plugin.name()
+            );
+        }
+    }
+
+    fn notify_end(&self, plugin: &LoadedGeyserPlugin, accounts: u64, is_complete: bool) {
+        let info = self.info();
+        if let Err(err) = plugin.notify_account_restream_end(
+            ReplicaAccountRestreamInfoVersions::V0_0_1(&info),
+            accounts,
+            is_complete,
+        ) {
+            error!(
+                "Failed to notify the end of account re-stream {}, error: {} to plugin {}",
+                self.id,
+                err,
+                plugin.name()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            catch_up::CatchUpSource,
+            plugin_dispatcher::{OverflowPolicy, PluginDispatchConfig},
+            recording_plugin::{Notification, RecordingPlugin},
+        },
+        agave_geyser_plugin_interface::geyser_plugin_interface::{
+            GeyserAccountFilter, GeyserPluginFilter,
+        },
+        solana_account::AccountSharedData,
+        solana_runtime::genesis_utils::create_genesis_config,
+        std::{
+            sync::Mutex,
+            time::{Duration, Instant},
+        },
+    };
+
+    fn wait_for_end(events: &Mutex<Vec<Notification>>) {
+        let start = Instant::now();
+        while !matches!(
+            events.lock().unwrap().last(),
+            Some(Notification::RestreamEnd { .. })
+        ) {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn check_restream(dispatch_config: Option<PluginDispatchConfig>) {
+        let genesis_config = create_genesis_config(1_000_000).genesis_config;
+        let bank = Bank::new_for_tests(&genesis_config);
+        let [owner, other_owner, filtered_out] = [(); 3].map(|()| Pubkey::new_unique());
+        let owned: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        for pubkey in &owned {
+            bank.store_account(pubkey, &AccountSharedData::new(1, 0, &owner));
+        }
+        bank.store_account(
+            &Pubkey::new_unique(),
+            &AccountSharedData::new(1, 0, &other_owner),
+        );
+        bank.store_account(
+            &Pubkey::new_unique(),
+            &AccountSharedData::new(1, 0, &filtered_out),
+        );
+        let bank_forks = BankForks::new_rw_arc(bank);
+        let source = AccountRestreamSource::default();
+
+        let events = Arc::<Mutex<Vec<Notification>>>::default();
+        let plugin = RecordingPlugin {
+            notifications: events.clone(),
+            filter: Some(GeyserPluginFilter {
+                accounts: Some(GeyserAccountFilter {
+                    owners: vec![owner.to_bytes(), other_owner.to_bytes()],
+                    ..GeyserAccountFilter::default()
+                }),
+                transactions: None,
+            }),
+            ..RecordingPlugin::default()
+        };
+        let mut plugin =
+            LoadedGeyserPlugin::new_without_library(Box::new(plugin), Some("restream".to_string()))
+                .with_dispatch_config(dispatch_config);
+        plugin.load("", false).unwrap();
+        let plugin_manager = GeyserPluginManager {
+            plugins: vec![plugin.start(&Arc::new(CatchUpSource::default()))],
+        };
+
+        // Refused until the banks are loaded, and for unknown plugins
+        assert!(
+            source
+                .start(&plugin_manager, "restream", vec![owner])
+                .is_err()
+        );
+        source.set_bank_forks(bank_forks);
+        assert!(source.start(&plugin_manager, "other", vec![owner]).is_err());
+        assert!(source.start(&plugin_manager, "restream", vec![]).is_err());
+
+        let restream = source
+            .start(&plugin_manager, "restream", vec![owner, filtered_out])
+            .unwrap();
+        assert_eq!(
+            restream,
+            AccountRestream {
+                id: 1,
+                plugin: "restream".to_string(),
+                slot: 0,
+                owners: vec![owner.to_string(), filtered_out.to_string()],
+            }
+        );
+        wait_for_end(&events);
+
+        let mut events = std::mem::take(&mut *events.lock().unwrap());
+        assert_eq!(
+            events.remove(0),
+            Notification::RestreamStart {
+                id: 1,
+                slot: 0,
+                owners: vec![owner.to_bytes(), filtered_out.to_bytes()],
+            }
+        );
+        assert_eq!(
+            events.pop(),
+            Some(Notification::RestreamEnd {
+                id: 1,
+                accounts: 3,
+                is_complete: true,
+            })
+        );
+        let mut expected: Vec<Notification> = owned
+            .iter()
+            .map(|&pubkey| Notification::Account {
+                slot: 0,
+                pubkey,
+                lamports: 1,
+                is_startup: true,
+                write_version: 0,
+                write_sequence: None,
+            })
+            .collect();
+        let key = |event: &Notification| match event {
+            Notification::Account { pubkey, .. } => *pubkey,
+            _ => Pubkey::default(),
+        };
+        events.sort_by_key(key);
+        expected.sort_by_key(key);
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn test_restream_inline() {
+        check_restream(None);
+    }
+
+    #[test]
+    fn test_restream_through_dispatch_queue() {
+        check_restream(Some(PluginDispatchConfig {
+            queue_capacity: 16,
+            overflow_policy: OverflowPolicy::Block,
+        }));
+    }
+}
@@ -0,0 +1,227 @@
+//! Tracking of the accounts written in unrooted slots, for plugins rolling
+//! back the account updates of abandoned forks.
+//!
+//! For plugins returning true from
+//! `GeyserPlugin::account_rollback_notifications_enabled`, every account update
+//! is given a write sequence, increasing within one run of the validator
+//! process, and the accounts written in each slot are kept until the slot is
+//! rooted or abandoned. A slot is abandoned when it is dead, or when a root is
+//! set that is neither one of its ancestors nor one of its descendants. Slot
+//! ancestry is learned from the slot status notifications; a slot whose
+//! ancestry is unknown is only abandoned once a higher slot is rooted.
+
+use {
+    agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+    solana_clock::Slot,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, HashSet},
+        sync::{
+            Mutex,
+            atomic::{AtomicU64, Ordering},
+        },
+    },
+};
+
+/// A slot that will never be rooted, with the accounts written in it
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct AbandonedSlot {
+    pub(crate) slot: Slot,
+    pub(crate) accounts: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Default)]
+struct UnrootedSlot {
+    parent: Option<Slot>,
+    accounts: HashSet<Pubkey>,
+}
+
+#[derive(Debug)]
+pub(crate) struct AccountWriteTracker {
+    next_write_sequence: AtomicU64,
+    slots: Mutex<BTreeMap<Slot, UnrootedSlot>>,
+}
+
+impl Default for AccountWriteTracker {
+    fn default() -> Self {
+        // The sequence is not persisted, so it restarts with the validator
+        Self::new(1)
+    }
+}
+
+impl AccountWriteTracker {
+    fn new(first_write_sequence: u64) -> Self {
+        Self {
+            next_write_sequence: AtomicU64::new(first_write_sequence.max(1)),
+            slots: Mutex::default(),
+        }
+    }
+
+    /// Records that `pubkey` is written at `slot` and returns the write
+    /// sequence of the update. Updates restored from a snapshot are only
+    /// given a write sequence, as the snapshot slot is rooted.
+    pub(crate) fn record_write(&self, slot: Slot, pubkey: &Pubkey, is_startup: bool) -> u64 {
+        let write_sequence = self.next_write_sequence.fetch_add(1, Ordering::Relaxed);
+        if !is_startup {
+            self.slots
+                .lock()
+                .unwrap()
+                .entry(slot)
+                .or_default()
+                .accounts
+                .insert(*pubkey);
+        }
+        write_sequence
+    }
+
+    /// Updates the tracked slots with a slot status notification and returns
+    /// the slots it abandons, in increasing order. Only slots with account
+    /// writes are returned.
+    pub(crate) fn update_slot_status(
+        &self,
+        slot: Slot,
+        parent: Option<Slot>,
+        status: &SlotStatus,
+    ) -> Vec<AbandonedSlot> {
+        let mut slots = self.slots.lock().unwrap();
+        match status {
+            SlotStatus::Rooted => Self::set_root(&mut slots, slot, parent),
+            SlotStatus::Dead(_) => slots
+                .remove(&slot)
+                .filter(|unrooted| !unrooted.accounts.is_empty())
+                .map(|unrooted| Self::abandon(slot, unrooted))
+                .into_iter()
+                .collect(),
+            _ => {
+                if let Some(parent) = parent {
+                    slots.entry(slot).or_default().parent = Some(parent);
+                }
+                vec![]
+            }
+        }
+    }
+
+    fn set_root(
+        slots: &mut BTreeMap<Slot, UnrootedSlot>,
+        root: Slot,
+        parent: Option<Slot>,
+    ) -> Vec<AbandonedSlot> {
+        // The writes of the root and of its ancestors are final, even if the
+        // ancestors were not notified as rooted
+        let mut ancestor = slots
+            .remove(&root)
+            .and_then(|unrooted| unrooted.parent)
+            .or(parent);
+        while let Some(slot) = ancestor {
+            ancestor = slots.remove(&slot).and_then(|unrooted| unrooted.parent);
+        }
+        let abandoned: Vec<Slot> = slots
+            .keys()
+            .copied()
+            .filter(|&slot| slot < root || !Self::descends_from(slots, slot, root))
+            .collect();
+        abandoned
+            .into_iter()
+            .filter_map(|slot| {
+                let unrooted = slots.remove(&slot)?;
+                (!unrooted.accounts.is_empty()).then(|| Self::abandon(slot, unrooted))
+            })
+            .collect()
+    }
+
+    /// Returns false if `slot`, higher than `root`, is known to be on another
+    /// fork than `root`
+    fn descends_from(slots: &BTreeMap<Slot, UnrootedSlot>, slot: Slot, root: Slot) -> bool {
+        let mut ancestor = slot;
+        while ancestor > root {
+            match slots.get(&ancestor).and_then(|unrooted| unrooted.parent) {
+                Some(parent) => ancestor = parent,
+                None => return true,
+            }
+        }
+        ancestor == root
+    }
+
+    fn abandon(slot: Slot, unrooted: UnrootedSlot) -> AbandonedSlot {
+        let mut accounts: Vec<[u8; 32]> = unrooted
+            .accounts
+            .iter()
+            .map(|pubkey| pubkey.to_bytes())
+            .collect();
+        accounts.sort_unstable();
+        AbandonedSlot { slot, accounts }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn abandoned(slot: Slot, pubkeys: &[Pubkey]) -> AbandonedSlot {
+        let mut accounts: Vec<[u8; 32]> = pubkeys.iter().map(Pubkey::to_bytes).collect();
+        accounts.sort_unstable();
+        AbandonedSlot { slot, accounts }
+    }
+
+    #[test]
+    fn test_write_sequence_increases() {
+        let tracker = AccountWriteTracker::new(10);
+        let pubkey = Pubkey::new_unique();
+        assert_eq!(tracker.record_write(0, &pubkey, true), 10);
+        assert_eq!(tracker.record_write(2, &pubkey, false), 11);
+        assert_eq!(tracker.record_write(1, &pubkey, false), 12);
+        assert!(AccountWriteTracker::default().record_write(0, &pubkey, false) > 12);
+    }
+
+    #[test]
+    fn test_abandon_forks_on_root() {
+        //     1 - 2 - 4
+        //      \
+        //       3 - 5
+        //            \
+        //             6 (parent unknown)
+        let tracker = AccountWriteTracker::new(1);
+        let [a, b, c, d]: [Pubkey; 4] = std::array::from_fn(|_| Pubkey::new_unique());
+        for (slot, parent) in [(2, 1), (3, 1), (4, 2), (5, 3)] {
+            tracker.update_slot_status(slot, Some(parent), &SlotStatus::CreatedBank);
+        }
+        tracker.record_write(2, &a, false);
+        tracker.record_write(3, &b, false);
+        tracker.record_write(3, &c, false);
+        tracker.record_write(4, &a, false);
+        tracker.record_write(5, &c, false);
+        tracker.record_write(6, &d, false);
+
+        assert_eq!(
+            tracker.update_slot_status(1, Some(0), &SlotStatus::Rooted),
+            vec![]
+        );
+        assert_eq!(
+            tracker.update_slot_status(2, Some(1), &SlotStatus::Rooted),
+            vec![abandoned(3, &[b, c]), abandoned(5, &[c])]
+        );
+        assert_eq!(
+            tracker.update_slot_status(7, Some(4), &SlotStatus::Rooted),
+            vec![abandoned(6, &[d])]
+        );
+        assert!(tracker.slots.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_abandon_dead_slot() {
+        let tracker = AccountWriteTracker::new(1);
+        let pubkey = Pubkey::new_unique();
+        tracker.record_write(3, &pubkey, false);
+        tracker.record_write(0, &pubkey, true);
+        tracker.update_slot_status(4, Some(2), &SlotStatus::CreatedBank);
+        assert_eq!(
+            tracker.update_slot_status(3, Some(2), &SlotStatus::Dead("bad".to_string())),
+            vec![abandoned(3, &[pubkey])]
+        );
+        assert_eq!(
+            tracker.update_slot_status(4, Some(2), &SlotStatus::Dead("bad".to_string())),
+            vec![]
+        );
+        assert!(tracker.slots.lock().unwrap().is_empty());
+    }
+}
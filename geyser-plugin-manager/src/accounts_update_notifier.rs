@@ -1,8 +1,11 @@
 /// Module responsible for notifying plugins of account updates
 use {
-    crate::geyser_plugin_manager::GeyserPluginManager,
+    crate::{
+        account_write_tracker::AccountWriteTracker,
+        geyser_plugin_manager::{GeyserPluginManager, LoadedGeyserPlugin},
+    },
     agave_geyser_plugin_interface::geyser_plugin_interface::{
-        ReplicaAccountInfoV3, ReplicaAccountInfoVersions,
+        ReplicaAccountInfoV3, ReplicaAccountInfoV4, ReplicaAccountInfoVersions,
     },
     arc_swap::ArcSwap,
     log::*,
@@ -19,6 +22,7 @@ use {
 pub(crate) struct AccountsUpdateNotifierImpl {
     plugin_manager: Arc<ArcSwap<GeyserPluginManager>>,
     snapshot_notifications_enabled: bool,
+    account_writes: Arc<AccountWriteTracker>,
 }
 
 impl AccountsUpdateNotifierInterface for AccountsUpdateNotifierImpl {
@@ -36,7 +40,8 @@ impl AccountsUpdateNotifierInterface for AccountsUpdateNotifierImpl {
     ) {
         let account_info =
             self.accountinfo_from_shared_account_data(account, txn, pubkey, write_version);
-        self.notify_plugins_of_account_update(account_info, slot, false);
+        let write_sequence = self.write_sequence(slot, pubkey, false);
+        self.notify_plugins_of_account_update(account_info, write_sequence, slot, false);
     }
 
     fn notify_account_restore_from_snapshot(
@@ -45,9 +50,10 @@ impl AccountsUpdateNotifierInterface for AccountsUpdateNotifierImpl {
         write_version: u64,
         account: &AccountForGeyser<'_>,
     ) {
+        let write_sequence = self.write_sequence(slot, account.pubkey, true);
         let mut account = self.accountinfo_from_account_for_geyser(account);
         account.write_version = write_version;
-        self.notify_plugins_of_account_update(account, slot, true);
+        self.notify_plugins_of_account_update(account, write_sequence, slot, true);
     }
 
     fn notify_end_of_restore_from_snapshot(&self) {
@@ -57,25 +63,54 @@ impl AccountsUpdateNotifierInterface for AccountsUpdateNotifierImpl {
         }
 
         for plugin in plugin_manager.plugins.iter() {
-            match plugin.notify_end_of_startup() {
-                Err(err) => {
-                    error!(
-                        "Failed to notify the end of restore from snapshot, error: {} to plugin {}",
-                        err,
-                        plugin.name()
-                    )
-                }
-                Ok(_) => {
-                    trace!(
-                        "Successfully notified the end of restore from snapshot to plugin {}",
-                        plugin.name()
-                    );
-                }
+            match plugin.dispatcher() {
+                None => Self::notify_end_of_startup(plugin),
+                Some(dispatcher) => dispatcher.dispatch(None, Self::notify_end_of_startup),
             }
         }
     }
 }
 
+/// An owned copy of a `ReplicaAccountInfoV3`, for plugins with a dispatch queue
+pub(crate) struct OwnedAccountInfo {
+    pubkey: Vec<u8>,
+    lamports: u64,
+    owner: Vec<u8>,
+    executable: bool,
+    rent_epoch: u64,
+    data: Vec<u8>,
+    write_version: u64,
+    txn: Option<SanitizedTransaction>,
+}
+
+impl OwnedAccountInfo {
+    pub(crate) fn new(account: &ReplicaAccountInfoV3) -> Self {
+        Self {
+            pubkey: account.pubkey.to_vec(),
+            lamports: account.lamports,
+            owner: account.owner.to_vec(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: account.data.to_vec(),
+            write_version: account.write_version,
+            txn: account.txn.cloned(),
+        }
+    }
+
+    pub(crate) fn as_replica(&self) -> ReplicaAccountInfoV3<'_> {
+        ReplicaAccountInfoV3 {
+            pubkey: &self.pubkey,
+            lamports: self.lamports,
+            owner: &self.owner,
+            executable: self.executable,
+            rent_epoch: self.rent_epoch,
+            data: &self.data,
+            write_version: self.write_version,
+            txn: self.txn.as_ref(),
+        }
+    }
+}
+
 impl AccountsUpdateNotifierImpl {
     pub fn new(
         plugin_manager: Arc<ArcSwap<GeyserPluginManager>>,
@@ -84,6 +119,30 @@ impl AccountsUpdateNotifierImpl {
         AccountsUpdateNotifierImpl {
             plugin_manager,
             snapshot_notifications_enabled,
+            account_writes: Arc::default(),
+        }
+    }
+
+    /// Shares the accounts written in unrooted slots with the slot status
+    /// notifier, which reports the abandoned slots
+    pub(crate) fn with_account_writes(self, account_writes: Arc<AccountWriteTracker>) -> Self {
+        Self {
+            account_writes,
+            ..self
+        }
+    }
+
+    /// The write sequence of an account update, only tracked while a plugin
+    /// rolls back account updates
+    fn write_sequence(&self, slot: Slot, pubkey: &Pubkey, is_startup: bool) -> u64 {
+        if self
+            .plugin_manager
+            .load()
+            .account_rollback_notifications_enabled()
+        {
+            self.account_writes.record_write(slot, pubkey, is_startup)
+        } else {
+            0
         }
     }
 
@@ -125,6 +184,7 @@ impl AccountsUpdateNotifierImpl {
     fn notify_plugins_of_account_update(
         &self,
         account: ReplicaAccountInfoV3,
+        write_sequence: u64,
         slot: Slot,
         is_startup: bool,
     ) {
@@ -133,32 +193,102 @@ impl AccountsUpdateNotifierImpl {
         if plugin_manager.plugins.is_empty() {
             return;
         }
+        // Copied once and shared by every plugin with a dispatch queue
+        let mut owned_account = None;
         for plugin in plugin_manager.plugins.iter() {
-            if !plugin.account_data_notifications_enabled() {
+            if !plugin.account_data_notifications_enabled()
+                || !plugin.notification_filter().matches_account(&account)
+            {
                 continue;
             }
-            match plugin.update_account(
-                ReplicaAccountInfoVersions::V0_0_3(&account),
+            match plugin.dispatcher() {
+                None => Self::update_account(plugin, &account, write_sequence, slot, is_startup),
+                Some(dispatcher) => {
+                    let owned_account: Arc<OwnedAccountInfo> = owned_account
+                        .get_or_insert_with(|| Arc::new(OwnedAccountInfo::new(&account)))
+                        .clone();
+                    dispatcher.dispatch(Some(slot), move |plugin| {
+                        Self::update_account(
+                            plugin,
+                            &owned_account.as_replica(),
+                            write_sequence,
+                            slot,
+                            is_startup,
+                        )
+                    });
+                }
+            }
+        }
+    }
+
+    /// Notifies the plugin of the account update, as a
+    /// `ReplicaAccountInfoV4` if the plugin rolls back account updates
+    pub(crate) fn update_account(
+        plugin: &LoadedGeyserPlugin,
+        account: &ReplicaAccountInfoV3,
+        write_sequence: u64,
+        slot: Slot,
+        is_startup: bool,
+    ) {
+        let result = if plugin.account_rollback_notifications_enabled() {
+            let account = ReplicaAccountInfoV4 {
+                pubkey: account.pubkey,
+                lamports: account.lamports,
+                owner: account.owner,
+                executable: account.executable,
+                rent_epoch: account.rent_epoch,
+                data: account.data,
+                write_version: account.write_version,
+                write_sequence,
+                txn: account.txn,
+            };
+            plugin.update_account(
+                ReplicaAccountInfoVersions::V0_0_4(&account),
                 slot,
                 is_startup,
-            ) {
-                Err(err) => {
-                    error!(
-                        "Failed to update account {} at slot {}, error: {} to plugin {}",
-                        bs58::encode(account.pubkey).into_string(),
-                        slot,
-                        err,
-                        plugin.name()
-                    )
-                }
-                Ok(_) => {
-                    trace!(
-                        "Successfully updated account {} at slot {} to plugin {}",
-                        bs58::encode(account.pubkey).into_string(),
-                        slot,
-                        plugin.name()
-                    );
-                }
+            )
+        } else {
+            plugin.update_account(
+                ReplicaAccountInfoVersions::V0_0_3(account),
+                slot,
+                is_startup,
+            )
+        };
+        match result {
+            Err(err) => {
+                error!(
+                    "Failed to update account {} at slot {}, error: {} to plugin {}",
+                    bs58::encode(account.pubkey).into_string(),
+                    slot,
+                    err,
+                    plugin.name()
+                )
+            }
+            Ok(_) => {
+                trace!(
+                    "Successfully updated account {} at slot {} to plugin {}",
+                    bs58::encode(account.pubkey).into_string(),
+                    slot,
+                    plugin.name()
+                );
+            }
+        }
+    }
+
+    fn notify_end_of_startup(plugin: &LoadedGeyserPlugin) {
+        match plugin.notify_end_of_startup() {
+            Err(err) => {
+                error!(
+                    "Failed to notify the end of restore from snapshot, error: {} to plugin {}",
+                    err,
+                    plugin.name()
+                )
+            }
+            Ok(_) => {
+                trace!(
+                    "Successfully notified the end of restore from snapshot to plugin {}",
+                    plugin.name()
+                );
             }
         }
     }
@@ -168,15 +298,20 @@ impl AccountsUpdateNotifierImpl {
 mod tests {
     use {
         super::*,
-        crate::geyser_plugin_manager::{GeyserPluginManager, LoadedGeyserPlugin},
+        crate::{
+            geyser_plugin_manager::{GeyserPluginManager, LoadedGeyserPlugin},
+            recording_plugin::{Notification, RecordingPlugin},
+            slot_status_notifier::SlotStatusNotifierImpl,
+        },
         agave_geyser_plugin_interface::geyser_plugin_interface::{
-            GeyserPlugin, ReplicaAccountInfoVersions,
+            GeyserAccountFilter, GeyserPlugin, GeyserPluginFilter, ReplicaAccountInfoVersions,
+            SlotStatus,
         },
         arc_swap::ArcSwap,
         libloading::Library,
         solana_accounts_db::accounts_update_notifier_interface::AccountsUpdateNotifierInterface,
         std::sync::{
-            Arc,
+            Arc, Mutex,
             atomic::{AtomicUsize, Ordering},
         },
     };
@@ -186,6 +321,7 @@ mod tests {
         name: &'static str,
         account_updates_enabled: bool,
         account_update_count: Arc<AtomicUsize>,
+        filter: Option<GeyserPluginFilter>,
     }
 
     impl GeyserPlugin for TestAccountPlugin {
@@ -206,6 +342,10 @@ mod tests {
         fn account_data_notifications_enabled(&self) -> bool {
             self.account_updates_enabled
         }
+
+        fn notification_filter(&self) -> Option<GeyserPluginFilter> {
+            self.filter.clone()
+        }
     }
 
     fn loaded_test_plugin(plugin: TestAccountPlugin) -> Arc<LoadedGeyserPlugin> {
@@ -214,11 +354,9 @@ mod tests {
         #[cfg(windows)]
         let library = libloading::os::windows::Library::this().unwrap();
 
-        Arc::new(LoadedGeyserPlugin::new(
-            Library::from(library),
-            Box::new(plugin),
-            None,
-        ))
+        let mut plugin = LoadedGeyserPlugin::new(Library::from(library), Box::new(plugin), None);
+        plugin.load("", false).unwrap();
+        Arc::new(plugin)
     }
 
     #[test]
@@ -231,11 +369,13 @@ mod tests {
                     name: "enabled",
                     account_updates_enabled: true,
                     account_update_count: enabled_count.clone(),
+                    filter: None,
                 }),
                 loaded_test_plugin(TestAccountPlugin {
                     name: "disabled",
                     account_updates_enabled: false,
                     account_update_count: disabled_count.clone(),
+                    filter: None,
                 }),
             ],
         })));
@@ -248,4 +388,114 @@ mod tests {
         assert_eq!(enabled_count.load(Ordering::Relaxed), 1);
         assert_eq!(disabled_count.load(Ordering::Relaxed), 0);
     }
+
+    #[test]
+    fn test_notify_account_update_applies_plugin_filters() {
+        let program = Pubkey::new_unique();
+        let unfiltered_count = Arc::new(AtomicUsize::new(0));
+        let filtered_count = Arc::new(AtomicUsize::new(0));
+        let plugin_manager = Arc::new(ArcSwap::from(Arc::new(GeyserPluginManager {
+            plugins: vec![
+                loaded_test_plugin(TestAccountPlugin {
+                    name: "unfiltered",
+                    account_updates_enabled: true,
+                    account_update_count: unfiltered_count.clone(),
+                    filter: None,
+                }),
+                loaded_test_plugin(TestAccountPlugin {
+                    name: "filtered",
+                    account_updates_enabled: true,
+                    account_update_count: filtered_count.clone(),
+                    filter: Some(GeyserPluginFilter {
+                        accounts: Some(GeyserAccountFilter {
+                            owners: vec![program.to_bytes()],
+                            ..GeyserAccountFilter::default()
+                        }),
+                        transactions: None,
+                    }),
+                }),
+            ],
+        })));
+        let notifier = AccountsUpdateNotifierImpl::new(plugin_manager, true);
+        let owned_account = AccountSharedData::new(1, 0, &program);
+        let other_account = AccountSharedData::new(1, 0, &Pubkey::new_unique());
+
+        notifier.notify_account_update(42, &owned_account, &None, &Pubkey::new_unique(), 1);
+        notifier.notify_account_update(42, &other_account, &None, &Pubkey::new_unique(), 2);
+        notifier.notify_account_restore_from_snapshot(
+            0,
+            3,
+            &AccountForGeyser {
+                pubkey: &Pubkey::new_unique(),
+                lamports: 1,
+                owner: &program,
+                executable: false,
+                rent_epoch: 0,
+                data: &[],
+            },
+        );
+
+        assert_eq!(unfiltered_count.load(Ordering::Relaxed), 3);
+        assert_eq!(filtered_count.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_notify_abandoned_slot_accounts() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let plugin_manager = Arc::new(ArcSwap::from(Arc::new(GeyserPluginManager {
+            plugins: vec![Arc::new(LoadedGeyserPlugin::new_without_library(
+                Box::new(RecordingPlugin {
+                    notifications: events.clone(),
+                    account_rollback: true,
+                    ..RecordingPlugin::default()
+                }),
+                None,
+            ))],
+        })));
+        let account_writes = Arc::new(AccountWriteTracker::default());
+        let notifier = AccountsUpdateNotifierImpl::new(plugin_manager.clone(), false)
+            .with_account_writes(account_writes.clone());
+        let slot_status_notifier =
+            SlotStatusNotifierImpl::new(plugin_manager).with_account_writes(account_writes);
+        let account = AccountSharedData::new(1, 0, &Pubkey::new_unique());
+        let pubkey = Pubkey::new_unique();
+
+        // Slots 2 and 3 fork off slot 1, rooting 2 abandons 3
+        slot_status_notifier.notify_slot_status(2, Some(1), SlotStatus::CreatedBank);
+        slot_status_notifier.notify_slot_status(3, Some(1), SlotStatus::CreatedBank);
+        notifier.notify_account_update(3, &account, &None, &pubkey, 1);
+        notifier.notify_account_update(2, &account, &None, &pubkey, 2);
+        slot_status_notifier.notify_slot_status(1, Some(0), SlotStatus::Rooted);
+        slot_status_notifier.notify_slot_status(2, Some(1), SlotStatus::Rooted);
+
+        let events: Vec<Notification> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| !matches!(event, Notification::SlotStatus { .. }))
+            .cloned()
+            .collect();
+        let [
+            Notification::Account {
+                slot: 3,
+                pubkey: first_pubkey,
+                write_sequence: Some(first_write_sequence),
+                ..
+            },
+            Notification::Account {
+                slot: 2,
+                pubkey: second_pubkey,
+                write_sequence: Some(second_write_sequence),
+                ..
+            },
+            Notification::SlotAbandoned { slot: 3, accounts },
+        ] = events.as_slice()
+        else {
+            panic!("unexpected events {events:?}");
+        };
+        assert_eq!(*first_pubkey, pubkey);
+        assert_eq!(*second_pubkey, pubkey);
+        assert!(second_write_sequence > first_write_sequence);
+        assert_eq!(*accounts, vec![pubkey.to_bytes()]);
+    }
 }
@@ -1,7 +1,7 @@
 use {
     crate::{
         block_metadata_notifier_interface::BlockMetadataNotifier,
-        geyser_plugin_manager::GeyserPluginManager,
+        geyser_plugin_manager::{GeyserPluginManager, LoadedGeyserPlugin},
     },
     agave_geyser_plugin_interface::geyser_plugin_interface::{
         ReplicaBlockInfoV4, ReplicaBlockInfoVersions,
@@ -38,7 +38,10 @@ impl BlockMetadataNotifier for BlockMetadataNotifierImpl {
             return;
         }
 
-        let rewards = Self::build_rewards(rewards, commission_rate_in_basis_points);
+        let rewards = Arc::new(Self::build_rewards(
+            rewards,
+            commission_rate_in_basis_points,
+        ));
         let block_info = Self::build_replica_block_info(
             parent_slot,
             parent_blockhash,
@@ -52,22 +55,26 @@ impl BlockMetadataNotifier for BlockMetadataNotifierImpl {
         );
 
         for plugin in plugin_manager.plugins.iter() {
-            let block_info = ReplicaBlockInfoVersions::V0_0_4(&block_info);
-            match plugin.notify_block_metadata(block_info) {
-                Err(err) => {
-                    error!(
-                        "Failed to update block metadata at slot {}, error: {} to plugin {}",
-                        slot,
-                        err,
-                        plugin.name()
-                    )
-                }
-                Ok(_) => {
-                    trace!(
-                        "Successfully updated block metadata at slot {} to plugin {}",
-                        slot,
-                        plugin.name()
-                    );
+            match plugin.dispatcher() {
+                None => Self::notify_plugin(plugin, &block_info),
+                Some(dispatcher) => {
+                    let parent_blockhash = parent_blockhash.to_string();
+                    let blockhash = blockhash.to_string();
+                    let rewards = rewards.clone();
+                    dispatcher.dispatch(Some(slot), move |plugin| {
+                        let block_info = Self::build_replica_block_info(
+                            parent_slot,
+                            &parent_blockhash,
+                            slot,
+                            &blockhash,
+                            &rewards,
+                            block_time,
+                            block_height,
+                            executed_transaction_count,
+                            entry_count,
+                        );
+                        Self::notify_plugin(plugin, &block_info)
+                    });
                 }
             }
         }
@@ -75,6 +82,26 @@ impl BlockMetadataNotifier for BlockMetadataNotifierImpl {
 }
 
 impl BlockMetadataNotifierImpl {
+    pub(crate) fn notify_plugin(plugin: &LoadedGeyserPlugin, block_info: &ReplicaBlockInfoV4) {
+        match plugin.notify_block_metadata(ReplicaBlockInfoVersions::V0_0_4(block_info)) {
+            Err(err) => {
+                error!(
+                    "Failed to update block metadata at slot {}, error: {} to plugin {}",
+                    block_info.slot,
+                    err,
+                    plugin.name()
+                )
+            }
+            Ok(_) => {
+                trace!(
+                    "Successfully updated block metadata at slot {} to plugin {}",
+                    block_info.slot,
+                    plugin.name()
+                );
+            }
+        }
+    }
+
     fn build_rewards(
         rewards: &KeyedRewardsAndNumPartitions,
         commission_rate_in_basis_points: bool,
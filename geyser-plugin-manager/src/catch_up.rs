@@ -0,0 +1,521 @@
+//! Replay of rooted slots from blockstore to plugins that restart or are
+//! reloaded.
+//!
+//! A plugin returning a slot from `GeyserPlugin::catch_up_from_slot` is
+//! always given a dispatch queue, see the `plugin_dispatcher` module. Before
+//! draining the queue, the plugin's worker thread replays to the plugin every
+//! rooted slot from the requested slot up to the highest root at the time the
+//! catch-up starts, while the live notifications produced meanwhile wait in the
+//! queue. A plugin without a `dispatch` section in its config file is given a
+//! `drop_oldest` queue of [`DEFAULT_CATCH_UP_QUEUE_CAPACITY`] notifications,
+//! which should be raised for plugins expected to catch up over many slots. A
+//! `block` queue drops its oldest notifications until the catch-up completes,
+//! so that the validator is never stalled by a catch-up.
+//!
+//! Catch-up reads what the ledger retains:
+//! * blocks, entries and transactions require `--enable-rpc-transaction-history`,
+//!   a warning is logged when catch-up starts without it,
+//! * account updates require `--enable-rpc-accounts-diff`, and are delivered
+//!   with a `write_version` and `write_sequence` of zero and without their
+//!   transaction.
+//!
+//! The ledger only keeps the accounts written by transactions. Account writes
+//! made outside of transactions, such as epoch rewards, fees and rent credited
+//! to the leader and sysvar updates, are not replayed, so the balances of these
+//! accounts may be stale after a catch-up.
+//!
+//! Slots older than the first block available in the ledger, or that cannot
+//! be read, are reported to the plugin by `GeyserPlugin::notify_gap`, and so
+//! are the replayed slots, with `account_updates_only`, to plugins receiving
+//! account updates. Slots
+//! rooted while the plugin is being loaded, or replayed from the local ledger
+//! when the validator starts, may be delivered both by catch-up and live.
+
+use {
+    crate::{
+        accounts_update_notifier::AccountsUpdateNotifierImpl,
+        block_metadata_notifier::BlockMetadataNotifierImpl,
+        entry_notifier::EntryNotifierImpl,
+        geyser_plugin_manager::LoadedGeyserPlugin,
+        plugin_dispatcher::{OverflowPolicy, PluginDispatchConfig},
+        slot_status_notifier::SlotStatusNotifierImpl,
+        transaction_notifier::TransactionNotifierImpl,
+    },
+    agave_geyser_plugin_interface::geyser_plugin_interface::{
+        NotificationGap, ReplicaAccountInfoV3, ReplicaBlockInfoV4, ReplicaEntryInfoV2, SlotStatus,
+    },
+    log::*,
+    solana_clock::Slot,
+    solana_ledger::blockstore::Blockstore,
+    solana_message::VersionedMessage,
+    solana_transaction::{
+        simple_vote_transaction_checker::is_simple_vote_transaction_impl,
+        versioned::VersionedTransaction,
+    },
+    solana_transaction_status::{
+        RewardsAndNumPartitions, VersionedConfirmedBlockWithEntries,
+        VersionedTransactionWithStatusMeta,
+    },
+    std::{
+        sync::{
+            Arc, OnceLock, Weak,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread,
+        time::Duration,
+    },
+};
+
+/// Queue capacity of a plugin requesting catch-up without a dispatch config
+pub const DEFAULT_CATCH_UP_QUEUE_CAPACITY: usize = 100_000;
+
+pub(crate) const DEFAULT_CATCH_UP_DISPATCH_CONFIG: PluginDispatchConfig = PluginDispatchConfig {
+    queue_capacity: DEFAULT_CATCH_UP_QUEUE_CAPACITY,
+    overflow_policy: OverflowPolicy::DropOldest,
+};
+
+// How often a catch-up waiting for the blockstore checks if its plugin was unloaded
+const BLOCKSTORE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The ledger catch-up reads from. Plugins given on the command line are
+/// loaded before the blockstore is opened, so their catch-up waits until the
+/// blockstore is provided.
+#[derive(Debug, Default)]
+pub(crate) struct CatchUpSource {
+    blockstore: OnceLock<Arc<Blockstore>>,
+    /// Whether the validator stores the transaction history in the blockstore
+    transaction_history: AtomicBool,
+}
+
+impl CatchUpSource {
+    pub(crate) fn set_blockstore(&self, blockstore: Arc<Blockstore>, transaction_history: bool) {
+        self.transaction_history
+            .store(transaction_history, Ordering::Relaxed);
+        if self.blockstore.set(blockstore).is_err() {
+            warn!("Geyser catch-up blockstore is already set");
+        }
+    }
+
+    fn wait_for_blockstore(&self, plugin: &Weak<LoadedGeyserPlugin>) -> Option<Arc<Blockstore>> {
+        loop {
+            if let Some(blockstore) = self.blockstore.get() {
+                return Some(Arc::clone(blockstore));
+            }
+            if plugin.strong_count() == 0 {
+                return None;
+            }
+            thread::sleep(BLOCKSTORE_POLL_INTERVAL);
+        }
+    }
+}
+
+/// A catch-up requested by a plugin, run by its dispatch worker
+#[derive(Debug)]
+pub(crate) struct CatchUp {
+    from_slot: Slot,
+    source: Arc<CatchUpSource>,
+}
+
+impl CatchUp {
+    pub(crate) fn new(from_slot: Slot, source: Arc<CatchUpSource>) -> Self {
+        Self { from_slot, source }
+    }
+
+    /// Replays the rooted slots to the plugin, reporting the slots that could
+    /// not be replayed with `report_gap`. Returns early if the plugin is
+    /// unloaded.
+    pub(crate) fn run(
+        self,
+        plugin: &Weak<LoadedGeyserPlugin>,
+        report_gap: impl Fn(&LoadedGeyserPlugin, &NotificationGap),
+    ) {
+        let Some(blockstore) = self.source.wait_for_blockstore(plugin) else {
+            return;
+        };
+        if !self.source.transaction_history.load(Ordering::Relaxed) {
+            warn!(
+                "Geyser catch-up requires --enable-rpc-transaction-history, the slots it cannot \
+                 read are reported to the plugin as gaps"
+            );
+        }
+        let to_slot = blockstore.max_root();
+        let first_available_slot = match blockstore.get_first_available_block() {
+            Ok(slot) => slot,
+            Err(err) => {
+                error!("Failed to find the first available block for catch-up, error: {err}");
+                to_slot.saturating_add(1)
+            }
+        };
+        info!(
+            "Catching up plugin from slot {} to slot {to_slot}",
+            self.from_slot
+        );
+
+        let mut pending_gap = (self.from_slot < first_available_slot)
+            .then(|| self.from_slot..=first_available_slot.saturating_sub(1));
+        let mut replayed_slots = None;
+        let mut missing_accounts_diffs = 0;
+        let start_slot = self.from_slot.max(first_available_slot);
+        let slots = match blockstore.rooted_slot_iterator(start_slot) {
+            Ok(slots) => slots,
+            Err(err) => {
+                error!("Failed to iterate rooted slots for catch-up, error: {err}");
+                return;
+            }
+        };
+        for slot in slots.take_while(|slot| *slot <= to_slot) {
+            let Some(plugin) = plugin.upgrade() else {
+                return;
+            };
+            match blockstore.get_rooted_block_with_entries(slot, false) {
+                Ok(block) => {
+                    if let Some(slots) = pending_gap.take() {
+                        report_gap(
+                            &plugin,
+                            &NotificationGap {
+                                dropped: 0,
+                                slots: Some(slots),
+                                account_updates_only: false,
+                            },
+                        );
+                    }
+                    missing_accounts_diffs = missing_accounts_diffs
+                        .saturating_add(Self::replay_block(&plugin, &blockstore, slot, block));
+                    replayed_slots = Some(match replayed_slots {
+                        None => slot..=slot,
+                        Some(slots) => *slots.start()..=slot,
+                    });
+                }
+                Err(err) => {
+                    warn!("Failed to read slot {slot} for catch-up, error: {err}");
+                    pending_gap = Some(match pending_gap {
+                        None => slot..=slot,
+                        Some(slots) => *slots.start()..=slot,
+                    });
+                }
+            }
+        }
+
+        let Some(plugin) = plugin.upgrade() else {
+            return;
+        };
+        if let Some(slots) = pending_gap {
+            report_gap(
+                &plugin,
+                &NotificationGap {
+                    dropped: 0,
+                    slots: Some(slots),
+                    account_updates_only: false,
+                },
+            );
+        }
+        if missing_accounts_diffs > 0 {
+            warn!(
+                "Catch-up of plugin {} is missing the account updates of {missing_accounts_diffs} \
+                 transactions, which requires --enable-rpc-accounts-diff",
+                plugin.name()
+            );
+        }
+        // The account writes made outside of transactions are not in the ledger
+        if plugin.account_data_notifications_enabled()
+            && let Some(slots) = replayed_slots.clone()
+        {
+            report_gap(
+                &plugin,
+                &NotificationGap {
+                    dropped: 0,
+                    slots: Some(slots),
+                    account_updates_only: true,
+                },
+            );
+        }
+        let last_slot = replayed_slots.map(|slots| *slots.end());
+        match plugin.notify_catch_up_complete(last_slot) {
+            Err(err) => error!(
+                "Failed to notify catch-up completion, error: {} to plugin {}",
+                err,
+                plugin.name()
+            ),
+            Ok(_) => info!("Caught up plugin {} to slot {last_slot:?}", plugin.name()),
+        }
+    }
+
+    /// Notifies the plugin of a rooted block, returning the number of
+    /// transactions whose account updates are not in the ledger
+    fn replay_block(
+        plugin: &LoadedGeyserPlugin,
+        blockstore: &Blockstore,
+        slot: Slot,
+        block: VersionedConfirmedBlockWithEntries,
+    ) -> usize {
+        let VersionedConfirmedBlockWithEntries { block, entries } = block;
+        let mut missing_accounts_diffs = 0;
+
+        for (index, VersionedTransactionWithStatusMeta { transaction, meta }) in
+            block.transactions.iter().enumerate()
+        {
+            let signature = &transaction.signatures[0];
+            if plugin.account_data_notifications_enabled() {
+                match blockstore.read_transaction_accounts_diff(slot, *signature) {
+                    Ok(Some(accounts_diff)) => {
+                        for account in &accounts_diff.accounts {
+                            let account = ReplicaAccountInfoV3 {
+                                pubkey: account.pubkey.as_ref(),
+                                lamports: account.lamports,
+                                owner: account.owner.as_ref(),
+                                executable: account.executable,
+                                rent_epoch: account.rent_epoch,
+                                data: &account.data,
+                                write_version: 0,
+                                txn: None,
+                            };
+                            if plugin.notification_filter().matches_account(&account) {
+                                AccountsUpdateNotifierImpl::update_account(
+                                    plugin, &account, 0, slot, false,
+                                );
+                            }
+                        }
+                    }
+                    Ok(None) => missing_accounts_diffs = missing_accounts_diffs.saturating_add(1),
+                    Err(err) => {
+                        warn!("Failed to read accounts diff of {signature} for catch-up: {err}");
+                        missing_accounts_diffs = missing_accounts_diffs.saturating_add(1);
+                    }
+                }
+            }
+
+            let is_vote = is_simple_vote_transaction(transaction);
+            if plugin.transaction_notifications_enabled()
+                && plugin
+                    .notification_filter()
+                    .matches_transaction(is_vote, transaction, meta)
+            {
+                let message_hash = transaction.message.hash();
+                let transaction_info = TransactionNotifierImpl::build_replica_transaction_info(
+                    index,
+                    signature,
+                    &message_hash,
+                    is_vote,
+                    meta,
+                    transaction,
+                );
+                TransactionNotifierImpl::notify_plugin(plugin, &transaction_info, slot);
+            }
+        }
+
+        if plugin.entry_notifications_enabled() {
+            for (index, entry) in entries.iter().enumerate() {
+                let entry_info = ReplicaEntryInfoV2 {
+                    slot,
+                    index,
+                    num_hashes: entry.num_hashes,
+                    hash: entry.hash.as_ref(),
+                    executed_transaction_count: entry.num_transactions,
+                    starting_transaction_index: entry.starting_transaction_index,
+                };
+                EntryNotifierImpl::notify_plugin(plugin, &entry_info);
+            }
+        }
+
+        let rewards = RewardsAndNumPartitions {
+            rewards: block.rewards,
+            num_partitions: block.num_partitions,
+        };
+        let block_info = ReplicaBlockInfoV4 {
+            parent_slot: block.parent_slot,
+            parent_blockhash: &block.previous_blockhash,
+            slot,
+            blockhash: &block.blockhash,
+            rewards: &rewards,
+            block_time: block.block_time,
+            block_height: block.block_height,
+            executed_transaction_count: block.transactions.len() as u64,
+            entry_count: entries.len() as u64,
+        };
+        BlockMetadataNotifierImpl::notify_plugin(plugin, &block_info);
+        SlotStatusNotifierImpl::notify_plugin(
+            plugin,
+            slot,
+            Some(block.parent_slot),
+            &SlotStatus::Rooted,
+        );
+
+        missing_accounts_diffs
+    }
+}
+
+fn is_simple_vote_transaction(transaction: &VersionedTransaction) -> bool {
+    let is_legacy = matches!(&transaction.message, VersionedMessage::Legacy(_));
+    let instruction_programs = transaction.message.instructions().iter().filter_map(|ix| {
+        transaction
+            .message
+            .static_account_keys()
+            .get(ix.program_id_index as usize)
+    });
+    is_simple_vote_transaction_impl(&transaction.signatures, is_legacy, instruction_programs)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            geyser_plugin_manager::GeyserPluginManager,
+            recording_plugin::{Notification, RecordingPlugin},
+        },
+        libloading::Library,
+        solana_ledger::{blockstore::make_many_slot_entries, get_tmp_ledger_path_auto_delete},
+        std::{
+            sync::Mutex,
+            time::{Duration, Instant},
+        },
+    };
+
+    #[test]
+    fn test_catch_up_before_live_notifications() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Arc::new(Blockstore::open(ledger_path.path()).unwrap());
+        // Slots 3 to 6. Slot 3 is the first root, so its parent blockhash is
+        // unknown and slot 4 is the first available block.
+        let (shreds, _) = make_many_slot_entries(3, 4, 1);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        blockstore.set_roots([3, 4, 5, 6].iter()).unwrap();
+
+        #[cfg(unix)]
+        let library = libloading::os::unix::Library::this();
+        #[cfg(windows)]
+        let library = libloading::os::windows::Library::this().unwrap();
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut plugin = LoadedGeyserPlugin::new(
+            Library::from(library),
+            Box::new(RecordingPlugin {
+                notifications: events.clone(),
+                catch_up_from_slot: Some(1),
+                ..RecordingPlugin::default()
+            }),
+            None,
+        );
+        plugin.load("", false).unwrap();
+        let catch_up_source = Arc::new(CatchUpSource::default());
+        let plugin = plugin.start(&catch_up_source);
+
+        // Live notifications wait for the catch-up, which waits for the blockstore
+        plugin.dispatcher().unwrap().dispatch(Some(7), |plugin| {
+            plugin
+                .update_slot_status(7, Some(6), &SlotStatus::Processed)
+                .unwrap()
+        });
+        thread::sleep(Duration::from_millis(10));
+        let stats = plugin.dispatch_stats().unwrap();
+        assert!(stats.catching_up);
+        assert_eq!(stats.queue_capacity, DEFAULT_CATCH_UP_QUEUE_CAPACITY);
+        assert!(events.lock().unwrap().is_empty());
+
+        catch_up_source.set_blockstore(blockstore, true);
+        let start = Instant::now();
+        while plugin.dispatch_stats().unwrap().delivered < 1 {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+        }
+        let stats = plugin.dispatch_stats().unwrap();
+        assert!(!stats.catching_up);
+        assert_eq!(stats.gaps, 2);
+
+        let rooted = |slot| Notification::SlotStatus {
+            slot,
+            parent: Some(slot - 1),
+            status: SlotStatus::Rooted,
+        };
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                Notification::Gap(NotificationGap {
+                    dropped: 0,
+                    slots: Some(1..=3),
+                    account_updates_only: false,
+                }),
+                Notification::Block { slot: 4 },
+                rooted(4),
+                Notification::Block { slot: 5 },
+                rooted(5),
+                Notification::Block { slot: 6 },
+                rooted(6),
+                Notification::Gap(NotificationGap {
+                    dropped: 0,
+                    slots: Some(4..=6),
+                    account_updates_only: true,
+                }),
+                Notification::CatchUpComplete { last_slot: Some(6) },
+                Notification::SlotStatus {
+                    slot: 7,
+                    parent: Some(6),
+                    status: SlotStatus::Processed,
+                },
+            ]
+        );
+
+        GeyserPluginManager::unload_plugin_blocking(plugin, 0);
+    }
+
+    #[test]
+    fn test_catch_up_does_not_block_producers() {
+        #[cfg(unix)]
+        let library = libloading::os::unix::Library::this();
+        #[cfg(windows)]
+        let library = libloading::os::windows::Library::this().unwrap();
+        let mut plugin = LoadedGeyserPlugin::new(
+            Library::from(library),
+            Box::new(RecordingPlugin {
+                catch_up_from_slot: Some(0),
+                ..RecordingPlugin::default()
+            }),
+            None,
+        )
+        .with_dispatch_config(Some(PluginDispatchConfig {
+            queue_capacity: 2,
+            overflow_policy: OverflowPolicy::Block,
+        }));
+        plugin.load("", false).unwrap();
+
+        // The blockstore is never provided, so the catch-up never completes and
+        // a blocking queue would hang this thread
+        let plugin = plugin.start(&Arc::default());
+        for slot in 0..5 {
+            plugin
+                .dispatcher()
+                .unwrap()
+                .dispatch(Some(slot), |_plugin| {});
+        }
+        let stats = plugin.dispatch_stats().unwrap();
+        assert!(stats.catching_up);
+        assert_eq!(stats.overflow_policy, OverflowPolicy::Block);
+        assert_eq!(stats.queued, 2);
+        assert_eq!(stats.dropped, 3);
+
+        GeyserPluginManager::unload_plugin_blocking(plugin, 0);
+    }
+
+    #[test]
+    fn test_catch_up_stops_on_unload() {
+        #[cfg(unix)]
+        let library = libloading::os::unix::Library::this();
+        #[cfg(windows)]
+        let library = libloading::os::windows::Library::this().unwrap();
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut plugin = LoadedGeyserPlugin::new(
+            Library::from(library),
+            Box::new(RecordingPlugin {
+                notifications: events.clone(),
+                catch_up_from_slot: Some(0),
+                ..RecordingPlugin::default()
+            }),
+            None,
+        );
+        plugin.load("", false).unwrap();
+
+        // The blockstore is never provided, unloading must not wait for it
+        let plugin = plugin.start(&Arc::default());
+        GeyserPluginManager::unload_plugin_blocking(plugin, 0);
+        assert!(events.lock().unwrap().is_empty());
+    }
+}
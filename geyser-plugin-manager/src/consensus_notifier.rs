@@ -0,0 +1,339 @@
+//! Dispatch thread that delivers votor consensus events to opted-in Geyser
+//! plugins.
+//!
+//! Decoupled from votor via a bounded channel: the consensus pool does a
+//! single non-blocking `try_send` per vote, certificate and finalized block
+//! (see [`agave_votor::consensus_notifier`]), and a thread owned by this
+//! notifier drains the channel and dispatches to plugins. Events that do not
+//! fit in the channel are dropped and counted in the consensus pool metrics,
+//! so a misbehaving plugin can never stall consensus.
+//!
+//! The thread exits when the channel disconnects, i.e. when votor drops the
+//! sender on shutdown.
+
+use {
+    crate::geyser_plugin_manager::GeyserPluginManager,
+    agave_geyser_plugin_interface::geyser_plugin_interface::{
+        ConsensusCertificateType, ConsensusVoteType, ReplicaConsensusCertificateInfoV0_0_1,
+        ReplicaConsensusCertificateInfoVersions, ReplicaConsensusVoteInfoV0_0_1,
+        ReplicaConsensusVoteInfoVersions,
+    },
+    agave_votor::consensus_notifier::{
+        ConsensusEvent, ConsensusEventReceiver, ConsensusEventSender,
+    },
+    agave_votor_messages::{certificate::CertificateType, vote::VoteType},
+    arc_swap::ArcSwap,
+    log::*,
+    std::{
+        sync::Arc,
+        thread::{self, JoinHandle},
+    },
+};
+
+/// Default capacity for the consensus pool → dispatch channel. Every
+/// staked validator casts a few votes per slot, so this absorbs roughly a
+/// second of votes at mainnet scale.
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 16384;
+
+/// Owns the dispatch thread for consensus notifications.
+pub struct ConsensusNotifier {
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl ConsensusNotifier {
+    /// Spawn the dispatch thread, which runs until `receiver` disconnects.
+    pub fn spawn(
+        plugin_manager: Arc<ArcSwap<GeyserPluginManager>>,
+        receiver: ConsensusEventReceiver,
+    ) -> Self {
+        let join_handle = thread::Builder::new()
+            .name("solGeyserCnsns".to_string())
+            .spawn(move || run_dispatch_loop(plugin_manager, receiver))
+            .expect("failed to spawn consensus notifier thread");
+        Self {
+            join_handle: Some(join_handle),
+        }
+    }
+
+    /// Wait for the dispatch thread to exit.
+    pub fn join(mut self) -> thread::Result<()> {
+        match self.join_handle.take() {
+            Some(handle) => handle.join(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Create a bounded channel suitable for use as the consensus pool →
+/// dispatch transport.
+pub fn channel(capacity: usize) -> (ConsensusEventSender, ConsensusEventReceiver) {
+    crossbeam_channel::bounded(capacity)
+}
+
+/// Returns true if any loaded plugin opts into consensus notifications.
+pub fn any_plugin_opts_in(plugin_manager: &GeyserPluginManager) -> bool {
+    plugin_manager
+        .plugins
+        .iter()
+        .any(|p| p.consensus_notifications_enabled())
+}
+
+/// Spawn the dispatch thread if (and only if) any loaded plugin opts into
+/// consensus notifications. Returns the notifier, to be held for the lifetime
+/// of the validator, and the sender to hand to votor. Returns `None`
+/// otherwise, in which case the consensus pool does no work for these
+/// notifications.
+pub fn attach(
+    plugin_manager: Arc<ArcSwap<GeyserPluginManager>>,
+    capacity: usize,
+) -> Option<(ConsensusNotifier, ConsensusEventSender)> {
+    if !any_plugin_opts_in(&plugin_manager.load()) {
+        return None;
+    }
+    let (sender, receiver) = channel(capacity);
+    Some((ConsensusNotifier::spawn(plugin_manager, receiver), sender))
+}
+
+fn run_dispatch_loop(
+    plugin_manager: Arc<ArcSwap<GeyserPluginManager>>,
+    receiver: ConsensusEventReceiver,
+) {
+    while let Ok(event) = receiver.recv() {
+        let plugin_manager = plugin_manager.load();
+        match &event {
+            ConsensusEvent::Vote {
+                vote,
+                vote_account,
+                stake,
+                total_stake,
+            } => {
+                let info = ReplicaConsensusVoteInfoV0_0_1 {
+                    slot: vote.vote.slot(),
+                    vote_type: vote_type(vote.vote.get_type()),
+                    block_id: vote.vote.block_id(),
+                    vote_account: vote_account.as_ref(),
+                    rank: vote.rank,
+                    stake: *stake,
+                    total_stake: *total_stake,
+                    signature: &vote.signature.0,
+                };
+                for plugin in plugin_manager.plugins.iter() {
+                    if !plugin.consensus_notifications_enabled() {
+                        continue;
+                    }
+                    if let Err(err) = plugin
+                        .notify_consensus_vote(ReplicaConsensusVoteInfoVersions::V0_0_1(&info))
+                    {
+                        error!(
+                            "Failed to notify consensus vote for slot {} to plugin {}: {}",
+                            info.slot,
+                            plugin.name(),
+                            err,
+                        );
+                    }
+                }
+            }
+            ConsensusEvent::Certificate {
+                certificate,
+                is_generated,
+            } => {
+                let (certificate_type, block) = certificate_type(certificate.cert_type);
+                let info = ReplicaConsensusCertificateInfoV0_0_1 {
+                    slot: certificate.cert_type.slot(),
+                    certificate_type,
+                    block_id: block.as_ref(),
+                    signature: &certificate.signature.0,
+                    bitmap: &certificate.bitmap,
+                    is_generated: *is_generated,
+                };
+                for plugin in plugin_manager.plugins.iter() {
+                    if !plugin.consensus_notifications_enabled() {
+                        continue;
+                    }
+                    if let Err(err) = plugin.notify_consensus_certificate(
+                        ReplicaConsensusCertificateInfoVersions::V0_0_1(&info),
+                    ) {
+                        error!(
+                            "Failed to notify consensus certificate for slot {} to plugin {}: {}",
+                            info.slot,
+                            plugin.name(),
+                            err,
+                        );
+                    }
+                }
+            }
+            ConsensusEvent::Finalized { block, is_fast } => {
+                for plugin in plugin_manager.plugins.iter() {
+                    if !plugin.consensus_notifications_enabled() {
+                        continue;
+                    }
+                    if let Err(err) =
+                        plugin.notify_block_finalized(block.slot, &block.block_id, *is_fast)
+                    {
+                        error!(
+                            "Failed to notify finalization of slot {} to plugin {}: {}",
+                            block.slot,
+                            plugin.name(),
+                            err,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn vote_type(vote_type: VoteType) -> ConsensusVoteType {
+    match vote_type {
+        VoteType::Notarize => ConsensusVoteType::Notarize,
+        VoteType::NotarizeFallback => ConsensusVoteType::NotarizeFallback,
+        VoteType::Skip => ConsensusVoteType::Skip,
+        VoteType::SkipFallback => ConsensusVoteType::SkipFallback,
+        VoteType::Finalize => ConsensusVoteType::Finalize,
+        VoteType::Genesis => ConsensusVoteType::Genesis,
+    }
+}
+
+/// Returns the interface type of the certificate, and the id of the block it
+/// certifies if any
+fn certificate_type(
+    cert_type: CertificateType,
+) -> (ConsensusCertificateType, Option<solana_hash::Hash>) {
+    let certificate_type = match cert_type {
+        CertificateType::Notarize(_) => ConsensusCertificateType::Notarize,
+        CertificateType::NotarizeFallback(_) => ConsensusCertificateType::NotarizeFallback,
+        CertificateType::Skip(_) => ConsensusCertificateType::Skip,
+        CertificateType::Finalize(_) => ConsensusCertificateType::Finalize,
+        CertificateType::FinalizeFast(_) => ConsensusCertificateType::FinalizeFast,
+        CertificateType::Genesis(_) => ConsensusCertificateType::Genesis,
+    };
+    (
+        certificate_type,
+        cert_type.to_block().map(|block| block.block_id),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            geyser_plugin_manager::LoadedGeyserPlugin,
+            recording_plugin::{Notification, RecordingPlugin},
+        },
+        agave_votor_messages::{
+            certificate::Certificate,
+            consensus_message::{Block, VoteMessage},
+            vote::Vote,
+        },
+        libloading::Library,
+        solana_bls_signatures::{BLS_SIGNATURE_AFFINE_SIZE, Signature as BLSSignature},
+        solana_hash::Hash,
+        solana_pubkey::Pubkey,
+        std::sync::Mutex,
+    };
+
+    fn loaded(plugin: RecordingPlugin) -> Arc<LoadedGeyserPlugin> {
+        #[cfg(unix)]
+        let library = libloading::os::unix::Library::this();
+        #[cfg(windows)]
+        let library = libloading::os::windows::Library::this().unwrap();
+        Arc::new(LoadedGeyserPlugin::new(
+            Library::from(library),
+            Box::new(plugin),
+            None,
+        ))
+    }
+
+    #[test]
+    fn test_dispatch_consensus_events() {
+        let enabled = Arc::new(Mutex::new(vec![]));
+        let disabled = Arc::new(Mutex::new(vec![]));
+        let plugin_manager = Arc::new(ArcSwap::from(Arc::new(GeyserPluginManager {
+            plugins: vec![
+                loaded(RecordingPlugin {
+                    notifications: enabled.clone(),
+                    consensus: true,
+                    ..RecordingPlugin::default()
+                }),
+                loaded(RecordingPlugin {
+                    notifications: disabled.clone(),
+                    ..RecordingPlugin::default()
+                }),
+            ],
+        })));
+        assert!(any_plugin_opts_in(&plugin_manager.load()));
+
+        let (notifier, sender) = attach(plugin_manager, 64).unwrap();
+        let block = Block {
+            slot: 5,
+            block_id: Hash::new_unique(),
+        };
+        let vote_account = Pubkey::new_unique();
+        sender
+            .send(ConsensusEvent::Vote {
+                vote: VoteMessage {
+                    vote: Vote::new_notarization_vote(block),
+                    signature: BLSSignature([0; BLS_SIGNATURE_AFFINE_SIZE]),
+                    rank: 3,
+                },
+                vote_account,
+                stake: 100,
+                total_stake: 1000,
+            })
+            .unwrap();
+        sender
+            .send(ConsensusEvent::Certificate {
+                certificate: Arc::new(Certificate {
+                    cert_type: CertificateType::Skip(6),
+                    signature: BLSSignature([0; BLS_SIGNATURE_AFFINE_SIZE]),
+                    bitmap: vec![1, 2, 3],
+                }),
+                is_generated: true,
+            })
+            .unwrap();
+        sender
+            .send(ConsensusEvent::Finalized {
+                block,
+                is_fast: true,
+            })
+            .unwrap();
+        drop(sender);
+        notifier.join().unwrap();
+
+        assert_eq!(
+            *enabled.lock().unwrap(),
+            vec![
+                Notification::ConsensusVote {
+                    slot: 5,
+                    vote_type: ConsensusVoteType::Notarize,
+                    block_id: Some(block.block_id),
+                    vote_account: vote_account.to_bytes().to_vec(),
+                    stake: 100,
+                    total_stake: 1000,
+                },
+                Notification::ConsensusCertificate {
+                    slot: 6,
+                    certificate_type: ConsensusCertificateType::Skip,
+                    block_id: None,
+                    bitmap: vec![1, 2, 3],
+                    is_generated: true,
+                },
+                Notification::BlockFinalized {
+                    slot: 5,
+                    block_id: block.block_id,
+                    is_fast: true,
+                },
+            ]
+        );
+        assert!(disabled.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_attach_without_opted_in_plugin() {
+        let plugin_manager = Arc::new(ArcSwap::from(Arc::new(GeyserPluginManager {
+            plugins: vec![loaded(RecordingPlugin::default())],
+        })));
+        assert!(attach(plugin_manager, 64).is_none());
+    }
+}
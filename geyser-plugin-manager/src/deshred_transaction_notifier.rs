@@ -1,6 +1,6 @@
 /// Module responsible for notifying plugins of transactions when deshredded
 use {
-    crate::geyser_plugin_manager::GeyserPluginManager,
+    crate::geyser_plugin_manager::{GeyserPluginManager, LoadedGeyserPlugin},
     agave_geyser_plugin_interface::geyser_plugin_interface::{
         ReplicaDeshredTransactionInfoV2, ReplicaDeshredTransactionInfoVersions,
     },
@@ -52,26 +52,36 @@ impl DeshredTransactionNotifier for DeshredTransactionNotifierImpl {
             completed_data_set_ending_shred_index_exclusive,
         };
 
+        let mut owned_transaction = None;
         for plugin in plugin_manager.plugins.iter() {
             if !plugin.deshred_transaction_notifications_enabled() {
                 continue;
             }
-            match plugin.notify_deshred_transaction(
-                ReplicaDeshredTransactionInfoVersions::V0_0_2(&transaction_info),
-                slot,
-            ) {
-                Err(err) => {
-                    error!(
-                        "Failed to notify deshred transaction, error: ({}) to plugin {}",
-                        err,
-                        plugin.name()
-                    )
-                }
-                Ok(_) => {
-                    trace!(
-                        "Successfully notified deshred transaction to plugin {}",
-                        plugin.name()
-                    );
+            match plugin.dispatcher() {
+                None => Self::notify_plugin(plugin, &transaction_info, slot),
+                Some(dispatcher) => {
+                    let owned: Arc<(Signature, VersionedTransaction, Option<LoadedAddresses>)> =
+                        owned_transaction
+                            .get_or_insert_with(|| {
+                                Arc::new((
+                                    *signature,
+                                    transaction.clone(),
+                                    loaded_addresses.cloned(),
+                                ))
+                            })
+                            .clone();
+                    dispatcher.dispatch(Some(slot), move |plugin| {
+                        let (signature, transaction, loaded_addresses) = owned.as_ref();
+                        let transaction_info = ReplicaDeshredTransactionInfoV2 {
+                            signature,
+                            is_vote,
+                            transaction,
+                            loaded_addresses: loaded_addresses.as_ref(),
+                            completed_data_set_starting_shred_index,
+                            completed_data_set_ending_shred_index_exclusive,
+                        };
+                        Self::notify_plugin(plugin, &transaction_info, slot)
+                    });
                 }
             }
         }
@@ -86,6 +96,31 @@ impl DeshredTransactionNotifier for DeshredTransactionNotifierImpl {
 }
 
 impl DeshredTransactionNotifierImpl {
+    fn notify_plugin(
+        plugin: &LoadedGeyserPlugin,
+        transaction_info: &ReplicaDeshredTransactionInfoV2,
+        slot: Slot,
+    ) {
+        match plugin.notify_deshred_transaction(
+            ReplicaDeshredTransactionInfoVersions::V0_0_2(transaction_info),
+            slot,
+        ) {
+            Err(err) => {
+                error!(
+                    "Failed to notify deshred transaction, error: ({}) to plugin {}",
+                    err,
+                    plugin.name()
+                )
+            }
+            Ok(_) => {
+                trace!(
+                    "Successfully notified deshred transaction to plugin {}",
+                    plugin.name()
+                );
+            }
+        }
+    }
+
     pub fn new(plugin_manager: Arc<ArcSwap<GeyserPluginManager>>) -> Self {
         Self { plugin_manager }
     }
@@ -1,6 +1,6 @@
 /// Module responsible for notifying plugins about entries
 use {
-    crate::geyser_plugin_manager::GeyserPluginManager,
+    crate::geyser_plugin_manager::{GeyserPluginManager, LoadedGeyserPlugin},
     agave_geyser_plugin_interface::geyser_plugin_interface::{
         ReplicaEntryInfoV2, ReplicaEntryInfoVersions,
     },
@@ -36,16 +36,23 @@ impl EntryNotifier for EntryNotifierImpl {
             if !plugin.entry_notifications_enabled() {
                 continue;
             }
-            match plugin.notify_entry(ReplicaEntryInfoVersions::V0_0_2(&entry_info)) {
-                Err(err) => {
-                    error!(
-                        "Failed to notify entry, error: ({}) to plugin {}",
-                        err,
-                        plugin.name()
-                    )
-                }
-                Ok(_) => {
-                    trace!("Successfully notified entry to plugin {}", plugin.name());
+            match plugin.dispatcher() {
+                None => Self::notify_plugin(plugin, &entry_info),
+                Some(dispatcher) => {
+                    let entry = EntrySummary {
+                        num_hashes: entry.num_hashes,
+                        hash: entry.hash,
+                        num_transactions: entry.num_transactions,
+                    };
+                    dispatcher.dispatch(Some(slot), move |plugin| {
+                        let entry_info = Self::build_replica_entry_info(
+                            slot,
+                            index,
+                            &entry,
+                            starting_transaction_index,
+                        );
+                        Self::notify_plugin(plugin, &entry_info)
+                    });
                 }
             }
         }
@@ -57,6 +64,21 @@ impl EntryNotifierImpl {
         Self { plugin_manager }
     }
 
+    pub(crate) fn notify_plugin(plugin: &LoadedGeyserPlugin, entry_info: &ReplicaEntryInfoV2) {
+        match plugin.notify_entry(ReplicaEntryInfoVersions::V0_0_2(entry_info)) {
+            Err(err) => {
+                error!(
+                    "Failed to notify entry, error: ({}) to plugin {}",
+                    err,
+                    plugin.name()
+                )
+            }
+            Ok(_) => {
+                trace!("Successfully notified entry to plugin {}", plugin.name());
+            }
+        }
+    }
+
     fn build_replica_entry_info(
         slot: Slot,
         index: usize,
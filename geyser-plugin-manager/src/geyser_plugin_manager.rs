@@ -1,10 +1,20 @@
 use {
-    crate::geyser_plugin_service::ARC_TRY_UNWRAP_ATTEMPT_SLEEP_DURATION,
-    agave_geyser_plugin_interface::geyser_plugin_interface::GeyserPlugin,
+    crate::{
+        account_restream::AccountRestream,
+        catch_up::{CatchUp, CatchUpSource, DEFAULT_CATCH_UP_DISPATCH_CONFIG},
+        geyser_plugin_service::ARC_TRY_UNWRAP_ATTEMPT_SLEEP_DURATION,
+        notification_filter::NotificationFilter,
+        plugin_dispatcher::{PluginDispatchConfig, PluginDispatchStats, PluginDispatcher},
+    },
+    agave_geyser_plugin_interface::geyser_plugin_interface::{
+        GeyserPlugin, Result as PluginResult,
+    },
     arc_swap::ArcSwap,
     jsonrpc_core::{ErrorCode, Result as JsonRpcResult},
     libloading::Library,
     log::*,
+    solana_clock::Slot,
+    solana_pubkey::Pubkey,
     std::{
         ops::{Deref, DerefMut},
         path::Path,
@@ -17,7 +27,12 @@ use {
 #[derive(Debug)]
 pub struct LoadedGeyserPlugin {
     name: String,
+    dispatch_config: Option<PluginDispatchConfig>,
+    // Declared before the plugin so the worker thread is stopped first
+    dispatcher: Option<PluginDispatcher>,
     plugin: Box<dyn GeyserPlugin>,
+    notification_filter: NotificationFilter,
+    catch_up_from_slot: Option<Slot>,
     // NOTE: While we do not access the library, the plugin we have loaded most
     // certainly does. To ensure we don't SIGSEGV we must declare the library
     // after the plugin so the plugin is dropped first.
@@ -26,22 +41,101 @@ pub struct LoadedGeyserPlugin {
     // any code before returning from Drop. This means if the Geyser plugins
     // spawn threads that access the Library, those threads must be `join`ed
     // before the Geyser plugin returns from on_unload / Drop.
+    //
+    // None for plugins linked into the validator, like the out-of-process
+    // plugin adapter.
     #[allow(dead_code)]
-    library: Library,
+    library: Option<Library>,
 }
 
 impl LoadedGeyserPlugin {
     pub fn new(library: Library, plugin: Box<dyn GeyserPlugin>, name: Option<String>) -> Self {
+        Self {
+            library: Some(library),
+            ..Self::new_without_library(plugin, name)
+        }
+    }
+
+    /// A plugin implemented by the validator itself rather than by a
+    /// dynamically loaded library
+    pub fn new_without_library(plugin: Box<dyn GeyserPlugin>, name: Option<String>) -> Self {
         Self {
             name: name.unwrap_or_else(|| plugin.name().to_owned()),
+            dispatch_config: None,
+            dispatcher: None,
             plugin,
-            library,
+            notification_filter: NotificationFilter::default(),
+            catch_up_from_slot: None,
+            library: None,
+        }
+    }
+
+    /// Delivers the plugin's notifications through a dedicated worker and
+    /// bounded queue instead of calling it inline, see [`Self::start`]
+    pub fn with_dispatch_config(self, dispatch_config: Option<PluginDispatchConfig>) -> Self {
+        Self {
+            dispatch_config,
+            ..self
         }
     }
 
+    /// Starts the dispatch worker of a loaded plugin, if it has a dispatch
+    /// config or requested catch-up, and returns the plugin ready to be added
+    /// to the manager
+    pub(crate) fn start(self, catch_up_source: &Arc<CatchUpSource>) -> Arc<Self> {
+        let catch_up = self
+            .catch_up_from_slot
+            .map(|from_slot| CatchUp::new(from_slot, Arc::clone(catch_up_source)));
+        let dispatch_config = match (self.dispatch_config, &catch_up) {
+            (None, Some(_)) => Some(DEFAULT_CATCH_UP_DISPATCH_CONFIG),
+            (dispatch_config, _) => dispatch_config,
+        };
+        match dispatch_config {
+            None => Arc::new(self),
+            Some(dispatch_config) => Arc::new_cyclic(|plugin| Self {
+                dispatcher: Some(PluginDispatcher::start(
+                    &self.name,
+                    dispatch_config,
+                    plugin.clone(),
+                    catch_up,
+                )),
+                ..self
+            }),
+        }
+    }
+
+    /// The queue notifications must go through, or `None` if the plugin is
+    /// called inline
+    pub(crate) fn dispatcher(&self) -> Option<&PluginDispatcher> {
+        self.dispatcher.as_ref()
+    }
+
+    pub fn dispatch_stats(&self) -> Option<PluginDispatchStats> {
+        self.dispatcher
+            .as_ref()
+            .map(|dispatcher| dispatcher.stats(&self.name))
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    /// Calls the plugin's `on_load` and then captures the notification filter
+    /// and catch-up slot it declares for the loaded config
+    pub fn load(&mut self, config_file: &str, is_reload: bool) -> PluginResult<()> {
+        self.plugin.on_load(config_file, is_reload)?;
+        self.notification_filter = self
+            .plugin
+            .notification_filter()
+            .map(NotificationFilter::from)
+            .unwrap_or_default();
+        self.catch_up_from_slot = self.plugin.catch_up_from_slot();
+        Ok(())
+    }
+
+    pub(crate) fn notification_filter(&self) -> &NotificationFilter {
+        &self.notification_filter
+    }
 }
 
 impl Deref for LoadedGeyserPlugin {
@@ -93,6 +187,14 @@ impl GeyserPluginManager {
         false
     }
 
+    /// Check if there is any plugin rolling back the account updates of
+    /// abandoned forks
+    pub fn account_rollback_notifications_enabled(&self) -> bool {
+        self.plugins
+            .iter()
+            .any(|plugin| plugin.account_rollback_notifications_enabled())
+    }
+
     /// Check if there is any plugin interested in transaction data
     pub fn transaction_notifications_enabled(&self) -> bool {
         for plugin in &self.plugins {
@@ -138,6 +240,15 @@ impl GeyserPluginManager {
         Ok(self.plugins.iter().map(|p| p.name().to_owned()).collect())
     }
 
+    /// Admin RPC request handler
+    pub(crate) fn dispatch_stats(&self) -> JsonRpcResult<Vec<PluginDispatchStats>> {
+        Ok(self
+            .plugins
+            .iter()
+            .filter_map(|plugin| plugin.dispatch_stats())
+            .collect())
+    }
+
     /// Admin RPC request handler
     /// # Safety
     ///
@@ -149,6 +260,7 @@ impl GeyserPluginManager {
     pub(crate) fn load_plugin(
         plugin_manager: &ArcSwap<GeyserPluginManager>,
         geyser_plugin_config_file: impl AsRef<Path>,
+        catch_up_source: &Arc<CatchUpSource>,
     ) -> JsonRpcResult<String> {
         let mut new_plugin_manager = (*plugin_manager.load_full()).clone();
 
@@ -182,7 +294,7 @@ impl GeyserPluginManager {
 
         // Call on_load and push plugin
         new_plugin
-            .on_load(new_config_file, false)
+            .load(new_config_file, false)
             .map_err(|on_load_err| jsonrpc_core::Error {
                 code: ErrorCode::InvalidRequest,
                 message: format!(
@@ -192,7 +304,9 @@ impl GeyserPluginManager {
                 data: None,
             })?;
         let name = new_plugin.name().to_string();
-        new_plugin_manager.plugins.push(Arc::new(new_plugin));
+        new_plugin_manager
+            .plugins
+            .push(new_plugin.start(catch_up_source));
         plugin_manager.store(Arc::new(new_plugin_manager));
 
         Ok(name)
@@ -234,6 +348,7 @@ impl GeyserPluginManager {
         plugin_manager: &ArcSwap<GeyserPluginManager>,
         name: &str,
         config_file: &str,
+        catch_up_source: &Arc<CatchUpSource>,
     ) -> JsonRpcResult<()> {
         let mut new_plugin_manager: GeyserPluginManager = (*plugin_manager.load_full()).clone();
         // Check if any plugin names match this one
@@ -287,10 +402,12 @@ impl GeyserPluginManager {
         setup_logger_for_plugin(&*new_plugin.plugin)?;
 
         // Attempt to on_load with new plugin
-        match new_plugin.on_load(new_parsed_config_file, true) {
+        match new_plugin.load(new_parsed_config_file, true) {
             // On success, push plugin and library
             Ok(()) => {
-                new_plugin_manager.plugins.push(Arc::new(new_plugin));
+                new_plugin_manager
+                    .plugins
+                    .push(new_plugin.start(catch_up_source));
                 plugin_manager.store(Arc::new(new_plugin_manager));
             }
 
@@ -318,6 +435,9 @@ impl GeyserPluginManager {
             match Arc::try_unwrap(plugin_ref) {
                 Ok(mut current_plugin) => {
                     let name = current_plugin.name().to_string();
+                    if let Some(dispatcher) = current_plugin.dispatcher.take() {
+                        dispatcher.join();
+                    }
                     current_plugin.plugin.on_unload();
                     info!("Unloaded plugin {name} at idx {idx}");
                     return;
@@ -361,6 +481,14 @@ pub enum GeyserPluginManagerRequest {
     ListPlugins {
         response_sender: OneShotSender<JsonRpcResult<Vec<String>>>,
     },
+    DispatchStats {
+        response_sender: OneShotSender<JsonRpcResult<Vec<PluginDispatchStats>>>,
+    },
+    RestreamAccounts {
+        name: String,
+        owners: Vec<Pubkey>,
+        response_sender: OneShotSender<JsonRpcResult<AccountRestream>>,
+    },
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -432,6 +560,34 @@ pub(crate) fn load_plugin_from_config(
         }
     };
 
+    let plugin_name = result["name"].as_str().map(|s| s.to_owned());
+    let dispatch_config = result
+        .get("dispatch")
+        .map(parse_dispatch_config)
+        .transpose()
+        .map_err(|err| {
+            GeyserPluginManagerError::InvalidConfigFileFormat(format!(
+                "Invalid dispatch section in the config file {geyser_plugin_config_file:?}: {err}"
+            ))
+        })?;
+
+    let config_file = geyser_plugin_config_file
+        .as_os_str()
+        .to_str()
+        .ok_or(GeyserPluginManagerError::InvalidPluginPath)?;
+
+    // The plugin runs in an `agave-geyser-plugin-host` process, reached
+    // through the socket of the ipc section
+    #[cfg(unix)]
+    if result.get("ipc").is_some() {
+        let plugin = Box::<agave_geyser_plugin_ipc::client::IpcGeyserPlugin>::default();
+        return Ok((
+            LoadedGeyserPlugin::new_without_library(plugin, plugin_name)
+                .with_dispatch_config(dispatch_config),
+            config_file,
+        ));
+    }
+
     let libpath = result["libpath"]
         .as_str()
         .ok_or(GeyserPluginManagerError::LibPathNotSet)?;
@@ -445,13 +601,6 @@ pub(crate) fn load_plugin_from_config(
         libpath = config_dir.join(libpath);
     }
 
-    let plugin_name = result["name"].as_str().map(|s| s.to_owned());
-
-    let config_file = geyser_plugin_config_file
-        .as_os_str()
-        .to_str()
-        .ok_or(GeyserPluginManagerError::InvalidPluginPath)?;
-
     let (plugin, lib) = unsafe {
         let lib = Library::new(libpath)
             .map_err(|e| GeyserPluginManagerError::PluginLoadError(e.to_string()))?;
@@ -462,11 +611,20 @@ pub(crate) fn load_plugin_from_config(
         (Box::from_raw(plugin_raw), lib)
     };
     Ok((
-        LoadedGeyserPlugin::new(lib, plugin, plugin_name),
+        LoadedGeyserPlugin::new(lib, plugin, plugin_name).with_dispatch_config(dispatch_config),
         config_file,
     ))
 }
 
+fn parse_dispatch_config(dispatch: &serde_json::Value) -> Result<PluginDispatchConfig, String> {
+    let dispatch_config: PluginDispatchConfig =
+        serde_json::from_value(dispatch.clone()).map_err(|err| err.to_string())?;
+    if dispatch_config.queue_capacity == 0 {
+        return Err("queue_capacity must be greater than zero".to_string());
+    }
+    Ok(dispatch_config)
+}
+
 #[cfg(test)]
 const TESTPLUGIN_CONFIG: &str = "TESTPLUGIN_CONFIG";
 #[cfg(test)]
@@ -505,8 +663,10 @@ mod tests {
             deshred_transaction_notifier::DeshredTransactionNotifierImpl,
             geyser_plugin_manager::{
                 GeyserPluginManager, LoadedGeyserPlugin, TESTPLUGIN_CONFIG, TESTPLUGIN2_CONFIG,
+                parse_dispatch_config,
             },
             geyser_plugin_service::ARC_TRY_UNWRAP_ATTEMPT_SLEEP_DURATION,
+            plugin_dispatcher::{OverflowPolicy, PluginDispatchConfig},
         },
         agave_geyser_plugin_interface::geyser_plugin_interface::{
             GeyserPlugin, ReplicaDeshredTransactionInfo, ReplicaDeshredTransactionInfoVersions,
@@ -670,8 +830,12 @@ mod tests {
         let plugin_manager = Arc::new(ArcSwap::new(Arc::new(GeyserPluginManager::default())));
 
         // No plugins are loaded, this should fail
-        let reload_result =
-            GeyserPluginManager::reload_plugin(&plugin_manager, DUMMY_NAME, DUMMY_CONFIG);
+        let reload_result = GeyserPluginManager::reload_plugin(
+            &plugin_manager,
+            DUMMY_NAME,
+            DUMMY_CONFIG,
+            &Arc::default(),
+        );
         assert_eq!(
             reload_result.unwrap_err().message,
             "The plugin you requested to reload is not loaded"
@@ -693,16 +857,24 @@ mod tests {
 
         // Try wrong name (same error)
         const WRONG_NAME: &str = "wrong_name";
-        let reload_result =
-            GeyserPluginManager::reload_plugin(&plugin_manager, WRONG_NAME, DUMMY_CONFIG);
+        let reload_result = GeyserPluginManager::reload_plugin(
+            &plugin_manager,
+            WRONG_NAME,
+            DUMMY_CONFIG,
+            &Arc::default(),
+        );
         assert_eq!(
             reload_result.unwrap_err().message,
             "The plugin you requested to reload is not loaded"
         );
 
         // Now try a (dummy) reload, replacing TestPlugin with TestPlugin2
-        let reload_result =
-            GeyserPluginManager::reload_plugin(&plugin_manager, DUMMY_NAME, TESTPLUGIN2_CONFIG);
+        let reload_result = GeyserPluginManager::reload_plugin(
+            &plugin_manager,
+            DUMMY_NAME,
+            TESTPLUGIN2_CONFIG,
+            &Arc::default(),
+        );
         assert!(reload_result.is_ok());
         assert!(!test_plugin_loaded.load(Ordering::Relaxed));
 
@@ -743,7 +915,8 @@ mod tests {
         let plugin_manager = Arc::new(ArcSwap::new(Arc::new(GeyserPluginManager::default())));
 
         // Load rpc call
-        let load_result = GeyserPluginManager::load_plugin(&plugin_manager, TESTPLUGIN_CONFIG);
+        let load_result =
+            GeyserPluginManager::load_plugin(&plugin_manager, TESTPLUGIN_CONFIG, &Arc::default());
         assert!(load_result.is_ok());
         assert_eq!(plugin_manager.load().plugins.len(), 1);
 
@@ -928,8 +1101,12 @@ mod tests {
         let plugin_manager = Arc::new(ArcSwap::new(Arc::new(GeyserPluginManager::default())));
 
         // No plugins are loaded, this should fail
-        let reload_result =
-            GeyserPluginManager::reload_plugin(&plugin_manager, DUMMY_NAME, DUMMY_CONFIG);
+        let reload_result = GeyserPluginManager::reload_plugin(
+            &plugin_manager,
+            DUMMY_NAME,
+            DUMMY_CONFIG,
+            &Arc::default(),
+        );
         assert_eq!(
             reload_result.unwrap_err().message,
             "The plugin you requested to reload is not loaded"
@@ -968,4 +1145,33 @@ mod tests {
         }
         assert!(!test_plugin_loaded.load(Ordering::Relaxed));
     }
+
+    #[test]
+    fn test_parse_dispatch_config() {
+        assert_eq!(
+            parse_dispatch_config(&serde_json::json!({"queue_capacity": 10})),
+            Ok(PluginDispatchConfig {
+                queue_capacity: 10,
+                overflow_policy: OverflowPolicy::Block,
+            })
+        );
+        assert_eq!(
+            parse_dispatch_config(&serde_json::json!({
+                "queue_capacity": 10,
+                "overflow_policy": "drop_oldest",
+            })),
+            Ok(PluginDispatchConfig {
+                queue_capacity: 10,
+                overflow_policy: OverflowPolicy::DropOldest,
+            })
+        );
+        assert!(parse_dispatch_config(&serde_json::json!({"queue_capacity": 0})).is_err());
+        assert!(
+            parse_dispatch_config(&serde_json::json!({
+                "queue_capacity": 10,
+                "overflow_policy": "drop_newest",
+            }))
+            .is_err()
+        );
+    }
 }
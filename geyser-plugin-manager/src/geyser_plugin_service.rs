@@ -1,8 +1,11 @@
 use {
     crate::{
+        account_restream::AccountRestreamSource,
+        account_write_tracker::AccountWriteTracker,
         accounts_update_notifier::AccountsUpdateNotifierImpl,
         block_metadata_notifier::BlockMetadataNotifierImpl,
         block_metadata_notifier_interface::BlockMetadataNotifierArc,
+        catch_up::CatchUpSource,
         deshred_transaction_notifier::DeshredTransactionNotifierImpl,
         entry_notifier::EntryNotifierImpl,
         geyser_plugin_manager::{GeyserPluginManager, GeyserPluginManagerRequest},
@@ -15,6 +18,7 @@ use {
     log::*,
     solana_accounts_db::accounts_update_notifier_interface::AccountsUpdateNotifier,
     solana_ledger::{
+        blockstore::Blockstore,
         deshred_transaction_notifier_interface::DeshredTransactionNotifierArc,
         entry_notifier_interface::EntryNotifierArc,
     },
@@ -23,6 +27,7 @@ use {
         slot_status_notifier::SlotStatusNotifier,
         transaction_notifier_interface::TransactionNotifierArc,
     },
+    solana_runtime::bank_forks::BankForks,
     std::{
         path::{Path, PathBuf},
         sync::{
@@ -42,6 +47,8 @@ pub(crate) const ARC_TRY_UNWRAP_ATTEMPT_SLEEP_DURATION: Duration = Duration::fro
 pub struct GeyserPluginService {
     slot_status_observer: Option<SlotStatusObserver>,
     plugin_manager: Arc<ArcSwap<GeyserPluginManager>>,
+    catch_up_source: Arc<CatchUpSource>,
+    account_restream_source: Arc<AccountRestreamSource>,
     accounts_update_notifier: Option<AccountsUpdateNotifier>,
     transaction_notifier: Option<TransactionNotifierArc>,
     deshred_transaction_notifier: Option<DeshredTransactionNotifierArc>,
@@ -92,8 +99,10 @@ impl GeyserPluginService {
             plugins: Vec::new(),
         })));
 
+        let catch_up_source = Arc::new(CatchUpSource::default());
+
         for geyser_plugin_config_file in geyser_plugin_config_files {
-            Self::load_plugin(&plugin_manager, geyser_plugin_config_file)?;
+            Self::load_plugin(&plugin_manager, geyser_plugin_config_file, &catch_up_source)?;
         }
 
         let account_data_notifications_enabled =
@@ -112,12 +121,17 @@ impl GeyserPluginService {
         let entry_notifications_enabled =
             plugin_manager.load().entry_notifications_enabled() || geyser_plugin_always_enabled;
 
+        // Accounts written in unrooted slots, recorded by the accounts update
+        // notifier and released by the slot status notifier
+        let account_writes = Arc::new(AccountWriteTracker::default());
+
         let accounts_update_notifier: Option<AccountsUpdateNotifier> =
             if account_data_notifications_enabled {
                 let accounts_update_notifier = AccountsUpdateNotifierImpl::new(
                     plugin_manager.clone(),
                     account_data_snapshot_notifications_enabled,
-                );
+                )
+                .with_account_writes(account_writes.clone());
                 Some(Arc::new(accounts_update_notifier))
             } else {
                 None
@@ -156,7 +170,8 @@ impl GeyserPluginService {
             || deshred_transaction_notifications_enabled
             || entry_notifications_enabled
         {
-            let slot_status_notifier = SlotStatusNotifierImpl::new(plugin_manager.clone());
+            let slot_status_notifier = SlotStatusNotifierImpl::new(plugin_manager.clone())
+                .with_account_writes(account_writes);
             let slot_status_notifier = Arc::new(RwLock::new(slot_status_notifier));
             (
                 Some(SlotStatusObserver::new(
@@ -173,15 +188,26 @@ impl GeyserPluginService {
         };
 
         // Initialize plugin manager rpc handler thread if needed
+        let account_restream_source = Arc::new(AccountRestreamSource::default());
         if let Some((request_receiver, exit)) = rpc_to_plugin_manager_receiver_and_exit {
             let plugin_manager = plugin_manager.clone();
-            Self::start_manager_rpc_handler(plugin_manager, request_receiver, exit)
+            let catch_up_source = catch_up_source.clone();
+            let account_restream_source = account_restream_source.clone();
+            Self::start_manager_rpc_handler(
+                plugin_manager,
+                catch_up_source,
+                account_restream_source,
+                request_receiver,
+                exit,
+            )
         };
 
         info!("Started GeyserPluginService");
         Ok(GeyserPluginService {
             slot_status_observer,
             plugin_manager,
+            catch_up_source,
+            account_restream_source,
             accounts_update_notifier,
             transaction_notifier,
             deshred_transaction_notifier,
@@ -194,9 +220,14 @@ impl GeyserPluginService {
     fn load_plugin(
         plugin_manager: &ArcSwap<GeyserPluginManager>,
         geyser_plugin_config_file: &Path,
+        catch_up_source: &Arc<CatchUpSource>,
     ) -> Result<(), GeyserPluginServiceError> {
-        GeyserPluginManager::load_plugin(plugin_manager, geyser_plugin_config_file)
-            .map_err(|e| GeyserPluginServiceError::FailedToLoadPlugin(e.into()))?;
+        GeyserPluginManager::load_plugin(
+            plugin_manager,
+            geyser_plugin_config_file,
+            catch_up_source,
+        )
+        .map_err(|e| GeyserPluginServiceError::FailedToLoadPlugin(e.into()))?;
         Ok(())
     }
 
@@ -213,6 +244,22 @@ impl GeyserPluginService {
         self.plugin_manager.clone()
     }
 
+    /// Provides the ledger from which plugins requesting catch-up are
+    /// replayed the rooted slots they missed, and whether the transaction
+    /// history is stored in it. Plugins loaded before this is called wait for
+    /// it to start their catch-up.
+    pub fn set_catch_up_blockstore(&self, blockstore: Arc<Blockstore>, transaction_history: bool) {
+        self.catch_up_source
+            .set_blockstore(blockstore, transaction_history);
+    }
+
+    /// Provides the banks whose root is re-streamed to plugins by the
+    /// `restreamPluginAccounts` admin RPC method, which is refused until this
+    /// is called.
+    pub fn set_account_restream_bank_forks(&self, bank_forks: Arc<RwLock<BankForks>>) {
+        self.account_restream_source.set_bank_forks(bank_forks);
+    }
+
     pub fn get_transaction_notifier(&self) -> Option<TransactionNotifierArc> {
         self.transaction_notifier.clone()
     }
@@ -261,6 +308,8 @@ impl GeyserPluginService {
 
     fn start_manager_rpc_handler(
         plugin_manager: Arc<ArcSwap<GeyserPluginManager>>,
+        catch_up_source: Arc<CatchUpSource>,
+        account_restream_source: Arc<AccountRestreamSource>,
         request_receiver: Receiver<GeyserPluginManagerRequest>,
         exit: Arc<AtomicBool>,
     ) {
@@ -277,6 +326,13 @@ impl GeyserPluginService {
                                     .expect("Admin rpc service will be waiting for response");
                             }
 
+                            GeyserPluginManagerRequest::DispatchStats { response_sender } => {
+                                let dispatch_stats = plugin_manager.load().dispatch_stats();
+                                response_sender
+                                    .send(dispatch_stats)
+                                    .expect("Admin rpc service will be waiting for response");
+                            }
+
                             GeyserPluginManagerRequest::ReloadPlugin {
                                 ref name,
                                 ref config_file,
@@ -286,6 +342,7 @@ impl GeyserPluginService {
                                     &plugin_manager,
                                     name,
                                     config_file,
+                                    &catch_up_source,
                                 );
                                 response_sender
                                     .send(reload_result)
@@ -296,8 +353,11 @@ impl GeyserPluginService {
                                 ref config_file,
                                 response_sender,
                             } => {
-                                let load_result =
-                                    GeyserPluginManager::load_plugin(&plugin_manager, config_file);
+                                let load_result = GeyserPluginManager::load_plugin(
+                                    &plugin_manager,
+                                    config_file,
+                                    &catch_up_source,
+                                );
                                 response_sender
                                     .send(load_result)
                                     .expect("Admin rpc service will be waiting for response");
@@ -313,6 +373,21 @@ impl GeyserPluginService {
                                     .send(unload_result)
                                     .expect("Admin rpc service will be waiting for response");
                             }
+
+                            GeyserPluginManagerRequest::RestreamAccounts {
+                                ref name,
+                                owners,
+                                response_sender,
+                            } => {
+                                let restream_result = account_restream_source.start(
+                                    &plugin_manager.load(),
+                                    name,
+                                    owners,
+                                );
+                                response_sender
+                                    .send(restream_result)
+                                    .expect("Admin rpc service will be waiting for response");
+                            }
                         }
                     }
 
@@ -1,12 +1,20 @@
 #![cfg(feature = "agave-unstable-api")]
+pub mod account_restream;
+mod account_write_tracker;
 pub mod accounts_update_notifier;
 pub mod block_metadata_notifier;
 pub mod block_metadata_notifier_interface;
+mod catch_up;
+pub mod consensus_notifier;
 pub mod contact_info_notifier;
 pub mod deshred_transaction_notifier;
 pub mod entry_notifier;
 pub mod geyser_plugin_manager;
 pub mod geyser_plugin_service;
+mod notification_filter;
+pub mod plugin_dispatcher;
+#[cfg(any(test, feature = "dev-context-only-utils"))]
+pub mod recording_plugin;
 pub mod slot_status_notifier;
 pub mod slot_status_observer;
 pub mod transaction_notifier;
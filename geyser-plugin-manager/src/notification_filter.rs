@@ -0,0 +1,218 @@
+/// Module applying the notification filters declared by plugins
+use {
+    agave_geyser_plugin_interface::geyser_plugin_interface::{
+        GeyserAccountFilter, GeyserPluginFilter, GeyserTransactionFilter, ReplicaAccountInfoV3,
+    },
+    solana_pubkey::Pubkey,
+    solana_transaction::versioned::VersionedTransaction,
+    solana_transaction_status::TransactionStatusMeta,
+    std::{collections::HashSet, ops::RangeInclusive},
+};
+
+/// A plugin's `GeyserPluginFilter`, indexed for evaluation on the
+/// notification path
+#[derive(Debug, Default)]
+pub(crate) struct NotificationFilter {
+    accounts: Option<AccountFilter>,
+    transactions: Option<TransactionFilter>,
+}
+
+#[derive(Debug)]
+struct AccountFilter {
+    owners: HashSet<Pubkey>,
+    pubkeys: HashSet<Pubkey>,
+    data_size_ranges: Vec<RangeInclusive<usize>>,
+}
+
+#[derive(Debug)]
+struct TransactionFilter {
+    vote: Option<bool>,
+    mentioned_accounts: HashSet<Pubkey>,
+}
+
+impl From<GeyserPluginFilter> for NotificationFilter {
+    fn from(filter: GeyserPluginFilter) -> Self {
+        Self {
+            accounts: filter.accounts.map(AccountFilter::from),
+            transactions: filter.transactions.map(TransactionFilter::from),
+        }
+    }
+}
+
+impl From<GeyserAccountFilter> for AccountFilter {
+    fn from(filter: GeyserAccountFilter) -> Self {
+        Self {
+            owners: filter
+                .owners
+                .into_iter()
+                .map(Pubkey::new_from_array)
+                .collect(),
+            pubkeys: filter
+                .pubkeys
+                .into_iter()
+                .map(Pubkey::new_from_array)
+                .collect(),
+            data_size_ranges: filter.data_size_ranges,
+        }
+    }
+}
+
+impl From<GeyserTransactionFilter> for TransactionFilter {
+    fn from(filter: GeyserTransactionFilter) -> Self {
+        Self {
+            vote: filter.vote,
+            mentioned_accounts: filter
+                .mentioned_accounts
+                .into_iter()
+                .map(Pubkey::new_from_array)
+                .collect(),
+        }
+    }
+}
+
+impl NotificationFilter {
+    pub(crate) fn matches_account(&self, account: &ReplicaAccountInfoV3) -> bool {
+        let Some(filter) = &self.accounts else {
+            return true;
+        };
+        let contains = |set: &HashSet<Pubkey>, key: &[u8]| {
+            Pubkey::try_from(key).is_ok_and(|key| set.contains(&key))
+        };
+        let identity_matches = (filter.owners.is_empty() && filter.pubkeys.is_empty())
+            || contains(&filter.pubkeys, account.pubkey)
+            || contains(&filter.owners, account.owner);
+        let data_size_matches = filter.data_size_ranges.is_empty()
+            || filter
+                .data_size_ranges
+                .iter()
+                .any(|range| range.contains(&account.data.len()));
+        identity_matches && data_size_matches
+    }
+
+    pub(crate) fn matches_transaction(
+        &self,
+        is_vote: bool,
+        transaction: &VersionedTransaction,
+        transaction_status_meta: &TransactionStatusMeta,
+    ) -> bool {
+        let Some(filter) = &self.transactions else {
+            return true;
+        };
+        if filter.vote.is_some_and(|vote| vote != is_vote) {
+            return false;
+        }
+        if filter.mentioned_accounts.is_empty() {
+            return true;
+        }
+        let loaded_addresses = &transaction_status_meta.loaded_addresses;
+        transaction
+            .message
+            .static_account_keys()
+            .iter()
+            .chain(&loaded_addresses.writable)
+            .chain(&loaded_addresses.readonly)
+            .any(|key| filter.mentioned_accounts.contains(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_message::{Message, VersionedMessage, v0::LoadedAddresses},
+        solana_signature::Signature,
+    };
+
+    fn account_info<'a>(
+        pubkey: &'a Pubkey,
+        owner: &'a Pubkey,
+        data: &'a [u8],
+    ) -> ReplicaAccountInfoV3<'a> {
+        ReplicaAccountInfoV3 {
+            pubkey: pubkey.as_ref(),
+            lamports: 1,
+            owner: owner.as_ref(),
+            executable: false,
+            rent_epoch: 0,
+            data,
+            write_version: 0,
+            txn: None,
+        }
+    }
+
+    #[test]
+    fn test_matches_account() {
+        let program = Pubkey::new_unique();
+        let watched = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        assert!(NotificationFilter::default().matches_account(&account_info(&other, &other, &[])));
+
+        let filter = NotificationFilter::from(GeyserPluginFilter {
+            accounts: Some(GeyserAccountFilter {
+                owners: vec![program.to_bytes()],
+                pubkeys: vec![watched.to_bytes()],
+                data_size_ranges: vec![0..=0, 10..=20],
+            }),
+            transactions: None,
+        });
+        assert!(filter.matches_account(&account_info(&other, &program, &[0; 15])));
+        assert!(filter.matches_account(&account_info(&watched, &other, &[])));
+        assert!(!filter.matches_account(&account_info(&other, &other, &[])));
+        assert!(!filter.matches_account(&account_info(&other, &program, &[0; 5])));
+
+        let filter = NotificationFilter::from(GeyserPluginFilter {
+            accounts: Some(GeyserAccountFilter {
+                data_size_ranges: vec![165..=165],
+                ..GeyserAccountFilter::default()
+            }),
+            transactions: None,
+        });
+        assert!(filter.matches_account(&account_info(&other, &other, &[0; 165])));
+        assert!(!filter.matches_account(&account_info(&other, &other, &[0; 82])));
+    }
+
+    #[test]
+    fn test_matches_transaction() {
+        let payer = Pubkey::new_unique();
+        let lookup = Pubkey::new_unique();
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::Legacy(Message::new(&[], Some(&payer))),
+        };
+        let meta = |loaded_addresses| TransactionStatusMeta {
+            loaded_addresses,
+            ..TransactionStatusMeta::default()
+        };
+        let no_lookups = meta(LoadedAddresses::default());
+        let with_lookup = meta(LoadedAddresses {
+            writable: vec![],
+            readonly: vec![lookup],
+        });
+
+        assert!(NotificationFilter::default().matches_transaction(true, &transaction, &no_lookups));
+
+        let non_votes = NotificationFilter::from(GeyserPluginFilter {
+            accounts: None,
+            transactions: Some(GeyserTransactionFilter {
+                vote: Some(false),
+                mentioned_accounts: vec![],
+            }),
+        });
+        assert!(non_votes.matches_transaction(false, &transaction, &no_lookups));
+        assert!(!non_votes.matches_transaction(true, &transaction, &no_lookups));
+
+        let mentions = |key: Pubkey| {
+            NotificationFilter::from(GeyserPluginFilter {
+                accounts: None,
+                transactions: Some(GeyserTransactionFilter {
+                    vote: None,
+                    mentioned_accounts: vec![key.to_bytes()],
+                }),
+            })
+        };
+        assert!(mentions(payer).matches_transaction(false, &transaction, &no_lookups));
+        assert!(mentions(lookup).matches_transaction(false, &transaction, &with_lookup));
+        assert!(!mentions(lookup).matches_transaction(false, &transaction, &no_lookups));
+    }
+}
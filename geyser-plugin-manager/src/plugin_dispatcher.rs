@@ -0,0 +1,628 @@
+//! Asynchronous delivery of notifications to Geyser plugins.
+//!
+//! By default plugins are called inline on the thread producing a
+//! notification, such as a replay or banking thread, so a slow plugin slows
+//! down the validator. A plugin whose config file has a `dispatch` section is
+//! instead given a dedicated worker thread fed by a bounded queue:
+//!
+//! ```json
+//! {
+//!     "libpath": "libmy_plugin.so",
+//!     "dispatch": { "queue_capacity": 100000, "overflow_policy": "drop_oldest" }
+//! }
+//! ```
+//!
+//! The overflow policy decides what happens when the plugin falls behind and
+//! its queue is full:
+//! * `block` (default) makes the producing thread wait for room in the queue,
+//!   which bounds memory but applies back pressure to the validator. While the
+//!   plugin catches up, the queue behaves as `drop_oldest` instead.
+//! * `drop_oldest` discards the oldest queued notifications. The plugin is
+//!   told about the discarded notifications by `GeyserPlugin::notify_gap`
+//!   before it receives the next one.
+//! * `disconnect` stops delivering notifications to the plugin until it is
+//!   reloaded.
+//!
+//! # Ordering
+//!
+//! Each plugin has a single queue and a single worker, so notifications are
+//! delivered in the order they were queued. A notifier queues a notification
+//! before it returns, while still on the producing thread, which gives the
+//! same guarantees as inline delivery:
+//! * updates of an account are delivered in write order, as the writes of an
+//!   account are serialized by the account locks held while notifying,
+//! * the account, transaction and entry notifications of a slot are
+//!   delivered before the slot status and block metadata notifications sent
+//!   after them for that slot.
+//!
+//! Notifications produced concurrently by different threads, for example for
+//! different slots replayed in parallel, may interleave. Notifications still
+//! queued when a plugin is unloaded or reloaded are discarded. Contact info
+//! notifications are not affected by the dispatch config, as they already
+//! have their own best-effort delivery thread.
+//!
+//! A plugin requesting catch-up has the rooted slots it missed replayed by its
+//! worker before any queued notification, see the `catch_up` module.
+
+use {
+    crate::{catch_up::CatchUp, geyser_plugin_manager::LoadedGeyserPlugin},
+    agave_geyser_plugin_interface::geyser_plugin_interface::NotificationGap,
+    crossbeam_channel::{Receiver, Select, Sender, TryRecvError, TrySendError, bounded},
+    log::*,
+    serde::{Deserialize, Serialize},
+    solana_clock::Slot,
+    std::{
+        sync::{
+            Arc, Mutex, Weak,
+            atomic::{AtomicBool, AtomicU64, Ordering},
+        },
+        thread::{self, JoinHandle},
+    },
+};
+
+// Marks a slot statistic that has not been set yet
+const NO_SLOT: u64 = u64::MAX;
+
+/// What to do with a notification when a plugin's queue is full
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Wait for the plugin to make room in its queue
+    #[default]
+    Block,
+    /// Discard the oldest queued notifications and report them as a gap
+    DropOldest,
+    /// Stop delivering notifications to the plugin
+    Disconnect,
+}
+
+/// The `dispatch` section of a plugin config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginDispatchConfig {
+    /// Maximum number of notifications queued for the plugin
+    pub queue_capacity: usize,
+    #[serde(default)]
+    pub overflow_policy: OverflowPolicy,
+}
+
+/// Queue statistics of a plugin with a dispatch queue
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginDispatchStats {
+    pub name: String,
+    pub queue_capacity: usize,
+    pub overflow_policy: OverflowPolicy,
+    /// Notifications waiting in the queue
+    pub queued: usize,
+    pub delivered: u64,
+    pub dropped: u64,
+    /// Number of gaps reported to the plugin
+    pub gaps: u64,
+    /// Highest slot of a queued notification
+    pub last_queued_slot: Option<Slot>,
+    /// Highest slot of a delivered notification
+    pub last_delivered_slot: Option<Slot>,
+    pub disconnected: bool,
+    /// Whether rooted slots are still being replayed to the plugin, in which
+    /// case live notifications are held in the queue
+    pub catching_up: bool,
+}
+
+impl PluginDispatchStats {
+    /// Number of slots between the newest queued and newest delivered notifications
+    pub fn slot_lag(&self) -> Option<Slot> {
+        let last_queued_slot = self.last_queued_slot?;
+        Some(last_queued_slot.saturating_sub(self.last_delivered_slot.unwrap_or_default()))
+    }
+}
+
+struct QueuedNotification {
+    slot: Option<Slot>,
+    notify: Box<dyn FnOnce(&LoadedGeyserPlugin) + Send>,
+}
+
+#[derive(Debug)]
+struct DispatchState {
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    gaps: AtomicU64,
+    last_queued_slot: AtomicU64,
+    last_delivered_slot: AtomicU64,
+    disconnected: AtomicBool,
+    catching_up: AtomicBool,
+    // Notifications dropped since the last gap was reported. The lock is held
+    // while dropping notifications and while the worker dequeues one, so a gap
+    // is reported right before the first notification queued after the
+    // notifications it covers.
+    pending_gap: Mutex<Option<NotificationGap>>,
+}
+
+impl Default for DispatchState {
+    fn default() -> Self {
+        Self {
+            delivered: AtomicU64::default(),
+            dropped: AtomicU64::default(),
+            gaps: AtomicU64::default(),
+            last_queued_slot: AtomicU64::new(NO_SLOT),
+            last_delivered_slot: AtomicU64::new(NO_SLOT),
+            disconnected: AtomicBool::default(),
+            catching_up: AtomicBool::default(),
+            pending_gap: Mutex::default(),
+        }
+    }
+}
+
+fn record_slot(statistic: &AtomicU64, slot: Slot) {
+    // NO_SLOT is the maximum value, so it cannot be raised with fetch_max
+    let _ = statistic.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+        (current == NO_SLOT || current < slot).then_some(slot)
+    });
+}
+
+fn load_slot(statistic: &AtomicU64) -> Option<Slot> {
+    let slot = statistic.load(Ordering::Relaxed);
+    (slot != NO_SLOT).then_some(slot)
+}
+
+/// Owns the queue and worker thread of a plugin
+#[derive(Debug)]
+pub(crate) struct PluginDispatcher {
+    config: PluginDispatchConfig,
+    sender: Sender<QueuedNotification>,
+    // Used by the drop-oldest policy to evict queued notifications
+    receiver: Receiver<QueuedNotification>,
+    state: Arc<DispatchState>,
+    worker: JoinHandle<()>,
+}
+
+impl PluginDispatcher {
+    pub(crate) fn start(
+        name: &str,
+        config: PluginDispatchConfig,
+        plugin: Weak<LoadedGeyserPlugin>,
+        catch_up: Option<CatchUp>,
+    ) -> Self {
+        let (sender, receiver) = bounded(config.queue_capacity);
+        let state = Arc::new(DispatchState {
+            catching_up: AtomicBool::new(catch_up.is_some()),
+            ..DispatchState::default()
+        });
+        let worker = {
+            let receiver = receiver.clone();
+            let state = Arc::clone(&state);
+            thread::Builder::new()
+                .name("solGeyserDisp".to_string())
+                .spawn(move || Self::run(receiver, plugin, state, catch_up))
+                .unwrap()
+        };
+        info!("Started dispatch queue for plugin {name} with {config:?}");
+        Self {
+            config,
+            sender,
+            receiver,
+            state,
+            worker,
+        }
+    }
+
+    fn run(
+        receiver: Receiver<QueuedNotification>,
+        plugin: Weak<LoadedGeyserPlugin>,
+        state: Arc<DispatchState>,
+        catch_up: Option<CatchUp>,
+    ) {
+        if let Some(catch_up) = catch_up {
+            catch_up.run(&plugin, |plugin, gap| Self::report_gap(plugin, gap, &state));
+            state.catching_up.store(false, Ordering::Relaxed);
+        }
+        let mut ready = Select::new();
+        ready.recv(&receiver);
+        loop {
+            // A notification dequeued without the gap lock may be older than
+            // the notifications a concurrent producer drops, so only wait for
+            // one here and dequeue it together with the pending gap
+            let (notification, gap) = {
+                let mut pending_gap = state.pending_gap.lock().unwrap();
+                match receiver.try_recv() {
+                    Ok(notification) => (notification, pending_gap.take()),
+                    Err(TryRecvError::Empty) => {
+                        drop(pending_gap);
+                        ready.ready();
+                        continue;
+                    }
+                    Err(TryRecvError::Disconnected) => break,
+                }
+            };
+            // The plugin is being unloaded, remaining notifications are discarded
+            let Some(plugin) = plugin.upgrade() else {
+                break;
+            };
+            if state.disconnected.load(Ordering::Relaxed) {
+                state.dropped.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            if let Some(gap) = gap {
+                Self::report_gap(&plugin, &gap, &state);
+            }
+            (notification.notify)(&plugin);
+            state.delivered.fetch_add(1, Ordering::Relaxed);
+            if let Some(slot) = notification.slot {
+                record_slot(&state.last_delivered_slot, slot);
+            }
+        }
+    }
+
+    fn report_gap(plugin: &LoadedGeyserPlugin, gap: &NotificationGap, state: &DispatchState) {
+        state.gaps.fetch_add(1, Ordering::Relaxed);
+        if let Err(err) = plugin.notify_gap(gap) {
+            error!(
+                "Failed to notify gap of {} notifications, error: {} to plugin {}",
+                gap.dropped,
+                err,
+                plugin.name()
+            );
+        }
+    }
+
+    /// Queues `notify` to be called with the plugin on the worker thread.
+    /// `slot` is the slot the notification refers to, if any.
+    pub(crate) fn dispatch(
+        &self,
+        slot: Option<Slot>,
+        notify: impl FnOnce(&LoadedGeyserPlugin) + Send + 'static,
+    ) {
+        if self.state.disconnected.load(Ordering::Relaxed) {
+            self.state.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        if let Some(slot) = slot {
+            record_slot(&self.state.last_queued_slot, slot);
+        }
+        let notification = QueuedNotification {
+            slot,
+            notify: Box::new(notify),
+        };
+        match self.overflow_policy() {
+            OverflowPolicy::Block => {
+                // The dispatcher holds a receiver, so sending cannot fail
+                let _ = self.sender.send(notification);
+            }
+            OverflowPolicy::DropOldest => self.dispatch_or_drop_oldest(notification),
+            OverflowPolicy::Disconnect => {
+                if let Err(TrySendError::Full(_)) = self.sender.try_send(notification) {
+                    self.state.disconnected.store(true, Ordering::Relaxed);
+                    self.state.dropped.fetch_add(1, Ordering::Relaxed);
+                    error!(
+                        "Geyser plugin queue is full, disconnecting the plugin until it is \
+                         reloaded"
+                    );
+                }
+            }
+        }
+    }
+
+    // Blocking the producers while the worker replays rooted slots would stall
+    // replay and banking for the whole catch-up, so a queue overflowing
+    // meanwhile drops its oldest notifications instead
+    fn overflow_policy(&self) -> OverflowPolicy {
+        match self.config.overflow_policy {
+            OverflowPolicy::Block if self.state.catching_up.load(Ordering::Relaxed) => {
+                OverflowPolicy::DropOldest
+            }
+            overflow_policy => overflow_policy,
+        }
+    }
+
+    fn dispatch_or_drop_oldest(&self, mut notification: QueuedNotification) {
+        loop {
+            match self.sender.try_send(notification) {
+                Ok(()) | Err(TrySendError::Disconnected(_)) => return,
+                Err(TrySendError::Full(returned)) => {
+                    notification = returned;
+                    let mut pending_gap = self.state.pending_gap.lock().unwrap();
+                    if let Ok(oldest) = self.receiver.try_recv() {
+                        self.state.dropped.fetch_add(1, Ordering::Relaxed);
+                        let gap = pending_gap.get_or_insert(NotificationGap {
+                            dropped: 0,
+                            slots: None,
+                            account_updates_only: false,
+                        });
+                        gap.dropped = gap.dropped.saturating_add(1);
+                        if let Some(slot) = oldest.slot {
+                            gap.slots = Some(match gap.slots.take() {
+                                None => slot..=slot,
+                                Some(slots) => {
+                                    (*slots.start()).min(slot)..=(*slots.end()).max(slot)
+                                }
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn stats(&self, name: &str) -> PluginDispatchStats {
+        PluginDispatchStats {
+            name: name.to_string(),
+            queue_capacity: self.config.queue_capacity,
+            overflow_policy: self.config.overflow_policy,
+            queued: self.sender.len(),
+            delivered: self.state.delivered.load(Ordering::Relaxed),
+            dropped: self.state.dropped.load(Ordering::Relaxed),
+            gaps: self.state.gaps.load(Ordering::Relaxed),
+            last_queued_slot: load_slot(&self.state.last_queued_slot),
+            last_delivered_slot: load_slot(&self.state.last_delivered_slot),
+            disconnected: self.state.disconnected.load(Ordering::Relaxed),
+            catching_up: self.state.catching_up.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Stops the worker thread, discarding queued notifications. Must be
+    /// called once no other reference to the plugin remains.
+    pub(crate) fn join(self) {
+        let Self { sender, worker, .. } = self;
+        drop(sender);
+        if worker.join().is_err() {
+            error!("Geyser plugin dispatch thread panicked");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            accounts_update_notifier::AccountsUpdateNotifierImpl,
+            geyser_plugin_manager::GeyserPluginManager,
+            recording_plugin::{Notification, RecordingPlugin},
+            slot_status_notifier::SlotStatusNotifierImpl,
+        },
+        agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+        arc_swap::ArcSwap,
+        libloading::Library,
+        solana_account::AccountSharedData,
+        solana_accounts_db::accounts_update_notifier_interface::AccountsUpdateNotifierInterface,
+        solana_pubkey::Pubkey,
+        std::time::{Duration, Instant},
+    };
+
+    fn start_plugin(
+        plugin: RecordingPlugin,
+        overflow_policy: OverflowPolicy,
+    ) -> Arc<LoadedGeyserPlugin> {
+        #[cfg(unix)]
+        let library = libloading::os::unix::Library::this();
+        #[cfg(windows)]
+        let library = libloading::os::windows::Library::this().unwrap();
+
+        LoadedGeyserPlugin::new(Library::from(library), Box::new(plugin), None)
+            .with_dispatch_config(Some(PluginDispatchConfig {
+                queue_capacity: 4,
+                overflow_policy,
+            }))
+            .start(&Arc::default())
+    }
+
+    fn wait_for_stats(
+        plugin: &LoadedGeyserPlugin,
+        condition: impl Fn(&PluginDispatchStats) -> bool,
+    ) -> PluginDispatchStats {
+        let start = Instant::now();
+        loop {
+            let stats = plugin.dispatch_stats().unwrap();
+            if condition(&stats) {
+                return stats;
+            }
+            assert!(start.elapsed() < Duration::from_secs(10), "{stats:?}");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn processed(slot: Slot) -> Notification {
+        Notification::SlotStatus {
+            slot,
+            parent: None,
+            status: SlotStatus::Processed,
+        }
+    }
+
+    fn wait_for_delivered(plugin: &LoadedGeyserPlugin, delivered: u64) -> PluginDispatchStats {
+        wait_for_stats(plugin, |stats| stats.delivered >= delivered)
+    }
+
+    #[test]
+    fn test_dispatch_preserves_order() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let plugin = start_plugin(
+            RecordingPlugin {
+                notifications: events.clone(),
+                ..RecordingPlugin::default()
+            },
+            OverflowPolicy::Block,
+        );
+        let plugin_manager = Arc::new(ArcSwap::from_pointee(GeyserPluginManager {
+            plugins: vec![plugin.clone()],
+        }));
+        let accounts_notifier = AccountsUpdateNotifierImpl::new(plugin_manager.clone(), false);
+        let slot_status_notifier = SlotStatusNotifierImpl::new(plugin_manager);
+        let account = AccountSharedData::new(1, 0, &Pubkey::new_unique());
+        let pubkey = Pubkey::new_unique();
+
+        // Far more notifications than the queue holds, so the producer blocks
+        let mut expected = vec![];
+        for slot in 0..10 {
+            for write_version in 0..10 {
+                let write_version = slot * 10 + write_version;
+                accounts_notifier.notify_account_update(
+                    slot,
+                    &account,
+                    &None,
+                    &pubkey,
+                    write_version,
+                );
+                expected.push(Notification::Account {
+                    slot,
+                    pubkey,
+                    lamports: 1,
+                    is_startup: false,
+                    write_version,
+                    write_sequence: None,
+                });
+            }
+            slot_status_notifier.notify_slot_status(slot, None, SlotStatus::Processed);
+            expected.push(processed(slot));
+        }
+
+        let stats = wait_for_delivered(&plugin, expected.len() as u64);
+        assert_eq!(*events.lock().unwrap(), expected);
+        assert_eq!(stats.dropped, 0);
+        assert_eq!(stats.last_queued_slot, Some(9));
+        assert_eq!(stats.last_delivered_slot, Some(9));
+        assert_eq!(stats.slot_lag(), Some(0));
+    }
+
+    #[test]
+    fn test_dispatch_preserves_order_per_producer() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let plugin = start_plugin(
+            RecordingPlugin {
+                notifications: events.clone(),
+                ..RecordingPlugin::default()
+            },
+            OverflowPolicy::Block,
+        );
+
+        let plugin_manager = Arc::new(ArcSwap::from_pointee(GeyserPluginManager {
+            plugins: vec![plugin.clone()],
+        }));
+        let notifier = Arc::new(AccountsUpdateNotifierImpl::new(plugin_manager, false));
+
+        // Each producer notifies its own slot, like replay of parallel forks
+        let producers: Vec<_> = (0..4)
+            .map(|slot| {
+                let notifier = notifier.clone();
+                thread::spawn(move || {
+                    let account = AccountSharedData::new(1, 0, &Pubkey::new_unique());
+                    let pubkey = Pubkey::new_unique();
+                    for write_version in 0..100 {
+                        notifier.notify_account_update(
+                            slot,
+                            &account,
+                            &None,
+                            &pubkey,
+                            write_version,
+                        );
+                    }
+                })
+            })
+            .collect();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        wait_for_delivered(&plugin, 400);
+        let events = events.lock().unwrap();
+        for slot in 0..4 {
+            let write_versions: Vec<_> = events
+                .iter()
+                .filter_map(|event| match event {
+                    Notification::Account {
+                        slot: event_slot,
+                        write_version,
+                        ..
+                    } if *event_slot == slot => Some(*write_version),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(write_versions, (0..100).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_dispatch_drop_oldest_reports_gap() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let gate = Arc::new(Mutex::new(()));
+        let plugin = start_plugin(
+            RecordingPlugin {
+                notifications: events.clone(),
+                gate: gate.clone(),
+                ..RecordingPlugin::default()
+            },
+            OverflowPolicy::DropOldest,
+        );
+        let plugin_manager = Arc::new(ArcSwap::from_pointee(GeyserPluginManager {
+            plugins: vec![plugin.clone()],
+        }));
+        let notifier = SlotStatusNotifierImpl::new(plugin_manager);
+
+        // Stall the worker on slot 0, then overflow the queue of 4 with slots 1..=9
+        let stalled = gate.lock().unwrap();
+        notifier.notify_slot_status(0, None, SlotStatus::Processed);
+        wait_for_stats(&plugin, |stats| stats.queued == 0);
+        for slot in 1..10 {
+            notifier.notify_slot_status(slot, None, SlotStatus::Processed);
+        }
+        drop(stalled);
+
+        let stats = wait_for_delivered(&plugin, 5);
+        assert_eq!(stats.dropped, 5);
+        assert_eq!(stats.gaps, 1);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                processed(0),
+                Notification::Gap(NotificationGap {
+                    dropped: 5,
+                    slots: Some(1..=5),
+                    account_updates_only: false,
+                }),
+                processed(6),
+                processed(7),
+                processed(8),
+                processed(9),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dispatch_disconnect() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let gate = Arc::new(Mutex::new(()));
+        let plugin = start_plugin(
+            RecordingPlugin {
+                notifications: events.clone(),
+                gate: gate.clone(),
+                ..RecordingPlugin::default()
+            },
+            OverflowPolicy::Disconnect,
+        );
+        let plugin_manager = Arc::new(ArcSwap::from_pointee(GeyserPluginManager {
+            plugins: vec![plugin.clone()],
+        }));
+        let notifier = SlotStatusNotifierImpl::new(plugin_manager);
+
+        let stalled = gate.lock().unwrap();
+        notifier.notify_slot_status(0, None, SlotStatus::Processed);
+        wait_for_stats(&plugin, |stats| stats.queued == 0);
+        for slot in 1..10 {
+            notifier.notify_slot_status(slot, None, SlotStatus::Processed);
+        }
+        drop(stalled);
+
+        // Slot 0 was being delivered, everything queued afterwards is discarded
+        let stats = wait_for_stats(&plugin, |stats| stats.dropped == 9);
+        assert!(stats.disconnected);
+        assert_eq!(stats.queued, 0);
+        assert_eq!(stats.delivered, 1);
+        assert_eq!(*events.lock().unwrap(), vec![processed(0)]);
+    }
+
+    #[test]
+    fn test_dispatcher_stops_on_unload() {
+        let plugin = start_plugin(RecordingPlugin::default(), OverflowPolicy::Block);
+        GeyserPluginManager::unload_plugin_blocking(plugin, 0);
+    }
+}
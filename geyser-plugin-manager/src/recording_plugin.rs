@@ -0,0 +1,273 @@
+//! A plugin recording the notifications it receives, for tests of the
+//! notifiers and of plugin delivery.
+
+use {
+    agave_geyser_plugin_interface::geyser_plugin_interface::{
+        ConsensusCertificateType, ConsensusVoteType, GeyserPlugin, GeyserPluginFilter,
+        NotificationGap, ReplicaAccountInfoVersions, ReplicaAccountRestreamInfoVersions,
+        ReplicaBlockInfoVersions, ReplicaConsensusCertificateInfoVersions,
+        ReplicaConsensusVoteInfoVersions, ReplicaTransactionInfoVersions, Result, SlotStatus,
+    },
+    solana_clock::Slot,
+    solana_hash::Hash,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::sync::{Arc, Mutex},
+};
+
+/// A notification received by a [`RecordingPlugin`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Notification {
+    Account {
+        slot: Slot,
+        pubkey: Pubkey,
+        lamports: u64,
+        is_startup: bool,
+        write_version: u64,
+        /// Only set for the accounts given as `ReplicaAccountInfoV4`
+        write_sequence: Option<u64>,
+    },
+    RestreamStart {
+        id: u64,
+        slot: Slot,
+        owners: Vec<[u8; 32]>,
+    },
+    RestreamEnd {
+        id: u64,
+        accounts: u64,
+        is_complete: bool,
+    },
+    SlotStatus {
+        slot: Slot,
+        parent: Option<Slot>,
+        status: SlotStatus,
+    },
+    SlotAbandoned {
+        slot: Slot,
+        accounts: Vec<[u8; 32]>,
+    },
+    Transaction {
+        slot: Slot,
+        signature: Signature,
+        fee: u64,
+    },
+    Block {
+        slot: Slot,
+    },
+    ConsensusVote {
+        slot: Slot,
+        vote_type: ConsensusVoteType,
+        block_id: Option<Hash>,
+        vote_account: Vec<u8>,
+        stake: u64,
+        total_stake: u64,
+    },
+    ConsensusCertificate {
+        slot: Slot,
+        certificate_type: ConsensusCertificateType,
+        block_id: Option<Hash>,
+        bitmap: Vec<u8>,
+        is_generated: bool,
+    },
+    BlockFinalized {
+        slot: Slot,
+        block_id: Hash,
+        is_fast: bool,
+    },
+    Gap(NotificationGap),
+    CatchUpComplete {
+        last_slot: Option<Slot>,
+    },
+}
+
+/// Records every notification into `notifications`. Account data
+/// notifications are always enabled, the other optional notifications are
+/// enabled by the fields of the same name.
+#[derive(Debug, Default)]
+pub struct RecordingPlugin {
+    pub notifications: Arc<Mutex<Vec<Notification>>>,
+    /// Held by tests to stall the notifications
+    pub gate: Arc<Mutex<()>>,
+    pub filter: Option<GeyserPluginFilter>,
+    pub catch_up_from_slot: Option<Slot>,
+    pub account_rollback: bool,
+    pub transactions: bool,
+    pub consensus: bool,
+}
+
+impl RecordingPlugin {
+    fn record(&self, notification: Notification) -> Result<()> {
+        let _gate = self.gate.lock().unwrap();
+        self.notifications.lock().unwrap().push(notification);
+        Ok(())
+    }
+}
+
+impl GeyserPlugin for RecordingPlugin {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    fn update_account(
+        &self,
+        account: ReplicaAccountInfoVersions,
+        slot: Slot,
+        is_startup: bool,
+    ) -> Result<()> {
+        let (pubkey, lamports, write_version, write_sequence) = match account {
+            ReplicaAccountInfoVersions::V0_0_3(account) => (
+                account.pubkey,
+                account.lamports,
+                account.write_version,
+                None,
+            ),
+            ReplicaAccountInfoVersions::V0_0_4(account) => (
+                account.pubkey,
+                account.lamports,
+                account.write_version,
+                Some(account.write_sequence),
+            ),
+            _ => unreachable!(),
+        };
+        self.record(Notification::Account {
+            slot,
+            pubkey: Pubkey::try_from(pubkey).unwrap(),
+            lamports,
+            is_startup,
+            write_version,
+            write_sequence,
+        })
+    }
+
+    fn notify_account_restream_start(
+        &self,
+        restream: ReplicaAccountRestreamInfoVersions,
+    ) -> Result<()> {
+        let ReplicaAccountRestreamInfoVersions::V0_0_1(restream) = restream;
+        self.record(Notification::RestreamStart {
+            id: restream.id,
+            slot: restream.slot,
+            owners: restream.owners.to_vec(),
+        })
+    }
+
+    fn notify_account_restream_end(
+        &self,
+        restream: ReplicaAccountRestreamInfoVersions,
+        accounts: u64,
+        is_complete: bool,
+    ) -> Result<()> {
+        let ReplicaAccountRestreamInfoVersions::V0_0_1(restream) = restream;
+        self.record(Notification::RestreamEnd {
+            id: restream.id,
+            accounts,
+            is_complete,
+        })
+    }
+
+    fn update_slot_status(
+        &self,
+        slot: Slot,
+        parent: Option<Slot>,
+        status: &SlotStatus,
+    ) -> Result<()> {
+        self.record(Notification::SlotStatus {
+            slot,
+            parent,
+            status: status.clone(),
+        })
+    }
+
+    fn notify_slot_abandoned(&self, slot: Slot, accounts: &[[u8; 32]]) -> Result<()> {
+        self.record(Notification::SlotAbandoned {
+            slot,
+            accounts: accounts.to_vec(),
+        })
+    }
+
+    fn notify_transaction(
+        &self,
+        transaction: ReplicaTransactionInfoVersions,
+        slot: Slot,
+    ) -> Result<()> {
+        let ReplicaTransactionInfoVersions::V0_0_3(transaction) = transaction else {
+            unreachable!();
+        };
+        self.record(Notification::Transaction {
+            slot,
+            signature: *transaction.signature,
+            fee: transaction.transaction_status_meta.fee,
+        })
+    }
+
+    fn notify_block_metadata(&self, blockinfo: ReplicaBlockInfoVersions) -> Result<()> {
+        let ReplicaBlockInfoVersions::V0_0_4(blockinfo) = blockinfo else {
+            unreachable!();
+        };
+        self.record(Notification::Block {
+            slot: blockinfo.slot,
+        })
+    }
+
+    fn notify_consensus_vote(&self, vote: ReplicaConsensusVoteInfoVersions) -> Result<()> {
+        let ReplicaConsensusVoteInfoVersions::V0_0_1(vote) = vote;
+        self.record(Notification::ConsensusVote {
+            slot: vote.slot,
+            vote_type: vote.vote_type,
+            block_id: vote.block_id.copied(),
+            vote_account: vote.vote_account.to_vec(),
+            stake: vote.stake,
+            total_stake: vote.total_stake,
+        })
+    }
+
+    fn notify_consensus_certificate(
+        &self,
+        certificate: ReplicaConsensusCertificateInfoVersions,
+    ) -> Result<()> {
+        let ReplicaConsensusCertificateInfoVersions::V0_0_1(certificate) = certificate;
+        self.record(Notification::ConsensusCertificate {
+            slot: certificate.slot,
+            certificate_type: certificate.certificate_type,
+            block_id: certificate.block_id.copied(),
+            bitmap: certificate.bitmap.to_vec(),
+            is_generated: certificate.is_generated,
+        })
+    }
+
+    fn notify_block_finalized(&self, slot: Slot, block_id: &Hash, is_fast: bool) -> Result<()> {
+        self.record(Notification::BlockFinalized {
+            slot,
+            block_id: *block_id,
+            is_fast,
+        })
+    }
+
+    fn notification_filter(&self) -> Option<GeyserPluginFilter> {
+        self.filter.clone()
+    }
+
+    fn notify_gap(&self, gap: &NotificationGap) -> Result<()> {
+        self.record(Notification::Gap(gap.clone()))
+    }
+
+    fn catch_up_from_slot(&self) -> Option<Slot> {
+        self.catch_up_from_slot
+    }
+
+    fn notify_catch_up_complete(&self, last_slot: Option<Slot>) -> Result<()> {
+        self.record(Notification::CatchUpComplete { last_slot })
+    }
+
+    fn account_rollback_notifications_enabled(&self) -> bool {
+        self.account_rollback
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        self.transactions
+    }
+
+    fn consensus_notifications_enabled(&self) -> bool {
+        self.consensus
+    }
+}
@@ -1,12 +1,19 @@
 use {
-    crate::geyser_plugin_manager::GeyserPluginManager,
-    agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus, arc_swap::ArcSwap, log::*,
-    solana_clock::Slot, solana_rpc::slot_status_notifier::SlotStatusNotifierInterface,
+    crate::{
+        account_write_tracker::{AbandonedSlot, AccountWriteTracker},
+        geyser_plugin_manager::{GeyserPluginManager, LoadedGeyserPlugin},
+    },
+    agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+    arc_swap::ArcSwap,
+    log::*,
+    solana_clock::Slot,
+    solana_rpc::slot_status_notifier::SlotStatusNotifierInterface,
     std::sync::Arc,
 };
 
 pub struct SlotStatusNotifierImpl {
     plugin_manager: Arc<ArcSwap<GeyserPluginManager>>,
+    account_writes: Arc<AccountWriteTracker>,
 }
 
 impl SlotStatusNotifierInterface for SlotStatusNotifierImpl {
@@ -41,7 +48,19 @@ impl SlotStatusNotifierInterface for SlotStatusNotifierImpl {
 
 impl SlotStatusNotifierImpl {
     pub fn new(plugin_manager: Arc<ArcSwap<GeyserPluginManager>>) -> Self {
-        Self { plugin_manager }
+        Self {
+            plugin_manager,
+            account_writes: Arc::default(),
+        }
+    }
+
+    /// Reports the abandoned slots among the accounts written in unrooted
+    /// slots, which are shared with the accounts update notifier
+    pub(crate) fn with_account_writes(self, account_writes: Arc<AccountWriteTracker>) -> Self {
+        Self {
+            account_writes,
+            ..self
+        }
     }
 
     pub fn notify_slot_status(&self, slot: Slot, parent: Option<Slot>, slot_status: SlotStatus) {
@@ -51,23 +70,84 @@ impl SlotStatusNotifierImpl {
         }
 
         for plugin in plugin_manager.plugins.iter() {
-            match plugin.update_slot_status(slot, parent, &slot_status) {
-                Err(err) => {
-                    error!(
-                        "Failed to update slot status at slot {}, error: {} to plugin {}",
-                        slot,
-                        err,
-                        plugin.name()
-                    )
+            match plugin.dispatcher() {
+                None => Self::notify_plugin(plugin, slot, parent, &slot_status),
+                Some(dispatcher) => {
+                    let slot_status = slot_status.clone();
+                    dispatcher.dispatch(Some(slot), move |plugin| {
+                        Self::notify_plugin(plugin, slot, parent, &slot_status)
+                    });
+                }
+            }
+        }
+
+        if !plugin_manager.account_rollback_notifications_enabled() {
+            return;
+        }
+        for abandoned in self
+            .account_writes
+            .update_slot_status(slot, parent, &slot_status)
+        {
+            let abandoned = Arc::new(abandoned);
+            for plugin in plugin_manager.plugins.iter() {
+                if !plugin.account_rollback_notifications_enabled() {
+                    continue;
                 }
-                Ok(_) => {
-                    trace!(
-                        "Successfully updated slot status at slot {} to plugin {}",
-                        slot,
-                        plugin.name()
-                    );
+                match plugin.dispatcher() {
+                    None => Self::notify_slot_abandoned(plugin, &abandoned),
+                    Some(dispatcher) => {
+                        let abandoned = abandoned.clone();
+                        dispatcher.dispatch(Some(abandoned.slot), move |plugin| {
+                            Self::notify_slot_abandoned(plugin, &abandoned)
+                        });
+                    }
                 }
             }
         }
     }
+
+    fn notify_slot_abandoned(plugin: &LoadedGeyserPlugin, abandoned: &AbandonedSlot) {
+        match plugin.notify_slot_abandoned(abandoned.slot, &abandoned.accounts) {
+            Err(err) => {
+                error!(
+                    "Failed to notify abandoned slot {}, error: {} to plugin {}",
+                    abandoned.slot,
+                    err,
+                    plugin.name()
+                )
+            }
+            Ok(_) => {
+                trace!(
+                    "Successfully notified abandoned slot {} to plugin {}",
+                    abandoned.slot,
+                    plugin.name()
+                );
+            }
+        }
+    }
+
+    pub(crate) fn notify_plugin(
+        plugin: &LoadedGeyserPlugin,
+        slot: Slot,
+        parent: Option<Slot>,
+        slot_status: &SlotStatus,
+    ) {
+        match plugin.update_slot_status(slot, parent, slot_status) {
+            Err(err) => {
+                error!(
+                    "Failed to update slot status at slot {}, error: {} to plugin {}",
+                    slot,
+                    err,
+                    plugin.name()
+                )
+            }
+            Ok(_) => {
+                trace!(
+                    "Successfully updated slot status at slot {} to plugin {}",
+                    slot,
+                    plugin.name()
+                );
+            }
+        }
+    }
 }
@@ -1,6 +1,6 @@
 /// Module responsible for notifying plugins of transactions
 use {
-    crate::geyser_plugin_manager::GeyserPluginManager,
+    crate::geyser_plugin_manager::{GeyserPluginManager, LoadedGeyserPlugin},
     agave_geyser_plugin_interface::geyser_plugin_interface::{
         ReplicaTransactionInfoV3, ReplicaTransactionInfoVersions,
     },
@@ -23,6 +23,15 @@ pub(crate) struct TransactionNotifierImpl {
     plugin_manager: Arc<ArcSwap<GeyserPluginManager>>,
 }
 
+/// An owned copy of the data referenced by a `ReplicaTransactionInfoV3`, for
+/// plugins with a dispatch queue
+struct OwnedTransaction {
+    signature: Signature,
+    message_hash: Hash,
+    transaction_status_meta: TransactionStatusMeta,
+    transaction: VersionedTransaction,
+}
+
 impl TransactionNotifier for TransactionNotifierImpl {
     fn notify_transaction(
         &self,
@@ -49,26 +58,42 @@ impl TransactionNotifier for TransactionNotifierImpl {
             return;
         }
 
+        // Copied once and shared by every plugin with a dispatch queue
+        let mut owned_transaction = None;
         for plugin in plugin_manager.plugins.iter() {
-            if !plugin.transaction_notifications_enabled() {
+            if !plugin.transaction_notifications_enabled()
+                || !plugin.notification_filter().matches_transaction(
+                    is_vote,
+                    transaction,
+                    transaction_status_meta,
+                )
+            {
                 continue;
             }
-            match plugin.notify_transaction(
-                ReplicaTransactionInfoVersions::V0_0_3(&transaction_log_info),
-                slot,
-            ) {
-                Err(err) => {
-                    error!(
-                        "Failed to notify transaction, error: ({}) to plugin {}",
-                        err,
-                        plugin.name()
-                    )
-                }
-                Ok(_) => {
-                    trace!(
-                        "Successfully notified transaction to plugin {}",
-                        plugin.name()
-                    );
+            match plugin.dispatcher() {
+                None => Self::notify_plugin(plugin, &transaction_log_info, slot),
+                Some(dispatcher) => {
+                    let owned_transaction: Arc<OwnedTransaction> = owned_transaction
+                        .get_or_insert_with(|| {
+                            Arc::new(OwnedTransaction {
+                                signature: *signature,
+                                message_hash: *message_hash,
+                                transaction_status_meta: transaction_status_meta.clone(),
+                                transaction: transaction.clone(),
+                            })
+                        })
+                        .clone();
+                    dispatcher.dispatch(Some(slot), move |plugin| {
+                        let transaction_log_info = Self::build_replica_transaction_info(
+                            index,
+                            &owned_transaction.signature,
+                            &owned_transaction.message_hash,
+                            is_vote,
+                            &owned_transaction.transaction_status_meta,
+                            &owned_transaction.transaction,
+                        );
+                        Self::notify_plugin(plugin, &transaction_log_info, slot)
+                    });
                 }
             }
         }
@@ -80,7 +105,32 @@ impl TransactionNotifierImpl {
         Self { plugin_manager }
     }
 
-    fn build_replica_transaction_info<'a>(
+    pub(crate) fn notify_plugin(
+        plugin: &LoadedGeyserPlugin,
+        transaction_log_info: &ReplicaTransactionInfoV3,
+        slot: Slot,
+    ) {
+        match plugin.notify_transaction(
+            ReplicaTransactionInfoVersions::V0_0_3(transaction_log_info),
+            slot,
+        ) {
+            Err(err) => {
+                error!(
+                    "Failed to notify transaction, error: ({}) to plugin {}",
+                    err,
+                    plugin.name()
+                )
+            }
+            Ok(_) => {
+                trace!(
+                    "Successfully notified transaction to plugin {}",
+                    plugin.name()
+                );
+            }
+        }
+    }
+
+    pub(crate) fn build_replica_transaction_info<'a>(
         index: usize,
         signature: &'a Signature,
         message_hash: &'a Hash,
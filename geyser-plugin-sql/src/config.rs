@@ -0,0 +1,261 @@
+//! The `sql` section of the plugin config.
+//!
+//! ```json5
+//! {
+//!     libpath: "libagave_geyser_plugin_sql.so",
+//!     sql: {
+//!         // Relative paths are relative to the directory of the config file
+//!         database: { sqlite: { path: "geyser.db" } },
+//!         // database: { postgres: { url: "host=localhost user=geyser" } },
+//!         batch_size: 1000,
+//!         flush_interval_ms: 100,
+//!         queue_size: 100000,
+//!         // Write accounts owned by these programs, or with these pubkeys.
+//!         // Every account is written when both lists are empty, and none
+//!         // when the section is missing.
+//!         accounts: {
+//!             owners: ["TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"],
+//!             pubkeys: [],
+//!             startup: false,
+//!         },
+//!         // Transactions are not written when the section is missing
+//!         transactions: { votes: false },
+//!         blocks: true,
+//!     },
+//! }
+//! ```
+
+use {
+    crate::SqlPluginError,
+    serde::Deserialize,
+    solana_pubkey::Pubkey,
+    std::{
+        fs,
+        path::{Path, PathBuf},
+        str::FromStr,
+        time::Duration,
+    },
+};
+
+const DEFAULT_BATCH_SIZE: usize = 1000;
+const DEFAULT_FLUSH_INTERVAL_MS: u64 = 100;
+const DEFAULT_QUEUE_SIZE: usize = 100_000;
+
+#[derive(Debug, Deserialize)]
+struct PluginConfig {
+    sql: SqlConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SqlConfig {
+    pub database: Database,
+
+    /// Largest number of rows committed in a single database transaction
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+
+    /// Longest time rows are kept before being committed
+    #[serde(default = "default_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+
+    /// Number of rows queued to the writer thread before notifications block
+    #[serde(default = "default_queue_size")]
+    pub queue_size: usize,
+
+    /// Accounts to write, none if `None`
+    #[serde(default)]
+    pub accounts: Option<AccountsConfig>,
+
+    /// Transactions to write, none if `None`
+    #[serde(default)]
+    pub transactions: Option<TransactionsConfig>,
+
+    /// Whether to write block metadata
+    #[serde(default = "default_true")]
+    pub blocks: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub enum Database {
+    Sqlite { path: PathBuf },
+    Postgres { url: String },
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct AccountsConfig {
+    /// Base58 pubkeys of the programs owning the accounts to write
+    #[serde(default)]
+    pub owners: Vec<String>,
+
+    /// Base58 pubkeys of the accounts to write regardless of their owner
+    #[serde(default)]
+    pub pubkeys: Vec<String>,
+
+    /// Whether to write the accounts restored from the snapshot at startup
+    #[serde(default)]
+    pub startup: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TransactionsConfig {
+    /// Whether to write vote transactions
+    #[serde(default)]
+    pub votes: bool,
+}
+
+fn default_batch_size() -> usize {
+    DEFAULT_BATCH_SIZE
+}
+
+fn default_flush_interval_ms() -> u64 {
+    DEFAULT_FLUSH_INTERVAL_MS
+}
+
+fn default_queue_size() -> usize {
+    DEFAULT_QUEUE_SIZE
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl SqlConfig {
+    /// Reads the `sql` section of `config_file`. A relative SQLite path is
+    /// resolved against the directory of `config_file`.
+    pub fn load(config_file: &Path) -> Result<Self, SqlPluginError> {
+        let contents = fs::read_to_string(config_file).map_err(|err| {
+            SqlPluginError::Config(format!("Failed to read {config_file:?}: {err}"))
+        })?;
+        let mut config = Self::parse(&contents)
+            .map_err(|err| SqlPluginError::Config(format!("{config_file:?}: {err}")))?;
+        if let Database::Sqlite { path } = &mut config.database {
+            if path.is_relative() {
+                if let Some(config_dir) = config_file.parent() {
+                    *path = config_dir.join(&*path);
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let PluginConfig { sql: config } =
+            json5::from_str(contents).map_err(|err| err.to_string())?;
+        if config.batch_size == 0 {
+            return Err("sql.batch_size must be positive".to_string());
+        }
+        if config.queue_size == 0 {
+            return Err("sql.queue_size must be positive".to_string());
+        }
+        if let Some(accounts) = &config.accounts {
+            accounts.owners()?;
+            accounts.pubkeys()?;
+        }
+        Ok(config)
+    }
+
+    pub fn flush_interval(&self) -> Duration {
+        Duration::from_millis(self.flush_interval_ms)
+    }
+}
+
+impl AccountsConfig {
+    pub fn owners(&self) -> Result<Vec<Pubkey>, String> {
+        parse_pubkeys("sql.accounts.owners", &self.owners)
+    }
+
+    pub fn pubkeys(&self) -> Result<Vec<Pubkey>, String> {
+        parse_pubkeys("sql.accounts.pubkeys", &self.pubkeys)
+    }
+}
+
+fn parse_pubkeys(field: &str, pubkeys: &[String]) -> Result<Vec<Pubkey>, String> {
+    pubkeys
+        .iter()
+        .map(|pubkey| {
+            Pubkey::from_str(pubkey)
+                .map_err(|err| format!("{field}: invalid pubkey {pubkey}: {err}"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::io::Write};
+
+    #[test]
+    fn test_parse_defaults() {
+        let config = SqlConfig::parse(
+            r#"{
+                libpath: "libagave_geyser_plugin_sql.so",
+                sql: { database: { postgres: { url: "host=localhost" } } },
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            SqlConfig {
+                database: Database::Postgres {
+                    url: "host=localhost".to_string()
+                },
+                batch_size: DEFAULT_BATCH_SIZE,
+                flush_interval_ms: DEFAULT_FLUSH_INTERVAL_MS,
+                queue_size: DEFAULT_QUEUE_SIZE,
+                accounts: None,
+                transactions: None,
+                blocks: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for contents in [
+            r#"{ libpath: "x" }"#,
+            r#"{ sql: { database: { mysql: { url: "x" } } } }"#,
+            r#"{ sql: { database: { sqlite: { path: "x" } }, batch_size: 0 } }"#,
+            r#"{ sql: { database: { sqlite: { path: "x" } }, unknown: 1 } }"#,
+            r#"{ sql: { database: { sqlite: { path: "x" } }, accounts: { owners: ["bad"] } } }"#,
+        ] {
+            assert!(SqlConfig::parse(contents).is_err(), "{contents}");
+        }
+    }
+
+    #[test]
+    fn test_load_relative_sqlite_path() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let config_file = config_dir.path().join("plugin.json");
+        let owner = Pubkey::new_unique();
+        write!(
+            fs::File::create(&config_file).unwrap(),
+            r#"{{
+                sql: {{
+                    database: {{ sqlite: {{ path: "geyser.db" }} }},
+                    accounts: {{ owners: ["{owner}"] }},
+                    transactions: {{}},
+                }},
+            }}"#
+        )
+        .unwrap();
+
+        let config = SqlConfig::load(&config_file).unwrap();
+        assert_eq!(
+            config.database,
+            Database::Sqlite {
+                path: config_dir.path().join("geyser.db")
+            }
+        );
+        let accounts = config.accounts.unwrap();
+        assert_eq!(accounts.owners().unwrap(), vec![owner]);
+        assert!(accounts.pubkeys().unwrap().is_empty());
+        assert!(!accounts.startup);
+        assert_eq!(
+            config.transactions,
+            Some(TransactionsConfig { votes: false })
+        );
+    }
+}
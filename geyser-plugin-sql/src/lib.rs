@@ -0,0 +1,81 @@
+#![cfg(feature = "agave-unstable-api")]
+//! A reference Geyser plugin writing accounts, transactions, blocks and slot
+//! statuses to a SQLite or PostgreSQL database.
+//!
+//! The plugin is configured by the `sql` section of the plugin config, see
+//! [`config::SqlConfig`]. The database schema is created and upgraded by the
+//! versioned migrations of [`migrations::MIGRATIONS`] when the plugin is
+//! loaded.
+//!
+//! Notifications are turned into rows and queued to a writer thread, which
+//! commits them in batches of up to `batch_size` rows, or every
+//! `flush_interval_ms` milliseconds, each batch in a single database
+//! transaction. A batch that fails to be written is retried with backoff, and
+//! if it still fails the writer stops and every later notification returns an
+//! error, rather than losing rows silently. The plugin blocks the notifying
+//! validator thread while the queue is full; validators that should not be
+//! slowed down by the database can give the plugin a bounded `dispatch` queue
+//! in the plugin config.
+//!
+//! The SQLite backend is enabled by the default `sqlite` feature, and the
+//! PostgreSQL backend by the `postgres` feature.
+
+use {
+    crate::plugin::SqlGeyserPlugin,
+    agave_geyser_plugin_interface::geyser_plugin_interface::{GeyserPlugin, GeyserPluginError},
+    thiserror::Error,
+};
+
+pub mod config;
+pub mod migrations;
+pub mod plugin;
+mod store;
+mod writer;
+
+#[derive(Debug, Error)]
+pub enum SqlPluginError {
+    #[error("{0}")]
+    Config(String),
+
+    #[error("the {0} backend is not enabled in this build of the plugin")]
+    BackendNotEnabled(&'static str),
+
+    #[error(
+        "the database schema version {database} is newer than the latest version {supported} \
+         known to the plugin"
+    )]
+    SchemaTooNew { database: u32, supported: u32 },
+
+    #[error("the database writer thread exited")]
+    WriterExited,
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[cfg(feature = "postgres")]
+    #[error("PostgreSQL error: {0}")]
+    Postgres(#[from] postgres::Error),
+}
+
+impl From<SqlPluginError> for GeyserPluginError {
+    fn from(err: SqlPluginError) -> Self {
+        match err {
+            SqlPluginError::Config(msg) => GeyserPluginError::ConfigFileReadError { msg },
+            err => GeyserPluginError::Custom(Box::new(err)),
+        }
+    }
+}
+
+/// Entry point called by the validator when loading the plugin library
+///
+/// # Safety
+///
+/// The returned pointer must be released by the validator, by turning it back
+/// into a `Box` with `Box::from_raw`.
+#[unsafe(no_mangle)]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn _create_plugin() -> *mut dyn GeyserPlugin {
+    let plugin: Box<dyn GeyserPlugin> = Box::new(SqlGeyserPlugin::default());
+    Box::into_raw(plugin)
+}
@@ -0,0 +1,194 @@
+//! Versioned migrations of the database schema.
+//!
+//! The versions applied to a database are recorded in its `schema_migrations`
+//! table. When the plugin is loaded, the migrations the database has not seen
+//! yet are applied in order, each in its own database transaction. New
+//! migrations are appended to [`MIGRATIONS`]; released migrations must never
+//! be edited.
+//!
+//! Unsigned integers are stored in signed 64-bit columns, so values above
+//! `i64::MAX`, such as the `rent_epoch` of rent exempt accounts, read back as
+//! negative numbers.
+
+use {
+    crate::{SqlPluginError, store::Store},
+    log::*,
+};
+
+/// A change of the database schema, written for each backend
+#[derive(Debug)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub sqlite: &'static str,
+    pub postgres: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create the accounts, transactions, blocks and slots tables",
+        sqlite: "
+            CREATE TABLE accounts (
+                pubkey BLOB PRIMARY KEY,
+                owner BLOB NOT NULL,
+                lamports INTEGER NOT NULL,
+                executable INTEGER NOT NULL,
+                rent_epoch INTEGER NOT NULL,
+                data BLOB NOT NULL,
+                slot INTEGER NOT NULL,
+                write_version INTEGER NOT NULL
+            );
+            CREATE TABLE transactions (
+                slot INTEGER NOT NULL,
+                signature BLOB NOT NULL,
+                idx INTEGER NOT NULL,
+                is_vote INTEGER NOT NULL,
+                fee INTEGER NOT NULL,
+                error TEXT,
+                PRIMARY KEY (slot, signature)
+            );
+            CREATE TABLE blocks (
+                slot INTEGER PRIMARY KEY,
+                parent_slot INTEGER NOT NULL,
+                blockhash TEXT NOT NULL,
+                parent_blockhash TEXT NOT NULL,
+                block_time INTEGER,
+                block_height INTEGER,
+                executed_transaction_count INTEGER NOT NULL,
+                entry_count INTEGER NOT NULL
+            );
+            CREATE TABLE slots (
+                slot INTEGER PRIMARY KEY,
+                parent INTEGER,
+                status TEXT NOT NULL,
+                dead_error TEXT
+            );
+        ",
+        postgres: "
+            CREATE TABLE accounts (
+                pubkey BYTEA PRIMARY KEY,
+                owner BYTEA NOT NULL,
+                lamports BIGINT NOT NULL,
+                executable BOOLEAN NOT NULL,
+                rent_epoch BIGINT NOT NULL,
+                data BYTEA NOT NULL,
+                slot BIGINT NOT NULL,
+                write_version BIGINT NOT NULL
+            );
+            CREATE TABLE transactions (
+                slot BIGINT NOT NULL,
+                signature BYTEA NOT NULL,
+                idx BIGINT NOT NULL,
+                is_vote BOOLEAN NOT NULL,
+                fee BIGINT NOT NULL,
+                error TEXT,
+                PRIMARY KEY (slot, signature)
+            );
+            CREATE TABLE blocks (
+                slot BIGINT PRIMARY KEY,
+                parent_slot BIGINT NOT NULL,
+                blockhash TEXT NOT NULL,
+                parent_blockhash TEXT NOT NULL,
+                block_time BIGINT,
+                block_height BIGINT,
+                executed_transaction_count BIGINT NOT NULL,
+                entry_count BIGINT NOT NULL
+            );
+            CREATE TABLE slots (
+                slot BIGINT PRIMARY KEY,
+                parent BIGINT,
+                status TEXT NOT NULL,
+                dead_error TEXT
+            );
+        ",
+    },
+    Migration {
+        version: 2,
+        description: "index accounts by owner and transactions by signature",
+        sqlite: "
+            CREATE INDEX accounts_owner ON accounts (owner);
+            CREATE INDEX transactions_signature ON transactions (signature);
+        ",
+        postgres: "
+            CREATE INDEX accounts_owner ON accounts (owner);
+            CREATE INDEX transactions_signature ON transactions (signature);
+        ",
+    },
+];
+
+/// Version of the schema created by [`MIGRATIONS`]
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Applies the migrations `store` has not seen yet and returns the schema
+/// version of `store`
+pub(crate) fn run(store: &mut dyn Store) -> Result<u32, SqlPluginError> {
+    let current = store.schema_version()?;
+    let latest = latest_version();
+    if current > latest {
+        return Err(SqlPluginError::SchemaTooNew {
+            database: current,
+            supported: latest,
+        });
+    }
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > current)
+    {
+        info!(
+            "Applying Geyser SQL plugin migration {}: {}",
+            migration.version, migration.description
+        );
+        store.apply_migration(migration)?;
+    }
+    Ok(latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migration_versions_increase() {
+        assert!(
+            MIGRATIONS
+                .iter()
+                .zip(1..)
+                .all(|(migration, version)| migration.version == version)
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_run_sqlite() {
+        use crate::store::sqlite::SqliteStore;
+
+        let db_dir = tempfile::tempdir().unwrap();
+        let path = db_dir.path().join("geyser.db");
+        let mut store = SqliteStore::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), 0);
+        assert_eq!(run(&mut store).unwrap(), latest_version());
+        assert_eq!(store.schema_version().unwrap(), latest_version());
+
+        // Reopening applies nothing
+        let mut store = SqliteStore::open(&path).unwrap();
+        assert_eq!(run(&mut store).unwrap(), latest_version());
+
+        // A database migrated by a newer plugin is rejected
+        store
+            .apply_migration(&Migration {
+                version: latest_version() + 1,
+                description: "from the future",
+                sqlite: "CREATE TABLE future (id INTEGER);",
+                postgres: "",
+            })
+            .unwrap();
+        assert!(matches!(
+            run(&mut store),
+            Err(SqlPluginError::SchemaTooNew { database, supported })
+                if database == latest_version() + 1 && supported == latest_version()
+        ));
+    }
+}
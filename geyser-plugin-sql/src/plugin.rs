@@ -0,0 +1,314 @@
+use {
+    crate::{
+        config::SqlConfig,
+        migrations,
+        store::{self, AccountRow, BlockRow, Row, SlotStatusRow, TransactionRow},
+        writer::Writer,
+    },
+    agave_geyser_plugin_interface::geyser_plugin_interface::{
+        GeyserAccountFilter, GeyserPlugin, GeyserPluginError, GeyserPluginFilter,
+        GeyserTransactionFilter, ReplicaAccountInfoVersions, ReplicaBlockInfoVersions,
+        ReplicaTransactionInfoVersions, Result, SlotStatus,
+    },
+    log::*,
+    solana_clock::Slot,
+    std::{collections::HashSet, path::Path},
+};
+
+/// Writes the notifications selected by the `sql` section of the plugin
+/// config to a SQLite or PostgreSQL database
+#[derive(Debug, Default)]
+pub struct SqlGeyserPlugin {
+    writer: Option<Writer>,
+    accounts: Option<AccountSelector>,
+    transactions: Option<TransactionSelector>,
+    blocks: bool,
+}
+
+#[derive(Debug, Default)]
+struct AccountSelector {
+    owners: HashSet<[u8; 32]>,
+    pubkeys: HashSet<[u8; 32]>,
+    startup: bool,
+}
+
+impl AccountSelector {
+    /// The validator applies the notification filter of the plugin, except
+    /// when the plugin runs in a plugin host, so accounts are selected again
+    fn is_selected(&self, pubkey: &[u8], owner: &[u8]) -> bool {
+        (self.owners.is_empty() && self.pubkeys.is_empty())
+            || <[u8; 32]>::try_from(owner).is_ok_and(|owner| self.owners.contains(&owner))
+            || <[u8; 32]>::try_from(pubkey).is_ok_and(|pubkey| self.pubkeys.contains(&pubkey))
+    }
+}
+
+#[derive(Debug, Default)]
+struct TransactionSelector {
+    votes: bool,
+}
+
+impl SqlGeyserPlugin {
+    fn send(&self, row: Row) -> Result<()> {
+        match &self.writer {
+            Some(writer) => Ok(writer.send(row)?),
+            None => Ok(()),
+        }
+    }
+
+    fn account_row(account: ReplicaAccountInfoVersions, slot: Slot) -> Option<AccountRow> {
+        let (pubkey, owner, lamports, executable, rent_epoch, data, write_version) = match account {
+            ReplicaAccountInfoVersions::V0_0_3(account) => (
+                account.pubkey,
+                account.owner,
+                account.lamports,
+                account.executable,
+                account.rent_epoch,
+                account.data,
+                account.write_version,
+            ),
+            ReplicaAccountInfoVersions::V0_0_4(account) => (
+                account.pubkey,
+                account.owner,
+                account.lamports,
+                account.executable,
+                account.rent_epoch,
+                account.data,
+                account.write_version,
+            ),
+            _ => return None,
+        };
+        Some(AccountRow {
+            pubkey: pubkey.to_vec(),
+            owner: owner.to_vec(),
+            lamports: lamports as i64,
+            executable,
+            rent_epoch: rent_epoch as i64,
+            data: data.to_vec(),
+            slot: slot as i64,
+            write_version: write_version as i64,
+        })
+    }
+}
+
+impl GeyserPlugin for SqlGeyserPlugin {
+    fn name(&self) -> &'static str {
+        "GeyserPluginSql"
+    }
+
+    fn on_load(&mut self, config_file: &str, _is_reload: bool) -> Result<()> {
+        let config = SqlConfig::load(Path::new(config_file))?;
+        let mut store = store::open(&config.database)?;
+        let schema_version = migrations::run(store.as_mut())?;
+        info!("Geyser SQL plugin database is at schema version {schema_version}");
+
+        self.accounts = match &config.accounts {
+            Some(accounts) => {
+                let owners = accounts
+                    .owners()
+                    .map_err(|msg| GeyserPluginError::ConfigFileReadError { msg })?;
+                let pubkeys = accounts
+                    .pubkeys()
+                    .map_err(|msg| GeyserPluginError::ConfigFileReadError { msg })?;
+                Some(AccountSelector {
+                    owners: owners.iter().map(|owner| owner.to_bytes()).collect(),
+                    pubkeys: pubkeys.iter().map(|pubkey| pubkey.to_bytes()).collect(),
+                    startup: accounts.startup,
+                })
+            }
+            None => None,
+        };
+        self.transactions = config
+            .transactions
+            .as_ref()
+            .map(|transactions| TransactionSelector {
+                votes: transactions.votes,
+            });
+        self.blocks = config.blocks;
+        self.writer = Some(
+            Writer::spawn(
+                store,
+                config.queue_size,
+                config.batch_size,
+                config.flush_interval(),
+            )
+            .map_err(|err| GeyserPluginError::Custom(Box::new(err)))?,
+        );
+        Ok(())
+    }
+
+    fn on_unload(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            writer.join();
+        }
+    }
+
+    fn update_account(
+        &self,
+        account: ReplicaAccountInfoVersions,
+        slot: Slot,
+        is_startup: bool,
+    ) -> Result<()> {
+        let Some(accounts) = &self.accounts else {
+            return Ok(());
+        };
+        if is_startup && !accounts.startup {
+            return Ok(());
+        }
+        let Some(account) = Self::account_row(account, slot) else {
+            return Ok(());
+        };
+        if !accounts.is_selected(&account.pubkey, &account.owner) {
+            return Ok(());
+        }
+        self.send(Row::Account(account))
+    }
+
+    fn update_slot_status(
+        &self,
+        slot: Slot,
+        parent: Option<u64>,
+        status: &SlotStatus,
+    ) -> Result<()> {
+        let dead_error = match status {
+            SlotStatus::Dead(error) => Some(error.clone()),
+            _ => None,
+        };
+        self.send(Row::SlotStatus(SlotStatusRow {
+            slot: slot as i64,
+            parent: parent.map(|parent| parent as i64),
+            status: status.as_str(),
+            dead_error,
+        }))
+    }
+
+    fn notify_transaction(
+        &self,
+        transaction: ReplicaTransactionInfoVersions,
+        slot: Slot,
+    ) -> Result<()> {
+        let Some(transactions) = &self.transactions else {
+            return Ok(());
+        };
+        let ReplicaTransactionInfoVersions::V0_0_3(transaction) = transaction else {
+            return Ok(());
+        };
+        if transaction.is_vote && !transactions.votes {
+            return Ok(());
+        }
+        let meta = transaction.transaction_status_meta;
+        self.send(Row::Transaction(TransactionRow {
+            slot: slot as i64,
+            signature: transaction.signature.as_ref().to_vec(),
+            index: transaction.index as i64,
+            is_vote: transaction.is_vote,
+            fee: meta.fee as i64,
+            error: meta.status.as_ref().err().map(ToString::to_string),
+        }))
+    }
+
+    fn notify_block_metadata(&self, blockinfo: ReplicaBlockInfoVersions) -> Result<()> {
+        if !self.blocks {
+            return Ok(());
+        }
+        let ReplicaBlockInfoVersions::V0_0_4(block) = blockinfo else {
+            return Ok(());
+        };
+        self.send(Row::Block(BlockRow {
+            slot: block.slot as i64,
+            parent_slot: block.parent_slot as i64,
+            blockhash: block.blockhash.to_string(),
+            parent_blockhash: block.parent_blockhash.to_string(),
+            block_time: block.block_time,
+            block_height: block.block_height.map(|height| height as i64),
+            executed_transaction_count: block.executed_transaction_count as i64,
+            entry_count: block.entry_count as i64,
+        }))
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        self.accounts.is_some()
+    }
+
+    fn account_data_snapshot_notifications_enabled(&self) -> bool {
+        self.accounts
+            .as_ref()
+            .is_some_and(|accounts| accounts.startup)
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        self.transactions.is_some()
+    }
+
+    fn notification_filter(&self) -> Option<GeyserPluginFilter> {
+        let accounts = self
+            .accounts
+            .as_ref()
+            .filter(|accounts| !accounts.owners.is_empty() || !accounts.pubkeys.is_empty())
+            .map(|accounts| GeyserAccountFilter {
+                owners: accounts.owners.iter().copied().collect(),
+                pubkeys: accounts.pubkeys.iter().copied().collect(),
+                data_size_ranges: vec![],
+            });
+        let transactions = self
+            .transactions
+            .as_ref()
+            .filter(|transactions| !transactions.votes)
+            .map(|_| GeyserTransactionFilter {
+                vote: Some(false),
+                mentioned_accounts: vec![],
+            });
+        (accounts.is_some() || transactions.is_some()).then_some(GeyserPluginFilter {
+            accounts,
+            transactions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, agave_geyser_plugin_interface::geyser_plugin_interface::ReplicaAccountInfoV3};
+
+    #[test]
+    fn test_account_selector() {
+        let [owner, pubkey, other] = [[1; 32], [2; 32], [3; 32]];
+        let all = AccountSelector::default();
+        assert!(all.is_selected(&other, &other));
+
+        let selector = AccountSelector {
+            owners: HashSet::from([owner]),
+            pubkeys: HashSet::from([pubkey]),
+            startup: false,
+        };
+        assert!(selector.is_selected(&other, &owner));
+        assert!(selector.is_selected(&pubkey, &other));
+        assert!(!selector.is_selected(&other, &other));
+        assert!(!selector.is_selected(&owner, &pubkey));
+    }
+
+    #[test]
+    fn test_account_row() {
+        let account = ReplicaAccountInfoV3 {
+            pubkey: &[1; 32],
+            lamports: 42,
+            owner: &[2; 32],
+            executable: true,
+            rent_epoch: u64::MAX,
+            data: &[3; 4],
+            write_version: 7,
+            txn: None,
+        };
+        assert_eq!(
+            SqlGeyserPlugin::account_row(ReplicaAccountInfoVersions::V0_0_3(&account), 9),
+            Some(AccountRow {
+                pubkey: vec![1; 32],
+                owner: vec![2; 32],
+                lamports: 42,
+                executable: true,
+                rent_epoch: -1,
+                data: vec![3; 4],
+                slot: 9,
+                write_version: 7,
+            })
+        );
+    }
+}
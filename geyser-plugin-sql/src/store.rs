@@ -0,0 +1,96 @@
+//! The database backends, writing batches of rows built from notifications.
+
+use crate::{SqlPluginError, config::Database, migrations::Migration};
+
+#[cfg(feature = "postgres")]
+pub(crate) mod postgresql;
+#[cfg(feature = "sqlite")]
+pub(crate) mod sqlite;
+
+/// A row written by the writer thread. Unsigned integers are converted to
+/// the signed 64-bit integers the databases store.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Row {
+    Account(AccountRow),
+    Transaction(TransactionRow),
+    Block(BlockRow),
+    SlotStatus(SlotStatusRow),
+}
+
+/// Latest state of an account, replaced by updates at a higher slot or with
+/// a higher write version
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct AccountRow {
+    pub(crate) pubkey: Vec<u8>,
+    pub(crate) owner: Vec<u8>,
+    pub(crate) lamports: i64,
+    pub(crate) executable: bool,
+    pub(crate) rent_epoch: i64,
+    pub(crate) data: Vec<u8>,
+    pub(crate) slot: i64,
+    pub(crate) write_version: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TransactionRow {
+    pub(crate) slot: i64,
+    pub(crate) signature: Vec<u8>,
+    pub(crate) index: i64,
+    pub(crate) is_vote: bool,
+    pub(crate) fee: i64,
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BlockRow {
+    pub(crate) slot: i64,
+    pub(crate) parent_slot: i64,
+    pub(crate) blockhash: String,
+    pub(crate) parent_blockhash: String,
+    pub(crate) block_time: Option<i64>,
+    pub(crate) block_height: Option<i64>,
+    pub(crate) executed_transaction_count: i64,
+    pub(crate) entry_count: i64,
+}
+
+/// Latest status of a slot. The parent is kept when a status does not carry
+/// it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SlotStatusRow {
+    pub(crate) slot: i64,
+    pub(crate) parent: Option<i64>,
+    pub(crate) status: &'static str,
+    pub(crate) dead_error: Option<String>,
+}
+
+pub(crate) trait Store: Send {
+    /// Returns the latest migration applied to the database, 0 if none
+    fn schema_version(&mut self) -> Result<u32, SqlPluginError>;
+
+    /// Applies `migration` and records it, in a single database transaction
+    fn apply_migration(&mut self, migration: &Migration) -> Result<(), SqlPluginError>;
+
+    /// Writes `rows` in a single database transaction
+    fn write(&mut self, rows: &[Row]) -> Result<(), SqlPluginError>;
+}
+
+/// Statement creating the table recording the applied migrations, valid for
+/// every backend
+const CREATE_SCHEMA_MIGRATIONS: &str = "CREATE TABLE IF NOT EXISTS schema_migrations (
+    version BIGINT PRIMARY KEY,
+    description TEXT NOT NULL
+)";
+
+/// Connects to `database`. Migrations are not applied.
+pub(crate) fn open(database: &Database) -> Result<Box<dyn Store>, SqlPluginError> {
+    match database {
+        #[cfg(feature = "sqlite")]
+        Database::Sqlite { path } => Ok(Box::new(sqlite::SqliteStore::open(path)?)),
+        #[cfg(not(feature = "sqlite"))]
+        Database::Sqlite { .. } => Err(SqlPluginError::BackendNotEnabled("sqlite")),
+        #[cfg(feature = "postgres")]
+        Database::Postgres { url } => Ok(Box::new(postgresql::PostgresStore::connect(url)?)),
+        #[cfg(not(feature = "postgres"))]
+        Database::Postgres { .. } => Err(SqlPluginError::BackendNotEnabled("postgres")),
+    }
+}
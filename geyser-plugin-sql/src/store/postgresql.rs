@@ -0,0 +1,168 @@
+use {
+    super::{CREATE_SCHEMA_MIGRATIONS, Row, Store},
+    crate::{SqlPluginError, migrations::Migration},
+    postgres::{Client, NoTls, Statement},
+};
+
+const UPSERT_ACCOUNT: &str = "INSERT INTO accounts
+    (pubkey, owner, lamports, executable, rent_epoch, data, slot, write_version)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+    ON CONFLICT (pubkey) DO UPDATE SET
+        owner = excluded.owner,
+        lamports = excluded.lamports,
+        executable = excluded.executable,
+        rent_epoch = excluded.rent_epoch,
+        data = excluded.data,
+        slot = excluded.slot,
+        write_version = excluded.write_version
+    WHERE (excluded.slot, excluded.write_version) > (accounts.slot, accounts.write_version)";
+
+const INSERT_TRANSACTION: &str = "INSERT INTO transactions
+    (slot, signature, idx, is_vote, fee, error)
+    VALUES ($1, $2, $3, $4, $5, $6)
+    ON CONFLICT (slot, signature) DO NOTHING";
+
+const UPSERT_BLOCK: &str = "INSERT INTO blocks
+    (slot, parent_slot, blockhash, parent_blockhash, block_time, block_height,
+     executed_transaction_count, entry_count)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+    ON CONFLICT (slot) DO UPDATE SET
+        parent_slot = excluded.parent_slot,
+        blockhash = excluded.blockhash,
+        parent_blockhash = excluded.parent_blockhash,
+        block_time = excluded.block_time,
+        block_height = excluded.block_height,
+        executed_transaction_count = excluded.executed_transaction_count,
+        entry_count = excluded.entry_count";
+
+const UPSERT_SLOT_STATUS: &str = "INSERT INTO slots
+    (slot, parent, status, dead_error)
+    VALUES ($1, $2, $3, $4)
+    ON CONFLICT (slot) DO UPDATE SET
+        parent = COALESCE(excluded.parent, slots.parent),
+        status = excluded.status,
+        dead_error = excluded.dead_error";
+
+struct Statements {
+    upsert_account: Statement,
+    insert_transaction: Statement,
+    upsert_block: Statement,
+    upsert_slot_status: Statement,
+}
+
+pub(crate) struct PostgresStore {
+    client: Client,
+    /// Prepared on the first write, once the tables exist
+    statements: Option<Statements>,
+}
+
+impl PostgresStore {
+    /// Connects to the database at `url`, a connection string such as
+    /// `host=localhost user=geyser` or `postgresql://geyser@localhost`.
+    /// TLS is not supported.
+    pub(crate) fn connect(url: &str) -> Result<Self, SqlPluginError> {
+        Ok(Self {
+            client: Client::connect(url, NoTls)?,
+            statements: None,
+        })
+    }
+
+    fn prepare(client: &mut Client) -> Result<Statements, SqlPluginError> {
+        Ok(Statements {
+            upsert_account: client.prepare(UPSERT_ACCOUNT)?,
+            insert_transaction: client.prepare(INSERT_TRANSACTION)?,
+            upsert_block: client.prepare(UPSERT_BLOCK)?,
+            upsert_slot_status: client.prepare(UPSERT_SLOT_STATUS)?,
+        })
+    }
+
+    fn write_rows(
+        client: &mut Client,
+        statements: &Statements,
+        rows: &[Row],
+    ) -> Result<(), SqlPluginError> {
+        let mut transaction = client.transaction()?;
+        for row in rows {
+            match row {
+                Row::Account(account) => transaction.execute(
+                    &statements.upsert_account,
+                    &[
+                        &account.pubkey,
+                        &account.owner,
+                        &account.lamports,
+                        &account.executable,
+                        &account.rent_epoch,
+                        &account.data,
+                        &account.slot,
+                        &account.write_version,
+                    ],
+                )?,
+                Row::Transaction(tx) => transaction.execute(
+                    &statements.insert_transaction,
+                    &[
+                        &tx.slot,
+                        &tx.signature,
+                        &tx.index,
+                        &tx.is_vote,
+                        &tx.fee,
+                        &tx.error,
+                    ],
+                )?,
+                Row::Block(block) => transaction.execute(
+                    &statements.upsert_block,
+                    &[
+                        &block.slot,
+                        &block.parent_slot,
+                        &block.blockhash,
+                        &block.parent_blockhash,
+                        &block.block_time,
+                        &block.block_height,
+                        &block.executed_transaction_count,
+                        &block.entry_count,
+                    ],
+                )?,
+                Row::SlotStatus(slot) => transaction.execute(
+                    &statements.upsert_slot_status,
+                    &[&slot.slot, &slot.parent, &slot.status, &slot.dead_error],
+                )?,
+            };
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+impl Store for PostgresStore {
+    fn schema_version(&mut self) -> Result<u32, SqlPluginError> {
+        self.client.batch_execute(CREATE_SCHEMA_MIGRATIONS)?;
+        let version: i64 = self
+            .client
+            .query_one(
+                "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+                &[],
+            )?
+            .get(0);
+        Ok(version as u32)
+    }
+
+    fn apply_migration(&mut self, migration: &Migration) -> Result<(), SqlPluginError> {
+        let mut transaction = self.client.transaction()?;
+        transaction.batch_execute(migration.postgres)?;
+        transaction.execute(
+            "INSERT INTO schema_migrations (version, description) VALUES ($1, $2)",
+            &[&i64::from(migration.version), &migration.description],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn write(&mut self, rows: &[Row]) -> Result<(), SqlPluginError> {
+        let statements = match self.statements.take() {
+            Some(statements) => statements,
+            None => Self::prepare(&mut self.client)?,
+        };
+        let result = Self::write_rows(&mut self.client, &statements, rows);
+        self.statements = Some(statements);
+        result
+    }
+}
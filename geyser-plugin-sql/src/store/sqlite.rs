@@ -0,0 +1,214 @@
+use {
+    super::{CREATE_SCHEMA_MIGRATIONS, Row, Store},
+    crate::{SqlPluginError, migrations::Migration},
+    rusqlite::{Connection, params},
+    std::{path::Path, time::Duration},
+};
+
+/// How long to wait for readers holding a lock on the database
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const UPSERT_ACCOUNT: &str = "INSERT INTO accounts
+    (pubkey, owner, lamports, executable, rent_epoch, data, slot, write_version)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+    ON CONFLICT (pubkey) DO UPDATE SET
+        owner = excluded.owner,
+        lamports = excluded.lamports,
+        executable = excluded.executable,
+        rent_epoch = excluded.rent_epoch,
+        data = excluded.data,
+        slot = excluded.slot,
+        write_version = excluded.write_version
+    WHERE (excluded.slot, excluded.write_version) > (accounts.slot, accounts.write_version)";
+
+const INSERT_TRANSACTION: &str = "INSERT INTO transactions
+    (slot, signature, idx, is_vote, fee, error)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+    ON CONFLICT (slot, signature) DO NOTHING";
+
+const UPSERT_BLOCK: &str = "INSERT INTO blocks
+    (slot, parent_slot, blockhash, parent_blockhash, block_time, block_height,
+     executed_transaction_count, entry_count)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+    ON CONFLICT (slot) DO UPDATE SET
+        parent_slot = excluded.parent_slot,
+        blockhash = excluded.blockhash,
+        parent_blockhash = excluded.parent_blockhash,
+        block_time = excluded.block_time,
+        block_height = excluded.block_height,
+        executed_transaction_count = excluded.executed_transaction_count,
+        entry_count = excluded.entry_count";
+
+const UPSERT_SLOT_STATUS: &str = "INSERT INTO slots
+    (slot, parent, status, dead_error)
+    VALUES (?1, ?2, ?3, ?4)
+    ON CONFLICT (slot) DO UPDATE SET
+        parent = COALESCE(excluded.parent, slots.parent),
+        status = excluded.status,
+        dead_error = excluded.dead_error";
+
+pub(crate) struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    pub(crate) fn open(path: &Path) -> Result<Self, SqlPluginError> {
+        let connection = Connection::open(path)?;
+        // Lets readers query the database while the plugin writes
+        connection.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(Self { connection })
+    }
+}
+
+impl Store for SqliteStore {
+    fn schema_version(&mut self) -> Result<u32, SqlPluginError> {
+        self.connection.execute(CREATE_SCHEMA_MIGRATIONS, [])?;
+        let version: i64 = self.connection.query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(version as u32)
+    }
+
+    fn apply_migration(&mut self, migration: &Migration) -> Result<(), SqlPluginError> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute_batch(migration.sqlite)?;
+        transaction.execute(
+            "INSERT INTO schema_migrations (version, description) VALUES (?1, ?2)",
+            params![i64::from(migration.version), migration.description],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn write(&mut self, rows: &[Row]) -> Result<(), SqlPluginError> {
+        let transaction = self.connection.transaction()?;
+        for row in rows {
+            match row {
+                Row::Account(account) => {
+                    transaction
+                        .prepare_cached(UPSERT_ACCOUNT)?
+                        .execute(params![
+                            account.pubkey,
+                            account.owner,
+                            account.lamports,
+                            account.executable,
+                            account.rent_epoch,
+                            account.data,
+                            account.slot,
+                            account.write_version,
+                        ])?
+                }
+                Row::Transaction(tx) => {
+                    transaction
+                        .prepare_cached(INSERT_TRANSACTION)?
+                        .execute(params![
+                            tx.slot,
+                            tx.signature,
+                            tx.index,
+                            tx.is_vote,
+                            tx.fee,
+                            tx.error,
+                        ])?
+                }
+                Row::Block(block) => transaction.prepare_cached(UPSERT_BLOCK)?.execute(params![
+                    block.slot,
+                    block.parent_slot,
+                    block.blockhash,
+                    block.parent_blockhash,
+                    block.block_time,
+                    block.block_height,
+                    block.executed_transaction_count,
+                    block.entry_count,
+                ])?,
+                Row::SlotStatus(slot) => {
+                    transaction
+                        .prepare_cached(UPSERT_SLOT_STATUS)?
+                        .execute(params![
+                            slot.slot,
+                            slot.parent,
+                            slot.status,
+                            slot.dead_error,
+                        ])?
+                }
+            };
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            migrations,
+            store::{AccountRow, SlotStatusRow},
+        },
+    };
+
+    fn account(slot: i64, write_version: i64, lamports: i64) -> Row {
+        Row::Account(AccountRow {
+            pubkey: vec![1; 32],
+            owner: vec![2; 32],
+            lamports,
+            executable: false,
+            rent_epoch: -1,
+            data: vec![3; 10],
+            slot,
+            write_version,
+        })
+    }
+
+    #[test]
+    fn test_write_keeps_latest_rows() {
+        let db_dir = tempfile::tempdir().unwrap();
+        let mut store = SqliteStore::open(&db_dir.path().join("geyser.db")).unwrap();
+        migrations::run(&mut store).unwrap();
+
+        store
+            .write(&[
+                account(5, 10, 100),
+                account(5, 12, 200),
+                account(5, 11, 300),
+                Row::SlotStatus(SlotStatusRow {
+                    slot: 5,
+                    parent: Some(4),
+                    status: "processed",
+                    dead_error: None,
+                }),
+            ])
+            .unwrap();
+        store
+            .write(&[
+                account(4, 20, 400),
+                Row::SlotStatus(SlotStatusRow {
+                    slot: 5,
+                    parent: None,
+                    status: "rooted",
+                    dead_error: None,
+                }),
+            ])
+            .unwrap();
+
+        let (lamports, write_version): (i64, i64) = store
+            .connection
+            .query_row("SELECT lamports, write_version FROM accounts", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((lamports, write_version), (200, 12));
+        let (parent, status): (Option<i64>, String) = store
+            .connection
+            .query_row(
+                "SELECT parent, status FROM slots WHERE slot = 5",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((parent, status.as_str()), (Some(4), "rooted"));
+    }
+}
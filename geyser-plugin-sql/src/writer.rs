@@ -0,0 +1,227 @@
+//! The thread writing the rows built from notifications to the database.
+
+use {
+    crate::{
+        SqlPluginError,
+        store::{Row, Store},
+    },
+    crossbeam_channel::{Receiver, RecvTimeoutError, Sender, bounded},
+    log::*,
+    std::{
+        io,
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+/// Number of times a batch is written before the writer gives up
+const MAX_WRITE_ATTEMPTS: u32 = 5;
+/// Wait before retrying a failed batch, doubled after every failed attempt
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub(crate) struct Writer {
+    sender: Sender<Row>,
+    thread: JoinHandle<()>,
+}
+
+impl Writer {
+    /// Spawns the thread writing the rows to `store` in batches of up to
+    /// `batch_size` rows, at least every `flush_interval`
+    pub(crate) fn spawn(
+        store: Box<dyn Store>,
+        queue_size: usize,
+        batch_size: usize,
+        flush_interval: Duration,
+    ) -> io::Result<Self> {
+        let (sender, receiver) = bounded(queue_size);
+        let thread = thread::Builder::new()
+            .name("solGeyserSql".to_string())
+            .spawn(move || run_write_loop(store, receiver, batch_size, flush_interval))?;
+        Ok(Self { sender, thread })
+    }
+
+    /// Queues `row`, blocking while the queue is full. Fails once the writer
+    /// stopped after a batch could not be written.
+    pub(crate) fn send(&self, row: Row) -> Result<(), SqlPluginError> {
+        self.sender
+            .send(row)
+            .map_err(|_| SqlPluginError::WriterExited)
+    }
+
+    /// Writes the queued rows and stops the thread
+    pub(crate) fn join(self) {
+        drop(self.sender);
+        if self.thread.join().is_err() {
+            error!("Geyser SQL plugin writer thread panicked");
+        }
+    }
+}
+
+fn run_write_loop(
+    mut store: Box<dyn Store>,
+    receiver: Receiver<Row>,
+    batch_size: usize,
+    flush_interval: Duration,
+) {
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut disconnected = false;
+    while !disconnected {
+        // Wait for the first row of the batch, then for the batch to fill up
+        // until the flush interval elapsed
+        match receiver.recv() {
+            Ok(row) => batch.push(row),
+            Err(_) => break,
+        }
+        let deadline = Instant::now() + flush_interval;
+        while batch.len() < batch_size {
+            match receiver.recv_deadline(deadline) {
+                Ok(row) => batch.push(row),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
+            }
+        }
+        // Dropping the receiver fails every later send, so the rows are never
+        // silently lost
+        if let Err(err) = write_with_retries(store.as_mut(), &batch) {
+            error!(
+                "Geyser SQL plugin failed to write {} rows, stopping the writer: {err}",
+                batch.len()
+            );
+            return;
+        }
+        batch.clear();
+    }
+}
+
+fn write_with_retries(store: &mut dyn Store, batch: &[Row]) -> Result<(), SqlPluginError> {
+    let mut backoff = RETRY_BACKOFF;
+    for attempt in 1..MAX_WRITE_ATTEMPTS {
+        match store.write(batch) {
+            Ok(()) => return Ok(()),
+            Err(err) => {
+                warn!(
+                    "Geyser SQL plugin failed to write {} rows (attempt {attempt} of \
+                     {MAX_WRITE_ATTEMPTS}), retrying in {backoff:?}: {err}",
+                    batch.len()
+                );
+                thread::sleep(backoff);
+                backoff = backoff.saturating_mul(2);
+            }
+        }
+    }
+    store.write(batch)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{migrations::Migration, store::SlotStatusRow},
+        std::sync::{Arc, Mutex},
+    };
+
+    #[derive(Default)]
+    struct BatchRecorder {
+        batches: Arc<Mutex<Vec<Vec<Row>>>>,
+        // Number of writes failing before the batches are recorded
+        failures: u32,
+    }
+
+    impl Store for BatchRecorder {
+        fn schema_version(&mut self) -> Result<u32, SqlPluginError> {
+            Ok(0)
+        }
+
+        fn apply_migration(&mut self, _migration: &Migration) -> Result<(), SqlPluginError> {
+            Ok(())
+        }
+
+        fn write(&mut self, rows: &[Row]) -> Result<(), SqlPluginError> {
+            if self.failures > 0 {
+                self.failures = self.failures.saturating_sub(1);
+                return Err(SqlPluginError::Config("write failed".to_string()));
+            }
+            self.batches.lock().unwrap().push(rows.to_vec());
+            Ok(())
+        }
+    }
+
+    fn slot_status(slot: i64) -> Row {
+        Row::SlotStatus(SlotStatusRow {
+            slot,
+            parent: None,
+            status: "processed",
+            dead_error: None,
+        })
+    }
+
+    #[test]
+    fn test_write_in_batches() {
+        let store = BatchRecorder::default();
+        let batches = store.batches.clone();
+        let writer = Writer::spawn(Box::new(store), 100, 2, Duration::from_secs(60)).unwrap();
+        for slot in 0..5 {
+            writer.send(slot_status(slot)).unwrap();
+        }
+        writer.join();
+
+        // The last partial batch is written when the writer stops
+        assert_eq!(
+            *batches.lock().unwrap(),
+            vec![
+                vec![slot_status(0), slot_status(1)],
+                vec![slot_status(2), slot_status(3)],
+                vec![slot_status(4)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_on_flush_interval() {
+        let store = BatchRecorder::default();
+        let batches = store.batches.clone();
+        let writer = Writer::spawn(Box::new(store), 100, 1000, Duration::from_millis(10)).unwrap();
+        writer.send(slot_status(0)).unwrap();
+        let start = Instant::now();
+        while batches.lock().unwrap().is_empty() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(*batches.lock().unwrap(), vec![vec![slot_status(0)]]);
+        writer.join();
+    }
+
+    #[test]
+    fn test_retry_failed_write() {
+        let store = BatchRecorder {
+            failures: 2,
+            ..BatchRecorder::default()
+        };
+        let batches = store.batches.clone();
+        let writer = Writer::spawn(Box::new(store), 100, 1, Duration::from_secs(60)).unwrap();
+        writer.send(slot_status(0)).unwrap();
+        writer.join();
+        assert_eq!(*batches.lock().unwrap(), vec![vec![slot_status(0)]]);
+    }
+
+    #[test]
+    fn test_stop_after_failed_write() {
+        let store = BatchRecorder {
+            failures: MAX_WRITE_ATTEMPTS,
+            ..BatchRecorder::default()
+        };
+        let batches = store.batches.clone();
+        let writer = Writer::spawn(Box::new(store), 1, 1, Duration::from_secs(60)).unwrap();
+        writer.send(slot_status(0)).unwrap();
+        let start = Instant::now();
+        while writer.send(slot_status(1)).is_ok() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+        }
+        writer.join();
+        assert!(batches.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+#![cfg(feature = "sqlite")]
+
+use {
+    rusqlite::{Connection, OptionalExtension},
+    solana_pubkey::Pubkey,
+    solana_system_transaction as system_transaction,
+    solana_test_validator::TestValidatorGenesis,
+    std::{
+        env::consts::{DLL_PREFIX, DLL_SUFFIX},
+        fs,
+        path::{Path, PathBuf},
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+const TRANSFER_LAMPORTS: u64 = 1_000_000_000;
+
+/// Returns the plugin library built by cargo for this test, which is found
+/// next to the test binary or in its parent directory
+fn plugin_library() -> PathBuf {
+    let name = format!("{DLL_PREFIX}agave_geyser_plugin_sql{DLL_SUFFIX}");
+    let test_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    [test_dir.clone(), test_dir.parent().unwrap().to_path_buf()]
+        .into_iter()
+        .map(|dir| dir.join(&name))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| panic!("{name} not found next to {test_dir:?}"))
+}
+
+fn write_config(config_dir: &Path, database: &str, account: &Pubkey) -> PathBuf {
+    let config_file = config_dir.join("geyser-plugin-sql.json");
+    let config = format!(
+        r#"{{
+            libpath: {:?},
+            sql: {{
+                database: {database},
+                flush_interval_ms: 10,
+                accounts: {{ pubkeys: ["{account}"] }},
+                transactions: {{}},
+                blocks: true,
+            }},
+        }}"#,
+        plugin_library(),
+    );
+    fs::write(&config_file, config).unwrap();
+    config_file
+}
+
+/// Polls `query` until it returns a row
+fn wait_for<T>(connection: &Connection, query: &str, params: impl rusqlite::Params + Clone) -> T
+where
+    T: rusqlite::types::FromSql,
+{
+    let start = Instant::now();
+    loop {
+        if let Some(value) = connection
+            .query_row(query, params.clone(), |row| row.get(0))
+            .optional()
+            .unwrap()
+        {
+            return value;
+        }
+        assert!(start.elapsed() < TIMEOUT, "timed out waiting for {query}");
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+#[test]
+fn test_sqlite_plugin_writes_validator_notifications() {
+    let config_dir = tempfile::tempdir().unwrap();
+    let recipient = Pubkey::new_unique();
+    let config_file = write_config(
+        config_dir.path(),
+        r#"{ sqlite: { path: "geyser.db" } }"#,
+        &recipient,
+    );
+
+    let mut genesis = TestValidatorGenesis::default_for_tests();
+    genesis.geyser_plugin_config_files = Some(vec![config_file]);
+    let (test_validator, mint_keypair) = genesis.start();
+    let rpc_client = test_validator.get_rpc_client();
+    let blockhash = rpc_client.get_latest_blockhash().unwrap();
+    let transaction =
+        system_transaction::transfer(&mint_keypair, &recipient, TRANSFER_LAMPORTS, blockhash);
+    let signature = rpc_client
+        .send_and_confirm_transaction(&transaction)
+        .unwrap();
+
+    let connection = Connection::open(config_dir.path().join("geyser.db")).unwrap();
+    let schema_version: i64 = wait_for(
+        &connection,
+        "SELECT MAX(version) FROM schema_migrations",
+        [],
+    );
+    assert_eq!(
+        schema_version,
+        i64::from(agave_geyser_plugin_sql::migrations::latest_version())
+    );
+
+    // Only the selected account is written
+    let lamports: i64 = wait_for(
+        &connection,
+        "SELECT lamports FROM accounts WHERE pubkey = ?1",
+        [recipient.to_bytes().to_vec()],
+    );
+    assert_eq!(lamports, TRANSFER_LAMPORTS as i64);
+    let accounts: i64 = connection
+        .query_row("SELECT COUNT(*) FROM accounts", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(accounts, 1);
+
+    let slot: i64 = wait_for(
+        &connection,
+        "SELECT slot FROM transactions WHERE signature = ?1 AND error IS NULL",
+        [signature.as_ref().to_vec()],
+    );
+    let _: String = wait_for(
+        &connection,
+        "SELECT blockhash FROM blocks WHERE slot = ?1",
+        [slot],
+    );
+    let _: i64 = wait_for(
+        &connection,
+        "SELECT slot FROM slots WHERE slot >= ?1 AND status = 'rooted'",
+        [slot],
+    );
+    let vote_transactions: i64 = connection
+        .query_row(
+            "SELECT COUNT(*) FROM transactions WHERE is_vote",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(vote_transactions, 0);
+}
+
+/// Runs against the PostgreSQL database given by the
+/// `GEYSER_PLUGIN_SQL_POSTGRES_URL` environment variable
+#[cfg(feature = "postgres")]
+#[test]
+#[ignore]
+fn test_postgres_plugin_writes_validator_notifications() {
+    let url = std::env::var("GEYSER_PLUGIN_SQL_POSTGRES_URL")
+        .expect("GEYSER_PLUGIN_SQL_POSTGRES_URL is not set");
+    let config_dir = tempfile::tempdir().unwrap();
+    let recipient = Pubkey::new_unique();
+    let config_file = write_config(
+        config_dir.path(),
+        &format!("{{ postgres: {{ url: {url:?} }} }}"),
+        &recipient,
+    );
+
+    let mut genesis = TestValidatorGenesis::default_for_tests();
+    genesis.geyser_plugin_config_files = Some(vec![config_file]);
+    let (test_validator, mint_keypair) = genesis.start();
+    let rpc_client = test_validator.get_rpc_client();
+    let blockhash = rpc_client.get_latest_blockhash().unwrap();
+    let transaction =
+        system_transaction::transfer(&mint_keypair, &recipient, TRANSFER_LAMPORTS, blockhash);
+    let signature = rpc_client
+        .send_and_confirm_transaction(&transaction)
+        .unwrap();
+
+    let mut client = postgres::Client::connect(&url, postgres::NoTls).unwrap();
+    let start = Instant::now();
+    loop {
+        let rows = client
+            .query(
+                "SELECT t.slot FROM transactions t JOIN blocks b ON b.slot = t.slot WHERE \
+                 t.signature = $1",
+                &[&signature.as_ref().to_vec()],
+            )
+            .unwrap();
+        if !rows.is_empty() {
+            break;
+        }
+        assert!(
+            start.elapsed() < TIMEOUT,
+            "timed out waiting for the transaction"
+        );
+        thread::sleep(Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,389 @@
+//! Fit cost model coefficients to the execution times measured while
+//! replaying the ledger.
+//!
+//! Replay reports, for every slot, the time spent and the compute units
+//! consumed by each program's top level instructions. Non-builtin programs
+//! are charged for the compute units they consume, so they are used to fit
+//! the compute unit to microsecond ratio. Builtins consume a fixed number of
+//! compute units per instruction, so each builtin is fitted a cost per
+//! instruction instead, expressed in compute units at the fitted ratio.
+//!
+//! Signature verification happens outside of execution and is not measured,
+//! so the cost per signature (`SIGNATURE_COST`) is not fitted.
+
+use {
+    serde::Serialize,
+    solana_builtins_default_costs::{
+        BuiltinMigrationFeatureIndex, get_builtin_migration_feature_index,
+    },
+    solana_cli_output::{QuietDisplay, VerboseDisplay},
+    solana_cost_model::block_cost_limits::COMPUTE_UNIT_TO_US_RATIO,
+    solana_pubkey::Pubkey,
+    solana_svm_timings::ProgramTiming,
+    std::{
+        collections::HashMap,
+        fmt::{self, Display, Formatter},
+    },
+};
+
+/// Time and compute units of one program's instructions in one slot.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Sample {
+    us: f64,
+    units: f64,
+    count: f64,
+}
+
+#[derive(Default)]
+pub struct CostCalibration {
+    num_slots: usize,
+    samples_by_program: HashMap<Pubkey, Vec<Sample>>,
+}
+
+impl CostCalibration {
+    /// Record the per-program timings of one replayed slot.
+    pub fn add_slot(&mut self, per_program_timings: &HashMap<Pubkey, ProgramTiming>) {
+        self.num_slots += 1;
+        for (program_id, timing) in per_program_timings {
+            // Failed instructions still took time to execute, so count them
+            // with the compute units they consumed.
+            let num_errored = timing.errored_txs_compute_consumed.len() as u64;
+            let sample = Sample {
+                us: timing.accumulated_us.0 as f64,
+                units: (timing.accumulated_units.0 + timing.total_errored_units.0) as f64,
+                count: (u64::from(timing.count.0) + num_errored) as f64,
+            };
+            if sample.count > 0.0 {
+                self.samples_by_program
+                    .entry(*program_id)
+                    .or_default()
+                    .push(sample);
+            }
+        }
+    }
+
+    /// Fit the cost coefficients to the recorded slots. Programs with fewer
+    /// than `min_instructions` instructions in total are left out.
+    pub fn fit(&self, min_instructions: u64) -> CostCalibrationReport {
+        let samples_by_program: Vec<_> = self
+            .samples_by_program
+            .iter()
+            .filter(|(_, samples)| {
+                samples.iter().map(|sample| sample.count).sum::<f64>() >= min_instructions as f64
+            })
+            .collect();
+        let is_builtin = |program_id: &Pubkey| {
+            !matches!(
+                get_builtin_migration_feature_index(program_id),
+                BuiltinMigrationFeatureIndex::NotBuiltin
+            )
+        };
+
+        // us = us_per_unit * units, over every non-builtin sample.
+        let program_samples: Vec<_> = samples_by_program
+            .iter()
+            .filter(|(program_id, _)| !is_builtin(program_id))
+            .flat_map(|(_, samples)| samples.iter().copied())
+            .collect();
+        let points: Vec<_> = program_samples
+            .iter()
+            .map(|sample| (sample.units, sample.us))
+            .collect();
+        let us_per_unit = fit_slope(&points);
+        let compute_unit_to_us_ratio = us_per_unit
+            .filter(|us_per_unit| *us_per_unit > 0.0)
+            .map(|us_per_unit| 1.0 / us_per_unit);
+        // Builtin costs are converted with the fitted ratio when there is one.
+        let ratio = compute_unit_to_us_ratio.unwrap_or(COMPUTE_UNIT_TO_US_RATIO as f64);
+
+        let mut programs: Vec<_> = samples_by_program
+            .iter()
+            .filter(|(program_id, _)| !is_builtin(program_id))
+            .map(|(program_id, samples)| {
+                let totals = total(samples);
+                ProgramCalibration {
+                    program_id: program_id.to_string(),
+                    instructions: totals.count as u64,
+                    units_per_us: (totals.us > 0.0).then(|| totals.units / totals.us),
+                    residual: us_per_unit
+                        .map(|us_per_unit| residual(samples, |sample| us_per_unit * sample.units)),
+                }
+            })
+            .collect();
+        programs.sort_by(|a, b| b.instructions.cmp(&a.instructions));
+
+        // us = us_per_instruction * count, for each builtin.
+        let mut builtins: Vec<_> = samples_by_program
+            .iter()
+            .filter(|(program_id, _)| is_builtin(program_id))
+            .map(|(program_id, samples)| {
+                let totals = total(samples);
+                let points: Vec<_> = samples
+                    .iter()
+                    .map(|sample| (sample.count, sample.us))
+                    .collect();
+                let us_per_instruction = fit_slope(&points).unwrap_or_default();
+                BuiltinCalibration {
+                    program_id: program_id.to_string(),
+                    instructions: totals.count as u64,
+                    us_per_instruction,
+                    consumed_units_per_instruction: totals.units / totals.count,
+                    recommended_units_per_instruction: (us_per_instruction * ratio).round() as u64,
+                    residual: residual(samples, |sample| us_per_instruction * sample.count),
+                }
+            })
+            .collect();
+        builtins.sort_by(|a, b| b.instructions.cmp(&a.instructions));
+
+        CostCalibrationReport {
+            num_slots: self.num_slots,
+            current_compute_unit_to_us_ratio: COMPUTE_UNIT_TO_US_RATIO,
+            compute_unit_to_us_ratio,
+            residual: us_per_unit
+                .map(|us_per_unit| residual(&program_samples, |sample| us_per_unit * sample.units)),
+            programs,
+            builtins,
+        }
+    }
+}
+
+/// Least squares slope of a line through the origin, `None` without data.
+fn fit_slope(points: &[(f64, f64)]) -> Option<f64> {
+    let (sum_xy, sum_xx) = points.iter().fold((0.0, 0.0), |(sum_xy, sum_xx), (x, y)| {
+        (sum_xy + x * y, sum_xx + x * x)
+    });
+    (sum_xx > 0.0).then(|| sum_xy / sum_xx)
+}
+
+fn total(samples: &[Sample]) -> Sample {
+    samples
+        .iter()
+        .fold(Sample::default(), |total, sample| Sample {
+            us: total.us + sample.us,
+            units: total.units + sample.units,
+            count: total.count + sample.count,
+        })
+}
+
+/// How far the measured times are from the times `predict`ed by a fit.
+fn residual(samples: &[Sample], predict: impl Fn(&Sample) -> f64) -> Residual {
+    let (sum_squared_error, sum_absolute_error, sum_us) = samples.iter().fold(
+        (0.0, 0.0, 0.0),
+        |(sum_squared, sum_absolute, sum_us), sample| {
+            let error = sample.us - predict(sample);
+            (
+                sum_squared + error * error,
+                sum_absolute + error.abs(),
+                sum_us + sample.us,
+            )
+        },
+    );
+    let num_samples = samples.len().max(1) as f64;
+    Residual {
+        rms_us: (sum_squared_error / num_samples).sqrt(),
+        relative_error: if sum_us > 0.0 {
+            sum_absolute_error / sum_us
+        } else {
+            0.0
+        },
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Residual {
+    /// Root mean square of the per-slot error, in microseconds.
+    pub rms_us: f64,
+    /// Total absolute error relative to the total measured time.
+    pub relative_error: f64,
+}
+
+impl Display for Residual {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "rms {:.1}us, {:.1}% error",
+            self.rms_us,
+            self.relative_error * 100.0
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramCalibration {
+    pub program_id: String,
+    pub instructions: u64,
+    /// Compute units this program consumes per microsecond of execution.
+    pub units_per_us: Option<f64>,
+    /// Residual of this program's slots under the fitted ratio.
+    pub residual: Option<Residual>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuiltinCalibration {
+    pub program_id: String,
+    pub instructions: u64,
+    pub us_per_instruction: f64,
+    pub consumed_units_per_instruction: f64,
+    pub recommended_units_per_instruction: u64,
+    pub residual: Residual,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostCalibrationReport {
+    pub num_slots: usize,
+    pub current_compute_unit_to_us_ratio: u64,
+    /// Fitted compute units per microsecond, `None` if no non-builtin
+    /// program was executed.
+    pub compute_unit_to_us_ratio: Option<f64>,
+    pub residual: Option<Residual>,
+    pub programs: Vec<ProgramCalibration>,
+    pub builtins: Vec<BuiltinCalibration>,
+}
+
+impl VerboseDisplay for CostCalibrationReport {}
+impl QuietDisplay for CostCalibrationReport {}
+
+impl Display for CostCalibrationReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "Slots replayed: {}", self.num_slots)?;
+        match (self.compute_unit_to_us_ratio, self.residual) {
+            (Some(ratio), Some(residual)) => writeln!(
+                f,
+                "COMPUTE_UNIT_TO_US_RATIO: current {}, fitted {ratio:.2} ({residual})",
+                self.current_compute_unit_to_us_ratio,
+            )?,
+            _ => writeln!(
+                f,
+                "COMPUTE_UNIT_TO_US_RATIO: current {}, not enough program executions to fit",
+                self.current_compute_unit_to_us_ratio,
+            )?,
+        }
+
+        writeln!(f, "\nBuiltins:")?;
+        writeln!(
+            f,
+            "{:<44} {:>12} {:>10} {:>14} {:>14}  residual",
+            "program", "instructions", "us/ix", "consumed CU/ix", "recommended CU"
+        )?;
+        for builtin in &self.builtins {
+            writeln!(
+                f,
+                "{:<44} {:>12} {:>10.2} {:>14.0} {:>14}  {}",
+                builtin.program_id,
+                builtin.instructions,
+                builtin.us_per_instruction,
+                builtin.consumed_units_per_instruction,
+                builtin.recommended_units_per_instruction,
+                builtin.residual,
+            )?;
+        }
+
+        writeln!(f, "\nPrograms:")?;
+        writeln!(
+            f,
+            "{:<44} {:>12} {:>10}  residual",
+            "program", "instructions", "CU/us"
+        )?;
+        for program in &self.programs {
+            let units_per_us = program
+                .units_per_us
+                .map(|units_per_us| format!("{units_per_us:.2}"))
+                .unwrap_or_else(|| "-".to_string());
+            let residual = program
+                .residual
+                .map(|residual| residual.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            writeln!(
+                f,
+                "{:<44} {:>12} {:>10}  {residual}",
+                program.program_id, program.instructions, units_per_us,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk_ids::system_program, std::num::Saturating};
+
+    fn program_timing(us: u64, units: u64, count: u32) -> ProgramTiming {
+        ProgramTiming {
+            accumulated_us: Saturating(us),
+            accumulated_units: Saturating(units),
+            count: Saturating(count),
+            ..ProgramTiming::default()
+        }
+    }
+
+    #[test]
+    fn test_fit_slope() {
+        assert_eq!(fit_slope(&[]), None);
+        assert_eq!(fit_slope(&[(0.0, 5.0)]), None);
+        assert_eq!(fit_slope(&[(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)]), Some(2.0));
+    }
+
+    #[test]
+    fn test_fit() {
+        let program_a = Pubkey::new_unique();
+        let program_b = Pubkey::new_unique();
+        let rare_program = Pubkey::new_unique();
+
+        let mut calibration = CostCalibration::default();
+        for slot in 1..=4 {
+            let mut per_program_timings = HashMap::new();
+            // Both programs consume 40 units per microsecond.
+            per_program_timings.insert(program_a, program_timing(slot * 10, slot * 400, 2));
+            per_program_timings.insert(program_b, program_timing(slot * 5, slot * 200, 1));
+            per_program_timings.insert(rare_program, program_timing(1_000, 1, 1));
+            // 3us per system instruction.
+            per_program_timings.insert(
+                system_program::id(),
+                program_timing(slot * 3, slot * 150, slot as u32),
+            );
+            calibration.add_slot(&per_program_timings);
+        }
+
+        let report = calibration.fit(5);
+        assert_eq!(report.num_slots, 4);
+        let ratio = report.compute_unit_to_us_ratio.unwrap();
+        assert!((ratio - 40.0).abs() < 1e-9);
+        assert!(report.residual.unwrap().relative_error < 1e-9);
+
+        assert_eq!(report.programs.len(), 2);
+        assert_eq!(report.programs[0].program_id, program_a.to_string());
+        assert_eq!(report.programs[0].instructions, 8);
+        assert!((report.programs[0].units_per_us.unwrap() - 40.0).abs() < 1e-9);
+
+        assert_eq!(report.builtins.len(), 1);
+        let builtin = &report.builtins[0];
+        assert_eq!(builtin.program_id, system_program::id().to_string());
+        assert_eq!(builtin.instructions, 10);
+        assert!((builtin.us_per_instruction - 3.0).abs() < 1e-9);
+        assert_eq!(builtin.consumed_units_per_instruction, 150.0);
+        assert_eq!(builtin.recommended_units_per_instruction, 120);
+    }
+
+    #[test]
+    fn test_fit_errored_and_empty() {
+        let program = Pubkey::new_unique();
+        let mut calibration = CostCalibration::default();
+        let mut timing = program_timing(30, 0, 0);
+        timing.errored_txs_compute_consumed = vec![400, 500];
+        timing.total_errored_units = Saturating(900);
+        calibration.add_slot(&HashMap::from([(program, timing)]));
+
+        let report = calibration.fit(1);
+        assert_eq!(report.programs[0].instructions, 2);
+        assert!((report.compute_unit_to_us_ratio.unwrap() - 30.0).abs() < 1e-9);
+
+        let report = CostCalibration::default().fit(1);
+        assert_eq!(report.compute_unit_to_us_ratio, None);
+        assert!(report.programs.is_empty());
+        assert!(report.builtins.is_empty());
+    }
+}
@@ -326,6 +326,7 @@ pub fn load_and_process_ledger(
                 write_blockstore.clone(),
                 arg_matches.is_present("enable_extended_tx_metadata_storage"),
                 None,
+                None,
                 tss_exit.clone(),
             );
 
@@ -333,6 +334,7 @@ pub fn load_and_process_ledger(
                 Some(TransactionStatusSender {
                     sender: transaction_status_sender,
                     dependency_tracker: None,
+                    record_post_accounts: false,
                 }),
                 Some(transaction_status_service),
             )
@@ -410,6 +412,15 @@ pub fn load_and_process_ledger(
     let unified_scheduler_handler_threads =
         value_t!(arg_matches, "unified_scheduler_handler_threads", usize).ok();
     match block_verification_method {
+        BlockVerificationMethod::BlockstoreProcessor => {
+            info!("no scheduler pool is installed for block verification...");
+            if let Some(count) = unified_scheduler_handler_threads {
+                warn!(
+                    "--unified-scheduler-handler-threads={count} is ignored because unified \
+                     scheduler isn't enabled"
+                );
+            }
+        }
         BlockVerificationMethod::UnifiedScheduler => {
             let no_replay_vote_sender = None;
             let no_prioritization_fee_cache = None;
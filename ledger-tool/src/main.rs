@@ -4,6 +4,7 @@ use {
         args::*,
         bigtable::*,
         blockstore::*,
+        cost_calibration::CostCalibration,
         ledger_path::*,
         ledger_utils::*,
         output::{
@@ -23,6 +24,7 @@ use {
         value_t_or_exit, values_t_or_exit,
     },
     dashmap::DashMap,
+    itertools::Itertools,
     log::*,
     serde::Serialize,
     solana_account::{AccountSharedData, ReadableAccount, WritableAccount, state_traits::StateMut},
@@ -38,9 +40,14 @@ use {
     solana_cluster_type::ClusterType,
     solana_core::{
         banking_simulation::{BankingSimulator, BankingTraceEvents},
+        banking_stage::{
+            BankingStage, transaction_scheduler::scheduler_controller::SchedulerConfig,
+        },
         resource_limits::adjust_nofile_limit,
         system_monitor_service::{SystemMonitorService, SystemMonitorStatsReportConfig},
-        validator::{BlockProductionMethod, BlockVerificationMethod, TransactionStructure},
+        validator::{
+            BlockProductionMethod, BlockVerificationMethod, SchedulerPacing, TransactionStructure,
+        },
     },
     solana_cost_model::{cost_model::CostModel, cost_tracker::CostTracker},
     solana_entry::entry::create_ticks,
@@ -51,7 +58,8 @@ use {
         blockstore::{Blockstore, banking_trace_path, create_new_ledger},
         blockstore_options::{AccessType, LedgerColumnOptions},
         blockstore_processor::{
-            ProcessSlotCallback, TransactionStatusMessage, TransactionStatusSender,
+            ProcessProgramTimingsCallback, ProcessSlotCallback, TransactionStatusMessage,
+            TransactionStatusSender,
         },
     },
     solana_measure::{measure::Measure, measure_time},
@@ -74,6 +82,7 @@ use {
     solana_runtime_transaction::runtime_transaction::RuntimeTransaction,
     solana_shred_version::compute_shred_version,
     solana_stake_interface::{self as stake, state::StakeStateV2},
+    solana_svm_timings::{ProgramTiming, enable_per_program_timings},
     solana_system_interface::program as system_program,
     solana_transaction::sanitized::MessageHash,
     solana_transaction_status::parse_ui_instruction,
@@ -103,6 +112,7 @@ use {
 mod args;
 mod bigtable;
 mod blockstore;
+mod cost_calibration;
 mod error;
 mod ledger_path;
 mod ledger_utils;
@@ -684,6 +694,7 @@ fn setup_slot_recording(
                     Some(TransactionStatusSender {
                         sender,
                         dependency_tracker: None,
+                        record_post_accounts: false,
                     }),
                     transaction_recorder,
                 )
@@ -943,6 +954,7 @@ fn main() {
         .max(rent.minimum_balance(StakeStateV2::size_of()))
         .to_string();
     let default_graph_vote_account_mode = GraphVoteAccountMode::default();
+    let default_scheduler_pacing = &BankingStage::default_fill_time_millis().to_string();
 
     let mut measure_total_execution_time = Measure::start("ledger tool");
 
@@ -1529,6 +1541,12 @@ fn main() {
         .subcommand(
             SubCommand::with_name("simulate-block-production")
                 .about("Simulate producing blocks with banking trace event files in the ledger")
+                .long_about(
+                    "Simulate producing blocks with banking trace event files in the ledger. When \
+                     several block production methods or scheduler pacings are given, the trace \
+                     is replayed once for each combination of them, and a report comparing the \
+                     simulated blocks is printed.",
+                )
                 .arg(&load_genesis_config_arg)
                 .args(&accounts_db_config_args)
                 .args(&snapshot_config_args)
@@ -1537,10 +1555,23 @@ fn main() {
                         .long("block-production-method")
                         .value_name("METHOD")
                         .takes_value(true)
+                        .multiple(true)
                         .possible_values(BlockProductionMethod::cli_names())
                         .default_value(BlockProductionMethod::default().into())
                         .help(BlockProductionMethod::cli_message()),
                 )
+                .arg(
+                    Arg::with_name("scheduler_pacing")
+                        .long("scheduler-pacing")
+                        .value_name("MILLIS")
+                        .takes_value(true)
+                        .multiple(true)
+                        .default_value(default_scheduler_pacing)
+                        .help(
+                            "Pacing fill time in milliseconds for the central-scheduler block \
+                             production methods, or 'disabled'",
+                        ),
+                )
                 .arg(
                     Arg::with_name("transaction_struct")
                         .long("transaction-structure")
@@ -1674,6 +1705,34 @@ fn main() {
                         .help("Output file in the csv format"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("calibrate-cost-model")
+                .about("Fit cost model coefficients to execution times measured during replay")
+                .long_about(
+                    "Replay the ledger from the snapshot up to --halt-at-slot, measuring the \
+                     execution time and compute units of every program's instructions. Fits the \
+                     compute unit to microsecond ratio to the non-builtin programs and a cost per \
+                     instruction to each builtin, and reports the residuals of the fits. The cost \
+                     per signature (SIGNATURE_COST) is not fitted, because signature verification \
+                     happens before replay executes transactions and is not measured.",
+                )
+                .arg(&load_genesis_config_arg)
+                .args(&accounts_db_config_args)
+                .args(&snapshot_config_args)
+                .arg(&halt_at_slot_arg)
+                .arg(&limit_load_slot_count_from_snapshot_arg)
+                .arg(&hard_forks_arg)
+                .arg(&allow_dead_slots_arg)
+                .arg(
+                    Arg::with_name("min_instructions")
+                        .long("min-instructions")
+                        .value_name("COUNT")
+                        .takes_value(true)
+                        .validator(is_parsable::<u64>)
+                        .default_value("100")
+                        .help("Leave out programs that executed fewer instructions"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("compute-slot-cost")
                 .about(
@@ -2622,7 +2681,7 @@ fn main() {
                         Some(banking_trace_events.hash_overrides().clone());
 
                     let slot = value_t!(arg_matches, "first_simulated_slot", Slot).unwrap();
-                    let simulator = BankingSimulator::new(banking_trace_events, slot);
+                    let simulator = BankingSimulator::new(banking_trace_events.clone(), slot);
                     let Some(parent_slot) = simulator.parent_slot() else {
                         eprintln!(
                             "Couldn't determine parent_slot of first_simulated_slot: {slot} due \
@@ -2640,35 +2699,73 @@ fn main() {
                         AccessType::PrimaryForMaintenance,
                     ));
                     let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
-                    let LoadAndProcessLedgerOutput { bank_forks, .. } =
-                        load_and_process_ledger_or_exit(
-                            arg_matches,
-                            &genesis_config,
-                            blockstore.clone(),
-                            process_options,
-                            None, // transaction status sender
-                        );
 
-                    let block_production_method = value_t_or_exit!(
+                    let block_production_methods = values_t_or_exit!(
                         arg_matches,
                         "block_production_method",
                         BlockProductionMethod
                     );
+                    let scheduler_pacings =
+                        values_t_or_exit!(arg_matches, "scheduler_pacing", SchedulerPacing);
+                    let policies = block_production_methods
+                        .into_iter()
+                        .cartesian_product(scheduler_pacings)
+                        .collect::<Vec<_>>();
+
+                    let mut reports = Vec::with_capacity(policies.len());
+                    for (block_production_method, scheduler_pacing) in policies {
+                        info!(
+                            "Using: block-production-method: {block_production_method}, \
+                             scheduler-pacing: {:?}",
+                            scheduler_pacing.fill_time(),
+                        );
 
-                    info!("Using: block-production-method: {block_production_method}");
+                        // Every simulation starts over from the parent slot, as the previous
+                        // one advanced the bank forks.
+                        let LoadAndProcessLedgerOutput {
+                            bank_forks,
+                            accounts_background_service,
+                            ..
+                        } = load_and_process_ledger_or_exit(
+                            arg_matches,
+                            &genesis_config,
+                            blockstore.clone(),
+                            process_options.clone(),
+                            None, // transaction status sender
+                        );
+                        let simulator = BankingSimulator::new(banking_trace_events.clone(), slot);
+                        let report = match simulator.start(
+                            genesis_config.clone(),
+                            bank_forks,
+                            blockstore.clone(),
+                            block_production_method.clone(),
+                            SchedulerConfig {
+                                scheduler_pacing: scheduler_pacing.clone(),
+                                ..SchedulerConfig::default()
+                            },
+                        ) {
+                            Ok(report) => report,
+                            Err(error) => {
+                                eprintln!("{error:?}");
+                                exit(1);
+                            }
+                        };
+                        accounts_background_service.join().unwrap();
+                        reports.push((block_production_method, scheduler_pacing, report));
+                    }
 
-                    match simulator.start(
-                        genesis_config,
-                        bank_forks,
-                        blockstore,
-                        block_production_method,
-                    ) {
-                        Ok(()) => println!("Ok"),
-                        Err(error) => {
-                            eprintln!("{error:?}");
-                            exit(1);
-                        }
-                    };
+                    for (block_production_method, scheduler_pacing, report) in reports {
+                        let scheduler_pacing = match scheduler_pacing.fill_time() {
+                            Some(fill_time) => format!("{}ms", fill_time.as_millis()),
+                            None => "disabled".to_string(),
+                        };
+                        println!(
+                            "block-production-method: {block_production_method} scheduler-pacing: \
+                             {scheduler_pacing}"
+                        );
+                        println!("{report}");
+                    }
+                    println!("Ok");
                 }
                 ("accounts", Some(arg_matches)) => {
                     let process_options = parse_process_options(&ledger_path, arg_matches);
@@ -3230,6 +3327,41 @@ fn main() {
                         println!("Capitalization: {}", Sol(bank.capitalization()));
                     }
                 }
+                ("calibrate-cost-model", Some(arg_matches)) => {
+                    let min_instructions = value_t_or_exit!(arg_matches, "min_instructions", u64);
+                    let output_format =
+                        OutputFormat::from_matches(arg_matches, "output_format", false);
+
+                    // Per-program timings are otherwise only collected at the trace log level
+                    enable_per_program_timings();
+                    let calibration = Arc::new(Mutex::new(CostCalibration::default()));
+                    let mut process_options = parse_process_options(&ledger_path, arg_matches);
+                    let program_timings_callback = Arc::new({
+                        let calibration = calibration.clone();
+                        move |_slot: Slot, per_program_timings: &HashMap<Pubkey, ProgramTiming>| {
+                            calibration.lock().unwrap().add_slot(per_program_timings);
+                        }
+                    });
+                    process_options.program_timings_callback =
+                        Some(program_timings_callback as ProcessProgramTimingsCallback);
+
+                    let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+                    let blockstore = open_blockstore(
+                        &ledger_path,
+                        arg_matches,
+                        get_access_type(&process_options),
+                    );
+                    let _ = load_and_process_ledger_or_exit(
+                        arg_matches,
+                        &genesis_config,
+                        Arc::new(blockstore),
+                        process_options,
+                        None,
+                    );
+
+                    let report = calibration.lock().unwrap().fit(min_instructions);
+                    println!("{}", output_format.formatted_string(&report));
+                }
                 ("compute-slot-cost", Some(arg_matches)) => {
                     let blockstore =
                         open_blockstore(&ledger_path, arg_matches, AccessType::ReadOnly);
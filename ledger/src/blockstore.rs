@@ -301,6 +301,7 @@ pub struct Blockstore {
     rewards_cf: LedgerColumn<cf::Rewards>,
     transaction_status_cf: LedgerColumn<cf::TransactionStatus>,
     transaction_memos_cf: LedgerColumn<cf::TransactionMemos>,
+    transaction_accounts_diff_cf: LedgerColumn<cf::TransactionAccountsDiff>,
     address_signatures_cf: LedgerColumn<cf::AddressSignatures>,
     perf_samples_cf: LedgerColumn<cf::PerfSamples>,
 
@@ -608,6 +609,7 @@ impl Blockstore {
         let rewards_cf = db.column();
         let transaction_status_cf = db.column();
         let transaction_memos_cf = db.column();
+        let transaction_accounts_diff_cf = db.column();
         let address_signatures_cf = db.column();
         let perf_samples_cf = db.column();
 
@@ -643,6 +645,7 @@ impl Blockstore {
             rewards_cf,
             roots_cf,
             transaction_memos_cf,
+            transaction_accounts_diff_cf,
             transaction_status_cf,
             alt_meta_cf,
             alt_index_cf,
@@ -1503,6 +1506,8 @@ impl Blockstore {
         self.rewards_cf.submit_rocksdb_cf_metrics();
         self.transaction_status_cf.submit_rocksdb_cf_metrics();
         self.transaction_memos_cf.submit_rocksdb_cf_metrics();
+        self.transaction_accounts_diff_cf
+            .submit_rocksdb_cf_metrics();
         self.address_signatures_cf.submit_rocksdb_cf_metrics();
         self.perf_samples_cf.submit_rocksdb_cf_metrics();
     }
@@ -4310,6 +4315,41 @@ impl Blockstore {
             .put_in_batch(db_write_batch, (*signature, slot), &memos)
     }
 
+    pub fn read_transaction_accounts_diff(
+        &self,
+        slot: Slot,
+        signature: Signature,
+    ) -> Result<Option<TransactionAccountsDiff>> {
+        self.transaction_accounts_diff_cf.get((slot, signature))
+    }
+
+    pub fn add_transaction_accounts_diff_to_batch(
+        &self,
+        slot: Slot,
+        signature: &Signature,
+        accounts_diff: &TransactionAccountsDiff,
+        db_write_batch: &mut WriteBatch,
+    ) -> Result<()> {
+        self.transaction_accounts_diff_cf.put_in_batch(
+            db_write_batch,
+            (slot, *signature),
+            accounts_diff,
+        )
+    }
+
+    /// Removes all transaction accounts diffs recorded for slots up to and
+    /// including `to_slot`.
+    ///
+    /// The accounts diff column is retained separately from the rest of the
+    /// ledger since it is typically much larger per slot, so callers prune it
+    /// on their own schedule in addition to the regular ledger cleanup.
+    pub fn purge_transaction_accounts_diffs(&self, to_slot: Slot) -> Result<()> {
+        let mut write_batch = self.get_write_batch()?;
+        self.transaction_accounts_diff_cf
+            .delete_range_in_batch(&mut write_batch, 0, to_slot);
+        self.write_batch(write_batch)
+    }
+
     /// Acquires the `lowest_cleanup_slot` lock and returns a tuple of the held lock
     /// and lowest available slot.
     ///
@@ -282,6 +282,8 @@ impl Blockstore {
             .delete_range_in_batch(write_batch, from_slot, to_slot);
         self.merkle_root_meta_cf
             .delete_range_in_batch(write_batch, from_slot, to_slot);
+        self.transaction_accounts_diff_cf
+            .delete_range_in_batch(write_batch, from_slot, to_slot);
 
         if purge_alt_columns {
             // Purge all alternate columns
@@ -346,6 +348,8 @@ impl Blockstore {
             .delete_file_in_range(from_slot, to_slot)?;
         self.merkle_root_meta_cf
             .delete_file_in_range(from_slot, to_slot)?;
+        self.transaction_accounts_diff_cf
+            .delete_file_in_range(from_slot, to_slot)?;
         self.alt_meta_cf.delete_file_in_range(from_slot, to_slot)?;
         self.alt_index_cf.delete_file_in_range(from_slot, to_slot)?;
         self.alt_data_shred_cf
@@ -695,6 +699,15 @@ pub mod tests {
                 .map(|(slot, _)| slot >= min_slot)
                 .unwrap_or(true)
         );
+        assert!(
+            blockstore
+                .transaction_accounts_diff_cf
+                .iter(IteratorMode::Start)
+                .unwrap()
+                .next()
+                .map(|((slot, _), _)| slot >= min_slot)
+                .unwrap_or(true)
+        );
     }
 
     #[test]
@@ -1054,6 +1067,69 @@ pub mod tests {
         assert_eq!(count, 1);
     }
 
+    #[test]
+    fn test_purge_transaction_accounts_diffs() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+
+        let accounts_diff = TransactionAccountsDiff {
+            accounts: vec![AccountPostState {
+                pubkey: Pubkey::new_unique(),
+                lamports: 42,
+                owner: Pubkey::new_unique(),
+                data: vec![1, 2, 3],
+                ..AccountPostState::default()
+            }],
+        };
+        let signatures: Vec<_> = (0..10).map(|_| Signature::new_unique()).collect();
+        let mut write_batch = blockstore.get_write_batch().unwrap();
+        for (slot, signature) in signatures.iter().enumerate() {
+            blockstore
+                .add_transaction_accounts_diff_to_batch(
+                    slot as Slot,
+                    signature,
+                    &accounts_diff,
+                    &mut write_batch,
+                )
+                .unwrap();
+        }
+        blockstore.write_batch(write_batch).unwrap();
+        assert_eq!(
+            blockstore
+                .read_transaction_accounts_diff(3, signatures[3])
+                .unwrap(),
+            Some(accounts_diff.clone())
+        );
+        // Diffs are keyed by slot, so a mismatched slot does not resolve
+        assert_eq!(
+            blockstore
+                .read_transaction_accounts_diff(4, signatures[3])
+                .unwrap(),
+            None
+        );
+
+        // The dedicated retention purge only touches the accounts diff column
+        blockstore.purge_transaction_accounts_diffs(4).unwrap();
+        for (slot, signature) in signatures.iter().enumerate() {
+            let accounts_diff = blockstore
+                .read_transaction_accounts_diff(slot as Slot, *signature)
+                .unwrap();
+            assert_eq!(accounts_diff.is_some(), slot > 4);
+        }
+
+        // The regular ledger purge also cleans the column
+        blockstore.purge_slots(0, 7, PurgeType::Exact).unwrap();
+        all_columns_empty_or_greater_than_slot(&blockstore, 8);
+        assert_eq!(
+            blockstore
+                .transaction_accounts_diff_cf
+                .iter(IteratorMode::Start)
+                .unwrap()
+                .count(),
+            2
+        );
+    }
+
     #[test]
     fn test_purge_slot_cleanup_chaining_missing_slot_meta() {
         let ledger_path = get_tmp_ledger_path_auto_delete!();
@@ -187,6 +187,18 @@ pub mod columns {
     /// * value type: [`String`]
     pub struct TransactionMemos;
 
+    #[derive(Debug)]
+    /// The transaction accounts diff column
+    ///
+    /// This column is optional; it is only populated when the node records the
+    /// post-execution state of the accounts written by each transaction. It is
+    /// keyed by slot first so that it can be range-purged independently of the
+    /// rest of the ledger.
+    ///
+    /// * index type: `(`[`Slot`]`, `[`Signature`]`)`
+    /// * value type: [`blockstore_meta::TransactionAccountsDiff`]
+    pub struct TransactionAccountsDiff;
+
     #[derive(Debug)]
     /// The rewards column
     ///
@@ -347,6 +359,10 @@ impl TypedColumn for columns::TransactionMemos {
     type Type = String;
 }
 
+impl TypedColumn for columns::TransactionAccountsDiff {
+    type Type = blockstore_meta::TransactionAccountsDiff;
+}
+
 impl<T: SlotColumn> Column for T {
     type Index = Slot;
     type Key = [u8; std::mem::size_of::<Slot>()];
@@ -478,6 +494,37 @@ impl ColumnName for columns::TransactionMemos {
     const NAME: &'static str = "transaction_memos";
 }
 
+impl Column for columns::TransactionAccountsDiff {
+    type Index = (Slot, Signature);
+    type Key = [u8; std::mem::size_of::<Slot>() + SIGNATURE_BYTES];
+
+    #[inline]
+    fn key((slot, signature): &Self::Index) -> Self::Key {
+        convert_column_index_to_key_bytes!(Key,
+            ..8 => &slot.to_be_bytes(),
+            8.. => signature.as_ref(),
+        )
+    }
+
+    fn index(key: &[u8]) -> Self::Index {
+        convert_column_key_bytes_to_index!(key,
+            0..8  => Slot::from_be_bytes,
+            8..72 => Signature::from,
+        )
+    }
+
+    fn slot(index: Self::Index) -> Slot {
+        index.0
+    }
+
+    fn as_index(slot: Slot) -> Self::Index {
+        (slot, Signature::default())
+    }
+}
+impl ColumnName for columns::TransactionAccountsDiff {
+    const NAME: &'static str = "transaction_accounts_diff";
+}
+
 impl SlotColumn for columns::Rewards {}
 impl ColumnName for columns::Rewards {
     const NAME: &'static str = "rewards";
@@ -10,6 +10,7 @@ use {
     bitflags::bitflags,
     solana_clock::{Slot, UnixTimestamp},
     solana_hash::{HASH_BYTES, Hash},
+    solana_pubkey::Pubkey,
     std::{
         fmt::{self, Debug, Display},
         ops::{Range, RangeBounds},
@@ -845,6 +846,23 @@ pub struct AddressSignatureMeta {
     pub writeable: bool,
 }
 
+/// The state of every account a transaction wrote to, as of right after the
+/// transaction was committed.
+#[derive(Clone, Debug, Default, SchemaRead, SchemaWrite, PartialEq, Eq)]
+pub struct TransactionAccountsDiff {
+    pub accounts: Vec<AccountPostState>,
+}
+
+#[derive(Clone, Debug, Default, SchemaRead, SchemaWrite, PartialEq, Eq)]
+pub struct AccountPostState {
+    pub pubkey: Pubkey,
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub data: Vec<u8>,
+}
+
 /// Performance information about validator execution during a time slice.
 ///
 #[repr(C)]
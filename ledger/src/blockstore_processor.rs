@@ -16,6 +16,7 @@ use {
     log::*,
     rayon::{ThreadPool, prelude::*},
     scopeguard::defer,
+    solana_account::AccountSharedData,
     solana_accounts_db::{
         accounts_db::AccountsDbConfig, accounts_update_notifier_interface::AccountsUpdateNotifier,
     },
@@ -53,10 +54,15 @@ use {
     solana_signature::Signature,
     solana_svm::{
         transaction_commit_result::{TransactionCommitResult, TransactionCommitResultExtensions},
-        transaction_processing_result::ProcessedTransaction,
+        transaction_processing_result::{
+            ProcessedTransaction, TransactionProcessingResult,
+            TransactionProcessingResultExtensions,
+        },
         transaction_processor::ExecutionRecordingConfig,
     },
-    solana_svm_timings::{ExecuteTimingType, ExecuteTimings, report_execute_timings},
+    solana_svm_timings::{
+        ExecuteTimingType, ExecuteTimings, ProgramTiming, report_execute_timings,
+    },
     solana_svm_transaction::{svm_message::SVMMessage, svm_transaction::SVMTransaction},
     solana_transaction::{
         TransactionVerificationMode, sanitized::SanitizedTransaction,
@@ -202,7 +208,12 @@ pub fn execute_batch<'a>(
     let record_transaction_meta = transaction_status_sender.is_some();
     let mut transaction_indexes = Cow::from(transaction_indexes);
 
+    let mut post_accounts = None;
     let pre_commit_callback = |_timings: &mut _, processing_results: &_| -> PreCommitResult {
+        post_accounts = transaction_status_sender.and_then(|transaction_status_sender| {
+            transaction_status_sender
+                .collect_post_accounts(batch.sanitized_transactions(), processing_results)
+        });
         match extra_pre_commit_callback {
             None => {
                 // We're entering into one of the block-verification methods.
@@ -334,6 +345,7 @@ pub fn execute_batch<'a>(
             token_balances,
             tx_costs,
             transaction_indexes.into_owned(),
+            post_accounts,
         );
     }
 
@@ -870,12 +882,19 @@ pub enum BlockstoreProcessorError {
 /// processing the blockstore
 pub type ProcessSlotCallback = Arc<dyn Fn(&Bank) + Sync + Send>;
 
+/// Callback for accessing the per-program execution timings of each slot
+/// replayed from the blockstore. Timings are only collected when trace level
+/// logging or `solana_svm_timings::enable_per_program_timings()` is enabled.
+pub type ProcessProgramTimingsCallback =
+    Arc<dyn Fn(Slot, &HashMap<Pubkey, ProgramTiming>) + Sync + Send>;
+
 #[derive(Default, Clone)]
 pub struct ProcessOptions {
     /// Run PoH, transaction signature and other transaction verification on the entries.
     pub run_verification: bool,
     pub halt_at_slot: Option<Slot>,
     pub slot_callback: Option<ProcessSlotCallback>,
+    pub program_timings_callback: Option<ProcessProgramTimingsCallback>,
     pub new_hard_forks: Option<Vec<Slot>>,
     pub debug_keys: Option<Arc<HashSet<Pubkey>>>,
     pub limit_load_slot_count_from_snapshot: Option<usize>,
@@ -2551,6 +2570,10 @@ fn load_frozen_forks(
             m.stop();
             process_single_slot_us += m.as_us();
 
+            if let Some(program_timings_callback) = &opts.program_timings_callback {
+                program_timings_callback(slot, &timing.details.per_program_timings);
+            }
+
             let mut m = Measure::start("voting");
             // If we've reached the last known root in blockstore, start looking
             // for newer cluster confirmed roots
@@ -2910,15 +2933,69 @@ pub struct TransactionStatusBatch {
     pub token_balances: TransactionTokenBalancesSet,
     pub costs: Vec<Option<u64>>,
     pub transaction_indexes: Vec<usize>,
+    pub post_accounts: Option<TransactionPostAccounts>,
 }
 
+/// The post-execution state of the accounts written by each transaction of a
+/// batch; empty for transactions that were not processed
+pub type TransactionPostAccounts = Vec<Vec<(Pubkey, AccountSharedData)>>;
+
 #[derive(Clone, Debug)]
 pub struct TransactionStatusSender {
     pub sender: Sender<TransactionStatusMessage>,
     pub dependency_tracker: Option<Arc<DependencyTracker>>,
+    /// Whether batches should carry the post-execution state of the accounts
+    /// written by each transaction
+    pub record_post_accounts: bool,
 }
 
 impl TransactionStatusSender {
+    /// Captures the state each processed transaction leaves its writable
+    /// accounts in if `record_post_accounts` is set.
+    ///
+    /// The states are taken from the execution results of the batch, so every
+    /// transaction reports its own writes even if a later transaction of the
+    /// same batch writes the same account. Transactions that failed execution
+    /// only report the accounts charged for fees.
+    pub fn collect_post_accounts(
+        &self,
+        transactions: &[impl SVMMessage],
+        processing_results: &[TransactionProcessingResult],
+    ) -> Option<TransactionPostAccounts> {
+        if !self.record_post_accounts {
+            return None;
+        }
+        let post_accounts = transactions
+            .iter()
+            .zip(processing_results)
+            .map(|(transaction, processing_result)| {
+                let rollback_accounts = match processing_result.processed_transaction() {
+                    None => return vec![],
+                    Some(ProcessedTransaction::Executed(executed_tx))
+                        if executed_tx.execution_details.status.is_ok() =>
+                    {
+                        return executed_tx
+                            .loaded_transaction
+                            .accounts
+                            .iter()
+                            .enumerate()
+                            .filter(|(index, _)| transaction.is_writable(*index))
+                            .map(|(_, (pubkey, account))| (*pubkey, account.clone()))
+                            .collect();
+                    }
+                    Some(ProcessedTransaction::Executed(executed_tx)) => {
+                        &executed_tx.loaded_transaction.rollback_accounts
+                    }
+                    Some(ProcessedTransaction::FeesOnly(fees_only_tx)) => {
+                        &fees_only_tx.rollback_accounts
+                    }
+                };
+                rollback_accounts.iter().cloned().collect()
+            })
+            .collect();
+        Some(post_accounts)
+    }
+
     pub fn send_transaction_status_batch(
         &self,
         slot: Slot,
@@ -2928,6 +3005,7 @@ impl TransactionStatusSender {
         token_balances: TransactionTokenBalancesSet,
         costs: Vec<Option<u64>>,
         transaction_indexes: Vec<usize>,
+        post_accounts: Option<TransactionPostAccounts>,
     ) {
         let work_sequence = self
             .dependency_tracker
@@ -2943,6 +3021,7 @@ impl TransactionStatusSender {
                 token_balances,
                 costs,
                 transaction_indexes,
+                post_accounts,
             },
             work_sequence,
         ))) {
@@ -3011,7 +3090,7 @@ pub mod tests {
         crossbeam_channel::bounded,
         rand::{Rng, rng},
         rayon::ThreadPoolBuilder,
-        solana_account::{AccountSharedData, WritableAccount},
+        solana_account::{AccountSharedData, ReadableAccount, WritableAccount},
         solana_bls_signatures::{BLS_SIGNATURE_AFFINE_SIZE, Signature as BLSSignature},
         solana_cost_model::cost_tracker::CostTrackerLimits,
         solana_entry::{
@@ -5634,6 +5713,7 @@ pub mod tests {
         let transaction_status_sender = TransactionStatusSender {
             sender: transaction_status_sender,
             dependency_tracker: None,
+            record_post_accounts: false,
         };
 
         let blockhash = bank.last_blockhash();
@@ -5963,6 +6043,7 @@ pub mod tests {
             Some(&TransactionStatusSender {
                 sender,
                 dependency_tracker: None,
+                record_post_accounts: false,
             }),
             None,
             ReplayVoteSendType::VerifiedExecuted,
@@ -6015,6 +6096,68 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_execute_batch_post_accounts() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(LAMPORTS_PER_SOL);
+        let (bank, _bank_forks) = Bank::new_with_bank_forks_for_tests(&genesis_config);
+        let pubkey = solana_pubkey::new_rand();
+        let amount = genesis_config.rent.minimum_balance(0);
+        // Both transactions write `pubkey`, like relaxed intra-batch account locks allow
+        let txs = [amount, amount + 1].map(|lamports| {
+            RuntimeTransaction::from_transaction_for_tests(system_transaction::transfer(
+                &mint_keypair,
+                &pubkey,
+                lamports,
+                genesis_config.hash(),
+            ))
+        });
+        let mut batch =
+            TransactionBatch::new(vec![Ok(()); 2], &bank, OwnedOrBorrowed::Borrowed(&txs));
+        batch.set_needs_unlock(false);
+        let batch = TransactionBatchWithIndexes {
+            batch,
+            transaction_indexes: vec![0, 1],
+        };
+        let (sender, receiver) = bounded(1024);
+        execute_batch(
+            &batch,
+            &bank,
+            Some(&TransactionStatusSender {
+                sender,
+                dependency_tracker: None,
+                record_post_accounts: true,
+            }),
+            None,
+            ReplayVoteSendType::VerifiedExecuted,
+            &mut ExecuteTimings::default(),
+            None,
+            None,
+            None::<fn(&_) -> _>,
+        )
+        .unwrap();
+
+        let Ok(TransactionStatusMessage::Batch((batch, _sequence))) = receiver.try_recv() else {
+            panic!("batch should have been sent");
+        };
+        let post_accounts = batch.post_accounts.unwrap();
+        let recipient_lamports = |accounts: &[(Pubkey, AccountSharedData)]| {
+            accounts
+                .iter()
+                .find(|(key, _)| *key == pubkey)
+                .unwrap()
+                .1
+                .lamports()
+        };
+        // Each transaction reports the state it left the account in
+        assert_eq!(recipient_lamports(&post_accounts[0]), amount);
+        assert_eq!(recipient_lamports(&post_accounts[1]), 2 * amount + 1);
+        assert_eq!(bank.get_balance(&pubkey), 2 * amount + 1);
+    }
+
     #[test]
     fn test_confirm_slot_entries_with_fix() {
         const HASHES_PER_TICK: u64 = 10;
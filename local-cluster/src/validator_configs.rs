@@ -54,6 +54,8 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         accounts_db_skip_shrink: config.accounts_db_skip_shrink,
         accounts_db_force_initial_clean: config.accounts_db_force_initial_clean,
         staked_nodes_overrides: config.staked_nodes_overrides.clone(),
+        rpc_rate_limiter: config.rpc_rate_limiter.clone(),
+        rpc_slow_query_log: config.rpc_slow_query_log.clone(),
         validator_exit: Arc::new(RwLock::new(Exit::default())),
         validator_exit_backpressure: config
             .validator_exit_backpressure
@@ -72,6 +74,7 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         block_production_num_workers: config.block_production_num_workers,
         block_production_scheduler_config: config.block_production_scheduler_config.clone(),
         enable_block_production_forwarding: config.enable_block_production_forwarding,
+        forwarding_policy: config.forwarding_policy.clone(),
         enable_scheduler_bindings: config.enable_scheduler_bindings,
         generator_config: config.generator_config.clone(),
         use_snapshot_archives_at_startup: config.use_snapshot_archives_at_startup,
@@ -30,6 +30,9 @@ pub const JSON_RPC_SERVER_ERROR_SLOT_NOT_EPOCH_BOUNDARY: i64 = -32018;
 pub const JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_UNREACHABLE: i64 = -32019;
 pub const JSON_RPC_SERVER_ERROR_FILTER_TRANSACTION_NOT_FOUND: i64 = -32020;
 pub const JSON_RPC_SERVER_ERROR_NO_SLOT_HISTORY: i64 = -32021;
+pub const JSON_RPC_SERVER_ERROR_RATE_LIMITED: i64 = -32022;
+pub const JSON_RPC_SERVER_ERROR_PINNED_SLOT_NOT_AVAILABLE: i64 = -32023;
+pub const JSON_RPC_SERVER_ERROR_PINNED_SLOT_COMMITMENT_NOT_REACHED: i64 = -32024;
 
 #[derive(Error, Debug)]
 #[allow(clippy::large_enum_variant)]
@@ -86,6 +89,18 @@ pub enum RpcCustomError {
     FilterTransactionNotFound { signature: String },
     #[error("NoSlotHistory")]
     NoSlotHistory,
+    #[error("RateLimited")]
+    RateLimited { method: String, retry_after_ms: u64 },
+    #[error("PinnedSlotNotAvailable")]
+    PinnedSlotNotAvailable { slot: Slot },
+    #[error("PinnedSlotCommitmentNotReached")]
+    PinnedSlotCommitmentNotReached { slot: Slot, commitment: String },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitedErrorData {
+    pub retry_after_ms: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -275,6 +290,26 @@ impl From<RpcCustomError> for Error {
                 message: "No slot history".to_string(),
                 data: None,
             },
+            RpcCustomError::RateLimited {
+                method,
+                retry_after_ms,
+            } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_RATE_LIMITED),
+                message: format!("Rate limit exceeded for {method}; retry in {retry_after_ms}ms"),
+                data: Some(serde_json::json!(RateLimitedErrorData { retry_after_ms })),
+            },
+            RpcCustomError::PinnedSlotNotAvailable { slot } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_PINNED_SLOT_NOT_AVAILABLE),
+                message: format!("Bank for pinned slot {slot} is not available on this node"),
+                data: None,
+            },
+            RpcCustomError::PinnedSlotCommitmentNotReached { slot, commitment } => Self {
+                code: ErrorCode::ServerError(
+                    JSON_RPC_SERVER_ERROR_PINNED_SLOT_COMMITMENT_NOT_REACHED,
+                ),
+                message: format!("Pinned slot {slot} has not reached {commitment} commitment"),
+                data: None,
+            },
         }
     }
 }
@@ -8,13 +8,13 @@ pub use solana_rpc_client_types::response::{
     RpcConfirmedTransactionStatusWithSignature, RpcContactInfo, RpcFeeCalculator,
     RpcFeeRateGovernor, RpcIdentity, RpcInflationGovernor, RpcInflationRate, RpcInflationReward,
     RpcKeyedAccount, RpcLeaderSchedule, RpcLogsResponse, RpcPerfSample, RpcPrioritizationFee,
-    RpcResponseContext, RpcSignatureConfirmation, RpcSignatureResult, RpcSimulateTransactionResult,
-    RpcSnapshotSlotInfo, RpcStorageTurn, RpcSupply, RpcTokenAccountBalance, RpcVersionInfo,
-    RpcVote, RpcVoteAccountInfo, RpcVoteAccountStatus, SlotInfo, SlotTransactionStats, SlotUpdate,
-    StakeActivationState, TransactionBinaryEncoding, TransactionConfirmationStatus,
-    TransactionError, TransactionParsedAccount, TransactionResult, UiAccount, UiAccountData,
-    UiAccountEncoding, UiAccountsList, UiCompiledInstruction, UiConfirmedBlock,
-    UiInnerInstructions, UiInstruction, UiLoadedAddresses, UiParsedInstruction,
+    RpcPriorityFeeEstimate, RpcResponseContext, RpcSignatureConfirmation, RpcSignatureResult,
+    RpcSimulateTransactionResult, RpcSnapshotSlotInfo, RpcStorageTurn, RpcSupply,
+    RpcTokenAccountBalance, RpcVersionInfo, RpcVote, RpcVoteAccountInfo, RpcVoteAccountStatus,
+    SlotInfo, SlotTransactionStats, SlotUpdate, StakeActivationState, TransactionBinaryEncoding,
+    TransactionConfirmationStatus, TransactionError, TransactionParsedAccount, TransactionResult,
+    UiAccount, UiAccountData, UiAccountEncoding, UiAccountsList, UiCompiledInstruction,
+    UiConfirmedBlock, UiInnerInstructions, UiInstruction, UiLoadedAddresses, UiParsedInstruction,
     UiPartiallyDecodedInstruction, UiReturnDataEncoding, UiTokenAmount, UiTransactionError,
     UiTransactionReturnData, UiTransactionStatusMeta, UiTransactionTokenBalance, Value,
     transaction,
@@ -336,6 +336,13 @@ impl RpcBlocksConfigWrapper {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcPriorityFeeEstimateConfig {
+    /// Number of most recent blocks to estimate from; defaults to all cached blocks.
+    pub lookback_slots: Option<usize>,
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcContextConfig {
@@ -42,6 +42,7 @@ pub enum RpcRequest {
     GetProgramAccounts,
     GetRecentPerformanceSamples,
     GetRecentPrioritizationFees,
+    GetPriorityFeeEstimate,
     GetHighestSnapshotSlot,
     GetSignaturesForAddress,
     GetSignatureStatuses,
@@ -109,6 +110,7 @@ impl RpcRequest {
             RpcRequest::GetProgramAccounts => "getProgramAccounts",
             RpcRequest::GetRecentPerformanceSamples => "getRecentPerformanceSamples",
             RpcRequest::GetRecentPrioritizationFees => "getRecentPrioritizationFees",
+            RpcRequest::GetPriorityFeeEstimate => "getPriorityFeeEstimate",
             RpcRequest::GetHighestSnapshotSlot => "getHighestSnapshotSlot",
             RpcRequest::GetSignaturesForAddress => "getSignaturesForAddress",
             RpcRequest::GetSignatureStatuses => "getSignatureStatuses",
@@ -588,6 +588,16 @@ pub struct RpcPrioritizationFee {
     pub prioritization_fee: u64,
 }
 
+/// Percentiles of the compute unit prices paid in recent blocks, in micro-lamports.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcPriorityFeeEstimate {
+    pub p25: u64,
+    pub p50: u64,
+    pub p75: u64,
+    pub p95: u64,
+}
+
 #[cfg(test)]
 pub mod tests {
 
@@ -0,0 +1,169 @@
+use {
+    solana_keypair::Keypair,
+    solana_net_utils::sockets,
+    solana_pubkey::Pubkey,
+    solana_rpc::{
+        rpc_grpc_service::proto::{
+            AccountSubscribeRequest, BlockSubscribeRequest, Commitment, LogsSubscribeRequest,
+            SlotSubscribeRequest, TransactionDetails, rpc_stream_client::RpcStreamClient,
+        },
+        rpc_pubsub_service::PubSubConfig,
+    },
+    solana_system_transaction as system_transaction,
+    solana_test_validator::{TestValidator, TestValidatorGenesis},
+    solana_transaction::versioned::VersionedTransaction,
+    std::net::{IpAddr, Ipv4Addr, SocketAddr},
+    tokio::time::{Duration, Instant, sleep, timeout},
+    tonic::{Streaming, transport::Channel},
+};
+
+async fn start_validator_with_grpc() -> (TestValidator, Keypair) {
+    let grpc_port = sockets::unique_port_range_for_tests(1).start;
+    TestValidatorGenesis::default_for_tests()
+        .pubsub_config(PubSubConfig {
+            grpc_addr: Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), grpc_port)),
+            enable_block_subscription: true,
+            ..PubSubConfig::default_for_tests()
+        })
+        .start_async()
+        .await
+}
+
+async fn connect(test_validator: &TestValidator) -> RpcStreamClient<Channel> {
+    let grpc_url = test_validator.rpc_grpc_url().unwrap();
+    let deadline = Instant::now() + Duration::from_secs(30);
+    loop {
+        match RpcStreamClient::connect(grpc_url.clone()).await {
+            Ok(client) => return client,
+            Err(err) => {
+                assert!(
+                    Instant::now() <= deadline,
+                    "Timed out connecting to {grpc_url}: {err}"
+                );
+                sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+async fn next_notification<T>(stream: &mut Streaming<T>) -> T {
+    timeout(Duration::from_secs(10), stream.message())
+        .await
+        .expect("timed out waiting for a notification")
+        .unwrap()
+        .expect("stream ended")
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_grpc_slot_stream() {
+    let (test_validator, _) = start_validator_with_grpc().await;
+    let mut client = connect(&test_validator).await;
+
+    let mut slots = client
+        .slot_subscribe(SlotSubscribeRequest {})
+        .await
+        .unwrap()
+        .into_inner();
+
+    let first = next_notification(&mut slots).await;
+    let second = next_notification(&mut slots).await;
+    assert_eq!(first.subscription_id, second.subscription_id);
+    assert!(second.slot > first.slot);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_grpc_account_and_logs_streams() {
+    let (test_validator, mint_keypair) = start_validator_with_grpc().await;
+    let mut client = connect(&test_validator).await;
+    let rpc_client = test_validator.get_async_rpc_client();
+    let recipient = Pubkey::new_unique();
+
+    let mut accounts = client
+        .account_subscribe(AccountSubscribeRequest {
+            pubkey: recipient.to_bytes().to_vec(),
+            commitment: Commitment::Processed.into(),
+            ..AccountSubscribeRequest::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let mut logs = client
+        .logs_subscribe(LogsSubscribeRequest {
+            commitment: Commitment::Processed.into(),
+            mentions: Some(recipient.to_bytes().to_vec()),
+            include_votes: false,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    let blockhash = rpc_client.get_latest_blockhash().await.unwrap();
+    let tx = system_transaction::transfer(&mint_keypair, &recipient, 1_000_000_000, blockhash);
+    rpc_client.send_and_confirm_transaction(&tx).await.unwrap();
+
+    let account = next_notification(&mut accounts).await.account.unwrap();
+    assert_eq!(account.lamports, 1_000_000_000);
+    // Owned by the system program.
+    assert_eq!(account.owner, Pubkey::default().to_bytes());
+    assert!(account.data.is_empty());
+
+    let logs = next_notification(&mut logs).await;
+    assert_eq!(logs.signature, tx.signatures[0].as_ref());
+    assert_eq!(logs.err, None);
+    assert!(!logs.logs.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_grpc_block_stream() {
+    let (test_validator, mint_keypair) = start_validator_with_grpc().await;
+    let mut client = connect(&test_validator).await;
+    let rpc_client = test_validator.get_async_rpc_client();
+    let recipient = Pubkey::new_unique();
+
+    let mut blocks = client
+        .block_subscribe(BlockSubscribeRequest {
+            commitment: Commitment::Confirmed.into(),
+            mentions_account_or_program: Some(recipient.to_bytes().to_vec()),
+            transaction_details: TransactionDetails::Full.into(),
+            show_rewards: false,
+            max_supported_transaction_version: Some(0),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    let blockhash = rpc_client.get_latest_blockhash().await.unwrap();
+    let tx = system_transaction::transfer(&mint_keypair, &recipient, 1_000_000_000, blockhash);
+    rpc_client.send_and_confirm_transaction(&tx).await.unwrap();
+
+    // Only blocks that mention `recipient` are streamed.
+    let notification = next_notification(&mut blocks).await;
+    assert_eq!(notification.err, None);
+    let block = notification.block.unwrap();
+    assert_eq!(block.blockhash.len(), 32);
+    assert!(block.signatures.is_empty());
+    assert_eq!(block.transactions.len(), 1);
+
+    let transaction = &block.transactions[0];
+    let versioned_transaction: VersionedTransaction =
+        bincode::deserialize(&transaction.transaction).unwrap();
+    assert_eq!(versioned_transaction.signatures, tx.signatures);
+    let meta = transaction.meta.as_ref().unwrap();
+    assert_eq!(meta.err, None);
+    assert_eq!(meta.post_balances.len(), meta.pre_balances.len());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_grpc_invalid_pubkey() {
+    let (test_validator, _) = start_validator_with_grpc().await;
+    let mut client = connect(&test_validator).await;
+
+    let status = client
+        .account_subscribe(AccountSubscribeRequest {
+            pubkey: vec![1, 2, 3],
+            ..AccountSubscribeRequest::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
@@ -6,12 +6,16 @@ pub mod max_slots;
 pub mod optimistically_confirmed_bank_tracker;
 pub mod parsed_token_accounts;
 pub mod rpc;
+pub mod rpc_bank_pin;
 mod rpc_cache;
 pub mod rpc_completed_slots_service;
+pub mod rpc_grpc_service;
 pub mod rpc_health;
 pub mod rpc_pubsub;
 pub mod rpc_pubsub_service;
+pub mod rpc_rate_limiter;
 pub mod rpc_service;
+pub mod rpc_slow_query_log;
 pub mod rpc_subscription_tracker;
 pub mod rpc_subscriptions;
 pub mod slot_status_notifier;
@@ -3,9 +3,15 @@
 use solana_runtime::installed_scheduler_pool::BankWithScheduler;
 use {
     crate::{
-        filter::filter_allows, max_slots::MaxSlots,
+        filter::filter_allows,
+        max_slots::MaxSlots,
         optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
-        parsed_token_accounts::*, rpc_cache::LargestAccountsCache, rpc_health::*,
+        parsed_token_accounts::*,
+        rpc_bank_pin::{RpcBankPin, RpcBankPinTarget},
+        rpc_cache::LargestAccountsCache,
+        rpc_health::*,
+        rpc_rate_limiter::RpcClientKey,
+        rpc_slow_query_log::RpcRequestTrace,
     },
     agave_snapshots::{paths as snapshot_paths, snapshot_config::SnapshotConfig},
     agave_votor_messages::certificate::Certificate,
@@ -17,7 +23,7 @@ use {
         types::error,
     },
     jsonrpc_derive::rpc,
-    solana_account::{AccountSharedData, ReadableAccount},
+    solana_account::{Account, AccountSharedData, ReadableAccount},
     solana_account_decoder::{
         MAX_BASE58_BYTES, UiAccount, UiAccountEncoding, UiDataSliceConfig, encode_ui_account,
         parse_account_data::SplTokenAdditionalDataV2,
@@ -41,9 +47,10 @@ use {
     solana_keypair::Keypair,
     solana_ledger::{
         blockstore::{Blockstore, BlockstoreError, SignatureInfosForAddress},
-        blockstore_meta::PerfSample,
+        blockstore_meta::{AccountPostState, PerfSample},
         leader_schedule_cache::LeaderScheduleCache,
     },
+    solana_measure::measure_us,
     solana_message::{AddressLoader, SanitizedMessage},
     solana_metrics::inc_new_counter_info,
     solana_perf::packet::PACKET_DATA_SIZE,
@@ -67,7 +74,7 @@ use {
         bank_forks::BankForks,
         commitment::{BlockCommitmentArray, BlockCommitmentCache},
         non_circulating_supply::{NonCirculatingSupply, calculate_non_circulating_supply},
-        prioritization_fee_cache::PrioritizationFeeCache,
+        prioritization_fee_cache::{PrioritizationFeeCache, PrioritizationFeeEstimate},
         stake_utils,
     },
     solana_runtime_transaction::runtime_transaction::RuntimeTransaction,
@@ -85,7 +92,8 @@ use {
         BlockEncodingOptions, ConfirmedBlock, ConfirmedTransactionStatusWithSignature,
         ConfirmedTransactionWithStatusMeta, EncodedConfirmedTransactionWithStatusMeta, Reward,
         RewardType, Rewards, TransactionBinaryEncoding, TransactionConfirmationStatus,
-        TransactionStatus, UiConfirmedBlock, UiTransactionEncoding, map_inner_instructions,
+        TransactionDetails, TransactionStatus, UiAccountPostState, UiConfirmedBlock,
+        UiTransactionAccountsDiff, UiTransactionEncoding, map_inner_instructions,
     },
     solana_validator_exit::Exit,
     solana_vote_program::vote_state::MAX_LOCKOUT_HISTORY,
@@ -138,6 +146,7 @@ type RpcCustomResult<T> = std::result::Result<T, RpcCustomError>;
 
 pub const MAX_REQUEST_BODY_SIZE: usize = 50 * (1 << 10); // 50kB
 pub const PERFORMANCE_SAMPLES_LIMIT: usize = 720;
+pub const DEFAULT_RPC_ACCOUNTS_DIFF_RETENTION_SLOTS: u64 = 10_000;
 
 fn new_response<T>(bank: &Bank, value: T) -> RpcResponse<T> {
     RpcResponse {
@@ -146,6 +155,36 @@ fn new_response<T>(bank: &Bank, value: T) -> RpcResponse<T> {
     }
 }
 
+fn block_signatures(confirmed_block: &ConfirmedBlock) -> Vec<Signature> {
+    confirmed_block
+        .transactions
+        .iter()
+        .map(|transaction| *transaction.transaction_signature())
+        .collect()
+}
+
+fn encode_account_post_state(post_state: AccountPostState) -> UiAccountPostState {
+    let AccountPostState {
+        pubkey,
+        lamports,
+        owner,
+        executable,
+        rent_epoch,
+        data,
+    } = post_state;
+    let account = Account {
+        lamports,
+        data,
+        owner,
+        executable,
+        rent_epoch,
+    };
+    UiAccountPostState {
+        pubkey: pubkey.to_string(),
+        account: encode_ui_account(&pubkey, &account, UiAccountEncoding::Base64, None, None),
+    }
+}
+
 fn is_finalized(
     block_commitment_cache: &BlockCommitmentCache,
     bank: &Bank,
@@ -176,6 +215,10 @@ pub struct JsonRpcConfig {
     pub scan_results_limit_bytes: Option<usize>,
     /// Disable the health check, used for tests and TestValidator
     pub disable_health_check: bool,
+    /// If set, record the post-execution state of the accounts written by each
+    /// transaction and keep it for this many slots. Served by `getBlock` with
+    /// the `accountsDiff` transaction details level.
+    pub accounts_diff_retention_slots: Option<u64>,
 }
 
 impl Default for JsonRpcConfig {
@@ -197,6 +240,7 @@ impl Default for JsonRpcConfig {
             max_request_body_size: Option::default(),
             scan_results_limit_bytes: Option::default(),
             disable_health_check: Default::default(),
+            accounts_diff_retention_slots: Option::default(),
         }
     }
 }
@@ -255,6 +299,10 @@ pub struct JsonRpcRequestProcessor {
     max_complete_transaction_status_slot: Arc<AtomicU64>,
     prioritization_fee_cache: Option<Arc<PrioritizationFeeCache>>,
     runtime: Arc<Runtime>,
+    rpc_client_key: Option<RpcClientKey>,
+    request_trace: Option<Arc<RpcRequestTrace>>,
+    bank_pin: Option<std::result::Result<RpcBankPin, String>>,
+    pinned_bank: Option<Arc<Bank>>,
 }
 impl Metadata for JsonRpcRequestProcessor {}
 
@@ -265,6 +313,70 @@ impl JsonRpcRequestProcessor {
             ..self.clone()
         }
     }
+
+    /// Attributes the calls of a request to `rpc_client_key` for rate limiting
+    pub fn with_rpc_client_key(self, rpc_client_key: Option<RpcClientKey>) -> Self {
+        Self {
+            rpc_client_key,
+            ..self
+        }
+    }
+
+    pub fn rpc_client_key(&self) -> Option<&RpcClientKey> {
+        self.rpc_client_key.as_ref()
+    }
+
+    /// Records where a call spends its time into `request_trace`
+    pub fn with_request_trace(self, request_trace: Arc<RpcRequestTrace>) -> Self {
+        Self {
+            request_trace: Some(request_trace),
+            ..self
+        }
+    }
+
+    fn trace(&self, record: impl FnOnce(&RpcRequestTrace)) {
+        if let Some(request_trace) = &self.request_trace {
+            record(request_trace);
+        }
+    }
+
+    /// Requests that every call of the request be served from the bank
+    /// selected by `bank_pin`, see [`Self::pin_bank`]. An error holds the
+    /// reason the pin could not be parsed and fails the whole request.
+    pub fn with_bank_pin(self, bank_pin: Option<std::result::Result<RpcBankPin, String>>) -> Self {
+        Self { bank_pin, ..self }
+    }
+
+    /// Resolves the bank requested by [`Self::with_bank_pin`] once, so that
+    /// every call made with the returned processor reads from it
+    pub fn pin_bank(self) -> Result<Self> {
+        let bank_pin = match &self.bank_pin {
+            None => return Ok(self),
+            Some(Err(message)) => return Err(Error::invalid_params(message.clone())),
+            Some(Ok(bank_pin)) => *bank_pin,
+        };
+        let bank = match bank_pin.target {
+            RpcBankPinTarget::Slot(slot) => self
+                .bank_forks
+                .read()
+                .unwrap()
+                .get(slot)
+                .ok_or(RpcCustomError::PinnedSlotNotAvailable { slot })?,
+            RpcBankPinTarget::Commitment(commitment) => self.lookup_bank(Some(commitment)),
+        };
+        if let Some(min_context_slot) = bank_pin.min_context_slot {
+            if bank.slot() < min_context_slot {
+                return Err(RpcCustomError::MinContextSlotNotReached {
+                    context_slot: bank.slot(),
+                }
+                .into());
+            }
+        }
+        Ok(Self {
+            pinned_bank: Some(bank),
+            ..self
+        })
+    }
 }
 
 impl JsonRpcRequestProcessor {
@@ -273,7 +385,7 @@ impl JsonRpcRequestProcessor {
             commitment,
             min_context_slot,
         } = config;
-        let bank = self.bank(commitment);
+        let bank = self.bank(commitment)?;
         if let Some(min_context_slot) = min_context_slot {
             if bank.slot() < min_context_slot {
                 return Err(RpcCustomError::MinContextSlotNotReached {
@@ -315,27 +427,30 @@ impl JsonRpcRequestProcessor {
         let index_key = index_key.to_owned();
         let program_id = program_id.to_owned();
         let byte_limit_for_scans = self.config.scan_results_limit_bytes;
-        let mut accounts = self
-            .runtime
-            .spawn_blocking(move || {
-                bank.get_filtered_indexed_accounts(
-                    &index_key,
-                    |account| {
-                        // The program-id account index checks for Account owner on inclusion.
-                        // However, due to the current AccountsDb implementation, an account may
-                        // remain in storage as a zero-lamport AccountSharedData::Default() after
-                        // being wiped and reinitialized in later updates. We include the redundant
-                        // filters here to avoid returning these accounts.
-                        account.owner().eq(&program_id)
-                            && filters
-                                .iter()
-                                .all(|filter_type| filter_allows(filter_type, account))
-                    },
-                    byte_limit_for_scans,
-                )
-            })
-            .await
-            .expect("Failed to spawn blocking task")?;
+        let (accounts, accounts_scan_us) = measure_us!(
+            self.runtime
+                .spawn_blocking(move || {
+                    bank.get_filtered_indexed_accounts(
+                        &index_key,
+                        |account| {
+                            // The program-id account index checks for Account owner on inclusion.
+                            // However, due to the current AccountsDb implementation, an account
+                            // may remain in storage as a zero-lamport AccountSharedData::Default()
+                            // after being wiped and reinitialized in later updates. We include the
+                            // redundant filters here to avoid returning these accounts.
+                            account.owner().eq(&program_id)
+                                && filters
+                                    .iter()
+                                    .all(|filter_type| filter_allows(filter_type, account))
+                        },
+                        byte_limit_for_scans,
+                    )
+                })
+                .await
+                .expect("Failed to spawn blocking task")
+        );
+        self.trace(|trace| trace.record_accounts_scan(accounts_scan_us));
+        let mut accounts = accounts?;
         if sort_results {
             // Avoid copying pubkeys (using Ord::cmp(a, b) silences clippy::unnecessary_sort_by).
             accounts.sort_unstable_by(|(addr_a, _), (addr_b, _)| Ord::cmp(addr_a, addr_b));
@@ -343,8 +458,51 @@ impl JsonRpcRequestProcessor {
         Ok(accounts)
     }
 
+    fn bank(&self, commitment: Option<CommitmentConfig>) -> RpcCustomResult<Arc<Bank>> {
+        let (bank, bank_lookup_us) = measure_us!(self.lookup_bank(commitment));
+        self.trace(|trace| trace.record_bank_lookup(bank.slot(), bank_lookup_us));
+        if self.pinned_bank.is_some() {
+            self.check_pinned_bank_commitment(&bank, commitment)?;
+        }
+        Ok(bank)
+    }
+
+    /// Fails unless the pinned `bank` is on the fork of the bank `commitment`
+    /// resolves to, so that a pinned call never reads a bank that is less
+    /// confirmed than it asked for
+    fn check_pinned_bank_commitment(
+        &self,
+        bank: &Bank,
+        commitment: Option<CommitmentConfig>,
+    ) -> RpcCustomResult<()> {
+        let commitment = commitment.unwrap_or_default();
+        let commitment_name = match commitment.commitment {
+            CommitmentLevel::Processed => return Ok(()),
+            CommitmentLevel::Confirmed => "confirmed",
+            CommitmentLevel::Finalized => "finalized",
+        };
+        let commitment_bank = self.lookup_unpinned_bank(Some(commitment));
+        if bank.slot() != commitment_bank.slot()
+            && !commitment_bank.ancestors.contains_key(&bank.slot())
+        {
+            return Err(RpcCustomError::PinnedSlotCommitmentNotReached {
+                slot: bank.slot(),
+                commitment: commitment_name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn lookup_bank(&self, commitment: Option<CommitmentConfig>) -> Arc<Bank> {
+        if let Some(pinned_bank) = &self.pinned_bank {
+            debug!("RPC using pinned slot: {:?}", pinned_bank.slot());
+            return Arc::clone(pinned_bank);
+        }
+        self.lookup_unpinned_bank(commitment)
+    }
+
     #[allow(deprecated)]
-    fn bank(&self, commitment: Option<CommitmentConfig>) -> Arc<Bank> {
+    fn lookup_unpinned_bank(&self, commitment: Option<CommitmentConfig>) -> Arc<Bank> {
         debug!("RPC commitment_config: {commitment:?}");
 
         let commitment = commitment.unwrap_or_default();
@@ -397,7 +555,9 @@ impl JsonRpcRequestProcessor {
     }
 
     fn genesis_creation_time(&self) -> UnixTimestamp {
-        self.bank(None).genesis_creation_time()
+        // The genesis creation time is the same in every bank, so the commitment
+        // of a pinned bank does not matter
+        self.lookup_bank(None).genesis_creation_time()
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -441,6 +601,10 @@ impl JsonRpcRequestProcessor {
                 max_complete_transaction_status_slot,
                 prioritization_fee_cache,
                 runtime,
+                rpc_client_key: None,
+                request_trace: None,
+                bank_pin: None,
+                pinned_bank: None,
             },
             transaction_receiver,
         )
@@ -524,6 +688,10 @@ impl JsonRpcRequestProcessor {
             max_complete_transaction_status_slot: Arc::new(AtomicU64::default()),
             prioritization_fee_cache: Some(Arc::new(PrioritizationFeeCache::default())),
             runtime,
+            rpc_client_key: None,
+            request_trace: None,
+            bank_pin: None,
+            pinned_bank: None,
         }
     }
 
@@ -591,9 +759,10 @@ impl JsonRpcRequestProcessor {
         &self,
         data_len: usize,
         commitment: Option<CommitmentConfig>,
-    ) -> u64 {
-        self.bank(commitment)
-            .get_minimum_balance_for_rent_exemption(data_len)
+    ) -> Result<u64> {
+        Ok(self
+            .bank(commitment)?
+            .get_minimum_balance_for_rent_exemption(data_len))
     }
 
     pub async fn get_program_accounts(
@@ -645,7 +814,7 @@ impl JsonRpcRequestProcessor {
                 .await?
             }
         };
-        let accounts = if is_known_spl_token_id(&program_id)
+        let (accounts, encoding_us) = measure_us!(if is_known_spl_token_id(&program_id)
             && encoding == UiAccountEncoding::JsonParsed
         {
             get_parsed_token_accounts(Arc::clone(&bank), keyed_accounts.into_iter()).collect()
@@ -659,7 +828,8 @@ impl JsonRpcRequestProcessor {
                     })
                 })
                 .collect::<Result<Vec<_>>>()?
-        };
+        });
+        self.trace(|trace| trace.record_encoding(encoding_us));
         Ok(match with_context {
             true => OptionalContext::Context(new_response(&bank, accounts)),
             false => OptionalContext::NoContext(accounts),
@@ -896,12 +1066,12 @@ impl JsonRpcRequestProcessor {
     pub fn get_inflation_governor(
         &self,
         commitment: Option<CommitmentConfig>,
-    ) -> RpcInflationGovernor {
-        self.bank(commitment).inflation().into()
+    ) -> Result<RpcInflationGovernor> {
+        Ok(self.bank(commitment)?.inflation().into())
     }
 
-    pub fn get_inflation_rate(&self) -> RpcInflationRate {
-        let bank = self.bank(None);
+    pub fn get_inflation_rate(&self) -> Result<RpcInflationRate> {
+        let bank = self.bank(None)?;
         let epoch = bank.epoch();
         let inflation = bank.inflation();
         let slot_in_year = bank.slot_in_year_for_inflation();
@@ -917,13 +1087,13 @@ impl JsonRpcRequestProcessor {
     pub fn get_epoch_schedule(&self) -> EpochSchedule {
         // Since epoch schedule data comes from the genesis config, any commitment level should be
         // fine
-        let bank = self.bank(Some(CommitmentConfig::finalized()));
+        let bank = self.lookup_bank(Some(CommitmentConfig::finalized()));
         bank.epoch_schedule().clone()
     }
 
-    pub fn get_ag_genesis_cert(&self) -> Option<Certificate> {
-        let bank = self.bank(Some(CommitmentConfig::finalized()));
-        bank.get_alpenglow_genesis_certificate()
+    pub fn get_ag_genesis_cert(&self) -> Result<Option<Certificate>> {
+        let bank = self.bank(Some(CommitmentConfig::finalized()))?;
+        Ok(bank.get_alpenglow_genesis_certificate())
     }
 
     pub fn get_balance(
@@ -940,7 +1110,7 @@ impl JsonRpcRequestProcessor {
         signature: &Signature,
         commitment: Option<CommitmentConfig>,
     ) -> Result<RpcResponse<bool>> {
-        let bank = self.bank(commitment);
+        let bank = self.bank(commitment)?;
         let status = bank.get_signature_status(signature);
         match status {
             Some(status) => Ok(new_response(&bank, status.is_ok())),
@@ -987,7 +1157,7 @@ impl JsonRpcRequestProcessor {
         start_slot: Slot,
         limit: usize,
     ) -> Result<Vec<Pubkey>> {
-        let bank = self.bank(commitment);
+        let bank = self.bank(commitment)?;
 
         let (mut epoch, mut slot_index) =
             bank.epoch_schedule().get_epoch_and_slot_index(start_slot);
@@ -1058,7 +1228,7 @@ impl JsonRpcRequestProcessor {
         config: Option<RpcLargestAccountsConfig>,
     ) -> RpcCustomResult<RpcResponse<Vec<RpcAccountBalance>>> {
         let config = config.unwrap_or_default();
-        let bank = self.bank(config.commitment);
+        let bank = self.bank(config.commitment)?;
 
         if let Some((slot, accounts)) = self.get_cached_largest_accounts(&config.filter) {
             Ok(RpcResponse {
@@ -1112,7 +1282,7 @@ impl JsonRpcRequestProcessor {
         config: Option<RpcSupplyConfig>,
     ) -> RpcCustomResult<RpcResponse<RpcSupply>> {
         let config = config.unwrap_or_default();
-        let bank = self.bank(config.commitment);
+        let bank = self.bank(config.commitment)?;
         let non_circulating_supply =
             self.calculate_non_circulating_supply(&bank)
                 .await
@@ -1153,7 +1323,7 @@ impl JsonRpcRequestProcessor {
             None
         };
 
-        let bank = self.bank(config.commitment);
+        let bank = self.bank(config.commitment)?;
         let commission_rate_in_basis_points = bank
             .feature_set
             .is_active(&agave_feature_set::commission_rate_in_basis_points::id());
@@ -1318,6 +1488,13 @@ impl JsonRpcRequestProcessor {
             show_rewards: config.rewards.unwrap_or(true),
             max_supported_transaction_version: config.max_supported_transaction_version,
         };
+        let include_accounts_diffs =
+            encoding_options.transaction_details == TransactionDetails::AccountsDiff;
+        if include_accounts_diffs && self.config.accounts_diff_retention_slots.is_none() {
+            return Err(Error::invalid_params(
+                "accountsDiff transaction details are not enabled on this node",
+            ));
+        }
         let commitment = config.commitment.unwrap_or_default();
         check_is_at_least_confirmed(commitment)?;
 
@@ -1340,19 +1517,27 @@ impl JsonRpcRequestProcessor {
                 .expect("Failed to spawn blocking task");
             self.check_blockstore_root(&result, slot)?;
             let encode_block = |confirmed_block: ConfirmedBlock| async move {
-                let mut encoded_block = self
-                    .runtime
-                    .spawn_blocking(move || {
-                        confirmed_block
-                            .encode_with_options(encoding, encoding_options)
-                            .map_err(RpcCustomError::from)
-                    })
-                    .await
-                    .expect("Failed to spawn blocking task")?;
+                let signatures = include_accounts_diffs.then(|| block_signatures(&confirmed_block));
+                let (encoded_block, encoding_us) = measure_us!(
+                    self.runtime
+                        .spawn_blocking(move || {
+                            confirmed_block
+                                .encode_with_options(encoding, encoding_options)
+                                .map_err(RpcCustomError::from)
+                        })
+                        .await
+                        .expect("Failed to spawn blocking task")
+                );
+                self.trace(|trace| trace.record_encoding(encoding_us));
+                let mut encoded_block = encoded_block?;
                 if slot == 0 {
                     encoded_block.block_time = Some(self.genesis_creation_time());
                     encoded_block.block_height = Some(0);
                 }
+                if let Some(signatures) = signatures {
+                    encoded_block.accounts_diffs =
+                        Some(self.get_block_accounts_diffs(slot, signatures).await?);
+                }
                 Ok::<UiConfirmedBlock, Error>(encoded_block)
             };
             if result.is_err() {
@@ -1373,7 +1558,7 @@ impl JsonRpcRequestProcessor {
             return encoded_block_future.await.transpose();
         } else if commitment.is_confirmed() {
             // Check if block is confirmed
-            let confirmed_bank = self.bank(Some(CommitmentConfig::confirmed()));
+            let confirmed_bank = self.bank(Some(CommitmentConfig::confirmed()))?;
             if confirmed_bank.status_cache_ancestors().contains(&slot) {
                 self.check_blockstore_writes_complete(slot)?;
                 let result = self
@@ -1401,16 +1586,25 @@ impl JsonRpcRequestProcessor {
                                 }
                             }
                         }
-                        let encoded_block = self
-                            .runtime
-                            .spawn_blocking(move || {
-                                confirmed_block
-                                    .encode_with_options(encoding, encoding_options)
-                                    .map_err(RpcCustomError::from)
-                            })
-                            .await
-                            .expect("Failed to spawn blocking task")?;
+                        let signatures =
+                            include_accounts_diffs.then(|| block_signatures(&confirmed_block));
+                        let (encoded_block, encoding_us) = measure_us!(
+                            self.runtime
+                                .spawn_blocking(move || {
+                                    confirmed_block
+                                        .encode_with_options(encoding, encoding_options)
+                                        .map_err(RpcCustomError::from)
+                                })
+                                .await
+                                .expect("Failed to spawn blocking task")
+                        );
+                        self.trace(|trace| trace.record_encoding(encoding_us));
 
+                        let mut encoded_block = encoded_block?;
+                        if let Some(signatures) = signatures {
+                            encoded_block.accounts_diffs =
+                                Some(self.get_block_accounts_diffs(slot, signatures).await?);
+                        }
                         Ok(encoded_block)
                     })
                     .into();
@@ -1421,6 +1615,40 @@ impl JsonRpcRequestProcessor {
         Err(RpcCustomError::BlockNotAvailable { slot }.into())
     }
 
+    async fn get_block_accounts_diffs(
+        &self,
+        slot: Slot,
+        signatures: Vec<Signature>,
+    ) -> Result<Vec<UiTransactionAccountsDiff>> {
+        self.runtime
+            .spawn_blocking({
+                let blockstore = Arc::clone(&self.blockstore);
+                move || {
+                    signatures
+                        .into_iter()
+                        .map(|signature| -> Result<_> {
+                            let accounts = blockstore
+                                .read_transaction_accounts_diff(slot, signature)
+                                .map_err(|_| Error::internal_error())?
+                                .map(|accounts_diff| {
+                                    accounts_diff
+                                        .accounts
+                                        .into_iter()
+                                        .map(encode_account_post_state)
+                                        .collect()
+                                });
+                            Ok(UiTransactionAccountsDiff {
+                                signature: signature.to_string(),
+                                accounts,
+                            })
+                        })
+                        .collect()
+                }
+            })
+            .await
+            .expect("Failed to spawn blocking task")
+    }
+
     pub async fn get_blocks(
         &self,
         start_slot: Slot,
@@ -1633,7 +1861,7 @@ impl JsonRpcRequestProcessor {
         signature: Signature,
         commitment: Option<CommitmentConfig>,
     ) -> Result<Option<RpcSignatureConfirmation>> {
-        let bank = self.bank(commitment);
+        let bank = self.bank(commitment)?;
         Ok(self
             .get_transaction_status(signature, &bank)
             .map(|transaction_status| {
@@ -1652,7 +1880,7 @@ impl JsonRpcRequestProcessor {
         signature: Signature,
         commitment: Option<CommitmentConfig>,
     ) -> Result<Option<transaction::Result<()>>> {
-        let bank = self.bank(commitment);
+        let bank = self.bank(commitment)?;
         Ok(bank
             .get_signature_status_slot(&signature)
             .map(|(_, status)| status))
@@ -1670,7 +1898,7 @@ impl JsonRpcRequestProcessor {
             self.check_if_transaction_history_enabled()?;
         }
 
-        let bank = self.bank(Some(CommitmentConfig::processed()));
+        let bank = self.bank(Some(CommitmentConfig::processed()))?;
         let mut statuses: Vec<Option<TransactionStatus>> = vec![];
 
         for signature in signatures {
@@ -1724,7 +1952,7 @@ impl JsonRpcRequestProcessor {
     ) -> Option<TransactionStatus> {
         let (slot, status) = bank.get_signature_status_slot(&signature)?;
 
-        let optimistically_confirmed_bank = self.bank(Some(CommitmentConfig::confirmed()));
+        let optimistically_confirmed_bank = self.lookup_bank(Some(CommitmentConfig::confirmed()));
         let optimistically_confirmed =
             optimistically_confirmed_bank.get_signature_status_slot(&signature);
 
@@ -1769,7 +1997,7 @@ impl JsonRpcRequestProcessor {
         let commitment = config.commitment.unwrap_or_default();
         check_is_at_least_confirmed(commitment)?;
 
-        let confirmed_bank = self.bank(Some(CommitmentConfig::confirmed()));
+        let confirmed_bank = self.bank(Some(CommitmentConfig::confirmed()))?;
         let confirmed_transaction = self
             .runtime
             .spawn_blocking({
@@ -2008,7 +2236,7 @@ impl JsonRpcRequestProcessor {
         pubkey: &Pubkey,
         commitment: Option<CommitmentConfig>,
     ) -> Result<RpcResponse<UiTokenAmount>> {
-        let bank = self.bank(commitment);
+        let bank = self.bank(commitment)?;
         let account = bank.get_account(pubkey).ok_or_else(|| {
             Error::invalid_params("Invalid param: could not find account".to_string())
         })?;
@@ -2032,7 +2260,7 @@ impl JsonRpcRequestProcessor {
         mint: &Pubkey,
         commitment: Option<CommitmentConfig>,
     ) -> Result<RpcResponse<UiTokenAmount>> {
-        let bank = self.bank(commitment);
+        let bank = self.bank(commitment)?;
         let mint_account = bank.get_account(mint).ok_or_else(|| {
             Error::invalid_params("Invalid param: could not find account".to_string())
         })?;
@@ -2071,7 +2299,7 @@ impl JsonRpcRequestProcessor {
         mint: Pubkey,
         commitment: Option<CommitmentConfig>,
     ) -> Result<RpcResponse<Vec<RpcTokenAccountBalance>>> {
-        let bank = self.bank(commitment);
+        let bank = self.bank(commitment)?;
         let (mint_owner, data) = get_mint_owner_and_additional_data(&bank, &mint)?;
         if !is_known_spl_token_id(&mint_owner) {
             return Err(Error::invalid_params(
@@ -2160,7 +2388,7 @@ impl JsonRpcRequestProcessor {
                 sort_results,
             )
             .await?;
-        let accounts = if encoding == UiAccountEncoding::JsonParsed {
+        let (accounts, encoding_us) = measure_us!(if encoding == UiAccountEncoding::JsonParsed {
             get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).collect()
         } else {
             keyed_accounts
@@ -2172,7 +2400,8 @@ impl JsonRpcRequestProcessor {
                     })
                 })
                 .collect::<Result<Vec<_>>>()?
-        };
+        });
+        self.trace(|trace| trace.record_encoding(encoding_us));
         Ok(new_response(&bank, accounts))
     }
 
@@ -2226,7 +2455,7 @@ impl JsonRpcRequestProcessor {
             )
             .await?
         };
-        let accounts = if encoding == UiAccountEncoding::JsonParsed {
+        let (accounts, encoding_us) = measure_us!(if encoding == UiAccountEncoding::JsonParsed {
             get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).collect()
         } else {
             keyed_accounts
@@ -2238,7 +2467,8 @@ impl JsonRpcRequestProcessor {
                     })
                 })
                 .collect::<Result<Vec<_>>>()?
-        };
+        });
+        self.trace(|trace| trace.record_encoding(encoding_us));
         Ok(new_response(&bank, accounts))
     }
 
@@ -2275,23 +2505,26 @@ impl JsonRpcRequestProcessor {
             })
         } else {
             // this path does not need to provide a mb limit because we only want to support secondary indexes
-            let mut accounts = self
-                .runtime
-                .spawn_blocking(move || {
-                    bank.get_filtered_program_accounts(
-                        &program_id,
-                        |account: &AccountSharedData| {
-                            filters
-                                .iter()
-                                .all(|filter_type| filter_allows(filter_type, account))
-                        },
-                    )
-                    .map_err(|e| RpcCustomError::ScanError {
-                        message: e.to_string(),
+            let (accounts, accounts_scan_us) = measure_us!(
+                self.runtime
+                    .spawn_blocking(move || {
+                        bank.get_filtered_program_accounts(
+                            &program_id,
+                            |account: &AccountSharedData| {
+                                filters
+                                    .iter()
+                                    .all(|filter_type| filter_allows(filter_type, account))
+                            },
+                        )
+                        .map_err(|e| RpcCustomError::ScanError {
+                            message: e.to_string(),
+                        })
                     })
-                })
-                .await
-                .expect("Failed to spawn blocking task")?;
+                    .await
+                    .expect("Failed to spawn blocking task")
+            );
+            self.trace(|trace| trace.record_accounts_scan(accounts_scan_us));
+            let mut accounts = accounts?;
             if sort_results {
                 // Avoid copying pubkeys (using Ord::cmp(a, b) silences clippy::unnecessary_sort_by).
                 accounts.sort_unstable_by(|(addr_a, _), (addr_b, _)| Ord::cmp(addr_a, addr_b));
@@ -2448,6 +2681,28 @@ impl JsonRpcRequestProcessor {
             })
             .collect())
     }
+
+    fn get_priority_fee_estimate(
+        &self,
+        pubkeys: Vec<Pubkey>,
+        config: RpcPriorityFeeEstimateConfig,
+    ) -> Result<RpcPriorityFeeEstimate> {
+        let Some(prioritization_fee_cache) = self.prioritization_fee_cache.as_deref() else {
+            error!("The PrioritizationFeeCache should always be available for the full RPC API");
+            return Err(Error::internal_error());
+        };
+
+        let lookback_slots = config.lookback_slots.unwrap_or(usize::MAX);
+        if lookback_slots == 0 {
+            return Err(Error::invalid_params(
+                "lookbackSlots must be greater than 0",
+            ));
+        }
+        let PrioritizationFeeEstimate { p25, p50, p75, p95 } = prioritization_fee_cache
+            .get_prioritization_fee_estimate(&pubkeys, lookback_slots)
+            .unwrap_or_default();
+        Ok(RpcPriorityFeeEstimate { p25, p50, p75, p95 })
+    }
 }
 
 pub(crate) fn optimize_filters(filters: &mut [RpcFilterType]) {
@@ -2965,7 +3220,7 @@ pub mod rpc_minimal {
                 let _ = verify_pubkey(identity)?;
             }
 
-            let bank = meta.bank(config.commitment);
+            let bank = meta.bank(config.commitment)?;
             let slot = slot.unwrap_or_else(|| bank.slot());
             let epoch = bank.epoch_schedule().get_epoch(slot);
 
@@ -3060,7 +3315,7 @@ pub mod rpc_bank {
             if data_len as u64 > solana_system_interface::MAX_PERMITTED_DATA_LENGTH {
                 return Err(Error::invalid_request());
             }
-            Ok(meta.get_minimum_balance_for_rent_exemption(data_len, commitment))
+            meta.get_minimum_balance_for_rent_exemption(data_len, commitment)
         }
 
         fn get_inflation_governor(
@@ -3069,12 +3324,12 @@ pub mod rpc_bank {
             commitment: Option<CommitmentConfig>,
         ) -> Result<RpcInflationGovernor> {
             debug!("get_inflation_governor rpc request received");
-            Ok(meta.get_inflation_governor(commitment))
+            meta.get_inflation_governor(commitment)
         }
 
         fn get_inflation_rate(&self, meta: Self::Metadata) -> Result<RpcInflationRate> {
             debug!("get_inflation_rate rpc request received");
-            Ok(meta.get_inflation_rate())
+            meta.get_inflation_rate()
         }
 
         fn get_epoch_schedule(&self, meta: Self::Metadata) -> Result<EpochSchedule> {
@@ -3115,7 +3370,7 @@ pub mod rpc_bank {
 
         fn get_ag_genesis_cert(&self, meta: Self::Metadata) -> Result<Option<Certificate>> {
             debug!("get_ag_genesis_cert rpc request received");
-            Ok(meta.get_ag_genesis_cert())
+            meta.get_ag_genesis_cert()
         }
 
         fn get_block_production(
@@ -3132,7 +3387,7 @@ pub mod rpc_bank {
                 None
             };
 
-            let bank = meta.bank(config.commitment);
+            let bank = meta.bank(config.commitment)?;
             let (first_slot, last_slot) = match config.range {
                 None => (
                     bank.epoch_schedule().get_first_slot_in_epoch(bank.epoch()),
@@ -3662,6 +3917,14 @@ pub mod rpc_full {
             meta: Self::Metadata,
             pubkey_strs: Option<Vec<String>>,
         ) -> Result<Vec<RpcPrioritizationFee>>;
+
+        #[rpc(meta, name = "getPriorityFeeEstimate")]
+        fn get_priority_fee_estimate(
+            &self,
+            meta: Self::Metadata,
+            pubkey_strs: Option<Vec<String>>,
+            config: Option<RpcPriorityFeeEstimateConfig>,
+        ) -> Result<RpcPriorityFeeEstimate>;
     }
 
     pub struct FullImpl;
@@ -3808,7 +4071,7 @@ pub mod rpc_full {
             let pubkey = verify_pubkey(&pubkey_str)?;
 
             let config = config.unwrap_or_default();
-            let bank = meta.bank(config.commitment);
+            let bank = meta.bank(config.commitment)?;
 
             let blockhash = if let Some(blockhash) = config.recent_blockhash {
                 verify_hash(&blockhash)?
@@ -4366,6 +4629,29 @@ pub mod rpc_full {
                 .collect::<Result<Vec<_>>>()?;
             meta.get_recent_prioritization_fees(pubkeys)
         }
+
+        fn get_priority_fee_estimate(
+            &self,
+            meta: Self::Metadata,
+            pubkey_strs: Option<Vec<String>>,
+            config: Option<RpcPriorityFeeEstimateConfig>,
+        ) -> Result<RpcPriorityFeeEstimate> {
+            let pubkey_strs = pubkey_strs.unwrap_or_default();
+            debug!(
+                "get_priority_fee_estimate rpc request received: {:?} pubkeys",
+                pubkey_strs.len()
+            );
+            if pubkey_strs.len() > MAX_TX_ACCOUNT_LOCKS {
+                return Err(Error::invalid_params(format!(
+                    "Too many inputs provided; max {MAX_TX_ACCOUNT_LOCKS}"
+                )));
+            }
+            let pubkeys = pubkey_strs
+                .into_iter()
+                .map(|pubkey_str| verify_pubkey(&pubkey_str))
+                .collect::<Result<Vec<_>>>()?;
+            meta.get_priority_fee_estimate(pubkeys, config.unwrap_or_default())
+        }
     }
 }
 
@@ -4547,6 +4833,7 @@ pub fn populate_blockstore_for_tests(
             None,
             blockstore,
             false,
+            Some(DEFAULT_RPC_ACCOUNTS_DIFF_RETENTION_SLOTS),
             None,
             tss_exit.clone(),
         );
@@ -4561,6 +4848,7 @@ pub fn populate_blockstore_for_tests(
                 &solana_ledger::blockstore_processor::TransactionStatusSender {
                     sender: transaction_status_sender,
                     dependency_tracker: None,
+                    record_post_accounts: true,
                 },
             ),
             Some(&replay_vote_sender),
@@ -4620,6 +4908,9 @@ pub mod tests {
         solana_rpc_client_api::{
             custom_error::{
                 JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+                JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED,
+                JSON_RPC_SERVER_ERROR_PINNED_SLOT_COMMITMENT_NOT_REACHED,
+                JSON_RPC_SERVER_ERROR_PINNED_SLOT_NOT_AVAILABLE,
                 JSON_RPC_SERVER_ERROR_TRANSACTION_HISTORY_NOT_AVAILABLE,
                 JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION,
             },
@@ -7538,6 +7829,160 @@ pub mod tests {
         assert_eq!(confirmed_block.rewards.unwrap(), vec![]);
     }
 
+    #[test]
+    fn test_get_block_accounts_diff() {
+        let block_config = RpcBlockConfig {
+            encoding: None,
+            transaction_details: Some(TransactionDetails::AccountsDiff),
+            rewards: Some(false),
+            commitment: None,
+            max_supported_transaction_version: None,
+        };
+
+        // Nodes that do not record accounts diffs reject the detail level
+        let rpc = RpcHandler::start();
+        rpc.create_test_transactions_and_populate_blockstore();
+        let request = create_test_request("getBlock", Some(json!([0u64, block_config])));
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(
+            response,
+            (
+                ErrorCode::InvalidParams.code(),
+                String::from("accountsDiff transaction details are not enabled on this node"),
+            )
+        );
+
+        let rpc = RpcHandler::start_with_config(JsonRpcConfig {
+            enable_rpc_transaction_history: true,
+            accounts_diff_retention_slots: Some(DEFAULT_RPC_ACCOUNTS_DIFF_RETENTION_SLOTS),
+            ..JsonRpcConfig::default()
+        });
+        let confirmed_block_signatures = rpc.create_test_transactions_and_populate_blockstore();
+        let request = create_test_request("getBlock", Some(json!([0u64, block_config])));
+        let result: Option<UiConfirmedBlock> =
+            parse_success_result(rpc.handle_request_sync(request));
+        let confirmed_block = result.unwrap();
+
+        // Transactions are returned at the `accounts` detail level
+        let transactions = confirmed_block.transactions.unwrap();
+        assert!(
+            transactions.iter().all(|transaction| matches!(
+                transaction.transaction,
+                EncodedTransaction::Accounts(_)
+            ))
+        );
+        let accounts_diffs = confirmed_block.accounts_diffs.unwrap();
+        assert_eq!(accounts_diffs.len(), transactions.len());
+
+        // Both the successful and the failed transaction wrote (at least) their fee payer
+        for signature in &confirmed_block_signatures[..2] {
+            let accounts_diff = accounts_diffs
+                .iter()
+                .find(|accounts_diff| accounts_diff.signature == signature.to_string())
+                .unwrap();
+            let accounts = accounts_diff.accounts.as_ref().unwrap();
+            assert!(!accounts.is_empty());
+            for UiAccountPostState { pubkey, account } in accounts {
+                let bank_account = rpc
+                    .working_bank()
+                    .get_account(&Pubkey::from_str(pubkey).unwrap())
+                    .unwrap_or_default();
+                assert_eq!(account.lamports, bank_account.lamports());
+                assert_eq!(account.owner, bank_account.owner().to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn test_rpc_pinned_bank() {
+        let rpc = RpcHandler::start();
+        let mint_pubkey = rpc.mint_keypair.pubkey();
+        rpc.advance_bank_to_confirmed_slot(1);
+        rpc.advance_bank_to_confirmed_slot(2);
+        rpc.block_commitment_cache
+            .write()
+            .unwrap()
+            .set_highest_super_majority_root(1);
+        let request = json!([
+            {
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "getSlot",
+                "params": [{"commitment": "processed"}],
+            },
+            {
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "getBalance",
+                "params": [mint_pubkey.to_string(), {"commitment": "finalized"}],
+            },
+        ])
+        .to_string();
+        let send_batch = |meta: JsonRpcRequestProcessor| {
+            let response = rpc.io.handle_request_sync(&request, meta).unwrap();
+            serde_json::from_str::<Vec<Value>>(&response).unwrap()
+        };
+        let handle_batch = |meta: JsonRpcRequestProcessor| {
+            let response = send_batch(meta);
+            (
+                response[0]["result"].as_u64().unwrap(),
+                response[1]["result"]["context"]["slot"].as_u64().unwrap(),
+            )
+        };
+
+        // Without a pin, each call resolves the bank matching its own commitment
+        assert_eq!(handle_batch(rpc.meta.clone()), (2, 1));
+
+        let pin = |target, min_context_slot| {
+            rpc.meta
+                .clone()
+                .with_bank_pin(Some(Ok(RpcBankPin {
+                    target,
+                    min_context_slot,
+                })))
+                .pin_bank()
+        };
+        let meta = pin(RpcBankPinTarget::Slot(1), None).unwrap();
+        assert_eq!(handle_batch(meta), (1, 1));
+        let meta = pin(
+            RpcBankPinTarget::Commitment(CommitmentConfig::finalized()),
+            Some(1),
+        )
+        .unwrap();
+        assert_eq!(handle_batch(meta), (1, 1));
+
+        // A call is never served from a pinned bank below its own commitment
+        let meta = pin(
+            RpcBankPinTarget::Commitment(CommitmentConfig::processed()),
+            Some(2),
+        )
+        .unwrap();
+        let response = send_batch(meta);
+        assert_eq!(response[0]["result"].as_u64().unwrap(), 2);
+        assert_eq!(
+            response[1]["error"]["code"].as_i64().unwrap(),
+            JSON_RPC_SERVER_ERROR_PINNED_SLOT_COMMITMENT_NOT_REACHED
+        );
+
+        let err = pin(RpcBankPinTarget::Slot(42), None).unwrap_err();
+        assert_eq!(
+            err.code,
+            ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_PINNED_SLOT_NOT_AVAILABLE)
+        );
+        let err = pin(RpcBankPinTarget::Slot(1), Some(2)).unwrap_err();
+        assert_eq!(
+            err.code,
+            ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED)
+        );
+        let err = rpc
+            .meta
+            .clone()
+            .with_bank_pin(Some(Err("Invalid pin".to_string())))
+            .pin_bank()
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidParams);
+    }
+
     #[test]
     fn test_get_block_production() {
         let rpc = RpcHandler::start();
@@ -9652,4 +10097,80 @@ pub mod tests {
             ],
         );
     }
+
+    #[test]
+    fn test_rpc_get_priority_fee_estimate() {
+        let rpc = RpcHandler::start();
+        let request = create_test_request("getPriorityFeeEstimate", None);
+        let response: RpcPriorityFeeEstimate =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(
+            response,
+            RpcPriorityFeeEstimate {
+                p25: 0,
+                p50: 0,
+                p75: 0,
+                p95: 0,
+            }
+        );
+
+        let slot0 = rpc.working_bank().slot();
+        let bank0_id = rpc.working_bank().bank_id();
+        let account0 = Pubkey::new_unique();
+        let account1 = Pubkey::new_unique();
+        let account2 = Pubkey::new_unique();
+        // 1_000 micro-lamports per CU over the default 6_000 CU limit round to a whole lamport.
+        let price = 1_000;
+        let transactions = vec![
+            Transaction::new_unsigned(Message::new(
+                &[
+                    system_instruction::transfer(&account0, &account1, 1),
+                    ComputeBudgetInstruction::set_compute_unit_price(price),
+                ],
+                Some(&account0),
+            )),
+            Transaction::new_unsigned(Message::new(
+                &[system_instruction::transfer(&account0, &account2, 1)],
+                Some(&account0),
+            )),
+        ];
+        rpc.update_prioritization_fee_cache(transactions);
+        let cache = rpc.get_prioritization_fee_cache();
+        cache.finalize_priority_fee(slot0, bank0_id);
+        while cache.available_block_count() < 1 {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+
+        // Percentiles are rounded up to within 25% of the price paid.
+        let is_estimate_of =
+            |estimate: u64, price: u64| (price..=price * 5 / 4).contains(&estimate);
+
+        let request = create_test_request("getPriorityFeeEstimate", None);
+        let response: RpcPriorityFeeEstimate =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert_eq!(response.p50, 0);
+        assert!(is_estimate_of(response.p95, price));
+
+        let request = create_test_request(
+            "getPriorityFeeEstimate",
+            Some(json!([[account1.to_string()], {"lookbackSlots": 10}])),
+        );
+        let response: RpcPriorityFeeEstimate =
+            parse_success_result(rpc.handle_request_sync(request));
+        assert!(is_estimate_of(response.p25, price));
+        assert!(is_estimate_of(response.p95, price));
+
+        let request = create_test_request(
+            "getPriorityFeeEstimate",
+            Some(json!([[account2.to_string()], {"lookbackSlots": 0}])),
+        );
+        let response = parse_failure_response(rpc.handle_request_sync(request));
+        assert_eq!(
+            response,
+            (
+                ErrorCode::InvalidParams.code(),
+                String::from("lookbackSlots must be greater than 0"),
+            )
+        );
+    }
 }
@@ -0,0 +1,226 @@
+//! The `rpc_bank_pin` module lets a client run every call of a JSON RPC
+//! request against a single bank.
+//!
+//! Each call of a batch normally resolves its own bank from its commitment at
+//! execution time, so a batch of `getAccountInfo` calls may observe several
+//! slots. A request carrying the [`RPC_PIN_SLOT_HEADER`] or
+//! [`RPC_MIN_CONTEXT_SLOT_HEADER`] header instead has its bank resolved once,
+//! before any call runs, and every call of the batch reads from that bank and
+//! reports its slot in the response context. The commitment and
+//! `minContextSlot` of individual calls are still validated against the
+//! pinned bank, but no longer select it: a call fails unless the pinned bank
+//! is on the fork of the bank its commitment would have selected, so a
+//! `finalized` call never reads an unconfirmed bank.
+
+use {
+    crate::rpc::JsonRpcRequestProcessor,
+    jsonrpc_core::{
+        Call, Error, Output, Request, Response, Version,
+        futures::future::{self, Either},
+        middleware::{Middleware, NoopCallFuture},
+    },
+    jsonrpc_http_server::hyper::HeaderMap,
+    solana_clock::Slot,
+    solana_commitment_config::CommitmentConfig,
+    std::future::Future,
+};
+
+/// Pins the request to a slot, or to the bank matching a commitment level
+/// (`processed`, `confirmed` or `finalized`) at the time the request arrives
+pub const RPC_PIN_SLOT_HEADER: &str = "x-rpc-pin-slot";
+/// Fails the whole request if the pinned bank is older than this slot. When
+/// sent without [`RPC_PIN_SLOT_HEADER`], the request is pinned to the bank
+/// of the default commitment.
+pub const RPC_MIN_CONTEXT_SLOT_HEADER: &str = "x-rpc-min-context-slot";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcBankPinTarget {
+    Slot(Slot),
+    Commitment(CommitmentConfig),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RpcBankPin {
+    pub target: RpcBankPinTarget,
+    pub min_context_slot: Option<Slot>,
+}
+
+impl RpcBankPin {
+    /// Returns the pin requested by `headers`, if any
+    pub fn from_headers(headers: &HeaderMap) -> Result<Option<Self>, String> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .map(|value| {
+                    value
+                        .to_str()
+                        .map(str::trim)
+                        .map_err(|_| format!("Invalid {name} header"))
+                })
+                .transpose()
+        };
+
+        let target = header(RPC_PIN_SLOT_HEADER)?
+            .map(|value| match value {
+                "processed" => Ok(RpcBankPinTarget::Commitment(CommitmentConfig::processed())),
+                "confirmed" => Ok(RpcBankPinTarget::Commitment(CommitmentConfig::confirmed())),
+                "finalized" => Ok(RpcBankPinTarget::Commitment(CommitmentConfig::finalized())),
+                slot => slot.parse().map(RpcBankPinTarget::Slot).map_err(|_| {
+                    format!(
+                        "Invalid {RPC_PIN_SLOT_HEADER} header: expected a slot or a commitment \
+                         level, got {slot:?}"
+                    )
+                }),
+            })
+            .transpose()?;
+        let min_context_slot = header(RPC_MIN_CONTEXT_SLOT_HEADER)?
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    format!("Invalid {RPC_MIN_CONTEXT_SLOT_HEADER} header: expected a slot")
+                })
+            })
+            .transpose()?;
+
+        Ok(match (target, min_context_slot) {
+            (None, None) => None,
+            (target, min_context_slot) => Some(Self {
+                target: target.unwrap_or(RpcBankPinTarget::Commitment(CommitmentConfig::default())),
+                min_context_slot,
+            }),
+        })
+    }
+}
+
+/// Resolves the bank pinned by a request before its calls are dispatched
+#[derive(Default)]
+pub(crate) struct RpcBankPinMiddleware;
+
+impl Middleware<JsonRpcRequestProcessor> for RpcBankPinMiddleware {
+    type Future = future::Ready<Option<Response>>;
+    type CallFuture = NoopCallFuture;
+
+    fn on_request<F, X>(
+        &self,
+        request: Request,
+        meta: JsonRpcRequestProcessor,
+        next: F,
+    ) -> Either<Self::Future, X>
+    where
+        F: Fn(Request, JsonRpcRequestProcessor) -> X + Send + Sync,
+        X: Future<Output = Option<Response>> + Send + 'static,
+    {
+        match meta.pin_bank() {
+            Ok(meta) => Either::Right(next(request, meta)),
+            Err(err) => Either::Left(future::ready(fail_request(request, err))),
+        }
+    }
+}
+
+/// Answers every call of `request` with `err`
+fn fail_request(request: Request, err: Error) -> Option<Response> {
+    let fail_call = |call: Call| match call {
+        Call::MethodCall(method_call) => Some(Output::from(
+            Err(err.clone()),
+            method_call.id,
+            method_call.jsonrpc,
+        )),
+        Call::Notification(_) => None,
+        Call::Invalid { id } => Some(Output::from(Err(err.clone()), id, Some(Version::V2))),
+    };
+    match request {
+        Request::Single(call) => fail_call(call).map(Response::Single),
+        Request::Batch(calls) => {
+            let outputs: Vec<_> = calls.into_iter().filter_map(fail_call).collect();
+            (!outputs.is_empty()).then_some(Response::Batch(outputs))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        jsonrpc_core::{Id, MethodCall, Notification, Params},
+        jsonrpc_http_server::hyper::header::HeaderValue,
+    };
+
+    fn headers(entries: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in entries {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_from_headers() {
+        assert_eq!(RpcBankPin::from_headers(&headers(&[])), Ok(None));
+        assert_eq!(
+            RpcBankPin::from_headers(&headers(&[(RPC_PIN_SLOT_HEADER, "42")])),
+            Ok(Some(RpcBankPin {
+                target: RpcBankPinTarget::Slot(42),
+                min_context_slot: None,
+            }))
+        );
+        assert_eq!(
+            RpcBankPin::from_headers(&headers(&[
+                (RPC_PIN_SLOT_HEADER, "confirmed"),
+                (RPC_MIN_CONTEXT_SLOT_HEADER, "7"),
+            ])),
+            Ok(Some(RpcBankPin {
+                target: RpcBankPinTarget::Commitment(CommitmentConfig::confirmed()),
+                min_context_slot: Some(7),
+            }))
+        );
+        assert_eq!(
+            RpcBankPin::from_headers(&headers(&[(RPC_MIN_CONTEXT_SLOT_HEADER, "7")])),
+            Ok(Some(RpcBankPin {
+                target: RpcBankPinTarget::Commitment(CommitmentConfig::default()),
+                min_context_slot: Some(7),
+            }))
+        );
+        assert!(RpcBankPin::from_headers(&headers(&[(RPC_PIN_SLOT_HEADER, "latest")])).is_err());
+        assert!(
+            RpcBankPin::from_headers(&headers(&[(RPC_MIN_CONTEXT_SLOT_HEADER, "-1")])).is_err()
+        );
+    }
+
+    #[test]
+    fn test_fail_request() {
+        let method_call = |id| {
+            Call::MethodCall(MethodCall {
+                jsonrpc: Some(Version::V2),
+                method: "getBalance".to_string(),
+                params: Params::None,
+                id: Id::Num(id),
+            })
+        };
+        let notification = Call::Notification(Notification {
+            jsonrpc: Some(Version::V2),
+            method: "getBalance".to_string(),
+            params: Params::None,
+        });
+        let err = Error::invalid_params("bad pin");
+
+        let Some(Response::Batch(outputs)) = fail_request(
+            Request::Batch(vec![method_call(1), notification.clone(), method_call(2)]),
+            err.clone(),
+        ) else {
+            panic!("expected a batch response");
+        };
+        assert_eq!(outputs.len(), 2);
+        for (output, id) in outputs.into_iter().zip([1, 2]) {
+            let Output::Failure(failure) = output else {
+                panic!("expected a failure");
+            };
+            assert_eq!(failure.id, Id::Num(id));
+            assert_eq!(failure.error, err);
+        }
+
+        assert_eq!(
+            fail_request(Request::Single(notification.clone()), err.clone()),
+            None
+        );
+        assert_eq!(fail_request(Request::Batch(vec![notification]), err), None);
+    }
+}
@@ -0,0 +1,686 @@
+//! The `rpc_grpc_service` module implements an optional gRPC streaming service
+//! that mirrors the websocket PubSub API.
+//!
+//! Streams are registered with the same `SubscriptionControl` as websocket
+//! subscriptions, so commitment tracking and server-side filtering are done by
+//! `RpcSubscriptions` in exactly the same way. Every stream owns a bounded
+//! queue that is only refilled as fast as the client drains it. A client that
+//! falls behind by more than the shared broadcast capacity is disconnected with
+//! `RESOURCE_EXHAUSTED` instead of silently missing notifications.
+//!
+//! Subscriptions are shared with websocket clients, so every notification is
+//! still rendered once by `RpcSubscriptions`. Each stream translates it into a
+//! typed protobuf message, which carries account data, transactions, keys and
+//! hashes as raw bytes instead of base58/base64 strings.
+
+use {
+    crate::{
+        rpc::{check_is_at_least_confirmed, verify_filters},
+        rpc_pubsub_service::PubSubConfig,
+        rpc_subscription_tracker::{
+            AccountSubscriptionParams, BlockSubscriptionKind, BlockSubscriptionParams,
+            LogsSubscriptionKind, LogsSubscriptionParams, ProgramSubscriptionParams,
+            SubscriptionControl, SubscriptionParams,
+        },
+        rpc_subscriptions::RpcSubscriptions,
+    },
+    base64::{Engine, prelude::BASE64_STANDARD},
+    proto::{
+        AccountNotification, AccountSubscribeRequest, BlockNotification, BlockSubscribeRequest,
+        DataSlice, LogsNotification, LogsSubscribeRequest, ProgramNotification,
+        ProgramSubscribeRequest, SlotNotification, SlotSubscribeRequest, account_filter,
+        rpc_stream_server::{RpcStream, RpcStreamServer},
+    },
+    serde::{Deserialize, de::DeserializeOwned},
+    solana_account_decoder::{UiAccount, UiAccountEncoding, UiDataSliceConfig},
+    solana_commitment_config::CommitmentConfig,
+    solana_metrics::TokenCounter,
+    solana_pubkey::Pubkey,
+    solana_rpc_client_api::{
+        filter::{Memcmp, RpcFilterType},
+        response::{
+            Response as RpcResponse, RpcBlockUpdate, RpcKeyedAccount, RpcLogsResponse, SlotInfo,
+        },
+    },
+    solana_transaction_status::{
+        EncodedTransaction, EncodedTransactionWithStatusMeta, Reward, RewardType,
+        TransactionBinaryEncoding, TransactionDetails, UiConfirmedBlock, UiTransactionEncoding,
+        UiTransactionStatusMeta,
+    },
+    std::{
+        fmt::Display,
+        net::SocketAddr,
+        thread::{self, Builder, JoinHandle},
+    },
+    stream_cancel::{Trigger, Tripwire},
+    tokio::{
+        select,
+        sync::{broadcast::error::RecvError, mpsc},
+    },
+    tokio_stream::wrappers::ReceiverStream,
+    tonic::{Request, Response, Status, transport::Server},
+};
+
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/solana.rpc.stream.rs"));
+}
+
+pub const DEFAULT_GRPC_STREAM_QUEUE_CAPACITY: usize = 1_024;
+
+pub struct GrpcStreamService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl GrpcStreamService {
+    pub fn new(
+        pubsub_config: PubSubConfig,
+        subscriptions: &RpcSubscriptions,
+        grpc_addr: SocketAddr,
+    ) -> (Trigger, Self) {
+        let subscription_control = subscriptions.control().clone();
+
+        let (trigger, tripwire) = Tripwire::new();
+        let thread_hdl = Builder::new()
+            .name("solRpcGrpc".to_string())
+            .spawn(move || {
+                info!("GrpcStreamService has started");
+                let runtime = tokio::runtime::Builder::new_multi_thread()
+                    .thread_name("solRpcGrpcRt")
+                    .worker_threads(pubsub_config.worker_threads)
+                    .enable_all()
+                    .build()
+                    .expect("runtime creation failed");
+                let service =
+                    RpcStreamServer::new(RpcStreamImpl::new(pubsub_config, subscription_control));
+                info!("rpc_grpc listening on {grpc_addr:?}");
+                if let Err(err) =
+                    runtime.block_on(Server::builder().add_service(service).serve_with_shutdown(
+                        grpc_addr,
+                        async move {
+                            tripwire.await;
+                        },
+                    ))
+                {
+                    error!("GrpcStreamService has stopped due to error: {err}");
+                };
+                info!("GrpcStreamService has stopped");
+            })
+            .expect("thread spawn failed");
+
+        (trigger, Self { thread_hdl })
+    }
+
+    pub fn close(self) -> thread::Result<()> {
+        self.join()
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
+
+type NotificationStream<T> = ReceiverStream<Result<T, Status>>;
+
+/// Translates a rendered websocket notification into the stream's message type.
+type Translate<T> = fn(u64, &str) -> Result<T, Status>;
+
+struct RpcStreamImpl {
+    config: PubSubConfig,
+    subscription_control: SubscriptionControl,
+    counter: TokenCounter,
+}
+
+impl RpcStreamImpl {
+    fn new(config: PubSubConfig, subscription_control: SubscriptionControl) -> Self {
+        Self {
+            config,
+            subscription_control,
+            counter: TokenCounter::new("rpc_grpc_streams"),
+        }
+    }
+
+    fn stream<T: Send + 'static>(
+        &self,
+        params: SubscriptionParams,
+        translate: Translate<T>,
+    ) -> Result<Response<NotificationStream<T>>, Status> {
+        // Listen before subscribing so that the first notification can't be missed.
+        let mut broadcast_receiver = self.subscription_control.broadcast_receiver();
+        let token = self.subscription_control.subscribe(params).map_err(|_| {
+            Status::resource_exhausted("Subscription refused. Node subscription limit reached")
+        })?;
+        let counter_token = self.counter.create_token();
+        let (sender, receiver) = mpsc::channel(self.config.grpc_stream_queue_capacity);
+
+        tokio::spawn(async move {
+            let subscription_id = token.id();
+            let stream_subscription_id = u64::from(subscription_id);
+            loop {
+                let result = select! {
+                    result = broadcast_receiver.recv() => result,
+                    _ = sender.closed() => break,
+                };
+                let status = match result {
+                    Ok(notification) if notification.subscription_id != subscription_id => {
+                        continue;
+                    }
+                    Ok(notification) => match notification.json.upgrade() {
+                        Some(json) => match translate(stream_subscription_id, &json) {
+                            Ok(message) => {
+                                // Waiting here is what pushes back on the broadcast queue: a
+                                // client that doesn't drain its stream eventually lags and is
+                                // disconnected.
+                                if sender.send(Ok(message)).await.is_err() || notification.is_final
+                                {
+                                    break;
+                                }
+                                continue;
+                            }
+                            Err(status) => status,
+                        },
+                        None => Status::resource_exhausted(
+                            "client has lagged behind (notification is gone)",
+                        ),
+                    },
+                    Err(RecvError::Lagged(_)) => {
+                        Status::resource_exhausted("client has lagged behind")
+                    }
+                    Err(RecvError::Closed) => Status::unavailable("node is shutting down"),
+                };
+                let _ = sender.send(Err(status)).await;
+                break;
+            }
+            drop(token);
+            drop(counter_token);
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+fn commitment_config(commitment: proto::Commitment) -> CommitmentConfig {
+    match commitment {
+        proto::Commitment::Unspecified => CommitmentConfig::default(),
+        proto::Commitment::Processed => CommitmentConfig::processed(),
+        proto::Commitment::Confirmed => CommitmentConfig::confirmed(),
+        proto::Commitment::Finalized => CommitmentConfig::finalized(),
+    }
+}
+
+fn transaction_details(details: proto::TransactionDetails) -> TransactionDetails {
+    match details {
+        proto::TransactionDetails::Unspecified | proto::TransactionDetails::Full => {
+            TransactionDetails::Full
+        }
+        proto::TransactionDetails::Signatures => TransactionDetails::Signatures,
+        proto::TransactionDetails::None => TransactionDetails::None,
+    }
+}
+
+fn data_slice(data_slice: Option<DataSlice>) -> Option<UiDataSliceConfig> {
+    data_slice.map(|DataSlice { offset, length }| UiDataSliceConfig {
+        offset: offset as usize,
+        length: length as usize,
+    })
+}
+
+fn param_pubkey(bytes: &[u8], thing: &str) -> Result<Pubkey, Status> {
+    Pubkey::try_from(bytes)
+        .map_err(|_| Status::invalid_argument(format!("Invalid Request: Invalid {thing} provided")))
+}
+
+fn account_filters(filters: Vec<proto::AccountFilter>) -> Result<Vec<RpcFilterType>, Status> {
+    let filters = filters
+        .into_iter()
+        .map(|filter| match filter.filter {
+            Some(account_filter::Filter::DataSize(data_size)) => {
+                Ok(RpcFilterType::DataSize(data_size))
+            }
+            Some(account_filter::Filter::Memcmp(proto::Memcmp { offset, bytes })) => Ok(
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset as usize, bytes)),
+            ),
+            Some(account_filter::Filter::TokenAccountState(_)) => {
+                Ok(RpcFilterType::TokenAccountState)
+            }
+            None => Err(Status::invalid_argument("Invalid Request: Empty filter")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    verify_filters(&filters).map_err(|err| Status::invalid_argument(err.message))?;
+    Ok(filters)
+}
+
+#[derive(Deserialize)]
+struct JsonNotification<T> {
+    params: JsonNotificationParams<T>,
+}
+
+#[derive(Deserialize)]
+struct JsonNotificationParams<T> {
+    result: T,
+}
+
+fn malformed(err: impl Display) -> Status {
+    Status::internal(format!("malformed notification: {err}"))
+}
+
+fn notification_result<T: DeserializeOwned>(json: &str) -> Result<T, Status> {
+    serde_json::from_str::<JsonNotification<T>>(json)
+        .map(|notification| notification.params.result)
+        .map_err(malformed)
+}
+
+fn base58_bytes(value: &str) -> Result<Vec<u8>, Status> {
+    bs58::decode(value).into_vec().map_err(malformed)
+}
+
+fn account(account: UiAccount) -> Result<proto::Account, Status> {
+    let data = account
+        .data
+        .decode()
+        .ok_or_else(|| malformed("account data is not binary"))?;
+    Ok(proto::Account {
+        lamports: account.lamports,
+        owner: base58_bytes(&account.owner)?,
+        space: account.space.unwrap_or(data.len() as u64),
+        data,
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+    })
+}
+
+fn transaction_meta(meta: UiTransactionStatusMeta) -> proto::TransactionStatusMeta {
+    proto::TransactionStatusMeta {
+        err: meta.err.map(|err| err.to_string()),
+        fee: meta.fee,
+        pre_balances: meta.pre_balances,
+        post_balances: meta.post_balances,
+        log_messages: Option::<Vec<String>>::from(meta.log_messages).unwrap_or_default(),
+        compute_units_consumed: meta.compute_units_consumed.into(),
+    }
+}
+
+fn transaction(
+    transaction: EncodedTransactionWithStatusMeta,
+) -> Result<proto::Transaction, Status> {
+    let EncodedTransaction::Binary(blob, TransactionBinaryEncoding::Base64) =
+        transaction.transaction
+    else {
+        return Err(malformed("transaction is not base64"));
+    };
+    Ok(proto::Transaction {
+        transaction: BASE64_STANDARD.decode(blob).map_err(malformed)?,
+        meta: transaction.meta.map(transaction_meta),
+    })
+}
+
+fn reward(reward: Reward) -> Result<proto::Reward, Status> {
+    let reward_type = match reward.reward_type {
+        None => proto::RewardType::Unspecified,
+        Some(RewardType::Fee) => proto::RewardType::Fee,
+        Some(RewardType::Rent) => proto::RewardType::Rent,
+        Some(RewardType::Staking) => proto::RewardType::Staking,
+        Some(RewardType::Voting) => proto::RewardType::Voting,
+        Some(RewardType::DeactivatedStake) => proto::RewardType::DeactivatedStake,
+    };
+    Ok(proto::Reward {
+        pubkey: base58_bytes(&reward.pubkey)?,
+        lamports: reward.lamports,
+        post_balance: reward.post_balance,
+        reward_type: reward_type.into(),
+        commission: reward.commission.map(u32::from),
+    })
+}
+
+fn block(block: UiConfirmedBlock) -> Result<proto::Block, Status> {
+    Ok(proto::Block {
+        previous_blockhash: base58_bytes(&block.previous_blockhash)?,
+        blockhash: base58_bytes(&block.blockhash)?,
+        parent_slot: block.parent_slot,
+        transactions: block
+            .transactions
+            .unwrap_or_default()
+            .into_iter()
+            .map(transaction)
+            .collect::<Result<_, _>>()?,
+        signatures: block
+            .signatures
+            .unwrap_or_default()
+            .iter()
+            .map(|signature| base58_bytes(signature))
+            .collect::<Result<_, _>>()?,
+        rewards: block
+            .rewards
+            .unwrap_or_default()
+            .into_iter()
+            .map(reward)
+            .collect::<Result<_, _>>()?,
+        block_time: block.block_time,
+        block_height: block.block_height,
+    })
+}
+
+fn slot_notification(subscription_id: u64, json: &str) -> Result<SlotNotification, Status> {
+    let SlotInfo { slot, parent, root } = notification_result(json)?;
+    Ok(SlotNotification {
+        subscription_id,
+        slot,
+        parent,
+        root,
+    })
+}
+
+fn account_notification(subscription_id: u64, json: &str) -> Result<AccountNotification, Status> {
+    let response: RpcResponse<UiAccount> = notification_result(json)?;
+    Ok(AccountNotification {
+        subscription_id,
+        slot: response.context.slot,
+        account: Some(account(response.value)?),
+    })
+}
+
+fn program_notification(subscription_id: u64, json: &str) -> Result<ProgramNotification, Status> {
+    let response: RpcResponse<RpcKeyedAccount> = notification_result(json)?;
+    Ok(ProgramNotification {
+        subscription_id,
+        slot: response.context.slot,
+        pubkey: base58_bytes(&response.value.pubkey)?,
+        account: Some(account(response.value.account)?),
+    })
+}
+
+fn logs_notification(subscription_id: u64, json: &str) -> Result<LogsNotification, Status> {
+    let response: RpcResponse<RpcLogsResponse> = notification_result(json)?;
+    Ok(LogsNotification {
+        subscription_id,
+        slot: response.context.slot,
+        signature: base58_bytes(&response.value.signature)?,
+        err: response.value.err.map(|err| err.to_string()),
+        logs: response.value.logs,
+    })
+}
+
+fn block_notification(subscription_id: u64, json: &str) -> Result<BlockNotification, Status> {
+    let response: RpcResponse<RpcBlockUpdate> = notification_result(json)?;
+    Ok(BlockNotification {
+        subscription_id,
+        slot: response.value.slot,
+        err: response.value.err.map(|err| err.to_string()),
+        block: response.value.block.map(block).transpose()?,
+    })
+}
+
+#[tonic::async_trait]
+impl RpcStream for RpcStreamImpl {
+    type SlotSubscribeStream = NotificationStream<SlotNotification>;
+    type AccountSubscribeStream = NotificationStream<AccountNotification>;
+    type ProgramSubscribeStream = NotificationStream<ProgramNotification>;
+    type LogsSubscribeStream = NotificationStream<LogsNotification>;
+    type BlockSubscribeStream = NotificationStream<BlockNotification>;
+
+    async fn slot_subscribe(
+        &self,
+        _request: Request<SlotSubscribeRequest>,
+    ) -> Result<Response<Self::SlotSubscribeStream>, Status> {
+        self.stream(SubscriptionParams::Slot, slot_notification)
+    }
+
+    async fn account_subscribe(
+        &self,
+        request: Request<AccountSubscribeRequest>,
+    ) -> Result<Response<Self::AccountSubscribeStream>, Status> {
+        let request = request.into_inner();
+        let params = AccountSubscriptionParams {
+            pubkey: param_pubkey(&request.pubkey, "pubkey")?,
+            commitment: commitment_config(request.commitment()),
+            encoding: UiAccountEncoding::Base64,
+            data_slice: data_slice(request.data_slice),
+        };
+        self.stream(SubscriptionParams::Account(params), account_notification)
+    }
+
+    async fn program_subscribe(
+        &self,
+        request: Request<ProgramSubscribeRequest>,
+    ) -> Result<Response<Self::ProgramSubscribeStream>, Status> {
+        let request = request.into_inner();
+        let params = ProgramSubscriptionParams {
+            pubkey: param_pubkey(&request.program_id, "program_id")?,
+            commitment: commitment_config(request.commitment()),
+            encoding: UiAccountEncoding::Base64,
+            data_slice: data_slice(request.data_slice),
+            filters: account_filters(request.filters)?,
+            with_context: true,
+        };
+        self.stream(SubscriptionParams::Program(params), program_notification)
+    }
+
+    async fn logs_subscribe(
+        &self,
+        request: Request<LogsSubscribeRequest>,
+    ) -> Result<Response<Self::LogsSubscribeStream>, Status> {
+        let request = request.into_inner();
+        let kind = match &request.mentions {
+            Some(mentions) => LogsSubscriptionKind::Single(param_pubkey(mentions, "mentions")?),
+            None if request.include_votes => LogsSubscriptionKind::AllWithVotes,
+            None => LogsSubscriptionKind::All,
+        };
+        let params = LogsSubscriptionParams {
+            kind,
+            commitment: commitment_config(request.commitment()),
+        };
+        self.stream(SubscriptionParams::Logs(params), logs_notification)
+    }
+
+    async fn block_subscribe(
+        &self,
+        request: Request<BlockSubscribeRequest>,
+    ) -> Result<Response<Self::BlockSubscribeStream>, Status> {
+        if !self.config.enable_block_subscription {
+            return Err(Status::unimplemented("Block subscriptions are disabled"));
+        }
+        let request = request.into_inner();
+        let commitment = commitment_config(request.commitment());
+        check_is_at_least_confirmed(commitment)
+            .map_err(|err| Status::invalid_argument(err.message))?;
+        let kind = match &request.mentions_account_or_program {
+            Some(mentions) => BlockSubscriptionKind::MentionsAccountOrProgram(param_pubkey(
+                mentions,
+                "mentions_account_or_program",
+            )?),
+            None => BlockSubscriptionKind::All,
+        };
+        let max_supported_transaction_version = request
+            .max_supported_transaction_version
+            .map(u8::try_from)
+            .transpose()
+            .map_err(|_| {
+                Status::invalid_argument(
+                    "Invalid Request: Invalid max_supported_transaction_version provided",
+                )
+            })?;
+        let params = BlockSubscriptionParams {
+            commitment,
+            encoding: UiTransactionEncoding::Base64,
+            kind,
+            transaction_details: transaction_details(request.transaction_details()),
+            show_rewards: request.show_rewards,
+            max_supported_transaction_version,
+        };
+        self.stream(SubscriptionParams::Block(params), block_notification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
+        proto::rpc_stream_client::RpcStreamClient,
+        solana_runtime::{
+            bank::Bank,
+            bank_forks::BankForks,
+            commitment::BlockCommitmentCache,
+            genesis_utils::{GenesisConfigInfo, create_genesis_config},
+        },
+        std::{
+            net::{IpAddr, Ipv4Addr},
+            sync::{
+                Arc, RwLock,
+                atomic::{AtomicBool, AtomicU64},
+            },
+            time::{Duration, Instant},
+        },
+    };
+
+    fn new_subscriptions() -> Arc<RpcSubscriptions> {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank = Bank::new_for_tests(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank);
+        let optimistically_confirmed_bank =
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
+        Arc::new(RpcSubscriptions::new_for_tests(
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(AtomicU64::default()),
+            bank_forks,
+            Arc::new(RwLock::new(BlockCommitmentCache::new_for_tests())),
+            optimistically_confirmed_bank,
+        ))
+    }
+
+    #[test]
+    fn test_grpc_stream_new() {
+        let grpc_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0);
+        let subscriptions = new_subscriptions();
+        let (trigger, grpc_stream_service) =
+            GrpcStreamService::new(PubSubConfig::default_for_tests(), &subscriptions, grpc_addr);
+        let thread = grpc_stream_service.thread_hdl.thread();
+        assert_eq!(thread.name().unwrap(), "solRpcGrpc");
+        trigger.cancel();
+        grpc_stream_service.join().unwrap();
+    }
+
+    #[test]
+    fn test_grpc_slot_subscribe() {
+        let port = solana_net_utils::sockets::unique_port_range_for_tests(1).start;
+        let grpc_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+        let subscriptions = new_subscriptions();
+        let (trigger, grpc_stream_service) =
+            GrpcStreamService::new(PubSubConfig::default_for_tests(), &subscriptions, grpc_addr);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let connect_deadline = Instant::now() + Duration::from_secs(30);
+            let mut client = loop {
+                match RpcStreamClient::connect(format!("http://{grpc_addr}")).await {
+                    Ok(client) => break client,
+                    Err(_) => {
+                        assert!(
+                            Instant::now() <= connect_deadline,
+                            "Timed out waiting for grpc service to start",
+                        );
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                }
+            };
+            let mut stream = client
+                .slot_subscribe(SlotSubscribeRequest {})
+                .await
+                .unwrap()
+                .into_inner();
+
+            subscriptions.notify_slot(1, 0, 0);
+            let notification = tokio::time::timeout(Duration::from_secs(10), stream.message())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            assert_eq!(notification.slot, 1);
+            assert_eq!(notification.parent, 0);
+            assert_eq!(notification.root, 0);
+        });
+
+        trigger.cancel();
+        grpc_stream_service.join().unwrap();
+    }
+
+    #[test]
+    fn test_grpc_block_subscribe_disabled() {
+        let subscriptions = new_subscriptions();
+        let rpc_stream = RpcStreamImpl::new(
+            PubSubConfig::default_for_tests(),
+            subscriptions.control().clone(),
+        );
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let status = runtime
+            .block_on(
+                rpc_stream.block_subscribe(Request::new(BlockSubscribeRequest {
+                    commitment: proto::Commitment::Confirmed.into(),
+                    ..BlockSubscribeRequest::default()
+                })),
+            )
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+    }
+
+    #[test]
+    fn test_translate_notifications() {
+        let signature = solana_signature::Signature::from([7; 64]);
+        let json = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "logsNotification",
+            "params": {
+                "result": {
+                    "context": { "slot": 5 },
+                    "value": {
+                        "signature": signature.to_string(),
+                        "err": null,
+                        "logs": ["Program log: hello"],
+                    },
+                },
+                "subscription": 3,
+            },
+        });
+        assert_eq!(
+            logs_notification(3, &json.to_string()).unwrap(),
+            LogsNotification {
+                subscription_id: 3,
+                slot: 5,
+                signature: signature.as_ref().to_vec(),
+                err: None,
+                logs: vec!["Program log: hello".to_string()],
+            }
+        );
+
+        let json = serde_json::json!({
+            "params": { "result": { "context": { "slot": 5 }, "value": null } },
+        });
+        let status = account_notification(3, &json.to_string()).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Internal);
+    }
+
+    #[test]
+    fn test_account_filters() {
+        let filters = account_filters(vec![
+            proto::AccountFilter {
+                filter: Some(account_filter::Filter::DataSize(165)),
+            },
+            proto::AccountFilter {
+                filter: Some(account_filter::Filter::Memcmp(proto::Memcmp {
+                    offset: 32,
+                    bytes: vec![1, 2, 3],
+                })),
+            },
+        ])
+        .unwrap();
+        assert_eq!(
+            filters,
+            vec![
+                RpcFilterType::DataSize(165),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(32, vec![1, 2, 3])),
+            ]
+        );
+
+        let status = account_filters(vec![proto::AccountFilter { filter: None }]).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+}
@@ -2,6 +2,7 @@
 
 use {
     crate::{
+        rpc_grpc_service::DEFAULT_GRPC_STREAM_QUEUE_CAPACITY,
         rpc_pubsub::{RpcSolPubSubImpl, RpcSolPubSubInternal},
         rpc_subscription_tracker::{
             SubscriptionControl, SubscriptionId, SubscriptionParams, SubscriptionToken,
@@ -46,6 +47,10 @@ pub struct PubSubConfig {
     pub queue_capacity_bytes: usize,
     pub worker_threads: usize,
     pub notification_threads: Option<NonZeroUsize>,
+    /// Address of the optional gRPC streaming service
+    pub grpc_addr: Option<SocketAddr>,
+    /// Number of notifications buffered per gRPC stream
+    pub grpc_stream_queue_capacity: usize,
 }
 
 impl PubSubConfig {
@@ -58,6 +63,8 @@ impl PubSubConfig {
             queue_capacity_bytes: DEFAULT_TEST_QUEUE_CAPACITY_BYTES,
             worker_threads: DEFAULT_WORKER_THREADS,
             notification_threads: NonZeroUsize::new(2),
+            grpc_addr: None,
+            grpc_stream_queue_capacity: DEFAULT_GRPC_STREAM_QUEUE_CAPACITY,
         }
     }
 }
@@ -0,0 +1,667 @@
+//! The `rpc_rate_limiter` module implements per-client token-bucket quotas for
+//! the JSON RPC service.
+//!
+//! Every client is identified either by one of the configured API keys or by
+//! its IP address. Each call is charged a configurable number of credits
+//! depending on the method, so a client looping over expensive methods like
+//! `getProgramAccounts` exhausts its budget long before one issuing cheap
+//! `getSlot` calls. Calls made without enough credits fail with
+//! [`RpcCustomError::RateLimited`].
+//!
+//! The IP address is the peer address of the connection, unless the
+//! connection comes from one of the configured trusted proxies. Only then is
+//! the client IP header, such as `X-Forwarded-For`, consulted. Clients can
+//! neither pick their bucket by forging that header nor by inventing API keys.
+
+use {
+    crate::rpc::JsonRpcRequestProcessor,
+    dashmap::DashMap,
+    jsonrpc_core::{
+        Call, Output,
+        futures::future::{self, Either},
+        middleware::{Middleware, NoopFuture},
+    },
+    jsonrpc_http_server::hyper::HeaderMap,
+    serde::{Deserialize, Serialize},
+    solana_rpc_client_api::custom_error::RpcCustomError,
+    solana_time_utils::AtomicInterval,
+    std::{
+        collections::HashMap,
+        future::Future,
+        net::{IpAddr, SocketAddr},
+        sync::{
+            Arc, RwLock,
+            atomic::{AtomicU64, Ordering},
+        },
+        time::{Duration, Instant},
+    },
+};
+
+pub const DEFAULT_RPC_CLIENT_IP_HEADER: &str = "x-forwarded-for";
+pub const DEFAULT_RPC_METHOD_COST: u64 = 1;
+const METRICS_REPORT_INTERVAL_MS: u64 = 10_000;
+
+fn default_client_ip_header() -> String {
+    DEFAULT_RPC_CLIENT_IP_HEADER.to_string()
+}
+
+fn default_method_cost() -> u64 {
+    DEFAULT_RPC_METHOD_COST
+}
+
+/// Refill rate and capacity of a single client's token bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RpcClientQuota {
+    /// Credits added to the bucket every second
+    pub credits_per_second: u64,
+    /// Maximum number of credits the bucket can hold
+    pub burst_credits: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RpcRateLimitConfig {
+    /// Quota applied to IP addresses without an entry in `client_overrides`
+    pub default_quota: RpcClientQuota,
+    /// Cost of methods that have no entry in `method_costs`
+    #[serde(default = "default_method_cost")]
+    pub default_method_cost: u64,
+    /// Per-method costs; a cost of zero exempts the method from limiting
+    #[serde(default)]
+    pub method_costs: HashMap<String, u64>,
+    /// If set, clients sending this header with one of the `api_keys` are
+    /// identified by that key instead of by their IP address. Unknown keys
+    /// are ignored.
+    #[serde(default)]
+    pub api_key_header: Option<String>,
+    /// Accepted API keys and their quotas
+    #[serde(default)]
+    pub api_keys: HashMap<String, RpcClientQuota>,
+    /// Header carrying the client IP address, appended to by the trusted
+    /// proxies. The address is read from the right, skipping trusted proxies.
+    #[serde(default = "default_client_ip_header")]
+    pub client_ip_header: String,
+    /// Addresses of the proxies in front of the RPC service. The client IP
+    /// header is ignored on connections from any other address.
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Quotas for specific IP addresses
+    #[serde(default)]
+    pub client_overrides: HashMap<IpAddr, RpcClientQuota>,
+}
+
+impl RpcRateLimitConfig {
+    pub fn new(default_quota: RpcClientQuota) -> Self {
+        Self {
+            default_quota,
+            default_method_cost: DEFAULT_RPC_METHOD_COST,
+            method_costs: HashMap::default(),
+            api_key_header: None,
+            api_keys: HashMap::default(),
+            client_ip_header: default_client_ip_header(),
+            trusted_proxies: Vec::default(),
+            client_overrides: HashMap::default(),
+        }
+    }
+
+    /// Rejects configurations under which some calls could never succeed
+    pub fn verify(&self) -> Result<(), String> {
+        let max_method_cost = self
+            .method_costs
+            .values()
+            .copied()
+            .chain(std::iter::once(self.default_method_cost))
+            .max()
+            .unwrap_or_default();
+        std::iter::once(("default".to_string(), &self.default_quota))
+            .chain(
+                self.api_keys
+                    .iter()
+                    .map(|(api_key, quota)| (format!("API key {api_key}"), quota)),
+            )
+            .chain(
+                self.client_overrides
+                    .iter()
+                    .map(|(ip, quota)| (ip.to_string(), quota)),
+            )
+            .try_for_each(|(client, quota)| {
+                if quota.credits_per_second == 0 {
+                    return Err(format!("{client} quota: creditsPerSecond must be positive"));
+                }
+                if quota.burst_credits < max_method_cost {
+                    return Err(format!(
+                        "{client} quota: burstCredits {} is less than the largest method cost \
+                         {max_method_cost}",
+                        quota.burst_credits
+                    ));
+                }
+                Ok(())
+            })
+    }
+
+    fn method_cost(&self, method: &str) -> u64 {
+        self.method_costs
+            .get(method)
+            .copied()
+            .unwrap_or(self.default_method_cost)
+    }
+
+    fn quota(&self, client_key: &RpcClientKey) -> &RpcClientQuota {
+        let overridden = match client_key {
+            RpcClientKey::ApiKey(api_key) => self.api_keys.get(api_key),
+            RpcClientKey::Ip(ip) => self.client_overrides.get(ip),
+        };
+        overridden.unwrap_or(&self.default_quota)
+    }
+
+    /// Resolves the IP address of the client behind a connection from `peer_ip`
+    fn client_ip(&self, peer_ip: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.trusted_proxies.contains(&peer_ip) {
+            return peer_ip;
+        }
+        // Each proxy appends the address it received the request from, so
+        // everything left of the last untrusted address may be forged.
+        let forwarded: Vec<_> = headers
+            .get_all(self.client_ip_header.as_str())
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+        forwarded
+            .iter()
+            .rev()
+            .map_while(|ip| ip.trim().parse::<IpAddr>().ok())
+            .find(|ip| !self.trusted_proxies.contains(ip))
+            .unwrap_or(peer_ip)
+    }
+}
+
+/// Identity a client's calls are accounted against
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RpcClientKey {
+    ApiKey(String),
+    Ip(IpAddr),
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    credits: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(quota: &RpcClientQuota, now: Instant) -> Self {
+        Self {
+            credits: quota.burst_credits as f64,
+            last_refill: now,
+        }
+    }
+
+    /// Takes `cost` credits from the bucket, or returns how long the caller
+    /// has to wait until enough credits are available
+    fn try_consume(
+        &mut self,
+        cost: u64,
+        quota: &RpcClientQuota,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.credits = (self.credits + elapsed.as_secs_f64() * quota.credits_per_second as f64)
+            .min(quota.burst_credits as f64);
+        self.last_refill = now;
+
+        let cost = cost as f64;
+        if self.credits >= cost {
+            self.credits -= cost;
+            Ok(())
+        } else {
+            Err(Duration::try_from_secs_f64(
+                (cost - self.credits) / quota.credits_per_second as f64,
+            )
+            .unwrap_or(Duration::MAX))
+        }
+    }
+
+    fn is_full(&self, quota: &RpcClientQuota, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.credits + elapsed.as_secs_f64() * quota.credits_per_second as f64
+            >= quota.burst_credits as f64
+    }
+}
+
+#[derive(Default)]
+struct RpcRateLimiterStats {
+    last_report: AtomicInterval,
+    allowed: AtomicU64,
+    allowed_credits: AtomicU64,
+    rejected: AtomicU64,
+    rejected_credits: AtomicU64,
+}
+
+/// Shared, reloadable rate limiter for the JSON RPC service. A limiter without
+/// a configuration lets every call through.
+#[derive(Default)]
+pub struct RpcRateLimiter {
+    config: RwLock<Option<Arc<RpcRateLimitConfig>>>,
+    buckets: DashMap<RpcClientKey, TokenBucket>,
+    stats: RpcRateLimiterStats,
+}
+
+impl RpcRateLimiter {
+    pub fn new(config: Option<RpcRateLimitConfig>) -> Self {
+        Self {
+            config: RwLock::new(config.map(Arc::new)),
+            ..Self::default()
+        }
+    }
+
+    /// Replaces the active configuration. All clients start over with a full
+    /// bucket under the new quotas.
+    pub fn set_config(&self, config: Option<RpcRateLimitConfig>) {
+        *self.config.write().unwrap() = config.map(Arc::new);
+        self.buckets.clear();
+    }
+
+    pub fn config(&self) -> Option<RpcRateLimitConfig> {
+        self.active_config().map(|config| (*config).clone())
+    }
+
+    fn active_config(&self) -> Option<Arc<RpcRateLimitConfig>> {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Identifies the client of an HTTP request received from `peer_addr`, or
+    /// returns `None` if rate limiting is disabled
+    pub fn client_key(&self, peer_addr: SocketAddr, headers: &HeaderMap) -> Option<RpcClientKey> {
+        let config = self.active_config()?;
+        let api_key = config
+            .api_key_header
+            .as_ref()
+            .and_then(|header| headers.get(header.as_str()))
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|api_key| config.api_keys.contains_key(*api_key));
+        if let Some(api_key) = api_key {
+            return Some(RpcClientKey::ApiKey(api_key.to_string()));
+        }
+        Some(RpcClientKey::Ip(config.client_ip(peer_addr.ip(), headers)))
+    }
+
+    /// Charges `client_key` for a call to `method`
+    pub fn check(&self, client_key: &RpcClientKey, method: &str) -> Result<(), RpcCustomError> {
+        self.check_at(client_key, method, Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        client_key: &RpcClientKey,
+        method: &str,
+        now: Instant,
+    ) -> Result<(), RpcCustomError> {
+        let Some(config) = self.active_config() else {
+            return Ok(());
+        };
+        let cost = config.method_cost(method);
+        if cost == 0 {
+            return Ok(());
+        }
+        let quota = config.quota(client_key);
+        let result = self
+            .buckets
+            .entry(client_key.clone())
+            .or_insert_with(|| TokenBucket::new(quota, now))
+            .try_consume(cost, quota, now);
+        self.maybe_report(&config, now);
+
+        match result {
+            Ok(()) => {
+                self.stats.allowed.fetch_add(1, Ordering::Relaxed);
+                self.stats
+                    .allowed_credits
+                    .fetch_add(cost, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(retry_after) => {
+                self.stats.rejected.fetch_add(1, Ordering::Relaxed);
+                self.stats
+                    .rejected_credits
+                    .fetch_add(cost, Ordering::Relaxed);
+                Err(RpcCustomError::RateLimited {
+                    method: method.to_string(),
+                    retry_after_ms: u64::try_from(retry_after.as_millis())
+                        .unwrap_or(u64::MAX)
+                        .max(1),
+                })
+            }
+        }
+    }
+
+    fn maybe_report(&self, config: &RpcRateLimitConfig, now: Instant) {
+        if !self
+            .stats
+            .last_report
+            .should_update(METRICS_REPORT_INTERVAL_MS)
+        {
+            return;
+        }
+        // Full buckets carry no state, so idle clients can be forgotten
+        self.buckets
+            .retain(|client_key, bucket| !bucket.is_full(config.quota(client_key), now));
+        datapoint_info!(
+            "rpc-rate-limiter",
+            (
+                "allowed",
+                self.stats.allowed.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "allowed_credits",
+                self.stats.allowed_credits.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "rejected",
+                self.stats.rejected.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "rejected_credits",
+                self.stats.rejected_credits.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            ("active_clients", self.buckets.len(), i64),
+        );
+    }
+}
+
+/// Charges every call of a request, including each call of a batch, against
+/// the client's quota before it is dispatched
+pub(crate) struct RpcRateLimitMiddleware {
+    rate_limiter: Arc<RpcRateLimiter>,
+}
+
+impl RpcRateLimitMiddleware {
+    pub(crate) fn new(rate_limiter: Arc<RpcRateLimiter>) -> Self {
+        Self { rate_limiter }
+    }
+}
+
+impl Middleware<JsonRpcRequestProcessor> for RpcRateLimitMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = future::Ready<Option<Output>>;
+
+    fn on_call<F, X>(
+        &self,
+        call: Call,
+        meta: JsonRpcRequestProcessor,
+        next: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, JsonRpcRequestProcessor) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        if let Some(client_key) = meta.rpc_client_key() {
+            match &call {
+                Call::MethodCall(method_call) => {
+                    if let Err(err) = self.rate_limiter.check(client_key, &method_call.method) {
+                        return Either::Left(future::ready(Some(Output::from(
+                            Err(err.into()),
+                            method_call.id.clone(),
+                            method_call.jsonrpc,
+                        ))));
+                    }
+                }
+                Call::Notification(notification) => {
+                    if self
+                        .rate_limiter
+                        .check(client_key, &notification.method)
+                        .is_err()
+                    {
+                        return Either::Left(future::ready(None));
+                    }
+                }
+                Call::Invalid { .. } => {}
+            }
+        }
+        Either::Right(next(call, meta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, jsonrpc_http_server::hyper::header::HeaderValue,
+        solana_rpc_client_api::custom_error::JSON_RPC_SERVER_ERROR_RATE_LIMITED,
+    };
+
+    fn test_config() -> RpcRateLimitConfig {
+        RpcRateLimitConfig {
+            method_costs: HashMap::from([
+                ("getProgramAccounts".to_string(), 5),
+                ("getHealth".to_string(), 0),
+            ]),
+            api_key_header: Some("x-api-key".to_string()),
+            api_keys: HashMap::from([(
+                "premium".to_string(),
+                RpcClientQuota {
+                    credits_per_second: 100,
+                    burst_credits: 100,
+                },
+            )]),
+            trusted_proxies: vec!["127.0.0.1".parse().unwrap()],
+            ..RpcRateLimitConfig::new(RpcClientQuota {
+                credits_per_second: 1,
+                burst_credits: 10,
+            })
+        }
+    }
+
+    #[test]
+    fn test_client_key() {
+        let client: SocketAddr = "10.0.0.1:8000".parse().unwrap();
+        let proxy: SocketAddr = "127.0.0.1:8000".parse().unwrap();
+        let rate_limiter = RpcRateLimiter::default();
+        assert_eq!(rate_limiter.client_key(client, &HeaderMap::new()), None);
+
+        rate_limiter.set_config(Some(test_config()));
+        let client_ip = RpcClientKey::Ip(client.ip());
+        assert_eq!(
+            rate_limiter.client_key(client, &HeaderMap::new()),
+            Some(client_ip.clone())
+        );
+
+        // Only trusted proxies can name the client
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("192.168.0.1, 10.0.0.2"),
+        );
+        assert_eq!(
+            rate_limiter.client_key(client, &headers),
+            Some(client_ip.clone())
+        );
+        assert_eq!(
+            rate_limiter.client_key(proxy, &headers),
+            Some(RpcClientKey::Ip("10.0.0.2".parse().unwrap()))
+        );
+
+        // Addresses left of the last untrusted hop may be forged by the client
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("192.168.0.1, 10.0.0.2, 127.0.0.1"),
+        );
+        assert_eq!(
+            rate_limiter.client_key(proxy, &headers),
+            Some(RpcClientKey::Ip("10.0.0.2".parse().unwrap()))
+        );
+
+        headers.insert("x-forwarded-for", HeaderValue::from_static("garbage"));
+        assert_eq!(
+            rate_limiter.client_key(proxy, &headers),
+            Some(RpcClientKey::Ip(proxy.ip()))
+        );
+
+        // Only configured API keys are accepted
+        headers.insert("x-api-key", HeaderValue::from_static("unknown"));
+        assert_eq!(rate_limiter.client_key(client, &headers), Some(client_ip));
+        headers.insert("x-api-key", HeaderValue::from_static("premium"));
+        assert_eq!(
+            rate_limiter.client_key(client, &headers),
+            Some(RpcClientKey::ApiKey("premium".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_method_costs_and_refill() {
+        let rate_limiter = RpcRateLimiter::new(Some(test_config()));
+        let client = RpcClientKey::Ip("10.0.0.1".parse().unwrap());
+        let now = Instant::now();
+
+        // Two scans drain the whole burst
+        assert!(
+            rate_limiter
+                .check_at(&client, "getProgramAccounts", now)
+                .is_ok()
+        );
+        assert!(
+            rate_limiter
+                .check_at(&client, "getProgramAccounts", now)
+                .is_ok()
+        );
+        let err = rate_limiter.check_at(&client, "getSlot", now).unwrap_err();
+        assert!(matches!(
+            err,
+            RpcCustomError::RateLimited {
+                ref method,
+                retry_after_ms: 1_000,
+            } if method == "getSlot"
+        ));
+        let err = jsonrpc_core::Error::from(err);
+        assert_eq!(
+            err.code,
+            jsonrpc_core::ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_RATE_LIMITED)
+        );
+
+        // Zero cost methods are never limited
+        assert!(rate_limiter.check_at(&client, "getHealth", now).is_ok());
+
+        // Other clients have their own bucket
+        let other_client = RpcClientKey::Ip("10.0.0.2".parse().unwrap());
+        assert!(rate_limiter.check_at(&other_client, "getSlot", now).is_ok());
+
+        // Credits are refilled over time
+        let later = now + Duration::from_secs(3);
+        assert!(rate_limiter.check_at(&client, "getSlot", later).is_ok());
+        assert!(rate_limiter.check_at(&client, "getSlot", later).is_ok());
+        assert!(rate_limiter.check_at(&client, "getSlot", later).is_ok());
+        assert!(rate_limiter.check_at(&client, "getSlot", later).is_err());
+        assert!(
+            rate_limiter
+                .check_at(
+                    &client,
+                    "getProgramAccounts",
+                    later + Duration::from_secs(4)
+                )
+                .is_err()
+        );
+        assert!(
+            rate_limiter
+                .check_at(
+                    &client,
+                    "getProgramAccounts",
+                    later + Duration::from_secs(5)
+                )
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_client_overrides() {
+        let rate_limiter = RpcRateLimiter::new(Some(test_config()));
+        let premium = RpcClientKey::ApiKey("premium".to_string());
+        let now = Instant::now();
+        for _ in 0..20 {
+            assert!(
+                rate_limiter
+                    .check_at(&premium, "getProgramAccounts", now)
+                    .is_ok()
+            );
+        }
+        assert!(
+            rate_limiter
+                .check_at(&premium, "getProgramAccounts", now)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_set_config() {
+        let rate_limiter = RpcRateLimiter::new(Some(test_config()));
+        let client = RpcClientKey::Ip("10.0.0.1".parse().unwrap());
+        let now = Instant::now();
+        for _ in 0..10 {
+            assert!(rate_limiter.check_at(&client, "getSlot", now).is_ok());
+        }
+        assert!(rate_limiter.check_at(&client, "getSlot", now).is_err());
+
+        // Reloading resets all buckets
+        rate_limiter.set_config(Some(test_config()));
+        assert!(rate_limiter.check_at(&client, "getSlot", now).is_ok());
+
+        rate_limiter.set_config(None);
+        assert_eq!(rate_limiter.config(), None);
+        for _ in 0..100 {
+            assert!(rate_limiter.check_at(&client, "getSlot", now).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_verify_config() {
+        assert!(test_config().verify().is_ok());
+
+        let mut config = test_config();
+        config.default_quota.credits_per_second = 0;
+        assert!(config.verify().is_err());
+
+        let mut config = test_config();
+        config
+            .method_costs
+            .insert("getProgramAccounts".to_string(), 11);
+        assert!(config.verify().is_err());
+
+        let mut config = test_config();
+        config.client_overrides.insert(
+            "10.0.0.1".parse().unwrap(),
+            RpcClientQuota {
+                credits_per_second: 1,
+                burst_credits: 1,
+            },
+        );
+        assert!(config.verify().is_err());
+    }
+
+    #[test]
+    fn test_deserialize_config() {
+        let config: RpcRateLimitConfig = serde_json::from_value(json!({
+            "defaultQuota": {
+                "creditsPerSecond": 1,
+                "burstCredits": 10,
+            },
+            "methodCosts": {
+                "getProgramAccounts": 5,
+                "getHealth": 0,
+            },
+            "apiKeyHeader": "x-api-key",
+            "apiKeys": {
+                "premium": {
+                    "creditsPerSecond": 100,
+                    "burstCredits": 100,
+                },
+            },
+            "trustedProxies": ["127.0.0.1"],
+        }))
+        .unwrap();
+        assert_eq!(config, test_config());
+    }
+}
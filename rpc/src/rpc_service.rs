@@ -6,18 +6,29 @@ use {
         max_slots::MaxSlots,
         optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
         rpc::{rpc_accounts::*, rpc_accounts_scan::*, rpc_bank::*, rpc_full::*, rpc_minimal::*, *},
+        rpc_bank_pin::{RpcBankPin, RpcBankPinMiddleware},
         rpc_cache::LargestAccountsCache,
         rpc_health::*,
+        rpc_rate_limiter::{RpcRateLimitMiddleware, RpcRateLimiter},
+        rpc_slow_query_log::{RpcSlowQueryLog, RpcSlowQueryMiddleware},
     },
     agave_snapshots::{
         SnapshotInterval, paths as snapshot_paths,
         snapshot_archive_info::SnapshotArchiveInfoGetter, snapshot_config::SnapshotConfig,
     },
     crossbeam_channel::unbounded,
-    jsonrpc_core::{MetaIoHandler, futures::prelude::*},
+    jsonrpc_core::{
+        MetaIoHandler, Metadata, Middleware,
+        futures::{future, prelude::*},
+    },
     jsonrpc_http_server::{
-        AccessControlAllowOrigin, CloseHandle, DomainsValidation, RequestMiddleware,
-        RequestMiddlewareAction, ServerBuilder, hyper,
+        AccessControlAllowOrigin, RequestMiddleware, RequestMiddlewareAction, RestApi, Rpc,
+        ServerHandler, cors,
+        hyper::{
+            self,
+            server::conn::AddrStream,
+            service::{Service, make_service_fn, service_fn},
+        },
     },
     regex::Regex,
     solana_cli_output::display::build_balance_message,
@@ -47,6 +58,7 @@ use {
     solana_tls_utils::NotifyKeyUpdate,
     solana_validator_exit::Exit,
     std::{
+        convert::Infallible,
         net::{SocketAddr, UdpSocket},
         path::{Path, PathBuf},
         pin::Pin,
@@ -118,11 +130,65 @@ pub struct JsonRpcService {
     #[cfg(test)]
     pub request_processor: JsonRpcRequestProcessor, // Used only by test_rpc_new()...
 
-    close_handle: Option<CloseHandle>,
+    close_handle: Option<CancellationToken>,
 
     client_updater: Arc<dyn NotifyKeyUpdate + Send + Sync>,
 }
 
+/// Peer address of the connection an HTTP request arrived on, stored in the
+/// request extensions
+#[derive(Debug, Clone, Copy)]
+struct RpcPeerAddr(SocketAddr);
+
+/// Starts serving JSON RPC over HTTP on `runtime`.
+///
+/// This mirrors `jsonrpc_http_server::ServerBuilder::start_http` with CORS open to
+/// any origin, except that every request carries the [`RpcPeerAddr`] of its
+/// connection, which the builder doesn't expose to the metadata extractor.
+fn start_http<M, S>(
+    runtime: &TokioRuntime,
+    rpc: &Rpc<M, S>,
+    request_middleware: Arc<dyn RequestMiddleware>,
+    max_request_body_size: usize,
+    rpc_addr: &SocketAddr,
+    close_handle: CancellationToken,
+) -> hyper::Result<impl Future<Output = hyper::Result<()>> + use<M, S>>
+where
+    M: Metadata + Unpin,
+    S: Middleware<M>,
+    S::Future: Unpin,
+    S::CallFuture: Unpin,
+{
+    let _guard = runtime.enter();
+    let rpc = rpc.downgrade();
+    let make_service = make_service_fn(move |conn: &AddrStream| {
+        let peer_addr = RpcPeerAddr(conn.remote_addr());
+        let mut handler = ServerHandler::new(
+            rpc.clone(),
+            Some(vec![AccessControlAllowOrigin::Any]),
+            Some(86400),
+            cors::AccessControlAllowHeaders::Any,
+            None,
+            request_middleware.clone(),
+            RestApi::Disabled,
+            None,
+            max_request_body_size,
+            true,
+        );
+        let service = service_fn(move |mut request: hyper::Request<hyper::Body>| {
+            request.extensions_mut().insert(peer_addr);
+            handler.call(request)
+        });
+        future::ready(Ok::<_, Infallible>(service))
+    });
+    Ok(hyper::Server::try_bind(rpc_addr)?
+        .http1_keepalive(true)
+        .tcp_nodelay(true)
+        .tcp_sleep_on_accept_errors(true)
+        .serve(make_service)
+        .with_graceful_shutdown(async move { close_handle.cancelled().await }))
+}
+
 struct RpcRequestMiddleware {
     ledger_path: PathBuf,
     full_snapshot_archive_path_regex: Regex,
@@ -493,6 +559,8 @@ pub struct JsonRpcServiceConfig<'a> {
     pub leader_schedule_cache: Arc<LeaderScheduleCache>,
     pub max_complete_transaction_status_slot: Arc<AtomicU64>,
     pub prioritization_fee_cache: Option<Arc<PrioritizationFeeCache>>,
+    pub rpc_rate_limiter: Arc<RpcRateLimiter>,
+    pub rpc_slow_query_log: Option<Arc<RpcSlowQueryLog>>,
     pub rpc_tpu_client_args: RpcTpuClientArgs<'a>,
 }
 
@@ -563,6 +631,8 @@ impl JsonRpcService {
             client,
             config.max_complete_transaction_status_slot,
             config.prioritization_fee_cache,
+            config.rpc_rate_limiter,
+            config.rpc_slow_query_log,
             runtime,
         )?;
         Ok(json_rpc_service)
@@ -596,6 +666,8 @@ impl JsonRpcService {
         client: Client,
         max_complete_transaction_status_slot: Arc<AtomicU64>,
         prioritization_fee_cache: Option<Arc<PrioritizationFeeCache>>,
+        rpc_rate_limiter: Arc<RpcRateLimiter>,
+        rpc_slow_query_log: Option<Arc<RpcSlowQueryLog>>,
         runtime: Arc<TokioRuntime>,
     ) -> Result<Self, String> {
         info!("rpc bound to {rpc_addr:?}");
@@ -709,7 +781,13 @@ impl JsonRpcService {
             .spawn(move || {
                 renice_this_thread(rpc_niceness_adj).unwrap();
 
-                let mut io = MetaIoHandler::default();
+                // Calls rejected by the rate limiter never reach the slow query log.
+                // Requests pinning a bank resolve it before any of their calls run.
+                let mut io = MetaIoHandler::with_middleware((
+                    RpcRateLimitMiddleware::new(rpc_rate_limiter.clone()),
+                    RpcSlowQueryMiddleware::new(rpc_slow_query_log),
+                    RpcBankPinMiddleware,
+                ));
 
                 io.extend_with(rpc_minimal::MinimalImpl.to_delegate());
                 if full_api {
@@ -725,40 +803,52 @@ impl JsonRpcService {
                     bank_forks,
                     health.clone(),
                 );
-                let server = ServerBuilder::with_meta_extractor(
-                    io,
-                    move |req: &hyper::Request<hyper::Body>| {
+                let rpc = Rpc {
+                    handler: Arc::new(io),
+                    extractor: Arc::new(move |req: &hyper::Request<hyper::Body>| {
                         let xbigtable = req.headers().get("x-bigtable");
-                        if xbigtable.is_some_and(|v| v == "disabled") {
+                        let request_processor = if xbigtable.is_some_and(|v| v == "disabled") {
                             request_processor.clone_without_bigtable()
                         } else {
                             request_processor.clone()
-                        }
-                    },
-                )
-                .event_loop_executor(runtime.handle().clone())
-                .threads(1)
-                .cors(DomainsValidation::AllowOnly(vec![
-                    AccessControlAllowOrigin::Any,
-                ]))
-                .cors_max_age(86400)
-                .request_middleware(request_middleware)
-                .max_request_body_size(max_request_body_size)
-                .start_http(&rpc_addr);
-
-                if let Err(e) = server {
-                    warn!(
-                        "JSON RPC service unavailable error: {e:?}. Also, check that port {} is \
-                         not already in use by another application",
-                        rpc_addr.port()
-                    );
-                    close_handle_sender.send(Err(e.to_string())).unwrap();
-                    return;
-                }
+                        };
+                        let rpc_client_key = req.extensions().get::<RpcPeerAddr>().and_then(
+                            |RpcPeerAddr(peer_addr)| {
+                                rpc_rate_limiter.client_key(*peer_addr, req.headers())
+                            },
+                        );
+                        request_processor
+                            .with_rpc_client_key(rpc_client_key)
+                            .with_bank_pin(RpcBankPin::from_headers(req.headers()).transpose())
+                    }),
+                };
+                let close_handle = CancellationToken::new();
+                let server = start_http(
+                    &runtime,
+                    &rpc,
+                    Arc::new(request_middleware),
+                    max_request_body_size,
+                    &rpc_addr,
+                    close_handle.clone(),
+                );
+
+                let server = match server {
+                    Ok(server) => server,
+                    Err(e) => {
+                        warn!(
+                            "JSON RPC service unavailable error: {e:?}. Also, check that port {} \
+                             is not already in use by another application",
+                            rpc_addr.port()
+                        );
+                        close_handle_sender.send(Err(e.to_string())).unwrap();
+                        return;
+                    }
+                };
 
-                let server = server.unwrap();
-                close_handle_sender.send(Ok(server.close_handle())).unwrap();
-                server.wait();
+                close_handle_sender.send(Ok(close_handle)).unwrap();
+                if let Err(err) = runtime.block_on(server) {
+                    error!("JSON RPC service has stopped due to error: {err}");
+                }
                 exit_bigtable_ledger_upload_service.store(true, Ordering::Relaxed);
             })
             .unwrap();
@@ -769,7 +859,7 @@ impl JsonRpcService {
             .write()
             .unwrap()
             .register_exit(Box::new(move || {
-                close_handle_.close();
+                close_handle_.cancel();
             }));
         Ok(Self {
             thread_hdl,
@@ -782,7 +872,7 @@ impl JsonRpcService {
 
     pub fn exit(&mut self) {
         if let Some(c) = self.close_handle.take() {
-            c.close()
+            c.cancel()
         }
     }
 
@@ -801,15 +891,9 @@ pub fn service_runtime(
     rpc_blocking_threads: usize,
     rpc_niceness_adj: i8,
 ) -> Arc<TokioRuntime> {
-    // The jsonrpc_http_server crate supports two execution models:
-    //
-    // - By default, it spawns a number of threads - configured with .threads(N) - and runs a
-    //   single-threaded futures executor in each thread.
-    // - Alternatively when configured with .event_loop_executor(executor) and .threads(1),
-    //   it executes all the tasks on the given executor, not spawning any extra internal threads.
-    //
-    // We use the latter configuration, using a multi threaded tokio runtime as the executor. We
-    // do this so we can configure the number of worker threads, the number of blocking threads
+    // The HTTP server spawns every connection, and with it every request, on this runtime
+    // rather than on internal threads of the jsonrpc_http_server crate. We use a multi threaded
+    // tokio runtime so we can configure the number of worker threads, the number of blocking threads
     // and then use tokio::task::spawn_blocking() to avoid blocking the worker threads on CPU
     // bound operations like getMultipleAccounts. This results in reduced latency, since fast
     // rpc calls (the majority) are not blocked by slow CPU bound ones.
@@ -915,6 +999,8 @@ mod tests {
             client,
             Arc::new(AtomicU64::default()),
             Some(Arc::new(PrioritizationFeeCache::default())),
+            Arc::new(RpcRateLimiter::default()),
+            None,
             runtime,
         )
         .expect("assume successful JsonRpcService start");
@@ -0,0 +1,545 @@
+//! The `rpc_slow_query_log` module records JSON RPC calls that take longer
+//! than a configured threshold.
+//!
+//! Every call is handed an [`RpcRequestTrace`] through its request metadata,
+//! which the request processor fills in with the bank slot it used and the
+//! time it spent looking up the bank, scanning accounts and encoding the
+//! response. Calls slower than the threshold are appended as JSON lines to a
+//! size-rotated log file and folded into per-method aggregates that can be
+//! queried through the admin RPC service.
+
+use {
+    crate::rpc::JsonRpcRequestProcessor,
+    jsonrpc_core::{
+        Call, Output, Params,
+        futures::future::Either,
+        middleware::{Middleware, NoopCallFuture, NoopFuture},
+    },
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+    solana_clock::Slot,
+    std::{
+        collections::HashMap,
+        ffi::OsString,
+        fs::{self, File, OpenOptions},
+        future::Future,
+        io::{self, BufWriter, Write},
+        path::{Path, PathBuf},
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicU64, Ordering},
+        },
+        time::{Duration, Instant},
+    },
+};
+
+pub const DEFAULT_RPC_SLOW_QUERY_THRESHOLD_MS: u64 = 1_000;
+pub const DEFAULT_RPC_SLOW_QUERY_LOG_MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
+pub const DEFAULT_RPC_SLOW_QUERY_LOG_MAX_FILES: usize = 5;
+
+// Strings longer than this, like serialized transactions, are replaced by
+// their length when logging parameters
+const MAX_LOGGED_STRING_LEN: usize = 128;
+const MAX_LOGGED_ARRAY_LEN: usize = 16;
+const MAX_LOGGED_DEPTH: usize = 8;
+const NO_SLOT: u64 = u64::MAX;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcSlowQueryLogConfig {
+    /// File slow calls are appended to
+    pub path: PathBuf,
+    /// Calls taking at least this long are logged
+    pub threshold: Duration,
+    /// Size at which the log file is rotated
+    pub max_file_size: u64,
+    /// Number of rotated files kept next to the active one
+    pub max_files: usize,
+}
+
+impl RpcSlowQueryLogConfig {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            threshold: Duration::from_millis(DEFAULT_RPC_SLOW_QUERY_THRESHOLD_MS),
+            max_file_size: DEFAULT_RPC_SLOW_QUERY_LOG_MAX_FILE_SIZE,
+            max_files: DEFAULT_RPC_SLOW_QUERY_LOG_MAX_FILES,
+        }
+    }
+}
+
+/// Where a single call spent its time, filled in while the call is processed
+#[derive(Debug)]
+pub struct RpcRequestTrace {
+    bank_slot: AtomicU64,
+    bank_lookup_us: AtomicU64,
+    accounts_scan_us: AtomicU64,
+    encoding_us: AtomicU64,
+}
+
+impl Default for RpcRequestTrace {
+    fn default() -> Self {
+        Self {
+            bank_slot: AtomicU64::new(NO_SLOT),
+            bank_lookup_us: AtomicU64::default(),
+            accounts_scan_us: AtomicU64::default(),
+            encoding_us: AtomicU64::default(),
+        }
+    }
+}
+
+impl RpcRequestTrace {
+    pub fn record_bank_lookup(&self, slot: Slot, us: u64) {
+        self.bank_slot.store(slot, Ordering::Relaxed);
+        self.bank_lookup_us.fetch_add(us, Ordering::Relaxed);
+    }
+
+    pub fn record_accounts_scan(&self, us: u64) {
+        self.accounts_scan_us.fetch_add(us, Ordering::Relaxed);
+    }
+
+    pub fn record_encoding(&self, us: u64) {
+        self.encoding_us.fetch_add(us, Ordering::Relaxed);
+    }
+
+    fn bank_slot(&self) -> Option<Slot> {
+        let slot = self.bank_slot.load(Ordering::Relaxed);
+        (slot != NO_SLOT).then_some(slot)
+    }
+}
+
+/// Aggregates over all slow calls of one method
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSlowQueryMethodStats {
+    pub method: String,
+    pub count: u64,
+    pub total_latency_us: u64,
+    pub max_latency_us: u64,
+    pub total_bank_lookup_us: u64,
+    pub total_accounts_scan_us: u64,
+    pub total_encoding_us: u64,
+    pub total_response_bytes: u64,
+    pub max_response_bytes: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcSlowQueryEntry<'a> {
+    timestamp: u64,
+    method: &'a str,
+    params: Value,
+    bank_slot: Option<Slot>,
+    latency_us: u64,
+    bank_lookup_us: u64,
+    accounts_scan_us: u64,
+    encoding_us: u64,
+    response_bytes: u64,
+    error_code: Option<i64>,
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, max_file_size: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_file_size,
+            max_files,
+            writer: BufWriter::new(file),
+            size,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push(format!(".{index}"));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        if self.max_files > 0 {
+            for index in (1..self.max_files).rev() {
+                match fs::rename(self.rotated_path(index), self.rotated_path(index + 1)) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                    _ => {}
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.writer = BufWriter::new(file);
+        self.size = 0;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_file_size {
+            self.rotate()?;
+        }
+        self.writer.write_all(line)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        self.size += len;
+        Ok(())
+    }
+}
+
+pub struct RpcSlowQueryLog {
+    threshold: Duration,
+    file: Mutex<RotatingFile>,
+    stats: Mutex<HashMap<String, RpcSlowQueryMethodStats>>,
+}
+
+impl RpcSlowQueryLog {
+    pub fn new(config: RpcSlowQueryLogConfig) -> io::Result<Self> {
+        let RpcSlowQueryLogConfig {
+            path,
+            threshold,
+            max_file_size,
+            max_files,
+        } = config;
+        Ok(Self {
+            threshold,
+            file: Mutex::new(RotatingFile::open(&path, max_file_size, max_files)?),
+            stats: Mutex::default(),
+        })
+    }
+
+    /// Per-method aggregates of the slow calls seen so far, slowest first
+    pub fn stats(&self) -> Vec<RpcSlowQueryMethodStats> {
+        let mut stats: Vec<_> = self.stats.lock().unwrap().values().cloned().collect();
+        stats.sort_unstable_by(|a, b| {
+            b.total_latency_us
+                .cmp(&a.total_latency_us)
+                .then_with(|| a.method.cmp(&b.method))
+        });
+        stats
+    }
+
+    pub fn reset_stats(&self) {
+        self.stats.lock().unwrap().clear();
+    }
+
+    fn record(
+        &self,
+        method: &str,
+        params: &Params,
+        trace: &RpcRequestTrace,
+        latency: Duration,
+        output: Option<&Output>,
+    ) {
+        let response_bytes = output.map(response_len).unwrap_or_default();
+        let error_code = match output {
+            Some(Output::Failure(failure)) => Some(failure.error.code.code()),
+            _ => None,
+        };
+        let entry = RpcSlowQueryEntry {
+            timestamp: solana_time_utils::timestamp(),
+            method,
+            params: sanitize_params(params),
+            bank_slot: trace.bank_slot(),
+            latency_us: latency.as_micros() as u64,
+            bank_lookup_us: trace.bank_lookup_us.load(Ordering::Relaxed),
+            accounts_scan_us: trace.accounts_scan_us.load(Ordering::Relaxed),
+            encoding_us: trace.encoding_us.load(Ordering::Relaxed),
+            response_bytes,
+            error_code,
+        };
+
+        {
+            let mut stats = self.stats.lock().unwrap();
+            let stats =
+                stats
+                    .entry(method.to_string())
+                    .or_insert_with(|| RpcSlowQueryMethodStats {
+                        method: method.to_string(),
+                        ..RpcSlowQueryMethodStats::default()
+                    });
+            stats.count += 1;
+            stats.total_latency_us += entry.latency_us;
+            stats.max_latency_us = stats.max_latency_us.max(entry.latency_us);
+            stats.total_bank_lookup_us += entry.bank_lookup_us;
+            stats.total_accounts_scan_us += entry.accounts_scan_us;
+            stats.total_encoding_us += entry.encoding_us;
+            stats.total_response_bytes += entry.response_bytes;
+            stats.max_response_bytes = stats.max_response_bytes.max(entry.response_bytes);
+        }
+        inc_new_counter_info!("rpc-slow-query", 1);
+
+        let line = serde_json::to_vec(&entry).expect("serialize slow query entry");
+        if let Err(err) = self.file.lock().unwrap().write_line(&line) {
+            warn!("Failed to write RPC slow query log: {err}");
+        }
+    }
+}
+
+/// Counts the bytes written to it and discards them
+#[derive(Default)]
+struct ByteCounter(u64);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 = self.0.saturating_add(buf.len() as u64);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Size of `output` once serialized, measured without buffering the response
+/// a second time
+fn response_len(output: &Output) -> u64 {
+    let mut counter = ByteCounter::default();
+    match serde_json::to_writer(&mut counter, output) {
+        Ok(()) => counter.0,
+        Err(_) => 0,
+    }
+}
+
+fn sanitize_params(params: &Params) -> Value {
+    match params {
+        Params::None => Value::Null,
+        Params::Array(values) => sanitize_array(values, 0),
+        Params::Map(map) => sanitize_value(&Value::Object(map.clone()), 0),
+    }
+}
+
+fn sanitize_array(values: &[Value], depth: usize) -> Value {
+    let mut sanitized: Vec<_> = values
+        .iter()
+        .take(MAX_LOGGED_ARRAY_LEN)
+        .map(|value| sanitize_value(value, depth + 1))
+        .collect();
+    if values.len() > MAX_LOGGED_ARRAY_LEN {
+        sanitized.push(Value::String(format!(
+            "<{} more>",
+            values.len() - MAX_LOGGED_ARRAY_LEN
+        )));
+    }
+    Value::Array(sanitized)
+}
+
+/// Truncates large values so that a single entry stays small no matter what
+/// the client sent
+fn sanitize_value(value: &Value, depth: usize) -> Value {
+    if depth > MAX_LOGGED_DEPTH {
+        return Value::String("<nested>".to_string());
+    }
+    match value {
+        Value::String(string) if string.len() > MAX_LOGGED_STRING_LEN => {
+            Value::String(format!("<{} bytes>", string.len()))
+        }
+        Value::Array(values) => sanitize_array(values, depth),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), sanitize_value(value, depth + 1)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+/// Times every call and hands the request processor a trace to fill in, if
+/// the slow query log is enabled
+pub(crate) struct RpcSlowQueryMiddleware {
+    slow_query_log: Option<Arc<RpcSlowQueryLog>>,
+}
+
+impl RpcSlowQueryMiddleware {
+    pub(crate) fn new(slow_query_log: Option<Arc<RpcSlowQueryLog>>) -> Self {
+        Self { slow_query_log }
+    }
+}
+
+impl Middleware<JsonRpcRequestProcessor> for RpcSlowQueryMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = NoopCallFuture;
+
+    fn on_call<F, X>(
+        &self,
+        call: Call,
+        meta: JsonRpcRequestProcessor,
+        next: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, JsonRpcRequestProcessor) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let (Some(slow_query_log), Call::MethodCall(method_call)) = (&self.slow_query_log, &call)
+        else {
+            return Either::Right(next(call, meta));
+        };
+        let slow_query_log = Arc::clone(slow_query_log);
+        let method = method_call.method.clone();
+        let params = method_call.params.clone();
+        let trace = Arc::new(RpcRequestTrace::default());
+        let start = Instant::now();
+        let output = next(call, meta.with_request_trace(Arc::clone(&trace)));
+        Either::Left(Box::pin(async move {
+            let output = output.await;
+            let latency = start.elapsed();
+            if latency >= slow_query_log.threshold {
+                slow_query_log.record(&method, &params, &trace, latency, output.as_ref());
+            }
+            output
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        jsonrpc_core::{Failure, Id, Success, Version},
+        tempfile::TempDir,
+    };
+
+    fn read_entries(path: &Path) -> Vec<Value> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_sanitize_params() {
+        let transaction = "A".repeat(MAX_LOGGED_STRING_LEN + 1);
+        let params = Params::Array(vec![
+            json!(transaction),
+            json!({"encoding": "base64", "filters": (0..20).collect::<Vec<_>>()}),
+        ]);
+        assert_eq!(
+            sanitize_params(&params),
+            json!([
+                format!("<{} bytes>", MAX_LOGGED_STRING_LEN + 1),
+                {
+                    "encoding": "base64",
+                    "filters": [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, "<4 more>"],
+                },
+            ])
+        );
+
+        let mut nested = json!(1);
+        for _ in 0..=MAX_LOGGED_DEPTH {
+            nested = json!([nested]);
+        }
+        let sanitized = sanitize_params(&Params::Array(vec![nested]));
+        assert!(sanitized.to_string().contains("<nested>"));
+        assert_eq!(sanitize_params(&Params::None), Value::Null);
+    }
+
+    #[test]
+    fn test_record_and_stats() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("slow.log");
+        let slow_query_log =
+            RpcSlowQueryLog::new(RpcSlowQueryLogConfig::new(path.clone())).unwrap();
+
+        let trace = RpcRequestTrace::default();
+        trace.record_bank_lookup(42, 10);
+        trace.record_accounts_scan(900);
+        trace.record_encoding(50);
+        let success = Output::Success(Success {
+            jsonrpc: Some(Version::V2),
+            result: json!([1, 2, 3]),
+            id: Id::Num(1),
+        });
+        let params = Params::Array(vec![json!("11111111111111111111111111111111")]);
+        slow_query_log.record(
+            "getProgramAccounts",
+            &params,
+            &trace,
+            Duration::from_millis(2),
+            Some(&success),
+        );
+        let failure = Output::Failure(Failure {
+            jsonrpc: Some(Version::V2),
+            error: jsonrpc_core::Error::invalid_params("bad"),
+            id: Id::Num(2),
+        });
+        slow_query_log.record(
+            "getSlot",
+            &Params::None,
+            &RpcRequestTrace::default(),
+            Duration::from_millis(1),
+            Some(&failure),
+        );
+        slow_query_log.record(
+            "getProgramAccounts",
+            &params,
+            &RpcRequestTrace::default(),
+            Duration::from_millis(3),
+            None,
+        );
+
+        let entries = read_entries(&path);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0]["method"], "getProgramAccounts");
+        assert_eq!(
+            entries[0]["params"],
+            json!(["11111111111111111111111111111111"])
+        );
+        assert_eq!(entries[0]["bankSlot"], 42);
+        assert_eq!(entries[0]["latencyUs"], 2_000);
+        assert_eq!(entries[0]["accountsScanUs"], 900);
+        assert_eq!(
+            entries[0]["responseBytes"],
+            serde_json::to_vec(&success).unwrap().len()
+        );
+        assert_eq!(entries[0]["errorCode"], Value::Null);
+        assert_eq!(entries[1]["bankSlot"], Value::Null);
+        assert_eq!(entries[1]["errorCode"], -32602);
+
+        let stats = slow_query_log.stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].method, "getProgramAccounts");
+        assert_eq!(stats[0].count, 2);
+        assert_eq!(stats[0].total_latency_us, 5_000);
+        assert_eq!(stats[0].max_latency_us, 3_000);
+        assert_eq!(stats[0].total_accounts_scan_us, 900);
+        assert_eq!(stats[1].method, "getSlot");
+        assert_eq!(stats[1].count, 1);
+
+        slow_query_log.reset_stats();
+        assert!(slow_query_log.stats().is_empty());
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("slow.log");
+        let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+        for line in ["first", "second", "third", "fourth"] {
+            file.write_line(line.as_bytes()).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(fs::read_to_string(file.rotated_path(1)).unwrap(), "third\n");
+        assert_eq!(
+            fs::read_to_string(file.rotated_path(2)).unwrap(),
+            "second\n"
+        );
+        assert!(!file.rotated_path(3).exists());
+
+        // Reopening appends to the active file
+        let mut file = RotatingFile::open(&path, 100, 2).unwrap();
+        file.write_line(b"fifth").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\nfifth\n");
+    }
+}
@@ -2,14 +2,20 @@
 //! transaction metadata objects to persist into the Blockstore and optionally
 //! broadcast over geyser. The service also records block metadata for any
 //! frozen banks it receives.
+//!
+//! When enabled, the service also persists the post-execution state of the
+//! accounts written by each transaction, and prunes those accounts diffs once
+//! they fall outside of their own retention window.
 
 use {
     crate::transaction_notifier_interface::TransactionNotifierArc,
     crossbeam_channel::{Receiver, RecvTimeoutError},
     itertools::izip,
+    solana_account::ReadableAccount,
     solana_clock::Slot,
     solana_ledger::{
         blockstore::{Blockstore, BlockstoreError},
+        blockstore_meta::{AccountPostState, TransactionAccountsDiff},
         blockstore_processor::{TransactionStatusBatch, TransactionStatusMessage},
     },
     solana_runtime::{
@@ -48,6 +54,39 @@ const TSS_TEST_QUIESCE_NUM_RETRIES: usize = 100;
 #[cfg(feature = "dev-context-only-utils")]
 const TSS_TEST_QUIESCE_SLEEP_TIME_MS: u64 = 50;
 
+// Minimum number of slots between two purges of the accounts diff column, so
+// that range deletes are not issued for every frozen bank
+const ACCOUNTS_DIFF_PURGE_INTERVAL_SLOTS: Slot = 100;
+
+/// Keeps the transaction accounts diff column bounded to (roughly) the most
+/// recent `retention_slots` slots
+struct AccountsDiffRetention {
+    retention_slots: Slot,
+    purged_through_slot: Slot,
+}
+
+impl AccountsDiffRetention {
+    fn new(retention_slots: Slot) -> Self {
+        Self {
+            retention_slots,
+            purged_through_slot: 0,
+        }
+    }
+
+    fn maybe_purge(&mut self, frozen_slot: Slot, blockstore: &Blockstore) -> Result<()> {
+        let purge_through_slot = frozen_slot.saturating_sub(self.retention_slots);
+        if purge_through_slot
+            >= self
+                .purged_through_slot
+                .saturating_add(ACCOUNTS_DIFF_PURGE_INTERVAL_SLOTS)
+        {
+            blockstore.purge_transaction_accounts_diffs(purge_through_slot)?;
+            self.purged_through_slot = purge_through_slot;
+        }
+        Ok(())
+    }
+}
+
 pub struct TransactionStatusService {
     thread_hdl: JoinHandle<()>,
     #[cfg(feature = "dev-context-only-utils")]
@@ -64,6 +103,7 @@ impl TransactionStatusService {
         transaction_notifier: Option<TransactionNotifierArc>,
         blockstore: Arc<Blockstore>,
         enable_extended_tx_metadata_storage: bool,
+        accounts_diff_retention_slots: Option<u64>,
         depenency_tracker: Option<Arc<DependencyTracker>>,
         exit: Arc<AtomicBool>,
     ) -> Self {
@@ -73,6 +113,8 @@ impl TransactionStatusService {
                 let transaction_status_receiver = transaction_status_receiver.clone();
                 move || {
                     info!("{} has started", Self::SERVICE_NAME);
+                    let mut accounts_diff_retention =
+                        accounts_diff_retention_slots.map(AccountsDiffRetention::new);
                     loop {
                         if exit.load(Ordering::Relaxed) {
                             break;
@@ -98,6 +140,7 @@ impl TransactionStatusService {
                             transaction_notifier.clone(),
                             &blockstore,
                             enable_extended_tx_metadata_storage,
+                            &mut accounts_diff_retention,
                             depenency_tracker.clone(),
                         ) {
                             Ok(_) => {}
@@ -126,6 +169,7 @@ impl TransactionStatusService {
         transaction_notifier: Option<TransactionNotifierArc>,
         blockstore: &Blockstore,
         enable_extended_tx_metadata_storage: bool,
+        accounts_diff_retention: &mut Option<AccountsDiffRetention>,
         dependency_tracker: Option<Arc<DependencyTracker>>,
     ) -> Result<()> {
         match transaction_status_message {
@@ -138,6 +182,7 @@ impl TransactionStatusService {
                     token_balances,
                     costs,
                     transaction_indexes,
+                    post_accounts,
                 },
                 work_id,
             )) => {
@@ -146,6 +191,10 @@ impl TransactionStatusService {
                 } else {
                     None
                 };
+                // Only persist accounts diffs if a retention window was configured
+                let mut post_accounts = post_accounts
+                    .filter(|_| accounts_diff_retention.is_some())
+                    .map(Vec::into_iter);
 
                 for (
                     transaction,
@@ -166,6 +215,8 @@ impl TransactionStatusService {
                     costs,
                     transaction_indexes,
                 ) {
+                    // Advance before skipping so diffs stay aligned with transactions
+                    let transaction_post_accounts = post_accounts.as_mut().and_then(Iterator::next);
                     let Ok(committed_tx) = commit_result else {
                         continue;
                     };
@@ -237,6 +288,28 @@ impl TransactionStatusService {
                             )?;
                         }
 
+                        if let Some(transaction_post_accounts) = transaction_post_accounts {
+                            let accounts_diff = TransactionAccountsDiff {
+                                accounts: transaction_post_accounts
+                                    .into_iter()
+                                    .map(|(pubkey, account)| AccountPostState {
+                                        pubkey,
+                                        lamports: account.lamports(),
+                                        owner: *account.owner(),
+                                        executable: account.executable(),
+                                        rent_epoch: account.rent_epoch(),
+                                        data: account.data().to_vec(),
+                                    })
+                                    .collect(),
+                            };
+                            blockstore.add_transaction_accounts_diff_to_batch(
+                                slot,
+                                transaction.signature(),
+                                &accounts_diff,
+                                batch,
+                            )?;
+                        }
+
                         let message = transaction.message();
                         let keys_with_writable = message
                             .account_keys()
@@ -270,6 +343,9 @@ impl TransactionStatusService {
                     return Err(Error::NonFrozenBank(bank.slot()));
                 }
                 Self::write_block_meta(&bank, blockstore)?;
+                if let Some(accounts_diff_retention) = accounts_diff_retention.as_mut() {
+                    accounts_diff_retention.maybe_purge(bank.slot(), blockstore)?;
+                }
                 max_complete_transaction_status_slot.fetch_max(bank.slot(), Ordering::SeqCst);
             }
         }
@@ -526,6 +602,7 @@ pub(crate) mod tests {
             token_balances,
             costs: vec![Some(123)],
             transaction_indexes: vec![transaction_index],
+            post_accounts: None,
         };
 
         let test_notifier = Arc::new(TestTransactionNotifier::new());
@@ -538,6 +615,7 @@ pub(crate) mod tests {
             Some(test_notifier.clone()),
             blockstore,
             false,
+            None,
             None, // No work dependency tracker
             exit.clone(),
         );
@@ -634,6 +712,7 @@ pub(crate) mod tests {
             token_balances,
             costs: vec![Some(123), Some(456)],
             transaction_indexes: vec![transaction_index1, transaction_index2],
+            post_accounts: None,
         };
 
         let test_notifier = Arc::new(TestTransactionNotifier::new());
@@ -647,6 +726,7 @@ pub(crate) mod tests {
             Some(test_notifier.clone()),
             blockstore,
             false,
+            None,
             Some(dependency_tracker.clone()),
             exit.clone(),
         );
@@ -694,4 +774,43 @@ pub(crate) mod tests {
             &result2.transaction.message.hash(),
         );
     }
+
+    #[test]
+    fn test_accounts_diff_retention() {
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Blockstore::open(ledger_path.path()).unwrap();
+
+        let signatures: Vec<_> = (0..200).map(|_| Signature::new_unique()).collect();
+        let mut write_batch = blockstore.get_write_batch().unwrap();
+        for (slot, signature) in signatures.iter().enumerate() {
+            blockstore
+                .add_transaction_accounts_diff_to_batch(
+                    slot as Slot,
+                    signature,
+                    &TransactionAccountsDiff::default(),
+                    &mut write_batch,
+                )
+                .unwrap();
+        }
+        blockstore.write_batch(write_batch).unwrap();
+        let is_retained = |slot: Slot| {
+            blockstore
+                .read_transaction_accounts_diff(slot, signatures[slot as usize])
+                .unwrap()
+                .is_some()
+        };
+
+        let mut retention = AccountsDiffRetention::new(50);
+        // Nothing is purged until a full purge interval falls out of retention
+        retention.maybe_purge(120, &blockstore).unwrap();
+        assert!(is_retained(0));
+
+        retention.maybe_purge(160, &blockstore).unwrap();
+        assert!(!is_retained(110));
+        assert!(is_retained(111));
+
+        // The next purge waits for another interval
+        retention.maybe_purge(170, &blockstore).unwrap();
+        assert!(is_retained(115));
+    }
 }
@@ -81,6 +81,9 @@ pub struct BankForks {
     sharable_banks: SharableBanks,
     highest_slot_at_startup: Slot,
     scheduler_pool: Option<InstalledSchedulerPoolArc>,
+    /// Whether newly inserted banks for block verification get a scheduler from `scheduler_pool`
+    /// or are replayed by `blockstore_processor` itself.
+    block_verification_scheduler_enabled: bool,
 
     /// The status tracker for the Alpenglow migration. Initialized via either
     /// the genesis or snapshot bank and then updated via block replay.
@@ -138,6 +141,7 @@ impl BankForks {
             descendants,
             highest_slot_at_startup: 0,
             scheduler_pool: None,
+            block_verification_scheduler_enabled: true,
             migration_status,
         }));
 
@@ -282,6 +286,18 @@ impl BankForks {
         );
     }
 
+    /// Switch block verification of banks inserted from now on between the installed scheduler
+    /// pool and `blockstore_processor`'s own replay. Banks already inserted are unaffected, so
+    /// the switch takes effect at the next slot boundary.
+    pub fn set_block_verification_scheduler_enabled(&mut self, enabled: bool) {
+        info!("Block verification by scheduler_pool enabled: {enabled}");
+        self.block_verification_scheduler_enabled = enabled;
+    }
+
+    pub fn block_verification_scheduler_enabled(&self) -> bool {
+        self.block_verification_scheduler_enabled
+    }
+
     pub fn insert(&mut self, bank: Bank) -> BankWithScheduler {
         self.insert_with_scheduling_mode(SchedulingMode::BlockVerification, bank)
     }
@@ -296,7 +312,11 @@ impl BankForks {
         }
 
         let bank = Arc::new(bank);
-        let bank = if let Some(scheduler_pool) = &self.scheduler_pool {
+        let scheduler_pool = self.scheduler_pool.as_ref().filter(|_| {
+            self.block_verification_scheduler_enabled
+                || !matches!(mode, SchedulingMode::BlockVerification)
+        });
+        let bank = if let Some(scheduler_pool) = scheduler_pool {
             Self::install_scheduler_into_bank(scheduler_pool, mode, bank)
         } else {
             BankWithScheduler::new_without_scheduler(bank)
@@ -1,10 +1,15 @@
 use {
+    crate::prioritization_fee_histogram::PrioritizationFeeHistogram,
     solana_clock::Slot,
     solana_measure::measure_us,
     solana_pubkey::Pubkey,
-    std::{collections::HashMap, num::Saturating},
+    std::{cmp::Reverse, collections::HashMap, num::Saturating},
 };
 
+/// The number of writable accounts, with the most transactions, whose compute
+/// unit price distribution is kept once a block is completed.
+const MAX_WRITABLE_ACCOUNT_HISTOGRAMS: usize = 256;
+
 #[derive(Debug, Default)]
 struct PrioritizationFeeMetrics {
     // Count of writable accounts in slot
@@ -153,6 +158,13 @@ pub struct PrioritizationFee {
     // The minimum prioritization fee of each writable account in transactions in this block.
     min_writable_account_fees: HashMap<Pubkey, u64>,
 
+    // The distribution of compute unit prices of transactions in this block.
+    compute_unit_price_histogram: PrioritizationFeeHistogram,
+
+    // The distribution of compute unit prices of transactions write-locking each account in
+    // this block.
+    writable_account_histograms: HashMap<Pubkey, PrioritizationFeeHistogram>,
+
     // Default to `false`, set to `true` when a block is completed, therefore the minimum fees recorded
     // are finalized, and can be made available for use (e.g., RPC query)
     is_finalized: bool,
@@ -166,6 +178,8 @@ impl Default for PrioritizationFee {
         PrioritizationFee {
             min_compute_unit_price: u64::MAX,
             min_writable_account_fees: HashMap::new(),
+            compute_unit_price_histogram: PrioritizationFeeHistogram::default(),
+            writable_account_histograms: HashMap::new(),
             is_finalized: false,
             metrics: PrioritizationFeeMetrics::default(),
         }
@@ -186,7 +200,12 @@ impl PrioritizationFee {
                     self.min_compute_unit_price = compute_unit_price;
                 }
 
+                self.compute_unit_price_histogram.add(compute_unit_price);
                 for write_account in writable_accounts {
+                    self.writable_account_histograms
+                        .entry(write_account)
+                        .or_default()
+                        .add(compute_unit_price);
                     self.min_writable_account_fees
                         .entry(write_account)
                         .and_modify(|write_lock_fee| {
@@ -216,11 +235,27 @@ impl PrioritizationFee {
         self.metrics.relevant_writable_accounts_count = self.get_writable_accounts_count() as u64;
     }
 
+    /// Only the distributions of the accounts with the most transactions are kept once the block
+    /// is completed, to bound the memory footprint of blocks with many writable accounts.
+    fn prune_writable_account_histograms(&mut self) {
+        if self.writable_account_histograms.len() <= MAX_WRITABLE_ACCOUNT_HISTOGRAMS {
+            return;
+        }
+        let mut histograms = self.writable_account_histograms.drain().collect::<Vec<_>>();
+        histograms
+            .select_nth_unstable_by_key(MAX_WRITABLE_ACCOUNT_HISTOGRAMS - 1, |(_, histogram)| {
+                Reverse(histogram.count())
+            });
+        histograms.truncate(MAX_WRITABLE_ACCOUNT_HISTOGRAMS);
+        self.writable_account_histograms = histograms.into_iter().collect();
+    }
+
     pub fn mark_block_completed(&mut self) -> Result<(), PrioritizationFeeError> {
         if self.is_finalized {
             return Err(PrioritizationFeeError::BlockIsAlreadyFinalized);
         }
         self.prune_irrelevant_writable_accounts();
+        self.prune_writable_account_histograms();
         self.is_finalized = true;
         Ok(())
     }
@@ -237,6 +272,17 @@ impl PrioritizationFee {
         self.min_writable_account_fees.iter()
     }
 
+    pub fn get_compute_unit_price_histogram(&self) -> &PrioritizationFeeHistogram {
+        &self.compute_unit_price_histogram
+    }
+
+    pub fn get_writable_account_histogram(
+        &self,
+        key: &Pubkey,
+    ) -> Option<&PrioritizationFeeHistogram> {
+        self.writable_account_histograms.get(key)
+    }
+
     pub fn get_writable_accounts_count(&self) -> usize {
         self.min_writable_account_fees.len()
     }
@@ -395,4 +441,50 @@ mod tests {
         assert!(prioritization_fee.mark_block_completed().is_ok());
         assert!(prioritization_fee.mark_block_completed().is_err());
     }
+
+    #[test]
+    fn test_fee_histograms() {
+        let write_account_a = Pubkey::new_unique();
+        let write_account_b = Pubkey::new_unique();
+
+        let mut prioritization_fee = PrioritizationFee::default();
+        prioritization_fee.update(1, 10, vec![write_account_a]);
+        prioritization_fee.update(2, 10, vec![write_account_a, write_account_b]);
+        prioritization_fee.update(3, 10, vec![]);
+
+        assert_eq!(
+            prioritization_fee
+                .get_compute_unit_price_histogram()
+                .count(),
+            3
+        );
+        let histogram_a = prioritization_fee
+            .get_writable_account_histogram(&write_account_a)
+            .unwrap();
+        assert_eq!(histogram_a.count(), 2);
+        assert_eq!(histogram_a.percentile(100), Some(2));
+        let histogram_b = prioritization_fee
+            .get_writable_account_histogram(&write_account_b)
+            .unwrap();
+        assert_eq!(histogram_b.count(), 1);
+
+        // Only the busiest accounts' histograms survive completion.
+        let busy_accounts = (0..MAX_WRITABLE_ACCOUNT_HISTOGRAMS)
+            .map(|_| Pubkey::new_unique())
+            .collect::<Vec<_>>();
+        for _ in 0..3 {
+            prioritization_fee.update(4, 10, busy_accounts.clone());
+        }
+        prioritization_fee.mark_block_completed().unwrap();
+        assert!(busy_accounts.iter().all(|account| {
+            prioritization_fee
+                .get_writable_account_histogram(account)
+                .is_some()
+        }));
+        assert!(
+            prioritization_fee
+                .get_writable_account_histogram(&write_account_b)
+                .is_none()
+        );
+    }
 }
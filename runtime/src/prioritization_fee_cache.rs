@@ -1,5 +1,8 @@
 use {
-    crate::{bank::Bank, prioritization_fee::PrioritizationFee},
+    crate::{
+        bank::Bank, prioritization_fee::PrioritizationFee,
+        prioritization_fee_histogram::PrioritizationFeeHistogram,
+    },
     crossbeam_channel::{Receiver, Sender, TryRecvError, unbounded},
     log::*,
     solana_accounts_db::account_locks::validate_account_locks,
@@ -28,6 +31,15 @@ const MAX_UNFINALIZED_SLOTS: u64 = 128;
 
 type UnfinalizedPrioritizationFees = BTreeMap<Slot, HashMap<BankId, PrioritizationFee>>;
 
+/// Percentiles of the compute unit prices paid in recent blocks, in micro-lamports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrioritizationFeeEstimate {
+    pub p25: u64,
+    pub p50: u64,
+    pub p75: u64,
+    pub p95: u64,
+}
+
 #[derive(Debug, Default)]
 struct PrioritizationFeeCacheMetrics {
     // Count of transactions that successfully updated each slot's prioritization fee cache.
@@ -449,6 +461,46 @@ impl PrioritizationFeeCache {
             })
             .collect()
     }
+
+    /// Estimates the compute unit price percentiles of transactions over the most recent
+    /// `num_slots` finalized blocks. Each percentile is the highest of that percentile over all
+    /// transactions, and over the transactions write-locking each of `account_keys`. Only the 256
+    /// writable accounts with the most transactions in a block have a distribution kept for it, so
+    /// an account outside of them only contributes the distribution of all transactions of that
+    /// block. Returns `None` if no transactions were recorded in the window.
+    pub fn get_prioritization_fee_estimate(
+        &self,
+        account_keys: &[Pubkey],
+        num_slots: usize,
+    ) -> Option<PrioritizationFeeEstimate> {
+        let mut histograms = vec![PrioritizationFeeHistogram::default(); account_keys.len() + 1];
+        {
+            let cache = self.cache.read().unwrap();
+            for slot_prioritization_fee in cache.values().rev().take(num_slots) {
+                histograms[0].merge(slot_prioritization_fee.get_compute_unit_price_histogram());
+                for (histogram, account_key) in histograms[1..].iter_mut().zip(account_keys) {
+                    if let Some(account_histogram) =
+                        slot_prioritization_fee.get_writable_account_histogram(account_key)
+                    {
+                        histogram.merge(account_histogram);
+                    }
+                }
+            }
+        }
+
+        let estimate = |percentile| {
+            histograms
+                .iter()
+                .filter_map(|histogram| histogram.percentile(percentile))
+                .max()
+        };
+        Some(PrioritizationFeeEstimate {
+            p25: estimate(25)?,
+            p50: estimate(50)?,
+            p75: estimate(75)?,
+            p95: estimate(95)?,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -905,6 +957,92 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_prioritization_fee_estimate() {
+        agave_logger::setup();
+        let write_account_a = Pubkey::new_unique();
+
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank0 = Bank::new_for_benches(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank0);
+        let bank = bank_forks.read().unwrap().working_bank();
+        let leader = SlotLeader::new_unique();
+        let bank1 = Arc::new(Bank::new_from_parent(bank.clone(), leader, 1));
+        let bank2 = Arc::new(Bank::new_from_parent(bank, leader, 2));
+
+        let prioritization_fee_cache = PrioritizationFeeCache::default();
+        assert_eq!(
+            prioritization_fee_cache.get_prioritization_fee_estimate(&[], 150),
+            None
+        );
+
+        // slot 1: [1, 1, 1, 1, 3 (a), 3 (a)]
+        let txs = [1, 1, 1, 1]
+            .into_iter()
+            .map(|compute_unit_price| {
+                build_sanitized_transaction_for_test(
+                    compute_unit_price,
+                    &Pubkey::new_unique(),
+                    &Pubkey::new_unique(),
+                )
+            })
+            .chain([3, 3].into_iter().map(|compute_unit_price| {
+                build_sanitized_transaction_for_test(
+                    compute_unit_price,
+                    &Pubkey::new_unique(),
+                    &write_account_a,
+                )
+            }))
+            .collect::<Vec<_>>();
+        sync_update(&prioritization_fee_cache, bank1.clone(), txs.iter());
+        sync_finalize_priority_fee_for_test(&prioritization_fee_cache, 1, bank1.bank_id());
+        assert_eq!(
+            prioritization_fee_cache.get_prioritization_fee_estimate(&[], 150),
+            Some(PrioritizationFeeEstimate {
+                p25: 1,
+                p50: 1,
+                p75: 3,
+                p95: 3,
+            })
+        );
+        // the account's percentiles are higher than the block's
+        assert_eq!(
+            prioritization_fee_cache.get_prioritization_fee_estimate(&[write_account_a], 150),
+            Some(PrioritizationFeeEstimate {
+                p25: 3,
+                p50: 3,
+                p75: 3,
+                p95: 3,
+            })
+        );
+
+        // slot 2: [0, 0, 0, 0, 0, 0]
+        let txs = (0..6)
+            .map(|_| {
+                build_sanitized_transaction_for_test(
+                    0,
+                    &Pubkey::new_unique(),
+                    &Pubkey::new_unique(),
+                )
+            })
+            .collect::<Vec<_>>();
+        sync_update(&prioritization_fee_cache, bank2.clone(), txs.iter());
+        sync_finalize_priority_fee_for_test(&prioritization_fee_cache, 2, bank2.bank_id());
+        assert_eq!(
+            prioritization_fee_cache.get_prioritization_fee_estimate(&[write_account_a], 1),
+            Some(PrioritizationFeeEstimate::default())
+        );
+        assert_eq!(
+            prioritization_fee_cache.get_prioritization_fee_estimate(&[], 2),
+            Some(PrioritizationFeeEstimate {
+                p25: 0,
+                p50: 0,
+                p75: 1,
+                p95: 3,
+            })
+        );
+    }
+
     #[test]
     fn test_purge_duplicated_bank() {
         // duplicated bank can exists for same slot before OC.
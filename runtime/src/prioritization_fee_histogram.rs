@@ -0,0 +1,146 @@
+/// Number of sub-buckets each power of two is split into, as a power of two.
+/// With 4 sub-buckets, a bucket's bounds are within 25% of each other.
+const SUB_BUCKET_BITS: u32 = 2;
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+
+/// Compact histogram of compute unit prices, with log-linear buckets: every
+/// power of two is split into `SUB_BUCKETS` equal buckets, and prices below
+/// `SUB_BUCKETS` have a bucket of their own. Only non-empty buckets are
+/// stored, so a histogram costs a few bytes per distinct price range.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrioritizationFeeHistogram {
+    /// (bucket index, count), sorted by bucket index.
+    buckets: Vec<(u8, u32)>,
+    count: u64,
+}
+
+impl PrioritizationFeeHistogram {
+    /// Record a transaction paying `compute_unit_price`.
+    pub fn add(&mut self, compute_unit_price: u64) {
+        self.add_to_bucket(bucket_index(compute_unit_price), 1);
+    }
+
+    /// Add all of `other`'s transactions to this histogram.
+    pub fn merge(&mut self, other: &Self) {
+        for &(index, count) in &other.buckets {
+            self.add_to_bucket(index, count);
+        }
+    }
+
+    /// Number of transactions recorded.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The compute unit price at the given `percentile` (0..=100), rounded
+    /// up to the upper bound of its bucket. `None` if the histogram is empty.
+    pub fn percentile(&self, percentile: u8) -> Option<u64> {
+        debug_assert!(percentile <= 100);
+        if self.count == 0 {
+            return None;
+        }
+        let rank = (self.count * u64::from(percentile.min(100)))
+            .div_ceil(100)
+            .max(1);
+        let mut seen = 0;
+        self.buckets.iter().find_map(|&(index, count)| {
+            seen += u64::from(count);
+            (seen >= rank).then(|| bucket_upper_bound(index))
+        })
+    }
+
+    fn add_to_bucket(&mut self, index: u8, count: u32) {
+        match self
+            .buckets
+            .binary_search_by_key(&index, |(index, _)| *index)
+        {
+            Ok(position) => {
+                let bucket_count = &mut self.buckets[position].1;
+                *bucket_count = bucket_count.saturating_add(count);
+            }
+            Err(position) => self.buckets.insert(position, (index, count)),
+        }
+        self.count = self.count.saturating_add(u64::from(count));
+    }
+}
+
+fn bucket_index(compute_unit_price: u64) -> u8 {
+    if compute_unit_price < SUB_BUCKETS {
+        return compute_unit_price as u8;
+    }
+    let msb = u64::BITS - 1 - compute_unit_price.leading_zeros();
+    let shift = msb - SUB_BUCKET_BITS;
+    let sub_bucket = (compute_unit_price >> shift) & (SUB_BUCKETS - 1);
+    ((u64::from(shift) + 1) * SUB_BUCKETS + sub_bucket) as u8
+}
+
+fn bucket_lower_bound(index: u8) -> u64 {
+    let index = u64::from(index);
+    if index < SUB_BUCKETS {
+        return index;
+    }
+    let shift = index / SUB_BUCKETS - 1;
+    (SUB_BUCKETS + index % SUB_BUCKETS) << shift
+}
+
+fn bucket_upper_bound(index: u8) -> u64 {
+    if index >= bucket_index(u64::MAX) {
+        return u64::MAX;
+    }
+    bucket_lower_bound(index + 1) - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bounds() {
+        for price in [0, 1, 2, 3] {
+            let index = bucket_index(price);
+            assert_eq!(bucket_lower_bound(index), price);
+            assert_eq!(bucket_upper_bound(index), price);
+        }
+        assert_eq!(bucket_index(4), 4);
+        assert_eq!(bucket_index(7), 7);
+        assert_eq!(bucket_index(8), 8);
+        assert_eq!(bucket_index(15), 11);
+        assert_eq!(bucket_lower_bound(bucket_index(15)), 14);
+        assert_eq!(bucket_upper_bound(bucket_index(15)), 15);
+
+        for price in [5, 100, 12_345, 1 << 40, u64::MAX / 3, u64::MAX] {
+            let index = bucket_index(price);
+            let lower_bound = bucket_lower_bound(index);
+            let upper_bound = bucket_upper_bound(index);
+            assert!(lower_bound <= price && price <= upper_bound);
+            assert!(upper_bound - lower_bound <= lower_bound / 4);
+        }
+        assert_eq!(bucket_upper_bound(bucket_index(u64::MAX)), u64::MAX);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let mut histogram = PrioritizationFeeHistogram::default();
+        assert_eq!(histogram.percentile(50), None);
+
+        for price in [0, 0, 1, 2, 3, 3, 3, 3] {
+            histogram.add(price);
+        }
+        assert_eq!(histogram.count(), 8);
+        assert_eq!(histogram.percentile(0), Some(0));
+        assert_eq!(histogram.percentile(25), Some(0));
+        assert_eq!(histogram.percentile(50), Some(2));
+        assert_eq!(histogram.percentile(75), Some(3));
+        assert_eq!(histogram.percentile(100), Some(3));
+
+        let mut other = PrioritizationFeeHistogram::default();
+        for _ in 0..8 {
+            other.add(1_000);
+        }
+        histogram.merge(&other);
+        assert_eq!(histogram.count(), 16);
+        assert_eq!(histogram.percentile(50), Some(3));
+        let p95 = histogram.percentile(95).unwrap();
+        assert!((1_000..=1_023).contains(&p95));
+    }
+}
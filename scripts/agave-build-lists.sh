@@ -22,6 +22,7 @@ AGAVE_BINS_END_USER=(
 )
 
 AGAVE_BINS_VAL_OP=(
+  agave-geyser-plugin-host
   agave-validator
   agave-watchtower
   solana-gossip
@@ -27,6 +27,7 @@ update_solana_dependencies() {
     solana-faucet
     solana-fee
     agave-geyser-plugin-interface
+    agave-geyser-plugin-ipc
     solana-geyser-plugin-manager
     solana-gossip
     solana-lattice-hash
@@ -111,6 +112,7 @@ patch_crates_io_solana_no_header() {
   crates_map+=("solana-faucet faucet")
   crates_map+=("solana-fee fee")
   crates_map+=("agave-geyser-plugin-interface geyser-plugin-interface")
+  crates_map+=("agave-geyser-plugin-ipc geyser-plugin-ipc")
   crates_map+=("solana-geyser-plugin-manager geyser-plugin-manager")
   crates_map+=("solana-gossip gossip")
   crates_map+=("solana-lattice-hash lattice-hash")
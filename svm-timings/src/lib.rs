@@ -9,9 +9,23 @@ use {
         collections::HashMap,
         num::Saturating,
         ops::{Index, IndexMut},
+        sync::atomic::{AtomicBool, Ordering},
     },
 };
 
+static PER_PROGRAM_TIMINGS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Always accumulate `ExecuteDetailsTimings::per_program_timings`, instead of
+/// only when trace level logging is enabled. Meant for offline tools that
+/// analyze program execution times.
+pub fn enable_per_program_timings() {
+    PER_PROGRAM_TIMINGS_ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn per_program_timings_enabled() -> bool {
+    PER_PROGRAM_TIMINGS_ENABLED.load(Ordering::Relaxed)
+}
+
 #[derive(Default, Debug, PartialEq, Eq)]
 pub struct ProgramTiming {
     pub accumulated_us: Saturating<u64>,
@@ -1,7 +1,7 @@
 use {
     solana_program_runtime::invoke_context::InvokeContext,
     solana_svm_measure::measure_us,
-    solana_svm_timings::{ExecuteDetailsTimings, ExecuteTimings},
+    solana_svm_timings::{ExecuteDetailsTimings, ExecuteTimings, per_program_timings_enabled},
     solana_svm_transaction::svm_message::SVMMessage,
     solana_transaction_error::TransactionError,
 };
@@ -40,8 +40,9 @@ pub(crate) fn process_message<'ix_data>(
         *accumulated_consumed_units =
             accumulated_consumed_units.saturating_add(compute_units_consumed);
         // The per_program_timings are only used for metrics reporting at the trace
-        // level, so they should only be accumulated when trace level is enabled.
-        if log::log_enabled!(log::Level::Trace) {
+        // level, so they should only be accumulated when trace level is enabled,
+        // unless a tool explicitly asked for them.
+        if log::log_enabled!(log::Level::Trace) || per_program_timings_enabled() {
             execute_timings.details.accumulate_program(
                 program_id,
                 process_instruction_us,
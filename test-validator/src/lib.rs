@@ -57,7 +57,9 @@ use {
     },
     solana_pubkey::Pubkey,
     solana_rent::Rent,
-    solana_rpc::{rpc::JsonRpcConfig, rpc_pubsub_service::PubSubConfig},
+    solana_rpc::{
+        rpc::JsonRpcConfig, rpc_pubsub_service::PubSubConfig, rpc_rate_limiter::RpcRateLimiter,
+    },
     solana_rpc_client::{nonblocking, rpc_client::RpcClient},
     solana_rpc_client_api::{
         client_error::Error as RpcClientError, request::MAX_MULTIPLE_ACCOUNTS,
@@ -158,6 +160,7 @@ pub struct TestValidatorGenesis {
     pub start_progress: Arc<RwLock<ValidatorStartProgress>>,
     pub authorized_voter_keypairs: Arc<RwLock<Vec<Arc<Keypair>>>>,
     pub staked_nodes_overrides: Arc<RwLock<HashMap<Pubkey, u64>>>,
+    pub rpc_rate_limiter: Arc<RpcRateLimiter>,
     pub max_ledger_shreds: Option<u64>,
     pub max_genesis_archive_unpacked_size: Option<u64>,
     pub geyser_plugin_config_files: Option<Vec<PathBuf>>,
@@ -193,6 +196,7 @@ impl Default for TestValidatorGenesis {
             start_progress: Arc::<RwLock<ValidatorStartProgress>>::default(),
             authorized_voter_keypairs: Arc::<RwLock<Vec<Arc<Keypair>>>>::default(),
             staked_nodes_overrides: Arc::new(RwLock::new(HashMap::new())),
+            rpc_rate_limiter: Arc::<RpcRateLimiter>::default(),
             max_ledger_shreds: Option::<u64>::default(),
             max_genesis_archive_unpacked_size: Option::<u64>::default(),
             geyser_plugin_config_files: Option::<Vec<PathBuf>>::default(),
@@ -848,6 +852,7 @@ pub struct TestValidator {
     ledger_path: PathBuf,
     preserve_ledger: bool,
     rpc_pubsub_url: String,
+    rpc_grpc_url: Option<String>,
     rpc_url: String,
     tpu_quic: SocketAddr,
     gossip: SocketAddr,
@@ -1135,6 +1140,10 @@ impl TestValidator {
         let vote_account_address = validator_vote_account.pubkey();
         let rpc_url = format!("http://{}", node.info.rpc().unwrap());
         let rpc_pubsub_url = format!("ws://{}/", node.info.rpc_pubsub().unwrap());
+        let rpc_grpc_url = config
+            .pubsub_config
+            .grpc_addr
+            .map(|grpc_addr| format!("http://{grpc_addr}"));
         let tpu_quic = node.info.tpu(Protocol::QUIC).unwrap();
         let gossip = node.info.gossip().unwrap();
 
@@ -1209,6 +1218,7 @@ impl TestValidator {
             max_ledger_shreds: config.max_ledger_shreds,
             no_wait_for_vote_to_start_leader: true,
             staked_nodes_overrides: config.staked_nodes_overrides.clone(),
+            rpc_rate_limiter: config.rpc_rate_limiter.clone(),
             accounts_db_config,
             runtime_config,
             enable_scheduler_bindings: config.enable_scheduler_bindings,
@@ -1238,6 +1248,7 @@ impl TestValidator {
             ledger_path,
             preserve_ledger,
             rpc_pubsub_url,
+            rpc_grpc_url,
             rpc_url,
             tpu_quic,
             gossip,
@@ -1371,6 +1382,11 @@ impl TestValidator {
         self.rpc_pubsub_url.clone()
     }
 
+    /// Return the validator's gRPC streaming URL, if the service is enabled
+    pub fn rpc_grpc_url(&self) -> Option<String> {
+        self.rpc_grpc_url.clone()
+    }
+
     /// Return the validator's vote account address
     pub fn vote_account_address(&self) -> Pubkey {
         self.vote_account_address
@@ -39,4 +39,12 @@ impl TransactionBatch {
     pub fn timestamp(&self) -> u64 {
         self.timestamp
     }
+
+    pub fn len(&self) -> usize {
+        self.wired_transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wired_transactions.is_empty()
+    }
 }
@@ -10,7 +10,7 @@ use {
         ser::{Serialize as SerializeTrait, SerializeTupleVariant},
     },
     serde_json::{Value, from_value},
-    solana_account_decoder_client_types::token::UiTokenAmount,
+    solana_account_decoder_client_types::{UiAccount, token::UiTokenAmount},
     solana_commitment_config::CommitmentConfig,
     solana_instruction::error::InstructionError,
     solana_message::{
@@ -73,6 +73,9 @@ pub enum TransactionDetails {
     Signatures,
     None,
     Accounts,
+    /// Same as `Accounts`, plus the post-execution state of every account each
+    /// transaction wrote to. Only served by nodes that record accounts diffs.
+    AccountsDiff,
 }
 
 #[derive(Error, Debug, PartialEq, Eq, Clone)]
@@ -115,6 +118,27 @@ pub struct UiConfirmedBlock {
     pub num_reward_partitions: Option<u64>,
     pub block_time: Option<i64>,
     pub block_height: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accounts_diffs: Option<Vec<UiTransactionAccountsDiff>>,
+}
+
+/// The accounts written by a single transaction, in the state they were left in
+/// once the transaction was committed
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UiTransactionAccountsDiff {
+    pub signature: String,
+    /// `None` if the node did not record (or no longer retains) the diff for
+    /// this transaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accounts: Option<Vec<UiAccountPostState>>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UiAccountPostState {
+    pub pubkey: String,
+    pub account: UiAccount,
 }
 
 /// A duplicate representation of a Transaction for pretty JSON serialization
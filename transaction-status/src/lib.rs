@@ -9,12 +9,12 @@ pub use {
         EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction,
         EncodedTransactionWithStatusMeta, InnerInstruction, InnerInstructions, Reward, Rewards,
         TransactionBinaryEncoding, TransactionConfirmationStatus, TransactionDetails,
-        TransactionStatus, TransactionStatusMeta, TransactionTokenBalance, UiAccountsList,
-        UiAddressTableLookup, UiCompiledInstruction, UiConfirmedBlock, UiInnerInstructions,
-        UiInstruction, UiLoadedAddresses, UiMessage, UiParsedInstruction, UiParsedMessage,
-        UiPartiallyDecodedInstruction, UiRawMessage, UiReturnDataEncoding, UiTransaction,
-        UiTransactionEncoding, UiTransactionReturnData, UiTransactionStatusMeta,
-        UiTransactionTokenBalance, option_serializer,
+        TransactionStatus, TransactionStatusMeta, TransactionTokenBalance, UiAccountPostState,
+        UiAccountsList, UiAddressTableLookup, UiCompiledInstruction, UiConfirmedBlock,
+        UiInnerInstructions, UiInstruction, UiLoadedAddresses, UiMessage, UiParsedInstruction,
+        UiParsedMessage, UiPartiallyDecodedInstruction, UiRawMessage, UiReturnDataEncoding,
+        UiTransaction, UiTransactionAccountsDiff, UiTransactionEncoding, UiTransactionReturnData,
+        UiTransactionStatusMeta, UiTransactionTokenBalance, option_serializer,
     },
 };
 use {
@@ -359,7 +359,9 @@ impl ConfirmedBlock {
                 ),
             ),
             TransactionDetails::None => (None, None),
-            TransactionDetails::Accounts => (
+            // Accounts diffs are not part of the block itself; callers with
+            // access to them attach `UiConfirmedBlock::accounts_diffs` afterwards
+            TransactionDetails::Accounts | TransactionDetails::AccountsDiff => (
                 Some(
                     self.transactions
                         .into_iter()
@@ -388,6 +390,7 @@ impl ConfirmedBlock {
             num_reward_partitions: self.num_partitions,
             block_time: self.block_time,
             block_height: self.block_height,
+            accounts_diffs: None,
         })
     }
 }
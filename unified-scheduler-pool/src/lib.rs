@@ -59,7 +59,12 @@ use {
 };
 
 mod sleepless_testing;
-use crate::sleepless_testing::BuilderTracked;
+mod stats;
+pub use crate::stats::{BlockReplayLatency, SchedulerPoolStats};
+use crate::{
+    sleepless_testing::BuilderTracked,
+    stats::{PoolStats, thread_cpu_time},
+};
 
 // dead_code is false positive; these tuple fields are used via Debug.
 #[allow(dead_code)]
@@ -106,6 +111,7 @@ pub struct SchedulerPool<S: SpawnableScheduler<TH>, TH: TaskHandler> {
     timeout_listeners: Mutex<Vec<(TimeoutListener, Instant)>>,
     common_handler_context: CommonHandlerContext,
     block_verification_handler_count: CountOrDefault,
+    stats: Arc<PoolStats>,
     // weak_self could be elided by changing InstalledScheduler::take_scheduler()'s receiver to
     // Arc<Self> from &Self, because SchedulerPool is used as in the form of Arc<SchedulerPool>
     // almost always. But, this would cause wasted and noisy Arc::clone()'s at every call sites.
@@ -353,6 +359,9 @@ where
                 prioritization_fee_cache,
             },
             block_verification_handler_count,
+            stats: Arc::new(PoolStats::new(
+                block_verification_handler_count.unwrap_or(Self::default_handler_count()),
+            )),
             weak_self: weak_self.clone(),
             next_scheduler_id: AtomicSchedulerId::default(),
             max_usage_queue_count,
@@ -435,6 +444,12 @@ where
         self.scheduler_inners.lock().expect("not poisoned").len()
     }
 
+    /// Statistics of the schedulers spawned by this pool since its creation.
+    pub fn stats(&self) -> SchedulerPoolStats {
+        let pooled_scheduler_count = self.scheduler_inners.lock().expect("not poisoned").len();
+        self.stats.snapshot(pooled_scheduler_count)
+    }
+
     fn create_handler_context(&self) -> HandlerContext {
         let thread_count = self.block_verification_handler_count;
 
@@ -1241,6 +1256,8 @@ impl<S: SpawnableScheduler<TH>, TH: TaskHandler> ThreadManager<S, TH> {
 
         assert_matches!(self.session_result_with_timings, None);
 
+        let stats = self.pool.stats.clone();
+
         // High-level flow of new tasks:
         // 1. the replay stage thread send a new task.
         // 2. the scheduler thread accepts the task.
@@ -1251,6 +1268,7 @@ impl<S: SpawnableScheduler<TH>, TH: TaskHandler> ThreadManager<S, TH> {
         let scheduler_main_loop = {
             let handler_context = handler_context.clone_for_scheduler_thread();
             let session_result_sender = self.session_result_sender.clone();
+            let stats = stats.clone();
             // Taking new_task_receiver here is important to ensure there's a single receiver. In
             // this way, the replay stage will get .send() failures reliably, after this scheduler
             // thread died along with the single receiver.
@@ -1310,6 +1328,11 @@ impl<S: SpawnableScheduler<TH>, TH: TaskHandler> ThreadManager<S, TH> {
                     )
                 };
 
+                // Stats are only taken at session boundaries to keep the per-task overhead of
+                // this thread at zero.
+                let mut session_started_at = Instant::now();
+                let mut cpu_time_at_session_end = thread_cpu_time();
+
                 // The following loop maintains and updates ResultWithTimings as its
                 // externally-provided mutable state for each session in this way:
                 //
@@ -1411,6 +1434,20 @@ impl<S: SpawnableScheduler<TH>, TH: TaskHandler> ThreadManager<S, TH> {
                     assert!(mem::replace(&mut is_finished, false));
 
                     sleepless_testing::at(CheckPoint::SessionFinished(current_slot));
+                    // Record the session before its result is observable by the replay code-path,
+                    // so that stats are always up to date with finished sessions. This is a
+                    // single clock read and an uncontended lock per session.
+                    let cpu_time = thread_cpu_time();
+                    stats.record_session(
+                        current_slot,
+                        session_started_at.elapsed(),
+                        cpu_time
+                            .zip(cpu_time_at_session_end)
+                            .map(|(now, previous)| now.saturating_sub(previous))
+                            .unwrap_or_default(),
+                    );
+                    cpu_time_at_session_end = cpu_time;
+
                     // Finalize the current session after asserting it's explicitly requested so.
                     // Send result first because this is blocking the replay code-path.
                     session_result_sender
@@ -1447,6 +1484,7 @@ impl<S: SpawnableScheduler<TH>, TH: TaskHandler> ThreadManager<S, TH> {
                                 unreachable!("cannot receive new task before session start");
                             }
                             Ok(NewTaskPayload::OpenSubchannel(context_and_result_with_timings)) => {
+                                session_started_at = Instant::now();
                                 let new_context = context_and_result_with_timings.0;
                                 new_result_with_timings
                                     .replace(context_and_result_with_timings.1)
@@ -1508,8 +1546,9 @@ impl<S: SpawnableScheduler<TH>, TH: TaskHandler> ThreadManager<S, TH> {
             }
         };
 
-        let handler_main_loop = || {
+        let handler_main_loop = |handler_index| {
             let handler_context = handler_context.clone();
+            let stats = stats.clone();
             let mut runnable_task_receiver = runnable_task_receiver.clone();
             let finished_blocked_task_sender = finished_blocked_task_sender.clone();
             let finished_idle_task_sender = finished_idle_task_sender.clone();
@@ -1562,11 +1601,13 @@ impl<S: SpawnableScheduler<TH>, TH: TaskHandler> ThreadManager<S, TH> {
                         }
                     }
                     let mut task = ExecutedTask::new_boxed(task);
+                    let handling_started_at = Instant::now();
                     Self::execute_task_with_handler(
                         runnable_task_receiver.context(),
                         &mut task,
                         &handler_context,
                     );
+                    stats.record_handler_busy(handler_index, handling_started_at.elapsed());
                     if sender.send(Ok(task)).is_err() {
                         warn!("handler_thread: scheduler thread aborted...");
                         break;
@@ -1589,7 +1630,7 @@ impl<S: SpawnableScheduler<TH>, TH: TaskHandler> ThreadManager<S, TH> {
                 |thx| {
                     thread::Builder::new()
                         .name(format!("solScHandle{mode_char}{thx:02}"))
-                        .spawn_tracked(handler_main_loop())
+                        .spawn_tracked(handler_main_loop(thx))
                         .unwrap()
                 }
             })
@@ -2687,6 +2728,57 @@ mod tests {
         assert!(!child_bank.has_installed_scheduler());
     }
 
+    #[test]
+    fn test_scheduler_install_into_bank_after_switching_replay() {
+        agave_logger::setup();
+
+        let pool = DefaultSchedulerPool::new_dyn_for_verification(None, None, None, None, None);
+
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank = Bank::new_for_tests(&genesis_config);
+        let bank_forks = BankForks::new_rw_arc(bank);
+        let bank = bank_forks.read().unwrap().root_bank();
+        bank_forks.write().unwrap().install_scheduler_pool(pool);
+
+        // banks inserted while the scheduler is disabled are replayed without it
+        bank_forks
+            .write()
+            .unwrap()
+            .set_block_verification_scheduler_enabled(false);
+        let bank1 = Bank::new_from_parent_with_bank_forks(
+            bank_forks.as_ref(),
+            bank,
+            SlotLeader::default(),
+            1,
+        );
+        assert!(
+            !bank_forks
+                .read()
+                .unwrap()
+                .get_with_scheduler(1)
+                .unwrap()
+                .has_installed_scheduler()
+        );
+
+        // and re-enabling only affects banks inserted afterwards
+        bank_forks
+            .write()
+            .unwrap()
+            .set_block_verification_scheduler_enabled(true);
+        Bank::new_from_parent_with_bank_forks(bank_forks.as_ref(), bank1, SlotLeader::default(), 2);
+        let mut bank_forks = bank_forks.write().unwrap();
+        assert!(
+            !bank_forks
+                .get_with_scheduler(1)
+                .unwrap()
+                .has_installed_scheduler()
+        );
+        let mut bank2 = bank_forks.get_with_scheduler(2).unwrap();
+        assert!(bank2.has_installed_scheduler());
+        bank_forks.remove(bank2.slot());
+        bank2.drop_scheduler();
+    }
+
     fn setup_dummy_fork_graph(bank: Bank) -> (Arc<Bank>, Arc<RwLock<BankForks>>) {
         let slot = bank.slot();
         let bank_fork = BankForks::new_rw_arc(bank);
@@ -2723,6 +2815,52 @@ mod tests {
         assert_eq!(bank.transaction_count(), 1);
     }
 
+    #[test]
+    fn test_scheduler_pool_stats() {
+        agave_logger::setup();
+
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let tx0 = RuntimeTransaction::from_transaction_for_tests(system_transaction::transfer(
+            &mint_keypair,
+            &solana_pubkey::new_rand(),
+            2,
+            genesis_config.hash(),
+        ));
+        let bank = Bank::new_for_tests(&genesis_config);
+        let (bank, _bank_forks) = setup_dummy_fork_graph(bank);
+        let pool = DefaultSchedulerPool::new_for_verification(Some(2), None, None, None, None);
+
+        let stats = pool.stats();
+        assert_eq!(stats.pooled_scheduler_count, 0);
+        assert_eq!(stats.handler_busy_us, vec![0, 0]);
+        assert_eq!(stats.completed_session_count, 0);
+        assert_eq!(stats.recent_block_replays, vec![]);
+
+        let context = SchedulingContext::new(bank.clone());
+        let scheduler = pool.take_scheduler(context).unwrap();
+        scheduler.schedule_execution(tx0, 0).unwrap();
+        let bank = BankWithScheduler::new(bank, Some(scheduler));
+        assert_matches!(bank.wait_for_completed_scheduler(), Some((Ok(()), _)));
+
+        let stats = pool.stats();
+        assert_eq!(stats.pooled_scheduler_count, 1);
+        assert_eq!(stats.handler_count, 2);
+        assert_eq!(
+            stats.handler_busy_us.iter().sum::<u64>(),
+            stats.execution_us
+        );
+        assert!(stats.execution_us > 0);
+        assert_eq!(stats.completed_session_count, 1);
+        assert_matches!(
+            stats.recent_block_replays[..],
+            [BlockReplayLatency { slot, .. }] if slot == bank.slot()
+        );
+    }
+
     fn do_test_scheduler_schedule_execution_failure(extra_tx_after_failure: bool) {
         agave_logger::setup();
 
@@ -0,0 +1,185 @@
+//! Runtime statistics of [`SchedulerPool`](crate::SchedulerPool).
+//!
+//! These are updated by the scheduler and handler threads with care not to burden the scheduler
+//! thread: it only touches them once per session, while handler threads record their busy time
+//! per task, which is negligible compared to the actual transaction execution.
+
+use {
+    solana_clock::Slot,
+    std::{
+        collections::VecDeque,
+        sync::{
+            Mutex,
+            atomic::{AtomicU64, Ordering::Relaxed},
+        },
+        time::Duration,
+    },
+};
+
+/// Number of the most recent sessions whose replay latency is retained.
+const MAX_RECENT_BLOCK_REPLAYS: usize = 64;
+
+#[derive(Debug)]
+pub(crate) struct PoolStats {
+    /// Busy time of each handler thread index, summed over all schedulers of the pool.
+    handler_busy_us: Box<[AtomicU64]>,
+    /// Cpu time consumed by the scheduler threads of the pool.
+    scheduling_us: AtomicU64,
+    completed_session_count: AtomicU64,
+    recent_block_replays: Mutex<VecDeque<BlockReplayLatency>>,
+}
+
+impl PoolStats {
+    pub(crate) fn new(handler_count: usize) -> Self {
+        Self {
+            handler_busy_us: (0..handler_count).map(|_| AtomicU64::default()).collect(),
+            scheduling_us: AtomicU64::default(),
+            completed_session_count: AtomicU64::default(),
+            recent_block_replays: Mutex::new(VecDeque::with_capacity(MAX_RECENT_BLOCK_REPLAYS)),
+        }
+    }
+
+    pub(crate) fn record_handler_busy(&self, handler_index: usize, busy: Duration) {
+        self.handler_busy_us[handler_index].fetch_add(as_us(busy), Relaxed);
+    }
+
+    pub(crate) fn record_session(&self, slot: Slot, replay: Duration, scheduling: Duration) {
+        self.scheduling_us.fetch_add(as_us(scheduling), Relaxed);
+        self.completed_session_count.fetch_add(1, Relaxed);
+
+        let mut recent_block_replays = self.recent_block_replays.lock().unwrap();
+        if recent_block_replays.len() == MAX_RECENT_BLOCK_REPLAYS {
+            recent_block_replays.pop_front();
+        }
+        recent_block_replays.push_back(BlockReplayLatency {
+            slot,
+            replay_us: as_us(replay),
+        });
+    }
+
+    pub(crate) fn snapshot(&self, pooled_scheduler_count: usize) -> SchedulerPoolStats {
+        let handler_busy_us: Vec<_> = self
+            .handler_busy_us
+            .iter()
+            .map(|busy_us| busy_us.load(Relaxed))
+            .collect();
+        SchedulerPoolStats {
+            pooled_scheduler_count,
+            handler_count: handler_busy_us.len(),
+            execution_us: handler_busy_us.iter().sum(),
+            handler_busy_us,
+            scheduling_us: self.scheduling_us.load(Relaxed),
+            completed_session_count: self.completed_session_count.load(Relaxed),
+            recent_block_replays: self
+                .recent_block_replays
+                .lock()
+                .unwrap()
+                .iter()
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+fn as_us(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+/// Cpu time consumed by the calling thread so far, if the platform can tell it.
+pub(crate) fn thread_cpu_time() -> Option<Duration> {
+    #[cfg(unix)]
+    {
+        let mut time = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // SAFETY: `time` is a valid, writable timespec and CLOCK_THREAD_CPUTIME_ID is supported
+        // by all the unix targets the validator runs on.
+        let result = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) };
+        (result == 0).then(|| Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Wall time taken by a scheduler session of a block, from the session start to its completion,
+/// which includes waiting for the block's entries to arrive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockReplayLatency {
+    pub slot: Slot,
+    pub replay_us: u64,
+}
+
+/// Point-in-time statistics of a [`SchedulerPool`](crate::SchedulerPool), accumulated since its
+/// creation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchedulerPoolStats {
+    /// Number of idle schedulers held by the pool.
+    pub pooled_scheduler_count: usize,
+    /// Number of handler threads of each scheduler.
+    pub handler_count: usize,
+    /// Busy time of each handler thread index, summed over all schedulers.
+    pub handler_busy_us: Vec<u64>,
+    /// Total busy time of all handler threads.
+    pub execution_us: u64,
+    /// Total cpu time of all scheduler threads.
+    pub scheduling_us: u64,
+    pub completed_session_count: u64,
+    /// Replay latency of the most recently completed sessions, oldest first.
+    pub recent_block_replays: Vec<BlockReplayLatency>,
+}
+
+impl SchedulerPoolStats {
+    /// Time spent scheduling tasks relative to the time spent executing them.
+    pub fn scheduling_to_execution_ratio(&self) -> f64 {
+        if self.execution_us == 0 {
+            0.0
+        } else {
+            self.scheduling_us as f64 / self.execution_us as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_stats() {
+        let stats = PoolStats::new(2);
+        stats.record_handler_busy(0, Duration::from_micros(300));
+        stats.record_handler_busy(1, Duration::from_micros(100));
+        stats.record_handler_busy(0, Duration::from_micros(200));
+        for slot in 0..(MAX_RECENT_BLOCK_REPLAYS as Slot + 2) {
+            stats.record_session(slot, Duration::from_millis(slot), Duration::from_micros(1));
+        }
+
+        let snapshot = stats.snapshot(3);
+        assert_eq!(snapshot.pooled_scheduler_count, 3);
+        assert_eq!(snapshot.handler_count, 2);
+        assert_eq!(snapshot.handler_busy_us, vec![500, 100]);
+        assert_eq!(snapshot.execution_us, 600);
+        assert_eq!(snapshot.scheduling_us, MAX_RECENT_BLOCK_REPLAYS as u64 + 2);
+        assert_eq!(
+            snapshot.completed_session_count,
+            MAX_RECENT_BLOCK_REPLAYS as u64 + 2
+        );
+        assert_eq!(
+            snapshot.recent_block_replays.len(),
+            MAX_RECENT_BLOCK_REPLAYS
+        );
+        assert_eq!(
+            snapshot.recent_block_replays.first(),
+            Some(&BlockReplayLatency {
+                slot: 2,
+                replay_us: 2_000,
+            })
+        );
+        assert_eq!(
+            snapshot.scheduling_to_execution_ratio(),
+            (MAX_RECENT_BLOCK_REPLAYS as f64 + 2.0) / 600.0
+        );
+    }
+}
@@ -21,17 +21,25 @@ use {
         consensus::{Tower, tower_storage::TowerStorage},
         repair::repair_service,
         validator::{
-            BlockProductionMethod, SchedulerPacing, TransactionStructure, ValidatorStartProgress,
-            should_require_vote_history_file,
+            BlockProductionMethod, BlockVerificationMethod, SchedulerPacing, TransactionStructure,
+            ValidatorStartProgress, should_require_vote_history_file,
         },
     },
-    solana_geyser_plugin_manager::GeyserPluginManagerRequest,
+    solana_geyser_plugin_manager::{
+        GeyserPluginManagerRequest, account_restream::AccountRestream,
+        plugin_dispatcher::PluginDispatchStats,
+    },
     solana_gossip::contact_info::{ContactInfo, Protocol, SOCKET_ADDR_UNSPECIFIED},
     solana_keypair::{Keypair, read_keypair_file},
     solana_metrics::{datapoint_info, datapoint_warn},
     solana_pubkey::Pubkey,
+    solana_rpc::{
+        rpc_rate_limiter::{RpcRateLimitConfig, RpcRateLimiter},
+        rpc_slow_query_log::{RpcSlowQueryLog, RpcSlowQueryMethodStats},
+    },
     solana_runtime::{bank::VATHealthError, snapshot_controller::SnapshotController},
     solana_signer::Signer,
+    solana_unified_scheduler_pool::SchedulerPoolStats,
     solana_validator_exit::Exit,
     std::{
         collections::{HashMap, HashSet},
@@ -61,6 +69,8 @@ pub struct AdminRpcRequestMetadata {
     pub tower_storage: Arc<dyn TowerStorage>,
     pub vote_history_storage: Arc<dyn VoteHistoryStorage>,
     pub staked_nodes_overrides: Arc<RwLock<HashMap<Pubkey, u64>>>,
+    pub rpc_rate_limiter: Arc<RpcRateLimiter>,
+    pub rpc_slow_query_log: Option<Arc<RpcSlowQueryLog>>,
     pub post_init: Arc<RwLock<Option<AdminRpcRequestMetadataPostInit>>>,
     pub rpc_to_plugin_manager_sender: Option<Sender<GeyserPluginManagerRequest>>,
 }
@@ -124,6 +134,48 @@ pub struct AdminRpcValidatorAdmissionTicketStatus {
     pub next_epoch_vat_failure_reason: Option<VATHealthError>,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdminRpcBlockReplayLatency {
+    pub slot: Slot,
+    pub replay_us: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdminRpcBlockVerificationStats {
+    pub block_verification_method: BlockVerificationMethod,
+    pub pooled_scheduler_count: usize,
+    pub handler_count: usize,
+    pub handler_busy_us: Vec<u64>,
+    pub scheduling_us: u64,
+    pub execution_us: u64,
+    pub scheduling_to_execution_ratio: f64,
+    pub completed_session_count: u64,
+    pub recent_block_replays: Vec<AdminRpcBlockReplayLatency>,
+}
+
+impl AdminRpcBlockVerificationStats {
+    fn new(block_verification_method: BlockVerificationMethod, stats: SchedulerPoolStats) -> Self {
+        Self {
+            block_verification_method,
+            pooled_scheduler_count: stats.pooled_scheduler_count,
+            handler_count: stats.handler_count,
+            scheduling_us: stats.scheduling_us,
+            execution_us: stats.execution_us,
+            scheduling_to_execution_ratio: stats.scheduling_to_execution_ratio(),
+            completed_session_count: stats.completed_session_count,
+            recent_block_replays: stats
+                .recent_block_replays
+                .iter()
+                .map(|latency| AdminRpcBlockReplayLatency {
+                    slot: latency.slot,
+                    replay_us: latency.replay_us,
+                })
+                .collect(),
+            handler_busy_us: stats.handler_busy_us,
+        }
+    }
+}
+
 impl From<ContactInfo> for AdminRpcContactInfo {
     fn from(node: ContactInfo) -> Self {
         macro_rules! unwrap_socket {
@@ -177,6 +229,47 @@ impl Display for AdminRpcRepairWhitelist {
 impl solana_cli_output::VerboseDisplay for AdminRpcRepairWhitelist {}
 impl solana_cli_output::QuietDisplay for AdminRpcRepairWhitelist {}
 
+impl Display for AdminRpcBlockVerificationStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Block verification method: {}",
+            self.block_verification_method
+        )?;
+        writeln!(
+            f,
+            "Unified scheduler pool: {} idle schedulers, {} handlers each",
+            self.pooled_scheduler_count, self.handler_count
+        )?;
+        for (index, busy_us) in self.handler_busy_us.iter().enumerate() {
+            writeln!(f, "  Handler {index:02} busy: {} ms", busy_us / 1_000)?;
+        }
+        writeln!(
+            f,
+            "Scheduling: {} ms, execution: {} ms, ratio: {:.4}",
+            self.scheduling_us / 1_000,
+            self.execution_us / 1_000,
+            self.scheduling_to_execution_ratio
+        )?;
+        writeln!(
+            f,
+            "Recent block replays (of {} in total):",
+            self.completed_session_count
+        )?;
+        for latency in &self.recent_block_replays {
+            writeln!(
+                f,
+                "  Slot {}: {:.1} ms",
+                latency.slot,
+                latency.replay_us as f64 / 1_000.0
+            )?;
+        }
+        Ok(())
+    }
+}
+impl solana_cli_output::VerboseDisplay for AdminRpcBlockVerificationStats {}
+impl solana_cli_output::QuietDisplay for AdminRpcBlockVerificationStats {}
+
 #[rpc]
 pub trait AdminRpc {
     type Metadata;
@@ -207,6 +300,21 @@ pub trait AdminRpc {
     #[rpc(meta, name = "listPlugins")]
     fn list_plugins(&self, meta: Self::Metadata) -> BoxFuture<Result<Vec<String>>>;
 
+    #[rpc(meta, name = "pluginDispatchStats")]
+    fn plugin_dispatch_stats(
+        &self,
+        meta: Self::Metadata,
+    ) -> BoxFuture<Result<Vec<PluginDispatchStats>>>;
+
+    /// Re-streams to a plugin the accounts owned by `owners` in the root bank
+    #[rpc(meta, name = "restreamPluginAccounts")]
+    fn restream_plugin_accounts(
+        &self,
+        meta: Self::Metadata,
+        name: String,
+        owners: Vec<String>,
+    ) -> BoxFuture<Result<AccountRestream>>;
+
     #[rpc(meta, name = "rpcAddress")]
     fn rpc_addr(&self, meta: Self::Metadata) -> Result<Option<SocketAddr>>;
 
@@ -250,6 +358,19 @@ pub trait AdminRpc {
     #[rpc(meta, name = "setStakedNodesOverrides")]
     fn set_staked_nodes_overrides(&self, meta: Self::Metadata, path: String) -> Result<()>;
 
+    #[rpc(meta, name = "setRpcRateLimits")]
+    fn set_rpc_rate_limits(&self, meta: Self::Metadata, path: Option<String>) -> Result<()>;
+
+    #[rpc(meta, name = "rpcRateLimits")]
+    fn rpc_rate_limits(&self, meta: Self::Metadata) -> Result<Option<RpcRateLimitConfig>>;
+
+    #[rpc(meta, name = "rpcSlowQueryStats")]
+    fn rpc_slow_query_stats(
+        &self,
+        meta: Self::Metadata,
+        reset: Option<bool>,
+    ) -> Result<Vec<RpcSlowQueryMethodStats>>;
+
     #[rpc(meta, name = "contactInfo")]
     fn contact_info(&self, meta: Self::Metadata) -> Result<AdminRpcContactInfo>;
 
@@ -305,6 +426,21 @@ pub trait AdminRpc {
         scheduler_pacing: SchedulerPacing,
     ) -> Result<()>;
 
+    #[rpc(meta, name = "blockVerificationStats")]
+    fn block_verification_stats(
+        &self,
+        meta: Self::Metadata,
+    ) -> Result<AdminRpcBlockVerificationStats>;
+
+    /// Switches replay of blocks between the unified scheduler and `blockstore_processor`. Blocks
+    /// already being replayed are unaffected.
+    #[rpc(meta, name = "setBlockVerificationMethod")]
+    fn set_block_verification_method(
+        &self,
+        meta: Self::Metadata,
+        block_verification_method: BlockVerificationMethod,
+    ) -> Result<()>;
+
     #[rpc(meta, name = "isGeneratingSnapshots")]
     fn is_generating_snapshots(&self, meta: Self::Metadata) -> Result<bool>;
 
@@ -523,6 +659,79 @@ impl AdminRpc for AdminRpcImpl {
         })
     }
 
+    fn plugin_dispatch_stats(
+        &self,
+        meta: Self::Metadata,
+    ) -> BoxFuture<Result<Vec<PluginDispatchStats>>> {
+        Box::pin(async move {
+            // Construct channel for plugin to respond to this particular rpc request instance
+            let (response_sender, response_receiver) = oneshot_channel();
+
+            // Send request to plugin manager
+            if let Some(ref rpc_to_manager_sender) = meta.rpc_to_plugin_manager_sender {
+                rpc_to_manager_sender
+                    .send(GeyserPluginManagerRequest::DispatchStats { response_sender })
+                    .expect("GeyerPluginService should never drop request receiver");
+            } else {
+                return Err(jsonrpc_core::Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: "No geyser plugin service".to_string(),
+                    data: None,
+                });
+            }
+
+            // Await response from plugin manager
+            response_receiver
+                .await
+                .expect("GeyerPluginService's oneshot sender shouldn't drop early")
+        })
+    }
+
+    fn restream_plugin_accounts(
+        &self,
+        meta: Self::Metadata,
+        name: String,
+        owners: Vec<String>,
+    ) -> BoxFuture<Result<AccountRestream>> {
+        Box::pin(async move {
+            let owners = owners
+                .iter()
+                .map(|owner| {
+                    owner.parse::<Pubkey>().map_err(|err| {
+                        jsonrpc_core::error::Error::invalid_params(format!(
+                            "Invalid owner {owner}: {err}"
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            // Construct channel for plugin to respond to this particular rpc request instance
+            let (response_sender, response_receiver) = oneshot_channel();
+
+            // Send request to plugin manager if there is a geyser service
+            if let Some(ref rpc_to_manager_sender) = meta.rpc_to_plugin_manager_sender {
+                rpc_to_manager_sender
+                    .send(GeyserPluginManagerRequest::RestreamAccounts {
+                        name,
+                        owners,
+                        response_sender,
+                    })
+                    .expect("GeyerPluginService should never drop request receiver");
+            } else {
+                return Err(jsonrpc_core::Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: "No geyser plugin service".to_string(),
+                    data: None,
+                });
+            }
+
+            // Await response from plugin manager
+            response_receiver
+                .await
+                .expect("GeyerPluginService's oneshot sender shouldn't drop early")
+        })
+    }
+
     fn rpc_addr(&self, meta: Self::Metadata) -> Result<Option<SocketAddr>> {
         debug!("rpc_addr admin rpc request received");
         Ok(meta.rpc_addr)
@@ -636,6 +845,44 @@ impl AdminRpc for AdminRpcImpl {
         Ok(())
     }
 
+    fn set_rpc_rate_limits(&self, meta: Self::Metadata, path: Option<String>) -> Result<()> {
+        let config = path
+            .as_ref()
+            .map(|path| {
+                load_rpc_rate_limits(path).map_err(|err| {
+                    jsonrpc_core::error::Error::invalid_params(format!(
+                        "Failed to load RPC rate limits from {path}: {err}"
+                    ))
+                })
+            })
+            .transpose()?;
+        meta.rpc_rate_limiter.set_config(config);
+        match path {
+            Some(path) => info!("RPC rate limits loaded from {path}"),
+            None => info!("RPC rate limits disabled"),
+        }
+        Ok(())
+    }
+
+    fn rpc_rate_limits(&self, meta: Self::Metadata) -> Result<Option<RpcRateLimitConfig>> {
+        Ok(meta.rpc_rate_limiter.config())
+    }
+
+    fn rpc_slow_query_stats(
+        &self,
+        meta: Self::Metadata,
+        reset: Option<bool>,
+    ) -> Result<Vec<RpcSlowQueryMethodStats>> {
+        let slow_query_log = meta.rpc_slow_query_log.ok_or_else(|| {
+            jsonrpc_core::error::Error::invalid_params("RPC slow query log is not enabled")
+        })?;
+        let stats = slow_query_log.stats();
+        if reset.unwrap_or_default() {
+            slow_query_log.reset_stats();
+        }
+        Ok(stats)
+    }
+
     fn contact_info(&self, meta: Self::Metadata) -> Result<AdminRpcContactInfo> {
         meta.with_post_init(|post_init| Ok(post_init.cluster_info.my_contact_info().into()))
     }
@@ -883,7 +1130,10 @@ impl AdminRpc for AdminRpcImpl {
                 .try_send(BankingControlMsg::Internal {
                     block_production_method,
                     num_workers,
-                    config: SchedulerConfig { scheduler_pacing },
+                    config: SchedulerConfig {
+                        scheduler_pacing,
+                        ..SchedulerConfig::default()
+                    },
                 })
                 .is_err()
             {
@@ -896,6 +1146,51 @@ impl AdminRpc for AdminRpcImpl {
         })
     }
 
+    fn block_verification_stats(
+        &self,
+        meta: Self::Metadata,
+    ) -> Result<AdminRpcBlockVerificationStats> {
+        meta.with_post_init(|post_init| {
+            let scheduler_pool = post_init.scheduler_pool.upgrade().ok_or_else(|| {
+                jsonrpc_core::error::Error::invalid_params("Unified scheduler pool is unavailable")
+            })?;
+            let block_verification_method = if post_init
+                .bank_forks
+                .read()
+                .unwrap()
+                .block_verification_scheduler_enabled()
+            {
+                BlockVerificationMethod::UnifiedScheduler
+            } else {
+                BlockVerificationMethod::BlockstoreProcessor
+            };
+            Ok(AdminRpcBlockVerificationStats::new(
+                block_verification_method,
+                scheduler_pool.stats(),
+            ))
+        })
+    }
+
+    fn set_block_verification_method(
+        &self,
+        meta: Self::Metadata,
+        block_verification_method: BlockVerificationMethod,
+    ) -> Result<()> {
+        debug!("set_block_verification_method rpc request received: {block_verification_method}");
+
+        meta.with_post_init(|post_init| {
+            post_init
+                .bank_forks
+                .write()
+                .unwrap()
+                .set_block_verification_scheduler_enabled(matches!(
+                    block_verification_method,
+                    BlockVerificationMethod::UnifiedScheduler
+                ));
+            Ok(())
+        })
+    }
+
     fn is_generating_snapshots(&self, meta: Self::Metadata) -> Result<bool> {
         if let Some(snapshot_controller) = meta.snapshot_controller() {
             Ok(snapshot_controller.is_generating_snapshots())
@@ -1142,6 +1437,16 @@ pub fn load_staked_nodes_overrides(
     }
 }
 
+pub fn load_rpc_rate_limits(
+    path: &str,
+) -> std::result::Result<RpcRateLimitConfig, Box<dyn error::Error>> {
+    debug!("Loading RPC rate limits configuration from {path}");
+    let file = std::fs::File::open(path)?;
+    let config: RpcRateLimitConfig = serde_yaml::from_reader(file)?;
+    config.verify()?;
+    Ok(config)
+}
+
 #[cfg(test)]
 mod tests {
     use {
@@ -1175,7 +1480,12 @@ mod tests {
             bank::{Bank, BankTestConfig},
             bank_forks::BankForks,
         },
-        std::{collections::HashSet, fs::remove_dir_all, sync::atomic::AtomicBool},
+        solana_unified_scheduler_pool::DefaultSchedulerPool,
+        std::{
+            collections::HashSet,
+            fs::remove_dir_all,
+            sync::{Weak, atomic::AtomicBool},
+        },
         tokio::sync::mpsc,
     };
 
@@ -1261,8 +1571,11 @@ mod tests {
                     snapshot_controller,
                     blockstore,
                     votor_event_sender,
+                    scheduler_pool: Weak::new(),
                 }))),
                 staked_nodes_overrides: Arc::new(RwLock::new(HashMap::new())),
+                rpc_rate_limiter: Arc::new(RpcRateLimiter::default()),
+                rpc_slow_query_log: None,
                 rpc_to_plugin_manager_sender: None,
             };
             let mut io = MetaIoHandler::default();
@@ -1416,6 +1729,8 @@ mod tests {
                 ),
                 post_init: post_init.clone(),
                 staked_nodes_overrides: Arc::new(RwLock::new(HashMap::new())),
+                rpc_rate_limiter: Arc::new(RpcRateLimiter::default()),
+                rpc_slow_query_log: None,
                 rpc_to_plugin_manager_sender: None,
             };
 
@@ -1499,6 +1814,8 @@ mod tests {
             ),
             post_init: post_init.clone(),
             staked_nodes_overrides: Arc::new(RwLock::new(HashMap::new())),
+            rpc_rate_limiter: Arc::new(RpcRateLimiter::default()),
+            rpc_slow_query_log: None,
             rpc_to_plugin_manager_sender: None,
         };
 
@@ -1595,6 +1912,8 @@ mod tests {
             ),
             post_init: Arc::new(RwLock::new(None)),
             staked_nodes_overrides: Arc::new(RwLock::new(HashMap::new())),
+            rpc_rate_limiter: Arc::new(RpcRateLimiter::default()),
+            rpc_slow_query_log: None,
             rpc_to_plugin_manager_sender: None,
         };
 
@@ -1609,4 +1928,141 @@ mod tests {
             "snapshot_controller unavailable"
         );
     }
+
+    #[test]
+    fn test_set_rpc_rate_limits() {
+        let rpc = RpcHandler::_start();
+        let RpcHandler { io, meta, .. } = rpc;
+        let set_rpc_rate_limits = |config: &str| {
+            let file = tempfile::NamedTempFile::new().unwrap();
+            std::fs::write(file.path(), config).unwrap();
+            let request = format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"setRpcRateLimits","params":["{}"]}}"#,
+                file.path().display(),
+            );
+            let response = io.handle_request_sync(&request, meta.clone());
+            serde_json::from_str::<Value>(&response.expect("actual response"))
+                .expect("actual response deserialization")
+        };
+
+        let result = set_rpc_rate_limits(
+            "defaultQuota:\n  creditsPerSecond: 10\n  burstCredits: 100\nmethodCosts:\n  \
+             getProgramAccounts: 50\n",
+        );
+        assert_eq!(result["result"], Value::Null);
+
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"rpcRateLimits","params":[]}"#;
+        let response = io.handle_request_sync(request, meta.clone());
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"]["defaultQuota"]["burstCredits"], 100);
+        assert_eq!(result["result"]["methodCosts"]["getProgramAccounts"], 50);
+
+        // Configurations under which a method can never be afforded are rejected
+        let result = set_rpc_rate_limits(
+            "defaultQuota:\n  creditsPerSecond: 10\n  burstCredits: 100\ndefaultMethodCost: 1000\n",
+        );
+        assert!(result["error"].is_object());
+        assert_eq!(
+            meta.rpc_rate_limiter.config().unwrap().default_method_cost,
+            1
+        );
+
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"setRpcRateLimits","params":[null]}"#;
+        let response = io.handle_request_sync(request, meta.clone());
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"], Value::Null);
+        assert_eq!(meta.rpc_rate_limiter.config(), None);
+    }
+
+    #[test]
+    fn test_rpc_slow_query_stats() {
+        let RpcHandler { io, mut meta } = RpcHandler::_start();
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"rpcSlowQueryStats","params":[]}"#;
+
+        let response = io.handle_request_sync(request, meta.clone());
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(
+            result["error"]["message"],
+            "RPC slow query log is not enabled"
+        );
+
+        let log_dir = tempfile::TempDir::new().unwrap();
+        meta.rpc_slow_query_log = Some(Arc::new(
+            RpcSlowQueryLog::new(solana_rpc::rpc_slow_query_log::RpcSlowQueryLogConfig::new(
+                log_dir.path().join("slow.log"),
+            ))
+            .unwrap(),
+        ));
+        let response = io.handle_request_sync(request, meta);
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_block_verification_method() {
+        let RpcHandler { io, meta } = RpcHandler::_start();
+        let stats_request =
+            r#"{"jsonrpc":"2.0","id":1,"method":"blockVerificationStats","params":[]}"#;
+
+        let response = io.handle_request_sync(stats_request, meta.clone());
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(
+            result["error"]["message"],
+            "Unified scheduler pool is unavailable"
+        );
+
+        let scheduler_pool = DefaultSchedulerPool::new(Some(2), None, None, None, None);
+        {
+            let mut post_init = meta.post_init.write().unwrap();
+            let post_init = post_init.as_mut().unwrap();
+            post_init.scheduler_pool = Arc::downgrade(&scheduler_pool);
+            post_init
+                .bank_forks
+                .write()
+                .unwrap()
+                .install_scheduler_pool(scheduler_pool);
+        }
+        let response = io.handle_request_sync(stats_request, meta.clone());
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(
+            result["result"]["block_verification_method"],
+            "unified-scheduler"
+        );
+        assert_eq!(
+            result["result"]["handler_busy_us"],
+            serde_json::json!([0, 0])
+        );
+
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"setBlockVerificationMethod","params":["blockstore-processor"]}"#;
+        let response = io.handle_request_sync(request, meta.clone());
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"], Value::Null);
+        assert!(
+            !meta
+                .post_init
+                .read()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .bank_forks
+                .read()
+                .unwrap()
+                .block_verification_scheduler_enabled()
+        );
+
+        let response = io.handle_request_sync(stats_request, meta);
+        let result: Value = serde_json::from_str(&response.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(
+            result["result"]["block_verification_method"],
+            "blockstore-processor"
+        );
+    }
 }
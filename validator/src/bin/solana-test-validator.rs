@@ -453,6 +453,8 @@ fn main() {
             validator_exit_backpressure: HashMap::default(),
             authorized_voter_keypairs: genesis.authorized_voter_keypairs.clone(),
             staked_nodes_overrides: genesis.staked_nodes_overrides.clone(),
+            rpc_rate_limiter: genesis.rpc_rate_limiter.clone(),
+            rpc_slow_query_log: None,
             post_init: admin_service_post_init,
             tower_storage: tower_storage.clone(),
             vote_history_storage: vote_history_storage.clone(),
@@ -69,14 +69,17 @@ pub fn app<'a>(version: &'a str, default_args: &'a DefaultArgs) -> App<'a, 'a> {
             SubCommand::with_name("init").about("Initialize the ledger directory then exit"),
         )
         .subcommand(commands::monitor::command())
+        .subcommand(commands::rpc_slow_query_stats::command())
         .subcommand(SubCommand::with_name("run").about("Run the validator"))
         .subcommand(commands::plugin::command())
         .subcommand(commands::set_identity::command())
         .subcommand(commands::set_log_filter::command())
+        .subcommand(commands::set_rpc_rate_limits::command())
         .subcommand(commands::staked_nodes_overrides::command())
         .subcommand(commands::wait_for_restart_window::command())
         .subcommand(commands::set_public_address::command())
         .subcommand(commands::manage_block_production::command(default_args))
+        .subcommand(commands::block_verification::command())
         .subcommand(commands::blockstore::command());
 
     commands::run::add_args(app, default_args)
@@ -0,0 +1,149 @@
+use {
+    crate::{
+        admin_rpc_service,
+        commands::{FromClapArgMatches, Result},
+    },
+    clap::{App, AppSettings, Arg, ArgMatches, SubCommand, value_t},
+    solana_cli_output::OutputFormat,
+    solana_core::validator::BlockVerificationMethod,
+    std::path::Path,
+};
+
+pub const COMMAND: &str = "block-verification";
+
+#[derive(Debug, PartialEq)]
+pub struct BlockVerificationStatsArgs {
+    pub output: OutputFormat,
+}
+
+impl FromClapArgMatches for BlockVerificationStatsArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self> {
+        Ok(BlockVerificationStatsArgs {
+            output: OutputFormat::from_matches(matches, "output", false),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BlockVerificationSetMethodArgs {
+    pub block_verification_method: BlockVerificationMethod,
+}
+
+impl FromClapArgMatches for BlockVerificationSetMethodArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self> {
+        Ok(BlockVerificationSetMethodArgs {
+            block_verification_method: value_t!(
+                matches,
+                "block_verification_method",
+                BlockVerificationMethod
+            )?,
+        })
+    }
+}
+
+pub fn command<'a>() -> App<'a, 'a> {
+    SubCommand::with_name(COMMAND)
+        .about("Inspect and control the validator's block verification")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::InferSubcommands)
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about(
+                    "Display the block verification method and the unified scheduler's pool size, \
+                     handler busy time, scheduling-vs-execution ratio and recent block replay \
+                     latencies",
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .takes_value(true)
+                        .value_name("MODE")
+                        .possible_values(&["json", "json-compact"])
+                        .help("Output display mode"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-method")
+                .about("Switch the transaction scheduling method for verifying ledger entries")
+                .arg(
+                    Arg::with_name("block_verification_method")
+                        .value_name("METHOD")
+                        .takes_value(true)
+                        .required(true)
+                        .possible_values(BlockVerificationMethod::cli_names())
+                        .help(BlockVerificationMethod::cli_message()),
+                )
+                .after_help(
+                    "Note: blocks already being replayed keep their method; the switch applies \
+                     from the next slot onwards and only to the currently running validator \
+                     instance",
+                ),
+        )
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<()> {
+    match matches.subcommand() {
+        ("stats", Some(subcommand_matches)) => {
+            let stats_args = BlockVerificationStatsArgs::from_clap_arg_match(subcommand_matches)?;
+
+            let admin_client = admin_rpc_service::connect(ledger_path);
+            let stats = admin_rpc_service::runtime()
+                .block_on(async move { admin_client.await?.block_verification_stats().await })?;
+
+            println!("{}", stats_args.output.formatted_string(&stats));
+        }
+        ("set-method", Some(subcommand_matches)) => {
+            let BlockVerificationSetMethodArgs {
+                block_verification_method,
+            } = BlockVerificationSetMethodArgs::from_clap_arg_match(subcommand_matches)?;
+
+            let admin_client = admin_rpc_service::connect(ledger_path);
+            admin_rpc_service::runtime().block_on(async move {
+                admin_client
+                    .await?
+                    .set_block_verification_method(block_verification_method)
+                    .await
+            })?;
+            println!("Block verification method set to {block_verification_method}");
+        }
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_args_struct_by_command_block_verification_stats() {
+        let matches = command().get_matches_from(vec![COMMAND, "stats", "--output", "json"]);
+        let subcommand_matches = matches.subcommand_matches("stats").unwrap();
+        assert_eq!(
+            BlockVerificationStatsArgs::from_clap_arg_match(subcommand_matches).unwrap(),
+            BlockVerificationStatsArgs {
+                output: OutputFormat::Json
+            }
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_block_verification_set_method() {
+        let matches =
+            command().get_matches_from(vec![COMMAND, "set-method", "blockstore-processor"]);
+        let subcommand_matches = matches.subcommand_matches("set-method").unwrap();
+        assert_eq!(
+            BlockVerificationSetMethodArgs::from_clap_arg_match(subcommand_matches).unwrap(),
+            BlockVerificationSetMethodArgs {
+                block_verification_method: BlockVerificationMethod::BlockstoreProcessor
+            }
+        );
+
+        assert!(
+            command()
+                .get_matches_from_safe(vec![COMMAND, "set-method", "unknown"])
+                .is_err()
+        );
+    }
+}
@@ -1,4 +1,5 @@
 pub mod authorized_voter;
+pub mod block_verification;
 pub mod blockstore;
 pub mod contact_info;
 pub mod exit;
@@ -7,10 +8,12 @@ pub mod monitor;
 pub mod plugin;
 pub mod repair_shred_from_peer;
 pub mod repair_whitelist;
+pub mod rpc_slow_query_stats;
 pub mod run;
 pub mod set_identity;
 pub mod set_log_filter;
 pub mod set_public_address;
+pub mod set_rpc_rate_limits;
 pub mod staked_nodes_overrides;
 pub mod wait_for_restart_window;
 
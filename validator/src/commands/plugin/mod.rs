@@ -3,7 +3,8 @@ use {
         admin_rpc_service,
         commands::{FromClapArgMatches, Result},
     },
-    clap::{App, AppSettings, Arg, ArgMatches, SubCommand, value_t},
+    clap::{App, AppSettings, Arg, ArgMatches, SubCommand, value_t, values_t},
+    solana_pubkey::Pubkey,
     std::path::Path,
 };
 
@@ -50,6 +51,21 @@ impl FromClapArgMatches for PluginReloadArgs {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct PluginRestreamArgs {
+    pub name: String,
+    pub owners: Vec<Pubkey>,
+}
+
+impl FromClapArgMatches for PluginRestreamArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self> {
+        Ok(PluginRestreamArgs {
+            name: value_t!(matches, "name", String)?,
+            owners: values_t!(matches, "owner", Pubkey)?,
+        })
+    }
+}
+
 pub fn command<'a>() -> App<'a, 'a> {
     let name_arg = Arg::with_name("name").required(true).takes_value(true);
     let config_arg = Arg::with_name("config").required(true).takes_value(true);
@@ -59,6 +75,11 @@ pub fn command<'a>() -> App<'a, 'a> {
         .setting(AppSettings::SubcommandRequiredElseHelp)
         .setting(AppSettings::InferSubcommands)
         .subcommand(SubCommand::with_name("list").about("List all current running geyser plugins"))
+        .subcommand(
+            SubCommand::with_name("stats").about(
+                "Show the dispatch queue statistics of geyser plugins with a dispatch queue",
+            ),
+        )
         .subcommand(
             SubCommand::with_name("unload")
                 .about("Unload a particular geyser plugin. You must specify the geyser plugin name")
@@ -81,6 +102,21 @@ pub fn command<'a>() -> App<'a, 'a> {
                 )
                 .arg(&config_arg),
         )
+        .subcommand(
+            SubCommand::with_name("restream")
+                .about(
+                    "Re-stream to a geyser plugin the accounts owned by the given programs in the \
+                     root bank, as at startup",
+                )
+                .arg(&name_arg)
+                .arg(
+                    Arg::with_name("owner")
+                        .required(true)
+                        .takes_value(true)
+                        .multiple(true)
+                        .value_name("OWNER_PUBKEY"),
+                ),
+        )
 }
 
 pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<()> {
@@ -98,6 +134,29 @@ pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<()> {
                 println!("There are currently no plugins loaded");
             }
         }
+        ("stats", _) => {
+            let admin_client = admin_rpc_service::connect(ledger_path);
+            let stats = admin_rpc_service::runtime()
+                .block_on(async move { admin_client.await?.plugin_dispatch_stats().await })?;
+            if stats.is_empty() {
+                println!("There are currently no plugins with a dispatch queue");
+            }
+            for stats in stats {
+                println!("{}:", stats.name);
+                println!(
+                    "  queued: {}/{} ({:?} on overflow)",
+                    stats.queued, stats.queue_capacity, stats.overflow_policy
+                );
+                if let Some(slot_lag) = stats.slot_lag() {
+                    println!("  slot lag: {slot_lag}");
+                }
+                println!("  delivered: {}", stats.delivered);
+                println!("  dropped: {} in {} gap(s)", stats.dropped, stats.gaps);
+                if stats.disconnected {
+                    println!("  disconnected");
+                }
+            }
+        }
         ("unload", Some(subcommand_matches)) => {
             let PluginUnloadArgs { name } =
                 PluginUnloadArgs::from_clap_arg_match(subcommand_matches)?;
@@ -129,6 +188,23 @@ pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<()> {
             })?;
             println!("Successfully reloaded plugin: {name}");
         }
+        ("restream", Some(subcommand_matches)) => {
+            let PluginRestreamArgs { name, owners } =
+                PluginRestreamArgs::from_clap_arg_match(subcommand_matches)?;
+
+            let admin_client = admin_rpc_service::connect(ledger_path);
+            let owners = owners.iter().map(Pubkey::to_string).collect();
+            let restream = admin_rpc_service::runtime().block_on(async {
+                admin_client
+                    .await?
+                    .restream_plugin_accounts(name.clone(), owners)
+                    .await
+            })?;
+            println!(
+                "Started re-stream {} of the accounts of slot {} to plugin: {name}",
+                restream.id, restream.slot
+            );
+        }
         _ => unreachable!(),
     }
 
@@ -210,4 +286,42 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn verify_args_struct_by_command_plugin_restream_with_name() {
+        verify_args_struct_by_command_is_error::<PluginRestreamArgs>(
+            command(),
+            vec![COMMAND, "restream", "testname"],
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_plugin_restream_with_invalid_owner() {
+        let app = command();
+        let matches = app.get_matches_from(vec![COMMAND, "restream", "testname", "notapubkey"]);
+        let subcommand_matches = matches.subcommand_matches("restream").unwrap();
+        assert!(PluginRestreamArgs::from_clap_arg_match(subcommand_matches).is_err());
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_plugin_restream_with_name_and_owners() {
+        let owners = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let app = command();
+        let matches = app.get_matches_from(vec![
+            COMMAND.to_string(),
+            "restream".to_string(),
+            "testname".to_string(),
+            owners[0].to_string(),
+            owners[1].to_string(),
+        ]);
+        let subcommand_matches = matches.subcommand_matches("restream").unwrap();
+        let args = PluginRestreamArgs::from_clap_arg_match(subcommand_matches).unwrap();
+        assert_eq!(
+            args,
+            PluginRestreamArgs {
+                name: "testname".to_string(),
+                owners: owners.to_vec(),
+            }
+        );
+    }
 }
@@ -0,0 +1,122 @@
+use {
+    crate::{
+        admin_rpc_service,
+        commands::{FromClapArgMatches, Result},
+    },
+    clap::{App, Arg, ArgMatches, SubCommand},
+    std::path::Path,
+};
+
+const COMMAND: &str = "rpc-slow-query-stats";
+
+#[derive(Debug, PartialEq)]
+pub struct RpcSlowQueryStatsArgs {
+    pub reset: bool,
+    pub json: bool,
+}
+
+impl FromClapArgMatches for RpcSlowQueryStatsArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self> {
+        Ok(RpcSlowQueryStatsArgs {
+            reset: matches.is_present("reset"),
+            json: matches.value_of("output") == Some("json"),
+        })
+    }
+}
+
+pub fn command<'a>() -> App<'a, 'a> {
+    SubCommand::with_name(COMMAND)
+        .about("Display per-method aggregates of the JSON RPC slow query log")
+        .arg(
+            Arg::with_name("reset")
+                .long("reset")
+                .takes_value(false)
+                .help("Clear the aggregates after displaying them"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["json"])
+                .help("Output display mode"),
+        )
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<()> {
+    let rpc_slow_query_stats_args = RpcSlowQueryStatsArgs::from_clap_arg_match(matches)?;
+
+    let admin_client = admin_rpc_service::connect(ledger_path);
+    let stats = admin_rpc_service::runtime().block_on(async move {
+        admin_client
+            .await?
+            .rpc_slow_query_stats(Some(rpc_slow_query_stats_args.reset))
+            .await
+    })?;
+
+    if rpc_slow_query_stats_args.json {
+        let stats =
+            serde_json::to_string_pretty(&stats).map_err(Box::<dyn std::error::Error>::from)?;
+        println!("{stats}");
+        return Ok(());
+    }
+
+    println!(
+        "{:<40} {:>8} {:>12} {:>12} {:>12} {:>12} {:>12} {:>14}",
+        "Method", "Count", "Avg ms", "Max ms", "Bank ms", "Scan ms", "Encode ms", "Avg bytes"
+    );
+    for stats in stats {
+        let count = stats.count.max(1);
+        println!(
+            "{:<40} {:>8} {:>12.1} {:>12.1} {:>12.1} {:>12.1} {:>12.1} {:>14}",
+            stats.method,
+            stats.count,
+            stats.total_latency_us as f64 / count as f64 / 1_000.0,
+            stats.max_latency_us as f64 / 1_000.0,
+            stats.total_bank_lookup_us as f64 / count as f64 / 1_000.0,
+            stats.total_accounts_scan_us as f64 / count as f64 / 1_000.0,
+            stats.total_encoding_us as f64 / count as f64 / 1_000.0,
+            stats.total_response_bytes / count,
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::commands::tests::{
+            verify_args_struct_by_command, verify_args_struct_by_command_is_error,
+        },
+    };
+
+    #[test]
+    fn verify_args_struct_by_command_rpc_slow_query_stats_default() {
+        verify_args_struct_by_command(
+            command(),
+            vec![COMMAND],
+            RpcSlowQueryStatsArgs {
+                reset: false,
+                json: false,
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_rpc_slow_query_stats_reset_json() {
+        verify_args_struct_by_command(
+            command(),
+            vec![COMMAND, "--reset", "--output", "json"],
+            RpcSlowQueryStatsArgs {
+                reset: true,
+                json: true,
+            },
+        );
+        verify_args_struct_by_command_is_error::<RpcSlowQueryStatsArgs>(
+            command(),
+            vec![COMMAND, "--output", "yaml"],
+        );
+    }
+}
@@ -20,7 +20,9 @@ use {
         keypair::SKIP_SEED_PHRASE_VALIDATION_ARG,
     },
     solana_core::{
+        banking_stage::transaction_scheduler::block_space_reservation::BlockSpaceReservation,
         banking_trace::DirByteLimit,
+        forwarding_stage::forwarding_policy::MAX_LEADER_FANOUT,
         validator::{BlockProductionMethod, BlockVerificationMethod},
     },
     solana_keypair::Keypair,
@@ -31,7 +33,7 @@ use {
     solana_send_transaction_service::send_transaction_service::Config as SendTransactionServiceConfig,
     solana_signer::Signer,
     solana_unified_scheduler_pool::DefaultSchedulerPool,
-    std::{collections::HashSet, net::SocketAddr, path::PathBuf},
+    std::{collections::HashSet, net::SocketAddr, num::NonZeroU64, path::PathBuf},
 };
 
 const EXCLUDE_KEY: &str = "account-index-exclude-key";
@@ -824,6 +826,39 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
                  otherwise use --bind-address]",
             ),
     )
+    .arg(
+        Arg::with_name("rpc_rate_limits")
+            .long("rpc-rate-limits")
+            .value_name("PATH")
+            .takes_value(true)
+            .help(
+                "Provide path to a yaml file with per-client JSON RPC rate limits. Clients are \
+                 identified by a configured API key or by their IP address, which is only taken \
+                 from the client IP header on connections from a configured trusted proxy. Each \
+                 method call is charged against a token bucket according to its configured cost. \
+                 The limits can be reloaded with `agave-validator set-rpc-rate-limits`.",
+            ),
+    )
+    .arg(
+        Arg::with_name("rpc_slow_query_log")
+            .long("rpc-slow-query-log")
+            .value_name("PATH")
+            .takes_value(true)
+            .help(
+                "Append JSON RPC calls slower than --rpc-slow-query-threshold-ms to this file, \
+                 along with their parameters, bank slot, latency breakdown and response size. The \
+                 file is rotated once it grows past 100 MiB.",
+            ),
+    )
+    .arg(
+        Arg::with_name("rpc_slow_query_threshold_ms")
+            .long("rpc-slow-query-threshold-ms")
+            .value_name("MILLISECONDS")
+            .takes_value(true)
+            .requires("rpc_slow_query_log")
+            .validator(is_parsable::<u64>)
+            .help("Minimum latency of a JSON RPC call to be slow query logged [default: 1000]"),
+    )
     .arg(
         Arg::with_name("geyser_plugin_config")
             .long("geyser-plugin-config")
@@ -1185,6 +1220,33 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
                  method",
             ),
     )
+    .arg(
+        Arg::with_name("block_production_write_lock_pricing_threshold_percent")
+            .long("block-production-write-lock-pricing-threshold-percent")
+            .value_name("PERCENT")
+            .takes_value(true)
+            .validator(|s| is_within_range(s, 0..100))
+            .help(
+                "Price write locks on accounts whose cost exceeds this percentage of the \
+                 per-account limit. Transactions write-locking such accounts are scheduled at a \
+                 priority discounted by the account's remaining capacity. Disabled if not set",
+            ),
+    )
+    .arg(
+        Arg::with_name("block_production_block_space_reservation")
+            .long("block-production-block-space-reservation")
+            .value_name("PUBKEY:PERCENT")
+            .takes_value(true)
+            .multiple(true)
+            .validator(is_parsable::<BlockSpaceReservation>)
+            .help(
+                "Reserve this percentage of the block CU limit of every leader slot for \
+                 transactions received from the peer with this QUIC identity. They are scheduled \
+                 ahead of other transactions until the reservation is used up. Reserved block \
+                 space left unused is not given to other transactions. May be specified multiple \
+                 times",
+            ),
+    )
     .arg(
         Arg::with_name("filter_keys")
             .long("filter-keys")
@@ -1198,6 +1260,34 @@ pub fn add_args<'a>(app: App<'a, 'a>, default_args: &'a DefaultArgs) -> App<'a,
                  performance. External schedulers must implement this filtering themselves",
             ),
     )
+    .arg(
+        Arg::with_name("transaction_forwarding_leader_fanout")
+            .long("transaction-forwarding-leader-fanout")
+            .value_name("COUNT")
+            .takes_value(true)
+            .validator(|s| is_within_range(s, 1..=MAX_LEADER_FANOUT))
+            .help("Number of upcoming leaders forwarded transactions are sent to [default: 1]"),
+    )
+    .arg(
+        Arg::with_name("transaction_forwarding_skip_empty_block_leaders")
+            .long("transaction-forwarding-skip-empty-block-leaders")
+            .takes_value(false)
+            .help(
+                "Do not forward transactions to leaders whose most recent block had no non-vote \
+                 transactions",
+            ),
+    )
+    .arg(
+        Arg::with_name("transaction_forwarding_max_bytes_per_sender")
+            .long("transaction-forwarding-max-bytes-per-sender")
+            .value_name("BYTES")
+            .takes_value(true)
+            .validator(is_parsable::<NonZeroU64>)
+            .help(
+                "Maximum number of bytes of transactions forwarded per second for a single fee \
+                 payer. Unlimited if not set",
+            ),
+    )
     .arg(
         Arg::with_name("enable_scheduler_bindings")
             .long("enable-scheduler-bindings")
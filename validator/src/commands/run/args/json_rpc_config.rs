@@ -3,7 +3,9 @@ use {
     clap::{Arg, ArgMatches, value_t},
     solana_accounts_db::accounts_index::AccountSecondaryIndexes,
     solana_clap_utils::input_validators::is_parsable,
-    solana_rpc::rpc::{JsonRpcConfig, RpcBigtableConfig},
+    solana_rpc::rpc::{
+        DEFAULT_RPC_ACCOUNTS_DIFF_RETENTION_SLOTS, JsonRpcConfig, RpcBigtableConfig,
+    },
     std::sync::LazyLock,
 };
 
@@ -18,6 +20,12 @@ static DEFAULT_RPC_BLOCKING_THREADS: LazyLock<String> =
 const DEFAULT_RPC_NICENESS_ADJ: &str = "0";
 static DEFAULT_RPC_MAX_REQUEST_BODY_SIZE: LazyLock<String> =
     LazyLock::new(|| solana_rpc::rpc::MAX_REQUEST_BODY_SIZE.to_string());
+static RPC_ACCOUNTS_DIFF_RETENTION_SLOTS_HELP: LazyLock<String> = LazyLock::new(|| {
+    format!(
+        "Number of recent slots for which transaction accounts diffs are kept [default: \
+         {DEFAULT_RPC_ACCOUNTS_DIFF_RETENTION_SLOTS}]"
+    )
+});
 const MB: usize = 1_024 * 1_024;
 
 impl FromClapArgMatches for JsonRpcConfig {
@@ -63,6 +71,14 @@ impl FromClapArgMatches for JsonRpcConfig {
             .ok()
             .map(|mb| mb * MB),
             disable_health_check: false,
+            accounts_diff_retention_slots: if matches.is_present("enable_rpc_accounts_diff") {
+                Some(
+                    value_t!(matches, "rpc_accounts_diff_retention_slots", u64)
+                        .unwrap_or(DEFAULT_RPC_ACCOUNTS_DIFF_RETENTION_SLOTS),
+                )
+            } else {
+                None
+            },
         })
     }
 }
@@ -92,6 +108,22 @@ pub(crate) fn args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
                 "Include CPI inner instructions, logs, and return data in the historical \
                  transaction info stored",
             ),
+        Arg::with_name("enable_rpc_accounts_diff")
+            .long("enable-rpc-accounts-diff")
+            .requires("enable_rpc_transaction_history")
+            .takes_value(false)
+            .help(
+                "Record the post-execution state of the accounts written by each transaction, \
+                 served by 'getBlock' with the 'accountsDiff' transaction details level. This \
+                 will cause a significant increase in disk usage and IOPS",
+            ),
+        Arg::with_name("rpc_accounts_diff_retention_slots")
+            .long("rpc-accounts-diff-retention-slots")
+            .value_name("SLOTS")
+            .takes_value(true)
+            .requires("enable_rpc_accounts_diff")
+            .validator(is_parsable::<u64>)
+            .help(&RPC_ACCOUNTS_DIFF_RETENTION_SLOTS_HELP),
         Arg::with_name("rpc_faucet_addr")
             .long("rpc-faucet-address")
             .value_name("HOST:PORT")
@@ -259,6 +291,51 @@ pub(super) mod tests {
         }
     }
 
+    #[test]
+    fn verify_args_struct_by_command_run_with_enable_rpc_accounts_diff() {
+        {
+            let default_run_args = crate::commands::run::args::RunArgs::default();
+            let expected_args = RunArgs {
+                json_rpc_config: JsonRpcConfig {
+                    enable_rpc_transaction_history: true,
+                    accounts_diff_retention_slots: Some(DEFAULT_RPC_ACCOUNTS_DIFF_RETENTION_SLOTS),
+                    ..default_run_args.json_rpc_config.clone()
+                },
+                ..default_run_args.clone()
+            };
+            verify_args_struct_by_command_run_with_identity_setup(
+                default_run_args,
+                vec![
+                    "--enable-rpc-transaction-history", // required by enable_rpc_accounts_diff
+                    "--enable-rpc-accounts-diff",
+                ],
+                expected_args,
+            );
+        }
+
+        {
+            let default_run_args = crate::commands::run::args::RunArgs::default();
+            let expected_args = RunArgs {
+                json_rpc_config: JsonRpcConfig {
+                    enable_rpc_transaction_history: true,
+                    accounts_diff_retention_slots: Some(500),
+                    ..default_run_args.json_rpc_config.clone()
+                },
+                ..default_run_args.clone()
+            };
+            verify_args_struct_by_command_run_with_identity_setup(
+                default_run_args,
+                vec![
+                    "--enable-rpc-transaction-history",
+                    "--enable-rpc-accounts-diff",
+                    "--rpc-accounts-diff-retention-slots",
+                    "500",
+                ],
+                expected_args,
+            );
+        }
+    }
+
     #[test]
     fn verify_args_struct_by_command_run_with_rpc_faucet_addr() {
         {
@@ -5,12 +5,15 @@ use {
     clap::{Arg, ArgMatches, value_t},
     solana_clap_utils::input_validators::is_parsable,
     solana_rayon_threadlimit::get_thread_count,
-    solana_rpc::rpc_pubsub_service::{
-        DEFAULT_MAX_ACTIVE_SUBSCRIPTIONS, DEFAULT_QUEUE_CAPACITY_BYTES,
-        DEFAULT_QUEUE_CAPACITY_ITEMS, DEFAULT_TEST_QUEUE_CAPACITY_BYTES,
-        DEFAULT_TEST_QUEUE_CAPACITY_ITEMS, PubSubConfig,
+    solana_rpc::{
+        rpc_grpc_service::DEFAULT_GRPC_STREAM_QUEUE_CAPACITY,
+        rpc_pubsub_service::{
+            DEFAULT_MAX_ACTIVE_SUBSCRIPTIONS, DEFAULT_QUEUE_CAPACITY_BYTES,
+            DEFAULT_QUEUE_CAPACITY_ITEMS, DEFAULT_TEST_QUEUE_CAPACITY_BYTES,
+            DEFAULT_TEST_QUEUE_CAPACITY_ITEMS, PubSubConfig,
+        },
     },
-    std::{num::NonZeroUsize, sync::LazyLock},
+    std::{net::SocketAddr, num::NonZeroUsize, sync::LazyLock},
 };
 
 static DEFAULT_RPC_PUBSUB_MAX_ACTIVE_SUBSCRIPTIONS: LazyLock<String> =
@@ -30,6 +33,9 @@ const DEFAULT_RPC_PUBSUB_WORKER_THREADS: &str = "4";
 static DEFAULT_TEST_RPC_PUBSUB_WORKER_THREADS: LazyLock<String> =
     LazyLock::new(|| PubSubConfig::default_for_tests().worker_threads.to_string());
 
+static DEFAULT_RPC_GRPC_STREAM_QUEUE_CAPACITY: LazyLock<String> =
+    LazyLock::new(|| DEFAULT_GRPC_STREAM_QUEUE_CAPACITY.to_string());
+
 #[cfg_attr(test, qualifiers(pub(crate)))]
 static DEFAULT_RPC_PUBSUB_NUM_NOTIFICATION_THREADS: LazyLock<String> =
     LazyLock::new(|| get_thread_count().to_string());
@@ -116,6 +122,23 @@ pub(crate) fn args<'a, 'b>(test_validator: bool) -> Vec<Arg<'a, 'b>> {
             .default_value(DEFAULT_RPC_PUBSUB_WORKER_THREADS)
             .help("PubSub worker threads"),
         rpc_pubsub_notification_threads,
+        Arg::with_name("rpc_grpc_address")
+            .long("rpc-grpc-address")
+            .takes_value(true)
+            .value_name("HOST:PORT")
+            .validator(is_parsable::<SocketAddr>)
+            .requires("full_rpc_api")
+            .help(
+                "Enable the unstable gRPC streaming service on this address. It serves slot, \
+                 account, program, logs and block streams backed by the RPC PubSub subscriptions",
+            ),
+        Arg::with_name("rpc_grpc_stream_queue_capacity")
+            .long("rpc-grpc-stream-queue-capacity")
+            .takes_value(true)
+            .value_name("NUMBER")
+            .validator(is_parsable::<NonZeroUsize>)
+            .default_value(&DEFAULT_RPC_GRPC_STREAM_QUEUE_CAPACITY)
+            .help("The maximum number of notifications buffered for each gRPC stream"),
     ]
 }
 
@@ -135,6 +158,8 @@ impl FromClapArgMatches for PubSubConfig {
             notification_threads: value_t!(matches, "rpc_pubsub_notification_threads", usize)
                 .ok()
                 .and_then(NonZeroUsize::new),
+            grpc_addr: value_t!(matches, "rpc_grpc_address", SocketAddr).ok(),
+            grpc_stream_queue_capacity: value_t!(matches, "rpc_grpc_stream_queue_capacity", usize)?,
         })
     }
 }
@@ -283,6 +308,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn verify_args_struct_by_command_run_with_grpc_address() {
+        let default_run_args = crate::commands::run::args::RunArgs::default();
+        let expected_args = RunArgs {
+            json_rpc_config: JsonRpcConfig {
+                full_api: true,
+                ..default_run_args.json_rpc_config.clone()
+            },
+            pub_sub_config: PubSubConfig {
+                grpc_addr: Some("127.0.0.1:10000".parse().unwrap()),
+                grpc_stream_queue_capacity: 9999,
+                ..default_run_args.pub_sub_config.clone()
+            },
+            ..default_run_args.clone()
+        };
+        verify_args_struct_by_command_run_with_identity_setup(
+            default_run_args,
+            vec![
+                "--full-rpc-api", // required by --rpc-grpc-address
+                "--rpc-grpc-address",
+                "127.0.0.1:10000",
+                "--rpc-grpc-stream-queue-capacity",
+                "9999",
+            ],
+            expected_args,
+        );
+    }
+
     #[test]
     fn test_default_rpc_pubsub_max_active_subscriptions_unchanged() {
         assert_eq!(
@@ -1,6 +1,8 @@
 use {
     crate::{
-        admin_rpc_service::{self, StakedNodesOverrides, load_staked_nodes_overrides},
+        admin_rpc_service::{
+            self, StakedNodesOverrides, load_rpc_rate_limits, load_staked_nodes_overrides,
+        },
         bootstrap,
         cli::{self},
         commands::{FromClapArgMatches, run::args::RunArgs},
@@ -32,9 +34,13 @@ use {
     solana_clap_utils::input_parsers::{keypair_of, keypairs_of, pubkey_of, value_of, values_of},
     solana_clock::{DEFAULT_SLOTS_PER_EPOCH, Slot},
     solana_core::{
-        banking_stage::transaction_scheduler::scheduler_controller::SchedulerConfig,
+        banking_stage::transaction_scheduler::{
+            block_space_reservation::BlockSpaceReservation, scheduler_controller::SchedulerConfig,
+            write_lock_pricing::WriteLockPricingConfig,
+        },
         banking_trace::DISABLED_BAKING_TRACE_DIR,
         consensus::tower_storage,
+        forwarding_stage::forwarding_policy::ForwardingPolicyConfig,
         repair::repair_handler::RepairHandlerType,
         resource_limits,
         snapshot_packager_service::SnapshotPackagerService,
@@ -62,6 +68,10 @@ use {
     solana_net_utils::multihomed_sockets::BindIpAddrs,
     solana_poh::poh_service,
     solana_pubkey::Pubkey,
+    solana_rpc::{
+        rpc_rate_limiter::RpcRateLimiter,
+        rpc_slow_query_log::{RpcSlowQueryLog, RpcSlowQueryLogConfig},
+    },
     solana_runtime::{runtime_config::RuntimeConfig, snapshot_utils},
     solana_signer::Signer,
     solana_streamer::{
@@ -80,6 +90,7 @@ use {
         path::{Path, PathBuf},
         str::{self, FromStr},
         sync::{Arc, RwLock, atomic::AtomicBool},
+        time::Duration,
     },
 };
 #[cfg(target_os = "linux")]
@@ -444,6 +455,30 @@ pub fn execute(
         .staked_map_id,
     ));
 
+    let rpc_rate_limiter = Arc::new(RpcRateLimiter::new(
+        matches.value_of("rpc_rate_limits").map(|path| {
+            load_rpc_rate_limits(path).unwrap_or_else(|err| {
+                error!("Failed to load rpc-rate-limits from {path}: {err}");
+                clap::Error::with_description(
+                    "Failed to load configuration of rpc-rate-limits argument",
+                    clap::ErrorKind::InvalidValue,
+                )
+                .exit()
+            })
+        }),
+    ));
+
+    let rpc_slow_query_log = matches.value_of("rpc_slow_query_log").map(|path| {
+        let mut config = RpcSlowQueryLogConfig::new(PathBuf::from(path));
+        if let Ok(threshold_ms) = value_t!(matches, "rpc_slow_query_threshold_ms", u64) {
+            config.threshold = Duration::from_millis(threshold_ms);
+        }
+        RpcSlowQueryLog::new(config)
+            .map(Arc::new)
+            .map_err(|err| format!("Failed to open rpc slow query log {path}: {err}"))
+    });
+    let rpc_slow_query_log = rpc_slow_query_log.transpose()?;
+
     let init_complete_file = matches.value_of("init_complete_file");
 
     let private_rpc = matches.is_present("private_rpc");
@@ -548,6 +583,23 @@ pub fn execute(
         ))?;
     }
 
+    let block_space_reservations = values_t!(
+        matches,
+        "block_production_block_space_reservation",
+        BlockSpaceReservation
+    )
+    .unwrap_or_default();
+    let reserved_share_percent: u32 = block_space_reservations
+        .iter()
+        .map(|reservation| u32::from(reservation.share_percent))
+        .sum();
+    if reserved_share_percent > 100 {
+        Err(format!(
+            "block space reservations add up to {reserved_share_percent}% of the block, they must \
+             not exceed 100%"
+        ))?;
+    }
+
     let shrink_ratio = if accounts_shrink_optimize_total_space {
         AccountShrinkThreshold::TotalSpace { shrink_ratio }
     } else {
@@ -832,6 +884,8 @@ pub fn execute(
             ..RuntimeConfig::default()
         },
         staked_nodes_overrides: staked_nodes_overrides.clone(),
+        rpc_rate_limiter: rpc_rate_limiter.clone(),
+        rpc_slow_query_log: rpc_slow_query_log.clone(),
         use_snapshot_archives_at_startup,
         ip_echo_server_threads,
         rayon_global_threads,
@@ -866,8 +920,34 @@ pub fn execute(
                 "block_production_pacing_fill_time_millis",
                 SchedulerPacing
             ),
+            write_lock_pricing: value_t!(
+                matches,
+                "block_production_write_lock_pricing_threshold_percent",
+                u8
+            )
+            .ok()
+            .map(|utilization_threshold_percent| WriteLockPricingConfig {
+                utilization_threshold_percent,
+            }),
+            block_space_reservations,
         },
         enable_block_production_forwarding: staked_nodes_overrides_path.is_some(),
+        forwarding_policy: ForwardingPolicyConfig {
+            leader_fanout: value_t!(
+                matches,
+                "transaction_forwarding_leader_fanout",
+                NonZeroUsize
+            )
+            .unwrap_or(NonZeroUsize::MIN),
+            skip_empty_block_leaders: matches
+                .is_present("transaction_forwarding_skip_empty_block_leaders"),
+            max_bytes_per_sender: value_t!(
+                matches,
+                "transaction_forwarding_max_bytes_per_sender",
+                NonZeroU64
+            )
+            .ok(),
+        },
         enable_scheduler_bindings: matches.is_present("enable_scheduler_bindings"),
         banking_trace_dir_byte_limit: parse_banking_trace_dir_byte_limit(matches),
         validator_exit: Arc::new(RwLock::new(Exit::default())),
@@ -944,6 +1024,8 @@ pub fn execute(
             tower_storage: validator_config.tower_storage.clone(),
             vote_history_storage: validator_config.vote_history_storage.clone(),
             staked_nodes_overrides,
+            rpc_rate_limiter,
+            rpc_slow_query_log,
             rpc_to_plugin_manager_sender,
         },
     );
@@ -0,0 +1,101 @@
+use {
+    crate::{
+        admin_rpc_service,
+        commands::{FromClapArgMatches, Result},
+    },
+    clap::{App, Arg, ArgGroup, ArgMatches, SubCommand},
+    std::path::Path,
+};
+
+const COMMAND: &str = "set-rpc-rate-limits";
+
+#[derive(Debug, PartialEq)]
+pub struct SetRpcRateLimitsArgs {
+    pub path: Option<String>,
+}
+
+impl FromClapArgMatches for SetRpcRateLimitsArgs {
+    fn from_clap_arg_match(matches: &ArgMatches) -> Result<Self> {
+        Ok(SetRpcRateLimitsArgs {
+            path: matches.value_of("path").map(str::to_string),
+        })
+    }
+}
+
+pub fn command<'a>() -> App<'a, 'a> {
+    SubCommand::with_name(COMMAND)
+        .about("Replace the JSON RPC rate limits of the running validator")
+        .arg(
+            Arg::with_name("path")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("Provide path to a yaml file with the new RPC rate limits."),
+        )
+        .arg(
+            Arg::with_name("disable")
+                .long("disable")
+                .takes_value(false)
+                .help("Disable RPC rate limiting"),
+        )
+        .group(
+            ArgGroup::with_name("rate_limits")
+                .args(&["path", "disable"])
+                .required(true),
+        )
+        .after_help(
+            "Note: all clients start over with a full quota once the new limits are applied",
+        )
+}
+
+pub fn execute(matches: &ArgMatches, ledger_path: &Path) -> Result<()> {
+    let set_rpc_rate_limits_args = SetRpcRateLimitsArgs::from_clap_arg_match(matches)?;
+
+    let admin_client = admin_rpc_service::connect(ledger_path);
+    admin_rpc_service::runtime().block_on(async move {
+        admin_client
+            .await?
+            .set_rpc_rate_limits(set_rpc_rate_limits_args.path)
+            .await
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::commands::tests::{
+            verify_args_struct_by_command, verify_args_struct_by_command_is_error,
+        },
+    };
+
+    #[test]
+    fn verify_args_struct_by_command_set_rpc_rate_limits_default() {
+        verify_args_struct_by_command_is_error::<SetRpcRateLimitsArgs>(command(), vec![COMMAND]);
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_set_rpc_rate_limits_path() {
+        verify_args_struct_by_command(
+            command(),
+            vec![COMMAND, "limits.yml"],
+            SetRpcRateLimitsArgs {
+                path: Some("limits.yml".to_string()),
+            },
+        );
+    }
+
+    #[test]
+    fn verify_args_struct_by_command_set_rpc_rate_limits_disable() {
+        verify_args_struct_by_command(
+            command(),
+            vec![COMMAND, "--disable"],
+            SetRpcRateLimitsArgs { path: None },
+        );
+        verify_args_struct_by_command_is_error::<SetRpcRateLimitsArgs>(
+            command(),
+            vec![COMMAND, "limits.yml", "--disable"],
+        );
+    }
+}
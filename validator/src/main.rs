@@ -111,6 +111,12 @@ pub fn main() {
         ("set-log-filter", Some(subcommand_matches)) => {
             commands::set_log_filter::execute(subcommand_matches, &ledger_path)
         }
+        ("rpc-slow-query-stats", Some(subcommand_matches)) => {
+            commands::rpc_slow_query_stats::execute(subcommand_matches, &ledger_path)
+        }
+        ("set-rpc-rate-limits", Some(subcommand_matches)) => {
+            commands::set_rpc_rate_limits::execute(subcommand_matches, &ledger_path)
+        }
         ("wait-for-restart-window", Some(subcommand_matches)) => {
             commands::wait_for_restart_window::execute(subcommand_matches, &ledger_path)
         }
@@ -126,6 +132,9 @@ pub fn main() {
         ("manage-block-production", Some(subcommand_matches)) => {
             commands::manage_block_production::execute(subcommand_matches, &ledger_path)
         }
+        ("block-verification", Some(subcommand_matches)) => {
+            commands::block_verification::execute(subcommand_matches, &ledger_path)
+        }
         ("blockstore", Some(subcommand_matches)) => {
             commands::blockstore::execute(subcommand_matches, &ledger_path)
         }
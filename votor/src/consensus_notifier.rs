@@ -0,0 +1,44 @@
+//! Lightweight channel wiring that lets a consumer (typically the Geyser
+//! plugin manager) observe the votes and certificates entering the consensus
+//! pool, and the blocks they finalize.
+//!
+//! The channel is optional: when no consumer is attached, the consensus pool
+//! does no work for these notifications. When a consumer is attached, each
+//! event is pushed through a bounded channel via `try_send` and dropped if the
+//! channel is full, so a slow consumer never delays consensus.
+
+use {
+    crate::common::Stake,
+    agave_votor_messages::{
+        certificate::Certificate,
+        consensus_message::{Block, VoteMessage},
+    },
+    solana_pubkey::Pubkey,
+    std::sync::Arc,
+};
+
+#[derive(Clone, Debug)]
+pub enum ConsensusEvent {
+    /// A verified vote newly added to the pool
+    Vote {
+        vote: VoteMessage,
+        vote_account: Pubkey,
+        stake: Stake,
+        total_stake: Stake,
+    },
+    /// A certificate newly added to the pool, received or built by the pool
+    /// itself (`is_generated`)
+    Certificate {
+        certificate: Arc<Certificate>,
+        is_generated: bool,
+    },
+    /// The pool has the certificates finalizing `block`
+    Finalized { block: Block, is_fast: bool },
+}
+
+/// Sender half of the consensus event channel. Owned by the consensus pool
+/// when a consumer is attached.
+pub type ConsensusEventSender = crossbeam_channel::Sender<ConsensusEvent>;
+
+/// Receiver half of the consensus event channel. Owned by the consumer.
+pub type ConsensusEventReceiver = crossbeam_channel::Receiver<ConsensusEvent>;
@@ -5,6 +5,7 @@ use {
             MAX_ENTRIES_PER_PUBKEY_FOR_NOTARIZE_LITE, MAX_ENTRIES_PER_PUBKEY_FOR_OTHER_TYPES,
             Stake, conflicting_types, vote_to_cert_types,
         },
+        consensus_notifier::{ConsensusEvent, ConsensusEventSender},
         consensus_pool::{
             parent_ready_tracker::{ParentReady, ParentReadyTracker},
             slot_stake_counters::SlotStakeCounters,
@@ -111,6 +112,9 @@ pub(crate) struct ConsensusPool {
     pending_safe_to_notar: Vec<Block>,
     /// The slot at which the state was last pruned.
     last_pruned_slot: Slot,
+    /// Receives the votes, certificates and finalized blocks added to the
+    /// pool, when a consumer is attached
+    consensus_event_sender: Option<ConsensusEventSender>,
 }
 
 impl ConsensusPool {
@@ -120,6 +124,7 @@ impl ConsensusPool {
         generated_cert_types: Arc<GeneratedCertTypes>,
         migration_status: Arc<MigrationStatus>,
         initial_parent_ready: ParentReady,
+        consensus_event_sender: Option<ConsensusEventSender>,
     ) -> Self {
         let parent_ready_tracker =
             ParentReadyTracker::new(cluster_info.clone(), root.slot(), initial_parent_ready);
@@ -136,9 +141,27 @@ impl ConsensusPool {
             generated_cert_types,
             pending_safe_to_notar: vec![],
             last_pruned_slot: 0,
+            consensus_event_sender,
         }
     }
 
+    /// Sends the event built by `event` to the attached consumer, if any.
+    /// Events are dropped rather than waiting for a slow consumer.
+    fn notify(&mut self, event: impl FnOnce() -> ConsensusEvent) {
+        let Some(sender) = &self.consensus_event_sender else {
+            return;
+        };
+        if sender.try_send(event()).is_err() {
+            self.stats.consensus_events_dropped =
+                self.stats.consensus_events_dropped.saturating_add(1);
+        }
+    }
+
+    fn push_finalized(&mut self, block: Block, is_fast: bool, events: &mut Vec<VotorEvent>) {
+        events.push(VotorEvent::Finalized(block, is_fast));
+        self.notify(|| ConsensusEvent::Finalized { block, is_fast });
+    }
+
     fn new_vote_pool(vote_type: VoteType) -> VotePool {
         match vote_type {
             VoteType::NotarizeFallback => VotePool::DuplicateBlockVotePool(
@@ -231,6 +254,10 @@ impl ConsensusPool {
                 }
             });
             let new_cert = Arc::new(cert_builder.build()?);
+            self.notify(|| ConsensusEvent::Certificate {
+                certificate: new_cert.clone(),
+                is_generated: true,
+            });
             self.insert_certificate(root_bank, cert_type, new_cert.clone(), events);
             self.generated_cert_types.insert_cert(cert_type);
             self.stats.incr_generated_cert(&new_cert.cert_type);
@@ -298,17 +325,17 @@ impl ConsensusPool {
                 self.parent_ready_tracker
                     .add_new_notar_fallback_or_stronger(block, events);
 
-                if let Some(finalize_cert) = self.get_finalize_cert(block.slot) {
+                if let Some(finalize_cert) = self.get_finalize_cert(block.slot).cloned() {
                     // It's fine to set FastFinalization to false here, because
                     // we will report correctly as long as we have FastFinalization cert.
-                    events.push(VotorEvent::Finalized(block, false));
+                    self.push_finalized(block, false, events);
                     if self
                         .highest_finalized_slot()
                         .is_none_or(|s| s < FinalizedSlot::Slow(block.slot))
                     {
                         self.highest_finalized_slot_cert =
                             Some(ValidatedBlockFinalizationCert::from_validated_slow(
-                                Arc::unwrap_or_clone(finalize_cert.clone()),
+                                Arc::unwrap_or_clone(finalize_cert),
                                 Arc::unwrap_or_clone(cert),
                                 root_bank,
                             ));
@@ -318,7 +345,7 @@ impl ConsensusPool {
             CertificateType::Finalize(slot) => {
                 if let Some(notarize_cert) = self.get_notarize_cert(slot) {
                     let block = notarize_cert.cert_type.to_block().unwrap();
-                    events.push(VotorEvent::Finalized(block, false));
+                    self.push_finalized(block, false, events);
                     if self
                         .highest_finalized_slot()
                         .is_none_or(|s| s < FinalizedSlot::Slow(slot))
@@ -333,7 +360,7 @@ impl ConsensusPool {
                 }
             }
             CertificateType::FinalizeFast(block) => {
-                events.push(VotorEvent::Finalized(block, true));
+                self.push_finalized(block, true, events);
                 self.parent_ready_tracker
                     .add_new_notar_fallback_or_stronger(block, events);
                 if self
@@ -429,6 +456,10 @@ impl ConsensusPool {
                 validator_vote_key,
             ));
         }
+        let notification = self
+            .consensus_event_sender
+            .is_some()
+            .then(|| vote_message.clone());
         match self.update_vote_pool(vote_message, validator_vote_key, validator_stake) {
             None => {
                 // No new vote pool entry was created, just return empty vec
@@ -436,6 +467,14 @@ impl ConsensusPool {
                 return Ok(vec![]);
             }
             Some(entry_stake) => {
+                if let Some(vote) = notification {
+                    self.notify(|| ConsensusEvent::Vote {
+                        vote,
+                        vote_account: validator_vote_key,
+                        stake: validator_stake.get(),
+                        total_stake: total_stake.get(),
+                    });
+                }
                 let fallback_vote_counters = self
                     .slot_stake_counters_map
                     .entry(vote_slot)
@@ -472,6 +511,10 @@ impl ConsensusPool {
             return Ok(vec![]);
         }
         let cert = Arc::new(cert);
+        self.notify(|| ConsensusEvent::Certificate {
+            certificate: cert.clone(),
+            is_generated: false,
+        });
         self.insert_certificate(root_bank, cert_type, cert.clone(), events);
         self.stats.incr_ingested_cert(&cert_type);
 
@@ -704,6 +747,12 @@ mod tests {
 
     impl TestContext {
         fn new() -> Self {
+            Self::new_with_consensus_event_sender(None)
+        }
+
+        fn new_with_consensus_event_sender(
+            consensus_event_sender: Option<ConsensusEventSender>,
+        ) -> Self {
             let num_validators = 10;
             let validator_keypairs = (0..num_validators)
                 .map(|_| ValidatorVoteKeypairs::new_rand())
@@ -725,6 +774,7 @@ mod tests {
                     generated_cert_types.clone(),
                     Arc::new(MigrationStatus::post_migration_status()),
                     initial_parent_ready,
+                    consensus_event_sender,
                 ),
                 bank_forks,
                 generated_cert_types,
@@ -1245,6 +1295,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_consensus_events() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let mut ctx = TestContext::new_with_consensus_event_sender(Some(sender));
+        let block = Block {
+            slot: 5,
+            block_id: Hash::new_unique(),
+        };
+        let vote = Vote::new_notarization_vote(block);
+
+        // 7 of the 10 equally staked validators notarize the block
+        ctx.add_certificate(vote);
+        // Votes already in the pool are not notified again
+        ctx.add_message(dummy_vote_message(
+            &ctx.validators,
+            ctx.pool.cluster_info.my_shred_version(),
+            &vote,
+            0,
+        ));
+        let events = receiver.try_iter().collect::<Vec<_>>();
+        let votes = events
+            .iter()
+            .filter_map(|event| match event {
+                ConsensusEvent::Vote {
+                    vote,
+                    stake,
+                    total_stake,
+                    ..
+                } => Some((vote.vote, vote.rank, *stake, *total_stake)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            votes,
+            (0..7)
+                .map(|rank| (vote, rank, 100, 1000))
+                .collect::<Vec<_>>()
+        );
+        assert!(events.iter().any(|event| matches!(
+            event,
+            ConsensusEvent::Certificate { certificate, is_generated: true }
+                if certificate.cert_type == CertificateType::Notarize(block)
+        )));
+
+        // A received finalize certificate finalizes the notarized block
+        ctx.add_message(ConsensusMessage::Certificate(Certificate {
+            cert_type: CertificateType::Finalize(5),
+            signature: BLSSignature([0; BLS_SIGNATURE_AFFINE_SIZE]),
+            bitmap: dummy_bitmap(),
+        }));
+        let events = receiver.try_iter().collect::<Vec<_>>();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[0],
+            ConsensusEvent::Certificate { certificate, is_generated: false }
+                if certificate.cert_type == CertificateType::Finalize(5)
+        ));
+        assert!(matches!(
+            events[1],
+            ConsensusEvent::Finalized { block: finalized_block, is_fast: false }
+                if finalized_block == block
+        ));
+    }
+
     #[test]
     fn test_add_vote_zero_stake() {
         let mut ctx = TestContext::new();
@@ -101,6 +101,7 @@ impl VoteStats {
 
 pub(super) struct ConsensusPoolStats {
     pub(super) conflicting_votes: u32,
+    pub(super) consensus_events_dropped: u32,
     pub(super) event_safe_to_notarize: u32,
     pub(super) event_safe_to_skip: u32,
     pub(super) exist_certs: u32,
@@ -121,6 +122,7 @@ impl Default for ConsensusPoolStats {
     fn default() -> Self {
         Self {
             conflicting_votes: 0,
+            consensus_events_dropped: 0,
             event_safe_to_notarize: 0,
             event_safe_to_skip: 0,
             exist_certs: 0,
@@ -156,6 +158,7 @@ impl ConsensusPoolStats {
     fn report(&self) {
         let Self {
             conflicting_votes,
+            consensus_events_dropped,
             event_safe_to_notarize,
             event_safe_to_skip,
             exist_certs,
@@ -172,6 +175,11 @@ impl ConsensusPoolStats {
         datapoint_info!(
             "consensus_pool",
             ("conflicting_votes", *conflicting_votes as i64, i64),
+            (
+                "consensus_events_dropped",
+                *consensus_events_dropped as i64,
+                i64
+            ),
             ("event_safe_to_skip", *event_safe_to_skip as i64, i64),
             (
                 "event_safe_to_notarize",
@@ -10,6 +10,7 @@ mod stats;
 use {
     crate::{
         common::DELTA_STANDSTILL,
+        consensus_notifier::ConsensusEventSender,
         consensus_pool::{
             AddVoteError, ConsensusPool,
             parent_ready_tracker::{BlockProductionParent, ParentReady},
@@ -69,6 +70,9 @@ pub(crate) struct ConsensusPoolContext {
 
     /// Used to communicate the highest finalization cert the pool has observed to the block creation loop.
     pub(crate) highest_finalized: Arc<RwLock<Option<ValidatedBlockFinalizationCert>>>,
+    /// Receives the votes and certificates entering the pool, see
+    /// [`crate::consensus_notifier`]
+    pub(crate) consensus_event_sender: Option<ConsensusEventSender>,
 }
 
 pub(crate) struct ConsensusPoolService {
@@ -307,6 +311,7 @@ impl ConsensusPoolService {
             ctx.generated_cert_types.clone(),
             ctx.migration_status.clone(),
             initial_parent_ready,
+            ctx.consensus_event_sender.clone(),
         );
 
         info!("{}: Certificate pool loop starting", ctx.cluster_info.id());
@@ -749,6 +754,7 @@ mod tests {
                 generated_cert_types.clone(),
                 migration_status.clone(),
                 initial_parent_ready,
+                None,
             );
             let my_vote_pubkey = Pubkey::new_unique();
             let (consensus_message_sender, consensus_message_receiver) = unbounded();
@@ -772,6 +778,7 @@ mod tests {
                 event_sender,
                 repair_event_sender,
                 highest_finalized: Arc::new(RwLock::new(None)),
+                consensus_event_sender: None,
             };
 
             TestContext {
@@ -69,7 +69,7 @@ pub(crate) struct EventHandlerContext {
 }
 
 #[derive(Debug, Error)]
-enum EventLoopError {
+pub(crate) enum EventLoopError {
     #[error("Receiver is disconnected")]
     ReceiverDisconnected(#[from] RecvError),
 
@@ -87,7 +87,7 @@ pub(crate) struct EventHandler {
     t_event_handler: JoinHandle<()>,
 }
 
-struct LocalContext {
+pub(crate) struct LocalContext {
     pub(crate) my_pubkey: Pubkey,
     pub(crate) pending_blocks: PendingBlocks,
     pub(crate) finalized_blocks: BTreeSet<Block>,
@@ -99,6 +99,19 @@ struct LocalContext {
     pub(crate) standstill_slot: Option<Slot>,
 }
 
+impl LocalContext {
+    pub(crate) fn new(my_pubkey: Pubkey) -> Self {
+        Self {
+            my_pubkey,
+            pending_blocks: PendingBlocks::default(),
+            finalized_blocks: BTreeSet::default(),
+            received_shred: BTreeSet::default(),
+            stats: EventHandlerStats::new(),
+            standstill_slot: None,
+        }
+    }
+}
+
 impl EventHandler {
     pub(crate) fn new(ctx: EventHandlerContext) -> Self {
         let exit = ctx.exit.clone();
@@ -125,14 +138,7 @@ impl EventHandler {
             voting_context: mut vctx,
             root_context: rctx,
         } = context;
-        let mut local_context = LocalContext {
-            my_pubkey: ctx.cluster_info.keypair().pubkey(),
-            pending_blocks: PendingBlocks::default(),
-            finalized_blocks: BTreeSet::default(),
-            received_shred: BTreeSet::default(),
-            stats: EventHandlerStats::new(),
-            standstill_slot: None,
-        };
+        let mut local_context = LocalContext::new(ctx.cluster_info.keypair().pubkey());
 
         // Wait until migration has completed
         info!("{}: Event loop initialized", local_context.my_pubkey);
@@ -173,15 +179,7 @@ impl EventHandler {
                 .receive_event_time_us
                 .saturating_add(receive_event_time.as_us() as u32);
 
-            let root_bank = vctx.sharable_banks.root();
-            if event.should_ignore(root_bank.slot().max(vctx.vote_history.root())) {
-                local_context.stats.ignored = local_context.stats.ignored.saturating_add(1);
-                continue;
-            }
-
-            let mut event_processing_time = Measure::start("event_processing");
-            let stats_event = local_context.stats.handle_event_arrival(&event);
-            let votes = Self::handle_event(
+            let votes = Self::process_event(
                 event,
                 &timer_manager,
                 &ctx,
@@ -189,10 +187,6 @@ impl EventHandler {
                 &rctx,
                 &mut local_context,
             )?;
-            event_processing_time.stop();
-            local_context
-                .stats
-                .incr_event_with_timing(stats_event, event_processing_time.as_us());
 
             let mut send_votes_batch_time = Measure::start("send_votes_batch");
             for vote in votes {
@@ -210,6 +204,32 @@ impl EventHandler {
         Ok(())
     }
 
+    /// Handles `event` unless it is at or below the root, and returns the
+    /// votes to send.
+    pub(crate) fn process_event(
+        event: VotorEvent,
+        timer_manager: &RwLock<TimerManager>,
+        ctx: &SharedContext,
+        vctx: &mut VotingContext,
+        rctx: &RootContext,
+        local_context: &mut LocalContext,
+    ) -> Result<Vec<BLSOp>, EventLoopError> {
+        let root_bank = vctx.sharable_banks.root();
+        if event.should_ignore(root_bank.slot().max(vctx.vote_history.root())) {
+            local_context.stats.ignored = local_context.stats.ignored.saturating_add(1);
+            return Ok(vec![]);
+        }
+
+        let mut event_processing_time = Measure::start("event_processing");
+        let stats_event = local_context.stats.handle_event_arrival(&event);
+        let votes = Self::handle_event(event, timer_manager, ctx, vctx, rctx, local_context)?;
+        event_processing_time.stop();
+        local_context
+            .stats
+            .incr_event_with_timing(stats_event, event_processing_time.as_us());
+        Ok(votes)
+    }
+
     fn handle_parent_ready_event(
         slot: Slot,
         parent_block: Block,
@@ -7,11 +7,14 @@ extern crate log;
 pub mod commitment;
 pub mod common;
 pub mod consensus_metrics;
+pub mod consensus_notifier;
 pub mod consensus_pool;
 mod consensus_pool_service;
 pub mod event;
 mod event_handler;
 pub mod root_utils;
+#[cfg(test)]
+mod simulator;
 mod staked_validators_cache;
 mod timer_manager;
 pub mod vote_history;
@@ -0,0 +1,1150 @@
+//! Deterministic, single-process simulator of a votor cluster.
+//!
+//! Every correct node runs the votor [`EventHandler`] against its own
+//! [`ConsensusPool`], bank forks and [`TimerManager`], the latter on a
+//! virtual clock. The simulator stands in for the rest of the validator: it
+//! replays the blocks a node receives into its bank forks, adds the messages
+//! it receives and the votes it casts to its pool, and feeds the resulting
+//! events back to the event handler. Time is virtual: the simulator
+//! processes scheduled message deliveries, block completions and timer wake
+//! ups in order, so a run takes no wall clock time and always yields the same
+//! result. Faults are scripted per run: network delays, partitions, crashed
+//! nodes and Byzantine double voters.
+//!
+//! Every finalization is checked against the blocks finalized so far by the
+//! correct nodes, and a run fails unless all correct nodes finalize the
+//! expected slot within its liveness bound.
+
+use {
+    crate::{
+        commitment::CommitmentAggregationData,
+        consensus_pool::ConsensusPool,
+        event::{
+            CompletedBlock, LatestSwitchRequest, LeaderWindowInfo, RepairEventReceiver, VotorEvent,
+        },
+        event_handler::{EventHandler, LocalContext},
+        root_utils::RootContext,
+        tests::get_cluster_info,
+        timer_manager::TimerManager,
+        vote_history::VoteHistory,
+        vote_history_storage::NullVoteHistoryStorage,
+        voting_service::BLSOp,
+        voting_utils::VotingContext,
+        votor::SharedContext,
+    },
+    agave_bls_sigverify::generated_cert_types::GeneratedCertTypes,
+    agave_votor_messages::{
+        consensus_message::{BLS_KEYPAIR_DERIVE_SEED, Block, ConsensusMessage},
+        metric_types::ConsensusMetricsEventReceiver,
+        migration::MigrationStatus,
+        vote::Vote,
+        wire::get_vote_payload_to_sign,
+    },
+    crossbeam_channel::{Receiver, unbounded},
+    parking_lot::RwLock as PlRwLock,
+    solana_bls_signatures::keypair::Keypair as BLSKeypair,
+    solana_clock::{DEFAULT_MS_PER_SLOT, Slot},
+    solana_hash::Hash,
+    solana_keypair::Keypair,
+    solana_leader_schedule::{NUM_CONSECUTIVE_LEADER_SLOTS, SlotLeader},
+    solana_ledger::{blockstore::Blockstore, get_tmp_ledger_path_auto_delete},
+    solana_pubkey::Pubkey,
+    solana_runtime::{
+        bank::Bank,
+        bank_forks::BankForks,
+        bank_forks_controller::{BankForksController, BankForksControllerError},
+        genesis_utils::{
+            GenesisConfigInfo, ValidatorVoteKeypairs,
+            create_genesis_config_with_alpenglow_vote_accounts,
+        },
+        installed_scheduler_pool::BankWithScheduler,
+        leader_schedule_utils::{
+            first_of_consecutive_leader_slots, last_of_consecutive_leader_slots,
+        },
+    },
+    solana_signer::Signer,
+    std::{
+        cmp::Ordering,
+        collections::{BTreeMap, HashMap, HashSet, VecDeque},
+        sync::{Arc, RwLock},
+        time::{Duration, Instant},
+    },
+    tempfile::TempDir,
+    thiserror::Error,
+};
+
+/// Time a leader takes to produce a block.
+const SLOT_TIME: Duration = Duration::from_millis(DEFAULT_MS_PER_SLOT);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Behavior {
+    Honest,
+    /// Honest until `at`, after which the node neither sends nor processes
+    /// anything.
+    Crashed {
+        at: Duration,
+    },
+    /// Votes both notarize and skip on every block it receives, in opposite
+    /// orders to even and odd numbered nodes, and finalize on every notarized
+    /// block. As a leader, sends a different version of its blocks to even and
+    /// odd numbered nodes.
+    DoubleVoter,
+}
+/// Cuts `nodes` off from the rest of the cluster from `start` until `end`.
+/// Messages crossing the partition are held and delivered when it heals.
+pub(crate) struct Partition {
+    pub(crate) start: Duration,
+    pub(crate) end: Duration,
+    pub(crate) nodes: Vec<usize>,
+}
+
+impl Partition {
+    fn separates(&self, from: usize, to: usize) -> bool {
+        self.nodes.contains(&from) != self.nodes.contains(&to)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Network {
+    /// One-way delay of every message and block.
+    pub(crate) delay: Duration,
+    /// Extra delay of the messages and blocks sent from one node to another.
+    pub(crate) link_delays: HashMap<(usize, usize), Duration>,
+    pub(crate) partitions: Vec<Partition>,
+}
+
+impl Network {
+    fn arrival(&self, from: usize, to: usize, sent: Duration) -> Duration {
+        if from == to {
+            return sent;
+        }
+        let link_delay = self
+            .link_delays
+            .get(&(from, to))
+            .copied()
+            .unwrap_or_default();
+        let mut arrival = sent.saturating_add(self.delay).saturating_add(link_delay);
+        while let Some(partition) = self.partitions.iter().find(|partition| {
+            partition.separates(from, to) && (partition.start..partition.end).contains(&arrival)
+        }) {
+            arrival = partition.end;
+        }
+        arrival
+    }
+}
+
+/// Every correct node must finalize a block in `slot` or later within
+/// `within` of the start of the run.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct LivenessBound {
+    pub(crate) slot: Slot,
+    pub(crate) within: Duration,
+}
+
+pub(crate) struct SimulationConfig {
+    /// Behavior of each node, all nodes have the same stake.
+    pub(crate) behaviors: Vec<Behavior>,
+    pub(crate) network: Network,
+    pub(crate) liveness: LivenessBound,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub(crate) enum Violation {
+    #[error(
+        "node {node} finalized {block:?} conflicting with {finalized:?} finalized by node {by}"
+    )]
+    ConflictingFinalization {
+        node: usize,
+        block: Block,
+        by: usize,
+        finalized: Block,
+    },
+
+    #[error(
+        "node {node} finalized up to slot {highest_finalized:?} within {within:?}, expected slot \
+         {slot}"
+    )]
+    Liveness {
+        node: usize,
+        highest_finalized: Option<Slot>,
+        slot: Slot,
+        within: Duration,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct SimulationReport {
+    /// Blocks finalized by each node.
+    pub(crate) finalized: Vec<BTreeMap<Slot, Block>>,
+    /// Messages rejected by the consensus pools, e.g. conflicting votes.
+    pub(crate) rejected_messages: usize,
+    /// Time at which the liveness bound was met.
+    pub(crate) elapsed: Duration,
+}
+
+enum Action {
+    Event(VotorEvent),
+    Message(ConsensusMessage),
+    Block {
+        block: Block,
+        parent_block: Block,
+        leader: usize,
+    },
+    Wake,
+}
+
+enum Output {
+    /// Send to a single node, or to all other nodes.
+    Send {
+        to: Option<usize>,
+        message: ConsensusMessage,
+    },
+    Produce {
+        slot: Slot,
+        parent_block: Block,
+    },
+    Finalized(Block),
+}
+
+fn leader(slot: Slot, num_nodes: usize) -> usize {
+    let window = slot
+        .checked_div(NUM_CONSECUTIVE_LEADER_SLOTS.get() as Slot)
+        .unwrap();
+    (window as usize).checked_rem(num_nodes).unwrap()
+}
+
+fn block_id(slot: Slot, leader: usize, version: usize) -> Hash {
+    let mut bytes = [0; 32];
+    bytes[..8].copy_from_slice(&slot.to_le_bytes());
+    bytes[8..16].copy_from_slice(&(leader as u64).to_le_bytes());
+    bytes[16..24].copy_from_slice(&(version as u64).to_le_bytes());
+    Hash::new_from_array(bytes)
+}
+
+/// Applies the bank forks changes of the event handler right away, in place of
+/// replay stage.
+struct SimulatedBankForksController {
+    bank_forks: Arc<RwLock<BankForks>>,
+}
+
+impl BankForksController for SimulatedBankForksController {
+    fn insert_bank(&self, bank: Bank) -> Result<BankWithScheduler, BankForksControllerError> {
+        Ok(self.bank_forks.write().unwrap().insert(bank))
+    }
+
+    fn enqueue_set_root(
+        &self,
+        _parent_slot: Slot,
+        new_root: Slot,
+        highest_super_majority_root: Option<Slot>,
+    ) {
+        self.bank_forks
+            .write()
+            .unwrap()
+            .set_root(new_root, None, highest_super_majority_root);
+    }
+
+    fn clear_bank(&self, slot: Slot) -> Result<(), BankForksControllerError> {
+        self.bank_forks.write().unwrap().clear_bank(slot, false);
+        Ok(())
+    }
+}
+
+/// Receivers of the channels the event handler reports to the rest of the
+/// validator on, none of which the simulator models.
+struct SideChannels {
+    bls: Receiver<BLSOp>,
+    commitment: Receiver<CommitmentAggregationData>,
+    consensus_metrics: ConsensusMetricsEventReceiver,
+    repair: RepairEventReceiver,
+    leader_window_info: Receiver<LeaderWindowInfo>,
+}
+
+impl SideChannels {
+    fn drain(&self) {
+        self.bls.try_iter().for_each(drop);
+        self.commitment.try_iter().for_each(drop);
+        self.consensus_metrics.try_iter().for_each(drop);
+        self.repair.try_iter().for_each(drop);
+        self.leader_window_info.try_iter().for_each(drop);
+    }
+}
+
+struct Node {
+    id: usize,
+    num_nodes: usize,
+    behavior: Behavior,
+    slot_leader: SlotLeader,
+    vote_pubkey: Pubkey,
+    rank: u16,
+    bls_keypair: BLSKeypair,
+    shred_version: u16,
+    pool: ConsensusPool,
+    clock: Arc<PlRwLock<Instant>>,
+    timer_manager: PlRwLock<TimerManager>,
+    timer_events: Receiver<VotorEvent>,
+    shared_context: SharedContext,
+    voting_context: VotingContext,
+    root_context: RootContext,
+    local_context: LocalContext,
+    own_votes: Receiver<ConsensusMessage>,
+    side_channels: SideChannels,
+    /// Blocks received before their parent, by parent.
+    orphan_blocks: HashMap<Block, Vec<(Block, SlotLeader)>>,
+    produced_windows: HashSet<Slot>,
+    double_votes: HashSet<Vote>,
+    rejected_messages: usize,
+    _ledger_path: TempDir,
+}
+
+impl Node {
+    fn new(
+        id: usize,
+        num_nodes: usize,
+        behavior: Behavior,
+        keypairs: &ValidatorVoteKeypairs,
+        genesis: &GenesisConfigInfo,
+        start: Instant,
+    ) -> Self {
+        let node_pubkey = keypairs.node_keypair.pubkey();
+        let vote_pubkey = keypairs.vote_keypair.pubkey();
+        let cluster_info = get_cluster_info(keypairs.node_keypair.insecure_clone());
+        let bank_forks = BankForks::new_rw_arc(Bank::new_for_tests(&genesis.genesis_config));
+        let root_bank = bank_forks.read().unwrap().root_bank();
+        let rank_map = root_bank
+            .epoch_stakes_from_slot(root_bank.slot())
+            .unwrap()
+            .bls_pubkey_to_rank_map()
+            .clone();
+        let genesis_block = Block {
+            slot: root_bank.slot(),
+            block_id: root_bank.block_id().unwrap_or_default(),
+        };
+        let ledger_path = get_tmp_ledger_path_auto_delete!();
+        let blockstore = Arc::new(Blockstore::open(ledger_path.path()).unwrap());
+
+        let clock = Arc::new(PlRwLock::new(start));
+        let (timer_sender, timer_events) = unbounded();
+        let (own_vote_sender, own_votes) = unbounded();
+        let (bls_sender, bls) = unbounded();
+        let (commitment_sender, commitment) = unbounded();
+        let (consensus_metrics_sender, consensus_metrics) = unbounded();
+        let (repair_event_sender, repair) = unbounded();
+        let (leader_window_info_sender, leader_window_info) = unbounded();
+
+        let shared_context = SharedContext {
+            blockstore,
+            bank_forks: bank_forks.clone(),
+            cluster_info: cluster_info.clone(),
+            leader_window_info_sender,
+            highest_parent_ready: Arc::default(),
+            vote_history_storage: Arc::new(NullVoteHistoryStorage::default()),
+            repair_event_sender,
+            latest_switch_request: LatestSwitchRequest::default(),
+        };
+        let voting_context = VotingContext {
+            cluster_info: cluster_info.clone(),
+            vote_history: VoteHistory::new(node_pubkey, root_bank.slot()),
+            vote_account_pubkey: vote_pubkey,
+            identity_keypair: Arc::new(keypairs.node_keypair.insecure_clone()),
+            authorized_voter_keypairs: Arc::new(RwLock::new(vec![Arc::new(
+                keypairs.vote_keypair.insecure_clone(),
+            )])),
+            derived_bls_keypairs: HashMap::new(),
+            own_vote_sender,
+            bls_sender,
+            commitment_sender,
+            wait_to_vote_slot: None,
+            sharable_banks: bank_forks.read().unwrap().sharable_banks(),
+            consensus_metrics_sender,
+        };
+        let root_context = RootContext {
+            bank_notification_sender: None,
+            bank_forks_controller: Arc::new(SimulatedBankForksController { bank_forks }),
+        };
+
+        Self {
+            id,
+            num_nodes,
+            behavior,
+            slot_leader: SlotLeader {
+                id: node_pubkey,
+                vote_address: vote_pubkey,
+            },
+            vote_pubkey,
+            rank: *rank_map.get_rank_for_vote_pubkey(&vote_pubkey).unwrap(),
+            bls_keypair: BLSKeypair::derive_from_signer(
+                &keypairs.vote_keypair,
+                BLS_KEYPAIR_DERIVE_SEED,
+            )
+            .unwrap(),
+            shred_version: cluster_info.my_shred_version(),
+            pool: ConsensusPool::new(
+                cluster_info,
+                &root_bank,
+                Arc::new(GeneratedCertTypes::default()),
+                Arc::new(MigrationStatus::post_migration_status()),
+                (root_bank.slot().checked_add(1).unwrap(), genesis_block),
+                None,
+            ),
+            timer_manager: PlRwLock::new(TimerManager::new_with_virtual_clock(
+                timer_sender,
+                clock.clone(),
+            )),
+            clock,
+            timer_events,
+            shared_context,
+            voting_context,
+            root_context,
+            local_context: LocalContext::new(node_pubkey),
+            own_votes,
+            side_channels: SideChannels {
+                bls,
+                commitment,
+                consensus_metrics,
+                repair,
+                leader_window_info,
+            },
+            orphan_blocks: HashMap::new(),
+            produced_windows: HashSet::new(),
+            double_votes: HashSet::new(),
+            rejected_messages: 0,
+            _ledger_path: ledger_path,
+        }
+    }
+
+    fn handle_message(
+        &mut self,
+        message: ConsensusMessage,
+        now: Instant,
+        outputs: &mut Vec<Output>,
+    ) -> Option<Instant> {
+        let mut events = VecDeque::new();
+        self.ingest(message, &mut events, outputs);
+        self.run(events, now, outputs)
+    }
+
+    /// Block `block` of `leader` has been received in full.
+    fn handle_block(
+        &mut self,
+        block: Block,
+        parent_block: Block,
+        leader: SlotLeader,
+        now: Instant,
+        outputs: &mut Vec<Output>,
+    ) -> Option<Instant> {
+        let mut events = VecDeque::new();
+        if self.behavior == Behavior::DoubleVoter {
+            self.equivocate(
+                [
+                    Vote::new_notarization_vote(block),
+                    Vote::new_skip_vote(block.slot),
+                ],
+                &mut events,
+                outputs,
+            );
+        } else {
+            self.replay(block, parent_block, leader, &mut events);
+        }
+        self.run(events, now, outputs)
+    }
+
+    /// Adds a frozen bank for `block` to the bank forks, along with the banks
+    /// of the blocks that were waiting on it, and queues their completion.
+    /// Blocks whose parent has not been replayed wait for it.
+    fn replay(
+        &mut self,
+        block: Block,
+        parent_block: Block,
+        leader: SlotLeader,
+        events: &mut VecDeque<VotorEvent>,
+    ) {
+        let mut blocks = vec![(block, parent_block, leader)];
+        while let Some((block, parent_block, leader)) = blocks.pop() {
+            let (parent, is_replayed) = {
+                let bank_forks = self.shared_context.bank_forks.read().unwrap();
+                let parent = bank_forks.get(parent_block.slot).filter(|parent| {
+                    parent.block_id().unwrap_or_default() == parent_block.block_id
+                });
+                (parent, bank_forks.get(block.slot).is_some())
+            };
+            if is_replayed {
+                continue;
+            }
+            let Some(parent) = parent else {
+                self.orphan_blocks
+                    .entry(parent_block)
+                    .or_default()
+                    .push((block, leader));
+                continue;
+            };
+            let bank = Bank::new_from_parent(parent, leader, block.slot);
+            bank.set_block_id(Some(block.block_id));
+            bank.freeze();
+            let bank = self
+                .shared_context
+                .bank_forks
+                .write()
+                .unwrap()
+                .insert(bank)
+                .clone_without_scheduler();
+            events.push_back(VotorEvent::Block(CompletedBlock {
+                slot: block.slot,
+                bank,
+            }));
+            blocks.extend(
+                self.orphan_blocks
+                    .remove(&block)
+                    .into_iter()
+                    .flatten()
+                    .map(|(child, leader)| (child, block, leader)),
+            );
+        }
+    }
+
+    /// Handles `events` and the events of the timers that fire by `now`.
+    /// Returns when the timers fire next.
+    fn run(
+        &mut self,
+        mut events: VecDeque<VotorEvent>,
+        now: Instant,
+        outputs: &mut Vec<Output>,
+    ) -> Option<Instant> {
+        *self.clock.write() = now;
+        loop {
+            while let Some(event) = events.pop_front() {
+                self.handle_event(event, &mut events, outputs);
+            }
+            let next_fire = self.timer_manager.read().progress();
+            events.extend(self.timer_events.try_iter());
+            if events.is_empty() {
+                self.side_channels.drain();
+                return next_fire;
+            }
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        event: VotorEvent,
+        events: &mut VecDeque<VotorEvent>,
+        outputs: &mut Vec<Output>,
+    ) {
+        if self.behavior == Behavior::DoubleVoter {
+            self.handle_event_as_double_voter(event, events, outputs);
+            return;
+        }
+        match event {
+            VotorEvent::ParentReady { slot, parent_block } => {
+                self.maybe_produce(slot, parent_block, outputs);
+            }
+            VotorEvent::Finalized(block, _) => outputs.push(Output::Finalized(block)),
+            _ => (),
+        }
+        let bls_ops = EventHandler::process_event(
+            event,
+            &self.timer_manager,
+            &self.shared_context,
+            &mut self.voting_context,
+            &self.root_context,
+            &mut self.local_context,
+        )
+        .expect("event handler failed");
+        for bls_op in bls_ops {
+            let messages: Vec<_> = match bls_op {
+                BLSOp::PushVote { vote, .. } => vec![ConsensusMessage::Vote((*vote).clone())],
+                BLSOp::RefreshVotes { votes } => votes
+                    .iter()
+                    .map(|vote| ConsensusMessage::Vote((**vote).clone()))
+                    .collect(),
+                BLSOp::PushCertificates { certificates }
+                | BLSOp::RefreshCertificates { certificates } => certificates
+                    .iter()
+                    .map(|certificate| ConsensusMessage::Certificate((**certificate).clone()))
+                    .collect(),
+            };
+            outputs.extend(
+                messages
+                    .into_iter()
+                    .map(|message| Output::Send { to: None, message }),
+            );
+        }
+        // The event handler hands the votes it casts to its own pool.
+        let own_votes: Vec<_> = self.own_votes.try_iter().collect();
+        for message in own_votes {
+            self.ingest(message, events, outputs);
+        }
+    }
+
+    fn handle_event_as_double_voter(
+        &mut self,
+        event: VotorEvent,
+        events: &mut VecDeque<VotorEvent>,
+        outputs: &mut Vec<Output>,
+    ) {
+        let vote = match event {
+            VotorEvent::BlockNotarized(block) => Vote::new_finalization_vote(block.slot),
+            VotorEvent::SafeToNotar(block) => Vote::new_notarization_fallback_vote(block),
+            VotorEvent::SafeToSkip(slot) => Vote::new_skip_fallback_vote(slot),
+            VotorEvent::ParentReady { slot, parent_block } => {
+                self.maybe_produce(slot, parent_block, outputs);
+                return;
+            }
+            _ => return,
+        };
+        if self.double_votes.insert(vote) {
+            let message = self.sign(vote);
+            outputs.push(Output::Send {
+                to: None,
+                message: message.clone(),
+            });
+            self.ingest(message, events, outputs);
+        }
+    }
+
+    fn maybe_produce(&mut self, slot: Slot, parent_block: Block, outputs: &mut Vec<Output>) {
+        if leader(slot, self.num_nodes) == self.id
+            && self
+                .produced_windows
+                .insert(first_of_consecutive_leader_slots(slot))
+        {
+            outputs.push(Output::Produce { slot, parent_block });
+        }
+    }
+
+    /// Sends both `votes` to every node, in opposite orders to even and odd
+    /// numbered nodes.
+    fn equivocate(
+        &mut self,
+        votes: [Vote; 2],
+        events: &mut VecDeque<VotorEvent>,
+        outputs: &mut Vec<Output>,
+    ) {
+        let mut messages = votes.map(|vote| self.sign(vote));
+        for to in (0..self.num_nodes).filter(|to| *to != self.id) {
+            let order = if to.is_multiple_of(2) { [0, 1] } else { [1, 0] };
+            for index in order {
+                outputs.push(Output::Send {
+                    to: Some(to),
+                    message: messages[index].clone(),
+                });
+            }
+        }
+        if !self.id.is_multiple_of(2) {
+            messages.reverse();
+        }
+        for message in messages {
+            self.ingest(message, events, outputs);
+        }
+    }
+
+    fn sign(&self, vote: Vote) -> ConsensusMessage {
+        let payload = get_vote_payload_to_sign(vote, self.shred_version);
+        ConsensusMessage::new_vote(vote, self.bls_keypair.sign(&payload).into(), self.rank)
+    }
+
+    /// Adds `message` to the pool and sends out the certificates it completes.
+    fn ingest(
+        &mut self,
+        message: ConsensusMessage,
+        events: &mut VecDeque<VotorEvent>,
+        outputs: &mut Vec<Output>,
+    ) {
+        let is_vote = matches!(message, ConsensusMessage::Vote(_));
+        let root_bank = self.voting_context.sharable_banks.root();
+        let mut new_events = vec![];
+        match self
+            .pool
+            .add_message(&root_bank, &self.vote_pubkey, message, &mut new_events)
+        {
+            Ok((_, new_certificates)) if is_vote => {
+                outputs.extend(
+                    new_certificates
+                        .into_iter()
+                        .map(|certificate| Output::Send {
+                            to: None,
+                            message: ConsensusMessage::Certificate((*certificate).clone()),
+                        }),
+                );
+            }
+            Ok(_) => (),
+            Err(_) => self.rejected_messages = self.rejected_messages.saturating_add(1),
+        }
+        events.extend(new_events);
+    }
+}
+
+pub(crate) struct Simulation {
+    nodes: Vec<Node>,
+    network: Network,
+    liveness: LivenessBound,
+    start: Instant,
+    /// Virtual time since the start of the run.
+    now: Duration,
+    queue: BTreeMap<(Duration, u64), (usize, Action)>,
+    next_sequence: u64,
+    scheduled_wakes: Vec<Option<Duration>>,
+    block_parents: HashMap<Block, Block>,
+    /// Blocks finalized by each node.
+    finalized: Vec<BTreeMap<Slot, Block>>,
+    /// Blocks finalized by the correct nodes, and the first node to finalize
+    /// each.
+    correct_finalized: BTreeMap<Slot, (Block, usize)>,
+}
+
+impl Simulation {
+    pub(crate) fn new(config: SimulationConfig) -> Self {
+        let SimulationConfig {
+            behaviors,
+            network,
+            liveness,
+        } = config;
+        let num_nodes = behaviors.len();
+        // Seeded keypairs make the ranks, and with them the runs, reproducible.
+        let validator_keypairs: Vec<_> = (0..num_nodes)
+            .map(|id| {
+                let seed = |kind: u8| {
+                    let mut secret = [kind; 32];
+                    secret[..8].copy_from_slice(&(id as u64).to_le_bytes());
+                    Keypair::new_from_array(secret)
+                };
+                ValidatorVoteKeypairs::new(seed(1), seed(2), seed(3))
+            })
+            .collect();
+        let genesis = create_genesis_config_with_alpenglow_vote_accounts(
+            1_000_000_000,
+            &validator_keypairs,
+            vec![100; num_nodes],
+        );
+        let start = Instant::now();
+        let nodes: Vec<_> = validator_keypairs
+            .iter()
+            .zip(behaviors)
+            .enumerate()
+            .map(|(id, (keypairs, behavior))| {
+                Node::new(id, num_nodes, behavior, keypairs, &genesis, start)
+            })
+            .collect();
+        let root_bank = nodes[0].voting_context.sharable_banks.root();
+        let genesis_block = Block {
+            slot: root_bank.slot(),
+            block_id: root_bank.block_id().unwrap_or_default(),
+        };
+
+        let mut simulation = Self {
+            nodes,
+            network,
+            liveness,
+            start,
+            now: Duration::ZERO,
+            queue: BTreeMap::new(),
+            next_sequence: 0,
+            scheduled_wakes: vec![None; num_nodes],
+            block_parents: HashMap::new(),
+            finalized: vec![BTreeMap::new(); num_nodes],
+            correct_finalized: BTreeMap::new(),
+        };
+        // Every node starts out parent ready for the first slot after genesis.
+        for id in 0..num_nodes {
+            simulation.schedule(
+                Duration::ZERO,
+                id,
+                Action::Event(VotorEvent::ParentReady {
+                    slot: genesis_block.slot.checked_add(1).unwrap(),
+                    parent_block: genesis_block,
+                }),
+            );
+        }
+        simulation
+    }
+
+    /// Runs the cluster until every correct node has met the liveness bound.
+    pub(crate) fn run(mut self) -> Result<SimulationReport, Violation> {
+        while let Some(((at, _), (id, action))) = self.queue.pop_first() {
+            if at > self.liveness.within {
+                break;
+            }
+            self.now = at;
+            self.step(id, action)?;
+            if self.lagging_node().is_none() {
+                return Ok(SimulationReport {
+                    finalized: self.finalized,
+                    rejected_messages: self.nodes.iter().map(|node| node.rejected_messages).sum(),
+                    elapsed: self.now,
+                });
+            }
+        }
+        let node = self.lagging_node().unwrap();
+        Err(Violation::Liveness {
+            node,
+            highest_finalized: self.finalized[node].keys().next_back().copied(),
+            slot: self.liveness.slot,
+            within: self.liveness.within,
+        })
+    }
+
+    /// A node that is always correct and has yet to meet the liveness bound.
+    fn lagging_node(&self) -> Option<usize> {
+        self.nodes
+            .iter()
+            .filter(|node| node.behavior == Behavior::Honest)
+            .map(|node| node.id)
+            .find(|id| {
+                self.finalized[*id]
+                    .keys()
+                    .next_back()
+                    .is_none_or(|slot| *slot < self.liveness.slot)
+            })
+    }
+
+    fn is_up(&self, id: usize, at: Duration) -> bool {
+        match self.nodes[id].behavior {
+            Behavior::Crashed { at: crashed_at } => at < crashed_at,
+            Behavior::Honest | Behavior::DoubleVoter => true,
+        }
+    }
+
+    fn schedule(&mut self, at: Duration, id: usize, action: Action) {
+        self.queue.insert((at, self.next_sequence), (id, action));
+        self.next_sequence = self.next_sequence.checked_add(1).unwrap();
+    }
+
+    fn step(&mut self, id: usize, action: Action) -> Result<(), Violation> {
+        if !self.is_up(id, self.now) {
+            return Ok(());
+        }
+        let now = self.start.checked_add(self.now).unwrap();
+        let mut outputs = vec![];
+        let next_fire = match action {
+            Action::Event(event) => self.nodes[id].run(VecDeque::from([event]), now, &mut outputs),
+            Action::Message(message) => self.nodes[id].handle_message(message, now, &mut outputs),
+            Action::Block {
+                block,
+                parent_block,
+                leader,
+            } => {
+                let leader = self.nodes[leader].slot_leader;
+                self.nodes[id].handle_block(block, parent_block, leader, now, &mut outputs)
+            }
+            Action::Wake => self.nodes[id].run(VecDeque::new(), now, &mut outputs),
+        };
+        if let Some(next_fire) = next_fire {
+            let at = next_fire.duration_since(self.start);
+            if self.scheduled_wakes[id] != Some(at) {
+                self.scheduled_wakes[id] = Some(at);
+                self.schedule(at, id, Action::Wake);
+            }
+        }
+
+        for output in outputs {
+            match output {
+                Output::Send {
+                    to: Some(to),
+                    message,
+                } => self.send(id, to, message),
+                Output::Send { to: None, message } => {
+                    for to in (0..self.nodes.len()).filter(|to| *to != id) {
+                        self.send(id, to, message.clone());
+                    }
+                }
+                Output::Produce { slot, parent_block } => self.produce(id, slot, parent_block),
+                Output::Finalized(block) => self.record_finalized(id, block)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, from: usize, to: usize, message: ConsensusMessage) {
+        let at = self.network.arrival(from, to, self.now);
+        self.schedule(at, to, Action::Message(message));
+    }
+
+    /// Streams the blocks of the leader window of `slot` on top of
+    /// `parent_block`, one every `SLOT_TIME`, until the leader crashes.
+    fn produce(&mut self, leader: usize, slot: Slot, parent_block: Block) {
+        let versions = if self.nodes[leader].behavior == Behavior::DoubleVoter {
+            2
+        } else {
+            1
+        };
+        for version in 0..versions {
+            let mut parent = parent_block;
+            let mut started = self.now;
+            for slot in slot..=last_of_consecutive_leader_slots(slot) {
+                let completed = started.saturating_add(SLOT_TIME);
+                let is_completed = self.is_up(leader, completed);
+                if !self.is_up(leader, started) {
+                    break;
+                }
+                let block = Block {
+                    slot,
+                    block_id: block_id(slot, leader, version),
+                };
+                self.block_parents.insert(block, parent);
+                for to in 0..self.nodes.len() {
+                    if to.checked_rem(versions) != Some(version) {
+                        continue;
+                    }
+                    let at = self.network.arrival(leader, to, started);
+                    self.schedule(at, to, Action::Event(VotorEvent::FirstShred(slot)));
+                    if !is_completed {
+                        continue;
+                    }
+                    let at = self.network.arrival(leader, to, completed);
+                    self.schedule(
+                        at,
+                        to,
+                        Action::Block {
+                            block,
+                            parent_block: parent,
+                            leader,
+                        },
+                    );
+                }
+                parent = block;
+                started = completed;
+            }
+        }
+    }
+
+    /// Checks `block`, finalized by node `id`, against the blocks finalized
+    /// by the correct nodes so far.
+    fn record_finalized(&mut self, id: usize, block: Block) -> Result<(), Violation> {
+        self.finalized[id].insert(block.slot, block);
+        if self.nodes[id].behavior == Behavior::DoubleVoter {
+            return Ok(());
+        }
+        for (finalized, by) in self.correct_finalized.values() {
+            let is_consistent = match block.slot.cmp(&finalized.slot) {
+                Ordering::Less => self.is_ancestor(block, *finalized),
+                Ordering::Equal => block == *finalized,
+                Ordering::Greater => self.is_ancestor(*finalized, block),
+            };
+            if !is_consistent {
+                return Err(Violation::ConflictingFinalization {
+                    node: id,
+                    block,
+                    by: *by,
+                    finalized: *finalized,
+                });
+            }
+        }
+        self.correct_finalized
+            .entry(block.slot)
+            .or_insert((block, id));
+        Ok(())
+    }
+
+    fn is_ancestor(&self, ancestor: Block, mut block: Block) -> bool {
+        while block.slot > ancestor.slot {
+            let Some(parent) = self.block_parents.get(&block) else {
+                return false;
+            };
+            block = *parent;
+        }
+        block == ancestor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(behaviors: Vec<Behavior>, network: Network, slot: Slot) -> SimulationConfig {
+        SimulationConfig {
+            behaviors,
+            network,
+            liveness: LivenessBound {
+                slot,
+                within: Duration::from_secs(20),
+            },
+        }
+    }
+
+    fn network(delay_ms: u64) -> Network {
+        Network {
+            delay: Duration::from_millis(delay_ms),
+            ..Network::default()
+        }
+    }
+
+    fn finalized_slots(report: &SimulationReport, node: usize) -> Vec<Slot> {
+        report.finalized[node].keys().copied().collect()
+    }
+
+    #[test]
+    fn test_honest_cluster_finalizes() {
+        let report = Simulation::new(config(vec![Behavior::Honest; 4], network(20), 16))
+            .run()
+            .unwrap();
+        // Windows are produced back to back and every block is finalized.
+        assert!(report.elapsed < Duration::from_secs(8));
+        assert_eq!(report.rejected_messages, 0);
+        for node in 0..4 {
+            assert!(finalized_slots(&report, node).starts_with(&[1, 2, 3, 4, 5, 6, 7, 8]));
+        }
+    }
+
+    #[test]
+    fn test_simulation_is_deterministic() {
+        let run = || {
+            let mut network = network(20);
+            network
+                .link_delays
+                .insert((0, 3), Duration::from_millis(300));
+            network
+                .link_delays
+                .insert((2, 1), Duration::from_millis(150));
+            Simulation::new(config(vec![Behavior::Honest; 4], network, 12))
+                .run()
+                .unwrap()
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn test_crashed_leader_window_is_skipped() {
+        let mut behaviors = vec![Behavior::Honest; 5];
+        behaviors[1] = Behavior::Crashed { at: Duration::ZERO };
+        let report = Simulation::new(config(behaviors, network(20), 16))
+            .run()
+            .unwrap();
+        // Node 1 leads slots 4 to 7, which are skipped by everyone else.
+        for node in [0, 2, 3, 4] {
+            let slots = finalized_slots(&report, node);
+            assert!(slots.iter().all(|slot| !(4..=7).contains(slot)));
+            assert!(slots.contains(&8));
+        }
+        assert!(report.finalized[1].is_empty());
+    }
+
+    #[test]
+    fn test_leader_crashing_mid_window() {
+        let mut behaviors = vec![Behavior::Honest; 5];
+        behaviors[2] = Behavior::Crashed {
+            at: Duration::from_millis(3_500),
+        };
+        Simulation::new(config(behaviors, network(20), 16))
+            .run()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_partition_heals() {
+        let mut network = network(20);
+        network.partitions.push(Partition {
+            start: Duration::from_millis(500),
+            end: Duration::from_secs(3),
+            nodes: vec![0, 1],
+        });
+        let report = Simulation::new(config(vec![Behavior::Honest; 4], network, 12))
+            .run()
+            .unwrap();
+        // Neither side holds enough stake to finalize during the partition,
+        // the blocks of slots 2 and 3 only got notarize votes from nodes 0
+        // and 1 and are never finalized.
+        assert!(report.elapsed > Duration::from_secs(3));
+        for node in 0..4 {
+            let slots = finalized_slots(&report, node);
+            assert!(slots.contains(&1));
+            assert!(!slots.contains(&2) && !slots.contains(&3));
+        }
+    }
+
+    #[test]
+    fn test_double_voter_cannot_break_safety() {
+        let mut behaviors = vec![Behavior::Honest; 5];
+        // Node 0 leads the first window and equivocates its blocks.
+        behaviors[0] = Behavior::DoubleVoter;
+        let report = Simulation::new(config(behaviors, network(20), 16))
+            .run()
+            .unwrap();
+        // Every node drops one of the conflicting votes of the double voter.
+        assert!(report.rejected_messages > 0);
+    }
+
+    #[test]
+    fn test_double_voter_with_slow_links() {
+        let mut behaviors = vec![Behavior::Honest; 5];
+        behaviors[3] = Behavior::DoubleVoter;
+        let mut network = network(10);
+        for to in [0, 2, 4] {
+            network
+                .link_delays
+                .insert((1, to), Duration::from_millis(400));
+        }
+        Simulation::new(config(behaviors, network, 16))
+            .run()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_liveness_violation() {
+        let mut behaviors = vec![Behavior::Honest; 4];
+        behaviors[3] = Behavior::Crashed { at: Duration::ZERO };
+        behaviors[2] = Behavior::Crashed { at: Duration::ZERO };
+        // Half of the stake is not enough to make progress.
+        assert_eq!(
+            Simulation::new(SimulationConfig {
+                behaviors,
+                network: network(20),
+                liveness: LivenessBound {
+                    slot: 4,
+                    within: Duration::from_secs(10),
+                },
+            })
+            .run(),
+            Err(Violation::Liveness {
+                node: 0,
+                highest_finalized: None,
+                slot: 4,
+                within: Duration::from_secs(10),
+            })
+        );
+    }
+
+    #[test]
+    fn test_conflicting_finalization_detected() {
+        let mut simulation = Simulation::new(config(vec![Behavior::Honest; 4], network(20), 4));
+        let parent = Block {
+            slot: 1,
+            block_id: block_id(1, 0, 0),
+        };
+        let block = Block {
+            slot: 2,
+            block_id: block_id(2, 0, 0),
+        };
+        let conflicting_block = Block {
+            slot: 2,
+            block_id: block_id(2, 0, 1),
+        };
+        let other_parent = Block {
+            slot: 1,
+            block_id: block_id(1, 0, 1),
+        };
+        simulation.block_parents.insert(block, parent);
+        simulation.block_parents.insert(conflicting_block, parent);
+
+        simulation.record_finalized(0, parent).unwrap();
+        simulation.record_finalized(1, block).unwrap();
+        simulation.record_finalized(2, parent).unwrap();
+        assert_eq!(
+            simulation.record_finalized(3, conflicting_block),
+            Err(Violation::ConflictingFinalization {
+                node: 3,
+                block: conflicting_block,
+                by: 1,
+                finalized: block,
+            })
+        );
+        assert_eq!(
+            simulation.record_finalized(3, other_parent),
+            Err(Violation::ConflictingFinalization {
+                node: 3,
+                block: other_parent,
+                by: 0,
+                finalized: parent,
+            })
+        );
+    }
+}
@@ -25,7 +25,27 @@ use {
 /// timers and send events.
 pub(crate) struct TimerManager {
     timers: Arc<PlRwLock<Timers>>,
-    handle: JoinHandle<()>,
+    clock: Clock,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// Source of the time timers are set at.
+enum Clock {
+    System,
+    /// Time set by the owner of the timer manager, which also fires the
+    /// timers. Used by the simulator to run in virtual time.
+    #[cfg(test)]
+    Virtual(Arc<PlRwLock<Instant>>),
+}
+
+impl Clock {
+    fn now(&self) -> Instant {
+        match self {
+            Self::System => Instant::now(),
+            #[cfg(test)]
+            Self::Virtual(now) => *now.read(),
+        }
+    }
 }
 
 impl TimerManager {
@@ -54,7 +74,32 @@ impl TimerManager {
             })
         };
 
-        Self { timers, handle }
+        Self {
+            timers,
+            clock: Clock::System,
+            handle: Some(handle),
+        }
+    }
+
+    /// Creates a timer manager without a background thread. Timeouts are set
+    /// at the time read from `clock`, and fire when `progress` is called.
+    #[cfg(test)]
+    pub(crate) fn new_with_virtual_clock(
+        event_sender: Sender<VotorEvent>,
+        clock: Arc<PlRwLock<Instant>>,
+    ) -> Self {
+        Self {
+            timers: Arc::new(PlRwLock::new(Timers::new(DELTA_TIMEOUT, event_sender))),
+            clock: Clock::Virtual(clock),
+            handle: None,
+        }
+    }
+
+    /// Sends the events of the timers that are ready at the current time of
+    /// the clock. Returns when the next timer might become ready.
+    #[cfg(test)]
+    pub(crate) fn progress(&self) -> Option<Instant> {
+        self.timers.write().progress(self.clock.now())
     }
 
     pub(crate) fn set_timeouts(
@@ -66,7 +111,7 @@ impl TimerManager {
     ) -> bool {
         self.timers.write().set_timeouts(
             slot,
-            Instant::now(),
+            self.clock.now(),
             standstill_slot,
             delta_first_slice,
             delta_block,
@@ -74,7 +119,9 @@ impl TimerManager {
     }
 
     pub(crate) fn join(self) {
-        self.handle.join().unwrap();
+        if let Some(handle) = self.handle {
+            handle.join().unwrap();
+        }
     }
 
     #[cfg(test)]
@@ -314,6 +314,12 @@ pub enum VoteHistoryError {
 
     #[error("The vote history is useless because of new hard fork: {0}")]
     HardFork(Slot),
+
+    #[error("The vote history lease is not held, the vote history must be loaded first")]
+    LeaseNotHeld,
+
+    #[error("The vote history lease was lost, the latest vote history is at revision {0:?}")]
+    LeaseLost(Option<u64>),
 }
 
 impl VoteHistoryError {
@@ -6,9 +6,11 @@ use {
     solana_signature::Signature,
     solana_signer::Signer,
     std::{
+        collections::HashMap,
         fs::{self, File},
         io::{self, BufReader, BufWriter},
         path::PathBuf,
+        sync::{Arc, Mutex},
     },
     wincode::{SchemaRead, SchemaWrite},
 };
@@ -168,6 +170,172 @@ impl VoteHistoryStorage for FileVoteHistoryStorage {
     }
 }
 
+/// Revision at which a key of a [`VoteHistoryBackend`] was last written. Like
+/// etcd's `mod_revision`, every write gets a revision higher than all the
+/// previous ones.
+pub type Revision = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareAndSet {
+    /// The value was written at the given revision.
+    Written(Revision),
+    /// The key was not at the expected revision, it is at the given one
+    /// instead, or absent.
+    Conflict(Option<Revision>),
+}
+
+/// A key-value store shared by the machines that may vote for a node, such as
+/// etcd, with compare-and-set writes.
+pub trait VoteHistoryBackend: Sync + Send {
+    /// The value of `key` and the revision it was last written at.
+    fn get(&self, key: &str) -> io::Result<Option<(Vec<u8>, Revision)>>;
+
+    /// Writes `value` to `key` only if the key is still at revision
+    /// `expected`, or absent for `None`.
+    fn compare_and_set(
+        &self,
+        key: &str,
+        expected: Option<Revision>,
+        value: Vec<u8>,
+    ) -> io::Result<CompareAndSet>;
+}
+
+/// In-process [`VoteHistoryBackend`], to share between the storages of
+/// different validators in tests and local clusters.
+#[derive(Debug, Default)]
+pub struct InMemoryVoteHistoryBackend {
+    state: Mutex<InMemoryBackendState>,
+}
+
+#[derive(Debug, Default)]
+struct InMemoryBackendState {
+    revision: Revision,
+    entries: HashMap<String, (Vec<u8>, Revision)>,
+}
+
+impl VoteHistoryBackend for InMemoryVoteHistoryBackend {
+    fn get(&self, key: &str) -> io::Result<Option<(Vec<u8>, Revision)>> {
+        Ok(self.state.lock().unwrap().entries.get(key).cloned())
+    }
+
+    fn compare_and_set(
+        &self,
+        key: &str,
+        expected: Option<Revision>,
+        value: Vec<u8>,
+    ) -> io::Result<CompareAndSet> {
+        let mut state = self.state.lock().unwrap();
+        let current = state.entries.get(key).map(|(_, revision)| *revision);
+        if current != expected {
+            return Ok(CompareAndSet::Conflict(current));
+        }
+        state.revision = state.revision.checked_add(1).unwrap();
+        let revision = state.revision;
+        state.entries.insert(key.to_string(), (value, revision));
+        Ok(CompareAndSet::Written(revision))
+    }
+}
+
+/// Stores the vote history in a [`VoteHistoryBackend`] shared between the
+/// hot and cold machines of a validator.
+///
+/// Loading the vote history of a node takes the lease on it: the storage
+/// writes back the vote history it read, or an empty value if the node has
+/// none yet, with a compare-and-set, and only stores a new vote history if the
+/// backend is still at the revision it wrote. Once another machine loads or
+/// stores the vote history, stores from this one fail. The voting service
+/// exits the validator when it cannot save the vote history, before sending
+/// the vote, so a machine that lost the lease stops instead of double voting.
+///
+/// A validator uses this storage when it is set as its
+/// `ValidatorConfig::vote_history_storage`, there is no command line argument
+/// for it. [`InMemoryVoteHistoryBackend`] only shares the vote history within
+/// a process, failing over between machines needs a [`VoteHistoryBackend`]
+/// over a store they share, such as etcd.
+pub struct RemoteVoteHistoryStorage {
+    backend: Arc<dyn VoteHistoryBackend>,
+    /// Revision of the vote history of each node, as last loaded or stored.
+    leases: Mutex<HashMap<Pubkey, Revision>>,
+}
+
+impl RemoteVoteHistoryStorage {
+    pub fn new(backend: Arc<dyn VoteHistoryBackend>) -> Self {
+        Self {
+            backend,
+            leases: Mutex::default(),
+        }
+    }
+
+    pub fn key(node_pubkey: &Pubkey) -> String {
+        format!("vote_history-{node_pubkey}")
+    }
+
+    /// Whether this storage may store the vote history of `node_pubkey`.
+    pub fn holds_lease(&self, node_pubkey: &Pubkey) -> bool {
+        self.leases.lock().unwrap().contains_key(node_pubkey)
+    }
+}
+
+impl VoteHistoryStorage for RemoteVoteHistoryStorage {
+    fn load(&self, node_pubkey: &Pubkey) -> Result<VoteHistory> {
+        let key = Self::key(node_pubkey);
+        trace!("load {key}");
+        let (data, revision) = match self.backend.get(&key)? {
+            Some((data, revision)) => (data, Some(revision)),
+            None => (Vec::new(), None),
+        };
+        // An empty value only marks the lease of a node without a vote history
+        let vote_history = if data.is_empty() {
+            None
+        } else {
+            let saved_vote_history: SavedVoteHistoryVersions = wincode::deserialize(&data)?;
+            Some(saved_vote_history.try_into_vote_history(node_pubkey)?)
+        };
+
+        // Writing back what was read bumps the revision, which fences the
+        // machine that held the lease before
+        let mut leases = self.leases.lock().unwrap();
+        leases.remove(node_pubkey);
+        match self.backend.compare_and_set(&key, revision, data)? {
+            CompareAndSet::Written(revision) => {
+                leases.insert(*node_pubkey, revision);
+            }
+            CompareAndSet::Conflict(revision) => {
+                return Err(VoteHistoryError::LeaseLost(revision));
+            }
+        }
+        vote_history.ok_or_else(|| {
+            VoteHistoryError::IoError(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{key} not found"),
+            ))
+        })
+    }
+
+    fn store(&self, saved_vote_history: &SavedVoteHistoryVersions) -> Result<()> {
+        let pubkey = saved_vote_history.pubkey();
+        let key = Self::key(&pubkey);
+        trace!("store: {key}");
+        // Hold the leases across the write so that stores of the same node
+        // are not reordered.
+        let mut leases = self.leases.lock().unwrap();
+        let lease = leases
+            .get_mut(&pubkey)
+            .ok_or(VoteHistoryError::LeaseNotHeld)?;
+        let data = wincode::serialize(saved_vote_history)?;
+        match self.backend.compare_and_set(&key, Some(*lease), data)? {
+            CompareAndSet::Written(revision) => {
+                *lease = revision;
+                Ok(())
+            }
+            CompareAndSet::Conflict(revision) => {
+                leases.remove(&pubkey);
+                Err(VoteHistoryError::LeaseLost(revision))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use {
@@ -271,4 +439,120 @@ mod test {
         let error = storage.load(&pubkey).err().unwrap();
         assert!(matches!(error, VoteHistoryError::DeserializeError(_)));
     }
+
+    fn save(storage: &RemoteVoteHistoryStorage, vote_history: &VoteHistory, keypair: &Keypair) {
+        vote_history.save(storage, keypair).unwrap();
+    }
+
+    #[test]
+    fn test_remote_vote_history_storage() {
+        let storage =
+            RemoteVoteHistoryStorage::new(Arc::new(InMemoryVoteHistoryBackend::default()));
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        let mut vote_history = VoteHistory::new(pubkey, 0);
+
+        // Storing requires the lease, which loading takes even without a
+        // vote history
+        assert!(matches!(
+            vote_history.save(&storage, &keypair),
+            Err(VoteHistoryError::LeaseNotHeld)
+        ));
+        assert!(storage.load(&pubkey).err().unwrap().is_file_missing());
+        assert!(storage.holds_lease(&pubkey));
+        save(&storage, &vote_history, &keypair);
+        assert_eq!(storage.load(&pubkey).unwrap().root(), 0);
+
+        vote_history.set_root(1);
+        vote_history.add_vote(Vote::new_skip_vote(2));
+        save(&storage, &vote_history, &keypair);
+        let restored_vote_history = storage.load(&pubkey).unwrap();
+        assert_eq!(restored_vote_history.root(), 1);
+        assert_eq!(
+            restored_vote_history.votes_cast_since(0),
+            vote_history.votes_cast_since(0)
+        );
+    }
+
+    #[test]
+    fn test_remote_vote_history_storage_failover() {
+        let backend = Arc::new(InMemoryVoteHistoryBackend::default());
+        let hot = RemoteVoteHistoryStorage::new(backend.clone());
+        let cold = RemoteVoteHistoryStorage::new(backend);
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        let mut vote_history = VoteHistory::new(pubkey, 0);
+
+        assert!(hot.load(&pubkey).is_err());
+        vote_history.add_vote(Vote::new_skip_vote(1));
+        save(&hot, &vote_history, &keypair);
+
+        // Failing over to the cold machine takes the lease
+        let mut cold_vote_history = cold.load(&pubkey).unwrap();
+        assert_eq!(
+            cold_vote_history.votes_cast_since(0),
+            vote_history.votes_cast_since(0)
+        );
+        cold_vote_history.add_vote(Vote::new_skip_vote(2));
+        save(&cold, &cold_vote_history, &keypair);
+
+        // The hot machine can no longer save, so it stops voting
+        vote_history.add_vote(Vote::new_skip_vote(3));
+        assert!(matches!(
+            vote_history.save(&hot, &keypair),
+            Err(VoteHistoryError::LeaseLost(Some(_)))
+        ));
+        assert!(!hot.holds_lease(&pubkey));
+        assert!(matches!(
+            vote_history.save(&hot, &keypair),
+            Err(VoteHistoryError::LeaseNotHeld)
+        ));
+
+        // Loading on the other machine takes the lease before it stores
+        let mut hot_vote_history = hot.load(&pubkey).unwrap();
+        cold_vote_history.add_vote(Vote::new_skip_vote(4));
+        assert!(matches!(
+            cold_vote_history.save(&cold, &keypair),
+            Err(VoteHistoryError::LeaseLost(Some(_)))
+        ));
+        assert!(!cold.holds_lease(&pubkey));
+        hot_vote_history.add_vote(Vote::new_skip_vote(5));
+        save(&hot, &hot_vote_history, &keypair);
+        assert_eq!(
+            cold.load(&pubkey).unwrap().votes_cast_since(0),
+            hot_vote_history.votes_cast_since(0)
+        );
+        assert!(matches!(
+            hot_vote_history.save(&hot, &keypair),
+            Err(VoteHistoryError::LeaseLost(Some(_)))
+        ));
+    }
+
+    #[test]
+    fn test_remote_vote_history_storage_invalid_signature() {
+        let backend = Arc::new(InMemoryVoteHistoryBackend::default());
+        let storage = RemoteVoteHistoryStorage::new(backend.clone());
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        assert!(storage.load(&pubkey).is_err());
+        save(&storage, &VoteHistory::new(pubkey, 0), &keypair);
+
+        // A vote history stored under the key of another node
+        let other_pubkey = Pubkey::new_unique();
+        let (data, _) = backend
+            .get(&RemoteVoteHistoryStorage::key(&pubkey))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            backend
+                .compare_and_set(&RemoteVoteHistoryStorage::key(&other_pubkey), None, data)
+                .unwrap(),
+            CompareAndSet::Written(3)
+        );
+        assert!(matches!(
+            storage.load(&other_pubkey),
+            Err(VoteHistoryError::InvalidSignature)
+        ));
+        assert!(!storage.holds_lease(&other_pubkey));
+    }
 }
@@ -437,6 +437,9 @@ impl VotingService {
                 saved_vote_history,
             } => {
                 let mut measure = Measure::start("alpenglow vote history save");
+                // Never send a vote that is not saved, this also stops a
+                // validator whose vote history lease was taken by another
+                // machine from double voting.
                 if let Err(err) = vote_history_storage.store(&saved_vote_history) {
                     error!("Unable to save vote history to storage: {err:?}");
                     std::process::exit(1);
@@ -47,6 +47,7 @@ use {
     crate::{
         commitment::CommitmentAggregationData,
         consensus_metrics::ConsensusMetrics,
+        consensus_notifier::ConsensusEventSender,
         consensus_pool_service::{ConsensusPoolContext, ConsensusPoolService},
         event::{
             LatestSwitchRequest, LeaderWindowInfo, RepairEventSender, VotorEventReceiver,
@@ -117,6 +118,7 @@ pub struct VotorConfig {
     pub own_vote_sender: Sender<ConsensusMessage>,
     pub repair_event_sender: RepairEventSender,
     pub latest_switch_request: LatestSwitchRequest,
+    pub consensus_event_sender: Option<ConsensusEventSender>,
 
     // Receivers
     pub event_receiver: VotorEventReceiver,
@@ -166,6 +168,7 @@ impl Votor {
             own_vote_sender,
             repair_event_sender,
             latest_switch_request,
+            consensus_event_sender,
             event_receiver,
             consensus_message_receiver,
             own_message_receiver,
@@ -248,6 +251,7 @@ impl Votor {
             event_sender,
             repair_event_sender,
             highest_finalized,
+            consensus_event_sender,
         };
 
         let metrics = ConsensusMetrics::start_metrics_loop(